@@ -0,0 +1,178 @@
+//! Keeping the live cells of a world larger than the screen in view as
+//! a pattern grows and shrinks (see `--autoframe`), e.g. for demos
+//! running by themselves.
+//!
+//! Every `EVERY` generations the bounds of the live cells (see
+//! `World::live_bounds`) are looked at anew, and the viewport centering
+//! them becomes the target.  The viewport glides toward it by a
+//! `SMOOTHING`th of the distance left per generation rather than
+//! jumping there.  Lest a pattern wobbling back and forth sway the view
+//! with it, the viewport only sets off once the target is more than a
+//! `SLACK`th of the screen away, or once live cells which would fit the
+//! screen stick out of it; it then goes all the way.
+//!
+//! The view has no zoom levels: a pattern larger than the screen is
+//! kept centered; the mini-map (see `M`) shows all of it.
+
+use std::cmp;
+
+use world::World;
+
+/// The number of generations between two looks at the live cells.
+pub const EVERY: usize = 4;
+
+/// The viewport moves by this fraction of the distance left at once
+/// (by at least one cell.)
+pub const SMOOTHING: usize = 4;
+
+/// The viewport stays put as long as its target is at most this
+/// fraction of the screen's width (or height) away.
+pub const SLACK: usize = 4;
+
+/// The state of framing the live cells.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AutoFrame {
+    // ~ the viewport centering the live cells when last looked at
+    target: Option<(usize, usize)>,
+    // ~ whether the viewport is on its way to the target
+    moving: bool,
+    // ~ the number of generations since the live cells were looked at
+    age: usize,
+}
+
+impl AutoFrame {
+    pub fn new() -> AutoFrame {
+        AutoFrame::default()
+    }
+
+    /// Determines the viewport to show the given world's next generation
+    /// with on a screen of `width` x `height` cells, coming from the given
+    /// one; looks at the live cells if it is time to.
+    pub fn next(&mut self,
+                world: &World,
+                viewport: (usize, usize),
+                (width, height): (usize, usize))
+                -> (usize, usize) {
+        if self.age == 0 {
+            let bounds = world.live_bounds();
+            self.target = bounds.map(|b| centered(world, b, width, height));
+            if let (Some(b), Some(t)) = (bounds, self.target) {
+                self.moving |= far(viewport.0, t.0, width) || far(viewport.1, t.1, height) ||
+                               sticks_out(viewport.0, b.0, b.2, width) ||
+                               sticks_out(viewport.1, b.1, b.3, height);
+            }
+        }
+        self.age = (self.age + 1) % EVERY;
+        let target = match self.target {
+            Some(t) if self.moving => t,
+            _ => return viewport,
+        };
+        let next = (glide(viewport.0, target.0), glide(viewport.1, target.1));
+        if next == target {
+            self.moving = false;
+        }
+        next
+    }
+}
+
+// ~ the viewport centering the given bounds on the screen, as far as the
+// world reaches
+fn centered(world: &World,
+            (x, y, w, h): (usize, usize, usize, usize),
+            width: usize,
+            height: usize)
+            -> (usize, usize) {
+    let max = (world.width().saturating_sub(width), world.height().saturating_sub(height));
+    (cmp::min((x + w / 2).saturating_sub(width / 2), max.0),
+     cmp::min((y + h / 2).saturating_sub(height / 2), max.1))
+}
+
+fn far(v: usize, target: usize, len: usize) -> bool {
+    distance(v, target) > len / SLACK
+}
+
+// ~ whether the live cells from `start` on `n` cells long fit a screen
+// `len` cells long but not the one starting at `v`
+fn sticks_out(v: usize, start: usize, n: usize, len: usize) -> bool {
+    n <= len && (start < v || start + n > v + len)
+}
+
+// ~ a step from `v` toward `target`
+fn glide(v: usize, target: usize) -> usize {
+    let step = (distance(v, target) + SMOOTHING - 1) / SMOOTHING;
+    if v < target {
+        v + step
+    } else {
+        v - step
+    }
+}
+
+fn distance(a: usize, b: usize) -> usize {
+    if a < b {
+        b - a
+    } else {
+        a - b
+    }
+}
+
+#[cfg(test)]
+fn blocks_at(at: &[(usize, usize)]) -> World {
+    let mut world = World::empty(200, 100);
+    for &(x, y) in at {
+        for &(dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+            world.set_alive(x + dx, y + dy, true);
+        }
+    }
+    world
+}
+
+#[test]
+fn test_glide() {
+    // ~ a block far off the screen of 40x20: the viewport glides there
+    // by a quarter of the distance left per generation
+    let world = blocks_at(&[(150, 80)]);
+    let mut f = AutoFrame::new();
+    let mut v = (0, 0);
+    let mut path = Vec::new();
+    for _ in 0..40 {
+        v = f.next(&world, v, (40, 20));
+        path.push(v);
+    }
+    assert_eq!(&[(33, 18), (58, 32), (77, 42), (91, 50)], &path[..4]);
+    assert_eq!((131, 71), v);
+    // ~ the world's edges hold the viewport back
+    let world = blocks_at(&[(195, 0)]);
+    let mut f = AutoFrame::new();
+    for _ in 0..40 {
+        v = f.next(&world, v, (40, 20));
+    }
+    assert_eq!((160, 0), v);
+    // ~ an empty world leaves the viewport be
+    assert_eq!((7, 3), AutoFrame::new().next(&World::empty(200, 100), (7, 3), (40, 20)));
+}
+
+#[test]
+fn test_hysteresis() {
+    // ~ the block centered, then moved by a few cells back and forth:
+    // the viewport stays put
+    let mut f = AutoFrame::new();
+    let v = (81, 41);
+    assert_eq!(v, f.next(&blocks_at(&[(100, 50)]), v, (40, 20)));
+    for i in 1..40 {
+        let at = if i % 8 < 4 { (105, 53) } else { (96, 48) };
+        assert_eq!(v, f.next(&blocks_at(&[at]), v, (40, 20)), "{}", i);
+    }
+    // ~ moved further: the viewport follows all the way
+    let mut f = AutoFrame::new();
+    assert_eq!((85, 41), f.next(&blocks_at(&[(115, 50)]), v, (40, 20)));
+    // ~ sticking out of the screen by a little: likewise
+    let world = blocks_at(&[(100, 44), (100, 61)]);
+    let mut f = AutoFrame::new();
+    let mut path = vec![v];
+    for _ in 0..8 {
+        let w = f.next(&world, *path.last().unwrap(), (40, 20));
+        path.push(w);
+    }
+    assert_eq!(vec![(81, 41), (81, 42), (81, 43), (81, 43)], &path[..4]);
+    assert!(path[4..].iter().all(|&w| w == (81, 43)));
+}
@@ -0,0 +1,131 @@
+//! The keys of the user interface along with what they do.
+//!
+//! The table tells in particular which keys change the world (or its
+//! saved state, or write files), such that the read-only mode (see
+//! `--read-only`) can refuse them.  Keys not listed are taken as
+//! harmless; any key added which changes something is to be listed as
+//! `mutating`.  The keys of the rule editor, the stamp preview
+//! (including nudging the pattern just stamped), and the prompts are
+//! not listed: the keys opening them are.
+
+use rustbox::keyboard::Key;
+
+/// When a binding applies besides its key being pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    /// whenever no other binding of the key applies
+    Always,
+    /// while the list of bookmarks is shown
+    BookmarksShown,
+    /// if a stats log was given (see `--statlog`)
+    Statlog,
+}
+
+pub struct Binding {
+    pub key: Key,
+    pub context: Context,
+    /// what the key does, e.g. to name it when refused
+    pub action: &'static str,
+    /// whether it changes the world or its saved state, or writes a
+    /// file
+    pub mutating: bool,
+}
+
+macro_rules! bindings {
+    ($(($key:expr, $context:ident, $action:expr, $mutating:expr)),*) => {
+        &[$(Binding {
+            key: $key,
+            context: Context::$context,
+            action: $action,
+            mutating: $mutating,
+        }),*]
+    }
+}
+
+/// The key bindings; the ones of a specific context precede the one of
+/// the same key applying always.
+pub const BINDINGS: &'static [Binding] = bindings![
+    (Key::Char(' '), Always, "pause/resume", false),
+    (Key::Char('s'), Always, "step", false),
+    (Key::Char('R'), Always, "play backwards", false),
+    (Key::Char('g'), Always, "go to a generation", false),
+    (Key::Char('+'), Always, "speed up", false),
+    (Key::Char('-'), Always, "slow down", false),
+    (Key::Char('0'), Always, "unthrottled animation", false),
+    (Key::Char('a'), Always, "adaptive slow motion", false),
+    (Key::Char('q'), Always, "quit", false),
+    (Key::Ctrl('l'), Always, "redraw", false),
+    (Key::Up, Always, "pan", false),
+    (Key::Down, Always, "pan", false),
+    (Key::Left, Always, "pan", false),
+    (Key::Right, Always, "pan", false),
+    (Key::Char('v'), Always, "mark the view", false),
+    (Key::Char('V'), Always, "return to a view", false),
+    (Key::Char('n'), Always, "status line", false),
+    (Key::Char('H'), Always, "generation display", false),
+    (Key::Char('G'), Always, "population chart", false),
+    (Key::Char('M'), Always, "mini-map", false),
+    (Key::Char('F'), Always, "auto-framing", false),
+    (Key::Char('T'), Always, "follow an object", false),
+    (Key::Char('#'), Always, "neighbour count digits", false),
+    (Key::Char('c'), Always, "neighbour count overlay", false),
+    (Key::Char('o'), Always, "reference overlay", false),
+    (Key::Char('d'), Always, "density colors", false),
+    (Key::Char('P'), Always, "performance counters", false),
+    (Key::Char('p'), Always, "measure the period", false),
+    (Key::Char('B'), Always, "list of bookmarks", false),
+    (Key::Char('@'), Always, "replay a macro", false),
+    (Key::Char('L'), Statlog, "log the statistics", false),
+    (Key::Char('1'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('2'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('3'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('4'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('5'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('6'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('7'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('8'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('9'), BookmarksShown, "jump to a bookmark", true),
+    (Key::Char('r'), Always, "regenerate", true),
+    (Key::Char('w'), Always, "switch the edges", true),
+    (Key::Char('e'), Always, "rule editor", true),
+    (Key::Char('S'), Always, "stamp", true),
+    (Key::Char('X'), Always, "sandbox a region", true),
+    (Key::Char('m'), Always, "save to a slot", true),
+    (Key::Char('\''), Always, "load a slot", true),
+    (Key::Char('['), Always, "load the previous file", true),
+    (Key::Char(']'), Always, "load the next file", true),
+    (Key::Char('L'), Always, "load a file", true),
+    (Key::Char('W'), Always, "save to a file", true),
+    (Key::Char('y'), Always, "copy", true),
+    (Key::F(12), Always, "screenshot", true),
+    (Key::Char('b'), Always, "bookmark", true),
+    (Key::Char('Q'), Always, "record a macro", true)
+];
+
+/// Finds the binding of the given key in the first context `applies`
+/// accepts (`Context::Always` is accepted anyway); `None` for keys
+/// without a binding.
+pub fn lookup<F>(key: Key, applies: F) -> Option<&'static Binding>
+    where F: Fn(Context) -> bool
+{
+    BINDINGS.iter().find(|b| b.key == key && (b.context == Context::Always || applies(b.context)))
+}
+
+#[test]
+fn test_lookup() {
+    let none = |_| false;
+    assert_eq!(Some("regenerate"), lookup(Key::Char('r'), none).map(|b| b.action));
+    assert_eq!(None, lookup(Key::Char('1'), none).map(|b| b.action));
+    assert_eq!(None, lookup(Key::Char('x'), none).map(|b| b.action));
+    let shown = |c| c == Context::BookmarksShown;
+    assert!(lookup(Key::Char('1'), shown).unwrap().mutating);
+    assert!(lookup(Key::Char('L'), none).unwrap().mutating);
+    assert!(!lookup(Key::Char('L'), |c| c == Context::Statlog).unwrap().mutating);
+    // ~ a key bound in a context comes before its binding applying
+    // always
+    for (i, b) in BINDINGS.iter().enumerate() {
+        if b.context == Context::Always {
+            assert!(BINDINGS[i + 1..].iter().all(|later| later.key != b.key), "{:?}", b.key);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Explicitly marked generations of a run, kept as snapshots to jump
+//! back to.
+//!
+//! Bookmarks can be persisted in a binary file: the magic `CGOLBKM1`,
+//! the fingerprint of the run (see `fingerprint`, `u64`), the number
+//! of bookmarks (`u32`), and for each bookmark the length (`u32`) of
+//! its world in the binary format (see `World::save_binary`) followed
+//! by the world.  All numbers are little endian.
+
+use std::collections::vec_deque::{self, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+
+use io_util::{self, invalid_data, read_u32, read_u64, write_u32, write_u64};
+use world::{self, BoundaryMode, UpdateMode, World};
+
+/// The default number of bookmarks kept; each can be selected by a
+/// single digit.
+pub const DEFAULT_CAP: usize = 9;
+
+const MAGIC: &'static [u8; 8] = b"CGOLBKM1";
+
+/// A marked generation.
+pub struct Bookmark {
+    generation: usize,
+    alive: usize,
+    // ~ the world in the binary format; a fraction of its in-memory
+    // size
+    data: Vec<u8>,
+}
+
+impl Bookmark {
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The number of live cells of the marked generation.
+    pub fn alive(&self) -> usize {
+        self.alive
+    }
+
+    /// Recreates the marked world; its boundary mode and neighbourhood
+    /// are not part of the snapshot.
+    pub fn world(&self) -> io::Result<World> {
+        World::load_binary(&self.data[..])
+    }
+}
+
+pub struct Bookmarks {
+    // ~ the oldest bookmark first
+    marks: VecDeque<Bookmark>,
+    cap: usize,
+    // ~ identifies the run the bookmarks belong to
+    fingerprint: u64,
+    // ~ whether a bookmark was added since these were created or read
+    modified: bool,
+}
+
+impl Bookmarks {
+    /// Creates an empty list of at most `cap` bookmarks of the run
+    /// with the given fingerprint.
+    pub fn new(fingerprint: u64, cap: usize) -> Bookmarks {
+        assert!(cap > 0);
+        Bookmarks {
+            marks: VecDeque::with_capacity(cap),
+            cap: cap,
+            fingerprint: fingerprint,
+            modified: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.marks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Identifies the run these bookmarks belong to (see
+    /// `fingerprint`.)
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// The `i`-th bookmark; the oldest one first.
+    pub fn get(&self, i: usize) -> Option<&Bookmark> {
+        self.marks.get(i)
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, Bookmark> {
+        self.marks.iter()
+    }
+
+    /// Marks the given world's generation.  Once the cap is reached the
+    /// oldest bookmark is dropped and returned.
+    pub fn add(&mut self, world: &World) -> Option<Bookmark> {
+        let mut data = Vec::new();
+        world.save_binary(&mut data).unwrap();
+        let evicted = if self.marks.len() == self.cap {
+            self.marks.pop_front()
+        } else {
+            None
+        };
+        self.marks.push_back(Bookmark {
+            generation: world.generation(),
+            alive: world.alive(),
+            data: data,
+        });
+        self.modified = true;
+        evicted
+    }
+
+    /// Reads the bookmarks from the given file; a missing file results
+    /// in no bookmarks.  Bookmarks of another run (i.e. of a different
+    /// fingerprint) are refused.
+    pub fn load_from_file(filename: &str, fingerprint: u64, cap: usize) -> Result<Bookmarks, String> {
+        match File::open(filename) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Bookmarks::new(fingerprint, cap)),
+            Err(e) => Err(format!("{}: {}", filename, e)),
+            Ok(f) => {
+                Bookmarks::read_from(BufReader::new(f), fingerprint, cap)
+                    .map_err(|e| format!("{}: {}", filename, e))
+            }
+        }
+    }
+
+    /// Writes the bookmarks to the given file.  They are written to a
+    /// temporary file first which is then renamed over the given one
+    /// (see `io_util::atomic_write`.)
+    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
+        io_util::atomic_write(filename, |mut out| self.write_to(&mut out))
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Reads bookmarks of the run with the given fingerprint; keeps
+    /// the `cap` most recent ones.
+    pub fn read_from<R: Read>(mut input: R, fingerprint: u64, cap: usize) -> io::Result<Bookmarks> {
+        let mut magic = [0u8; 8];
+        try!(input.read_exact(&mut magic));
+        if &magic != MAGIC {
+            return Err(invalid_data("not a bookmarks file"));
+        }
+        if try!(read_u64(&mut input)) != fingerprint {
+            return Err(invalid_data("bookmarks of a different world or rule"));
+        }
+        let mut bookmarks = Bookmarks::new(fingerprint, cap);
+        for _ in 0..try!(read_u32(&mut input)) {
+            let len = try!(read_u32(&mut input)) as usize;
+            if len > 24 + world::MAX_CELLS / 8 {
+                return Err(invalid_data("invalid bookmark"));
+            }
+            let mut data = vec![0; len];
+            try!(input.read_exact(&mut data));
+            let w = try!(World::load_binary(&data[..]));
+            if bookmarks.marks.len() == cap {
+                bookmarks.marks.pop_front();
+            }
+            bookmarks.marks.push_back(Bookmark {
+                generation: w.generation(),
+                alive: w.alive(),
+                data: data,
+            });
+        }
+        Ok(bookmarks)
+    }
+
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        try!(out.write_all(MAGIC));
+        try!(write_u64(out, self.fingerprint));
+        try!(write_u32(out, self.marks.len() as u32));
+        for m in &self.marks {
+            try!(write_u32(out, m.data.len() as u32));
+            try!(out.write_all(&m.data));
+        }
+        Ok(())
+    }
+}
+
+/// Identifies a run by its initial world and rule, i.e. boundary mode,
+/// neighbourhood, rule of birth and survival, and update mode.
+pub fn fingerprint(initial: &World) -> u64 {
+    const FNV_PRIME: u64 = 0x100000001b3;
+    let mix = |hash: u64, v: i64| (hash ^ v as u64).wrapping_mul(FNV_PRIME);
+
+    let mut hash = initial.generation_hash();
+    hash = match initial.boundary() {
+        BoundaryMode::Wrap => mix(hash, 1),
+        BoundaryMode::Bounded => mix(hash, 2),
+        BoundaryMode::Twisted { dx, dy } => mix(mix(mix(hash, 3), dx as i64), dy as i64),
+    };
+    for &(dx, dy) in initial.neighbourhood() {
+        hash = mix(mix(hash, dx as i64), dy as i64);
+    }
+    // ~ as does the classic rule
+    if initial.rule() != world::CONWAY {
+        let rule = initial.rule();
+        hash = mix(mix(hash, rule.birth as i64), rule.survival as i64);
+    }
+    // ~ the classic synchronous update contributes nothing
+    match initial.update_mode() {
+        UpdateMode::Synchronous => hash,
+        UpdateMode::AsyncRandomOrder { seed } => mix(mix(hash, 1), seed as i64),
+        UpdateMode::AsyncRowMajor => mix(hash, 2),
+        UpdateMode::AsyncRandomSubset { seed, percent } => {
+            mix(mix(mix(hash, 3), seed as i64), percent as i64)
+        }
+    }
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut w = World::random_seeded(1, 61, 23);
+    for _ in 0..12 {
+        w.advance_generation(|_, _, _| {});
+    }
+    let mut bookmarks = Bookmarks::new(fingerprint(&w), DEFAULT_CAP);
+    assert!(bookmarks.add(&w).is_none());
+    let b = bookmarks.get(0).unwrap();
+    assert_eq!((12, w.alive()), (b.generation(), b.alive()));
+    let v = b.world().unwrap();
+    assert_eq!(w.generation_hash(), v.generation_hash());
+    assert_eq!(12, v.generation());
+    // ~ one bit per cell
+    assert!(b.data.len() < 61 * 23 / 4);
+}
+
+#[test]
+fn test_cap_eviction() {
+    let mut w = World::random_seeded(2, 20, 20);
+    let mut bookmarks = Bookmarks::new(fingerprint(&w), 3);
+    for _ in 0..3 {
+        assert!(bookmarks.add(&w).is_none());
+        w.advance_generation(|_, _, _| {});
+    }
+    // ~ the oldest one is dropped
+    assert_eq!(0, bookmarks.add(&w).unwrap().generation());
+    let gens: Vec<_> = bookmarks.iter().map(|b| b.generation()).collect();
+    assert_eq!(vec![1, 2, 3], gens);
+
+    // ~ reading them with a smaller cap keeps the most recent ones
+    let mut buf = Vec::new();
+    bookmarks.write_to(&mut buf).unwrap();
+    let read = Bookmarks::read_from(&buf[..], bookmarks.fingerprint, 2).unwrap();
+    assert!(!read.is_modified());
+    assert_eq!(vec![2, 3], read.iter().map(|b| b.generation()).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_fingerprint_mismatch() {
+    let w = World::random_seeded(3, 20, 20);
+    let mut bookmarks = Bookmarks::new(fingerprint(&w), DEFAULT_CAP);
+    bookmarks.add(&w);
+    let mut buf = Vec::new();
+    bookmarks.write_to(&mut buf).unwrap();
+    assert_eq!(1, Bookmarks::read_from(&buf[..], fingerprint(&w), DEFAULT_CAP).unwrap().len());
+
+    // ~ another seed, and the same world under another rule
+    let other = World::random_seeded(4, 20, 20);
+    let mut twisted = w.clone();
+    twisted.set_boundary(BoundaryMode::Twisted { dx: 1, dy: 0 });
+    let mut row_major = w.clone();
+    row_major.set_update_mode(UpdateMode::AsyncRowMajor);
+    let mut highlife = w.clone();
+    highlife.set_rule(world::Rule::parse("B36/S23").unwrap());
+    for v in &[other, twisted, row_major, highlife] {
+        assert!(fingerprint(v) != fingerprint(&w));
+        let e = Bookmarks::read_from(&buf[..], fingerprint(v), DEFAULT_CAP).err().unwrap();
+        assert_eq!("bookmarks of a different world or rule", e.to_string());
+    }
+    assert!(Bookmarks::read_from(&buf[..buf.len() - 1], fingerprint(&w), DEFAULT_CAP).is_err());
+}
@@ -0,0 +1,93 @@
+//! Copying text to the system's clipboard.
+//!
+//! With the `clipboard` feature the text is piped to the first of the
+//! known clipboard tools found: `wl-copy` (Wayland), `xclip` or `xsel`
+//! (X11).  Without the feature, or where none of them works, the text
+//! is written to a file in the temporary directory instead, for the
+//! user to pick up from there.
+
+use std::env;
+use std::path::PathBuf;
+
+use io_util;
+
+/// Where copied text ended up.
+#[derive(Debug, PartialEq)]
+pub enum Copied {
+    /// Handed to the clipboard tool of the given name.
+    Clipboard(&'static str),
+    /// Written to the given file.
+    File(PathBuf),
+}
+
+/// Copies the given text to the clipboard, falling back to writing it
+/// to the file of the given name in the temporary directory.
+pub fn copy(text: &str, fallback_name: &str) -> Result<Copied, String> {
+    match to_clipboard(text) {
+        Some(tool) => Ok(Copied::Clipboard(tool)),
+        None => write_temp(text, fallback_name).map(Copied::File),
+    }
+}
+
+/// Writes the given text to the file of the given name in the
+/// temporary directory; returns the file's path.
+pub fn write_temp(text: &str, name: &str) -> Result<PathBuf, String> {
+    let path = env::temp_dir().join(name);
+    io_util::atomic_write(&path, |out| out.write_all(text.as_bytes()))
+        .map(|_| path.clone())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The clipboard tools tried in turn along with their arguments.
+#[cfg(feature = "clipboard")]
+const TOOLS: &'static [(&'static str, &'static [&'static str])] = &[("wl-copy", &[]),
+                                                                    ("xclip", &["-selection", "clipboard"]),
+                                                                    ("xsel", &["--clipboard", "--input"])];
+
+/// Pipes the given text to the first clipboard tool accepting it;
+/// returns its name.  Tools missing or failing (e.g. `wl-copy` outside
+/// of a Wayland session) are skipped.
+#[cfg(feature = "clipboard")]
+fn to_clipboard(text: &str) -> Option<&'static str> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    for &(tool, args) in TOOLS {
+        let child = Command::new(tool)
+                        .args(args)
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+        // ~ the pipe is closed by dropping it before waiting, telling
+        // the tool the text is complete
+        let written = child.stdin.take().map_or(false, |mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        match child.wait() {
+            Ok(status) if status.success() && written => return Some(tool),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn to_clipboard(_: &str) -> Option<&'static str> {
+    None
+}
+
+#[test]
+fn test_write_temp() {
+    use std::fs::{self, File};
+    use std::io::Read;
+
+    let path = write_temp("x = 1, y = 1\no!\n", "cgol-test-clipboard.rle").unwrap();
+    assert_eq!(env::temp_dir().join("cgol-test-clipboard.rle"), path);
+    let mut s = String::new();
+    File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+    assert_eq!("x = 1, y = 1\no!\n", s);
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,123 @@
+//! Color gradients and the detection of the terminal's color
+//! capabilities.
+
+use rustbox::Color;
+
+/// A 24-bit color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// A perceptually uniform palette from dark purple over teal to
+/// yellow (approximating matplotlib's viridis.)
+pub const VIRIDIS: &'static [(f64, Rgb)] = &[(0.0, Rgb(68, 1, 84)),
+                                             (0.25, Rgb(59, 82, 139)),
+                                             (0.5, Rgb(33, 145, 140)),
+                                             (0.75, Rgb(94, 201, 98)),
+                                             (1.0, Rgb(253, 231, 37))];
+
+/// A palette from black over red and orange to light yellow.
+pub const FIRE: &'static [(f64, Rgb)] = &[(0.0, Rgb(0, 0, 0)),
+                                          (0.35, Rgb(180, 20, 0)),
+                                          (0.7, Rgb(255, 150, 0)),
+                                          (1.0, Rgb(255, 255, 200))];
+
+/// The colors a terminal is able to display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorTier {
+    /// the eight basic colors
+    Basic,
+    /// arbitrary 24-bit colors
+    Truecolor,
+}
+
+impl ColorTier {
+    /// Determines the color tier from the value of the `COLORTERM`
+    /// environment variable; `force` selects truecolor regardless of
+    /// it.  Without the `truecolor` feature the result is always
+    /// `Basic`.
+    #[cfg(feature = "truecolor")]
+    pub fn detect(colorterm: Option<&str>, force: bool) -> ColorTier {
+        match colorterm {
+            _ if force => ColorTier::Truecolor,
+            Some("truecolor") | Some("24bit") => ColorTier::Truecolor,
+            _ => ColorTier::Basic,
+        }
+    }
+
+    #[cfg(not(feature = "truecolor"))]
+    pub fn detect(_: Option<&str>, _: bool) -> ColorTier {
+        ColorTier::Basic
+    }
+}
+
+/// Evaluates the gradient described by `stops` at `t`.  The stops are
+/// pairs of a position in `0.0..1.0` and the color at that position,
+/// sorted by position; colors between two stops are interpolated
+/// linearly.  `t` is clamped into the range covered by the stops.
+pub fn gradient(t: f64, stops: &[(f64, Rgb)]) -> Rgb {
+    assert!(!stops.is_empty());
+    let t = if t.is_nan() { 0.0 } else { t };
+    let (first, last) = (stops[0], stops[stops.len() - 1]);
+    if t <= first.0 {
+        return first.1;
+    }
+    if t >= last.0 {
+        return last.1;
+    }
+    let i = stops.iter().position(|&(pos, _)| pos > t).unwrap();
+    let ((p0, c0), (p1, c1)) = (stops[i - 1], stops[i]);
+    let f = (t - p0) / (p1 - p0);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+    Rgb(mix(c0.0, c1.0), mix(c0.1, c1.1), mix(c0.2, c1.2))
+}
+
+/// Approximates the given color with the closest of the basic colors.
+pub fn nearest_basic(c: Rgb) -> Color {
+    const BASIC: [(Rgb, Color); 8] = [(Rgb(0, 0, 0), Color::Black),
+                                      (Rgb(205, 0, 0), Color::Red),
+                                      (Rgb(0, 205, 0), Color::Green),
+                                      (Rgb(205, 205, 0), Color::Yellow),
+                                      (Rgb(0, 0, 238), Color::Blue),
+                                      (Rgb(205, 0, 205), Color::Magenta),
+                                      (Rgb(0, 205, 205), Color::Cyan),
+                                      (Rgb(229, 229, 229), Color::White)];
+    let dist = |a: Rgb| {
+        let d = |x: u8, y: u8| (x as i32 - y as i32) * (x as i32 - y as i32);
+        d(a.0, c.0) + d(a.1, c.1) + d(a.2, c.2)
+    };
+    BASIC.iter().min_by_key(|&&(rgb, _)| dist(rgb)).unwrap().1
+}
+
+#[test]
+fn test_gradient_clamps() {
+    assert_eq!(Rgb(0, 0, 0), gradient(-1.0, FIRE));
+    assert_eq!(Rgb(255, 255, 200), gradient(7.0, FIRE));
+    assert_eq!(VIRIDIS[0].1, gradient(0.0, VIRIDIS));
+    assert_eq!(VIRIDIS[4].1, gradient(1.0, VIRIDIS));
+    assert_eq!(Rgb(1, 2, 3), gradient(0.5, &[(0.0, Rgb(1, 2, 3))]));
+}
+
+#[test]
+fn test_gradient_interpolates() {
+    let stops = [(0.0, Rgb(0, 100, 200)), (0.5, Rgb(100, 100, 0)), (1.0, Rgb(200, 0, 0))];
+    assert_eq!(Rgb(50, 100, 100), gradient(0.25, &stops));
+    assert_eq!(Rgb(100, 100, 0), gradient(0.5, &stops));
+    assert_eq!(Rgb(150, 50, 0), gradient(0.75, &stops));
+}
+
+#[test]
+fn test_gradient_monotonic() {
+    // ~ all channels of the fire palette grow with `t`
+    let mut prev = gradient(0.0, FIRE);
+    for i in 1..101 {
+        let c = gradient(i as f64 / 100.0, FIRE);
+        assert!(c.0 >= prev.0 && c.1 >= prev.1 && c.2 >= prev.2);
+        prev = c;
+    }
+}
+
+#[test]
+fn test_nearest_basic() {
+    assert!(Color::Red == nearest_basic(Rgb(250, 10, 10)));
+    assert!(Color::Black == nearest_basic(Rgb(10, 10, 10)));
+}
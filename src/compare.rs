@@ -0,0 +1,209 @@
+//! Running a world under a second rule alongside it (see `--compare`),
+//! to watch where the two rules part ways.
+//!
+//! A `Ghost` is a copy of the world living by the other rule, advanced
+//! in lockstep with the world.  It follows the world: as soon as the
+//! world was changed other than by advancing it alongside the ghost (a
+//! new world generated, a file loaded, a rewind, an edit), the ghost
+//! starts over from the world's current cells.  It takes the world's
+//! edges along, though: a torus turned bounded (or back) is the same
+//! world to compare, just with other edges.  The user interface
+//! draws the cells alive in both worlds as usual, and those alive in
+//! one of them only in the color of its rule (see `Presence`.)
+
+use simulation::{self, Change};
+use world::{Rule, World};
+
+/// Where a cell is alive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Presence {
+    Neither,
+    Both,
+    /// in the world only
+    First,
+    /// in the ghost only
+    Second,
+}
+
+/// A world's copy living by another rule; see the module's
+/// description.
+pub struct Ghost {
+    world: World,
+    // ~ the checksum and the generation of the world as of the last
+    // generation advanced alongside (or started over from)
+    seen: (u64, usize),
+    // ~ the cells changed by the last generation
+    changes: Vec<Change>,
+    // ~ the number of cells alive in one of the worlds only, and for
+    // each cell whether it is; the count is kept up to date from the
+    // cells changed rather than counted anew with every generation
+    differing: usize,
+    differs: Vec<bool>,
+}
+
+impl Ghost {
+    /// Creates a ghost living by the given rule; it takes after the
+    /// first world it is to `follow`.
+    pub fn new(rule: Rule) -> Ghost {
+        let mut world = World::empty(0, 0);
+        world.set_rule(rule);
+        Ghost {
+            world: world,
+            seen: (0, 0),
+            changes: Vec::new(),
+            differing: 0,
+            differs: Vec::new(),
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.world.rule()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Starts over from the given world unless it is still the one the
+    /// ghost was last advanced alongside; tells whether it did.  Either
+    /// way, the ghost takes on the world's boundary mode.
+    pub fn follow(&mut self, world: &World) -> bool {
+        self.world.set_boundary(world.boundary());
+        if (world.width(), world.height()) == (self.world.width(), self.world.height()) &&
+           (world.checksum(), world.generation()) == self.seen {
+            return false;
+        }
+        self.start_over(world);
+        true
+    }
+
+    /// Advances the ghost by a generation along with the given world,
+    /// which was just advanced from the one the ghost follows changing
+    /// the given cells (see `simulation::step`.)
+    pub fn step(&mut self, world: &World, world_changes: &[Change]) {
+        simulation::step(&mut self.world, None, &mut self.changes);
+        self.seen = (world.checksum(), world.generation());
+        // ~ only the cells changed in either world can have come to
+        // differ or ceased to
+        let width = self.world.width();
+        for &(x, y, _) in world_changes.iter().chain(self.changes.iter()) {
+            let differs = world.is_alive(x, y) != self.world.is_alive(x, y);
+            if differs != self.differs[y * width + x] {
+                self.differs[y * width + x] = differs;
+                if differs {
+                    self.differing += 1;
+                } else {
+                    self.differing -= 1;
+                }
+            }
+        }
+    }
+
+    /// The cells the last generation changed in the ghost (see
+    /// `simulation::step`.)
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// The number of cells alive in one of the worlds only.
+    pub fn differing(&self) -> usize {
+        self.differing
+    }
+
+    /// Tells where the given cell is alive; cells beyond the edges of
+    /// the ghost (e.g. of a world changed since) count as dead in it.
+    pub fn presence(&self, x: usize, y: usize, alive: bool) -> Presence {
+        match (alive, self.world.get(x, y).unwrap_or(false)) {
+            (false, false) => Presence::Neither,
+            (true, true) => Presence::Both,
+            (true, false) => Presence::First,
+            (false, true) => Presence::Second,
+        }
+    }
+
+    fn start_over(&mut self, world: &World) {
+        let rule = self.world.rule();
+        self.world = world.clone();
+        self.world.set_rule(rule);
+        self.changes.clear();
+        self.seen = (world.checksum(), world.generation());
+        self.differing = 0;
+        self.differs = vec![false; world.width() * world.height()];
+    }
+}
+
+/// Parses the two rules to compare separated by a comma, e.g.
+/// `B3/S23,B36/S23`.
+pub fn parse_rules(s: &str) -> Option<(Rule, Rule)> {
+    let mut parts = s.split(',');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(a), Some(b), None) => {
+            match (Rule::parse(a.trim()), Rule::parse(b.trim())) {
+                (Some(a), Some(b)) => Some((a, b)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn test_parse_rules() {
+    let (a, b) = parse_rules("B3/S23, b36/s23").unwrap();
+    assert_eq!(("B3/S23".to_owned(), "B36/S23".to_owned()), (a.to_string(), b.to_string()));
+    assert!(parse_rules("B3/S23").is_none());
+    assert!(parse_rules("B3/S23,B36/S23,B2/S").is_none());
+    assert!(parse_rules("B3/S23,life").is_none());
+}
+
+#[test]
+fn test_lockstep() {
+    let (conway, highlife) = parse_rules("B3/S23,B36/S23").unwrap();
+    let mut world = World::random_seeded(11, 40, 20);
+    world.set_rule(conway);
+    let mut ghost = Ghost::new(highlife);
+    assert!(ghost.follow(&world));
+    assert_eq!((highlife, 0), (ghost.rule(), ghost.differing()));
+    let mut diverged = None;
+    let mut changes = Vec::new();
+    for _ in 0..20 {
+        assert!(!ghost.follow(&world));
+        simulation::step(&mut world, None, &mut changes);
+        ghost.step(&world, &changes);
+        assert_eq!(world.generation(), ghost.world().generation());
+        assert_eq!(world.diff(ghost.world()).len(), ghost.differing());
+        if diverged.is_none() && ghost.differing() > 0 {
+            diverged = Some(world.generation());
+        }
+    }
+    // ~ a cell born of six neighbours makes the difference
+    let at = diverged.expect("the rules did not diverge");
+    assert!(at > 0 && at < 20, "{}", at);
+    let live: Vec<_> = (0..world.height())
+                           .flat_map(|y| (0..world.width()).map(move |x| (x, y)))
+                           .filter(|&(x, y)| ghost.presence(x, y, world.is_alive(x, y)) == Presence::First)
+                           .collect();
+    assert!(live.iter().all(|&(x, y)| world.is_alive(x, y) && !ghost.world().is_alive(x, y)));
+
+    // ~ the same rule stays in step
+    let mut twin = Ghost::new(conway);
+    let mut copy = world.clone();
+    twin.follow(&copy);
+    for _ in 0..10 {
+        simulation::step(&mut copy, None, &mut changes);
+        twin.step(&copy, &changes);
+    }
+    assert_eq!((0, copy.live_cells()), (twin.differing(), twin.world().live_cells()));
+
+    // ~ other edges: taken along without starting over
+    world.set_boundary(::world::BoundaryMode::Bounded);
+    assert!(!ghost.follow(&world));
+    assert_eq!(::world::BoundaryMode::Bounded, ghost.world().boundary());
+
+    // ~ a world changed otherwise: started over from it
+    world.set_alive(0, 0, !world.is_alive(0, 0));
+    assert!(ghost.follow(&world));
+    assert_eq!((0, world.live_cells()), (ghost.differing(), ghost.world().live_cells()));
+    assert!(ghost.changes().is_empty());
+    assert!(!ghost.follow(&world));
+}
@@ -0,0 +1,215 @@
+//! Counting the objects (e.g. the gliders emitted by a gun) passing
+//! through a region of a world.
+
+use std::fmt;
+
+use world::World;
+
+/// The number of generations a region must have been empty before an
+/// object entering it is counted as a new one.
+pub const DEFAULT_DEBOUNCE: usize = 8;
+
+/// A rectangular region of a world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    /// Parses a region given as "X,Y,W,H"; the width and height must
+    /// be positive, and the region's far edges addressable.
+    pub fn parse(s: &str) -> Option<Rect> {
+        let ns: Option<Vec<usize>> = s.split(',').map(|n| n.trim().parse().ok()).collect();
+        match ns {
+            Some(ref ns) if ns.len() == 4 && ns[2] > 0 && ns[3] > 0 &&
+                            ns[0].checked_add(ns[2]).is_some() && ns[1].checked_add(ns[3]).is_some() => {
+                Some(Rect {
+                    x: ns[0],
+                    y: ns[1],
+                    width: ns[2],
+                    height: ns[3],
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Counts the objects crossing a region by sampling the number of live
+/// cells within it after every generation.  An object is counted when
+/// it enters the empty region; since the cells of a passing object
+/// may flicker in and out of the region, the region must have been
+/// empty for `debounce` generations before the next object is
+/// counted.
+pub struct StreamCounter {
+    region: Rect,
+    debounce: usize,
+    count: usize,
+    // ~ the generation the counter started observing at
+    first_generation: Option<usize>,
+    last_generation: usize,
+    // ~ the last generation the region was found occupied at
+    last_occupied: Option<usize>,
+}
+
+impl StreamCounter {
+    pub fn new(region: Rect, debounce: usize) -> StreamCounter {
+        StreamCounter {
+            region: region,
+            debounce: debounce,
+            count: 0,
+            first_generation: None,
+            last_generation: 0,
+            last_occupied: None,
+        }
+    }
+
+    pub fn region(&self) -> Rect {
+        self.region
+    }
+
+    /// Moves the region along with the cells of a world grown by the
+    /// given number of cells to the left and top.
+    pub fn translate(&mut self, dx: usize, dy: usize) {
+        self.region.x += dx;
+        self.region.y += dy;
+    }
+
+    /// The number of objects counted so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The number of objects counted per generation observed.
+    pub fn rate(&self) -> f64 {
+        match self.first_generation {
+            Some(first) if self.last_generation > first => {
+                self.count as f64 / (self.last_generation - first) as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Samples the region of the given world; to be called once per
+    /// generation.  Edited cells (see `World::is_dirty`) break the
+    /// continuity of the objects' passage; the region is then taken
+    /// as if observed for the first time.
+    pub fn observe(&mut self, world: &World) {
+        let gen = world.generation();
+        if world.is_dirty() {
+            self.last_occupied = None;
+        }
+        if self.first_generation.is_none() {
+            self.first_generation = Some(gen);
+        }
+        self.last_generation = gen;
+
+        let r = &self.region;
+        if world.count_in_rect(r.x, r.y, r.width, r.height) == 0 {
+            return;
+        }
+        let new_object = match self.last_occupied {
+            None => true,
+            Some(last) => gen > last + self.debounce,
+        };
+        if new_object {
+            self.count += 1;
+        }
+        self.last_occupied = Some(gen);
+    }
+}
+
+impl fmt::Display for StreamCounter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "gliders: {} ({:.4}/gen)", self.count, self.rate())
+    }
+}
+
+#[test]
+fn test_stream_counter() {
+    // ~ two south-east bound gliders crossing the column x=30 about 70
+    // generations apart
+    let mut w = World::empty(60, 60);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        w.set_alive(x + 20, y + 2, true);
+        w.set_alive(x + 2, y + 20, true);
+    }
+    let mut c = StreamCounter::new(Rect {
+                                       x: 30,
+                                       y: 0,
+                                       width: 1,
+                                       height: 60,
+                                   },
+                                   DEFAULT_DEBOUNCE);
+    c.observe(&w);
+    for i in 0..200 {
+        w.advance_generation(|_, _, _| {});
+        c.observe(&w);
+        if i == 100 {
+            assert_eq!(1, c.count());
+        }
+    }
+    assert_eq!(2, c.count());
+    assert_eq!("gliders: 2 (0.0100/gen)", c.to_string());
+
+    // ~ without debouncing, the flickering cells of a passing glider
+    // are counted several times
+    let mut w = World::empty(60, 60);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        w.set_alive(x + 20, y + 2, true);
+    }
+    let mut c = StreamCounter::new(Rect {
+                                       x: 30,
+                                       y: 0,
+                                       width: 1,
+                                       height: 60,
+                                   },
+                                   0);
+    for _ in 0..100 {
+        w.advance_generation(|_, _, _| {});
+        c.observe(&w);
+    }
+    assert!(c.count() > 1);
+
+    // ~ cells edited into the region right after an object passed are
+    // not attributed to that object
+    let mut w = World::empty(10, 10);
+    let mut c = StreamCounter::new(Rect {
+                                       x: 0,
+                                       y: 0,
+                                       width: 2,
+                                       height: 2,
+                                   },
+                                   DEFAULT_DEBOUNCE);
+    w.set_alive(0, 0, true);
+    w.advance_generation(|_, _, _| {});
+    w.set_alive(1, 1, true);
+    c.observe(&w);
+    assert_eq!(1, c.count());
+    w.advance_generation(|_, _, _| {});
+    w.set_alive(1, 1, true);
+    c.observe(&w);
+    assert_eq!(2, c.count());
+}
+
+#[test]
+fn test_parse_rect() {
+    assert_eq!(Some(Rect {
+                   x: 3,
+                   y: 0,
+                   width: 1,
+                   height: 20,
+               }),
+               Rect::parse("3, 0,1,20"));
+    assert_eq!(None, Rect::parse("3,0,0,20"));
+    assert_eq!(None, Rect::parse("3,0,1"));
+    assert_eq!(None, Rect::parse("3,-1,1,20"));
+    // ~ reaching beyond the addressable cells
+    let huge = usize::max_value();
+    assert_eq!(None, Rect::parse(&format!("{},0,2,20", huge - 1)));
+    assert_eq!(None, Rect::parse(&format!("0,1,2,{}", huge)));
+    assert!(Rect::parse(&format!("{},0,1,20", huge - 1)).is_some());
+}
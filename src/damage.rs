@@ -0,0 +1,233 @@
+//! The parts of the screen to be drawn anew ("damage").
+//!
+//! Whatever changes what the screen is to show, e.g. the cells changed
+//! by advancing a generation, adds the rectangle concerned to a
+//! `DamageList`; the next frame draws only those rectangles (and the
+//! overlays intersecting them) rather than the whole screen.
+//! Overlapping and adjacent rectangles are merged into their union,
+//! and beyond a cap on their number a rectangle is merged into the one
+//! growing the least by it.  Resizes and mode switches damage the whole
+//! screen.
+//!
+//! As long as the damage consists of single cells only (e.g. those
+//! changed by a generation) the list also keeps the cells themselves,
+//! such that a frame draws exactly those rather than the rectangles
+//! they were merged into.
+
+use std::cmp;
+
+/// A rectangle of the screen as `(x, y, width, height)`.
+pub type Rect = (usize, usize, usize, usize);
+
+/// The number of rectangles a damage list keeps apart by default.
+pub const DEFAULT_CAP: usize = 32;
+
+/// The smallest rectangle containing both given ones.
+pub fn union(a: Rect, b: Rect) -> Rect {
+    let (x, y) = (cmp::min(a.0, b.0), cmp::min(a.1, b.1));
+    let (right, bottom) = (cmp::max(a.0 + a.2, b.0 + b.2), cmp::max(a.1 + a.3, b.1 + b.3));
+    (x, y, right - x, bottom - y)
+}
+
+/// Determines whether the given rectangles share at least one cell.
+pub fn intersects(a: Rect, b: Rect) -> bool {
+    a.2 > 0 && a.3 > 0 && b.2 > 0 && b.3 > 0 && a.0 < b.0 + b.2 && b.0 < a.0 + a.2 &&
+    a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+}
+
+/// Determines whether the given rectangles overlap or are next to each
+/// other (including diagonally), such that their union covers few
+/// cells beyond them.
+pub fn touches(a: Rect, b: Rect) -> bool {
+    a.0 <= b.0 + b.2 && b.0 <= a.0 + a.2 && a.1 <= b.1 + b.3 && b.1 <= a.1 + a.3
+}
+
+fn area(r: Rect) -> usize {
+    r.2 * r.3
+}
+
+/// The damaged rectangles of the screen since the last frame.
+#[derive(Clone, Debug)]
+pub struct DamageList {
+    // ~ pairwise neither overlapping nor adjacent
+    rects: Vec<Rect>,
+    // ~ whether the whole screen is damaged; `rects` is empty then
+    full: bool,
+    cap: usize,
+    // ~ the damaged cells one by one; `None` once any larger rectangle
+    // was damaged
+    cells: Option<Vec<(usize, usize)>>,
+}
+
+impl DamageList {
+    /// Creates an empty list keeping at most `cap` (at least one)
+    /// rectangles apart.
+    pub fn new(cap: usize) -> DamageList {
+        DamageList {
+            rects: Vec::new(),
+            full: false,
+            cap: if cap == 0 { 1 } else { cap },
+            cells: Some(Vec::new()),
+        }
+    }
+
+    /// Adds the given rectangle; empty ones are ignored.
+    pub fn add(&mut self, rect: Rect) {
+        if self.full || area(rect) == 0 {
+            return;
+        }
+        match self.cells {
+            Some(ref mut cells) if area(rect) == 1 => cells.push((rect.0, rect.1)),
+            _ => self.cells = None,
+        }
+        self.merge(rect);
+    }
+
+    // ~ adds the given (non-empty) rectangle to the rectangles kept
+    // apart
+    fn merge(&mut self, rect: Rect) {
+        // ~ swallow all the rectangles the (growing) union touches
+        let mut rect = rect;
+        while let Some(i) = self.rects.iter().position(|&r| touches(r, rect)) {
+            rect = union(self.rects.swap_remove(i), rect);
+        }
+        if self.rects.len() < self.cap {
+            self.rects.push(rect);
+        } else {
+            // ~ merge into the rectangle growing the least; the union
+            // might touch others now
+            let i = (0..self.rects.len())
+                        .min_by_key(|&i| area(union(self.rects[i], rect)) - area(self.rects[i]))
+                        .unwrap();
+            let merged = union(self.rects.swap_remove(i), rect);
+            self.merge(merged);
+        }
+    }
+
+    /// Adds the cell at the given position.
+    pub fn add_cell(&mut self, x: usize, y: usize) {
+        self.add((x, y, 1, 1));
+    }
+
+    /// Marks the whole screen as damaged, e.g. after it was resized.
+    pub fn mark_full(&mut self) {
+        self.full = true;
+        self.rects.clear();
+        self.cells = None;
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.full && self.rects.is_empty()
+    }
+
+    /// The damaged rectangles, unless the whole screen is damaged.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    /// The damaged cells (in the order added, possibly repeated) if all
+    /// damage was added cell by cell.
+    pub fn cells(&self) -> Option<&[(usize, usize)]> {
+        self.cells.as_ref().map(|c| &c[..])
+    }
+
+    /// Determines whether any damage falls into the given rectangle.
+    pub fn intersects(&self, rect: Rect) -> bool {
+        (self.full && area(rect) > 0) || self.rects.iter().any(|&r| intersects(r, rect))
+    }
+}
+
+#[test]
+fn test_rect_ops() {
+    assert_eq!((1, 2, 6, 5), union((1, 2, 3, 3), (4, 5, 3, 2)));
+    assert_eq!((0, 0, 4, 4), union((0, 0, 4, 4), (1, 1, 2, 2)));
+    assert!(intersects((0, 0, 3, 3), (2, 2, 3, 3)));
+    assert!(!intersects((0, 0, 3, 3), (3, 0, 3, 3)));
+    assert!(!intersects((0, 0, 3, 3), (1, 1, 0, 5)));
+    // ~ adjacent, diagonally adjacent, and one cell apart
+    assert!(touches((0, 0, 3, 3), (3, 0, 3, 3)));
+    assert!(touches((0, 0, 3, 3), (3, 3, 1, 1)));
+    assert!(!touches((0, 0, 3, 3), (4, 0, 1, 1)));
+    assert!(!touches((0, 0, 3, 3), (0, 4, 3, 1)));
+}
+
+#[test]
+fn test_damage_list() {
+    let mut d = DamageList::new(DEFAULT_CAP);
+    assert!(d.is_empty());
+    d.add((5, 5, 0, 3));
+    assert!(d.is_empty());
+    // ~ a row of cells is coalesced into one rectangle
+    for x in 2..6 {
+        d.add_cell(x, 1);
+    }
+    assert_eq!(&[(2, 1, 4, 1)], d.rects());
+    // ~ apart from it, and overlapping it
+    d.add((10, 10, 2, 2));
+    d.add((4, 0, 3, 2));
+    let mut rects = d.rects().to_vec();
+    rects.sort();
+    assert_eq!(vec![(2, 0, 5, 2), (10, 10, 2, 2)], rects);
+    assert!(d.intersects((11, 11, 5, 5)) && !d.intersects((7, 0, 3, 3)));
+    // ~ bridging both merges all three
+    d.add((6, 2, 4, 8));
+    assert_eq!(&[(2, 0, 10, 12)], d.rects());
+
+    // ~ the full screen sentinel swallows everything
+    d.mark_full();
+    assert!(d.is_full() && !d.is_empty() && d.rects().is_empty());
+    d.add_cell(0, 0);
+    assert!(d.rects().is_empty());
+    assert!(d.intersects((100, 100, 1, 1)) && !d.intersects((1, 1, 0, 0)));
+}
+
+#[test]
+fn test_damage_cap() {
+    // ~ scattered cells beyond the cap are merged into the nearest
+    // rectangle, growing it least
+    let mut d = DamageList::new(3);
+    for &(x, y) in &[(0, 0), (10, 0), (0, 10)] {
+        d.add_cell(x, y);
+    }
+    assert_eq!(3, d.rects().len());
+    d.add_cell(12, 1);
+    let mut rects = d.rects().to_vec();
+    rects.sort();
+    assert_eq!(vec![(0, 0, 1, 1), (0, 10, 1, 1), (10, 0, 3, 2)], rects);
+    // ~ a merge touching another rectangle swallows it as well
+    d.add_cell(1, 8);
+    d.add_cell(5, 5);
+    assert!(d.rects().len() <= 3);
+    for &(x, y) in &[(0, 0), (10, 0), (0, 10), (12, 1), (1, 8), (5, 5)] {
+        assert!(d.intersects((x, y, 1, 1)), "({}, {})", x, y);
+    }
+    // ~ a cap of one keeps the bounding box of all damage
+    let mut d = DamageList::new(0);
+    d.add_cell(3, 4);
+    d.add_cell(9, 1);
+    assert_eq!(&[(3, 1, 7, 4)], d.rects());
+    // ~ the cells themselves are known nevertheless
+    assert_eq!(Some(&[(3, 4), (9, 1)][..]), d.cells());
+}
+
+#[test]
+fn test_damage_cells() {
+    let mut d = DamageList::new(DEFAULT_CAP);
+    assert_eq!(Some(&[][..]), d.cells());
+    d.add_cell(1, 1);
+    d.add((2, 1, 1, 1));
+    d.add((7, 7, 0, 0));
+    assert_eq!(Some(&[(1, 1), (2, 1)][..]), d.cells());
+    // ~ any larger rectangle gives them up
+    d.add((5, 5, 2, 1));
+    assert_eq!(None, d.cells());
+    d.add_cell(9, 9);
+    assert_eq!(None, d.cells());
+    let mut d = DamageList::new(DEFAULT_CAP);
+    d.mark_full();
+    assert_eq!(None, d.cells());
+}
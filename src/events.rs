@@ -0,0 +1,447 @@
+//! A compact binary stream recording the evolution of a world.
+//!
+//! The stream starts with a header (the magic `CGOLEVT1`, followed by
+//! the world's width, height, and keyframe interval as little endian
+//! `u32`s) and continues with a sequence of records:
+//!
+//! - a keyframe, `'K'` followed by the generation (`u64`), the number
+//!   of live cells (`u32`), the coordinates of the live cells (two
+//!   `u32`s each), and the world's `generation_hash` (`u64`); a
+//!   keyframe is written for the initial world and then every
+//!   "keyframe interval" generations,
+//! - a change list, `'C'` followed by the generation (`u64`), the
+//!   number of changes (`u32`), and the changes leading to that
+//!   generation (two `u32` coordinates and a `u8`; 1 for a birth, 0
+//!   for a death.)
+//!
+//! Keyframes allow consumers to start replaying a stream from the
+//! middle (see `seek`) and to verify the stream's consistency (see
+//! `verify`.)
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use io_util::{invalid_data, read_u32, read_u64, write_u32, write_u64};
+use world::World;
+
+const MAGIC: &'static [u8; 8] = b"CGOLEVT1";
+const TAG_KEYFRAME: u8 = b'K';
+const TAG_CHANGES: u8 = b'C';
+
+/// A single cell changed from one generation to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub x: usize,
+    pub y: usize,
+    /// `true` if the cell was born, `false` if it died
+    pub alive: bool,
+}
+
+pub enum Record {
+    /// The full state of the world at the given generation.
+    Keyframe {
+        generation: u64,
+        world: World,
+        fingerprint: u64,
+    },
+    /// The changes leading to the given generation.
+    Changes {
+        generation: u64,
+        changes: Vec<Change>,
+    },
+}
+
+// --------------------------------------------------------------------
+
+/// Writes the evolution of a world as an event stream.
+pub struct Writer<W: Write> {
+    out: W,
+    keyframe_every: u64,
+}
+
+impl<W: Write> Writer<W> {
+    /// Starts a new stream with a keyframe of the given world.
+    pub fn new(mut out: W, world: &World, keyframe_every: u64) -> io::Result<Writer<W>> {
+        assert!(keyframe_every > 0);
+        try!(out.write_all(MAGIC));
+        try!(write_u32(&mut out, world.width() as u32));
+        try!(write_u32(&mut out, world.height() as u32));
+        try!(write_u32(&mut out, keyframe_every as u32));
+        let mut w = Writer {
+            out: out,
+            keyframe_every: keyframe_every,
+        };
+        try!(w.write_keyframe(world));
+        Ok(w)
+    }
+
+    /// Records the changes which led to the world's current
+    /// generation, followed by a keyframe if the generation is due for
+    /// one.
+    pub fn write_changes(&mut self, world: &World, changes: &[Change]) -> io::Result<()> {
+        try!(self.out.write_all(&[TAG_CHANGES]));
+        try!(write_u64(&mut self.out, world.generation() as u64));
+        try!(write_u32(&mut self.out, changes.len() as u32));
+        for c in changes {
+            try!(write_u32(&mut self.out, c.x as u32));
+            try!(write_u32(&mut self.out, c.y as u32));
+            try!(self.out.write_all(&[c.alive as u8]));
+        }
+        if world.generation() as u64 % self.keyframe_every == 0 {
+            try!(self.write_keyframe(world));
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// The output the stream is written to.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    fn write_keyframe(&mut self, world: &World) -> io::Result<()> {
+        try!(self.out.write_all(&[TAG_KEYFRAME]));
+        try!(write_u64(&mut self.out, world.generation() as u64));
+        try!(write_u32(&mut self.out, world.alive() as u32));
+        for y in 0..world.height() {
+            for x in 0..world.width() {
+                if world.is_alive(x, y) {
+                    try!(write_u32(&mut self.out, x as u32));
+                    try!(write_u32(&mut self.out, y as u32));
+                }
+            }
+        }
+        write_u64(&mut self.out, world.generation_hash())
+    }
+}
+
+// --------------------------------------------------------------------
+
+/// Reads the records of an event stream.
+pub struct Reader<R: Read> {
+    input: R,
+    width: usize,
+    height: usize,
+    keyframe_every: u64,
+}
+
+impl<R: Read> Reader<R> {
+    /// Consumes and validates the stream's header.
+    pub fn new(mut input: R) -> io::Result<Reader<R>> {
+        let mut magic = [0u8; 8];
+        try!(input.read_exact(&mut magic));
+        if &magic != MAGIC {
+            return Err(invalid_data("not an event stream"));
+        }
+        let width = try!(read_u32(&mut input)) as usize;
+        let height = try!(read_u32(&mut input)) as usize;
+        let keyframe_every = try!(read_u32(&mut input)) as u64;
+        if keyframe_every == 0 {
+            return Err(invalid_data("invalid keyframe interval"));
+        }
+        Ok(Reader {
+            input: input,
+            width: width,
+            height: height,
+            keyframe_every: keyframe_every,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn keyframe_every(&self) -> u64 {
+        self.keyframe_every
+    }
+
+    /// Reads the next record; `None` at the end of the stream.
+    pub fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let mut tag = [0u8; 1];
+        if try!(self.input.read(&mut tag)) == 0 {
+            return Ok(None);
+        }
+        let generation = try!(read_u64(&mut self.input));
+        let n = try!(read_u32(&mut self.input));
+        match tag[0] {
+            TAG_KEYFRAME => {
+                let mut world = World::empty(self.width, self.height);
+                for _ in 0..n {
+                    let (x, y) = try!(self.read_coords());
+                    if world.is_alive(x, y) {
+                        return Err(invalid_data("duplicate cell in keyframe"));
+                    }
+                    world.set_alive(x, y, true);
+                }
+                // ~ the recorded generation is part of the record, not
+                // of the world
+                world.restart();
+                let fingerprint = try!(read_u64(&mut self.input));
+                Ok(Some(Record::Keyframe {
+                    generation: generation,
+                    world: world,
+                    fingerprint: fingerprint,
+                }))
+            }
+            TAG_CHANGES => {
+                let mut changes = Vec::with_capacity(n as usize);
+                for _ in 0..n {
+                    let (x, y) = try!(self.read_coords());
+                    let mut alive = [0u8; 1];
+                    try!(self.input.read_exact(&mut alive));
+                    changes.push(Change {
+                        x: x,
+                        y: y,
+                        alive: alive[0] != 0,
+                    });
+                }
+                Ok(Some(Record::Changes {
+                    generation: generation,
+                    changes: changes,
+                }))
+            }
+            _ => Err(invalid_data("unknown record")),
+        }
+    }
+
+    fn read_coords(&mut self) -> io::Result<(usize, usize)> {
+        let x = try!(read_u32(&mut self.input)) as usize;
+        let y = try!(read_u32(&mut self.input)) as usize;
+        if x >= self.width || y >= self.height {
+            return Err(invalid_data("cell out of bounds"));
+        }
+        Ok((x, y))
+    }
+}
+
+// --------------------------------------------------------------------
+
+/// Iterates the change lists of an event stream.  The initial keyframe
+/// is reported as the births of all its live cells; later keyframes
+/// are skipped.  The iteration ends at the end of the stream or at the
+/// first malformed record, the error being available through
+/// `error()`.
+pub struct Replay<R: Read> {
+    reader: Reader<R>,
+    started: bool,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> Replay<R> {
+    pub fn new(reader: Reader<R>) -> Replay<R> {
+        Replay {
+            reader: reader,
+            started: false,
+            error: None,
+        }
+    }
+
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+}
+
+impl<R: Read> Iterator for Replay<R> {
+    type Item = (u64, Vec<Change>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.next_record() {
+                Ok(None) => return None,
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+                Ok(Some(Record::Keyframe { generation, world, .. })) => {
+                    if !self.started {
+                        self.started = true;
+                        return Some((generation, live_cells(&world)));
+                    }
+                }
+                Ok(Some(Record::Changes { generation, changes })) => {
+                    self.started = true;
+                    return Some((generation, changes));
+                }
+            }
+        }
+    }
+}
+
+/// Opens the event stream at the given path for replaying.
+pub fn replay<P: AsRef<Path>>(path: P) -> io::Result<Replay<BufReader<File>>> {
+    let f = try!(File::open(path));
+    Reader::new(BufReader::new(f)).map(Replay::new)
+}
+
+/// Positions the given event stream at the last keyframe not after the
+/// given generation.  Returns the world at that keyframe and the
+/// replay of the changes following it.
+pub fn seek<R: Read>(input: R, generation: u64) -> io::Result<(u64, World, Replay<R>)> {
+    let mut reader = try!(Reader::new(input));
+    let target = generation - generation % reader.keyframe_every();
+    loop {
+        match try!(reader.next_record()) {
+            None => return Err(invalid_data("no such keyframe")),
+            Some(Record::Keyframe { generation, world, .. }) => {
+                if generation == target {
+                    let mut replay = Replay::new(reader);
+                    replay.started = true;
+                    return Ok((generation, world, replay));
+                }
+            }
+            Some(Record::Changes { .. }) => {}
+        }
+    }
+}
+
+/// The outcome of a successful `verify`.
+#[derive(Debug)]
+pub struct Verification {
+    pub generations: u64,
+    pub keyframes: u64,
+}
+
+/// Replays the given event stream onto its initial keyframe and checks
+/// that every change flips a cell and every further keyframe matches
+/// the replayed world.
+pub fn verify<R: Read>(input: R) -> Result<Verification, String> {
+    let mut reader = try!(Reader::new(input).map_err(|e| e.to_string()));
+    let (mut world, mut generation) = match reader.next_record() {
+        Ok(Some(Record::Keyframe { generation, world, fingerprint })) => {
+            if world.generation_hash() != fingerprint {
+                return Err(format!("generation {}: fingerprint mismatch", generation));
+            }
+            (world, generation)
+        }
+        Ok(_) => return Err("missing initial keyframe".to_owned()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut v = Verification {
+        generations: 0,
+        keyframes: 1,
+    };
+    loop {
+        match reader.next_record() {
+            Err(e) => return Err(format!("after generation {}: {}", generation, e)),
+            Ok(None) => return Ok(v),
+            Ok(Some(Record::Changes { generation: g, changes })) => {
+                if g != generation + 1 {
+                    return Err(format!("generation {}: expected generation {}", g, generation + 1));
+                }
+                for c in changes {
+                    if world.is_alive(c.x, c.y) == c.alive {
+                        return Err(format!("generation {}: cell {},{} did not change", g, c.x, c.y));
+                    }
+                    world.set_alive(c.x, c.y, c.alive);
+                }
+                generation = g;
+                v.generations += 1;
+            }
+            Ok(Some(Record::Keyframe { generation: g, world: keyframe, fingerprint })) => {
+                if g != generation {
+                    return Err(format!("generation {}: keyframe out of sequence", g));
+                }
+                if world.generation_hash() != fingerprint ||
+                   keyframe.generation_hash() != fingerprint {
+                    return Err(format!("generation {}: fingerprint mismatch", g));
+                }
+                v.keyframes += 1;
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------
+
+fn live_cells(world: &World) -> Vec<Change> {
+    let mut cells = Vec::with_capacity(world.alive());
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            if world.is_alive(x, y) {
+                cells.push(Change {
+                    x: x,
+                    y: y,
+                    alive: true,
+                });
+            }
+        }
+    }
+    cells
+}
+
+// ~ records `n` generations of the given world; returns the stream and
+// the hashes of all the generations
+#[cfg(test)]
+fn record(mut world: World, n: usize, keyframe_every: u64) -> (Vec<u8>, Vec<u64>) {
+    let mut hashes = vec![world.generation_hash()];
+    let mut w = Writer::new(Vec::new(), &world, keyframe_every).unwrap();
+    for _ in 0..n {
+        let mut changes = Vec::new();
+        world.advance_generation(|x, y, alive| {
+            changes.push(Change {
+                x: x,
+                y: y,
+                alive: alive,
+            })
+        });
+        w.write_changes(&world, &changes).unwrap();
+        hashes.push(world.generation_hash());
+    }
+    (w.out, hashes)
+}
+
+#[test]
+fn test_round_trip() {
+    let (stream, hashes) = record(World::random_seeded(11, 40, 30), 100, 25);
+    assert_eq!(5, verify(&stream[..]).unwrap().keyframes);
+
+    let mut world = World::empty(40, 30);
+    let mut replay = Replay::new(Reader::new(&stream[..]).unwrap());
+    for (generation, changes) in replay.by_ref() {
+        for c in changes {
+            world.set_alive(c.x, c.y, c.alive);
+        }
+        assert_eq!(hashes[generation as usize], world.generation_hash());
+    }
+    assert!(replay.error().is_none());
+    assert_eq!(hashes[100], world.generation_hash());
+
+    // ~ a corrupted fingerprint is detected
+    let mut stream = stream;
+    let n = stream.len();
+    stream[n - 1] ^= 1;
+    assert!(verify(&stream[..]).is_err());
+}
+
+#[test]
+fn test_seek() {
+    let (stream, hashes) = record(World::random_seeded(12, 40, 30), 100, 25);
+    let (generation, mut world, replay) = seek(&stream[..], 60).unwrap();
+    assert_eq!(50, generation);
+    assert_eq!(hashes[50], world.generation_hash());
+    for (generation, changes) in replay {
+        for c in changes {
+            world.set_alive(c.x, c.y, c.alive);
+        }
+        assert_eq!(hashes[generation as usize], world.generation_hash());
+    }
+}
+
+#[test]
+fn test_sparse_stream_size() {
+    let mut world = World::empty(200, 200);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        world.set_alive(x + 100, y + 100, true);
+    }
+    let (stream, _) = record(world, 100, 50);
+    // ~ compared to storing 100 full boards as bitmaps
+    assert!(stream.len() * 20 < 100 * 200 * 200 / 8);
+}
@@ -0,0 +1,304 @@
+//! Following a moving object with the viewport (see `T`), e.g. to watch
+//! a glider travel across a world larger than the screen.
+//!
+//! The object is the group of live cells connected through the world's
+//! neighbourhood (like the reaper's objects) containing the live cell
+//! nearest to a given one, the center of the view when following is
+//! engaged.  After each generation it is looked for anew where it was:
+//! the groups with a live cell within its previous bounds grown by
+//! `MARGIN` cells make it up, which copes with the phases of a
+//! spaceship shifting its bounds with every generation.  On a torus
+//! positions are compared by their nearest images, such that the
+//! object is followed across the edges; across the edges of a twisted
+//! torus they are taken as on a plain one.  The object is lost once no
+//! live cell is left there (it died or was deleted), or once it grew
+//! beyond `MERGED` times its size (it merged with another one.)
+//!
+//! The viewport glides toward centering the object by at most `SPEED`
+//! cells per generation.  The viewport does not wrap around the edges
+//! of a torus: it jumps to the other side once the object crossed
+//! them, and it stays within the world, showing an object next to the
+//! edges off the center.
+
+use std::cmp;
+use std::collections::HashSet;
+
+use world::{BoundaryMode, World};
+
+/// The number of cells an object's bounds are grown by to look for it
+/// after a generation; the cells it may have moved by.
+pub const MARGIN: usize = 2;
+
+/// The factor of an object's size beyond which it counts as merged with
+/// another one.
+pub const MERGED: usize = 2;
+
+/// The number of cells the viewport moves by at most per generation.
+pub const SPEED: usize = 2;
+
+/// Why an object followed was lost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lost {
+    /// it died out or was deleted
+    Vanished,
+    /// it merged with another object
+    Merged,
+}
+
+/// An object followed from generation to generation; see the module's
+/// description.
+#[derive(Clone, Debug)]
+pub struct Follow {
+    // ~ the cells of the object as of the last generation
+    cells: Vec<(usize, usize)>,
+    // ~ the center of the cells, and how far they reach from it in
+    // either direction
+    center: (usize, usize),
+    reach: (usize, usize),
+}
+
+impl Follow {
+    /// Starts following the object containing the live cell nearest to
+    /// the given one; `None` if the world is empty.
+    pub fn engage(world: &World, (x, y): (usize, usize)) -> Option<Follow> {
+        let distance = |&(cx, cy): &(usize, usize)| {
+            let (dx, dy) = (offset(world, x, cx, world.width()), offset(world, y, cy, world.height()));
+            dx * dx + dy * dy
+        };
+        let nearest = match world.live_cells().into_iter().min_by_key(distance) {
+            Some(c) => c,
+            None => return None,
+        };
+        let mut seen = HashSet::new();
+        let mut cells = Vec::new();
+        fill(world, nearest, &mut seen, &mut cells, usize::max_value());
+        let mut f = Follow {
+            cells: Vec::new(),
+            center: nearest,
+            reach: (0, 0),
+        };
+        f.set_cells(world, cells);
+        Some(f)
+    }
+
+    /// The cell at the center of the object.
+    pub fn center(&self) -> (usize, usize) {
+        self.center
+    }
+
+    /// The cells of the object.
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// Looks for the object in the given world, advanced by a generation
+    /// since the last look.
+    pub fn track(&mut self, world: &World) -> Result<(), Lost> {
+        let (cx, cy) = self.center;
+        if cx >= world.width() || cy >= world.height() {
+            // ~ the world was replaced by a smaller one
+            return Err(Lost::Vanished);
+        }
+        let limit = MERGED * self.cells.len();
+        let (rx, ry) = ((self.reach.0 + MARGIN) as isize, (self.reach.1 + MARGIN) as isize);
+        let mut seen = HashSet::new();
+        let mut cells = Vec::new();
+        for dy in -ry..ry + 1 {
+            for dx in -rx..rx + 1 {
+                if let Some((x, y)) = world.shift_target(cx, cy, dx, dy) {
+                    if world.is_alive(x, y) && !seen.contains(&(x, y)) {
+                        fill(world, (x, y), &mut seen, &mut cells, limit);
+                        if cells.len() > limit {
+                            return Err(Lost::Merged);
+                        }
+                    }
+                }
+            }
+        }
+        if cells.is_empty() {
+            return Err(Lost::Vanished);
+        }
+        self.set_cells(world, cells);
+        Ok(())
+    }
+
+    /// Moves the object's cells along with those of a world which grew
+    /// by the given number of cells to the left and top.
+    pub fn translate(&mut self, dx: usize, dy: usize) {
+        for c in &mut self.cells {
+            *c = (c.0 + dx, c.1 + dy);
+        }
+        self.center = (self.center.0 + dx, self.center.1 + dy);
+    }
+
+    /// Determines the viewport to show the given world with on a screen
+    /// of `width` x `height` cells, coming from the given one: a step
+    /// toward centering the object.
+    pub fn next_viewport(&self,
+                         world: &World,
+                         viewport: (usize, usize),
+                         (width, height): (usize, usize))
+                         -> (usize, usize) {
+        let (cx, cy) = self.center;
+        (step(viewport.0, cx, width, world.width()), step(viewport.1, cy, height, world.height()))
+    }
+
+    fn set_cells(&mut self, world: &World, cells: Vec<(usize, usize)>) {
+        // ~ the mean of the offsets from a cell of the object (nearest
+        // images on a torus), rounded down whichever cell it is
+        let (x0, y0) = cells[0];
+        let n = cells.len() as isize;
+        let mean = |sum: isize| if sum < 0 { (sum - n + 1) / n } else { sum / n };
+        let dx = mean(cells.iter().map(|&(x, _)| offset(world, x0, x, world.width())).sum());
+        let dy = mean(cells.iter().map(|&(_, y)| offset(world, y0, y, world.height())).sum());
+        let center = (wrap(x0, dx, world.width()), wrap(y0, dy, world.height()));
+        let mut reach = (0, 0);
+        for &(x, y) in &cells {
+            reach.0 = cmp::max(reach.0, offset(world, center.0, x, world.width()).abs() as usize);
+            reach.1 = cmp::max(reach.1, offset(world, center.1, y, world.height()).abs() as usize);
+        }
+        self.center = center;
+        self.reach = reach;
+        self.cells = cells;
+    }
+}
+
+// ~ collects the live cells connected to the given one into `cells`,
+// stopping once there are more than `limit`
+fn fill(world: &World,
+        start: (usize, usize),
+        seen: &mut HashSet<(usize, usize)>,
+        cells: &mut Vec<(usize, usize)>,
+        limit: usize) {
+    let mut i = cells.len();
+    seen.insert(start);
+    cells.push(start);
+    while i < cells.len() && cells.len() <= limit {
+        let (x, y) = cells[i];
+        for &(dx, dy) in world.neighbourhood() {
+            if let Some(c) = world.shift_target(x, y, dx, dy) {
+                if world.is_alive(c.0, c.1) && seen.insert(c) {
+                    cells.push(c);
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+// ~ the offset from `a` to `b` along an axis `len` cells long; that to
+// the nearest image of `b` unless the world is bounded
+fn offset(world: &World, a: usize, b: usize, len: usize) -> isize {
+    let d = b as isize - a as isize;
+    if world.boundary() == BoundaryMode::Bounded {
+        return d;
+    }
+    let len = len as isize;
+    let d = (d % len + len) % len;
+    if d > len / 2 {
+        d - len
+    } else {
+        d
+    }
+}
+
+fn wrap(v: usize, d: isize, len: usize) -> usize {
+    let len = len as isize;
+    ((v as isize + d) % len + len) as usize % len as usize
+}
+
+// ~ a step of the viewport's coordinate `v` toward centering `center`
+// on a screen `len` cells long, within a world `world_len` cells long;
+// a jump if it is more than half the world away
+fn step(v: usize, center: usize, len: usize, world_len: usize) -> usize {
+    let target = cmp::min(center.saturating_sub(len / 2), world_len.saturating_sub(len));
+    if target > v + world_len / 2 || v > target + world_len / 2 {
+        target
+    } else if target > v {
+        v + cmp::min(SPEED, target - v)
+    } else {
+        v - cmp::min(SPEED, v - target)
+    }
+}
+
+#[cfg(test)]
+fn glider_at(world: &mut World, x: usize, y: usize) {
+    // ~ heading right and down
+    for &(dx, dy) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        world.set_alive((x + dx) % world.width(), (y + dy) % world.height(), true);
+    }
+}
+
+#[test]
+fn test_track_glider() {
+    let mut world = World::empty(40, 30);
+    glider_at(&mut world, 35, 25);
+    for &(x, y) in &[(20, 5), (21, 5), (20, 6), (21, 6)] {
+        world.set_alive(x, y, true);
+    }
+    // ~ the glider is nearer than the block
+    let mut f = Follow::engage(&world, (30, 20)).unwrap();
+    assert_eq!((36, 26), f.center());
+    // ~ across the edges of the torus, through all its phases
+    for _ in 0..40 {
+        world.advance_generations(1);
+        f.track(&world).unwrap();
+        assert_eq!(5, f.cells().len());
+        assert!(f.cells().iter().all(|&(x, y)| world.is_alive(x, y)));
+    }
+    assert_eq!((6, 6), f.center());
+    // ~ the block is followed too; it stays put
+    let mut b = Follow::engage(&world, (20, 5)).unwrap();
+    world.advance_generations(1);
+    f.track(&world).unwrap();
+    b.track(&world).unwrap();
+    assert_eq!((4, (20, 5)), (b.cells().len(), b.center()));
+    // ~ the glider deleted
+    for (x, y) in f.cells().to_vec() {
+        world.set_alive(x, y, false);
+    }
+    world.advance_generations(1);
+    assert_eq!(Err(Lost::Vanished), f.track(&world));
+    assert!(Follow::engage(&World::empty(10, 10), (5, 5)).is_none());
+}
+
+#[test]
+fn test_merged() {
+    // ~ a glider touching two blocks in a row: thirteen cells connected
+    let mut world = World::empty(40, 30);
+    glider_at(&mut world, 5, 5);
+    let mut f = Follow::engage(&world, (6, 6)).unwrap();
+    for &(x, y) in &[(8, 8), (9, 8), (8, 9), (9, 9), (10, 10), (11, 10), (10, 11), (11, 11)] {
+        world.set_alive(x, y, true);
+    }
+    assert_eq!(Err(Lost::Merged), f.track(&world));
+    // ~ one block: not yet twice the glider
+    for &(x, y) in &[(10, 10), (11, 10), (10, 11), (11, 11)] {
+        world.set_alive(x, y, false);
+    }
+    let mut f = Follow::engage(&world, (6, 6)).unwrap();
+    assert_eq!((9, Ok(())), (f.cells().len(), f.track(&world)));
+}
+
+#[test]
+fn test_next_viewport() {
+    let mut world = World::empty(200, 100);
+    glider_at(&mut world, 100, 50);
+    let f = Follow::engage(&world, (100, 50)).unwrap();
+    // ~ gliding toward centering (101, 51) on a screen of 40x20
+    let mut v = (0, 0);
+    let mut path = Vec::new();
+    for _ in 0..50 {
+        v = f.next_viewport(&world, v, (40, 20));
+        path.push(v);
+    }
+    assert_eq!(&[(2, 2), (4, 4)], &path[..2]);
+    assert_eq!((81, 41), v);
+    // ~ a jump when the object crossed the edges; the edges hold the
+    // viewport back
+    let mut world = World::empty(200, 100);
+    glider_at(&mut world, 195, 50);
+    let f = Follow::engage(&world, (195, 50)).unwrap();
+    assert_eq!((160, 41), f.next_viewport(&world, (0, 41), (40, 20)));
+    assert_eq!((158, 41), f.next_viewport(&world, (156, 41), (40, 20)));
+}
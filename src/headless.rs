@@ -0,0 +1,1222 @@
+use std::cmp;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use events::{self, Change};
+use io_util;
+use manifest::{Json, Manifest};
+use metrics::{self, Metric};
+use parser::{self, Format};
+use perf::{Counted, PerfCounters, PhaseProfile};
+use period::{self, Measurement};
+use progress::{self, ProgressMeter};
+use reaper::{self, Reaper};
+use schedule::RuleSchedule;
+use simulation;
+use workspace::{Labels, Workspace};
+use world::{AutoExpand, StepSummary, World};
+
+/// The process exit code signaling a run stopped by the user (SIGINT.)
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// The number of lines of the population chart printed at the end of
+/// a run (see `Options::plot_width`.)
+pub const PLOT_HEIGHT: usize = 20;
+
+/// The interval (in seconds) at which the progress of a run is logged
+/// (at the `info` level.)
+pub const PROGRESS_LOG_SECS: u64 = 5;
+
+/// The number of generations advanced at once by a run none of whose
+/// generations but the final one is looked at; Ctrl-C is still noticed
+/// between any two of them.
+pub const SKIP_CHUNK: usize = 64;
+
+// ~ the columns of the population history (see `Options::csv_file`)
+// following the generation and the cells alive; written like the
+// statistics, the births and deaths being those of the step to the
+// generation (none for the first row)
+const CSV_METRICS: [Metric; 3] = [Metric::Births, Metric::Deaths, Metric::Density];
+
+/// The format of the generations written to stdout while running (see
+/// `Options::emit_frames`.)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// the plaintext format preceded by a comment line `!gen N` and
+    /// followed by a blank line
+    Cells,
+    /// one JSON object per line: `{"gen":N,"alive":M,"cells":[[x,y],...]}`
+    Json,
+    /// the whole grid drawn with the given characters (see
+    /// `World::as_ascii`) followed by a blank line
+    Ascii { alive: char, dead: char },
+}
+
+impl fmt::Display for FrameFormat {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            FrameFormat::Cells => "cells",
+            FrameFormat::Json => "json",
+            FrameFormat::Ascii { .. } => "ascii",
+        })
+    }
+}
+
+pub struct Options {
+    /// the number of generations to advance; `None` for no limit
+    pub steps: Option<usize>,
+    /// the generation to advance to instead of a number of `steps`; a
+    /// run starting beyond it fails
+    pub goto: Option<usize>,
+    /// file to write per generation statistics to as CSV
+    pub stats_file: Option<String>,
+    /// additional metrics to record in the statistics
+    pub metrics: Vec<Metric>,
+    /// file to write the population history to as CSV, i.e. the
+    /// statistics with births, deaths and density; `-` for stdout
+    pub csv_file: Option<String>,
+    /// file to write the final world to; in the binary format if its
+    /// name ends with `parser::BINARY_EXTENSION`, as a coordinate list
+    /// if it ends with one of `parser::COORDS_EXTENSIONS`, otherwise in
+    /// the plaintext format
+    pub output_file: Option<String>,
+    /// file to write the event stream of the run to
+    pub events_file: Option<String>,
+    /// the number of generations between two keyframes in the event stream
+    pub keyframe_every: u64,
+    /// whether to measure the period of the final world
+    pub measure_period: bool,
+    /// the manifest recording the run
+    pub manifest: Option<Manifest>,
+    /// whether to stop the run as soon as all cells are dead
+    pub exit_on_extinct: bool,
+    /// grows the (bounded) world as its live cells approach the edges
+    pub auto_expand: Option<AutoExpand>,
+    /// print the world's checksum (see `World::checksum`) every that
+    /// many generations
+    pub checksum_every: Option<usize>,
+    /// print a chart of the population of all generations of the run
+    /// at most that many characters wide at its end
+    pub plot_width: Option<usize>,
+    /// write the generations to stdout in the given format as they
+    /// are computed
+    pub emit_frames: Option<FrameFormat>,
+    /// write only the generations divisible by that number
+    pub emit_every: usize,
+    /// log the performance counters (see `perf`) at this interval (in
+    /// seconds)
+    pub perf_log_secs: Option<u64>,
+    /// remove stable debris (see `reaper`) by this policy
+    pub reap: Option<reaper::Policy>,
+    /// force the statistics and the events written so far to the disk
+    /// at this interval (in seconds; see `io_util`)
+    pub sync_secs: u64,
+    /// switch the rule at the generations scheduled (see `schedule`)
+    pub schedule: Option<RuleSchedule>,
+    /// break the time spent advancing generations down by phase (see
+    /// `perf::PhaseProfile`)
+    pub profile: bool,
+}
+
+/// Describes the outcome of a headless run.
+#[derive(Debug)]
+pub struct Summary {
+    pub generation: usize,
+    pub alive: usize,
+    pub interrupted: bool,
+    /// whether no cell of the final world is alive
+    pub extinct: bool,
+    /// the period of the final world if requested; `Some(None)` if
+    /// none was found
+    pub period: Option<Option<Measurement>>,
+    /// the time spent in each phase if requested
+    pub profile: Option<PhaseProfile>,
+}
+
+impl Summary {
+    pub fn exit_code(&self) -> i32 {
+        if self.interrupted {
+            EXIT_INTERRUPTED
+        } else {
+            0
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt,
+                    "generation={} alive={} interrupted={}",
+                    self.generation,
+                    self.alive,
+                    self.interrupted));
+        match self.period {
+            None => Ok(()),
+            Some(None) => write!(fmt, " period=unknown"),
+            Some(Some(ref m)) => write!(fmt, " period={} dx={} dy={} ({})", m.period, m.dx, m.dy, m),
+        }
+    }
+}
+
+/// Advances the given world without any user interface until the
+/// configured number of steps is reached, the world dies out (if
+/// requested), or the user hits Ctrl-C.  The labels of the world's
+/// cells are kept for the final world written.  The first Ctrl-C stops
+/// the run at the next generation boundary (still producing all
+/// requested outputs), a second one aborts the process immediately.
+/// Frames (see `Options::emit_frames`) are flushed to stdout one by
+/// one; the run waits for a slow reader of a pipe rather than buffering
+/// them.
+pub fn run(ws: Workspace, opts: &Options) -> Result<Summary, String> {
+    sigint::install();
+    run_until(ws, opts, io::stdout(), sigint::received)
+}
+
+fn run_until<W, F>(ws: Workspace, opts: &Options, mut frames: W, interrupted: F) -> Result<Summary, String>
+    where W: Write,
+          F: Fn() -> bool
+{
+    macro_rules! err {
+        ($filename:expr, $expr:expr) => {
+            match $expr {
+                Err(e) => return Err(format!("{}: {}", $filename, e)),
+                Ok(v) => v,
+            }
+        }
+    }
+
+    let Workspace { mut world, mut labels } = ws;
+    if opts.auto_expand.is_some() && opts.events_file.is_some() {
+        // ~ the event stream is bound to the world's initial dimensions
+        return Err("Exporting events of auto-expanding worlds is not supported".to_owned());
+    }
+    if let Some(ref m) = opts.manifest {
+        try!(m.write_started(&world));
+    }
+
+    let mut stats = match opts.stats_file {
+        None => None,
+        Some(ref f) => {
+            let mut out = Counted::new(BufWriter::new(err!(f, File::create(f))));
+            let reaped = opts.reap.map(|_| 0);
+            err!(f, write_stats_header(&mut out, &world, &opts.metrics, reaped.is_some()));
+            err!(f, write_stats(&mut out, &world, &StepSummary::default(), &opts.metrics, reaped));
+            Some((f, out))
+        }
+    };
+
+    // ~ the population history goes to `frames` for `-`, which then
+    // take no frames
+    let mut csv = match opts.csv_file {
+        None => None,
+        Some(ref f) if f == "-" => Some(("stdout", None)),
+        Some(ref f) => Some((&f[..], Some(BufWriter::new(err!(f, File::create(f)))))),
+    };
+    macro_rules! write_csv {
+        ($write:ident($($arg:expr),*)) => {
+            if let Some((f, ref mut out)) = csv {
+                match *out {
+                    Some(ref mut out) => err!(f, $write(out, $($arg),*)),
+                    None => err!(f, $write(&mut frames, $($arg),*)),
+                }
+            }
+        }
+    }
+    write_csv!(write_stats_header(&world, &CSV_METRICS, false));
+    write_csv!(write_stats(&world, &StepSummary::default(), &CSV_METRICS, None));
+
+    let mut events = match opts.events_file {
+        None => None,
+        Some(ref f) => {
+            let out = Counted::new(BufWriter::new(err!(f, File::create(f))));
+            Some((f, err!(f, events::Writer::new(out, &world, opts.keyframe_every))))
+        }
+    };
+
+    if let Some(format) = opts.emit_frames {
+        if world.generation() % opts.emit_every == 0 {
+            err!("stdout", write_frame(&mut frames, &world, format));
+        }
+    }
+
+    // ~ the population of every generation for the final chart
+    let mut populations = Vec::new();
+    let first_generation = world.generation();
+    if opts.plot_width.is_some() {
+        populations.push(world.alive());
+    }
+    let mut was_interrupted = false;
+    let mut warned_capped = false;
+    let mut steps = 0;
+    let mut changes = Vec::new();
+    let mut reaper = opts.reap.map(Reaper::new);
+    if let Some(ref mut r) = reaper {
+        r.observe(&mut world, &mut changes);
+    }
+    let mut meter = ProgressMeter::new(progress::DEFAULT_WINDOW_SECS);
+    // ~ when and after how many steps the meter was last sampled
+    let mut sampled = (Instant::now(), 0);
+    let mut perf = PerfCounters::new();
+    perf.set_timed(opts.perf_log_secs.is_some());
+    let mut profile = PhaseProfile::new();
+    let mut perf_logged = Instant::now();
+    let mut synced = Instant::now();
+    macro_rules! log_perf {
+        () => {{
+            perf.bytes_written = stats.as_ref().map_or(0, |&(_, ref out)| out.written()) +
+                                 events.as_ref().map_or(0, |&(_, ref out)| out.get_ref().written());
+            info!("perf: {}", perf);
+        }}
+    }
+    let limit = match opts.goto {
+        Some(n) if n < world.generation() => {
+            return Err(format!("Cannot go back to generation {} from generation {}", n, world.generation()))
+        }
+        Some(n) => Some(n - world.generation()),
+        None => opts.steps,
+    };
+    // ~ whether the generations of the run but the final one go unseen
+    let quiet = stats.is_none() && csv.is_none() && events.is_none() && opts.emit_frames.is_none() &&
+                opts.checksum_every.is_none() && opts.plot_width.is_none() && opts.auto_expand.is_none() &&
+                !opts.exit_on_extinct && reaper.is_none() && !opts.profile;
+    while limit.map_or(true, |n| steps < n) {
+        if interrupted() {
+            was_interrupted = true;
+            break;
+        }
+        if opts.exit_on_extinct && world.is_empty() {
+            break;
+        }
+        if let Some(ref s) = opts.schedule {
+            if let Some(rule) = s.apply(&mut world) {
+                info!("generation {}: switched to rule {}", world.generation(), rule);
+            }
+        }
+        if quiet {
+            // ~ nothing looks at the generations in between, up to the
+            // next switch of the rules
+            let n = cmp::min(SKIP_CHUNK, limit.map_or(SKIP_CHUNK, |n| n - steps));
+            let n = opts.schedule.as_ref().map_or(n, |s| s.steady_for(world.generation(), n));
+            let started = perf.start();
+            let mut advanced = 0;
+            while advanced < n && (advanced == 0 || !interrupted()) {
+                world.advance_generation(|_, _, _| {});
+                advanced += 1;
+            }
+            let n = advanced;
+            perf.add_skipped(n, world.width() * world.height());
+            perf.add_step_time(started);
+            steps += n;
+        } else {
+            let started = perf.start();
+            let (summary, g) = if opts.profile {
+                simulation::step_timed(&mut world, opts.auto_expand.as_ref(), &mut changes, &mut profile)
+            } else {
+                simulation::step(&mut world, opts.auto_expand.as_ref(), &mut changes)
+            };
+            if let Some(ref mut r) = reaper {
+                let n = r.observe(&mut world, &mut changes);
+                if n > 0 {
+                    info!("generation {}: reaped {} cells of stable debris", world.generation(), n);
+                }
+            }
+            perf.add_step(&summary);
+            perf.add_step_time(started);
+            steps += 1;
+            if opts.auto_expand.is_some() {
+                labels.translate(g.left, g.top);
+                if !g.is_empty() {
+                    info!("generation {}: grew the world by {} left, {} top, {} right, {} bottom to {}x{}",
+                          world.generation(),
+                          g.left,
+                          g.top,
+                          g.right,
+                          g.bottom,
+                          world.width(),
+                          world.height());
+                }
+                if g.capped && !warned_capped {
+                    warn!("generation {}: the world reached its maximum size {}x{}; not growing any further",
+                          world.generation(),
+                          world.width(),
+                          world.height());
+                    warned_capped = true;
+                }
+            }
+            if let Some((f, ref mut out)) = stats {
+                let reaped = reaper.as_ref().map(|r| r.reaped());
+                err!(f, write_stats(out, &world, &summary, &opts.metrics, reaped));
+            }
+            write_csv!(write_stats(&world, &summary, &CSV_METRICS, None));
+            if let Some((f, ref mut out)) = events {
+                let changes: Vec<_> = changes.iter()
+                                             .map(|&(x, y, alive)| {
+                                                 Change {
+                                                     x: x,
+                                                     y: y,
+                                                     alive: alive,
+                                                 }
+                                             })
+                                             .collect();
+                err!(f, out.write_changes(&world, &changes));
+            }
+            if let Some(format) = opts.emit_frames {
+                if world.generation() % opts.emit_every == 0 {
+                    err!("stdout", write_frame(&mut frames, &world, format));
+                }
+            }
+            if let Some(n) = opts.checksum_every {
+                if world.generation() % n == 0 {
+                    println!("generation={} checksum={:016x}", world.generation(), world.checksum());
+                }
+            }
+            if opts.plot_width.is_some() {
+                populations.push(world.alive());
+            }
+        }
+        let elapsed = sampled.0.elapsed();
+        if elapsed >= Duration::from_secs(PROGRESS_LOG_SECS) {
+            meter.add_sample(steps - sampled.1, elapsed);
+            sampled = (Instant::now(), steps);
+            log_progress(&meter, world.generation(), limit.map(|n| n - steps));
+        }
+        if opts.perf_log_secs.map_or(false, |secs| perf_logged.elapsed() >= Duration::from_secs(secs)) {
+            log_perf!();
+            perf_logged = Instant::now();
+        }
+        if synced.elapsed() >= Duration::from_secs(opts.sync_secs) {
+            if let Some((f, ref mut out)) = stats {
+                err!(f, sync_stats(out));
+            }
+            if let Some((f, Some(ref mut out))) = csv {
+                err!(f, sync_csv(out));
+            }
+            if let Some((f, ref mut out)) = events {
+                err!(f, sync_events(out));
+            }
+            synced = Instant::now();
+        }
+    }
+    if opts.perf_log_secs.is_some() {
+        log_perf!();
+    }
+    if let Some(width) = opts.plot_width {
+        print!("{}", metrics::plot_from(&populations, first_generation, width, PLOT_HEIGHT));
+    }
+
+    if let Some((f, ref mut out)) = stats {
+        err!(f, sync_stats(out));
+    }
+    match csv {
+        Some((f, Some(ref mut out))) => err!(f, sync_csv(out)),
+        Some((f, None)) => err!(f, frames.flush()),
+        None => {}
+    }
+    if let Some((f, ref mut out)) = events {
+        err!(f, sync_events(out));
+    }
+    if let Some(ref f) = opts.output_file {
+        let format = Format::from_filename(f);
+        if format == Some(Format::Binary) {
+            // ~ the binary format has no room for comments
+            try!(io_util::atomic_write(f, |mut out| world.save_binary(&mut out))
+                     .map_err(|e| format!("{}: {}", f, e)));
+        } else if format == Some(Format::Coords) {
+            try!(parser::save_coords(f, &world, &[&final_comment(&world, was_interrupted)]));
+        } else {
+            try!(write_plaintext(f, &world, &labels, was_interrupted));
+        }
+    }
+    if let Some(ref m) = opts.manifest {
+        let artifacts: Vec<&str> = [&opts.stats_file, &opts.events_file, &opts.output_file]
+                                       .iter()
+                                       .filter_map(|f| f.as_ref().map(|f| &f[..]))
+                                       .chain(opts.csv_file.iter().map(|f| &f[..]).filter(|&f| f != "-"))
+                                       .collect();
+        try!(m.write_finished(&world, was_interrupted, &artifacts));
+    }
+    Ok(Summary {
+        generation: world.generation(),
+        alive: world.alive(),
+        interrupted: was_interrupted,
+        extinct: world.is_empty(),
+        period: if opts.measure_period {
+            Some(match opts.schedule {
+                Some(ref s) => s.measure_period(&world, period::DEFAULT_MAX_PERIOD),
+                None => period::measure(&world, period::DEFAULT_MAX_PERIOD),
+            })
+        } else {
+            None
+        },
+        profile: if opts.profile { Some(profile) } else { None },
+    })
+}
+
+/// Writes the differences between the worlds `a` and `b` (see
+/// `World::diff`), one line per cell: `- X Y` for a cell alive only in
+/// `a` and `+ X Y` for one alive only in `b`; preceded by a line
+/// `size AxB CxD` if their dimensions differ.  Returns whether the
+/// worlds differ, in their size or in any cell.
+pub fn write_diff<W: Write>(out: &mut W, a: &World, b: &World) -> io::Result<bool> {
+    let resized = (a.width(), a.height()) != (b.width(), b.height());
+    if resized {
+        try!(writeln!(out, "size {}x{} {}x{}", a.width(), a.height(), b.width(), b.height()));
+    }
+    let cells = a.diff(b);
+    for &(x, y, in_a) in &cells {
+        try!(writeln!(out, "{} {} {}", if in_a { '-' } else { '+' }, x, y));
+    }
+    Ok(resized || !cells.is_empty())
+}
+
+// ~ writes the given generation to `out` in the given format; flushed
+// right away for the reader to see it
+fn write_frame<W: Write>(out: &mut W, world: &World, format: FrameFormat) -> io::Result<()> {
+    match format {
+        FrameFormat::Cells => {
+            let comment = format!("gen {}", world.generation());
+            try!(out.write_all(parser::render_cells(world, &[&comment]).as_bytes()));
+            try!(writeln!(out, ""));
+        }
+        FrameFormat::Json => {
+            let cells = world.live_cells()
+                             .iter()
+                             .map(|&(x, y)| Json::Array(vec![Json::Int(x as i64), Json::Int(y as i64)]))
+                             .collect();
+            let frame = Json::Object(vec![("gen".to_owned(), Json::Int(world.generation() as i64)),
+                                          ("alive".to_owned(), Json::Int(world.alive() as i64)),
+                                          ("cells".to_owned(), Json::Array(cells))]);
+            try!(writeln!(out, "{}", frame));
+        }
+        FrameFormat::Ascii { alive, dead } => {
+            try!(out.write_all(world.as_ascii(alive, dead).as_bytes()));
+            try!(writeln!(out, ""));
+        }
+    }
+    out.flush()
+}
+
+fn final_comment(world: &World, was_interrupted: bool) -> String {
+    if was_interrupted {
+        format!("partial: interrupted at generation {}", world.generation())
+    } else {
+        format!("generation {}", world.generation())
+    }
+}
+
+// ~ writes the final world of a run in the plaintext format; the
+// comments note the generation and the origin
+fn write_plaintext(f: &str,
+                   world: &World,
+                   labels: &Labels,
+                   was_interrupted: bool)
+                   -> Result<(), String> {
+    let comment = final_comment(world, was_interrupted);
+    let ws = Workspace {
+        world: world.clone(),
+        labels: labels.clone(),
+    };
+    let origin = world.origin();
+    if origin != (0, 0) {
+        let origin = format!("origin {},{}", origin.0, origin.1);
+        parser::save_workspace(f, &ws, &[&comment, &origin])
+    } else {
+        parser::save_workspace(f, &ws, &[&comment])
+    }
+}
+
+// ~ logs the generation reached, the rate, and for a run of a limited
+// number of steps the generations left and the time they will take
+fn log_progress(meter: &ProgressMeter, generation: usize, left: Option<usize>) {
+    let rate = meter.rate().map_or("?".to_owned(), |r| format!("{:.0}", r));
+    match left {
+        Some(left) => {
+            let eta = meter.eta(left).map_or("?".to_owned(), progress::format_duration);
+            info!("generation {}: {} left, {} gen/s, ETA {}", generation, left, rate, eta);
+        }
+        None => info!("generation {}: {} gen/s", generation, rate),
+    }
+}
+
+// ~ flushes the given statistics and forces them to the disk
+fn sync_stats(out: &mut Counted<BufWriter<File>>) -> io::Result<()> {
+    try!(out.flush());
+    out.get_ref().get_ref().sync_data()
+}
+
+// ~ flushes the given population history and forces it to the disk
+fn sync_csv(out: &mut BufWriter<File>) -> io::Result<()> {
+    try!(out.flush());
+    out.get_ref().sync_data()
+}
+
+// ~ flushes the given events and forces them to the disk
+fn sync_events(out: &mut events::Writer<Counted<BufWriter<File>>>) -> io::Result<()> {
+    try!(out.flush());
+    out.get_ref().get_ref().get_ref().sync_data()
+}
+
+// ~ the columns of the statistics: the given metrics, and the cells
+// reaped so far if `reaping`.  the entropy of big worlds is computed
+// from a sample; the header notes the sampling stride
+fn write_stats_header<W: Write>(out: &mut W, world: &World, ms: &[Metric], reaping: bool) -> io::Result<()> {
+    try!(write!(out, "generation,alive"));
+    for m in ms {
+        match *m {
+            Metric::Entropy(block) => {
+                try!(write!(out, ",entropy{}", block));
+                let stride = metrics::entropy_stride(world, block);
+                if stride > 1 {
+                    try!(write!(out, "_stride{}", stride));
+                }
+            }
+            Metric::Density => try!(write!(out, ",density")),
+            Metric::Activity => try!(write!(out, ",activity")),
+            Metric::Births => try!(write!(out, ",births")),
+            Metric::Deaths => try!(write!(out, ",deaths")),
+            Metric::Neighbours => {
+                for n in 0..9 {
+                    try!(write!(out, ",neighbours{}", n));
+                }
+            }
+        }
+    }
+    if reaping {
+        try!(write!(out, ",reaped"));
+    }
+    writeln!(out, "")
+}
+
+fn write_stats<W: Write>(out: &mut W,
+                         world: &World,
+                         summary: &StepSummary,
+                         ms: &[Metric],
+                         reaped: Option<usize>)
+                         -> io::Result<()> {
+    try!(write!(out, "{},{}", world.generation(), world.alive()));
+    for m in ms {
+        let v = match *m {
+            Metric::Entropy(block) => {
+                match metrics::block_entropy_auto(world, block) {
+                    Ok(v) => v,
+                    Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidInput, e)),
+                }
+            }
+            Metric::Density => metrics::density(world),
+            Metric::Activity => metrics::activity_ratio(summary, world),
+            Metric::Births | Metric::Deaths => {
+                let n = if *m == Metric::Births { summary.births } else { summary.deaths };
+                try!(write!(out, ",{}", n));
+                continue;
+            }
+            Metric::Neighbours => {
+                for n in world.neighbour_histogram().iter() {
+                    try!(write!(out, ",{}", n));
+                }
+                continue;
+            }
+        };
+        try!(write!(out, ",{:.6}", v));
+    }
+    if let Some(n) = reaped {
+        try!(write!(out, ",{}", n));
+    }
+    writeln!(out, "")
+}
+
+#[test]
+fn test_run_interrupted() {
+    use std::cell::Cell;
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::process;
+
+    let dir = env::temp_dir();
+    let stats = dir.join(format!("cgol-test-run-interrupted-{}.csv", process::id()));
+    let stats = stats.to_str().unwrap().to_owned();
+    let output = dir.join(format!("cgol-test-run-interrupted-{}.cells", process::id()));
+    let output = output.to_str().unwrap().to_owned();
+    let opts = Options {
+        steps: Some(100),
+        goto: None,
+        stats_file: Some(stats.clone()),
+        metrics: vec![Metric::Density, Metric::Neighbours],
+        csv_file: None,
+        output_file: Some(output.clone()),
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    // ~ simulate a Ctrl-C arriving during the fifth generation
+    let checks = Cell::new(0);
+    let summary = run_until(Workspace::new(World::random_seeded(3, 20, 20)), &opts, io::sink(), || {
+                      checks.set(checks.get() + 1);
+                      checks.get() > 5
+                  })
+                      .unwrap();
+    assert!(summary.interrupted);
+    assert_eq!(5, summary.generation);
+    assert_eq!(EXIT_INTERRUPTED, summary.exit_code());
+    assert!(summary.to_string().ends_with("interrupted=true"));
+
+    let mut s = String::new();
+    File::open(&stats).unwrap().read_to_string(&mut s).unwrap();
+    // ~ header plus the initial and five advanced generations
+    assert_eq!(7, s.lines().count());
+    assert!(s.starts_with("generation,alive,density,neighbours0,neighbours1,"));
+    // ~ every cell counted by its neighbours
+    for line in s.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        assert_eq!(12, fields.len());
+        assert_eq!(400, fields[3..].iter().map(|n| n.parse::<usize>().unwrap()).sum::<usize>());
+    }
+
+    s.clear();
+    File::open(&output).unwrap().read_to_string(&mut s).unwrap();
+    assert!(s.starts_with("!partial: interrupted at generation 5\n"));
+
+    let _ = fs::remove_file(&stats);
+    let _ = fs::remove_file(&output);
+
+    // ~ a quiet run, advancing in chunks, stops at the same generation
+    let quiet = Options {
+        stats_file: None,
+        metrics: Vec::new(),
+        output_file: None,
+        ..opts
+    };
+    let checks = Cell::new(0);
+    let summary = run_until(Workspace::new(World::random_seeded(3, 20, 20)), &quiet, io::sink(), || {
+                      checks.set(checks.get() + 1);
+                      checks.get() > 5
+                  })
+                      .unwrap();
+    assert!(summary.interrupted);
+    assert_eq!(5, summary.generation);
+}
+
+#[test]
+fn test_run_binary_output() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    let output = env::temp_dir().join(format!("cgol-test-run-binary-{}.cgolb", process::id()));
+    let output = output.to_str().unwrap().to_owned();
+    let opts = Options {
+        steps: Some(30),
+        goto: None,
+        stats_file: None,
+        metrics: Vec::new(),
+        csv_file: None,
+        output_file: Some(output.clone()),
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    let mut world = World::random_seeded(4, 30, 20);
+    run_until(Workspace::new(world.clone()), &opts, io::sink(), || false).unwrap();
+    for _ in 0..30 {
+        world.advance_generation(|_, _, _| {});
+    }
+    let saved = World::load_binary(File::open(&output).unwrap()).unwrap();
+    assert_eq!(30, saved.generation());
+    assert_eq!(world.generation_hash(), saved.generation_hash());
+    let _ = fs::remove_file(&output);
+}
+
+#[test]
+fn test_run_reaping() {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::process;
+
+    let stats = env::temp_dir().join(format!("cgol-test-run-reaping-{}.csv", process::id()));
+    let stats = stats.to_str().unwrap().to_owned();
+    let opts = Options {
+        steps: Some(12),
+        goto: None,
+        stats_file: Some(stats.clone()),
+        metrics: Vec::new(),
+        csv_file: None,
+        output_file: None,
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: Some(reaper::Policy {
+            every: 4,
+            age: 6,
+            distance: 5,
+        }),
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    // ~ a block far from a glider
+    let mut world = World::empty(40, 40);
+    for &(x, y) in &[(2, 2), (3, 2), (2, 3), (3, 3), (21, 20), (22, 21), (20, 22), (21, 22), (22, 22)] {
+        world.set_alive(x, y, true);
+    }
+    world.restart();
+    let summary = run_until(Workspace::new(world), &opts, io::sink(), || false).unwrap();
+    assert_eq!(5, summary.alive);
+    let mut s = String::new();
+    File::open(&stats).unwrap().read_to_string(&mut s).unwrap();
+    let lines: Vec<&str> = s.lines().collect();
+    assert_eq!(("generation,alive,reaped", "0,9,0"), (lines[0], lines[1]));
+    assert_eq!(("7,9,0", "8,5,4", "12,5,4"), (lines[8], lines[9], lines[13]));
+    let _ = fs::remove_file(&stats);
+}
+
+#[test]
+fn test_run_csv() {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::process;
+
+    let mut opts = Options {
+        steps: Some(25),
+        goto: None,
+        stats_file: None,
+        metrics: Vec::new(),
+        csv_file: Some("-".to_owned()),
+        output_file: None,
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    let world = World::random_seeded(9, 20, 10);
+    let mut stdout = Vec::new();
+    run_until(Workspace::new(world.clone()), &opts, &mut stdout, || false).unwrap();
+    let s = String::from_utf8(stdout).unwrap();
+    let lines: Vec<&str> = s.lines().collect();
+    // ~ a line for the initial generation and one per step
+    assert_eq!("generation,alive,births,deaths,density", lines[0]);
+    assert_eq!(1 + 26, lines.len());
+    let mut alive = world.alive();
+    for (g, line) in lines[1..].iter().enumerate() {
+        let fields: Vec<&str> = line.split(',').collect();
+        assert_eq!(5, fields.len(), "{}", line);
+        let n: Vec<usize> = fields[..4].iter().map(|n| n.parse().unwrap()).collect();
+        assert_eq!(g, n[0]);
+        assert_eq!(alive + n[2] - n[3], n[1], "{}", line);
+        alive = n[1];
+        assert_eq!(format!("{:.6}", alive as f64 / 200.0), fields[4]);
+    }
+
+    // ~ the same to a file
+    let csv = env::temp_dir().join(format!("cgol-test-run-csv-{}.csv", process::id()));
+    let csv = csv.to_str().unwrap().to_owned();
+    opts.csv_file = Some(csv.clone());
+    let mut stdout = Vec::new();
+    run_until(Workspace::new(world), &opts, &mut stdout, || false).unwrap();
+    assert!(stdout.is_empty());
+    let mut t = String::new();
+    File::open(&csv).unwrap().read_to_string(&mut t).unwrap();
+    assert_eq!(s, t);
+    let _ = fs::remove_file(&csv);
+}
+
+#[test]
+fn test_run_exit_on_extinct() {
+    let mut opts = Options {
+        steps: Some(10),
+        goto: None,
+        stats_file: None,
+        metrics: Vec::new(),
+        csv_file: None,
+        output_file: None,
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: true,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    let lone_cell = || {
+        let mut w = World::empty(5, 5);
+        w.set_alive(2, 2, true);
+        w
+    };
+    let summary = run_until(Workspace::new(lone_cell()), &opts, io::sink(), || false).unwrap();
+    assert!(summary.extinct);
+    assert_eq!(1, summary.generation);
+
+    opts.exit_on_extinct = false;
+    let summary = run_until(Workspace::new(lone_cell()), &opts, io::sink(), || false).unwrap();
+    assert!(summary.extinct);
+    assert_eq!(10, summary.generation);
+}
+
+#[test]
+fn test_run_goto() {
+    use patterns;
+
+    let mut opts = Options {
+        steps: None,
+        goto: Some(1000),
+        stats_file: None,
+        metrics: Vec::new(),
+        csv_file: None,
+        output_file: None,
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    // ~ a pulsar (period 3) is in its second phase at generation 1000
+    let cells = patterns::STAMPS.iter().find(|&&(name, _)| name == "Pulsar").unwrap().1;
+    let mut pattern = parser::parse_cells(cells).unwrap();
+    pattern.trim(0);
+    let mut pulsar = World::empty(20, 20);
+    pulsar.blit(3, 3, &pattern);
+    let mut phases = vec![pulsar.alive()];
+    let mut w = pulsar.clone();
+    for _ in 0..2 {
+        w.advance_generation(|_, _, _| {});
+        phases.push(w.alive());
+    }
+    assert!(phases[0] != phases[1] && phases[1] != phases[2]);
+    let summary = run_until(Workspace::new(pulsar.clone()), &opts, io::sink(), || false).unwrap();
+    assert_eq!((1000, phases[1]), (summary.generation, summary.alive));
+    // ~ likewise generation by generation; not back
+    opts.exit_on_extinct = true;
+    let summary = run_until(Workspace::new(pulsar.clone()), &opts, io::sink(), || false).unwrap();
+    assert_eq!((1000, phases[1]), (summary.generation, summary.alive));
+    pulsar.advance_generations(1001);
+    assert!(run_until(Workspace::new(pulsar), &opts, io::sink(), || false).is_err());
+}
+
+#[test]
+fn test_run_schedule() {
+    use world::Rule;
+
+    let mut opts = Options {
+        steps: Some(100),
+        goto: None,
+        stats_file: None,
+        metrics: Vec::new(),
+        csv_file: None,
+        output_file: None,
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: None,
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: Some(RuleSchedule::parse("0:B3/S23,30:B36/S23,70:B3678/S34678").unwrap()),
+        profile: false,
+    };
+    let board = World::random_seeded(42, 48, 32);
+    let mut manual = board.clone();
+    for &(until, rule) in &[(30, "B3/S23"), (70, "B36/S23"), (100, "B3678/S34678")] {
+        manual.set_rule(Rule::parse(rule).unwrap());
+        manual.advance_generations(until - manual.generation());
+    }
+    // ~ skipping ahead in chunks stops at the switches
+    let summary = run_until(Workspace::new(board.clone()), &opts, io::sink(), || false).unwrap();
+    assert_eq!((100, manual.alive()), (summary.generation, summary.alive));
+    // ~ likewise generation by generation
+    opts.exit_on_extinct = true;
+    let summary = run_until(Workspace::new(board.clone()), &opts, io::sink(), || false).unwrap();
+    assert_eq!((100, manual.alive()), (summary.generation, summary.alive));
+    assert!(summary.profile.is_none());
+    // ~ profiled: every generation timed, the outcome the same
+    opts.exit_on_extinct = false;
+    opts.profile = true;
+    let summary = run_until(Workspace::new(board), &opts, io::sink(), || false).unwrap();
+    assert_eq!((100, manual.alive()), (summary.generation, summary.alive));
+    assert_eq!(100, summary.profile.expect("no profile").generations);
+}
+
+#[test]
+fn test_run_manifest() {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::path::Path;
+    use std::process;
+    use manifest::Json;
+
+    let dir = env::temp_dir();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+    let pid = process::id();
+    let (stats, output, manifest) = (path(&format!("cgol-test-manifest-{}.csv", pid)),
+                                     path(&format!("cgol-test-manifest-{} \"ü\".cells", pid)),
+                                     path(&format!("cgol-test-manifest-{}.json", pid)));
+    let opts = Options {
+        steps: Some(3),
+        goto: None,
+        stats_file: Some(stats.clone()),
+        metrics: Vec::new(),
+        csv_file: None,
+        output_file: Some(output.clone()),
+        events_file: None,
+        keyframe_every: 100,
+        measure_period: false,
+        manifest: Some(Manifest::new(manifest.clone(),
+                                     vec![("steps".to_owned(), Json::Int(3)),
+                                          ("seed".to_owned(), Json::Int(4))])),
+        exit_on_extinct: false,
+        auto_expand: None,
+        checksum_every: None,
+        plot_width: None,
+        emit_frames: None,
+        emit_every: 1,
+        perf_log_secs: None,
+        reap: None,
+        sync_secs: io_util::DEFAULT_SYNC_SECS,
+        schedule: None,
+        profile: false,
+    };
+    let world = World::random_seeded(4, 10, 8);
+    let summary = run_until(Workspace::new(world), &opts, io::sink(), || false).unwrap();
+
+    let mut s = String::new();
+    File::open(&manifest).unwrap().read_to_string(&mut s).unwrap();
+    assert!(s.starts_with("{\"complete\":true,\"interrupted\":false,"));
+    assert!(s.contains(r#""config":{"steps":3,"seed":4},"rule":"B3/S23","width":10,"height":8,"#));
+    assert!(s.contains(&format!(r#""generation":{},"alive":{},"#,
+                                summary.generation,
+                                summary.alive)));
+    assert!(s.contains(r#""fingerprint":""#));
+    assert!(s.trim_end_matches('\n').ends_with("]}"));
+    for f in &[&stats, &output] {
+        let size = fs::metadata(f).unwrap().len();
+        let entry = Json::Object(vec![("path".to_owned(), Json::str(&f[..])),
+                                      ("size".to_owned(), Json::Int(size as i64))]);
+        assert!(s.contains(&entry.to_string()));
+    }
+    // ~ no temporary file left behind (see `io_util::temp_path`)
+    let manifest_path = Path::new(&manifest);
+    let prefix = format!(".{}.", manifest_path.file_name().unwrap().to_string_lossy());
+    assert!(fs::read_dir(manifest_path.parent().unwrap())
+                .unwrap()
+                .all(|e| !e.unwrap().file_name().to_string_lossy().starts_with(&prefix)));
+
+    for f in &[&stats, &output, &manifest] {
+        let _ = fs::remove_file(f);
+    }
+}
+
+#[cfg(unix)]
+mod sigint {
+    use std::os::raw::c_int;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SIGINT: c_int = 2;
+
+    static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    extern "C" fn on_sigint(_: c_int) {
+        if RECEIVED.fetch_add(1, Ordering::SeqCst) > 0 {
+            // ~ the second Ctrl-C; abort immediately
+            unsafe { _exit(super::EXIT_INTERRUPTED) };
+        }
+    }
+
+    pub fn install() {
+        unsafe { signal(SIGINT, on_sigint) };
+    }
+
+    pub fn received() -> bool {
+        RECEIVED.load(Ordering::SeqCst) > 0
+    }
+}
+
+#[cfg(not(unix))]
+mod sigint {
+    pub fn install() {}
+
+    pub fn received() -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_run_emit_frames() {
+    let opts = |format, every| {
+        Options {
+            steps: Some(5),
+            goto: None,
+            stats_file: None,
+            metrics: Vec::new(),
+            csv_file: None,
+            output_file: None,
+            events_file: None,
+            keyframe_every: 100,
+            measure_period: false,
+            manifest: None,
+            exit_on_extinct: false,
+            auto_expand: None,
+            checksum_every: None,
+            plot_width: None,
+            emit_frames: Some(format),
+            emit_every: every,
+            perf_log_secs: None,
+            reap: None,
+            sync_secs: io_util::DEFAULT_SYNC_SECS,
+            schedule: None,
+            profile: false,
+        }
+    };
+    let mut blinker = World::empty(5, 5);
+    for x in 1..4 {
+        blinker.set_alive(x, 2, true);
+    }
+    // ~ the initial and five advanced generations, each followed by a
+    // blank line
+    let mut out = Vec::new();
+    run_until(Workspace::new(blinker.clone()), &opts(FrameFormat::Cells, 1), &mut out, || false).unwrap();
+    let s = String::from_utf8(out).unwrap();
+    let frames: Vec<&str> = s.split_terminator("\n\n").collect();
+    assert_eq!(6, frames.len());
+    assert_eq!("!gen 0\n.\n.\n.OOO\n.\n.", frames[0]);
+    assert_eq!("!gen 1\n.\n..O\n..O\n..O\n.", frames[1]);
+    for (g, frame) in frames.iter().enumerate() {
+        assert!(frame.starts_with(&format!("!gen {}\n", g)));
+        let w = parser::parse_cells(frame).unwrap();
+        assert_eq!(3, w.alive());
+    }
+    // ~ one object per line, thinned to every other generation
+    let mut out = Vec::new();
+    run_until(Workspace::new(blinker.clone()), &opts(FrameFormat::Json, 2), &mut out, || false).unwrap();
+    let s = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = s.lines().collect();
+    assert_eq!(vec![r#"{"gen":0,"alive":3,"cells":[[1,2],[2,2],[3,2]]}"#,
+                    r#"{"gen":2,"alive":3,"cells":[[1,2],[2,2],[3,2]]}"#,
+                    r#"{"gen":4,"alive":3,"cells":[[1,2],[2,2],[3,2]]}"#],
+               lines);
+    // ~ plain grids; no steps give the initial one only
+    let mut out = Vec::new();
+    let ascii = FrameFormat::Ascii {
+        alive: '#',
+        dead: '.',
+    };
+    run_until(Workspace::new(blinker.clone()), &opts(ascii, 4), &mut out, || false).unwrap();
+    assert_eq!(".....\n.....\n.###.\n.....\n.....\n\n".repeat(2), String::from_utf8(out).unwrap());
+    let mut out = Vec::new();
+    let mut once = opts(ascii, 1);
+    once.steps = Some(0);
+    run_until(Workspace::new(blinker.clone()), &once, &mut out, || false).unwrap();
+    assert_eq!(".....\n.....\n.###.\n.....\n.....\n\n", String::from_utf8(out).unwrap());
+    // ~ the cells listed are exactly the live ones
+    let mut out = Vec::new();
+    let world = World::random_seeded(5, 12, 9);
+    let summary = run_until(Workspace::new(world.clone()), &opts(FrameFormat::Json, 5), &mut out, || false)
+                      .unwrap();
+    let mut last = world;
+    for _ in 0..5 {
+        last.advance_generation(|_, _, _| {});
+    }
+    assert_eq!(5, summary.generation);
+    let cells: Vec<String> = last.live_cells().iter().map(|&(x, y)| format!("[{},{}]", x, y)).collect();
+    let expected = format!(r#"{{"gen":5,"alive":{},"cells":[{}]}}"#, last.alive(), cells.join(","));
+    assert_eq!(Some(&expected[..]), String::from_utf8(out).unwrap().lines().nth(1));
+}
+
+#[test]
+fn test_write_diff() {
+    let mut a = World::empty(3, 3);
+    a.set_alive(1, 0, true);
+    a.set_alive(2, 2, true);
+    let mut b = World::empty(4, 3);
+    b.set_alive(1, 0, true);
+    b.set_alive(3, 1, true);
+    let mut out = Vec::new();
+    assert!(write_diff(&mut out, &a, &b).unwrap());
+    assert_eq!("size 3x3 4x3\n+ 3 1\n- 2 2\n", String::from_utf8(out).unwrap());
+
+    // ~ only the size differs
+    let mut out = Vec::new();
+    b.set_alive(3, 1, false);
+    b.set_alive(2, 2, true);
+    assert!(write_diff(&mut out, &a, &b).unwrap());
+    assert_eq!("size 3x3 4x3\n", String::from_utf8(out).unwrap());
+
+    // ~ no difference at all
+    let mut out = Vec::new();
+    assert!(!write_diff(&mut out, &a, &a.clone()).unwrap());
+    assert!(out.is_empty());
+}
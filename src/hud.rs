@@ -0,0 +1,158 @@
+//! A heads-up display of the generation number in large digits, drawn
+//! in a corner of the world's part of the screen; legible in screen
+//! recordings even if the status line is cropped.
+
+use minimap::Corner;
+use world::World;
+
+/// What the display shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Generation,
+    /// the generation and the population
+    Population,
+}
+
+impl Mode {
+    /// The mode following this one when cycling through them.
+    pub fn next(self) -> Mode {
+        match self {
+            Mode::Off => Mode::Generation,
+            Mode::Generation => Mode::Population,
+            Mode::Population => Mode::Off,
+        }
+    }
+}
+
+/// The lines of a large digit.
+pub const DIGIT_HEIGHT: usize = 5;
+
+// ~ the digits 0 to 9, three columns wide, one string per line
+const FONT: [[&'static str; DIGIT_HEIGHT]; 10] = [["███", "█ █", "█ █", "█ █", "███"],
+                                                   [" █ ", "██ ", " █ ", " █ ", "███"],
+                                                   ["███", "  █", "███", "█  ", "███"],
+                                                   ["███", "  █", "███", "  █", "███"],
+                                                   ["█ █", "█ █", "███", "  █", "  █"],
+                                                   ["███", "█  ", "███", "  █", "███"],
+                                                   ["███", "█  ", "███", "█ █", "███"],
+                                                   ["███", "  █", "  █", "  █", "  █"],
+                                                   ["███", "█ █", "███", "█ █", "███"],
+                                                   ["███", "█ █", "███", "  █", "███"]];
+
+/// The corners tried (in this order) when looking for the emptiest one.
+pub const CORNERS: [Corner; 4] = [Corner::TopRight, Corner::TopLeft, Corner::BottomRight, Corner::BottomLeft];
+
+/// Renders the given number in large digits, separated by a blank
+/// column, as `DIGIT_HEIGHT` lines of equal width.
+pub fn digits(n: usize) -> Vec<String> {
+    let n = n.to_string();
+    (0..DIGIT_HEIGHT)
+        .map(|y| {
+            let glyphs: Vec<&str> = n.bytes().map(|d| FONT[(d - b'0') as usize][y]).collect();
+            glyphs.join(" ")
+        })
+        .collect()
+}
+
+/// Renders the contents of the display: the generation in large
+/// digits, optionally followed by a line with the population, all
+/// lines padded by a blank column on either side to the same width.
+pub fn render(generation: usize, population: Option<usize>) -> Vec<String> {
+    let mut lines = digits(generation);
+    if let Some(p) = population {
+        lines.push(format!("{} alive", p));
+    }
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    lines.iter().map(|l| format!(" {:1$} ", l, width)).collect()
+}
+
+/// Determines the top left position of a box of `width` x `height`
+/// characters placed in the given corner of a screen of
+/// `screen_width` x `screen_height` characters; `None` if it does not
+/// fit.
+pub fn place(corner: Corner,
+             width: usize,
+             height: usize,
+             screen_width: usize,
+             screen_height: usize)
+             -> Option<(usize, usize)> {
+    if width > screen_width || height > screen_height {
+        return None;
+    }
+    Some(match corner {
+        Corner::TopLeft => (0, 0),
+        Corner::TopRight => (screen_width - width, 0),
+        Corner::BottomLeft => (0, screen_height - height),
+        Corner::BottomRight => (screen_width - width, screen_height - height),
+    })
+}
+
+/// Chooses the corner in which a box of `width` x `height` characters
+/// covers the fewest live cells of the world shown on a screen of
+/// `screen_width` x `screen_height` characters (one character per
+/// cell, starting with the cell `viewport`.)  `current` is kept
+/// unless another corner is strictly emptier, such that the display
+/// does not jump back and forth between equally busy corners.
+pub fn emptiest_corner(world: &World,
+                       current: Corner,
+                       width: usize,
+                       height: usize,
+                       screen_width: usize,
+                       screen_height: usize,
+                       viewport: (usize, usize))
+                       -> Corner {
+    let covered = |corner| {
+        place(corner, width, height, screen_width, screen_height)
+            .map(|(x, y)| world.count_in_rect(viewport.0 + x, viewport.1 + y, width, height))
+    };
+    let mut best = (current, covered(current));
+    for &corner in CORNERS.iter() {
+        let n = covered(corner);
+        if n.is_some() && (best.1.is_none() || n < best.1) {
+            best = (corner, n);
+        }
+    }
+    best.0
+}
+
+#[test]
+fn test_render() {
+    assert_eq!(vec!["█ █ ███", "█ █   █", "███ ███", "  █   █", "  █ ███"], digits(43));
+    assert_eq!(vec![" ███        ",
+                    " █ █        ",
+                    " █ █        ",
+                    " █ █        ",
+                    " ███        ",
+                    " 1234 alive "],
+               render(0, Some(1234)));
+    let r = render(107, Some(3));
+    assert_eq!(6, r.len());
+    assert!(r.iter().all(|l| l.chars().count() == 13));
+    assert_eq!(" 3 alive     ", r[5]);
+    assert_eq!("  █  ███ ███ ", r[0]);
+}
+
+#[test]
+fn test_emptiest_corner() {
+    let mut w = World::empty(20, 10);
+    assert_eq!(Some((15, 0)), place(Corner::TopRight, 5, 4, 20, 10));
+    assert_eq!(Some((0, 6)), place(Corner::BottomLeft, 5, 4, 20, 10));
+    assert_eq!(None, place(Corner::TopLeft, 21, 4, 20, 10));
+    // ~ an empty world keeps the current corner
+    assert_eq!(Corner::BottomLeft, emptiest_corner(&w, Corner::BottomLeft, 5, 4, 20, 10, (0, 0)));
+    // ~ busy top corners and a single cell at the bottom right
+    for x in 0..20 {
+        w.set_alive(x, 1, true);
+    }
+    w.set_alive(17, 8, true);
+    assert_eq!(Corner::BottomLeft, emptiest_corner(&w, Corner::TopRight, 5, 4, 20, 10, (0, 0)));
+    assert_eq!(Corner::BottomLeft, emptiest_corner(&w, Corner::BottomRight, 5, 4, 20, 10, (0, 0)));
+    w.set_alive(2, 7, true);
+    w.set_alive(3, 7, true);
+    assert_eq!(Corner::BottomRight, emptiest_corner(&w, Corner::BottomLeft, 5, 4, 20, 10, (0, 0)));
+    // ~ the cells shown matter only
+    assert_eq!(Corner::TopRight, emptiest_corner(&w, Corner::BottomRight, 5, 4, 20, 10, (0, 2)));
+    // ~ a box too large for the screen stays where it is
+    assert_eq!(Corner::TopLeft, emptiest_corner(&w, Corner::TopLeft, 25, 4, 20, 10, (0, 0)));
+}
@@ -0,0 +1,281 @@
+//! Writing files such that a crash (or Ctrl-C) never leaves them
+//! truncated.
+//!
+//! `atomic_write` writes a file's new content to a temporary file next
+//! to it (in the same directory, hence on the same filesystem), forces
+//! it to the disk, and only then renames it over the file: the file has
+//! either its old or its new content, never a part of the latter.  The
+//! temporary file is new and of a name unique to the write (see
+//! `temp_path`), such that writers of the same file, in one process or
+//! several, never write into (or rename) each other's.
+//!
+//! The files written bit by bit while a run goes on (the statistics and
+//! the events of a headless run) cannot be replaced as a whole; they
+//! are forced to the disk at an interval instead (see
+//! `DEFAULT_SYNC_SECS`), such that at most the last interval is lost.
+//!
+//! The binary files (worlds, event streams, bookmarks, slots) share the
+//! little endian integers read and written by the helpers below.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The interval (in seconds) at which the files written bit by bit are
+/// forced to the disk unless specified otherwise.
+pub const DEFAULT_SYNC_SECS: u64 = 10;
+
+// ~ numbers the temporary files of the process
+static TEMP_SERIAL: AtomicUsize = AtomicUsize::new(0);
+
+// ~ the symbolic links followed to the file written at most, as in
+// Linux's path resolution
+const MAX_LINKS: usize = 40;
+
+/// A temporary file the new content of the given file may be written
+/// to: the hidden `.NAME.PID.SERIAL.tmp` in the same directory, for the
+/// file's name, the process's id, and a number distinguishing the
+/// writes of the process.
+pub fn temp_path(path: &Path, serial: usize) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.{}.tmp", process::id(), serial));
+    path.with_file_name(name)
+}
+
+// ~ creates a temporary file for the given one which did not exist
+// before (e.g. left over by a crashed process of the same id)
+fn create_temp(path: &Path) -> io::Result<(PathBuf, File)> {
+    loop {
+        let tmp = temp_path(path, TEMP_SERIAL.fetch_add(1, Ordering::Relaxed));
+        match OpenOptions::new().write(true).create_new(true).open(&tmp) {
+            Ok(f) => return Ok((tmp, f)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// ~ follows the symbolic links at the given path to the file they end
+// in (which need not exist)
+fn resolve_links(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(ref m) if m.file_type().is_symlink() => {
+                // ~ a relative target is relative to the link's directory
+                let target = try!(fs::read_link(&path));
+                path = path.parent().map_or(target.clone(), |dir| dir.join(&target));
+            }
+            Ok(_) => return Ok(path),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(path),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::Other, "too many levels of symbolic links"))
+}
+
+/// Replaces the content of the file at `path` by what `write` writes
+/// (through a buffer.)  If writing fails, the file is left untouched
+/// and the temporary file removed.  The file keeps its permissions,
+/// and a symbolic link keeps pointing to it: the file the link ends in
+/// is replaced rather than the link.  The errors are those of the
+/// operating system; they are to be reported along with the path.
+pub fn atomic_write<P, F>(path: P, write: F) -> io::Result<()>
+    where P: AsRef<Path>,
+          F: FnOnce(&mut dyn Write) -> io::Result<()>
+{
+    let path = try!(resolve_links(path.as_ref()));
+    let (tmp, f) = try!(create_temp(&path));
+    let r = {
+        let kept = match fs::metadata(&path) {
+            Ok(m) => f.set_permissions(m.permissions()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        };
+        let mut out = BufWriter::new(f);
+        kept.and_then(|_| write(&mut out)).and_then(|_| out.flush()).and_then(|_| out.get_ref().sync_all())
+    };
+    if let Err(e) = r.and_then(|_| fs::rename(&tmp, &path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    sync_dir(&path);
+    Ok(())
+}
+
+// ~ forces the rename of a file in the given file's directory to the
+// disk; not all platforms and filesystems support (or need) it, hence
+// the errors are ignored
+fn sync_dir(path: &Path) {
+    if cfg!(unix) {
+        let dir = match path.parent() {
+            Some(d) if !d.as_os_str().is_empty() => d,
+            _ => Path::new("."),
+        };
+        let _ = File::open(dir).and_then(|d| d.sync_all());
+    }
+}
+
+pub(crate) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn write_u32<W: Write>(out: &mut W, n: u32) -> io::Result<()> {
+    out.write_all(&[n as u8, (n >> 8) as u8, (n >> 16) as u8, (n >> 24) as u8])
+}
+
+pub(crate) fn write_u64<W: Write>(out: &mut W, n: u64) -> io::Result<()> {
+    try!(write_u32(out, n as u32));
+    write_u32(out, (n >> 32) as u32)
+}
+
+pub(crate) fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+    let mut b = [0u8; 4];
+    try!(input.read_exact(&mut b));
+    Ok(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+}
+
+pub(crate) fn read_u64<R: Read>(input: &mut R) -> io::Result<u64> {
+    let lo = try!(read_u32(input)) as u64;
+    let hi = try!(read_u32(input)) as u64;
+    Ok(lo | hi << 32)
+}
+
+#[test]
+fn test_atomic_write() {
+    use std::env;
+
+    let name = format!("cgol-test-atomic-write-{}.txt", process::id());
+    let path = env::temp_dir().join(&name);
+    // ~ the temporary files of the test's file left behind
+    let leftovers = || {
+        fs::read_dir(env::temp_dir())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&format!(".{}.", name)))
+            .count()
+    };
+    let read = || {
+        let mut s = String::new();
+        File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+        s
+    };
+    atomic_write(&path, |out| out.write_all(b"first\n")).unwrap();
+    assert_eq!("first\n", read());
+    atomic_write(&path, |out| {
+        try!(out.write_all(b"second\n"));
+        out.write_all(b"more\n")
+    })
+    .unwrap();
+    assert_eq!("second\nmore\n", read());
+    assert_eq!(0, leftovers());
+
+    // ~ failing halfway: the file keeps its content, the temporary one
+    // is gone
+    let e = atomic_write(&path, |out| {
+                try!(out.write_all(&[b'x'; 100000]));
+                Err(io::Error::new(io::ErrorKind::Other, "disk on fire"))
+            })
+            .unwrap_err();
+    assert_eq!("disk on fire", e.to_string());
+    assert_eq!("second\nmore\n", read());
+    assert_eq!(0, leftovers());
+
+    // ~ the errors of the system, e.g. a missing directory
+    let missing = env::temp_dir().join(format!("cgol-test-no-such-dir-{}", process::id())).join("f.txt");
+    let e = atomic_write(&missing, |out| out.write_all(b"x")).unwrap_err();
+    assert_eq!(io::ErrorKind::NotFound, e.kind());
+    assert!(!missing.exists());
+    let _ = fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_atomic_write_keeps_mode_and_links() {
+    use std::env;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = env::temp_dir().join(format!("cgol-test-atomic-links-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    let (file, link, chained) = (dir.join("file.txt"), dir.join("link.txt"), dir.join("chained.txt"));
+    fs::write(&file, "old\n").unwrap();
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+    atomic_write(&file, |out| out.write_all(b"new\n")).unwrap();
+    assert_eq!(0o640, fs::metadata(&file).unwrap().permissions().mode() & 0o777);
+
+    // ~ through a relative link, and a link to it
+    symlink("file.txt", &link).unwrap();
+    symlink(&link, &chained).unwrap();
+    atomic_write(&chained, |out| out.write_all(b"linked\n")).unwrap();
+    for p in &[&link, &chained] {
+        assert!(fs::symlink_metadata(p).unwrap().file_type().is_symlink());
+    }
+    assert_eq!("linked\n", fs::read_to_string(&file).unwrap());
+    assert_eq!(0o640, fs::metadata(&file).unwrap().permissions().mode() & 0o777);
+
+    // ~ a dangling link gets its file
+    fs::remove_file(&file).unwrap();
+    atomic_write(&link, |out| out.write_all(b"created\n")).unwrap();
+    assert_eq!("created\n", fs::read_to_string(&file).unwrap());
+
+    // ~ a loop of links is refused
+    let (a, b) = (dir.join("a"), dir.join("b"));
+    symlink("b", &a).unwrap();
+    symlink("a", &b).unwrap();
+    assert!(atomic_write(&a, |out| out.write_all(b"x")).is_err());
+    let temps = fs::read_dir(&dir)
+                    .unwrap()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+                    .count();
+    assert_eq!(0, temps);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_temp_path() {
+    let pid = process::id();
+    assert_eq!(PathBuf::from(format!("dir/.a.cells.{}.7.tmp", pid)), temp_path(Path::new("dir/a.cells"), 7));
+    assert_eq!(PathBuf::from(format!(".a.cells.{}.0.tmp", pid)), temp_path(Path::new("a.cells"), 0));
+}
+
+#[test]
+fn test_atomic_write_concurrent() {
+    use std::env;
+    use std::sync::Arc;
+    use std::thread;
+
+    let path = Arc::new(env::temp_dir().join(format!("cgol-test-atomic-concurrent-{}.txt", process::id())));
+    // ~ a file merely named like the old temporary files is left alone
+    let unrelated = env::temp_dir().join(format!("cgol-test-atomic-concurrent-{}.txt.tmp", process::id()));
+    File::create(&unrelated).unwrap().write_all(b"mine\n").unwrap();
+    // ~ writers of the same file never mix their contents
+    let writers: Vec<_> = (0..4u8)
+                              .map(|i| {
+                                  let path = path.clone();
+                                  thread::spawn(move || {
+                                      let content = [b'a' + i; 50000];
+                                      for _ in 0..20 {
+                                          atomic_write(&*path, |out| out.write_all(&content)).unwrap();
+                                      }
+                                  })
+                              })
+                              .collect();
+    for w in writers {
+        w.join().unwrap();
+    }
+    let mut content = Vec::new();
+    File::open(&*path).unwrap().read_to_end(&mut content).unwrap();
+    assert_eq!(50000, content.len());
+    assert!(content.iter().all(|&c| c == content[0]));
+    let mut s = String::new();
+    File::open(&unrelated).unwrap().read_to_string(&mut s).unwrap();
+    assert_eq!("mine\n", s);
+    let _ = fs::remove_file(&*path);
+    let _ = fs::remove_file(&unrelated);
+}
@@ -0,0 +1,54 @@
+#![feature(test)]
+
+#[macro_use]
+extern crate log;
+extern crate rustbox;
+extern crate time;
+extern crate rand;
+
+#[cfg(test)]
+extern crate test;
+
+pub mod world;
+pub mod parser;
+pub mod ui;
+mod terminal;
+pub mod headless;
+pub mod events;
+pub mod metrics;
+pub mod color;
+pub mod period;
+pub mod manifest;
+pub mod counter;
+pub mod slots;
+pub mod minimap;
+pub mod patterns;
+pub mod bookmarks;
+pub mod theme;
+pub mod workspace;
+pub mod screen;
+pub mod screenshot;
+pub mod hud;
+pub mod macros;
+pub mod damage;
+pub mod clipboard;
+pub mod compare;
+pub mod schedule;
+pub mod follow;
+pub mod simulation;
+pub mod stamp;
+pub mod prompt;
+pub mod progress;
+pub mod remote;
+pub mod soup;
+pub mod bindings;
+pub mod rng;
+pub mod perf;
+pub mod reaper;
+pub mod io_util;
+pub mod text;
+pub mod runspec;
+pub mod stress;
+pub mod synthesis;
+pub mod autoframe;
+pub mod reference;
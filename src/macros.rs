@@ -0,0 +1,179 @@
+//! Recorded sequences of key presses ("macros"), kept in registers
+//! named by the letters `a` to `z` and replayed on demand.
+//!
+//! The macros can be persisted in a plain text file, one per line:
+//! the register's letter, a `=`, and the keys as typed up to the end of
+//! the line, e.g. `g=m1ss` (spaces included.)  A key pressed along with
+//! Ctrl is written as `^` followed by the key, e.g. `^l`, and `^` itself
+//! as `^^`.  Empty lines and lines starting with `#` are ignored.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use rustbox::keyboard::Key;
+
+use io_util;
+use text::TextInput;
+
+/// Tells whether the given character names a register.
+pub fn is_register(c: char) -> bool {
+    c >= 'a' && c <= 'z'
+}
+
+pub struct Macros {
+    macros: BTreeMap<char, Vec<Key>>,
+    // ~ whether a macro was recorded since the macros were created or
+    // read
+    modified: bool,
+}
+
+impl Macros {
+    pub fn new() -> Macros {
+        Macros {
+            macros: BTreeMap::new(),
+            modified: false,
+        }
+    }
+
+    /// The keys recorded in the given register, if any.
+    pub fn get(&self, register: char) -> Option<&[Key]> {
+        self.macros.get(&register).map(|keys| &keys[..])
+    }
+
+    /// Records the given keys in the given register, replacing any
+    /// keys recorded there before.
+    pub fn set(&mut self, register: char, keys: Vec<Key>) {
+        debug_assert!(is_register(register));
+        self.macros.insert(register, keys);
+        self.modified = true;
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Reads the macros from the given file; a missing file results
+    /// in no macros.
+    pub fn load_from_file(filename: &str) -> Result<Macros, String> {
+        match File::open(filename) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Macros::new()),
+            Err(e) => Err(format!("{}: {}", filename, e)),
+            Ok(f) => Macros::read_from(f).map_err(|e| format!("{}: {}", filename, e)),
+        }
+    }
+
+    /// Writes the macros to the given file.  The macros are written to
+    /// a temporary file first which is then renamed over the given one
+    /// (see `io_util::atomic_write`.)
+    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
+        io_util::atomic_write(filename, |mut out| self.write_to(&mut out))
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn read_from<R: Read>(input: R) -> io::Result<Macros> {
+        let input = try!(TextInput::read_from(input));
+        let mut macros = Macros::new();
+        for (i, line) in input.as_str().lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut chars = line.chars();
+            let (register, keys) = match (chars.next(), chars.next()) {
+                (Some(r), Some('=')) if is_register(r) => (r, chars.as_str()),
+                _ => return Err(invalid_line(i, "expected a register (a-z) followed by '='")),
+            };
+            let keys = try!(decode(keys).map_err(|e| invalid_line(i, &e)));
+            macros.macros.insert(register, keys);
+        }
+        Ok(macros)
+    }
+
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (register, keys) in &self.macros {
+            try!(writeln!(out, "{}={}", register, encode(keys)));
+        }
+        Ok(())
+    }
+}
+
+fn invalid_line(i: usize, reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, reason))
+}
+
+/// Writes the given keys the way they are stored in a file; keys
+/// other than characters (with or without Ctrl) are skipped.
+pub fn encode(keys: &[Key]) -> String {
+    let mut s = String::new();
+    for key in keys {
+        match *key {
+            Key::Char('^') => s.push_str("^^"),
+            Key::Char(c) => s.push(c),
+            Key::Ctrl(c) => {
+                s.push('^');
+                s.push(c);
+            }
+            _ => {}
+        }
+    }
+    s
+}
+
+/// Reads keys written by `encode`.
+pub fn decode(s: &str) -> Result<Vec<Key>, String> {
+    let mut keys = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        keys.push(match c {
+            '^' => {
+                match chars.next() {
+                    Some('^') => Key::Char('^'),
+                    Some(c) => Key::Ctrl(c),
+                    None => return Err("'^' at the end of the keys".to_owned()),
+                }
+            }
+            c => Key::Char(c),
+        });
+    }
+    Ok(keys)
+}
+
+#[test]
+fn test_encoding() {
+    let keys = vec![Key::Char('m'), Key::Char('1'), Key::Char(' '), Key::Ctrl('l'), Key::Char('^'), Key::Esc];
+    assert_eq!("m1 ^l^^", encode(&keys));
+    assert_eq!(Ok(keys[..5].to_vec()), decode("m1 ^l^^"));
+    assert!(decode("ss^").is_err());
+}
+
+#[test]
+fn test_read_write() {
+    let mut m = Macros::read_from(&b"# stepping\ns=sss\n\ng= ^l\n"[..]).unwrap();
+    assert!(!m.is_modified());
+    assert_eq!(Some(&[Key::Char('s'); 3][..]), m.get('s'));
+    assert_eq!(Some(&[Key::Char(' '), Key::Ctrl('l')][..]), m.get('g'));
+    assert_eq!(None, m.get('a'));
+    m.set('a', vec![Key::Char('@'), Key::Char('s')]);
+    assert!(m.is_modified());
+    let mut out = Vec::new();
+    m.write_to(&mut out).unwrap();
+    assert_eq!("a=@s\ng= ^l\ns=sss\n", String::from_utf8(out).unwrap());
+
+    for &(s, e) in [("S=s\n", "line 1: expected a register (a-z) followed by '='"),
+                    ("a=s\nbs\n", "line 2: expected a register (a-z) followed by '='"),
+                    ("a=s^\n", "line 1: '^' at the end of the keys")]
+                       .iter() {
+        assert_eq!(e, Macros::read_from(s.as_bytes()).err().unwrap().to_string());
+    }
+}
+
+#[test]
+fn test_read_fixture() {
+    use std::fs::File;
+
+    // ~ a byte order mark, CRLF, and a Latin-1 comment
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/text/macros.txt");
+    let m = Macros::read_from(File::open(path).unwrap()).unwrap();
+    assert_eq!(Some(&[Key::Char('s'); 3][..]), m.get('s'));
+    assert_eq!(Some(&[Key::Char(' '), Key::Ctrl('l')][..]), m.get('g'));
+}
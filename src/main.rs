@@ -1,21 +1,30 @@
-#![feature(test)]
-
-#[macro_use]
-extern crate log;
+extern crate cgol;
 extern crate env_logger;
 extern crate getopts;
-extern crate rustbox;
-extern crate time;
+#[macro_use]
+extern crate log;
 extern crate rand;
 
-extern crate test;
-
 use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
 use std::process;
+use std::time::Duration;
 
-mod world;
-mod parser;
-mod ui;
+use cgol::{compare, events, headless, io_util, metrics, parser, perf, reaper, remote, rng, soup, stress, ui};
+use cgol::color::ColorTier;
+use cgol::counter::Rect;
+use cgol::headless::FrameFormat;
+use cgol::manifest::{self, Json, Manifest};
+use cgol::schedule::RuleSchedule;
+use cgol::minimap::Corner;
+use cgol::parser::{Format, ParseError};
+use cgol::patterns::Pack;
+use cgol::runspec::RunSpec;
+use cgol::synthesis::Fleet;
+use cgol::theme::{self, Theme};
+use cgol::workspace::Workspace;
+use cgol::world::{self, AutoExpand, BoundaryMode, RandomKind, Rule, UpdateMode, World};
 
 fn main() {
     env_logger::init().unwrap();
@@ -33,12 +42,211 @@ fn main() {
     }
 
     let cfg = err!(Config::from_cmdline());
-    let world = match cfg.map_filename.as_ref() {
-        None => None,
-        Some(f) => Some(err!(parser::load_from_file(f))),
+    if let Some(ref f) = cfg.verify_events {
+        let input = err!(File::open(f).map_err(|e| format!("{}: {}", f, e)));
+        let v = err!(events::verify(BufReader::new(input)).map_err(|e| format!("{}: {}", f, e)));
+        println!("{}: ok ({} generations, {} keyframes)", f, v.generations, v.keyframes);
+        return;
+    }
+    if let Some((ref a, ref b)) = cfg.diff {
+        // ~ like diff(1); 0 for no differences, 1 for some, and 2 for
+        // trouble
+        let load = |f: &str| {
+            parser::load_world(f, cfg.max_line_length, cfg.allow_empty).unwrap_or_else(|e| {
+                println!("{}", load_error(f, e));
+                process::exit(2);
+            })
+        };
+        let (a, b) = (load(a), load(b));
+        let stdout = io::stdout();
+        match headless::write_diff(&mut stdout.lock(), &a, &b) {
+            Ok(false) => return,
+            Ok(true) => process::exit(1),
+            Err(e) => {
+                println!("{}", e);
+                process::exit(2);
+            }
+        }
+    }
+    if let Some((count, value, max_generations)) = cfg.soup_histogram {
+        let first_seed = cfg.seed.unwrap_or_else(rand::random);
+        let soup = |seed| {
+            let mut w = World::random_seeded_with(seed, cfg.size.0, cfg.size.1, cfg.random_kind);
+            w.set_boundary(cfg.boundary);
+            w.set_neighbourhood(cfg.neighbourhood.clone());
+            w.set_rule(cfg.rule);
+            w.set_update_mode(cfg.update_mode);
+            w
+        };
+        // ~ stdout is taken by the histogram
+        let _ = writeln!(io::stderr(),
+                         "{} of {} soups of {}x{} from seed {}",
+                         value,
+                         count,
+                         cfg.size.0,
+                         cfg.size.1,
+                         first_seed);
+        print!("{}", soup::histogram(soup, first_seed, count, max_generations, value));
+        return;
+    }
+    if let Some(ref limits) = cfg.stress {
+        // ~ implied by --stress
+        let expand = cfg.auto_expand.unwrap();
+        let mut world = stress::initial_world(&expand);
+        if world.width() * world.height() > limits.max_cells {
+            err!(Err(format!("The initial world of {}x{} cells exceeds --max-cells",
+                             world.width(),
+                             world.height())));
+        }
+        world.set_neighbourhood(cfg.neighbourhood.clone());
+        world.set_rule(cfg.rule);
+        world.set_update_mode(cfg.update_mode);
+        // ~ stdout is taken by the outcome
+        let _ = writeln!(io::stderr(),
+                         "growing the {} under {} up to {} cells for at most {}s",
+                         stress::PATTERN,
+                         cfg.rule,
+                         limits.max_cells,
+                         limits.time_limit.as_secs());
+        let outcome = err!(stress::run(&mut world, &expand, limits, io::stderr())
+                               .map_err(|e| format!("stderr: {}", e)));
+        println!("{}", outcome);
+        return;
+    }
+    if cfg.fix {
+        // ~ checked to be given along with --fix
+        let f = cfg.map_filename.as_ref().unwrap();
+        if err!(parser::fix_file(f, cfg.max_line_length).map_err(|e| format!("{}: {}", f, e))) {
+            println!("{}: fixed", f);
+        } else {
+            println!("{}: already in canonical form", f);
+        }
+        return;
+    }
+    let world = match (cfg.map_filename.as_ref(), cfg.run.as_ref()) {
+        (None, None) => None,
+        (None, Some(run)) => Some(Workspace::new(err!(run.world(cfg.size.0, cfg.size.1)))),
+        (Some(f), _) => {
+            if cfg.strict_parse {
+                err!(parser::lint_file(f, cfg.input_format).map_err(|e| format!("{}: {}", f, e)));
+            }
+            Some(err!(parser::load_workspace_as(f, cfg.input_format, cfg.max_line_length, cfg.allow_empty)
+                          .map_err(|e| load_error(f, e))))
+        }
     };
+    let world = world.map(|mut ws| {
+        ws.world.set_boundary(cfg.boundary);
+        ws.world.set_neighbourhood(cfg.neighbourhood.clone());
+        ws.world.set_rule(cfg.rule);
+        ws.world.set_update_mode(cfg.update_mode);
+        ws
+    });
 
-    if let Err(e) = ui::run(world, cfg.alive_char, cfg.dead_char) {
+    if let Some(ref opts) = cfg.headless {
+        let mut ws = match (world, cfg.seed) {
+            (Some(ws), _) => ws,
+            (None, Some(seed)) => {
+                Workspace::new(match cfg.fleet {
+                    Some((fleet, n)) => fleet.world(n, seed, cfg.size.0, cfg.size.1),
+                    None => World::random_seeded_with(seed, cfg.size.0, cfg.size.1, cfg.random_kind),
+                })
+            }
+            (None, None) => {
+                Workspace::new(World::random_with(&mut rand::thread_rng(),
+                                                  cfg.size.0,
+                                                  cfg.size.1,
+                                                  cfg.random_kind))
+            }
+        };
+        ws.world.set_boundary(cfg.boundary);
+        ws.world.set_neighbourhood(cfg.neighbourhood.clone());
+        ws.world.set_rule(cfg.rule);
+        ws.world.set_update_mode(cfg.update_mode);
+        err!(insert_patterns(&mut ws.world, &cfg));
+        if let Some(max) = cfg.presettle {
+            let settled = soup::presettle(&mut ws.world, max);
+            // ~ stdout may be taken by the frames
+            let _ = writeln!(io::stderr(), "{}", soup::describe_presettle(settled, max));
+        }
+        let summary = err!(headless::run(ws, opts));
+        let mut report = String::new();
+        if opts.exit_on_extinct && summary.extinct {
+            report.push_str(&format!("extinct at generation {}\n", summary.generation));
+        }
+        report.push_str(&format!("{}\n", summary));
+        if opts.emit_frames.is_some() || opts.csv_file.as_ref().map_or(false, |f| f == "-") {
+            // ~ stdout is taken by the frames or the population history
+            let _ = io::stderr().write_all(report.as_bytes());
+        } else {
+            print!("{}", report);
+        }
+        if let Some(ref p) = summary.profile {
+            let _ = writeln!(io::stderr(), "{}", p);
+        }
+        process::exit(summary.exit_code());
+    }
+
+    let opts = ui::Options {
+        seed: cfg.seed,
+        random_kind: cfg.random_kind,
+        boundary: cfg.boundary,
+        alive_char: cfg.alive_char,
+        dead_char: cfg.dead_char,
+        color_tier: cfg.color_tier,
+        theme: cfg.theme,
+        show_status: cfg.show_status,
+        density_color: cfg.density_color,
+        auto_expand: cfg.auto_expand,
+        neighbourhood: cfg.neighbourhood.clone(),
+        rule: cfg.rule,
+        update_mode: cfg.update_mode,
+        count_region: cfg.count_region,
+        slots_file: cfg.slots_file.clone(),
+        macros_file: cfg.macros_file.clone(),
+        macro_delay_ms: cfg.macro_delay_ms,
+        minimap_corner: cfg.minimap_corner,
+        file: cfg.map_filename.clone(),
+        pack: cfg.pack.clone(),
+        max_line_length: cfg.max_line_length,
+        allow_empty: cfg.allow_empty,
+        bookmarks_file: cfg.bookmarks_file.clone(),
+        slowmo_bounds: cfg.slowmo_bounds,
+        statlog_file: cfg.statlog_file.clone(),
+        hud_corner: cfg.hud_corner,
+        listen: cfg.listen.clone(),
+        read_only: cfg.read_only,
+        autoframe: cfg.autoframe,
+        screenshot_on_exit: cfg.screenshot_on_exit.clone(),
+        compare: cfg.compare,
+        schedule: cfg.schedule.clone(),
+        perf_log_secs: cfg.perf_log_secs,
+        reap: cfg.reap,
+        presettle: cfg.presettle,
+        reference: cfg.reference.as_ref().map(|f| {
+            err!(parser::load_world(f, cfg.max_line_length, cfg.allow_empty).map_err(|e| load_error(f, e)))
+        }),
+        fleet: cfg.fleet,
+        // ~ confusing, but not worth refusing to run
+        message: {
+            let mut warnings = ui::glyph_collisions(cfg.alive_char, cfg.dead_char);
+            warnings.extend(cfg.theme_warning.clone());
+            if warnings.is_empty() {
+                None
+            } else {
+                Some(format!("warning: {}", warnings.join("; ")))
+            }
+        },
+    };
+    let r = if cfg.demo {
+        ui::run_demo(&opts)
+    } else {
+        let mut world = world;
+        if let Some(ref mut ws) = world {
+            err!(insert_patterns(&mut ws.world, &cfg));
+        }
+        ui::run(world, &opts)
+    };
+    if let Err(e) = r {
         println!("{}", e);
         process::exit(1);
     }
@@ -46,8 +254,76 @@ fn main() {
 
 struct Config {
     map_filename: Option<String>,
+    input_format: Option<Format>,
+    pack: Option<String>,
+    max_line_length: usize,
+    allow_empty: bool,
+    strict_parse: bool,
+    fix: bool,
+    seed: Option<u64>,
+    random_kind: RandomKind,
+    size: (usize, usize),
+    boundary: BoundaryMode,
+    auto_expand: Option<AutoExpand>,
+    neighbourhood: Vec<(isize, isize)>,
+    rule: Rule,
+    update_mode: UpdateMode,
+    count_region: Option<Rect>,
+    slots_file: Option<String>,
+    macros_file: Option<String>,
+    macro_delay_ms: u64,
+    bookmarks_file: Option<String>,
+    slowmo_bounds: (i64, i64),
+    statlog_file: Option<String>,
+    hud_corner: Option<Corner>,
+    minimap_corner: Corner,
+    headless: Option<headless::Options>,
+    // ~ the patterns to place onto the world before it starts; the
+    // files along with the positions of their top left cells
+    inserts: Vec<(String, isize, isize)>,
+    // ~ the rule and the pattern to start from instead of a file
+    run: Option<RunSpec>,
+    // ~ the ships to seed an empty world with instead of random cells
+    fleet: Option<(Fleet, usize)>,
+    // ~ whether inserts clipped at the edges of a bounded world are an
+    // error
+    strict_insert: bool,
+    // ~ the address to listen for a remote control at
+    listen: Option<String>,
+    // ~ whether to refuse the keys changing the world
+    read_only: bool,
+    // ~ whether to keep the live cells in view
+    autoframe: bool,
+    // ~ the file to write the screen to on exit, if any
+    screenshot_on_exit: Option<String>,
+    // ~ the rule to run the world under alongside, if any
+    compare: Option<Rule>,
+    // ~ the generations at which to switch the rule, if any
+    schedule: Option<RuleSchedule>,
+    // ~ the interval to log the performance counters at
+    perf_log_secs: Option<u64>,
+    // ~ the policy of removing stable debris
+    reap: Option<reaper::Policy>,
+    // ~ the generations to settle the initial world for at most
+    presettle: Option<usize>,
+    // ~ the file of the pattern to show behind the world, if any
+    reference: Option<String>,
+    // ~ the number of soups to run, what to count of them, and the
+    // generations after which to give up on one
+    soup_histogram: Option<(usize, soup::Value, usize)>,
+    // ~ when to end growing a world as fast as possible
+    stress: Option<stress::Limits>,
+    verify_events: Option<String>,
+    diff: Option<(String, String)>,
+    demo: bool,
     alive_char: char,
     dead_char: char,
+    color_tier: ColorTier,
+    theme: Theme,
+    // ~ why the theme file was not used, if it was not
+    theme_warning: Option<String>,
+    show_status: bool,
+    density_color: bool,
 }
 
 impl Config {
@@ -64,8 +340,161 @@ impl Config {
         let mut opts = getopts::Options::new();
         opts.optflag("h", "help", "print this help screen");
         opts.optopt("f", "file", "load map from FILE", "FILE");
+        opts.optopt("", "format", "read the file given with --file in FORMAT (cells, binary or \
+                                   coords) regardless of its extension and content", "FORMAT");
+        opts.optopt("", "pack", "browse the patterns of DIR (listed by its index.txt, if any) \
+                                 with [ and ]", "DIR");
+        opts.optopt("", "pattern", "load the pattern NAME of the pack", "NAME");
+        opts.optopt("", "max-line-length", "reject plaintext files with lines longer than N \
+                                            characters (default: 100000)", "N");
+        opts.optflag("", "allow-empty", "accept plaintext files without live cells as an empty \
+                                         world of the pattern's size");
+        opts.optflag("", "strict-parse", "reject plaintext files not in canonical form, listing all \
+                                          formatting problems");
+        opts.optflag("", "fix", "rewrite the plaintext file given with --file in canonical form \
+                                 and exit");
+        opts.optopt("", "seed", "generate the random world from SEED", "SEED");
+        opts.optopt("", "random-kind", "distribute the live cells of random worlds uniformly \
+                                        (default) or in blobs of N cells wide (clustered[:N])",
+                    "KIND");
+        opts.optopt("", "twist", "wrap the world's edges with an offset of DX columns \
+                                 (and DY rows)", "DX[,DY]");
+        opts.optflag("", "bounded", "do not wrap the world's edges");
+        opts.optopt("", "neighbors", "count the cells at the given offsets as neighbours \
+                                      (default: the eight surrounding cells); a cell with more \
+                                      than eight live ones is neither born nor survives", "DX,DY;...");
+        opts.optopt("", "rule", "the numbers of live neighbours at which a cell is born and \
+                                 survives (default: B3/S23)", "B.../S...");
+        opts.optopt("", "compare", "run the world under RULE1 and alongside under RULE2, coloring the \
+                                    cells alive under one of them only", "RULE1,RULE2");
+        opts.optopt("", "rule-schedule", "switch the rule at the given generations, starting with the \
+                                          one at 0, e.g. 0:B3/S23,500:B36/S23", "GEN:RULE,...");
+        opts.optopt("", "run", "start an empty world (of --size) with PATTERN, a bundled pattern's \
+                                name or rows of . and O separated by $, under RULE; with its top \
+                                left cell at X,Y or centered, e.g. B3/S23:glider@40,20",
+                    "RULE:PATTERN[@X,Y]");
+        opts.optopt("", "gliders", "start an empty world with N gliders placed at random (seeded by \
+                                    --seed) near its edges, heading for its center", "N");
+        opts.optopt("", "spaceships", "like --gliders, with a random mix of gliders and lightweight \
+                                       spaceships", "N");
+        opts.optopt("", "update-mode", "update the cells all at once (default), or one at a time \
+                                        in a random order (seeded by --seed) or row by row; \
+                                        async-random:P updates only P percent of the cells",
+                    "sync|async-random[:P]|async-row-major");
+        opts.optopt("", "count-region", "count the objects (e.g. gliders) passing the region of \
+                                         the given position and size", "X,Y,W,H");
+        opts.optopt("", "macros-file", "read the recorded macros from FILE at startup and write \
+                                        them back on exit", "FILE");
+        opts.optopt("", "macro-delay", "pause MS milliseconds before each key of a replayed \
+                                        macro (default: 0)", "MS");
+        opts.optopt("", "slots-file", "read the save slots from FILE at startup and write them \
+                                       back on exit", "FILE");
+        opts.optopt("", "bookmarks-file", "read the bookmarks of a previous run of the same world \
+                                           from FILE at startup and write them back on exit", "FILE");
+        opts.optopt("", "slowmo-delay", "bounds of the delay (in milliseconds) of the adaptive \
+                                         slow motion (default: 20,1000)", "MIN,MAX");
+        opts.optopt("", "statlog", "append the statistics of the displayed generation to FILE \
+                                    on pressing L", "FILE");
+        opts.optopt("", "minimap-corner", "show the mini-map in the given corner (default: \
+                                           top-right)", "top-left|top-right|bottom-left|bottom-right");
+        opts.optopt("", "hud-corner", "show the large generation display (see H) in the given \
+                                       corner (default: auto, the one with the fewest live \
+                                       cells)", "auto|top-left|top-right|bottom-left|bottom-right");
+        opts.optflag("", "auto-expand", "grow the (bounded) world whenever live cells come close \
+                                         to its edges");
+        opts.optopt("", "expand-by", "grow an auto-expanding world by N cells at once \
+                                      (default: 16)", "N");
+        opts.optopt("", "max-size", "do not grow an auto-expanding world beyond WxH", "WxH");
+        opts.optflag("", "headless", "advance the world without a user interface");
+        opts.optopt("", "steps", "stop a headless run after N generations", "N");
+        opts.optopt("", "goto", "advance a headless run up to generation N as fast as possible \
+                                 (instead of --steps)", "N");
+        opts.optopt("", "size", "size of a headless random world or of the world of --run \
+                                 (default: 80x25)", "WxH");
+        opts.optopt("", "stats", "write per generation statistics of a headless run to FILE as CSV", "FILE");
+        opts.optopt("", "metrics", "record additional METRICS in the statistics, e.g. \
+                                    entropy:3,density,activity,births", "METRICS");
+        opts.optopt("", "csv", "write the population, births, deaths and density of every \
+                                generation of a headless run of --steps to FILE as CSV (- for \
+                                stdout)", "FILE");
+        opts.optopt("", "output", "write the final world of a headless run to FILE", "FILE");
+        opts.optopt("", "export-events", "write the changes of a headless run to FILE", "FILE");
+        opts.optflag("", "exit-on-extinct", "stop a headless run once all cells are dead");
+        opts.optopt("", "manifest", "write a JSON record of a headless run to FILE (default: \
+                                     run-manifest.json if any output is written)", "FILE");
+        opts.optopt("", "keyframe-every", "write a keyframe every N generations to the exported \
+                                           events (default: 100)", "N");
+        opts.optopt("", "sync-every", "force the statistics and the events of a headless run to \
+                                       the disk every SECS seconds (default: 10)", "SECS");
+        opts.optopt("", "checksum-every", "print a checksum of the world every N generations of \
+                                           a headless run", "N");
+        opts.optflag("", "plot-population", "print a chart of the population of a headless run \
+                                             at its end");
+        opts.optopt("", "emit-frames", "write every generation of a headless run to stdout as it \
+                                        is computed", "cells|json");
+        opts.optflag("", "scroll", "run headless, printing every generation to stdout as a plain \
+                                    grid of --alive-char and --dead-char, e.g. to page through \
+                                    with less");
+        opts.optopt("", "emit-every", "write only every Nth generation with --emit-frames \
+                                       (default: 1)", "N");
+        opts.optflag("", "measure-period", "report the period and displacement of the final world \
+                                            of a headless run");
+        opts.optflag("", "profile", "print to stderr where the time of a headless run went: counting \
+                                     the neighbours, applying the changes or reporting the changed \
+                                     cells");
+        opts.optflagopt("", "presettle", "run the loaded or generated world until it settles, but \
+                                          for at most N generations (default: 10000), and start \
+                                          from there as generation 0", "N");
+        opts.optflag("", "diff", "print the cells alive in only one of the two given pattern \
+                                  files; exits with 1 if their sizes or any cells differ");
+        opts.optopt("", "soup-histogram", "run N random worlds of --size seeded from --seed on until \
+                                           they settle (at most --steps generations, default: 10000) \
+                                           and print a histogram of their final populations", "N");
+        opts.optflag("", "stress", "grow a world from a small exploding pattern (see --auto-expand, \
+                                    --expand-by and --max-size) as fast as possible, printing the \
+                                    generation, size and time to stderr every second, until it \
+                                    reaches --max-cells or --time-limit");
+        opts.optopt("", "max-cells", "stop --stress once the world would grow beyond N cells \
+                                      (default: 16777216)", "N");
+        opts.optopt("", "time-limit", "stop --stress after SECS seconds (default: 60)", "SECS");
+        opts.optopt("", "soup-value", "what --soup-histogram counts: population (default) or \
+                                       generations (until settled)", "VALUE");
+        opts.optmulti("", "insert", "place the live cells of the pattern FILE with its top left \
+                                     cell at X,Y, wrapping around the edges unless --bounded; \
+                                     may be given repeatedly", "FILE@X,Y");
+        opts.optflag("", "strict-insert", "refuse patterns given with --insert extending beyond \
+                                           the edges of a --bounded world instead of clipping them");
+        opts.optflag("", "demo", "cycle through a few bundled patterns until a key is pressed");
+        opts.optopt("", "verify-events", "verify the consistency of the events in FILE", "FILE");
         opts.optopt("", "alive-char", "character to represent alive cells with", "C");
         opts.optopt("", "dead-char", "character to represent dead cells with", "C");
+        opts.optopt("", "fg", "color of alive cells; a name (e.g. green) or a palette index \
+                               0-7", "COLOR");
+        opts.optopt("", "bg", "background color of the world", "COLOR");
+        opts.optopt("", "theme-file", "read the colors of the cells by their state and number of \
+                                       live neighbours from FILE; --fg and --bg take precedence", "FILE");
+        opts.optflag("", "no-status", "hide the status line");
+        opts.optflag("", "density-color", "color live cells by their number of live neighbours");
+        opts.optflagopt("", "perf-log", "log the performance counters every SECS seconds \
+                                         (default: 10)", "SECS");
+        opts.optflagopt("", "reap", "remove the stable debris far from any activity; SETTINGS like \
+                                     every=500,age=200,distance=30 (the defaults)", "SETTINGS");
+        opts.optopt("", "reference", "show the pattern in FILE dimly behind the world, e.g. to \
+                                      recreate it cell by cell (toggle with o)", "FILE");
+        opts.optflag("", "read-only", "refuse the keys changing the world, e.g. for observers of a \
+                                       shared terminal; typing !unlock allows them again");
+        opts.optflag("", "autoframe", "keep the live cells of a world larger than the screen in view \
+                                       as they move (toggle with F)");
+        opts.optopt("", "screenshot-on-exit", "write the screen as shown when quitting to FILE (with ANSI \
+                                               colors if FILE ends with .ans)", "FILE");
+        if cfg!(feature = "truecolor") {
+            opts.optflag("", "truecolor", "use 24-bit colors even if the terminal does not \
+                                           announce support for them");
+        }
+        if cfg!(feature = "remote") {
+            opts.optopt("", "listen", "accept remote control commands (JSON lines) over TCP at ADDR; \
+                                       a port alone listens on 127.0.0.1", "ADDR");
+        }
         let m = match opts.parse(&args) {
             Ok(m) => m,
             Err(e) => {
@@ -75,13 +504,757 @@ impl Config {
         if m.opt_present("h") {
             return Err(Config::cmdline_usage(&pname, opts));
         }
-        if !m.free.is_empty() {
+        let diff = if m.opt_present("diff") {
+            if m.free.len() != 2 {
+                return Err("--diff expects two files".to_owned());
+            }
+            Some((m.free[0].clone(), m.free[1].clone()))
+        } else if !m.free.is_empty() {
             return Err("No arguments expected!".to_owned());
+        } else {
+            None
+        };
+        if m.opt_present("demo") {
+            let others = ["file", "pattern", "pack", "headless", "scroll", "diff", "verify-events",
+                          "soup-histogram", "insert", "run", "gliders", "spaceships", "presettle",
+                          "reference"];
+            for &o in others.iter() {
+                if m.opt_present(o) {
+                    return Err(format!("--demo and --{} are mutually exclusive", o));
+                }
+            }
         }
+        let run = match m.opt_str("run") {
+            None => None,
+            Some(s) => {
+                for &o in ["file", "pattern", "rule", "soup-histogram"].iter() {
+                    if m.opt_present(o) {
+                        return Err(format!("--run and --{} are mutually exclusive", o));
+                    }
+                }
+                Some(try!(RunSpec::parse(&s)))
+            }
+        };
+        let fleet = match (m.opt_str("gliders"), m.opt_str("spaceships")) {
+            (None, None) => None,
+            (Some(_), Some(_)) => return Err("--gliders and --spaceships are mutually exclusive".to_owned()),
+            (gliders, spaceships) => {
+                let (fleet, name) = if gliders.is_some() {
+                    (Fleet::Gliders, "gliders")
+                } else {
+                    (Fleet::Spaceships, "spaceships")
+                };
+                for &o in ["file", "pattern", "run", "soup-histogram", "stress"].iter() {
+                    if m.opt_present(o) {
+                        return Err(format!("--{} and --{} are mutually exclusive", name, o));
+                    }
+                }
+                let s = gliders.or(spaceships).unwrap();
+                match s.parse() {
+                    Ok(n) if n > 0 => Some((fleet, n)),
+                    _ => return Err(format!("Invalid number of {}: {}", name, s)),
+                }
+            }
+        };
+        // ~ scrolling is a headless run writing plain frames
+        let headless_mode = m.opt_present("headless") || m.opt_present("scroll");
+        let listen = if cfg!(feature = "remote") {
+            m.opt_str("listen").map(|a| remote::address(&a))
+        } else {
+            None
+        };
+        if listen.is_some() && (headless_mode || m.opt_present("demo")) {
+            return Err("--listen requires the interactive mode".to_owned());
+        }
+        if m.opt_present("read-only") && headless_mode {
+            return Err("--read-only requires the interactive mode".to_owned());
+        }
+        if m.opt_present("autoframe") && headless_mode {
+            return Err("--autoframe requires the interactive mode".to_owned());
+        }
+        if m.opt_present("screenshot-on-exit") && headless_mode {
+            return Err("--screenshot-on-exit requires the interactive mode".to_owned());
+        }
+        if m.opt_present("compare") && headless_mode {
+            return Err("--compare requires the interactive mode".to_owned());
+        }
+        if m.opt_present("reference") && headless_mode {
+            return Err("--reference requires the interactive mode".to_owned());
+        }
+        if m.opt_present("profile") && !headless_mode {
+            return Err("--profile requires --headless".to_owned());
+        }
+        let soup_histogram = match m.opt_str("soup-histogram") {
+            None if m.opt_present("soup-value") => {
+                return Err("--soup-value requires --soup-histogram".to_owned())
+            }
+            None => None,
+            Some(_) if headless_mode || m.opt_present("file") || m.opt_present("pattern") ||
+                       m.opt_present("insert") => {
+                let msg = "--soup-histogram cannot be combined with --headless, --scroll, --file, --pattern \
+                           or --insert";
+                return Err(msg.to_owned());
+            }
+            Some(s) => {
+                let count = match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid number of soups: {}", s)),
+                };
+                let value = match m.opt_str("soup-value") {
+                    None => soup::Value::Population,
+                    Some(s) => {
+                        try!(soup::Value::parse(&s).ok_or_else(|| format!("Invalid soup value: {}", s)))
+                    }
+                };
+                let max_generations = match m.opt_str("steps") {
+                    None => soup::DEFAULT_MAX_GENERATIONS,
+                    Some(s) => try!(s.parse().map_err(|_| format!("Invalid number of steps: {}", s))),
+                };
+                Some((count, value, max_generations))
+            }
+        };
+        let stress = if m.opt_present("stress") {
+            let others = ["headless", "scroll", "demo", "file", "pattern", "run", "insert", "soup-histogram",
+                          "diff", "verify-events", "presettle"];
+            for &o in others.iter() {
+                if m.opt_present(o) {
+                    return Err(format!("--stress and --{} are mutually exclusive", o));
+                }
+            }
+            let max_cells = match m.opt_str("max-cells") {
+                None => stress::DEFAULT_MAX_CELLS,
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid number of cells: {}", s)),
+                },
+            };
+            let time_limit = match m.opt_str("time-limit") {
+                None => stress::DEFAULT_TIME_LIMIT_SECS,
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid time limit: {}", s)),
+                },
+            };
+            Some(stress::Limits {
+                max_cells: max_cells,
+                time_limit: Duration::from_secs(time_limit),
+                report_every: Duration::from_secs(stress::REPORT_SECS),
+            })
+        } else if m.opt_present("max-cells") || m.opt_present("time-limit") {
+            return Err("--max-cells and --time-limit require --stress".to_owned());
+        } else {
+            None
+        };
+        let seed = match m.opt_str("seed") {
+            // ~ make random headless runs reproducible
+            None if headless_mode && !m.opt_present("file") && !m.opt_present("pattern") &&
+                    run.is_none() => {
+                Some(rand::random())
+            }
+            None => None,
+            Some(s) => match s.parse() {
+                Ok(seed) => Some(seed),
+                Err(_) => return Err(format!("Invalid seed: {}", s)),
+            },
+        };
+        let max_line_length = match m.opt_str("max-line-length") {
+            None => parser::DEFAULT_MAX_LINE_LENGTH,
+            Some(s) => match s.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(format!("Invalid line length: {}", s)),
+            },
+        };
+        let map_filename = match (m.opt_str("file"), m.opt_str("pattern")) {
+            (Some(_), Some(_)) => return Err("--file and --pattern are mutually exclusive".to_owned()),
+            (f, None) => f,
+            (None, Some(name)) => {
+                let dir = try!(m.opt_str("pack").ok_or("--pattern requires --pack".to_owned()));
+                let pack = try!(Pack::open(&dir, max_line_length, m.opt_present("allow-empty")));
+                let i = try!(pack.find(&name).ok_or_else(|| format!("{}: no such pattern: {}", dir, name)));
+                Some(pack.path(i).to_string_lossy().into_owned())
+            }
+        };
+        let inserts: Vec<_> = try!(m.opt_strs("insert")
+                                     .iter()
+                                     .map(|s| parse_insert(s).ok_or_else(|| format!("Invalid insert: {}", s)))
+                                     .collect());
+        // ~ the random world of the interactive mode is generated only
+        // once it knows the terminal's size
+        if !inserts.is_empty() && !headless_mode && map_filename.is_none() && run.is_none() {
+            return Err("--insert requires --file, --run or --headless".to_owned());
+        }
+        if m.opt_present("strict-insert") && inserts.is_empty() {
+            return Err("--strict-insert requires --insert".to_owned());
+        }
+        let input_format = match m.opt_str("format") {
+            None => None,
+            Some(s) => Some(try!(Format::parse(&s).ok_or_else(|| format!("Invalid format: {}", s)))),
+        };
+        if m.opt_present("fix") {
+            match map_filename {
+                None => return Err("--fix requires --file".to_owned()),
+                Some(ref f) if input_format.or_else(|| Format::from_filename(f))
+                                           .map_or(false, |f| f != Format::Cells) => {
+                    return Err("--fix applies to plaintext files only".to_owned())
+                }
+                Some(_) => {}
+            }
+        }
+        let random_kind = match m.opt_str("random-kind") {
+            None => RandomKind::Uniform,
+            Some(s) => try!(parse_random_kind(&s).ok_or_else(|| format!("Invalid random kind: {}", s))),
+        };
+        let size = match m.opt_str("size") {
+            None => (80, 25),
+            Some(s) => try!(parse_size(&s).ok_or_else(|| format!("Invalid size: {}", s))),
+        };
+        // ~ growing the world is what --stress is about
+        let auto_expand = if m.opt_present("auto-expand") || stress.is_some() {
+            let increment = match m.opt_str("expand-by") {
+                None => 16,
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid expansion increment: {}", s)),
+                },
+            };
+            let max_size = match m.opt_str("max-size") {
+                None => None,
+                Some(s) => Some(try!(parse_size(&s).ok_or_else(|| format!("Invalid size: {}", s)))),
+            };
+            Some(AutoExpand {
+                margin: 2,
+                increment: increment,
+                max_size: max_size,
+            })
+        } else {
+            None
+        };
+        let boundary = match m.opt_str("twist") {
+            Some(_) if auto_expand.is_some() => {
+                return Err("--twist and --auto-expand are mutually exclusive".to_owned())
+            }
+            None if m.opt_present("bounded") || auto_expand.is_some() => BoundaryMode::Bounded,
+            None => BoundaryMode::Wrap,
+            Some(_) if m.opt_present("bounded") => {
+                return Err("--twist and --bounded are mutually exclusive".to_owned())
+            }
+            Some(s) => {
+                let (dx, dy) = try!(parse_twist(&s).ok_or_else(|| format!("Invalid twist: {}", s)));
+                BoundaryMode::Twisted { dx: dx, dy: dy }
+            }
+        };
+        let neighbourhood = match m.opt_str("neighbors") {
+            None => world::NEIGHBOURS.to_vec(),
+            Some(s) => {
+                try!(parse_neighbourhood(&s).ok_or_else(|| format!("Invalid neighbourhood: {}", s)))
+            }
+        };
+        let compare = match m.opt_str("compare") {
+            None => None,
+            Some(s) => {
+                // ~ the ghost neither grows nor gets reaped along with the
+                // world
+                for &o in ["rule", "run", "auto-expand", "reap"].iter() {
+                    if m.opt_present(o) {
+                        return Err(format!("--compare and --{} are mutually exclusive", o));
+                    }
+                }
+                Some(try!(compare::parse_rules(&s).ok_or_else(|| format!("Invalid rules: {}", s))))
+            }
+        };
+        let schedule = match m.opt_str("rule-schedule") {
+            None => None,
+            Some(s) => {
+                // ~ a presettled world would start over at generation 0
+                // under the rule of a later one
+                for &o in ["rule", "run", "compare", "presettle"].iter() {
+                    if m.opt_present(o) {
+                        return Err(format!("--rule-schedule and --{} are mutually exclusive", o));
+                    }
+                }
+                Some(try!(RuleSchedule::parse(&s)))
+            }
+        };
+        let rule = match (m.opt_str("rule"), compare, schedule.as_ref()) {
+            (_, _, Some(schedule)) => *schedule.rule_at(0),
+            (_, Some((first, _)), None) => first,
+            (None, None, None) => run.as_ref().map_or(world::CONWAY, |r| r.rule),
+            (Some(s), None, None) => try!(Rule::parse(&s).ok_or_else(|| format!("Invalid rule: {}", s))),
+        };
+        let update_mode = match m.opt_str("update-mode") {
+            None => UpdateMode::Synchronous,
+            Some(s) => {
+                try!(parse_update_mode(&s, seed.unwrap_or_else(rand::random))
+                         .ok_or_else(|| format!("Invalid update mode: {}", s)))
+            }
+        };
+        let count_region = match m.opt_str("count-region") {
+            None => None,
+            Some(s) => Some(try!(Rect::parse(&s).ok_or_else(|| format!("Invalid region: {}", s)))),
+        };
+        // ~ an unusable theme file is not worth refusing to run
+        let (mut theme, theme_warning) = match m.opt_str("theme-file") {
+            None => (Theme::default(), None),
+            Some(f) => match Theme::load_from_file(&f) {
+                Ok(theme) => (theme, None),
+                Err(e) => (Theme::default(), Some(format!("{}; using the default colors", e))),
+            },
+        };
+        if let Some(s) = m.opt_str("fg") {
+            theme.fg = try!(theme::parse_color(&s));
+        }
+        if let Some(s) = m.opt_str("bg") {
+            theme.bg = try!(theme::parse_color(&s));
+        }
+        let slowmo_bounds = match m.opt_str("slowmo-delay") {
+            None => ui::DEFAULT_SLOWMO_BOUNDS,
+            Some(s) => try!(parse_delay_bounds(&s).ok_or_else(|| format!("Invalid delay bounds: {}", s))),
+        };
+        let minimap_corner = match m.opt_str("minimap-corner") {
+            None => Corner::TopRight,
+            Some(s) => try!(parse_corner(&s).ok_or_else(|| format!("Invalid corner: {}", s))),
+        };
+        let macro_delay_ms = match m.opt_str("macro-delay") {
+            None => 0,
+            Some(s) => try!(s.parse().map_err(|_| format!("Invalid macro delay: {}", s))),
+        };
+        let hud_corner = match m.opt_str("hud-corner") {
+            None => None,
+            Some(ref s) if s == "auto" => None,
+            Some(s) => Some(try!(parse_corner(&s).ok_or_else(|| format!("Invalid corner: {}", s)))),
+        };
+        let alive_char = m.opt_str("alive-char").and_then(|s| s.chars().next()).unwrap_or('O');
+        let dead_char = m.opt_str("dead-char").and_then(|s| s.chars().next()).unwrap_or(' ');
+        let perf_log_secs = if m.opt_present("perf-log") {
+            match m.opt_str("perf-log") {
+                None => Some(perf::DEFAULT_LOG_SECS),
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("Invalid interval: {}", s)),
+                },
+            }
+        } else {
+            None
+        };
+        let reap = if m.opt_present("reap") {
+            Some(try!(reaper::Policy::parse(&m.opt_str("reap").unwrap_or_default())))
+        } else {
+            None
+        };
+        let presettle = if m.opt_present("presettle") {
+            match m.opt_str("presettle") {
+                None => Some(soup::DEFAULT_MAX_GENERATIONS),
+                Some(s) => match s.parse() {
+                    Ok(n) => Some(n),
+                    Err(_) => return Err(format!("Invalid number of generations: {}", s)),
+                },
+            }
+        } else {
+            None
+        };
+        let headless = if headless_mode {
+            let steps = match m.opt_str("steps") {
+                None => None,
+                Some(s) => match s.parse() {
+                    Ok(n) => Some(n),
+                    Err(_) => return Err(format!("Invalid number of steps: {}", s)),
+                },
+            };
+            let goto = match m.opt_str("goto") {
+                None => None,
+                Some(_) if steps.is_some() => {
+                    return Err("--goto and --steps are mutually exclusive".to_owned())
+                }
+                Some(s) => match s.parse() {
+                    Ok(n) => Some(n),
+                    Err(_) => return Err(format!("Invalid generation: {}", s)),
+                },
+            };
+            let keyframe_every = match m.opt_str("keyframe-every") {
+                None => 100,
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid keyframe interval: {}", s)),
+                },
+            };
+            let sync_secs = match m.opt_str("sync-every") {
+                None => io_util::DEFAULT_SYNC_SECS,
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid interval: {}", s)),
+                },
+            };
+            let checksum_every = match m.opt_str("checksum-every") {
+                None => None,
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("Invalid checksum interval: {}", s)),
+                },
+            };
+            let emit_frames = match m.opt_str("emit-frames") {
+                None if m.opt_present("scroll") => {
+                    Some(FrameFormat::Ascii {
+                        alive: alive_char,
+                        dead: dead_char,
+                    })
+                }
+                None => None,
+                Some(_) if m.opt_present("scroll") => {
+                    return Err("--scroll and --emit-frames are mutually exclusive".to_owned())
+                }
+                Some(s) => Some(try!(parse_frame_format(&s).ok_or_else(|| format!("Invalid frame format: {}", s)))),
+            };
+            let emit_every = match m.opt_str("emit-every") {
+                None => 1,
+                Some(_) if emit_frames.is_none() => return Err("--emit-every requires --emit-frames".to_owned()),
+                Some(s) => match s.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid frame interval: {}", s)),
+                },
+            };
+            if emit_frames.is_some() && (checksum_every.is_some() || m.opt_present("plot-population")) {
+                // ~ stdout is taken by the frames
+                return Err(format!("--{} cannot be combined with --checksum-every or --plot-population",
+                                   if m.opt_present("scroll") { "scroll" } else { "emit-frames" }));
+            }
+            let csv_file = m.opt_str("csv");
+            if csv_file.is_some() && steps.is_none() {
+                return Err("--csv requires --steps".to_owned());
+            }
+            if csv_file.as_ref().map_or(false, |f| f == "-") &&
+               (emit_frames.is_some() || checksum_every.is_some() || m.opt_present("plot-population")) {
+                // ~ stdout is taken by the population history
+                let others = "--emit-frames, --scroll, --checksum-every or --plot-population";
+                return Err(format!("--csv - cannot be combined with {}", others));
+            }
+            let metrics = match m.opt_str("metrics") {
+                None => Vec::new(),
+                Some(s) => try!(metrics::parse_metrics(&s)),
+            };
+            let (stats_file, output_file, events_file) = (m.opt_str("stats"),
+                                                          m.opt_str("output"),
+                                                          m.opt_str("export-events"));
+            let manifest_file = m.opt_str("manifest").or_else(|| {
+                if stats_file.is_some() || output_file.is_some() || events_file.is_some() ||
+                   csv_file.as_ref().map_or(false, |f| f != "-") {
+                    Some(manifest::DEFAULT_FILE.to_owned())
+                } else {
+                    None
+                }
+            });
+            let manifest = manifest_file.map(|f| {
+                let opt_str = |s: &Option<String>| Json::opt(s.clone(), Json::Str);
+                let fleet_size = |f: Fleet| {
+                    Json::opt(fleet.filter(|&(g, _)| g == f).map(|(_, n)| n), |n: usize| Json::Int(n as i64))
+                };
+                let config = vec![
+                    ("file".to_owned(), opt_str(&map_filename)),
+                    ("run".to_owned(), opt_str(&m.opt_str("run"))),
+                    ("gliders".to_owned(), fleet_size(Fleet::Gliders)),
+                    ("spaceships".to_owned(), fleet_size(Fleet::Spaceships)),
+                    ("seed".to_owned(), Json::opt(seed, |s: u64| Json::Str(s.to_string()))),
+                    ("random_generator".to_owned(), Json::str(rng::GENERATOR)),
+                    ("random_kind".to_owned(), Json::Str(random_kind.to_string())),
+                    ("size".to_owned(),
+                     Json::Array(vec![Json::Int(size.0 as i64), Json::Int(size.1 as i64)])),
+                    ("boundary".to_owned(), Json::Str(boundary.to_string())),
+                    ("neighbourhood".to_owned(),
+                     Json::Array(neighbourhood.iter()
+                                              .map(|&(dx, dy)| {
+                                                  Json::Array(vec![Json::Int(dx as i64),
+                                                                   Json::Int(dy as i64)])
+                                              })
+                                              .collect())),
+                    ("rule".to_owned(), Json::Str(rule.to_string())),
+                    ("rule_schedule".to_owned(),
+                     Json::opt(schedule.as_ref(), |s: &RuleSchedule| Json::Str(s.to_string()))),
+                    ("update_mode".to_owned(), Json::Str(update_mode_name(update_mode))),
+                    ("steps".to_owned(), Json::opt(steps, |n: usize| Json::Int(n as i64))),
+                    ("goto".to_owned(), Json::opt(goto, |n: usize| Json::Int(n as i64))),
+                    ("stats".to_owned(), opt_str(&stats_file)),
+                    ("csv".to_owned(), opt_str(&csv_file)),
+                    ("metrics".to_owned(),
+                     Json::Array(metrics.iter().map(|m| Json::Str(m.to_string())).collect())),
+                    ("output".to_owned(), opt_str(&output_file)),
+                    ("export_events".to_owned(), opt_str(&events_file)),
+                    ("keyframe_every".to_owned(), Json::Int(keyframe_every as i64)),
+                    ("sync_every".to_owned(), Json::Int(sync_secs as i64)),
+                    ("checksum_every".to_owned(), Json::opt(checksum_every, |n: usize| Json::Int(n as i64))),
+                    ("emit_frames".to_owned(), Json::opt(emit_frames, |f: FrameFormat| Json::Str(f.to_string()))),
+                    ("emit_every".to_owned(), Json::Int(emit_every as i64)),
+                    ("measure_period".to_owned(), Json::Bool(m.opt_present("measure-period"))),
+                    ("exit_on_extinct".to_owned(), Json::Bool(m.opt_present("exit-on-extinct"))),
+                    ("profile".to_owned(), Json::Bool(m.opt_present("profile"))),
+                    ("auto_expand".to_owned(), Json::opt(auto_expand, |a: AutoExpand| {
+                        Json::Object(vec![
+                            ("margin".to_owned(), Json::Int(a.margin as i64)),
+                            ("increment".to_owned(), Json::Int(a.increment as i64)),
+                            ("max_size".to_owned(), Json::opt(a.max_size, |(w, h): (usize, usize)| {
+                                Json::Array(vec![Json::Int(w as i64), Json::Int(h as i64)])
+                            })),
+                        ])
+                    })),
+                    ("presettle".to_owned(), Json::opt(presettle, |n: usize| Json::Int(n as i64))),
+                    ("reap".to_owned(), Json::opt(reap, |p: reaper::Policy| {
+                        Json::Object(vec![
+                            ("every".to_owned(), Json::Int(p.every as i64)),
+                            ("age".to_owned(), Json::Int(p.age as i64)),
+                            ("distance".to_owned(), Json::Int(p.distance as i64)),
+                        ])
+                    })),
+                    ("manifest".to_owned(), Json::str(&f[..])),
+                ];
+                Manifest::new(f, config)
+            });
+            Some(headless::Options {
+                steps: steps,
+                goto: goto,
+                stats_file: stats_file,
+                metrics: metrics,
+                csv_file: csv_file,
+                output_file: output_file,
+                events_file: events_file,
+                keyframe_every: keyframe_every,
+                measure_period: m.opt_present("measure-period"),
+                manifest: manifest,
+                exit_on_extinct: m.opt_present("exit-on-extinct"),
+                auto_expand: auto_expand,
+                checksum_every: checksum_every,
+                plot_width: if m.opt_present("plot-population") {
+                    // ~ as wide as the terminal if the shell tells
+                    Some(env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).unwrap_or(80))
+                } else {
+                    None
+                },
+                emit_frames: emit_frames,
+                emit_every: emit_every,
+                perf_log_secs: perf_log_secs,
+                reap: reap,
+                sync_secs: sync_secs,
+                schedule: schedule.clone(),
+                profile: m.opt_present("profile"),
+            })
+        } else {
+            None
+        };
         Ok(Config {
-            map_filename: m.opt_str("file"),
-            alive_char: m.opt_str("alive-char").and_then(|s| s.chars().next()).unwrap_or('O'),
-            dead_char: m.opt_str("dead-char").and_then(|s| s.chars().next()).unwrap_or(' '),
+            map_filename: map_filename,
+            input_format: input_format,
+            pack: m.opt_str("pack"),
+            max_line_length: max_line_length,
+            allow_empty: m.opt_present("allow-empty"),
+            strict_parse: m.opt_present("strict-parse"),
+            fix: m.opt_present("fix"),
+            seed: seed,
+            random_kind: random_kind,
+            size: size,
+            boundary: boundary,
+            auto_expand: auto_expand,
+            neighbourhood: neighbourhood,
+            rule: rule,
+            update_mode: update_mode,
+            count_region: count_region,
+            slots_file: m.opt_str("slots-file"),
+            macros_file: m.opt_str("macros-file"),
+            macro_delay_ms: macro_delay_ms,
+            bookmarks_file: m.opt_str("bookmarks-file"),
+            slowmo_bounds: slowmo_bounds,
+            statlog_file: m.opt_str("statlog"),
+            hud_corner: hud_corner,
+            minimap_corner: minimap_corner,
+            headless: headless,
+            inserts: inserts,
+            run: run,
+            fleet: fleet,
+            strict_insert: m.opt_present("strict-insert"),
+            listen: listen,
+            read_only: m.opt_present("read-only"),
+            autoframe: m.opt_present("autoframe"),
+            screenshot_on_exit: m.opt_str("screenshot-on-exit"),
+            compare: compare.map(|(_, second)| second),
+            schedule: schedule,
+            perf_log_secs: perf_log_secs,
+            reap: reap,
+            presettle: presettle,
+            reference: m.opt_str("reference"),
+            soup_histogram: soup_histogram,
+            stress: stress,
+            verify_events: m.opt_str("verify-events"),
+            diff: diff,
+            demo: m.opt_present("demo"),
+            alive_char: alive_char,
+            dead_char: dead_char,
+            theme: theme,
+            theme_warning: theme_warning,
+            color_tier: ColorTier::detect(env::var("COLORTERM").ok().as_ref().map(|s| &s[..]),
+                                          cfg!(feature = "truecolor") && m.opt_present("truecolor")),
+            show_status: !m.opt_present("no-status"),
+            density_color: m.opt_present("density-color"),
         })
     }
 }
+
+// ~ parses "MIN,MAX" delay bounds; MIN must be positive and not
+// exceed MAX
+fn parse_delay_bounds(s: &str) -> Option<(i64, i64)> {
+    let mut parts = s.splitn(2, ',');
+    match (parts.next().and_then(|n| n.trim().parse().ok()),
+           parts.next().and_then(|n| n.trim().parse().ok())) {
+        (Some(min), Some(max)) if min > 0 && min <= max => Some((min, max)),
+        _ => None,
+    }
+}
+
+// ~ parses a "WxH" dimension; both numbers must be positive
+fn parse_size(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, 'x');
+    match (parts.next().and_then(|w| w.parse().ok()),
+           parts.next().and_then(|h| h.parse().ok())) {
+        (Some(w), Some(h)) if w > 0 && h > 0 => Some((w, h)),
+        _ => None,
+    }
+}
+
+// ~ parses a "FILE@X,Y" insert; the file name may contain '@' itself
+fn parse_insert(s: &str) -> Option<(String, isize, isize)> {
+    let at = match s.rfind('@') {
+        Some(at) if at > 0 => at,
+        _ => return None,
+    };
+    let mut parts = s[at + 1..].splitn(2, ',');
+    match (parts.next().and_then(|x| x.trim().parse().ok()),
+           parts.next().and_then(|y| y.trim().parse().ok())) {
+        (Some(x), Some(y)) => Some((s[..at].to_owned(), x, y)),
+        _ => None,
+    }
+}
+
+// ~ places the patterns given with --insert onto the world in their
+// order, trimmed to their live cells
+fn insert_patterns(world: &mut World, cfg: &Config) -> Result<(), String> {
+    for &(ref f, x, y) in &cfg.inserts {
+        let mut pattern = try!(parser::load_world(f, cfg.max_line_length, cfg.allow_empty)
+                                   .map_err(|e| load_error(f, e)));
+        pattern.trim(0);
+        let report = world.blit(x, y, &pattern);
+        info!("inserted {} at {},{}: {} cells placed, {} clipped, {} overwritten",
+              f,
+              x,
+              y,
+              report.placed,
+              report.clipped,
+              report.overwrote);
+        if cfg.strict_insert && report.clipped > 0 {
+            return Err(format!("{}: {} cells beyond the edges of the world at {},{}",
+                               f,
+                               report.clipped,
+                               x,
+                               y));
+        }
+    }
+    Ok(())
+}
+
+fn parse_frame_format(s: &str) -> Option<FrameFormat> {
+    match s {
+        "cells" => Some(FrameFormat::Cells),
+        "json" => Some(FrameFormat::Json),
+        _ => None,
+    }
+}
+
+fn parse_corner(s: &str) -> Option<Corner> {
+    match s {
+        "top-left" => Some(Corner::TopLeft),
+        "top-right" => Some(Corner::TopRight),
+        "bottom-left" => Some(Corner::BottomLeft),
+        "bottom-right" => Some(Corner::BottomRight),
+        _ => None,
+    }
+}
+
+// ~ names the update mode the way it is given on the command line;
+// the random order along with its seed
+fn update_mode_name(mode: UpdateMode) -> String {
+    match mode {
+        UpdateMode::AsyncRandomOrder { seed } |
+        UpdateMode::AsyncRandomSubset { seed, .. } => format!("{} {}", mode, seed),
+        _ => mode.to_string(),
+    }
+}
+
+// ~ parses an update mode; a random order is derived from `seed`.
+// "async-random:P" updates P (1 to 100) percent of the cells
+fn parse_update_mode(s: &str, seed: u64) -> Option<UpdateMode> {
+    match s {
+        "sync" => Some(UpdateMode::Synchronous),
+        "async-random" => Some(UpdateMode::AsyncRandomOrder { seed: seed }),
+        "async-row-major" => Some(UpdateMode::AsyncRowMajor),
+        _ if s.starts_with("async-random:") => {
+            match s["async-random:".len()..].parse() {
+                Ok(p) if p >= 1 && p <= 100 => {
+                    Some(UpdateMode::AsyncRandomSubset {
+                        seed: seed,
+                        percent: p,
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// ~ parses a random kind: "uniform" or "clustered[:N]"
+fn parse_random_kind(s: &str) -> Option<RandomKind> {
+    match s {
+        "uniform" => Some(RandomKind::Uniform),
+        "clustered" => Some(RandomKind::Clustered { cluster_size: world::DEFAULT_CLUSTER_SIZE }),
+        _ if s.starts_with("clustered:") => {
+            match s["clustered:".len()..].parse() {
+                Ok(n) if n > 0 && usize::checked_mul(n, n).is_some() => {
+                    Some(RandomKind::Clustered { cluster_size: n })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// ~ parses a "DX[,DY]" twist; DY defaults to zero
+fn parse_twist(s: &str) -> Option<(isize, isize)> {
+    let mut parts = s.splitn(2, ',');
+    match (parts.next().and_then(|dx| dx.parse().ok()), parts.next()) {
+        (Some(dx), None) => Some((dx, 0)),
+        (Some(dx), Some(dy)) => dy.parse().ok().map(|dy| (dx, dy)),
+        _ => None,
+    }
+}
+
+// ~ describes a failure to load the given file; an empty pattern is
+// most likely a mistake, but might be meant
+fn load_error(filename: &str, e: ParseError) -> String {
+    match e {
+        ParseError::EmptyPattern => {
+            format!("{}: {} (use --allow-empty for an empty world of the pattern's size)",
+                    filename,
+                    e)
+        }
+        e => format!("{}: {}", filename, e),
+    }
+}
+
+// ~ parses a list of "DX,DY" offsets separated by semicolons
+fn parse_neighbourhood(s: &str) -> Option<Vec<(isize, isize)>> {
+    s.split(';')
+     .map(|o| {
+         let mut parts = o.splitn(2, ',');
+         match (parts.next().and_then(|dx| dx.trim().parse().ok()),
+                parts.next().and_then(|dy| dy.trim().parse().ok())) {
+             (Some(dx), Some(dy)) => Some((dx, dy)),
+             _ => None,
+         }
+     })
+     .collect()
+}
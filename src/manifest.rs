@@ -0,0 +1,417 @@
+//! A machine-readable record of a headless run: its effective
+//! configuration, timing, outcome, and the files it produced.
+//!
+//! The manifest is written once when the run starts (marked as
+//! incomplete) and rewritten when it ends.  Both writes go to a
+//! temporary file first which is then renamed over the manifest (see
+//! `io_util::atomic_write`), so a crashed run still leaves a
+//! consistent, partial record.
+
+use std::fmt;
+use std::fs;
+
+use time;
+
+use io_util;
+use world::World;
+
+/// The manifest written when output files are requested but no
+/// explicit manifest file is given.
+pub const DEFAULT_FILE: &'static str = "run-manifest.json";
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn str<S: Into<String>>(s: S) -> Json {
+        Json::Str(s.into())
+    }
+
+    /// `Null` for `None`, otherwise the value converted by `f`.
+    pub fn opt<T, F: FnOnce(T) -> Json>(v: Option<T>, f: F) -> Json {
+        v.map_or(Json::Null, f)
+    }
+
+    /// Parses a single JSON value, e.g. a command received over the
+    /// network.  Numbers without a fraction or an exponent become
+    /// `Int`s if they fit, others `Float`s.
+    pub fn parse(s: &str) -> Result<Json, String> {
+        let mut p = JsonParser {
+            s: s,
+            pos: 0,
+        };
+        let v = try!(p.value());
+        p.skip_whitespace();
+        if p.pos < s.len() {
+            return Err(p.error("trailing characters"));
+        }
+        Ok(v)
+    }
+
+    /// The value of the given key if this is an object having it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref kvs) => kvs.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v),
+            _ => None,
+        }
+    }
+}
+
+// ~ a recursive descent parser over `s`; `pos` is a byte offset
+struct JsonParser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, what: &str) -> String {
+        format!("Invalid JSON at offset {}: {}", self.pos, what)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if let Some(c) = c {
+            self.pos += c.len_utf8();
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), String> {
+        if self.s[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", word)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::Str),
+            Some('[') => {
+                self.pos += 1;
+                let mut vs = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(vs));
+                }
+                loop {
+                    vs.push(try!(self.value()));
+                    self.skip_whitespace();
+                    match self.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Json::Array(vs)),
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut kvs = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(kvs));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some('"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let k = try!(self.string());
+                    self.skip_whitespace();
+                    if self.next() != Some(':') {
+                        return Err(self.error("expected :"));
+                    }
+                    kvs.push((k, try!(self.value())));
+                    self.skip_whitespace();
+                    match self.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Json::Object(kvs)),
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_digit(10) => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        let mut integral = true;
+        while let Some(c) = self.peek() {
+            match c {
+                '.' | 'e' | 'E' | '+' => integral = false,
+                c if c == '-' || c.is_digit(10) => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        let n = &self.s[start..self.pos];
+        match n.parse() {
+            Ok(i) if integral => return Ok(Json::Int(i)),
+            _ => {}
+        }
+        // ~ `parse` accepts more than JSON does, e.g. "inf"; the
+        // characters taken rule that out
+        n.parse().map(Json::Float).map_err(|_| {
+            self.pos = start;
+            self.error("invalid number")
+        })
+    }
+
+    // ~ the string literal at the current position
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => try!(self.unicode_escape()),
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    s.push(c);
+                }
+                Some(c) if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    // ~ the character of a `\uXXXX` escape (past the `u`); characters
+    // beyond the BMP come as a surrogate pair of two escapes
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let hi = try!(self.hex4());
+        let code = if hi >= 0xd800 && hi < 0xdc00 {
+            try!(self.expect("\\u"));
+            let lo = try!(self.hex4());
+            if lo < 0xdc00 || lo >= 0xe000 {
+                return Err(self.error("invalid surrogate pair"));
+            }
+            0x10000 + ((hi - 0xd800) << 10) + (lo - 0xdc00)
+        } else {
+            hi
+        };
+        ::std::char::from_u32(code).ok_or_else(|| self.error("invalid escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.s[self.pos..].get(..4).and_then(|h| u32::from_str_radix(h, 16).ok());
+        match digits {
+            Some(n) if self.s[self.pos..self.pos + 4].chars().all(|c| c.is_digit(16)) => {
+                self.pos += 4;
+                Ok(n)
+            }
+            _ => Err(self.error("invalid escape")),
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(fmt, "null"),
+            Json::Bool(b) => write!(fmt, "{}", b),
+            Json::Int(n) => write!(fmt, "{}", n),
+            // ~ JSON knows neither NaN nor infinity
+            Json::Float(f) if !f.is_finite() => write!(fmt, "null"),
+            Json::Float(f) => write!(fmt, "{}", f),
+            Json::Str(ref s) => write_str(fmt, s),
+            Json::Array(ref vs) => {
+                try!(write!(fmt, "["));
+                for (i, v) in vs.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(fmt, ","));
+                    }
+                    try!(write!(fmt, "{}", v));
+                }
+                write!(fmt, "]")
+            }
+            Json::Object(ref kvs) => {
+                try!(write!(fmt, "{{"));
+                for (i, &(ref k, ref v)) in kvs.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(fmt, ","));
+                    }
+                    try!(write_str(fmt, k));
+                    try!(write!(fmt, ":{}", v));
+                }
+                write!(fmt, "}}")
+            }
+        }
+    }
+}
+
+// ~ writes `s` as a JSON string literal; non-ASCII characters are
+// written as they are (the output is UTF-8)
+fn write_str(fmt: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    try!(write!(fmt, "\""));
+    for c in s.chars() {
+        match c {
+            '"' => try!(write!(fmt, "\\\"")),
+            '\\' => try!(write!(fmt, "\\\\")),
+            '\n' => try!(write!(fmt, "\\n")),
+            '\r' => try!(write!(fmt, "\\r")),
+            '\t' => try!(write!(fmt, "\\t")),
+            c if (c as u32) < 0x20 => try!(write!(fmt, "\\u{:04x}", c as u32)),
+            c => try!(write!(fmt, "{}", c)),
+        }
+    }
+    write!(fmt, "\"")
+}
+
+pub struct Manifest {
+    path: String,
+    config: Vec<(String, Json)>,
+    started: String,
+}
+
+impl Manifest {
+    /// Creates a manifest to be written to `path` recording the given
+    /// effective configuration.
+    pub fn new(path: String, config: Vec<(String, Json)>) -> Manifest {
+        Manifest {
+            path: path,
+            config: config,
+            started: time::now_utc().rfc3339().to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Records the start of the run.
+    pub fn write_started(&self, world: &World) -> Result<(), String> {
+        self.write(world, None, &[])
+    }
+
+    /// Records the end of the run; `artifacts` are the paths of the
+    /// files the run produced.
+    pub fn write_finished(&self,
+                          world: &World,
+                          interrupted: bool,
+                          artifacts: &[&str])
+                          -> Result<(), String> {
+        self.write(world, Some(interrupted), artifacts)
+    }
+
+    // ~ `interrupted` is `None` while the run is still going on
+    fn write(&self,
+             world: &World,
+             interrupted: Option<bool>,
+             artifacts: &[&str])
+             -> Result<(), String> {
+        let artifacts = artifacts.iter()
+                                 .map(|&a| {
+                                     let size = fs::metadata(a).ok().map(|m| m.len() as i64);
+                                     Json::Object(vec![("path".to_owned(), Json::str(a)),
+                                                       ("size".to_owned(),
+                                                        Json::opt(size, Json::Int))])
+                                 })
+                                 .collect();
+        let doc = Json::Object(vec![
+            ("complete".to_owned(), Json::Bool(interrupted.is_some())),
+            ("interrupted".to_owned(), Json::opt(interrupted, Json::Bool)),
+            ("config".to_owned(), Json::Object(self.config.clone())),
+            ("rule".to_owned(), Json::Str(world.rule().to_string())),
+            ("width".to_owned(), Json::Int(world.width() as i64)),
+            ("height".to_owned(), Json::Int(world.height() as i64)),
+            ("started".to_owned(), Json::str(&self.started[..])),
+            ("finished".to_owned(),
+             Json::opt(interrupted.map(|_| time::now_utc().rfc3339().to_string()), Json::Str)),
+            ("generation".to_owned(), Json::Int(world.generation() as i64)),
+            ("alive".to_owned(), Json::Int(world.alive() as i64)),
+            ("fingerprint".to_owned(), Json::Str(format!("{:016x}", world.generation_hash()))),
+            ("artifacts".to_owned(), Json::Array(artifacts)),
+        ]);
+
+        io_util::atomic_write(&self.path, |out| writeln!(out, "{}", doc))
+            .map_err(|e| format!("{}: {}", self.path, e))
+    }
+}
+
+#[test]
+fn test_json_escaping() {
+    assert_eq!(r#""say \"hi\"""#, Json::str("say \"hi\"").to_string());
+    assert_eq!(r#""C:\\maps\\a.cells""#, Json::str("C:\\maps\\a.cells").to_string());
+    assert_eq!(r#""a\nb\tc\u0001""#, Json::str("a\nb\tc\u{1}").to_string());
+    assert_eq!("\"Žluťoučký kůň ☺\"", Json::str("Žluťoučký kůň ☺").to_string());
+}
+
+#[test]
+fn test_json_structure() {
+    let doc = Json::Object(vec![("a\"b".to_owned(), Json::Array(vec![Json::Int(-1),
+                                                                    Json::Null,
+                                                                    Json::Bool(true),
+                                                                    Json::Float(0.5),
+                                                                    Json::Float(1.0 / 0.0)])),
+                                ("e".to_owned(), Json::Object(vec![]))]);
+    assert_eq!(r#"{"a\"b":[-1,null,true,0.5,null],"e":{}}"#, doc.to_string());
+}
+
+#[test]
+fn test_json_parse() {
+    let doc = Json::parse(r#" {"cmd": "step", "n": 12, "x": [-1.5e1, null, true, false, {}],
+                              "s": "a\"\\\/\n\u00e9\ud83d\ude00"} "#)
+                  .unwrap();
+    assert_eq!(Some(&Json::str("step")), doc.get("cmd"));
+    assert_eq!(Some(&Json::Int(12)), doc.get("n"));
+    assert_eq!(Some(&Json::Array(vec![Json::Float(-15.0),
+                                      Json::Null,
+                                      Json::Bool(true),
+                                      Json::Bool(false),
+                                      Json::Object(vec![])])),
+               doc.get("x"));
+    assert_eq!(Some(&Json::str("a\"\\/\n\u{e9}\u{1f600}")), doc.get("s"));
+    assert_eq!(None, doc.get("y"));
+    assert_eq!(None, Json::Int(1).get("cmd"));
+    // ~ what is written reads back
+    let s = r#"{"a\"b":[-1,null,true,0.5,"\u0001"],"e":{}}"#;
+    assert_eq!(s, Json::parse(s).unwrap().to_string());
+    // ~ integers too large are taken as floats
+    assert_eq!(Json::Float(1e19), Json::parse("10000000000000000000").unwrap());
+
+    assert_eq!("Invalid JSON at offset 9: expected , or }", Json::parse(r#"{"a": 1 "b": 2}"#).unwrap_err());
+    assert_eq!("Invalid JSON at offset 3: trailing characters", Json::parse("{} x").unwrap_err());
+    for s in &["", "{", "[1,]", "{\"a\"}", "tru", "\"abc", "\"\\x\"", "\"\\ud800\"", "-", "1.2.3", "'a'"] {
+        assert!(Json::parse(s).is_err(), "{}", s);
+    }
+}
@@ -0,0 +1,290 @@
+//! Quantitative measures of the texture of a world.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+
+use world::{StepSummary, World};
+
+/// The number of block positions above which `block_entropy_auto`
+/// resorts to sampling.
+const SAMPLING_THRESHOLD: usize = 1 << 18;
+
+/// The largest supported block size; a block's cells must fit into a
+/// `u64`.
+const MAX_BLOCK: usize = 8;
+
+/// A metric to be recorded per generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// block entropy over blocks of the given size
+    Entropy(usize),
+    Density,
+    Activity,
+    /// the cells born by the last step
+    Births,
+    /// the cells died by the last step
+    Deaths,
+    /// the number of cells per number of live neighbours (see
+    /// `World::neighbour_histogram`)
+    Neighbours,
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Metric::Entropy(block) => write!(fmt, "entropy:{}", block),
+            Metric::Density => write!(fmt, "density"),
+            Metric::Activity => write!(fmt, "activity"),
+            Metric::Births => write!(fmt, "births"),
+            Metric::Deaths => write!(fmt, "deaths"),
+            Metric::Neighbours => write!(fmt, "neighbours"),
+        }
+    }
+}
+
+/// Parses a comma separated list of metrics, e.g. `entropy:3,density`.
+pub fn parse_metrics(s: &str) -> Result<Vec<Metric>, String> {
+    s.split(',')
+     .map(|m| {
+         let mut parts = m.splitn(2, ':');
+         match (parts.next().unwrap(), parts.next()) {
+             ("density", None) => Ok(Metric::Density),
+             ("activity", None) => Ok(Metric::Activity),
+             ("births", None) => Ok(Metric::Births),
+             ("deaths", None) => Ok(Metric::Deaths),
+             ("neighbours", None) => Ok(Metric::Neighbours),
+             ("entropy", Some(n)) => {
+                 match n.parse() {
+                     Ok(n) if n > 0 && n <= MAX_BLOCK => Ok(Metric::Entropy(n)),
+                     _ => Err(format!("Invalid entropy block size: {}", n)),
+                 }
+             }
+             _ => Err(format!("Unknown metric: {}", m)),
+         }
+     })
+     .collect()
+}
+
+/// The ratio of live cells.
+pub fn density(world: &World) -> f64 {
+    world.alive() as f64 / (world.width() * world.height()) as f64
+}
+
+/// The ratio of cells which changed in the step described by the
+/// summary.
+pub fn activity_ratio(summary: &StepSummary, world: &World) -> f64 {
+    (summary.births + summary.deaths) as f64 / (world.width() * world.height()) as f64
+}
+
+/// The characters drawing a column of a chart filled by zero to eight
+/// eighths of a line.
+const EIGHTHS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws the given series (e.g. the population of consecutive
+/// generations) as a bar chart of `height` lines; see `plot_from`.
+pub fn plot(series: &[usize], width: usize, height: usize) -> String {
+    plot_from(series, 0, width, height)
+}
+
+/// Draws the given series of consecutive generations starting with
+/// `first` as a bar chart of `height` lines of block characters, along
+/// with a y-axis labeled with the series' minimum and maximum and an
+/// x-axis labeled with the range of generations; at most `width`
+/// characters wide.  Longer series are downsampled by taking the
+/// maximum of adjacent values so that spikes are kept.  The minimum
+/// shows as the lowest bar; a constant series as a flat line.
+pub fn plot_from(series: &[usize], first: usize, width: usize, height: usize) -> String {
+    let (min, max) = match (series.iter().min(), series.iter().max()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return "no data\n".to_owned(),
+    };
+    let label_width = cmp::max(min.to_string().len(), max.to_string().len());
+    let columns = cmp::max(1, width.saturating_sub(label_width + 2));
+    let pooled: Vec<usize> = if series.len() <= columns {
+        series.to_vec()
+    } else {
+        (0..columns)
+            .map(|i| {
+                let (from, to) = (i * series.len() / columns, (i + 1) * series.len() / columns);
+                *series[from..to].iter().max().unwrap()
+            })
+            .collect()
+    };
+    let levels = height * 8;
+    let eighths: Vec<usize> = pooled.iter()
+                                    .map(|&v| {
+                                        if max == min {
+                                            1
+                                        } else {
+                                            1 + (v - min) * (levels - 1) / (max - min)
+                                        }
+                                    })
+                                    .collect();
+    let mut out = String::new();
+    for row in 0..height {
+        let label = if row == 0 {
+            max.to_string()
+        } else if row == height - 1 {
+            min.to_string()
+        } else {
+            String::new()
+        };
+        let base = (height - 1 - row) * 8;
+        let bars: String = eighths.iter()
+                                  .map(|&e| EIGHTHS[cmp::min(8, e.saturating_sub(base))])
+                                  .collect();
+        out.push_str(format!("{:>2$} |{}", label, bars, label_width).trim_end());
+        out.push('\n');
+    }
+    out.push_str(&format!("{:2$} +{}\n", "", "-".repeat(pooled.len()), label_width));
+    let (from, to) = (first.to_string(), (first + series.len() - 1).to_string());
+    let mut axis = format!("{:1$}  {2}", "", label_width, from);
+    if series.len() > 1 && from.len() + to.len() < pooled.len() {
+        axis.push_str(&format!("{:>1$}", to, pooled.len() - from.len()));
+    }
+    out.push_str(&axis);
+    out.push('\n');
+    out
+}
+
+/// Computes the Shannon entropy (in bits) of the distribution of the
+/// patterns seen through a `block`x`block` window sliding over the
+/// whole world (without wrapping around its edges.)  Uniform worlds
+/// have an entropy of zero.
+///
+/// This is `O(cells * block^2)`; see `block_entropy_auto` for big
+/// worlds.
+pub fn block_entropy(world: &World, block: usize) -> Result<f64, String> {
+    block_entropy_strided(world, block, 1)
+}
+
+/// Like `block_entropy`, but considers only every `stride`-th window
+/// position in both directions.
+pub fn block_entropy_strided(world: &World, block: usize, stride: usize) -> Result<f64, String> {
+    assert!(stride > 0);
+    if block == 0 || block > MAX_BLOCK {
+        return Err(format!("Unsupported block size: {}", block));
+    }
+    if block > world.width() || block > world.height() {
+        return Err(format!("Block size {} exceeds the world's dimensions {}x{}",
+                           block,
+                           world.width(),
+                           world.height()));
+    }
+    let mut patterns = HashMap::new();
+    let mut total = 0;
+    for y in (0..(world.height() - block + 1)).filter(|y| y % stride == 0) {
+        for x in (0..(world.width() - block + 1)).filter(|x| x % stride == 0) {
+            let mut key = 0u64;
+            for by in 0..block {
+                for bx in 0..block {
+                    key = key << 1 | world.is_alive(x + bx, y + by) as u64;
+                }
+            }
+            *patterns.entry(key).or_insert(0usize) += 1;
+            total += 1;
+        }
+    }
+    let total = total as f64;
+    Ok(patterns.values()
+               .map(|&n| {
+                   let p = n as f64 / total;
+                   -p * p.log2()
+               })
+               .fold(0.0, |acc, h| acc + h)
+               .abs()) // ~ avoid reporting -0 for uniform worlds
+}
+
+/// Determines the stride `block_entropy_auto` uses for the given world
+/// and block size; 1 unless the world is big.
+pub fn entropy_stride(world: &World, block: usize) -> usize {
+    let positions = (world.width().saturating_sub(block) + 1) *
+                    (world.height().saturating_sub(block) + 1);
+    let mut stride = 1;
+    while positions / (stride * stride) > SAMPLING_THRESHOLD {
+        stride += 1;
+    }
+    stride
+}
+
+/// Computes the block entropy exactly for small worlds and from a
+/// strided sample for big ones; see `entropy_stride`.
+pub fn block_entropy_auto(world: &World, block: usize) -> Result<f64, String> {
+    block_entropy_strided(world, block, entropy_stride(world, block))
+}
+
+#[cfg(test)]
+fn striped(width: usize, height: usize) -> World {
+    let mut w = World::empty(width, height);
+    for y in 0..height {
+        for x in (0..width).filter(|x| x % 2 == 0) {
+            w.set_alive(x, y, true);
+        }
+    }
+    w
+}
+
+#[test]
+fn test_block_entropy() {
+    let empty = World::empty(4, 4);
+    assert_eq!(0.0, block_entropy(&empty, 2).unwrap());
+
+    let mut full = World::empty(4, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            full.set_alive(x, y, true);
+        }
+    }
+    assert_eq!(0.0, block_entropy(&full, 3).unwrap());
+
+    // ~ as many live as dead cells
+    assert_eq!(1.0, block_entropy(&striped(4, 4), 1).unwrap());
+    // ~ two kinds of 2x2 blocks; one seen twice as often as the other
+    let h = block_entropy(&striped(4, 4), 2).unwrap();
+    assert!((h - 0.918295834054489).abs() < 1e-12);
+
+    assert!(block_entropy(&empty, 5).is_err());
+}
+
+#[test]
+fn test_block_entropy_sampled() {
+    let w = World::random_seeded(9, 300, 300);
+    let exact = block_entropy(&w, 3).unwrap();
+    let sampled = block_entropy_strided(&w, 3, 3).unwrap();
+    assert!((exact - sampled).abs() < 0.05);
+    assert_eq!(1, entropy_stride(&w, 3));
+    assert!(entropy_stride(&World::empty(2000, 2000), 3) > 1);
+}
+
+#[test]
+fn test_parse_metrics() {
+    assert_eq!(vec![Metric::Entropy(3), Metric::Density, Metric::Activity, Metric::Neighbours],
+               parse_metrics("entropy:3,density,activity,neighbours").unwrap());
+    assert_eq!(vec![Metric::Births, Metric::Deaths], parse_metrics("births,deaths").unwrap());
+    assert!(parse_metrics("entropy").is_err());
+    assert!(parse_metrics("entropy:9").is_err());
+    assert!(parse_metrics("density,foo").is_err());
+}
+
+#[test]
+fn test_plot() {
+    let lines = |ls: &[&str]| ls.iter().map(|l| format!("{}\n", l)).collect::<String>();
+    assert_eq!(lines(&["8 |   ▄█",
+                       "0 |▁▄███",
+                       "  +-----",
+                       "   0   4"]),
+               plot(&[0, 2, 4, 6, 8], 20, 2));
+    // ~ constant; a flat line at the bottom
+    assert_eq!(lines(&["7 |", "7 |▁▁▁", "  +---", "   10"]), plot_from(&[7, 7, 7], 10, 20, 2));
+    assert_eq!(lines(&["5 |▁", "  +-", "   0"]), plot(&[5], 20, 1));
+    assert_eq!("no data\n", plot(&[], 20, 5));
+    // ~ downsampled to seven columns keeping the spike
+    let mut long = vec![10; 600];
+    long[301] = 100;
+    assert_eq!(lines(&["100 |   █",
+                       " 10 |▁▁▁█▁▁▁",
+                       "    +-------",
+                       "     0   599"]),
+               plot(&long, 12, 2));
+}
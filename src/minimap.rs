@@ -0,0 +1,198 @@
+//! A downsampled overview of a whole world, shown in a corner of the
+//! screen while the world is larger than the screen.
+
+use std::cmp;
+
+use world::World;
+
+/// The largest dimensions of a mini-map (in characters.)
+pub const MAX_WIDTH: usize = 20;
+pub const MAX_HEIGHT: usize = 10;
+
+/// The number of generations after which the mini-map's contents are
+/// sampled anew.
+pub const REFRESH_EVERY: usize = 8;
+
+/// The corner of the screen a mini-map is shown in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// The placement of a mini-map on the screen and the number of world
+/// cells (in either direction) each of its characters stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub scale: usize,
+}
+
+impl Layout {
+    /// Lays out the mini-map of a world of the given dimensions in the
+    /// given corner of a screen of `screen_width` x `screen_height`
+    /// characters.  Both directions are scaled alike, such that the
+    /// map keeps the world's aspect ratio; small worlds are shown
+    /// unscaled.  `None` if the screen is too small to hold the map.
+    pub fn new(world_width: usize,
+               world_height: usize,
+               screen_width: usize,
+               screen_height: usize,
+               corner: Corner)
+               -> Option<Layout> {
+        let scale = cmp::max(1,
+                             cmp::max(div_ceil(world_width, MAX_WIDTH),
+                                      div_ceil(world_height, MAX_HEIGHT)));
+        let (width, height) = (div_ceil(world_width, scale), div_ceil(world_height, scale));
+        if width == 0 || height == 0 || width > screen_width || height > screen_height {
+            return None;
+        }
+        let (x, y) = match corner {
+            Corner::TopLeft => (0, 0),
+            Corner::TopRight => (screen_width - width, 0),
+            Corner::BottomLeft => (0, screen_height - height),
+            Corner::BottomRight => (screen_width - width, screen_height - height),
+        };
+        Some(Layout {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+            scale: scale,
+        })
+    }
+
+    /// Determines the characters of the map covered by the viewport of
+    /// the given position and (positive) size within the world as
+    /// `(left, top, right, bottom)`, relative to the map and inclusive.
+    /// The parts of the viewport beyond the world are ignored.
+    pub fn viewport_rect(&self, x: usize, y: usize, width: usize, height: usize)
+                         -> (usize, usize, usize, usize) {
+        let clamp = |v: usize, len: usize| cmp::min(v / self.scale, len - 1);
+        (clamp(x, self.width),
+         clamp(y, self.height),
+         clamp(x + width - 1, self.width),
+         clamp(y + height - 1, self.height))
+    }
+}
+
+/// Determines the ratio of live cells in the part of the world each
+/// character of the given mini-map stands for; row by row.
+pub fn downsample(world: &World, layout: &Layout) -> Vec<f64> {
+    let mut alive = vec![0usize; layout.width * layout.height];
+    for y in 0..world.height() {
+        let row = (y / layout.scale) * layout.width;
+        for x in 0..world.width() {
+            if world.is_alive(x, y) {
+                alive[row + x / layout.scale] += 1;
+            }
+        }
+    }
+    let area = |pos: usize, len: usize| cmp::min(layout.scale, len - pos * layout.scale);
+    alive.iter()
+         .enumerate()
+         .map(|(i, &n)| {
+             let (mx, my) = (i % layout.width, i / layout.width);
+             n as f64 / (area(mx, world.width()) * area(my, world.height())) as f64
+         })
+         .collect()
+}
+
+/// The character representing the given ratio of live cells.
+pub fn density_char(density: f64) -> char {
+    if density <= 0.0 {
+        ' '
+    } else if density <= 0.1 {
+        '.'
+    } else if density <= 0.25 {
+        ':'
+    } else if density <= 0.5 {
+        '*'
+    } else {
+        '#'
+    }
+}
+
+/// The character outlining a viewport at the given position of the
+/// map; `None` if the position is not part of the outline.
+pub fn outline_char(x: usize, y: usize, rect: (usize, usize, usize, usize)) -> Option<char> {
+    let (left, top, right, bottom) = rect;
+    if x < left || x > right || y < top || y > bottom {
+        return None;
+    }
+    match (x == left || x == right, y == top || y == bottom) {
+        (true, true) => Some('+'),
+        (false, true) => Some('-'),
+        (true, false) => Some('|'),
+        (false, false) => None,
+    }
+}
+
+fn div_ceil(a: usize, b: usize) -> usize {
+    (a + b - 1) / b
+}
+
+#[test]
+fn test_layout_aspect_ratios() {
+    // ~ a very wide world; a single row of characters
+    let l = Layout::new(2000, 10, 80, 23, Corner::TopRight).unwrap();
+    assert_eq!((60, 0, 20, 1, 100), (l.x, l.y, l.width, l.height, l.scale));
+    // ~ a very tall world; a single column
+    let l = Layout::new(10, 2000, 80, 23, Corner::BottomLeft).unwrap();
+    assert_eq!((0, 13, 1, 10, 200), (l.x, l.y, l.width, l.height, l.scale));
+    // ~ a square world in a map twice as wide as tall
+    let l = Layout::new(2000, 2000, 80, 23, Corner::BottomRight).unwrap();
+    assert_eq!((70, 13, 10, 10, 200), (l.x, l.y, l.width, l.height, l.scale));
+    // ~ small worlds are not scaled up
+    let l = Layout::new(7, 3, 80, 23, Corner::TopLeft).unwrap();
+    assert_eq!((0, 0, 7, 3, 1), (l.x, l.y, l.width, l.height, l.scale));
+    // ~ the screen is too small
+    assert_eq!(None, Layout::new(2000, 2000, 9, 23, Corner::TopLeft));
+}
+
+#[test]
+fn test_viewport_rect() {
+    let l = Layout::new(240, 44, 60, 11, Corner::TopRight).unwrap();
+    assert_eq!((20, 4, 12), (l.width, l.height, l.scale));
+    assert_eq!((0, 0, 4, 0), l.viewport_rect(0, 0, 60, 11));
+    assert_eq!((10, 1, 14, 2), l.viewport_rect(120, 20, 60, 11));
+    // ~ clamped to the map
+    assert_eq!((15, 3, 19, 3), l.viewport_rect(180, 40, 80, 25));
+    // ~ a viewport larger than the world covers the whole map
+    let l = Layout::new(30, 8, 80, 23, Corner::TopRight).unwrap();
+    assert_eq!((0, 0, 14, 3), l.viewport_rect(0, 0, 80, 22));
+}
+
+#[test]
+fn test_downsample() {
+    // ~ a 39x10 world at scale 2; the last column of characters stands
+    // for a single column of cells
+    let mut w = World::empty(39, 10);
+    for &(x, y) in [(0, 0), (0, 2), (1, 3), (38, 9)].iter() {
+        w.set_alive(x, y, true);
+    }
+    let l = Layout::new(39, 10, 80, 23, Corner::TopLeft).unwrap();
+    assert_eq!((20, 5, 2), (l.width, l.height, l.scale));
+    let d = downsample(&w, &l);
+    assert_eq!(0.25, d[0]);
+    assert_eq!(0.5, d[20]);
+    assert_eq!(0.5, d[4 * 20 + 19]);
+    assert_eq!(3, d.iter().filter(|&&d| d > 0.0).count());
+    assert_eq!(' ', density_char(d[1]));
+    assert_eq!(':', density_char(d[0]));
+    assert_eq!('*', density_char(d[20]));
+}
+
+#[test]
+fn test_outline_char() {
+    let r = (1, 1, 4, 3);
+    let rows: Vec<String> = (0..5)
+                                .map(|y| (0..6).map(|x| outline_char(x, y, r).unwrap_or(' ')).collect())
+                                .collect();
+    assert_eq!(vec!["      ", " +--+ ", " |  | ", " +--+ ", "      "], rows);
+}
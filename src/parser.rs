@@ -1,64 +1,494 @@
 use std::cmp;
+use std::error;
 use std::fmt;
 use std::fs::File;
-use std::io::Read;
-
-use world::World;
-
-/// Loads a world from the given filename. Results either in the
-/// loaded world or a human readable error description.
-pub fn load_from_file(filename: &str) -> Result<World, String> {
-    macro_rules! err {
-        ($expr:expr) => {
-            match $expr {
-                Err(e) => return Err(format!("{}: {}", filename, e)),
-                Ok(v) => v,
-            }
+use std::io::{self, BufReader, Read, Write};
+use std::str;
+
+use io_util;
+use text::{self, TextInput};
+use workspace::{self, Labels, Workspace};
+use world::{self, World};
+
+/// The extension of files in the binary format (see
+/// `World::save_binary`.)
+pub const BINARY_EXTENSION: &'static str = ".cgolb";
+
+/// The extensions of files in the coordinate list format (see
+/// `Format::Coords`.)
+pub const COORDS_EXTENSIONS: [&'static str; 2] = [".csv", ".coords"];
+
+/// The start of a comment line of a coordinate list giving the offset
+/// to add to the coordinates following it: `# offset X,Y`.
+const COORDS_OFFSET_COMMENT: &'static str = "# offset ";
+
+/// The default of the longest line (in characters) accepted in the
+/// plaintext format; a longer line is more likely a mistake than
+/// intended to dictate the width of the world.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 100000;
+
+/// The share (in percent) of the characters of a plaintext world
+/// (apart from comments and whitespace) which must be `.` or `O`.
+const PLAUSIBLE_PERCENT: usize = 80;
+
+/// The start of a comment line labeling a cell of a plaintext world:
+/// `!label X,Y TEXT` where `X` and `Y` are the (0-based) column and
+/// line of the cell within the pattern.
+const LABEL_COMMENT: &'static str = "!label ";
+
+/// The number of characters of a plaintext world (apart from comments
+/// and whitespace) below which it is not checked for plausibility;
+/// the first invalid character is reported instead.
+const PLAUSIBLE_MIN_CHARS: usize = 16;
+
+/// The formats worlds are read in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// the plaintext format (`.cells`)
+    Cells,
+    /// the binary format (see `World::save_binary`)
+    Binary,
+    /// a list of the live cells' coordinates, one `x,y` pair per line;
+    /// see `save_coords`
+    Coords,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "cells" => Some(Format::Cells),
+            "binary" => Some(Format::Binary),
+            "coords" => Some(Format::Coords),
+            _ => None,
         }
     }
-    let mut f = err!(File::open(filename));
-    let mut s = String::with_capacity(err!(f.metadata()).len() as usize);
-    err!(f.read_to_string(&mut s));
-    let w = err!(cells_parse(&s));
-    Ok(w)
+
+    /// Determines the format of the given file by its extension; `None`
+    /// if the extension tells none.
+    pub fn from_filename(filename: &str) -> Option<Format> {
+        if filename.ends_with(BINARY_EXTENSION) {
+            Some(Format::Binary)
+        } else if COORDS_EXTENSIONS.iter().any(|ext| filename.ends_with(ext)) {
+            Some(Format::Coords)
+        } else if filename.ends_with(".cells") {
+            Some(Format::Cells)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            Format::Cells => "cells",
+            Format::Binary => "binary",
+            Format::Coords => "coords",
+        })
+    }
+}
+
+/// Loads a world from the given filename in the format told by its
+/// extension (see `Format::from_filename`), or else by its content: a
+/// coordinate list if its first line apart from comments (and a header
+/// row) is an `x,y` pair, otherwise the plaintext format with lines of
+/// at most `max_line_length` characters.  A pattern without live cells
+/// is refused as `ParseError::EmptyPattern` unless `allow_empty`, an
+/// empty world of the pattern's size being loaded then.
+pub fn load_world(filename: &str, max_line_length: usize, allow_empty: bool) -> Result<World, ParseError> {
+    load_workspace(filename, max_line_length, allow_empty).map(|ws| ws.world)
+}
+
+/// Loads a world like `load_world` along with the labels of its cells;
+/// files in the binary format have none.
+pub fn load_workspace(filename: &str,
+                      max_line_length: usize,
+                      allow_empty: bool)
+                      -> Result<Workspace, ParseError> {
+    load_workspace_as(filename, None, max_line_length, allow_empty)
+}
+
+/// Loads a world like `load_workspace` in the given format regardless
+/// of the file's name and content; `None` to determine it from them.
+pub fn load_workspace_as(filename: &str,
+                         format: Option<Format>,
+                         max_line_length: usize,
+                         allow_empty: bool)
+                         -> Result<Workspace, ParseError> {
+    let format = format.or_else(|| Format::from_filename(filename));
+    if format == Some(Format::Binary) {
+        let f = try!(File::open(filename));
+        World::load_binary(BufReader::new(f)).map(Workspace::new).map_err(ParseError::Io)
+    } else {
+        load_workspace_from_file(filename, format, max_line_length, allow_empty)
+    }
+}
+
+/// Loads a world in the plaintext format from the given filename with
+/// lines of at most `max_line_length` characters; see `load_world`
+/// for `allow_empty`.  The errors' descriptions do not mention the
+/// filename.
+pub fn load_from_file(filename: &str, max_line_length: usize, allow_empty: bool) -> Result<World, ParseError> {
+    load_workspace_from_file(filename, Some(Format::Cells), max_line_length, allow_empty).map(|ws| ws.world)
+}
+
+// ~ loads a world in the plaintext or the coordinate list format;
+// `None` to tell them apart by the content
+fn load_workspace_from_file(filename: &str,
+                            format: Option<Format>,
+                            max_line_length: usize,
+                            allow_empty: bool)
+                            -> Result<Workspace, ParseError> {
+    // ~ binary garbage is rejected by the plausibility check rather
+    // than as invalid UTF-8
+    let input = try!(TextInput::read_file(filename));
+    let text = input.as_str();
+    let format = format.unwrap_or_else(|| if coords_sniff(&text) { Format::Coords } else { Format::Cells });
+    let ws = if format == Format::Coords {
+        Workspace::new(try!(coords_parse(text)))
+    } else {
+        try!(cells_parse_workspace(text, max_line_length))
+    };
+    // ~ most likely not what was meant to be loaded, e.g. a file of
+    // comments only; unnoticed it would run an empty simulation
+    if ws.world.is_empty() && !allow_empty {
+        return Err(ParseError::EmptyPattern);
+    }
+    Ok(ws)
+}
+
+/// Parses a world in the plaintext format from the given string, e.g.
+/// a pattern bundled with the program.
+pub fn parse_cells(world: &str) -> Result<World, ParseError> {
+    cells_parse_workspace(world, DEFAULT_MAX_LINE_LENGTH).map(|ws| ws.world)
+}
+
+/// Saves the given world in the plaintext format to the given
+/// filename, preceded by the given comment lines.
+pub fn save_to_file(filename: &str, world: &World, comments: &[&str]) -> Result<(), String> {
+    save_workspace(filename, &Workspace::new(world.clone()), comments)
+}
+
+/// Saves the given world like `save_to_file` followed by a comment
+/// line `!label X,Y TEXT` for each of its labels.
+pub fn save_workspace(filename: &str, ws: &Workspace, comments: &[&str]) -> Result<(), String> {
+    io_util::atomic_write(filename,
+                          |mut out| cells_write(&mut out, &ws.world, &ws.labels, comments, false))
+        .map_err(|e| format!("{}: {}", filename, e))
+}
+
+/// Renders the given world in the plaintext format like
+/// `save_to_file`, except for rows without any live cells being
+/// written as `.` rather than as blank lines; a blank line thus safely
+/// separates worlds rendered one after the other.
+pub fn render_cells(world: &World, comments: &[&str]) -> String {
+    let mut out = Vec::new();
+    // ~ writing to memory does not fail
+    cells_write(&mut out, world, &Labels::new(), comments, true).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Saves the live cells of the given world to the given filename as a
+/// coordinate list: one `x,y` pair per line in row-major order,
+/// preceded by the given comment lines (as `# ...`).  Unless the
+/// world's origin (see `World::origin`) is at (0, 0), a comment line
+/// `# offset X,Y` gives it; loading the list adds it to the
+/// coordinates again.
+pub fn save_coords(filename: &str, world: &World, comments: &[&str]) -> Result<(), String> {
+    io_util::atomic_write(filename, |mut out| coords_write(&mut out, world, comments))
+        .map_err(|e| format!("{}: {}", filename, e))
+}
+
+/// Renders the given world like `save_coords`.
+pub fn render_coords(world: &World, comments: &[&str]) -> String {
+    let mut out = Vec::new();
+    // ~ writing to memory does not fail
+    coords_write(&mut out, world, comments).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// The longest line `render_rle` writes for the encoded cells.
+const RLE_LINE_LENGTH: usize = 70;
+
+/// Renders the live cells of the given world in the run length encoded
+/// (RLE) format understood by most other Life programs: trimmed to the
+/// bounding box of the live cells (see `World::live_bounds`), with a
+/// header giving its size and the world's rule.  Only writing is
+/// supported; see `ParseError::UnsupportedFormat`.
+pub fn render_rle(world: &World) -> String {
+    let (x0, y0, width, height) = world.live_bounds().unwrap_or((0, 0, 0, 0));
+    let mut runs = Vec::new();
+    for h in y0..y0 + height {
+        if h > y0 {
+            rle_push(&mut runs, '$');
+        }
+        for w in x0..x0 + width {
+            rle_push(&mut runs, if world.is_alive(w, h) { 'o' } else { 'b' });
+        }
+        // ~ dead cells up to the end of a row go without saying
+        if let Some(&(_, 'b')) = runs.last() {
+            runs.pop();
+        }
+    }
+    runs.push((1, '!'));
+
+    let mut out = format!("x = {}, y = {}, rule = {}\n", width, height, world.rule());
+    let mut line = String::with_capacity(RLE_LINE_LENGTH);
+    for (n, tag) in runs {
+        let item = if n == 1 { tag.to_string() } else { format!("{}{}", n, tag) };
+        if line.len() + item.len() > RLE_LINE_LENGTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&item);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+fn rle_push(runs: &mut Vec<(usize, char)>, tag: char) {
+    match runs.last_mut() {
+        Some(&mut (ref mut n, t)) if t == tag => *n += 1,
+        _ => runs.push((1, tag)),
+    }
+}
+
+/// The extensions (without the dot) of the files `export` writes.
+pub const EXPORT_EXTENSIONS: [&'static str; 5] = ["cells", "cgolb", "csv", "coords", "rle"];
+
+/// Saves the given workspace to the given filename in the format told
+/// by its extension: in the binary format for `BINARY_EXTENSION`, as a
+/// coordinate list for `COORDS_EXTENSIONS`, in the RLE format (see
+/// `render_rle`) for `.rle`, and in the plaintext format for `.cells`,
+/// the latter two being preceded by the given comment lines (as `#C`
+/// lines in RLE.)  Only the plaintext format keeps the labels; other
+/// extensions are refused.
+pub fn export(filename: &str, ws: &Workspace, comments: &[&str]) -> Result<(), String> {
+    if filename.ends_with(".rle") {
+        let mut rle = String::new();
+        for c in comments {
+            rle.push_str(&format!("#C {}\n", c));
+        }
+        rle.push_str(&render_rle(&ws.world));
+        return io_util::atomic_write(filename, |out| out.write_all(rle.as_bytes()))
+                   .map_err(|e| format!("{}: {}", filename, e));
+    }
+    match Format::from_filename(filename) {
+        Some(Format::Cells) => save_workspace(filename, ws, comments),
+        Some(Format::Coords) => save_coords(filename, &ws.world, comments),
+        Some(Format::Binary) => {
+            io_util::atomic_write(filename, |mut out| ws.world.save_binary(&mut out))
+                .map_err(|e| format!("{}: {}", filename, e))
+        }
+        None => {
+            Err(format!("{}: unknown format (use .{})", filename, EXPORT_EXTENSIONS.join(", .")))
+        }
+    }
+}
+
+/// Checks the given file in the plaintext format to be in canonical
+/// form; reports all deviations at once as `ParseError::Lints`.  This
+/// is stricter than (and independent of) loading the file, which
+/// still may fail afterwards.  The file's text is read as for loading
+/// it (see `TextInput`), hence its line endings and a byte order mark
+/// do not matter.  Files in the binary or the coordinate list format
+/// always pass; the format is determined as for `load_workspace_as`.
+pub fn lint_file(filename: &str, format: Option<Format>) -> Result<(), ParseError> {
+    let format = format.or_else(|| Format::from_filename(filename));
+    if format == Some(Format::Binary) {
+        return Ok(());
+    }
+    let input = try!(TextInput::read_file(filename));
+    if format.unwrap_or_else(|| if coords_sniff(input.as_str()) { Format::Coords } else { Format::Cells }) ==
+       Format::Coords {
+        return Ok(());
+    }
+    let lints = cells_lint(input.as_str());
+    if lints.is_empty() {
+        Ok(())
+    } else {
+        Err(ParseError::Lints(lints))
+    }
+}
+
+/// Rewrites the given file in the plaintext format in canonical form
+/// (see `cells_fix`) provided it loads as with `load_world`; returns
+/// whether the file was changed.  Its line endings become LF, and any
+/// byte order mark is dropped.  Files not valid in UTF-8 (e.g. with
+/// comments in Latin-1) are refused rather than having their comments
+/// garbled.
+pub fn fix_file(filename: &str, max_line_length: usize) -> Result<bool, ParseError> {
+    let mut f = try!(File::open(filename));
+    let mut buf = Vec::new();
+    try!(f.read_to_end(&mut buf));
+    if let Err(e) = str::from_utf8(&buf) {
+        // ~ the position of the first invalid byte within the text as
+        // read for loading
+        let valid = TextInput::from_bytes(&buf[..e.valid_up_to()]);
+        let text = valid.as_str();
+        let line = text.rsplit('\n').next().unwrap_or("");
+        return Err(ParseError::Syntax {
+            row: text.matches('\n').count() + 1,
+            col: line.chars().count() + 1,
+            reason: "Byte not valid in UTF-8; not fixing the file to keep its encoding".to_owned(),
+        });
+    }
+    let input = TextInput::from_bytes(&buf);
+    try!(cells_parse_workspace(input.as_str(), max_line_length));
+    let fixed = cells_fix(input.as_str());
+    if fixed.as_bytes() == &buf[..] {
+        return Ok(false);
+    }
+    try!(io_util::atomic_write(filename, |out| out.write_all(fixed.as_bytes())));
+    Ok(true)
 }
 
 // --------------------------------------------------------------------
 
+/// The reasons for failing to load a world.
 #[derive(Debug)]
-struct Error {
-    row: usize,
-    col: usize,
-    reason: String,
+pub enum ParseError {
+    /// the file could not be read
+    Io(io::Error),
+    /// the file is malformed at the given (1-based) row and column
+    Syntax {
+        row: usize,
+        col: usize,
+        reason: String,
+    },
+    /// the file is in a format other than plaintext, e.g. RLE
+    UnsupportedFormat,
+    /// the world would have more than `world::MAX_CELLS` cells
+    TooLarge,
+    /// the pattern has no live cells, e.g. the file has only comments
+    /// or blank lines
+    EmptyPattern,
+    /// the (1-based) row is `len` characters long; longer than the
+    /// allowed `max`
+    LineTooLong { row: usize, len: usize, max: usize },
+    /// only `percent` percent of the characters are valid in the
+    /// plaintext format; it is likely some other kind of file
+    Implausible { percent: usize },
+    /// the file is not in the canonical form required by
+    /// `lint_file`; all of its problems in the order of their
+    /// appearance
+    Lints(Vec<Lint>),
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ParseError::Io(ref e) => write!(fmt, "{}", e),
+            ParseError::Syntax { row, col, ref reason } => write!(fmt, "{}:{}: {}", row, col, reason),
+            ParseError::UnsupportedFormat => write!(fmt, "Unsupported format (expected plaintext)"),
+            ParseError::TooLarge => write!(fmt, "World too large"),
+            ParseError::EmptyPattern => write!(fmt, "No live cells in the pattern"),
+            ParseError::LineTooLong { row, len, max } => {
+                write!(fmt, "{}: Line too long: {} characters (at most {})", row, len, max)
+            }
+            ParseError::Implausible { percent } => {
+                write!(fmt,
+                       "This does not look like a plaintext (.cells) file: only {}% of its \
+                        characters are '.' or 'O'",
+                       percent)
+            }
+            ParseError::Lints(ref lints) => {
+                try!(write!(fmt, "{} formatting problem(s):", lints.len()));
+                for lint in lints {
+                    try!(write!(fmt, "\n{}", lint));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ParseError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A deviation of a plaintext file from the canonical form at the
+/// given (1-based) row and column.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lint {
+    pub row: usize,
+    pub col: usize,
+    pub kind: LintKind,
+}
+
+/// The deviations from the canonical form reported by `lint_file`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LintKind {
+    /// whitespace at the end of a line of the pattern
+    TrailingWhitespace,
+    /// a tab within a line of the pattern
+    Tab,
+    /// the live cell `glyph` in a file marking live cells with `first`
+    /// elsewhere
+    MixedGlyphs { glyph: char, first: char },
+    /// a line of the pattern `len` characters long; longer than the
+    /// `width` of the first one
+    Ragged { len: usize, width: usize },
+    /// the last line does not end with a newline
+    NoFinalNewline,
 }
 
-impl fmt::Display for Error {
+impl fmt::Display for Lint {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(fmt, "{}:{}: {}", self.row, self.col, self.reason)
+        try!(write!(fmt, "{}:{}: ", self.row, self.col));
+        match self.kind {
+            LintKind::TrailingWhitespace => write!(fmt, "Trailing whitespace; remove it"),
+            LintKind::Tab => write!(fmt, "Tab; use '.' for dead cells"),
+            LintKind::MixedGlyphs { glyph, first } => {
+                write!(fmt,
+                       "Live cell '{}' after '{}'; use 'O' for all live cells",
+                       glyph,
+                       first)
+            }
+            LintKind::Ragged { len, width } => {
+                write!(fmt,
+                       "Line of {} characters, wider than the first one ({}); pad all lines \
+                        with '.' to the same width",
+                       len,
+                       width)
+            }
+            LintKind::NoFinalNewline => write!(fmt, "No newline at the end of the file; add one"),
+        }
     }
 }
 
 // --------------------------------------------------------------------
 
+/// Determines the number of header lines of the given world, i.e. the
+/// leading comment lines and any blank lines mixed in with them.
+fn cells_header_len(world: &str) -> usize {
+    world.lines()
+         .take_while(|line| line.chars().next() == Some('!') || line.trim().is_empty())
+         .count()
+}
+
 /// Scans and determines the dimension of the given world as a
 /// `(width, height)` tuple.
 fn cells_dimension(world: &str) -> (usize, usize) {
     world.lines()
-         .skip_while(|line| line.chars().next() == Some('!'))
+         .skip(cells_header_len(world))
          .enumerate()
          .map(|(line_i, line)| {
              (// width; 1 + `index of the last 'O' char in this line`
-              line.chars()
-                  .enumerate()
-                  .filter_map(|(i, c)| {
-                      if c == 'O' {
-                          Some(i + 1)
-                      } else {
-                          None
-                      }
-                  })
-                  .max()
-                  .unwrap_or(0),
+              line.bytes().rposition(|b| b == b'O').map_or(0, |i| i + 1),
               // heigth; 1 + `index of this line`
               line_i + 1)
          })
@@ -79,26 +509,763 @@ fn test_cells_dimension() {
 O"#));
 }
 
-fn cells_parse(world: &str) -> Result<World, Error> {
+/// Determines whether the given world looks like one in another
+/// format, i.e. starts (after the comments) with an RLE comment or
+/// header line.
+fn cells_foreign(world: &str) -> bool {
+    match world.lines().skip(cells_header_len(world)).next() {
+        None => false,
+        Some(line) => {
+            line.starts_with('#') ||
+            (line.starts_with('x') && line[1..].trim_start().starts_with('='))
+        }
+    }
+}
+
+/// Checks the pattern lines of the given world (i.e. following the
+/// header) to be made up mostly of valid characters, to be ASCII, and
+/// to be at most `max_line_length` characters long.
+fn cells_check(world: &str, max_line_length: usize) -> Result<(), ParseError> {
+    let header_len = cells_header_len(world);
+    let (mut valid, mut total) = (0, 0);
+    for line in world.lines().skip(header_len) {
+        for c in line.chars().filter(|c| !c.is_whitespace()) {
+            total += 1;
+            if c == '.' || c == 'O' {
+                valid += 1;
+            }
+        }
+    }
+    if total >= PLAUSIBLE_MIN_CHARS && valid * 100 < total * PLAUSIBLE_PERCENT {
+        return Err(ParseError::Implausible { percent: valid * 100 / total });
+    }
+    for (line_i, line) in world.lines().skip(header_len).enumerate() {
+        if let Some((col, reason)) = text::non_ascii(line) {
+            return Err(ParseError::Syntax {
+                row: header_len + line_i + 1,
+                col: col,
+                reason: reason,
+            });
+        }
+        let len = line.len();
+        if len > max_line_length {
+            return Err(ParseError::LineTooLong {
+                row: header_len + line_i + 1,
+                len: len,
+                max: max_line_length,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Reads the labels of the given world's header (see `LABEL_COMMENT`);
+/// at the coordinates within the pattern.
+fn cells_labels(world: &str) -> Result<Vec<(usize, usize, String)>, ParseError> {
+    let mut labels = Vec::new();
+    for (line_i, line) in world.lines().take(cells_header_len(world)).enumerate() {
+        if !line.starts_with(LABEL_COMMENT) {
+            continue;
+        }
+        let syntax = |reason: String| {
+            ParseError::Syntax {
+                row: line_i + 1,
+                col: LABEL_COMMENT.len() + 1,
+                reason: reason,
+            }
+        };
+        let rest = line[LABEL_COMMENT.len()..].trim_start();
+        let (coords, label) = match rest.find(char::is_whitespace) {
+            Some(i) => (&rest[..i], rest[i..].trim()),
+            None => (rest, ""),
+        };
+        let mut parts = coords.splitn(2, ',');
+        let (x, y) = match (parts.next().and_then(|x| x.parse().ok()),
+                            parts.next().and_then(|y| y.parse().ok())) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(syntax(format!("Invalid label coordinates: {}", coords))),
+        };
+        try!(workspace::check_label(label).map_err(syntax));
+        labels.push((x, y, label.to_owned()));
+    }
+    Ok(labels)
+}
+
+// ~ parses a plaintext world dropping its labels
+#[cfg(test)]
+fn cells_parse(world: &str, max_line_length: usize) -> Result<World, ParseError> {
+    cells_parse_workspace(world, max_line_length).map(|ws| ws.world)
+}
+
+fn cells_parse_workspace(world: &str, max_line_length: usize) -> Result<Workspace, ParseError> {
+    if cells_foreign(world) {
+        return Err(ParseError::UnsupportedFormat);
+    }
+    try!(cells_check(world, max_line_length));
+    let labels = try!(cells_labels(world));
     let mut w = {
-        let dim = cells_dimension(world);
+        // ~ labeled cells are part of the pattern even if dead
+        let dim = labels.iter().fold(cells_dimension(world), |(w, h), &(x, y, _)| {
+            (cmp::max(w, x.saturating_add(1)), cmp::max(h, y.saturating_add(1)))
+        });
+        if dim.0.saturating_add(2).saturating_mul(dim.1.saturating_add(2)) > world::MAX_CELLS {
+            return Err(ParseError::TooLarge);
+        }
         World::empty(dim.0 + 2, dim.1 + 2)
     };
+    let header_len = cells_header_len(world);
     let mut lines = world.lines()
-                         .skip_while(|line| line.chars().next() == Some('!'))
+                         .skip(header_len)
                          .enumerate();
+    // ~ the lines are ASCII (see `cells_check`); a byte per column
     while let Some((line_i, line)) = lines.next() {
-        for (col_i, c) in line.chars().enumerate() {
-            match c {
-                'O' => w.set_alive(col_i + 1, line_i + 1, true),
-                '.' => {}
-                c => return Err(Error {
-                    row: line_i + 1, // XXX this is not fully correct due to the skipped header
+        for (col_i, b) in line.bytes().enumerate() {
+            match b {
+                b'O' => w.set_alive(col_i + 1, line_i + 1, true),
+                b'.' => {}
+                b => return Err(ParseError::Syntax {
+                    row: header_len + line_i + 1,
                     col: col_i + 1,
-                    reason: format!("Invalid character: {}", c),
+                    reason: format!("Invalid character: {}", (b as char).escape_default()),
                 }),
             }
         }
     }
+    // ~ a fresh pattern rather than an edited world
+    w.restart();
+    let mut ws = Workspace::new(w);
+    for (x, y, label) in labels {
+        // ~ checked above
+        ws.labels.set(x + 1, y + 1, &label).unwrap();
+    }
+    Ok(ws)
+}
+
+fn cells_write<W: Write>(out: &mut W,
+                         world: &World,
+                         labels: &Labels,
+                         comments: &[&str],
+                         mark_empty_rows: bool)
+                         -> io::Result<()> {
+    for comment in comments {
+        try!(writeln!(out, "!{}", comment));
+    }
+    for (x, y, label) in labels.iter() {
+        try!(writeln!(out, "{}{},{} {}", LABEL_COMMENT, x, y, label));
+    }
+    let mut line = String::with_capacity(world.width());
+    for h in 0..world.height() {
+        line.clear();
+        for w in 0..world.width() {
+            line.push(if world.is_alive(w, h) { 'O' } else { '.' });
+        }
+        let line = line.trim_end_matches('.');
+        // ~ blank lines would be taken for part of the header; the
+        // labels' lines would no longer match those of the pattern
+        if line.is_empty() && (mark_empty_rows || !labels.is_empty()) {
+            try!(writeln!(out, "."));
+        } else {
+            try!(writeln!(out, "{}", line));
+        }
+    }
+    Ok(())
+}
+
+// ~ the coordinate list format is line oriented: blank lines and lines
+// starting with '#' are skipped, except for the offset comment applying
+// to the pairs following it.  a header row (e.g. `x,y`) may precede the
+// first pair
+fn coords_parse(list: &str) -> Result<World, ParseError> {
+    let mut offset = (0, 0);
+    let mut cells = Vec::new();
+    let mut seen_header = false;
+    for (line_i, line) in list.lines().enumerate() {
+        let (col, line) = (line.len() - line.trim_start().len() + 1, line.trim());
+        let syntax = |reason: String| {
+            ParseError::Syntax {
+                row: line_i + 1,
+                col: col,
+                reason: reason,
+            }
+        };
+        if line.starts_with(COORDS_OFFSET_COMMENT) {
+            offset = try!(coords_pair(&line[COORDS_OFFSET_COMMENT.len()..]).map_err(&syntax));
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((i, reason)) = text::non_ascii(line) {
+            return Err(ParseError::Syntax {
+                row: line_i + 1,
+                col: col + i - 1,
+                reason: reason,
+            });
+        }
+        match coords_pair(line) {
+            Ok((x, y)) => cells.push((x + offset.0, y + offset.1)),
+            Err(_) if cells.is_empty() && !seen_header && coords_header(line) => seen_header = true,
+            Err(reason) => return Err(syntax(reason)),
+        }
+    }
+    let (x0, y0) = match (cells.iter().map(|c| c.0).min(), cells.iter().map(|c| c.1).min()) {
+        (Some(x0), Some(y0)) => (x0, y0),
+        _ => return Ok(World::empty(2, 2)),
+    };
+    let (x1, y1) = (cells.iter().map(|c| c.0).max().unwrap(), cells.iter().map(|c| c.1).max().unwrap());
+    // ~ the coordinates fit into `i32`; their differences into `isize`
+    let (width, height) = ((x1 - x0) as usize + 3, (y1 - y0) as usize + 3);
+    if width.saturating_mul(height) > world::MAX_CELLS {
+        return Err(ParseError::TooLarge);
+    }
+    // ~ translated to the bounding box surrounded by a dead cell; the
+    // origin keeps the given coordinates
+    // ~ within the bounding box; duplicates change nothing
+    let coords = cells.into_iter().map(|(x, y)| ((x - x0) as usize + 1, (y - y0) as usize + 1));
+    let mut w = World::from_coords(width, height, coords).unwrap();
+    w.set_origin((x0 - 1, y0 - 1));
     Ok(w)
 }
+
+// ~ parses an `x,y` pair; the coordinates are limited to `i32` to
+// keep the world's dimensions and the offsets within bounds
+fn coords_pair(s: &str) -> Result<(isize, isize), String> {
+    let mut fields = s.split(',');
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(x), Some(y), None) => Ok((try!(coords_value(x.trim())), try!(coords_value(y.trim())))),
+        _ => Err(format!("Expected x,y: {}", s)),
+    }
+}
+
+fn coords_value(s: &str) -> Result<isize, String> {
+    match s.parse::<i32>() {
+        Ok(v) => Ok(v as isize),
+        Err(_) => {
+            let digits = s.trim_start_matches('-');
+            if !digits.is_empty() && digits.chars().all(|c| c.is_digit(10)) && s.len() - digits.len() <= 1 {
+                Err(format!("Coordinate out of range: {}", s))
+            } else {
+                Err(format!("Invalid coordinate: {}", s))
+            }
+        }
+    }
+}
+
+// ~ tells a header row naming the columns, e.g. `x,y`, from garbage
+fn coords_header(line: &str) -> bool {
+    let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+    fields.len() == 2 &&
+    fields.iter().all(|f| f.chars().next().map_or(false, |c| c.is_alphabetic()))
+}
+
+// ~ determines whether the given text starts (apart from comments and
+// a header row) with an `x,y` pair; lines of the plaintext format never
+// hold a comma
+fn coords_sniff(text: &str) -> bool {
+    let mut lines = text.lines()
+                        .map(|l| l.trim())
+                        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    match lines.next() {
+        Some(l) if coords_header(l) => lines.next().map_or(false, |l| coords_pair(l).is_ok()),
+        Some(l) => coords_pair(l).is_ok(),
+        None => false,
+    }
+}
+
+fn coords_write<W: Write>(out: &mut W, world: &World, comments: &[&str]) -> io::Result<()> {
+    for comment in comments {
+        try!(writeln!(out, "# {}", comment));
+    }
+    let origin = world.origin();
+    if origin != (0, 0) {
+        try!(writeln!(out, "{}{},{}", COORDS_OFFSET_COMMENT, origin.0, origin.1));
+    }
+    for (x, y) in world.live_cells() {
+        try!(writeln!(out, "{},{}", x, y));
+    }
+    Ok(())
+}
+
+/// Determines the deviations of the given world from the canonical
+/// plaintext form: the pattern lines (following the header) are to be
+/// free of tabs and trailing whitespace, mark live cells with one glyph
+/// only, and be no wider than the first one; the last line is to end
+/// with a newline.
+fn cells_lint(world: &str) -> Vec<Lint> {
+    let header_len = cells_header_len(world);
+    let mut lints = Vec::new();
+    let mut width = None;
+    let mut glyphs: Vec<char> = Vec::new();
+    for (line_i, line) in world.lines().enumerate().skip(header_len) {
+        let lint = |col: usize, kind: LintKind| {
+            Lint {
+                row: line_i + 1,
+                col: col,
+                kind: kind,
+            }
+        };
+        let content = line.trim_end();
+        let len = content.chars().count();
+        if let Some(i) = line.chars().position(|c| c == '\t') {
+            lints.push(lint(i + 1, LintKind::Tab));
+        }
+        for (i, c) in content.chars().enumerate() {
+            if (c == 'O' || c == 'o' || c == '*') && !glyphs.contains(&c) {
+                glyphs.push(c);
+                if glyphs.len() > 1 {
+                    lints.push(lint(i + 1,
+                                    LintKind::MixedGlyphs {
+                                        glyph: c,
+                                        first: glyphs[0],
+                                    }));
+                }
+            }
+        }
+        match width {
+            None => width = Some(len),
+            Some(width) if len > width => {
+                lints.push(lint(width + 1,
+                                LintKind::Ragged {
+                                    len: len,
+                                    width: width,
+                                }))
+            }
+            Some(_) => {}
+        }
+        if content.len() < line.len() {
+            lints.push(lint(len + 1, LintKind::TrailingWhitespace));
+        }
+    }
+    if !world.is_empty() && !world.ends_with('\n') {
+        let last = world.lines().last().unwrap_or("");
+        lints.push(Lint {
+            row: world.lines().count(),
+            col: last.chars().count() + 1,
+            kind: LintKind::NoFinalNewline,
+        });
+    }
+    lints.sort_by_key(|l| (l.row, l.col));
+    lints
+}
+
+/// Rewrites the given world in canonical plaintext form: the header
+/// lines are kept as they are, the pattern lines are padded with `.` to
+/// the width of the widest one, live cells are marked with `O`, and
+/// all lines end with a single LF.  Only worlds accepted by
+/// `cells_parse_workspace` keep their cells.
+fn cells_fix(world: &str) -> String {
+    let header_len = cells_header_len(world);
+    let width = world.lines()
+                     .skip(header_len)
+                     .map(|line| line.trim_end().chars().count())
+                     .max()
+                     .unwrap_or(0);
+    let mut fixed = String::with_capacity(world.len());
+    for (line_i, line) in world.lines().enumerate() {
+        if line_i < header_len {
+            fixed.push_str(line);
+        } else {
+            let line = line.trim_end();
+            fixed.extend(line.chars().map(|c| if c == 'o' || c == '*' { 'O' } else { c }));
+            for _ in line.chars().count()..width {
+                fixed.push('.');
+            }
+        }
+        fixed.push('\n');
+    }
+    fixed
+}
+
+#[test]
+fn test_cells_blank_header_lines() {
+    let s = "!a comment\n\n!another one\n   \n.O\n\nO.O";
+    assert_eq!((3, 3), cells_dimension(s));
+    let w = cells_parse(s, DEFAULT_MAX_LINE_LENGTH).unwrap();
+    assert_eq!((5, 5), (w.width(), w.height()));
+    assert_eq!(3, w.alive());
+    // ~ a fresh world, not an edited one
+    assert_eq!((0, false), (w.generation(), w.is_dirty()));
+    assert!(w.is_alive(2, 1));
+    assert!(w.is_alive(1, 3));
+    assert!(w.is_alive(3, 3));
+
+    // ~ errors refer to the line number within the file
+    match cells_parse("!comment\n\n.O\nx", DEFAULT_MAX_LINE_LENGTH) {
+        Err(ParseError::Syntax { row: 4, col: 1, ref reason }) => {
+            assert_eq!("Invalid character: x", reason)
+        }
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    let e = cells_parse("!glider\n.O\n..O\nO0O", DEFAULT_MAX_LINE_LENGTH).unwrap_err();
+    assert_eq!("4:2: Invalid character: 0", e.to_string());
+    match cells_parse("#N Glider\nx = 3, y = 3\nbo$2bo$3o!", DEFAULT_MAX_LINE_LENGTH) {
+        Err(ParseError::UnsupportedFormat) => {}
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+    match cells_parse("!a comment\nx=3, y=3, rule=B3/S23\nbo$2bo$3o!", DEFAULT_MAX_LINE_LENGTH) {
+        Err(ParseError::UnsupportedFormat) => {}
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+    let huge = format!("{}O\n", ".".repeat(1 << 16)) + &"\n".repeat(1 << 15);
+    match cells_parse(&huge, DEFAULT_MAX_LINE_LENGTH) {
+        Err(ParseError::TooLarge) => {}
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+    match load_from_file("/nonexistent/glider.cells", DEFAULT_MAX_LINE_LENGTH, false) {
+        Err(ParseError::Io(ref e)) if e.kind() == io::ErrorKind::NotFound => {}
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+}
+
+#[test]
+fn test_parse_limits() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    // ~ a very wide row just under and just over the limit
+    let wide = |n: usize| format!("!wide\n.O\n{}O\n", ".".repeat(n - 1));
+    let w = cells_parse(&wide(DEFAULT_MAX_LINE_LENGTH), DEFAULT_MAX_LINE_LENGTH).unwrap();
+    assert_eq!(DEFAULT_MAX_LINE_LENGTH + 2, w.width());
+    let e = cells_parse(&wide(DEFAULT_MAX_LINE_LENGTH + 1), DEFAULT_MAX_LINE_LENGTH).unwrap_err();
+    assert_eq!("3: Line too long: 100001 characters (at most 100000)", e.to_string());
+    match cells_parse(&wide(11), 10) {
+        Err(ParseError::LineTooLong { row: 3, len: 11, max: 10 }) => {}
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+
+    let file = env::temp_dir().join(format!("cgol-test-limits-{}.cells", process::id()));
+    let filename = file.to_str().unwrap();
+    // ~ a minified JSON document
+    fs::write(&file, r#"{"world":{"width":3,"height":3,"cells":[".O.","..O","OOO"]}}"#).unwrap();
+    let e = load_from_file(filename, DEFAULT_MAX_LINE_LENGTH, false).unwrap_err();
+    assert_eq!("This does not look like a plaintext (.cells) file: only 15% of its characters \
+                are '.' or 'O'",
+               e.to_string());
+    // ~ a binary file; neither valid UTF-8 nor plaintext
+    let mut buf = Vec::new();
+    World::random_seeded(1, 40, 40).save_binary(&mut buf).unwrap();
+    fs::write(&file, &buf).unwrap();
+    match load_from_file(filename, DEFAULT_MAX_LINE_LENGTH, false) {
+        Err(ParseError::Implausible { percent }) => assert!(percent < 10, "{}%", percent),
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+    // ~ a short file with a typo still reports the offending character
+    fs::write(&file, "!glider\n.O\n..O\nOO0\n").unwrap();
+    assert_eq!("4:3: Invalid character: 0",
+               load_from_file(filename, DEFAULT_MAX_LINE_LENGTH, false).unwrap_err().to_string());
+    let _ = fs::remove_file(&file);
+}
+
+#[test]
+fn test_empty_pattern() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    let file = env::temp_dir().join(format!("cgol-test-empty-{}.cells", process::id()));
+    let filename = file.to_str().unwrap();
+    // ~ comments only, blank lines only, and dead cells only; the
+    // latter as high as its lines, and as large as required by a label
+    for &(pattern, width, height) in &[("!just\n!comments\n", 2, 2),
+                                       ("\n  \n\t\n", 2, 2),
+                                       ("!empty\n.....\n..\n...\n", 2, 5),
+                                       ("!label 6,3 spot\n.\n", 9, 6)] {
+        fs::write(&file, pattern).unwrap();
+        match load_from_file(filename, DEFAULT_MAX_LINE_LENGTH, false) {
+            Err(ParseError::EmptyPattern) => {}
+            r => panic!("unexpected result for {:?}: {:?}", pattern, r.err()),
+        }
+        let w = load_world(filename, DEFAULT_MAX_LINE_LENGTH, true).unwrap();
+        assert_eq!((width, height, 0), (w.width(), w.height(), w.alive()), "{:?}", pattern);
+    }
+    assert_eq!("No live cells in the pattern", ParseError::EmptyPattern.to_string());
+
+    // ~ an empty world saved in the binary format is what it claims to
+    // be
+    let file = env::temp_dir().join(format!("cgol-test-empty-{}{}", process::id(), BINARY_EXTENSION));
+    let mut buf = Vec::new();
+    World::empty(4, 3).save_binary(&mut buf).unwrap();
+    fs::write(&file, &buf).unwrap();
+    assert!(load_world(file.to_str().unwrap(), DEFAULT_MAX_LINE_LENGTH, false).unwrap().is_empty());
+    let _ = fs::remove_file(&file);
+    let _ = fs::remove_file(env::temp_dir().join(format!("cgol-test-empty-{}.cells", process::id())));
+}
+
+#[test]
+fn test_cells_write() {
+    let w = cells_parse("!a comment\n.O\n..O\nOOO", DEFAULT_MAX_LINE_LENGTH).unwrap();
+    let mut out = Vec::new();
+    cells_write(&mut out, &w, &Labels::new(), &["glider"], false).unwrap();
+    let s = String::from_utf8(out).unwrap();
+    assert_eq!("!glider\n\n..O\n...O\n.OOO\n\n", s);
+    assert_eq!("!gen 0\n.\n..O\n...O\n.OOO\n.\n", render_cells(&w, &["gen 0"]));
+}
+
+#[test]
+fn test_render_rle() {
+    let mut w = cells_parse("!a comment\n.O\n..O\nOOO", DEFAULT_MAX_LINE_LENGTH).unwrap();
+    assert_eq!("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n", render_rle(&w));
+    w.set_rule(world::Rule::parse("B36/S23").unwrap());
+    assert_eq!("x = 3, y = 3, rule = B36/S23\nbo$2bo$3o!\n", render_rle(&w));
+    // ~ empty rows merge into the end of the row before
+    let w = cells_parse("O..O\n....\n.OO", DEFAULT_MAX_LINE_LENGTH).unwrap();
+    assert_eq!("x = 4, y = 3, rule = B3/S23\no2bo2$b2o!\n", render_rle(&w));
+    assert_eq!("x = 0, y = 0, rule = B3/S23\n!\n", render_rle(&World::empty(3, 3)));
+    // ~ long lines are wrapped between runs
+    let w = cells_parse(&"O.".repeat(40), 100).unwrap();
+    let rle = render_rle(&w);
+    let lines: Vec<_> = rle.lines().collect();
+    assert_eq!(vec!["x = 79, y = 1, rule = B3/S23", &"ob".repeat(35)[..], "obobobobo!"], lines);
+}
+
+#[test]
+fn test_coords() {
+    // ~ a header, comments, blank lines, and a duplicate
+    let w = coords_parse("x,y\n# a glider\n1,0\n\n 2 , 1\n0,2\n1,2\n2,2\n2,2\n").unwrap();
+    assert_eq!((5, 5, 5, (-1, -1)), (w.width(), w.height(), w.alive(), w.origin()));
+    assert_eq!("# offset -1,-1\n2,1\n3,2\n1,3\n2,3\n3,3\n", render_coords(&w, &[]));
+    assert_eq!(".\n..O\n...O\n.OOO\n.\n", render_cells(&w, &[]));
+    // ~ round trips keep the coordinates
+    let text = render_coords(&w, &["generation 0"]);
+    assert!(text.starts_with("# generation 0\n"));
+    let again = coords_parse(&text).unwrap();
+    assert_eq!((w.origin(), w.live_cells()), (again.origin(), again.live_cells()));
+
+    // ~ negative coordinates move the origin
+    let w = coords_parse("-3,-2\n-2,-2\n").unwrap();
+    assert_eq!((4, 3, (-4, -3)), (w.width(), w.height(), w.origin()));
+    assert_eq!(vec![(1, 1), (2, 1)], w.live_cells());
+    assert_eq!("# offset -4,-3\n1,1\n2,1\n", render_coords(&w, &[]));
+    let w = coords_parse("# offset 10,-5\n0,0\n").unwrap();
+    assert_eq!((9, -6), w.origin());
+    assert_eq!((2, 2, 0), {
+        let w = coords_parse("# nothing\n").unwrap();
+        (w.width(), w.height(), w.alive())
+    });
+
+    for &(list, e) in [("1,2\n3\n", "2:1: Expected x,y: 3"),
+                       ("1,2\n\n1,2,3\n", "3:1: Expected x,y: 1,2,3"),
+                       ("x,y\n1,y\n", "2:1: Invalid coordinate: y"),
+                       ("1,1\nx,y\n", "2:1: Invalid coordinate: x"),
+                       ("1,99999999999\n", "1:1: Coordinate out of range: 99999999999"),
+                       ("# offset a,b\n", "1:1: Invalid coordinate: a"),
+                       ("0,0\n1000000,1000000\n", "World too large")]
+                          .iter() {
+        assert_eq!(e, coords_parse(list).err().unwrap().to_string(), "{:?}", list);
+    }
+}
+
+#[test]
+fn test_export() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    let mut ws = Workspace::new(parse_cells("OO.\n.O.").unwrap());
+    let (x, y) = ws.world.live_cells()[2];
+    ws.labels.set(x, y, "tip").unwrap();
+    let mut trimmed = ws.world.clone();
+    trimmed.trim(0);
+    for ext in &EXPORT_EXTENSIONS {
+        let file = env::temp_dir().join(format!("cgol-test-export-{}.{}", process::id(), ext));
+        let file = file.to_str().unwrap();
+        export(file, &ws, &["exported"]).unwrap();
+        if *ext == "rle" {
+            let mut rle = String::new();
+            File::open(file).unwrap().read_to_string(&mut rle).unwrap();
+            assert_eq!("#C exported\nx = 2, y = 2, rule = B3/S23\n2o$bo!\n", rle);
+        } else {
+            // ~ the formats differ in the margins kept around the cells
+            let mut loaded = load_workspace(file, DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+            assert_eq!(*ext == "cells", loaded.labels.len() == 1, "{}", ext);
+            loaded.world.trim(0);
+            assert_eq!(trimmed.live_cells(), loaded.world.live_cells(), "{}", ext);
+        }
+        let _ = fs::remove_file(file);
+    }
+    let err = export("a/b.txt", &ws, &[]).unwrap_err();
+    assert_eq!("a/b.txt: unknown format (use .cells, .cgolb, .csv, .coords, .rle)", err);
+    let err = export("/nonexistent-cgol-dir/a.cells", &ws, &[]).unwrap_err();
+    assert!(err.starts_with("/nonexistent-cgol-dir/a.cells: "), "{}", err);
+}
+
+#[test]
+fn test_format_detection() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    assert_eq!(Some(Format::Coords), Format::from_filename("a/b.csv"));
+    assert_eq!(Some(Format::Binary), Format::from_filename("b.cgolb"));
+    assert_eq!(None, Format::from_filename("b.txt"));
+    assert!(coords_sniff("# cells\nx,y\n0,1\n"));
+    assert!(coords_sniff("0, 1\n"));
+    assert!(!coords_sniff("!Name: a,b\n.O\n"));
+    assert!(!coords_sniff("x,y\n"));
+
+    // ~ by content, by extension, and as told
+    let file = env::temp_dir().join(format!("cgol-test-coords-{}.txt", process::id()));
+    let file = file.to_str().unwrap();
+    fs::File::create(file).unwrap().write_all(b"0,0\n1,0\n2,0\n").unwrap();
+    assert_eq!(3, load_world(file, DEFAULT_MAX_LINE_LENGTH, false).unwrap().alive());
+    assert!(load_workspace_as(file, Some(Format::Cells), DEFAULT_MAX_LINE_LENGTH, false).is_err());
+    let csv = env::temp_dir().join(format!("cgol-test-coords-{}.csv", process::id()));
+    let csv = csv.to_str().unwrap();
+    save_coords(csv, &parse_cells("OO\nOO").unwrap(), &[]).unwrap();
+    let w = load_world(csv, DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    assert_eq!((4, 4, 4), (w.width(), w.height(), w.alive()));
+    let _ = fs::remove_file(file);
+    let _ = fs::remove_file(csv);
+}
+
+#[test]
+fn test_labels_round_trip() {
+    let s = "!a gun\n!label 1,0 ejector\n!label 4,2   lane 3\n.O\n..O\nOOO";
+    let ws = cells_parse_workspace(s, DEFAULT_MAX_LINE_LENGTH).unwrap();
+    // ~ the dead cell labeled "lane 3" widens the pattern
+    assert_eq!((7, 5), (ws.world.width(), ws.world.height()));
+    assert_eq!(vec![(2, 1, "ejector"), (5, 3, "lane 3")],
+               ws.labels.iter().collect::<Vec<_>>());
+    // ~ labels are comments to a plain parser
+    assert_eq!(5, cells_parse(s, DEFAULT_MAX_LINE_LENGTH).unwrap().alive());
+
+    let mut out = Vec::new();
+    cells_write(&mut out, &ws.world, &ws.labels, &["a gun"], false).unwrap();
+    let written = String::from_utf8(out).unwrap();
+    assert_eq!("!a gun\n!label 2,1 ejector\n!label 5,3 lane 3\n.\n..O\n...O\n.OOO\n.\n", written);
+    // ~ read back with another margin; the labels stay on their cells
+    let v = cells_parse_workspace(&written, DEFAULT_MAX_LINE_LENGTH).unwrap();
+    assert_eq!(vec![(3, 2, "ejector"), (6, 4, "lane 3")],
+               v.labels.iter().collect::<Vec<_>>());
+    assert!(v.world.is_alive(3, 2));
+    assert_eq!(ws.world.alive(), v.world.alive());
+
+    for &(s, reason) in [("!label 1 x\nO", "Invalid label coordinates: 1"),
+                         ("!label a,1 x\nO", "Invalid label coordinates: a,1"),
+                         ("!label 1,1\nO", "Empty label")]
+                            .iter() {
+        match cells_parse_workspace(s, DEFAULT_MAX_LINE_LENGTH) {
+            Err(ParseError::Syntax { row: 1, col: 8, reason: ref r }) => assert_eq!(reason, r),
+            r => panic!("unexpected result: {:?}", r.err()),
+        }
+    }
+    // ~ not labels
+    assert!(cells_parse_workspace("!labels 1,1 x\nO", DEFAULT_MAX_LINE_LENGTH).unwrap().labels.is_empty());
+    match cells_parse_workspace("!label 99999999,99999999 x\nO", DEFAULT_MAX_LINE_LENGTH) {
+        Err(ParseError::TooLarge) => {}
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+}
+
+#[test]
+fn test_cells_lint() {
+    let lints = |s: &str| {
+        cells_lint(s).into_iter().map(|l| (l.row, l.col, l.kind)).collect::<Vec<_>>()
+    };
+    assert!(lints("!glider\n!\n.O.\n..O\nOOO\n").is_empty());
+    assert!(lints("").is_empty());
+    // ~ each problem on its own; the header is not checked
+    assert_eq!(vec![(3, 4, LintKind::TrailingWhitespace)], lints("!a  \n.O.\nOOO  \n"));
+    assert_eq!(vec![(2, 2, LintKind::Tab)], lints(".O.\nO\tO\n"));
+    assert_eq!(vec![(2, 3, LintKind::MixedGlyphs { glyph: 'O', first: '*' })],
+               lints(".*.\n*.O\n"));
+    assert_eq!(vec![(3, 3, LintKind::Ragged { len: 3, width: 2 })],
+               lints("!glider\n.O\n..O\nOO\n"));
+    assert_eq!(vec![(3, 4, LintKind::NoFinalNewline)], lints(".O.\n..O\nOOO"));
+    // ~ all problems at once, in order
+    assert_eq!(vec![(2, 3, LintKind::Tab),
+                    (2, 3, LintKind::TrailingWhitespace),
+                    (3, 1, LintKind::MixedGlyphs { glyph: 'o', first: 'O' }),
+                    (3, 3, LintKind::Ragged { len: 3, width: 2 }),
+                    (4, 1, LintKind::MixedGlyphs { glyph: '*', first: 'O' }),
+                    (4, 3, LintKind::Ragged { len: 3, width: 2 }),
+                    (4, 4, LintKind::NoFinalNewline)],
+               lints(".O\nO.\t\noOO\n*.."));
+    assert_eq!("2 formatting problem(s):\n\
+                1:4: Trailing whitespace; remove it\n\
+                1:5: No newline at the end of the file; add one",
+               ParseError::Lints(cells_lint(".O. ")).to_string());
+}
+
+#[test]
+fn test_cells_fix() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    let s = "!a glider  \r\n!label 1,0 x\r\n\r\n.O\r\n..O\r\nOOO  \r\n\r\nO";
+    let fixed = cells_fix(s);
+    assert_eq!("!a glider  \n!label 1,0 x\n\n.O.\n..O\nOOO\n...\nO..\n", fixed);
+    assert!(cells_lint(&fixed).is_empty());
+    assert_eq!(fixed, cells_fix(&fixed));
+
+    let file = env::temp_dir().join(format!("cgol-test-fix-{}.cells", process::id()));
+    let filename = file.to_str().unwrap();
+    fs::write(&file, "!glider\r\n.O\r\n..O\r\nOOO").unwrap();
+    let before = load_workspace(filename, DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    match lint_file(filename, None) {
+        Err(ParseError::Lints(ref lints)) => assert_eq!(3, lints.len()),
+        r => panic!("unexpected result: {:?}", r.err()),
+    }
+    assert!(fix_file(filename, DEFAULT_MAX_LINE_LENGTH).unwrap());
+    assert_eq!("!glider\n.O.\n..O\nOOO\n", fs::read_to_string(&file).unwrap());
+    assert!(lint_file(filename, None).is_ok());
+    // ~ idempotent and keeping the world
+    assert!(!fix_file(filename, DEFAULT_MAX_LINE_LENGTH).unwrap());
+    let after = load_workspace(filename, DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    assert!(before.world.diff(&after.world).is_empty());
+    // ~ files not loading are left alone
+    fs::write(&file, ".O\nx\n").unwrap();
+    assert!(fix_file(filename, DEFAULT_MAX_LINE_LENGTH).is_err());
+    assert_eq!(".O\nx\n", fs::read_to_string(&file).unwrap());
+    // ~ as are files in another encoding than UTF-8
+    let latin1 = b"!caf\xe9\r\n.O \r\nO.\r\n";
+    fs::write(&file, &latin1[..]).unwrap();
+    match fix_file(filename, DEFAULT_MAX_LINE_LENGTH) {
+        Err(ParseError::Syntax { row: 1, col: 5, .. }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(&latin1[..], &fs::read(&file).unwrap()[..]);
+    // ~ linted as read for loading: a lone CR ends a line, and a byte
+    // order mark is no part of the pattern
+    fs::write(&file, "\u{feff}!glider\r.O.\r..O\rOOO\r").unwrap();
+    assert!(lint_file(filename, None).is_ok());
+    // ~ coordinate lists are no plaintext to lint, be it by the format
+    // given or by their content; a `.cells` name makes them plaintext
+    fs::write(&file, "1,0 \n2,1\n0,2\n1,2\n2,2").unwrap();
+    assert!(lint_file(filename, None).is_err());
+    assert!(lint_file(filename, Some(Format::Coords)).is_ok());
+    let coords = env::temp_dir().join(format!("cgol-test-fix-coords-{}", process::id()));
+    fs::copy(&file, &coords).unwrap();
+    assert!(lint_file(coords.to_str().unwrap(), None).is_ok());
+    let _ = fs::remove_file(&coords);
+    let _ = fs::remove_file(&file);
+}
+
+#[test]
+fn test_text_fixtures() {
+    let fixture = |name: &str| format!("{}/tests/fixtures/text/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let load = |name: &str| load_workspace(&fixture(name), DEFAULT_MAX_LINE_LENGTH, false);
+    // ~ CRLF, CR, a byte order mark, and Latin-1 comments: a glider all
+    let glider = parse_cells(".O\n..O\nOOO\n").unwrap();
+    for name in &["crlf.cells", "cr.cells", "bom.cells", "latin1.cells", "crlf.coords"] {
+        let ws = load(name).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(glider.diff(&ws.world).is_empty(), "{}", name);
+    }
+    // ~ the cells themselves are to be ASCII
+    for &(name, e) in &[("latin1-body.cells", "3:3: Byte not valid in UTF-8 (expected ASCII)"),
+                        ("nul.cells", "3:4: Invalid character: \\u{0}"),
+                        ("latin1-body.coords", "3:4: Byte not valid in UTF-8 (expected ASCII)")] {
+        assert_eq!(e, load(name).err().unwrap().to_string(), "{}", name);
+    }
+    assert_eq!("1:2: Non-ASCII character: é (expected ASCII)",
+               cells_parse(".é\n", DEFAULT_MAX_LINE_LENGTH).unwrap_err().to_string());
+}
+
+#[cfg(test)]
+mod corpus;
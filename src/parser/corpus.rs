@@ -0,0 +1,224 @@
+//! Round trips of the pattern corpus in `tests/corpus/` through the
+//! formats: every fixture is converted along the chain A→B→A for every
+//! ordered pair of formats A and B read as well as written, and the
+//! result is compared with the fixture as loaded.
+//!
+//! The live cells are compared up to a translation, since formats
+//! differ in the margins they keep around the pattern.  The metadata
+//! (see `Meta`) are compared as far as both formats of a chain keep
+//! them.  All differences are collected into one report naming the
+//! fixture, the chain, and the first differing cell or metadata.
+//!
+//! A new format joins the round trips with an entry in `CODECS`.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use workspace::Workspace;
+use super::{export, load_workspace, DEFAULT_MAX_LINE_LENGTH};
+
+const CORPUS_DIR: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+
+/// The metadata of a pattern a format may keep apart from its cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Meta {
+    /// the labels of the cells, relative to the live cells
+    Labels,
+    /// the generation of the world
+    Generation,
+    /// the logical coordinates of the live cells (see `World::origin`)
+    Origin,
+    /// the dimensions of the world
+    Size,
+    /// the rule of the world
+    Rule,
+}
+
+/// A format taking part in the round trips.
+struct Codec {
+    name: &'static str,
+    extension: &'static str,
+    write: fn(&str, &Workspace) -> Result<(), String>,
+    /// `None` for formats only written; they take no part
+    read: Option<fn(&str) -> Result<Workspace, String>>,
+    keeps: &'static [Meta],
+}
+
+const CODECS: &'static [Codec] = &[Codec {
+                                       name: "cells",
+                                       extension: "cells",
+                                       write: write_by_extension,
+                                       read: Some(read_by_extension),
+                                       keeps: &[Meta::Labels],
+                                   },
+                                   Codec {
+                                       name: "coords",
+                                       extension: "coords",
+                                       write: write_by_extension,
+                                       read: Some(read_by_extension),
+                                       keeps: &[Meta::Origin],
+                                   },
+                                   Codec {
+                                       name: "binary",
+                                       extension: "cgolb",
+                                       write: write_by_extension,
+                                       read: Some(read_by_extension),
+                                       keeps: &[Meta::Generation, Meta::Size],
+                                   },
+                                   Codec {
+                                       name: "rle",
+                                       extension: "rle",
+                                       write: write_by_extension,
+                                       read: None,
+                                       keeps: &[Meta::Rule],
+                                   }];
+
+fn write_by_extension(filename: &str, ws: &Workspace) -> Result<(), String> {
+    export(filename, ws, &["corpus round trip"])
+}
+
+fn read_by_extension(filename: &str) -> Result<Workspace, String> {
+    // ~ the corpus holds empty worlds on purpose
+    load_workspace(filename, DEFAULT_MAX_LINE_LENGTH, true).map_err(|e| e.to_string())
+}
+
+// ~ the live cells of the given workspace relative to the top left one
+// (the labels' if there are none), in row-major order, along with that
+fn normalized(ws: &Workspace) -> (Vec<(usize, usize)>, (usize, usize)) {
+    let cells = ws.world.live_cells();
+    let positions: Vec<(usize, usize)> = if cells.is_empty() {
+        ws.labels.iter().map(|(x, y, _)| (x, y)).collect()
+    } else {
+        cells.clone()
+    };
+    let anchor = (positions.iter().map(|c| c.0).min().unwrap_or(0),
+                  positions.iter().map(|c| c.1).min().unwrap_or(0));
+    (cells.into_iter().map(|(x, y)| (x - anchor.0, y - anchor.1)).collect(), anchor)
+}
+
+// ~ renders the given metadata of a workspace whose live cells are
+// anchored at `anchor` (see `normalized`)
+fn describe(ws: &Workspace, meta: Meta, anchor: (usize, usize)) -> String {
+    match meta {
+        Meta::Labels => {
+            let labels: Vec<String> = ws.labels
+                                        .iter()
+                                        .map(|(x, y, l)| {
+                                            format!("{},{} {}",
+                                                    x as isize - anchor.0 as isize,
+                                                    y as isize - anchor.1 as isize,
+                                                    l)
+                                        })
+                                        .collect();
+            labels.join("; ")
+        }
+        Meta::Generation => ws.world.generation().to_string(),
+        Meta::Origin => {
+            let (ox, oy) = ws.world.origin();
+            format!("{},{}", ox + anchor.0 as isize, oy + anchor.1 as isize)
+        }
+        Meta::Size => format!("{}x{}", ws.world.width(), ws.world.height()),
+        Meta::Rule => ws.world.rule().to_string(),
+    }
+}
+
+// ~ the first difference of the round trip from the original, if any
+fn compare(original: &Workspace, result: &Workspace, metas: &[Meta]) -> Option<String> {
+    let ((a, anchor_a), (b, anchor_b)) = (normalized(original), normalized(result));
+    if a != b {
+        let (mut i, mut j) = (0, 0);
+        // ~ both in row-major order; walk them like a merge
+        let key = |c: &(usize, usize)| (c.1, c.0);
+        loop {
+            match (a.get(i), b.get(j)) {
+                (Some(x), Some(y)) if x == y => {
+                    i += 1;
+                    j += 1;
+                }
+                (Some(x), Some(y)) if key(x) < key(y) => {
+                    return Some(format!("cell {},{} alive in the original only", x.0, x.1))
+                }
+                (_, Some(y)) => return Some(format!("cell {},{} alive after the round trip only", y.0, y.1)),
+                (Some(x), None) => return Some(format!("cell {},{} alive in the original only", x.0, x.1)),
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+    for &meta in metas {
+        let (before, after) = (describe(original, meta, anchor_a), describe(result, meta, anchor_b));
+        if before != after {
+            return Some(format!("{:?} differs: `{}` in the original, `{}` after the round trip",
+                                meta,
+                                before,
+                                after));
+        }
+    }
+    None
+}
+
+// ~ writes the given workspace in the given format to a temporary file
+fn write_temp(ws: &Workspace, codec: &Codec, stem: &str) -> Result<PathBuf, String> {
+    let path = env::temp_dir().join(format!("cgol-corpus-{}-{}.{}", stem, process::id(), codec.extension));
+    try!((codec.write)(path.to_str().unwrap(), ws).map_err(|e| format!("writing {}: {}", codec.name, e)));
+    Ok(path)
+}
+
+// ~ converts the given workspace to the formats in turn
+fn convert(ws: &Workspace, chain: &[&Codec], stem: &str) -> Result<Workspace, String> {
+    let mut ws = ws.clone();
+    for codec in chain {
+        let path = try!(write_temp(&ws, codec, stem));
+        // ~ only readable codecs make up chains
+        let read = codec.read.unwrap();
+        let result = read(path.to_str().unwrap()).map_err(|e| format!("reading {}: {}", codec.name, e));
+        let _ = fs::remove_file(&path);
+        ws = try!(result);
+    }
+    Ok(ws)
+}
+
+#[test]
+fn test_corpus_round_trips() {
+    let mut fixtures: Vec<String> = fs::read_dir(CORPUS_DIR)
+                                        .unwrap()
+                                        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+                                        .collect();
+    fixtures.sort();
+    assert!(fixtures.len() >= 12, "{:?}", fixtures);
+    let readable: Vec<&Codec> = CODECS.iter().filter(|c| c.read.is_some()).collect();
+    let mut failures = Vec::new();
+    for name in &fixtures {
+        let original = read_by_extension(&format!("{}/{}", CORPUS_DIR, name))
+                           .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let stem = name.replace('.', "-");
+        // ~ the formats only written are written at least
+        for codec in CODECS.iter().filter(|c| c.read.is_none()) {
+            match write_temp(&original, codec, &stem) {
+                Ok(path) => {
+                    let _ = fs::remove_file(&path);
+                }
+                Err(e) => failures.push(format!("{}, {}: {}", name, codec.name, e)),
+            }
+        }
+        for a in &readable {
+            for b in &readable {
+                let metas: Vec<Meta> = a.keeps.iter().cloned().filter(|m| b.keeps.contains(m)).collect();
+                let chain = format!("{}→{}→{}", a.name, b.name, a.name);
+                match convert(&original, &[a, b, a], &stem) {
+                    Ok(result) => {
+                        if let Some(diff) = compare(&original, &result, &metas) {
+                            failures.push(format!("{}, {}: {}", name, chain, diff));
+                        }
+                    }
+                    Err(e) => failures.push(format!("{}, {}: {}", name, chain, e)),
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(),
+            "{} round trips failed:\n{}",
+            failures.len(),
+            failures.join("\n"));
+}
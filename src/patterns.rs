@@ -0,0 +1,661 @@
+//! Directories of pattern files used as libraries ("packs") of
+//! patterns to browse and to pick from by name.
+//!
+//! A pack may come with an `index.txt` listing its patterns, one per
+//! line: the file's name (relative to the directory) optionally
+//! followed by whitespace and the pattern's display name.  Empty lines
+//! and lines starting with `#` are ignored.  Without an index the pack
+//! consists of all pattern files of the directory, sorted by name.
+//!
+//! Also bundles the patterns shown by the demo mode (`DEMO`) along with
+//! the `Sequencer` deciding when to show which.
+
+use std::cmp;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+use parser::{self, ParseError};
+use text::TextInput;
+use world::World;
+
+/// The name of a pack's index file.
+pub const INDEX_FILE: &'static str = "index.txt";
+
+/// The extensions of the files recognized as patterns.
+pub const EXTENSIONS: [&'static str; 4] = ["cells", "cgolb", "csv", "coords"];
+
+/// Lists the pattern files of the given directory sorted by their
+/// names.
+pub fn list_patterns(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in try!(fs::read_dir(dir)) {
+        let path = try!(entry).path();
+        let loadable = path.extension()
+                           .and_then(|e| e.to_str())
+                           .map_or(false, |e| EXTENSIONS.contains(&e));
+        if loadable && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    // ~ the parsed pattern; read on first use
+    world: Option<World>,
+}
+
+pub struct Pack {
+    entries: Vec<Entry>,
+    // ~ the longest line accepted in patterns in the plaintext format
+    max_line_length: usize,
+    // ~ whether patterns without live cells are accepted
+    allow_empty: bool,
+}
+
+impl Pack {
+    /// Opens the pack of the given directory reading its index if it
+    /// has one.  The patterns themselves are read on demand; see
+    /// `parser::load_world` for `max_line_length` and `allow_empty`.
+    pub fn open(dir: &str, max_line_length: usize, allow_empty: bool) -> Result<Pack, String> {
+        let dir = Path::new(dir);
+        let index = dir.join(INDEX_FILE);
+        let entries = match File::open(&index) {
+            Ok(f) => {
+                try!(read_index(dir, f)
+                         .map_err(|e| format!("{}: {}", index.display(), e)))
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                let files = try!(list_patterns(dir).map_err(|e| format!("{}: {}", dir.display(), e)));
+                files.into_iter()
+                     .map(|path| {
+                         Entry {
+                             name: display_name(&path),
+                             path: path,
+                             world: None,
+                         }
+                     })
+                     .collect()
+            }
+            Err(e) => return Err(format!("{}: {}", index.display(), e)),
+        };
+        Ok(Pack {
+            entries: entries,
+            max_line_length: max_line_length,
+            allow_empty: allow_empty,
+        })
+    }
+
+    /// The number of patterns in this pack.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The display name of the `i`-th pattern.
+    pub fn name(&self, i: usize) -> &str {
+        &self.entries[i].name
+    }
+
+    /// The file of the `i`-th pattern.
+    pub fn path(&self, i: usize) -> &Path {
+        &self.entries[i].path
+    }
+
+    /// Finds a pattern by its display name; failing that, by the name
+    /// of its file with or without the extension.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name == name).or_else(|| {
+            self.entries.iter().position(|e| {
+                e.path.file_name().map_or(false, |f| f == name) ||
+                e.path.file_stem().map_or(false, |f| f == name)
+            })
+        })
+    }
+
+    /// Finds a pattern by its file.
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.entries.iter().position(|e| e.path == path)
+    }
+
+    /// Reads the `i`-th pattern unless it was read before.
+    pub fn load(&mut self, i: usize) -> Result<World, ParseError> {
+        let e = &mut self.entries[i];
+        if e.world.is_none() {
+            e.world = Some(try!(parser::load_world(&e.path.to_string_lossy(),
+                                                   self.max_line_length,
+                                                   self.allow_empty)));
+        }
+        Ok(e.world.as_ref().unwrap().clone())
+    }
+}
+
+fn read_index<R: Read>(dir: &Path, input: R) -> io::Result<Vec<Entry>> {
+    let input = try!(TextInput::read_from(input));
+    let mut entries = Vec::new();
+    for line in input.as_str().lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (file, name) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        let path = dir.join(file);
+        entries.push(Entry {
+            name: if name.is_empty() {
+                display_name(&path)
+            } else {
+                name.to_owned()
+            },
+            path: path,
+            world: None,
+        });
+    }
+    Ok(entries)
+}
+
+// ~ the display name of a pattern not named by an index; its file's
+// name without the extension
+fn display_name(path: &Path) -> String {
+    path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+// --------------------------------------------------------------------
+
+/// The world shown by a phase of the demo.
+pub enum DemoWorld {
+    /// a pattern in the plaintext format, centered on the screen
+    Cells(&'static str),
+    /// a pattern in the plaintext format emitting gliders towards the
+    /// bottom right, placed near the top left corner of a world large
+    /// enough for none of them to wrap around the edges back into it
+    /// while the phase runs; the world may hence exceed the screen
+    Gun(&'static str),
+    /// a random world spanning the screen
+    Soup,
+}
+
+impl DemoWorld {
+    /// The pattern of the world, if any.
+    fn cells(&self) -> Option<&'static str> {
+        match *self {
+            DemoWorld::Cells(cells) | DemoWorld::Gun(cells) => Some(cells),
+            DemoWorld::Soup => None,
+        }
+    }
+}
+
+/// When a phase of the demo ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DemoStop {
+    /// after the given number of generations
+    Generations(usize),
+    /// once the world repeats one of its last `STABLE_PERIOD`
+    /// generations (e.g. settled into still lifes and blinkers), but
+    /// after the given number of generations at the latest
+    Stable(usize),
+}
+
+impl DemoStop {
+    /// The most generations a phase runs for.
+    pub fn limit(&self) -> usize {
+        match *self {
+            DemoStop::Generations(n) | DemoStop::Stable(n) => n,
+        }
+    }
+}
+
+/// A phase of the demo: a pattern run for a while at some speed.
+pub struct DemoPhase {
+    pub name: &'static str,
+    /// a one-line description shown along with the name
+    pub description: &'static str,
+    pub world: DemoWorld,
+    pub stop: DemoStop,
+    /// the delay (in milliseconds) between two generations
+    pub delay_ms: u64,
+}
+
+impl DemoPhase {
+    /// Creates the phase's world of the given size; its pattern is
+    /// centered in it unless larger.
+    pub fn world<R: Rng>(&self, rng: &mut R, width: usize, height: usize) -> World {
+        let mut w = match self.world {
+            DemoWorld::Soup => World::random(rng, width, height),
+            DemoWorld::Cells(cells) => {
+                // ~ bundled and hence known to parse
+                let mut w = parser::parse_cells(cells).unwrap();
+                let (dx, dy) = (width.saturating_sub(w.width()), height.saturating_sub(w.height()));
+                w.grow(dx / 2, dy / 2, dx - dx / 2, dy - dy / 2);
+                w
+            }
+            DemoWorld::Gun(cells) => {
+                let mut w = parser::parse_cells(cells).unwrap();
+                w.trim(0);
+                // ~ a glider moves one cell diagonally every four
+                // generations; a few more cells for its own size
+                let reach = self.stop.limit() / 4 + 4;
+                let right = cmp::max(width.saturating_sub(GUN_MARGIN + w.width()), reach);
+                let bottom = cmp::max(height.saturating_sub(GUN_MARGIN + w.height()), reach);
+                w.grow(GUN_MARGIN, GUN_MARGIN, right, bottom);
+                w
+            }
+        };
+        w.restart();
+        w
+    }
+}
+
+/// The number of cells between a `DemoWorld::Gun` and the top and the
+/// left edge.
+const GUN_MARGIN: usize = 2;
+
+/// Checks that the patterns of the given phases fit into a screen
+/// showing the given number of cells.
+pub fn check_fits(phases: &[DemoPhase], width: usize, height: usize) -> Result<(), String> {
+    for phase in phases {
+        if let Some(cells) = phase.world.cells() {
+            let (_, _, w, h) = parser::parse_cells(cells).unwrap().live_bounds().unwrap();
+            if w > width || h > height {
+                return Err(format!("The terminal is too small for the demo: the {} takes {}x{} cells, the \
+                                    screen shows {}x{}",
+                                   phase.name,
+                                   w,
+                                   h,
+                                   width,
+                                   height));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The number of most recent generations a world settled by
+/// `DemoStop::Stable` repeats.
+pub const STABLE_PERIOD: usize = 2;
+
+/// Tells whether a world observed generation by generation repeats one
+/// of its last `STABLE_PERIOD` generations, i.e. whether it settled
+/// into still lifes and oscillators of periods up to that.  The
+/// generations are compared by their checksums.
+pub struct Recurrence {
+    recent: Vec<u64>,
+}
+
+impl Recurrence {
+    pub fn new() -> Recurrence {
+        Recurrence { recent: Vec::with_capacity(STABLE_PERIOD + 1) }
+    }
+
+    /// Forgets the generations observed so far, e.g. for a new world.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Observes the next generation; tells whether it repeats one of
+    /// the last ones.
+    pub fn observe(&mut self, world: &World) -> bool {
+        self.repeats(world).is_some()
+    }
+
+    /// Observes the next generation like `observe`; tells how many
+    /// generations back the one it repeats was, if any: the period the
+    /// world settled with.
+    pub fn repeats(&mut self, world: &World) -> Option<usize> {
+        let checksum = world.checksum();
+        let back = self.recent.iter().rev().position(|&c| c == checksum).map(|i| i + 1);
+        if self.recent.len() == STABLE_PERIOD {
+            self.recent.remove(0);
+        }
+        self.recent.push(checksum);
+        back
+    }
+}
+
+/// How long (in milliseconds) the title card of a demo phase is shown
+/// before its pattern starts to evolve.
+pub const TITLE_MS: u64 = 2000;
+
+/// The phases of the demo mode, run in this order over and over.
+pub const DEMO: &'static [DemoPhase] = &[DemoPhase {
+                                             name: "Gosper glider gun",
+                                             description: "The first known gun, emitting a glider \
+                                                           every 30 generations",
+                                             world: DemoWorld::Gun(GOSPER_GLIDER_GUN),
+                                             stop: DemoStop::Generations(300),
+                                             delay_ms: 50,
+                                         },
+                                         DemoPhase {
+                                             name: "Pulsar",
+                                             description: "The most common oscillator of period 3",
+                                             world: DemoWorld::Cells(PULSAR),
+                                             stop: DemoStop::Generations(100),
+                                             delay_ms: 150,
+                                         },
+                                         DemoPhase {
+                                             name: "R-pentomino",
+                                             description: "Five cells evolving for over a thousand \
+                                                           generations",
+                                             world: DemoWorld::Cells(R_PENTOMINO),
+                                             stop: DemoStop::Stable(2000),
+                                             delay_ms: 30,
+                                         },
+                                         DemoPhase {
+                                             name: "Random soup",
+                                             description: "Cells alive by chance, settling into \
+                                                           the common objects",
+                                             world: DemoWorld::Soup,
+                                             stop: DemoStop::Generations(500),
+                                             delay_ms: 50,
+                                         }];
+
+/// The patterns to be stamped onto the world (see `stamp::Stamp`) by
+/// their names.
+pub const STAMPS: &'static [(&'static str, &'static str)] = &[("Glider", GLIDER),
+                                                              ("Lightweight spaceship", LWSS),
+                                                              ("R-pentomino", R_PENTOMINO),
+                                                              ("Pulsar", PULSAR),
+                                                              ("Gosper glider gun", GOSPER_GLIDER_GUN)];
+
+const GLIDER: &'static str = "!Glider
+.O.
+..O
+OOO
+";
+
+const LWSS: &'static str = "!Lightweight spaceship
+.O..O
+O....
+O...O
+OOOO.
+";
+
+const GOSPER_GLIDER_GUN: &'static str = "!Gosper glider gun
+........................O...........
+......................O.O...........
+............OO......OO............OO
+...........O...O....OO............OO
+OO........O.....O...OO..............
+OO........O...O.OO....O.O...........
+..........O.....O.......O...........
+...........O...O....................
+............OO......................
+";
+
+const PULSAR: &'static str = "!Pulsar
+..OOO...OOO..
+.............
+O....O.O....O
+O....O.O....O
+O....O.O....O
+..OOO...OOO..
+.............
+..OOO...OOO..
+O....O.O....O
+O....O.O....O
+O....O.O....O
+.............
+..OOO...OOO..
+";
+
+const R_PENTOMINO: &'static str = "!R-pentomino
+.OO
+OO.
+.O.
+";
+
+/// What the demo is to do next; see `Sequencer::next`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DemoCue {
+    /// start the given phase: show its world (with a fresh generation
+    /// counter) and its title card
+    Start(usize),
+    /// keep showing the title card for the given number of
+    /// milliseconds
+    Title(u64),
+    /// advance the world by one generation and wait for the phase's
+    /// delay
+    Step,
+}
+
+/// Runs through the phases of a demo over and over.  The time is
+/// passed in by the caller (in milliseconds since any fixed instant),
+/// keeping the sequencer free of a clock of its own.
+pub struct Sequencer<'a> {
+    phases: &'a [DemoPhase],
+    phase: usize,
+    // ~ the time the current phase was started at; `None` if it is
+    // still to be started
+    started: Option<u64>,
+    // ~ the number of generations the current phase advanced
+    generations: usize,
+    // ~ the most recent generations of the current phase (see
+    // `DemoStop::Stable`)
+    recent: Recurrence,
+}
+
+impl<'a> Sequencer<'a> {
+    /// Starts with the first of the given (non-empty) phases.
+    pub fn new(phases: &'a [DemoPhase]) -> Sequencer<'a> {
+        assert!(!phases.is_empty());
+        Sequencer {
+            phases: phases,
+            phase: 0,
+            started: None,
+            generations: 0,
+            recent: Recurrence::new(),
+        }
+    }
+
+    /// The current phase.
+    pub fn phase(&self) -> &'a DemoPhase {
+        &self.phases[self.phase]
+    }
+
+    /// Determines what to do next at the given time with the given
+    /// (displayed) world; moves on to the next phase (after the last
+    /// one to the first) once the current one is over.
+    pub fn next(&mut self, now: u64, world: &World) -> DemoCue {
+        let started = match self.started {
+            None => {
+                self.started = Some(now);
+                self.generations = 0;
+                self.recent.clear();
+                return DemoCue::Start(self.phase);
+            }
+            Some(t) => t,
+        };
+        let shown = now.saturating_sub(started);
+        if shown < TITLE_MS {
+            return DemoCue::Title(TITLE_MS - shown);
+        }
+        let over = match self.phase().stop {
+            DemoStop::Generations(n) => self.generations >= n,
+            DemoStop::Stable(n) => self.recent.observe(world) || self.generations >= n,
+        };
+        if over {
+            self.phase = (self.phase + 1) % self.phases.len();
+            self.started = None;
+            return self.next(now, world);
+        }
+        self.generations += 1;
+        DemoCue::Step
+    }
+}
+
+#[test]
+fn test_demo_worlds() {
+    use rand::thread_rng;
+
+    for phase in &DEMO[1..] {
+        let w = phase.world(&mut thread_rng(), 80, 24);
+        assert_eq!((80, 24, 0), (w.width(), w.height(), w.generation()), "{}", phase.name);
+        assert!(!w.is_empty(), "{}", phase.name);
+    }
+    // ~ centered
+    let w = DEMO[2].world(&mut thread_rng(), 9, 8);
+    assert_eq!(5, w.count_in_rect(3, 2, 3, 3));
+    // ~ not cropped on a tiny screen
+    let w = DEMO[1].world(&mut thread_rng(), 10, 5);
+    assert_eq!((15, 15, 48), (w.width(), w.height(), w.alive()));
+
+    // ~ the gun in the top left corner of a world exceeding the screen
+    let mut w = DEMO[0].world(&mut thread_rng(), 80, 24);
+    assert_eq!((117, 90, 0, 36), (w.width(), w.height(), w.generation(), w.alive()));
+    assert_eq!(36, w.count_in_rect(2, 2, 36, 9));
+    let size = |w: World| (w.width(), w.height());
+    assert_eq!((117, 90), size(DEMO[0].world(&mut thread_rng(), 10, 5)));
+    assert_eq!((200, 90), size(DEMO[0].world(&mut thread_rng(), 200, 30)));
+    // ~ the gun evolves as if the world had no edges: no glider comes
+    // back around into it
+    let mut unbounded = parser::parse_cells(GOSPER_GLIDER_GUN).unwrap();
+    unbounded.trim(0);
+    unbounded.grow(2, 2, 400, 400);
+    for _ in 0..DEMO[0].stop.limit() {
+        w.advance_generation(|_, _, _| {});
+        unbounded.advance_generation(|_, _, _| {});
+    }
+    for y in 0..12 {
+        for x in 0..40 {
+            assert_eq!(unbounded.is_alive(x, y), w.is_alive(x, y), "{},{}", x, y);
+        }
+    }
+}
+
+#[test]
+fn test_check_fits() {
+    assert_eq!(Ok(()), check_fits(DEMO, 36, 13));
+    assert_eq!(Err("The terminal is too small for the demo: the Gosper glider gun takes 36x9 cells, the \
+                    screen shows 35x23"
+                       .to_owned()),
+               check_fits(DEMO, 35, 23));
+    assert!(check_fits(DEMO, 80, 12).unwrap_err().contains("the Pulsar takes 13x13"));
+}
+
+#[test]
+fn test_sequencer() {
+    let blinker = ".O.\n.O.\n.O.\n";
+    let phases = [DemoPhase {
+                      name: "a",
+                      description: "",
+                      world: DemoWorld::Cells(blinker),
+                      stop: DemoStop::Generations(3),
+                      delay_ms: 10,
+                  },
+                  DemoPhase {
+                      name: "b",
+                      description: "",
+                      world: DemoWorld::Cells("OO\nOO\n"),
+                      stop: DemoStop::Stable(50),
+                      delay_ms: 20,
+                  },
+                  DemoPhase {
+                      name: "c",
+                      description: "",
+                      world: DemoWorld::Soup,
+                      stop: DemoStop::Stable(4),
+                      delay_ms: 10,
+                  }];
+    let mut seq = Sequencer::new(&phases);
+    // ~ plays the sequencer's cues with a fake clock, recording the
+    // time each phase started at and its number of steps
+    let mut now = 1000;
+    let mut world = World::empty(1, 1);
+    let mut log = Vec::new();
+    while log.len() < 5 {
+        match seq.next(now, &world) {
+            DemoCue::Start(i) => {
+                // ~ a soup that never settles
+                world = match phases[i].world {
+                    DemoWorld::Soup => parser::parse_cells(".O\n..O\nOOO").unwrap(),
+                    _ => phases[i].world(&mut ::rand::thread_rng(), 8, 8),
+                };
+                log.push((i, now, 0));
+            }
+            DemoCue::Title(ms) => now += ms,
+            DemoCue::Step => {
+                world.advance_generation(|_, _, _| {});
+                log.last_mut().unwrap().2 += 1;
+                now += seq.phase().delay_ms;
+            }
+        }
+    }
+    // ~ the blinker runs for its generations, the block is found
+    // stable after one, the glider runs until the limit, and it all
+    // starts over
+    assert_eq!(vec![(0, 1000, 3), (1, 3030, 1), (2, 5050, 4), (0, 7090, 3), (1, 9120, 0)], log);
+}
+
+#[test]
+fn test_pack() {
+    use std::env;
+    use std::io::Write;
+    use std::process;
+
+    let dir = env::temp_dir().join(format!("cgol-test-pack-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    for &(name, cells) in [("glider.cells", ".O\n..O\nOOO"), ("block.cells", "OO\nOO"),
+                           ("broken.cells", "x"), ("readme.md", "OOO")]
+                              .iter() {
+        File::create(dir.join(name)).unwrap().write_all(cells.as_bytes()).unwrap();
+    }
+    let dir_name = dir.to_str().unwrap();
+
+    // ~ without an index; all pattern files
+    let mut pack = Pack::open(dir_name, parser::DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    assert_eq!(vec!["block", "broken", "glider"],
+               (0..pack.len()).map(|i| pack.name(i)).collect::<Vec<_>>());
+    assert_eq!(Some(2), pack.find("glider.cells"));
+    assert_eq!(Some(2), pack.position(&dir.join("glider.cells")));
+    assert_eq!(5, pack.load(2).unwrap().alive());
+    assert!(pack.load(1).is_err());
+
+    // ~ with an index; patterns are read lazily, hence a missing file
+    // is an error only once loaded
+    File::create(dir.join(INDEX_FILE))
+        .unwrap()
+        .write_all(b"# a small pack\n\nglider.cells   Gosper's glider\nblock.cells\nmissing.cells X\n")
+        .unwrap();
+    let mut pack = Pack::open(dir_name, parser::DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    assert_eq!(3, pack.len());
+    assert_eq!(Some(0), pack.find("Gosper's glider"));
+    assert_eq!(Some(1), pack.find("block"));
+    assert_eq!(None, pack.find("broken"));
+    assert_eq!(4, pack.load(1).unwrap().alive());
+    assert!(pack.load(2).is_err());
+
+    // ~ parsed once; later changes of the file go unnoticed
+    fs::remove_file(dir.join("glider.cells")).unwrap();
+    assert!(pack.load(0).is_err());
+    let mut pack = Pack::open(dir_name, parser::DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    File::create(dir.join("glider.cells")).unwrap().write_all(b"O").unwrap();
+    assert_eq!(1, pack.load(0).unwrap().alive());
+    fs::remove_file(dir.join("glider.cells")).unwrap();
+    assert_eq!(1, pack.load(0).unwrap().alive());
+
+    assert!(Pack::open(dir.join("nonexistent").to_str().unwrap(), 10, false).is_err());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_pack_index_fixture() {
+    // ~ an index with CRLF and Latin-1 names, of patterns with CRLF and
+    // CR line endings
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/text/pack");
+    let mut pack = Pack::open(dir, parser::DEFAULT_MAX_LINE_LENGTH, false).unwrap();
+    assert_eq!(vec!["Gleiter \u{fffd}", "cr"], (0..pack.len()).map(|i| pack.name(i)).collect::<Vec<_>>());
+    assert_eq!(5, pack.load(0).unwrap().alive());
+    assert_eq!(5, pack.load(1).unwrap().alive());
+}
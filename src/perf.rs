@@ -0,0 +1,309 @@
+//! Counters of the work done by a run, e.g. for a user to report when
+//! the game feels slow.
+//!
+//! The counters are plain integers bumped along the way; the time
+//! spent stepping and rendering is measured only while asked for (see
+//! `set_timed`), reading the clock being the one cost worth avoiding.
+//! They are shown by the debug overlay (key `P`) and logged as a line
+//! of `key=value` pairs with `--perf-log`.
+//!
+//! `PhaseProfile` breaks the time spent advancing generations down by
+//! their phases (see `--profile`); it reads the clock at the end of
+//! every phase, which is why it is used only when asked for.
+
+use std::cell::Cell;
+use std::cmp;
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use world::{Phase, PhaseTimer, StepSummary};
+
+/// The interval (in seconds) of the log lines unless specified
+/// otherwise.
+pub const DEFAULT_LOG_SECS: u64 = 10;
+
+#[derive(Debug, Default)]
+pub struct PerfCounters {
+    pub generations: u64,
+    /// the cells whose next state was determined
+    pub cells_evaluated: u64,
+    /// the cells which changed their state
+    pub changes: u64,
+    /// the most cells changed by a single generation
+    pub peak_changes: usize,
+    /// the frames drawn anew entirely
+    pub full_redraws: u64,
+    /// the frames drawn by just their damaged parts
+    pub incremental_updates: u64,
+    // ~ counted through `&self` like the drawing methods printing
+    prints: Cell<u64>,
+    /// the bytes written to the statistics and event files
+    pub bytes_written: u64,
+    timed: bool,
+    /// the time spent stepping, if timed
+    pub step_ns: u64,
+    /// the time spent rendering, if timed
+    pub render_ns: u64,
+}
+
+impl PerfCounters {
+    pub fn new() -> PerfCounters {
+        PerfCounters::default()
+    }
+
+    /// Starts or stops measuring the time spent.
+    pub fn set_timed(&mut self, timed: bool) {
+        self.timed = timed;
+    }
+
+    /// Records a generation advanced.
+    pub fn add_step(&mut self, summary: &StepSummary) {
+        let changes = summary.births + summary.deaths;
+        self.generations += 1;
+        self.cells_evaluated += summary.evaluated as u64;
+        self.changes += changes as u64;
+        self.peak_changes = cmp::max(self.peak_changes, changes);
+    }
+
+    /// Records a number of generations advanced without their changes
+    /// being looked at, each evaluating the given number of cells.
+    pub fn add_skipped(&mut self, generations: usize, cells: usize) {
+        self.generations += generations as u64;
+        self.cells_evaluated += (generations * cells) as u64;
+    }
+
+    /// Records a call printing to the terminal.
+    pub fn count_print(&self) {
+        self.prints.set(self.prints.get() + 1);
+    }
+
+    /// The calls printing to the terminal.
+    pub fn prints(&self) -> u64 {
+        self.prints.get()
+    }
+
+    /// The time to measure from if timed.
+    pub fn start(&self) -> Option<Instant> {
+        if self.timed {
+            Some(Instant::now())
+        } else {
+            None
+        }
+    }
+
+    /// Adds the time since `started` (see `start`) to the stepping.
+    pub fn add_step_time(&mut self, started: Option<Instant>) {
+        self.step_ns += nanos_since(started);
+    }
+
+    /// Adds the time since `started` (see `start`) to the rendering.
+    pub fn add_render_time(&mut self, started: Option<Instant>) {
+        self.render_ns += nanos_since(started);
+    }
+}
+
+fn nanos_since(started: Option<Instant>) -> u64 {
+    started.map_or(0, |t| nanos(t.elapsed()))
+}
+
+fn nanos(d: Duration) -> u64 {
+    d.as_secs() * 1_000_000_000 + d.subsec_nanos() as u64
+}
+
+/// The time spent in each phase of advancing generations; see the
+/// module's description.
+#[derive(Clone, Debug, Default)]
+pub struct PhaseProfile {
+    /// the generations advanced, i.e. whose phases all ended
+    pub generations: u64,
+    /// the time spent counting the neighbours
+    pub count_ns: u64,
+    /// the time spent applying the changes
+    pub apply_ns: u64,
+    /// the time spent reporting the changed cells
+    pub report_ns: u64,
+    // ~ the generations with an applying phase of their own (see
+    // `Phase::Apply`)
+    applied: u64,
+    // ~ the end of the last phase
+    mark: Option<Instant>,
+}
+
+impl PhaseProfile {
+    pub fn new() -> PhaseProfile {
+        PhaseProfile::default()
+    }
+
+    pub fn total_ns(&self) -> u64 {
+        self.count_ns + self.apply_ns + self.report_ns
+    }
+}
+
+impl PhaseTimer for PhaseProfile {
+    fn begin(&mut self) {
+        self.mark = Some(Instant::now());
+    }
+
+    fn end(&mut self, phase: Phase) {
+        let now = Instant::now();
+        let ns = self.mark.map_or(0, |t| nanos(now.duration_since(t)));
+        self.mark = Some(now);
+        match phase {
+            Phase::Count => self.count_ns += ns,
+            Phase::Apply => {
+                self.apply_ns += ns;
+                self.applied += 1;
+            }
+            Phase::Report => {
+                self.report_ns += ns;
+                self.generations += 1;
+            }
+        }
+    }
+}
+
+/// Writes the breakdown, a line per phase with its total time and its
+/// share of all; `n/a` for applying if no generation had that phase.
+impl fmt::Display for PhaseProfile {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.total_ns();
+        try!(writeln!(fmt, "profile of {} generations:", self.generations));
+        let phases = [("counting", self.count_ns),
+                      ("applying", self.apply_ns),
+                      ("reporting", self.report_ns)];
+        for &(name, ns) in &phases {
+            if name == "applying" && self.applied == 0 && self.generations > 0 {
+                try!(writeln!(fmt, "  {:<10} {:>12}", name, "n/a"));
+                continue;
+            }
+            let share = if total > 0 { ns as f64 * 100.0 / total as f64 } else { 0.0 };
+            try!(writeln!(fmt, "  {:<10} {:>12.3} ms {:>6.1}%", name, ns as f64 / 1e6, share));
+        }
+        write!(fmt, "  {:<10} {:>12.3} ms", "total", total as f64 / 1e6)
+    }
+}
+
+/// Writes the counters as a line of `key=value` pairs; the times only
+/// if measured.
+impl fmt::Display for PerfCounters {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt,
+                    "generations={} cells_evaluated={} changes={} peak_changes={} full_redraws={} \
+                     incremental_updates={} prints={} bytes_written={}",
+                    self.generations,
+                    self.cells_evaluated,
+                    self.changes,
+                    self.peak_changes,
+                    self.full_redraws,
+                    self.incremental_updates,
+                    self.prints(),
+                    self.bytes_written));
+        if self.timed {
+            try!(write!(fmt, " step_ns={} render_ns={}", self.step_ns, self.render_ns));
+        }
+        Ok(())
+    }
+}
+
+/// A writer counting the bytes written through it.
+pub struct Counted<W: Write> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Counted<W> {
+    pub fn new(inner: W) -> Counted<W> {
+        Counted {
+            inner: inner,
+            written: 0,
+        }
+    }
+
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = try!(self.inner.write(buf));
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn test_counters() {
+    let mut p = PerfCounters::new();
+    for &(births, deaths) in &[(3, 1), (0, 6), (2, 2)] {
+        p.add_step(&StepSummary {
+            births: births,
+            deaths: deaths,
+            evaluated: 25,
+        });
+    }
+    p.add_skipped(2, 100);
+    p.count_print();
+    assert_eq!((5, 275, 14, 6, 1), (p.generations, p.cells_evaluated, p.changes, p.peak_changes, p.prints()));
+    // ~ no clock read unless timed
+    assert!(p.start().is_none());
+    p.add_step_time(None);
+    assert_eq!("generations=5 cells_evaluated=275 changes=14 peak_changes=6 full_redraws=0 \
+                incremental_updates=0 prints=1 bytes_written=0",
+               p.to_string());
+    p.set_timed(true);
+    let t = p.start();
+    p.add_render_time(t);
+    assert!(p.to_string().contains(" step_ns=0 render_ns="));
+
+    let mut out = Counted::new(Vec::new());
+    write!(out, "{}", 12345).unwrap();
+    out.write_all(b"\n").unwrap();
+    assert_eq!(6, out.written());
+}
+
+#[test]
+fn test_phase_profile() {
+    use world::{UpdateMode, World};
+
+    let mut world = World::random_seeded(3, 64, 64);
+    let mut profile = PhaseProfile::new();
+    let mut reported = 0;
+    for _ in 0..5 {
+        world.advance_generation_timed(|_, _, _| reported += 1, &mut profile);
+    }
+    assert_eq!(5, profile.generations);
+    assert!(reported > 0 && profile.count_ns > 0);
+    assert_eq!(profile.count_ns + profile.apply_ns + profile.report_ns, profile.total_ns());
+    let report = profile.to_string();
+    let lines: Vec<_> = report.lines().collect();
+    assert_eq!(5, lines.len());
+    assert_eq!("profile of 5 generations:", lines[0]);
+    assert!(lines[1].starts_with("  counting ") && lines[1].ends_with('%'), "{}", lines[1]);
+    assert!(lines[2].starts_with("  applying ") && lines[2].ends_with('%'), "{}", lines[2]);
+    assert!(lines[3].starts_with("  reporting ") && lines[3].ends_with('%'), "{}", lines[3]);
+    assert!(lines[4].starts_with("  total ") && lines[4].ends_with(" ms"), "{}", lines[4]);
+
+    // ~ the same world advanced untimed
+    let mut untimed = World::random_seeded(3, 64, 64);
+    untimed.advance_generations(5);
+    assert_eq!(untimed.live_cells(), world.live_cells());
+    assert_eq!("profile of 0 generations:", PhaseProfile::new().to_string().lines().next().unwrap());
+
+    // ~ the asynchronous sweep applies the changes as it counts
+    let mut world = World::random_seeded(3, 64, 64);
+    world.set_update_mode(UpdateMode::AsyncRowMajor);
+    let mut profile = PhaseProfile::new();
+    world.advance_generation_timed(|_, _, _| {}, &mut profile);
+    assert_eq!((1, 0), (profile.generations, profile.apply_ns));
+    assert_eq!("  applying            n/a", profile.to_string().lines().nth(2).unwrap());
+}
@@ -0,0 +1,216 @@
+//! Measuring the period and the displacement of the objects living in
+//! a world.
+
+use std::f64::consts::PI;
+use std::fmt;
+
+use world::World;
+
+/// The number of generations to look for a recurring configuration
+/// unless specified otherwise.
+pub const DEFAULT_MAX_PERIOD: usize = 1000;
+
+/// Describes how a world recurs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    /// the number of generations after which the configuration recurs
+    pub period: usize,
+    /// the net displacement (in columns) per period
+    pub dx: isize,
+    /// the net displacement (in rows) per period
+    pub dy: isize,
+}
+
+/// The kind of object a world's configuration is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Classification {
+    StillLife,
+    Oscillator,
+    Spaceship,
+}
+
+impl Measurement {
+    pub fn classify(&self) -> Classification {
+        if self.dx != 0 || self.dy != 0 {
+            Classification::Spaceship
+        } else if self.period == 1 {
+            Classification::StillLife
+        } else {
+            Classification::Oscillator
+        }
+    }
+
+    /// Formats the speed of a spaceship in the usual notation, e.g.
+    /// `c/4 diagonal` or `c/2 orthogonal`; the speed of other objects
+    /// is `0`.
+    pub fn speed(&self) -> String {
+        let (ax, ay) = (self.dx.abs() as usize, self.dy.abs() as usize);
+        let fraction = |d: usize| {
+            let g = gcd(d, self.period);
+            let (n, p) = (d / g, self.period / g);
+            match (n, p) {
+                (n, 1) => format!("{}c", n),
+                (1, p) => format!("c/{}", p),
+                (n, p) => format!("{}c/{}", n, p),
+            }
+        };
+        if ax == 0 && ay == 0 {
+            "0".to_owned()
+        } else if ax == 0 || ay == 0 {
+            format!("{} orthogonal", fraction(ax + ay))
+        } else if ax == ay {
+            format!("{} diagonal", fraction(ax))
+        } else {
+            format!("({},{})c/{} oblique", ax, ay, self.period)
+        }
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.classify() {
+            Classification::StillLife => write!(fmt, "still life"),
+            Classification::Oscillator => write!(fmt, "oscillator p{}", self.period),
+            Classification::Spaceship => {
+                write!(fmt,
+                       "spaceship p{} ({},{}) {}",
+                       self.period,
+                       self.dx,
+                       self.dy,
+                       self.speed())
+            }
+        }
+    }
+}
+
+/// Advances a copy of the given world until its configuration recurs,
+/// possibly translated, but at most for `max_period` generations.
+/// Returns `None` if the configuration does not recur within that
+/// time, e.g. because the world is still evolving towards a stable
+/// state.
+///
+/// The displacement is derived by tracking the centroid of the live
+/// cells from generation to generation; it is only meaningful for
+/// objects considerably smaller than the world.
+pub fn measure(world: &World, max_period: usize) -> Option<Measurement> {
+    let mut w = world.clone();
+    let mut centroid = centroid_or(&w, (0.0, 0.0));
+    let (mut dx, mut dy) = (0.0, 0.0);
+    for period in 1..(max_period + 1) {
+        w.advance_generation(|_, _, _| {});
+        let next = centroid_or(&w, centroid);
+        dx += circular_delta(centroid.0, next.0, w.width() as f64);
+        dy += circular_delta(centroid.1, next.1, w.height() as f64);
+        centroid = next;
+        if w.alive() != world.alive() {
+            continue;
+        }
+        let (dx, dy) = (dx.round() as isize, dy.round() as isize);
+        let mut shifted = world.clone();
+        shifted.shift(dx, dy);
+        if same_cells(&shifted, &w) {
+            return Some(Measurement {
+                period: period,
+                dx: dx,
+                dy: dy,
+            });
+        }
+    }
+    None
+}
+
+// ~ the circular mean of the live cells' positions; `default` for a
+// world without live cells
+fn centroid_or(world: &World, default: (f64, f64)) -> (f64, f64) {
+    if world.alive() == 0 {
+        return default;
+    }
+    let (w, h) = (world.width() as f64, world.height() as f64);
+    let (mut xc, mut xs, mut yc, mut ys) = (0.0, 0.0, 0.0, 0.0);
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            if world.is_alive(x, y) {
+                let (ax, ay) = (2.0 * PI * x as f64 / w, 2.0 * PI * y as f64 / h);
+                xc += ax.cos();
+                xs += ax.sin();
+                yc += ay.cos();
+                ys += ay.sin();
+            }
+        }
+    }
+    (xs.atan2(xc) * w / (2.0 * PI), ys.atan2(yc) * h / (2.0 * PI))
+}
+
+// ~ the shortest signed distance from `a` to `b` on a circle of the
+// given circumference
+fn circular_delta(a: f64, b: f64, circumference: f64) -> f64 {
+    let d = (b - a) % circumference;
+    if d > circumference / 2.0 {
+        d - circumference
+    } else if d < -circumference / 2.0 {
+        d + circumference
+    } else {
+        d
+    }
+}
+
+fn same_cells(a: &World, b: &World) -> bool {
+    a.width() == b.width() && a.height() == b.height() &&
+    (0..a.height()).all(|y| (0..a.width()).all(|x| a.is_alive(x, y) == b.is_alive(x, y)))
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+fn world_with(width: usize, height: usize, cells: &[(usize, usize)]) -> World {
+    let mut w = World::empty(width, height);
+    for &(x, y) in cells {
+        w.set_alive(x, y, true);
+    }
+    w
+}
+
+#[test]
+fn test_measure_glider() {
+    // ~ a south-east bound glider near the edges
+    let w = world_with(12, 10, &[(10, 7), (11, 8), (9, 9), (10, 9), (11, 9)]);
+    let m = measure(&w, 100).unwrap();
+    assert_eq!(Measurement { period: 4, dx: 1, dy: 1 }, m);
+    assert_eq!(Classification::Spaceship, m.classify());
+    assert_eq!("c/4 diagonal", m.speed());
+    assert_eq!("spaceship p4 (1,1) c/4 diagonal", m.to_string());
+}
+
+#[test]
+fn test_measure_blinker() {
+    let w = world_with(10, 10, &[(0, 5), (1, 5), (9, 5)]);
+    let m = measure(&w, 100).unwrap();
+    assert_eq!(Measurement { period: 2, dx: 0, dy: 0 }, m);
+    assert_eq!(Classification::Oscillator, m.classify());
+    assert_eq!("oscillator p2", m.to_string());
+}
+
+#[test]
+fn test_measure_still_life() {
+    let block = world_with(6, 6, &[(2, 2), (3, 2), (2, 3), (3, 3)]);
+    assert_eq!(Classification::StillLife, measure(&block, 10).unwrap().classify());
+    // ~ the R-pentomino is far from settled after ten generations
+    let r = world_with(40, 40, &[(20, 19), (21, 19), (19, 20), (20, 20), (20, 21)]);
+    assert_eq!(None, measure(&r, 10));
+}
+
+#[test]
+fn test_speed() {
+    let lwss = Measurement { period: 4, dx: -2, dy: 0 };
+    assert_eq!("c/2 orthogonal", lwss.speed());
+    let m = Measurement { period: 5, dx: 0, dy: 3 };
+    assert_eq!("3c/5 orthogonal", m.speed());
+    let knight = Measurement { period: 6, dx: 2, dy: 1 };
+    assert_eq!("(2,1)c/6 oblique", knight.speed());
+}
@@ -0,0 +1,160 @@
+//! Estimating the rate of long runs and the time left until they end.
+//!
+//! The estimate is an exponentially weighted moving average of the
+//! time per generation measured between samples, each sample weighing
+//! in by the time it covers: it follows a run slowing down (e.g. as its
+//! world gets denser) within a few window lengths, where the overall
+//! average would lag behind for as long as the run went on before.
+//! Averaging the time rather than the rate lets the time left catch up
+//! with a slowdown sooner.
+
+use std::time::Duration;
+
+/// The window (in seconds) of the moving average unless specified
+/// otherwise.
+pub const DEFAULT_WINDOW_SECS: f64 = 2.0;
+
+pub struct ProgressMeter {
+    window: f64,
+    // ~ seconds per generation; `None` until measured
+    pace: Option<f64>,
+    done: usize,
+    // ~ the generations (and seconds) of the samples not yet taken
+    // into the estimate
+    pending: (usize, f64),
+}
+
+impl ProgressMeter {
+    /// Creates a meter averaging the rate over about the given number
+    /// of seconds.
+    pub fn new(window_secs: f64) -> ProgressMeter {
+        ProgressMeter {
+            window: window_secs,
+            pace: None,
+            done: 0,
+            pending: (0, 0.0),
+        }
+    }
+
+    /// Records the given number of generations done within the given
+    /// time since the previous sample.  A sample without generations
+    /// (or time) is added to the next one.
+    pub fn add_sample(&mut self, generations: usize, elapsed: Duration) {
+        self.done += generations;
+        let generations = self.pending.0 + generations;
+        let secs = self.pending.1 + elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        if generations == 0 || secs <= 0.0 {
+            self.pending = (generations, secs);
+            return;
+        }
+        self.pending = (0, 0.0);
+        let sample = secs / generations as f64;
+        self.pace = Some(match self.pace {
+            None => sample,
+            Some(pace) => {
+                // ~ a sample covering the whole window weighs in by
+                // about two thirds
+                let weight = 1.0 - (-secs / self.window).exp();
+                pace + weight * (sample - pace)
+            }
+        });
+    }
+
+    /// The number of generations recorded.
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    /// The estimated number of generations per second; `None` until a
+    /// sample took measurable time.
+    pub fn rate(&self) -> Option<f64> {
+        self.pace.map(|p| 1.0 / p)
+    }
+
+    /// The estimated time the given number of generations will take at
+    /// the current rate; `None` while it is unknown.
+    pub fn eta(&self, remaining: usize) -> Option<Duration> {
+        self.pace.map(|pace| {
+            let ms = remaining as f64 * pace * 1000.0;
+            Duration::from_millis(ms.min(u64::max_value() as f64) as u64)
+        })
+    }
+}
+
+/// Formats the given duration to the second, e.g. `42s`, `3m05s`, or
+/// `2h07m` (to the minute.)
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs() + if d.subsec_nanos() >= 500_000_000 { 1 } else { 0 };
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+#[cfg(test)]
+fn secs(d: Option<Duration>) -> f64 {
+    let d = d.unwrap();
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9
+}
+
+#[test]
+fn test_steady_rate() {
+    let mut m = ProgressMeter::new(DEFAULT_WINDOW_SECS);
+    assert_eq!((None, None), (m.rate(), m.eta(100)));
+    // ~ samples of no measurable time count with the next one
+    m.add_sample(50, Duration::from_millis(0));
+    assert_eq!(None, m.rate());
+    m.add_sample(50, Duration::from_millis(100));
+    assert_eq!((Some(1000.0), 100), (m.rate(), m.done()));
+    // ~ irregular samples of the same rate keep it
+    for &(n, ms) in &[(30, 30), (500, 500), (7, 7), (250, 250)] {
+        m.add_sample(n, Duration::from_millis(ms));
+    }
+    assert!((m.rate().unwrap() - 1000.0).abs() < 1e-6);
+    assert!((secs(m.eta(5000)) - 5.0).abs() < 1e-3);
+    assert_eq!(887, m.done());
+    // ~ a stall is taken into account once generations follow
+    m.add_sample(0, Duration::from_secs(1));
+    assert!((m.rate().unwrap() - 1000.0).abs() < 1e-6);
+    m.add_sample(1, Duration::from_millis(0));
+    assert!(m.rate().unwrap() < 400.0);
+}
+
+#[test]
+fn test_slowdown() {
+    // ~ 1000 generations per second for a minute, then ten times slower
+    let mut m = ProgressMeter::new(DEFAULT_WINDOW_SECS);
+    let tick = Duration::from_millis(100);
+    for _ in 0..600 {
+        m.add_sample(100, tick);
+    }
+    assert!((secs(m.eta(10000)) - 10.0).abs() < 0.01);
+    let mut etas = Vec::new();
+    for _ in 0..100 {
+        m.add_sample(10, tick);
+        etas.push(secs(m.eta(10000)));
+    }
+    // ~ the estimate grows steadily towards the new 100 seconds; the
+    // overall average (about 917 generations per second) would still
+    // promise less than 11
+    assert!(etas.windows(2).all(|w| w[0] < w[1]));
+    assert!(etas[4] > 25.0 && etas[19] > 60.0, "{:?}", &etas[..20]);
+    assert!((etas[99] - 100.0).abs() < 1.0, "{}", etas[99]);
+    // ~ and back
+    for _ in 0..100 {
+        m.add_sample(100, tick);
+    }
+    assert!((secs(m.eta(10000)) - 10.0).abs() < 1.0);
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!("0s", format_duration(Duration::from_millis(499)));
+    assert_eq!("42s", format_duration(Duration::from_millis(41500)));
+    assert_eq!("3m05s", format_duration(Duration::from_secs(185)));
+    assert_eq!("59m59s", format_duration(Duration::from_secs(3599)));
+    assert_eq!("2h07m", format_duration(Duration::from_secs(2 * 3600 + 7 * 60 + 59)));
+}
@@ -0,0 +1,277 @@
+//! A line of text typed by the user on the status line, e.g. the name
+//! of a file to save the world to.
+//!
+//! Besides inserting and deleting characters at the cursor, `Ctrl-U`
+//! clears the line and `Tab` completes the file name being typed
+//! against the directory it names (see `complete`), cycling through
+//! the candidates on repeated presses.
+
+use std::fs;
+use std::io;
+
+use rustbox::keyboard::Key;
+
+/// A file or directory listed for completion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Lists the entries of the given directory in no particular order;
+/// those not named in UTF-8 are left out.
+pub fn list_dir(dir: &str) -> io::Result<Vec<DirEntry>> {
+    let mut entries = Vec::new();
+    for entry in try!(fs::read_dir(if dir.is_empty() { "." } else { dir })) {
+        let entry = try!(entry);
+        if let Ok(name) = entry.file_name().into_string() {
+            entries.push(DirEntry {
+                // ~ following symlinks
+                is_dir: entry.path().is_dir(),
+                name: name,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Determines the completions of the given input against the listing
+/// of the directory it names (the part up to its last `/`): the
+/// directories (with a trailing `/`) and the files with one of the
+/// given extensions (without the dot) whose names start with the rest
+/// of the input, sorted by name.  Hidden entries are left out unless
+/// the input names them with a leading dot.
+pub fn complete(input: &str, entries: &[DirEntry], extensions: &[&str]) -> Vec<String> {
+    let (dir, prefix) = split_dir(input);
+    let mut names: Vec<String> = entries.iter()
+                                        .filter(|e| e.name.starts_with(prefix))
+                                        .filter(|e| !e.name.starts_with('.') || prefix.starts_with('.'))
+                                        .filter(|e| e.is_dir || has_extension(&e.name, extensions))
+                                        .map(|e| if e.is_dir { format!("{}/", e.name) } else { e.name.clone() })
+                                        .collect();
+    names.sort();
+    names.into_iter().map(|n| format!("{}{}", dir, n)).collect()
+}
+
+// ~ splits a path into its directory (with the trailing `/`, if any)
+// and the name following it
+fn split_dir(input: &str) -> (&str, &str) {
+    match input.rfind('/') {
+        Some(i) => (&input[..i + 1], &input[i + 1..]),
+        None => ("", input),
+    }
+}
+
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    match name.rfind('.') {
+        Some(i) if i > 0 => extensions.contains(&&name[i + 1..]),
+        _ => false,
+    }
+}
+
+/// What a key typed into a prompt did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// the text or the cursor changed, or nothing at all
+    Editing,
+    /// `Enter` submitted the text
+    Submitted,
+    /// `Esc` cancelled the prompt
+    Cancelled,
+}
+
+pub struct Prompt {
+    label: &'static str,
+    // ~ the characters typed; the cursor is before the one at `cursor`
+    text: Vec<char>,
+    cursor: usize,
+    // ~ the extensions of the files offered for completion
+    extensions: &'static [&'static str],
+    // ~ the candidates of the last completion and the one shown; kept
+    // to cycle through them while `Tab` is pressed repeatedly
+    completions: Option<(Vec<String>, usize)>,
+}
+
+impl Prompt {
+    /// Creates a prompt showing the given label, pre-filled with the
+    /// given text and the cursor at its end.
+    pub fn new(label: &'static str, text: &str, extensions: &'static [&'static str]) -> Prompt {
+        let text: Vec<char> = text.chars().collect();
+        Prompt {
+            label: label,
+            cursor: text.len(),
+            text: text,
+            extensions: extensions,
+            completions: None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().cloned().collect()
+    }
+
+    /// The position of the cursor in characters of the text.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Handles the given key, completing file names against the real
+    /// file system.
+    pub fn key(&mut self, key: Key) -> Outcome {
+        self.key_with(key, |dir| list_dir(dir).unwrap_or_else(|_| Vec::new()))
+    }
+
+    /// Handles the given key like `key`, completing file names against
+    /// the entries `list` returns for a directory (`""` for the
+    /// current one.)
+    pub fn key_with<F>(&mut self, key: Key, list: F) -> Outcome
+        where F: FnOnce(&str) -> Vec<DirEntry>
+    {
+        if key != Key::Tab {
+            self.completions = None;
+        }
+        match key {
+            Key::Enter => return Outcome::Submitted,
+            Key::Esc => return Outcome::Cancelled,
+            Key::Tab => self.complete(list),
+            Key::Char(c) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            Key::Left if self.cursor > 0 => self.cursor -= 1,
+            Key::Right if self.cursor < self.text.len() => self.cursor += 1,
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.text.len(),
+            Key::Ctrl('u') => {
+                self.text.clear();
+                self.cursor = 0;
+            }
+            _ => {}
+        }
+        Outcome::Editing
+    }
+
+    // ~ replaces the text by the next candidate of the last completion
+    // or else by the first of a new one; a single candidate is taken
+    // for good so that `Tab` continues into a completed directory
+    fn complete<F>(&mut self, list: F)
+        where F: FnOnce(&str) -> Vec<DirEntry>
+    {
+        let next = match self.completions {
+            Some((ref candidates, ref mut i)) => {
+                *i = (*i + 1) % candidates.len();
+                Some(candidates[*i].clone())
+            }
+            None => None,
+        };
+        let text = match next {
+            Some(text) => text,
+            None => {
+                let input = self.text();
+                let candidates = complete(&input, &list(split_dir(&input).0), self.extensions);
+                match candidates.len() {
+                    0 => return,
+                    1 => candidates[0].clone(),
+                    _ => {
+                        let first = candidates[0].clone();
+                        self.completions = Some((candidates, 0));
+                        first
+                    }
+                }
+            }
+        };
+        self.text = text.chars().collect();
+        self.cursor = self.text.len();
+    }
+}
+
+#[cfg(test)]
+fn entries(names: &[&str]) -> Vec<DirEntry> {
+    names.iter()
+         .map(|n| {
+             DirEntry {
+                 name: n.trim_matches('/').to_owned(),
+                 is_dir: n.ends_with('/'),
+             }
+         })
+         .collect()
+}
+
+#[test]
+fn test_complete() {
+    let listing = entries(&["glider.cells", "gun.rle", "gosper.cgolb", "notes.txt", "gliders/", ".git/",
+                            ".hidden.cells", "g"]);
+    let exts = ["cells", "cgolb", "rle"];
+    assert_eq!(vec!["glider.cells", "gliders/", "gosper.cgolb", "gun.rle"],
+               complete("g", &listing, &exts));
+    assert_eq!(vec!["glider.cells", "gliders/"], complete("gli", &listing, &exts));
+    // ~ only the loadable files
+    assert_eq!(vec!["glider.cells", "gliders/", "gosper.cgolb"], complete("g", &listing, &exts[..2]));
+    assert!(complete("notes", &listing, &exts).is_empty());
+    // ~ hidden entries only when asked for; the directory is kept
+    assert_eq!(vec!["glider.cells", "gliders/", "gosper.cgolb", "gun.rle", "notes.txt"],
+               complete("", &listing, &["cells", "cgolb", "rle", "txt"]));
+    assert_eq!(vec!["dir/.git/", "dir/.hidden.cells"], complete("dir/.", &listing, &exts));
+    assert_eq!(vec!["a/b/glider.cells", "a/b/gliders/"], complete("a/b/glider", &listing, &exts));
+}
+
+#[test]
+fn test_editing() {
+    let mut p = Prompt::new("Save as", "gen.cells", &["cells"]);
+    assert_eq!(("gen.cells".to_owned(), 9), (p.text(), p.cursor()));
+    for &k in &[Key::Home, Key::Char('x'), Key::Right, Key::Delete, Key::End, Key::Backspace, Key::Left] {
+        assert_eq!(Outcome::Editing, p.key(k));
+    }
+    assert_eq!(("xgn.cell".to_owned(), 7), (p.text(), p.cursor()));
+    // ~ nothing to delete or move beyond the ends
+    p.key(Key::Ctrl('u'));
+    for &k in &[Key::Backspace, Key::Delete, Key::Left, Key::Right] {
+        p.key(k);
+    }
+    assert_eq!(("".to_owned(), 0), (p.text(), p.cursor()));
+    p.key(Key::Char('ä'));
+    p.key(Key::Ctrl('a'));
+    p.key(Key::Char('/'));
+    assert_eq!(("/ä".to_owned(), 1), (p.text(), p.cursor()));
+    assert_eq!(Outcome::Submitted, p.key(Key::Enter));
+    assert_eq!(Outcome::Cancelled, p.key(Key::Esc));
+}
+
+#[test]
+fn test_cycle_completions() {
+    let listing = |dir: &str| {
+        match dir {
+            "" => entries(&["glider.cells", "gliders/", "gun.cells"]),
+            "gliders/" => entries(&["small.cells"]),
+            _ => Vec::new(),
+        }
+    };
+    let mut p = Prompt::new("Load", "g", &["cells"]);
+    let mut shown = Vec::new();
+    for _ in 0..4 {
+        p.key_with(Key::Tab, &listing);
+        shown.push(p.text());
+    }
+    assert_eq!(vec!["glider.cells", "gliders/", "gun.cells", "glider.cells"], shown);
+    // ~ any other key ends the cycle; a single candidate is taken and
+    // the next Tab continues in it
+    p.key_with(Key::Tab, &listing);
+    p.key_with(Key::End, &listing);
+    p.key_with(Key::Tab, &listing);
+    assert_eq!(("gliders/small.cells".to_owned(), 19), (p.text(), p.cursor()));
+    // ~ no candidates; the text stays
+    let mut p = Prompt::new("Load", "x", &["cells"]);
+    p.key_with(Key::Tab, &listing);
+    assert_eq!(("x".to_owned(), 1), (p.text(), p.cursor()));
+}
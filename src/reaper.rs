@@ -0,0 +1,484 @@
+//! Removing the stable debris far from the action ("reaping"), e.g. to
+//! keep a long screensaver run lively.
+//!
+//! A world left running on a torus slowly fills with still lifes and
+//! blinkers until nothing interesting can happen any more.  The reaper
+//! looks at the world every `every` generations and removes the objects
+//! which have been periodic with a period of at most two for at least
+//! `age` generations and are farther than `distance` cells from any
+//! cell which has not (the "activity").  While the whole world is
+//! periodic nothing is removed; telling that it settled is up to
+//! others.
+//!
+//! A cell counts as periodic at a generation if it is in the state of
+//! two generations before, i.e. if it changed with both or with neither
+//! of the last two generations, which the cells changed by each
+//! generation tell without looking at the world.  The objects are the
+//! groups of live cells connected through the world's neighbourhood.
+//! They are kept from one look to the next; only the ones next to a
+//! cell changed in between are determined anew.  Distances are counted
+//! in cells in either direction; across the edges of a twisted torus
+//! they are taken as on a plain one.
+
+use std::cmp;
+use std::usize;
+
+use simulation::Change;
+use world::{BoundaryMode, World};
+
+/// When and what to reap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// the number of generations between two looks at the world
+    pub every: usize,
+    /// the number of generations an object must have been periodic
+    pub age: usize,
+    /// the number of cells an object must be apart from any activity
+    pub distance: usize,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            every: 500,
+            age: 200,
+            distance: 30,
+        }
+    }
+}
+
+impl Policy {
+    /// Parses a comma separated list of settings overriding the
+    /// defaults, e.g. `every=500,age=200,distance=30`; an empty string
+    /// keeps all defaults.
+    pub fn parse(s: &str) -> Result<Policy, String> {
+        let mut p = Policy::default();
+        for setting in s.split(',').filter(|s| !s.is_empty()) {
+            let mut parts = setting.splitn(2, '=');
+            let (key, value) = (parts.next().unwrap(), parts.next());
+            let n = match value.map(|v| v.parse::<usize>()) {
+                Some(Ok(n)) => n,
+                _ => return Err(format!("Invalid reaping setting: {}", setting)),
+            };
+            match key {
+                "every" if n > 0 => p.every = n,
+                "age" if n > 0 => p.age = n,
+                "distance" => p.distance = n,
+                _ => return Err(format!("Invalid reaping setting: {}", setting)),
+            }
+        }
+        Ok(p)
+    }
+}
+
+// ~ marks a cell belonging to no object
+const NO_OBJECT: usize = usize::MAX;
+
+// ~ marks a cell not observed to change
+const NEVER: usize = usize::MAX;
+
+/// Reaps the debris of a world observed generation by generation.
+pub struct Reaper {
+    policy: Policy,
+    // ~ the size of the world and the generation observed last; a
+    // world of a different size or not the next generation is taken
+    // as a new one
+    width: usize,
+    height: usize,
+    generation: usize,
+    // ~ the generation observed first, to look at the world at every
+    // `every` generations from there
+    first: usize,
+    // ~ per cell the generation it changed with last (`NEVER` unless
+    // observed), and the last one it was not periodic at
+    changed_at: Vec<usize>,
+    irregular_at: Vec<usize>,
+    // ~ the cells changed by the generation observed last
+    last_changes: Vec<(usize, usize)>,
+    // ~ the cells changed since the last look, once each
+    dirty: Vec<bool>,
+    dirty_cells: Vec<(usize, usize)>,
+    // ~ the objects found by the last look (freed slots are `None`),
+    // and the object each live cell belongs to
+    objects: Vec<Option<Vec<(usize, usize)>>>,
+    owner: Vec<usize>,
+    reaped: usize,
+}
+
+impl Reaper {
+    pub fn new(policy: Policy) -> Reaper {
+        Reaper {
+            policy: policy,
+            width: 0,
+            height: 0,
+            generation: 0,
+            first: 0,
+            changed_at: Vec::new(),
+            irregular_at: Vec::new(),
+            last_changes: Vec::new(),
+            dirty: Vec::new(),
+            dirty_cells: Vec::new(),
+            objects: Vec::new(),
+            owner: Vec::new(),
+            reaped: 0,
+        }
+    }
+
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// The number of cells removed so far.
+    pub fn reaped(&self) -> usize {
+        self.reaped
+    }
+
+    // ~ starts observing the given world afresh: nothing is known to be
+    // periodic and all its cells are to be looked at
+    fn reset(&mut self, world: &World) {
+        let (w, h, g) = (world.width(), world.height(), world.generation());
+        self.width = w;
+        self.height = h;
+        self.generation = g;
+        self.first = g;
+        self.changed_at = vec![NEVER; w * h];
+        // ~ the next generation is the first one telling whether a cell
+        // changed with the one before
+        self.irregular_at = vec![g + 1; w * h];
+        self.last_changes.clear();
+        self.dirty = vec![false; w * h];
+        self.dirty_cells.clear();
+        for y in 0..h {
+            for x in 0..w {
+                if world.is_alive(x, y) {
+                    self.mark_dirty(x, y);
+                }
+            }
+        }
+        self.objects.clear();
+        self.owner = vec![NO_OBJECT; w * h];
+    }
+
+    fn mark_dirty(&mut self, x: usize, y: usize) {
+        let i = y * self.width + x;
+        if !self.dirty[i] {
+            self.dirty[i] = true;
+            self.dirty_cells.push((x, y));
+        }
+    }
+
+    /// Observes the next generation of the given world along with the
+    /// cells it changed (see `simulation::step`), and reaps the debris
+    /// if it is time to look.  The cells removed are appended to
+    /// `changes`; returns their number.
+    pub fn observe(&mut self, world: &mut World, changes: &mut Vec<Change>) -> usize {
+        let g = world.generation();
+        if (world.width(), world.height()) != (self.width, self.height) || g != self.generation + 1 {
+            self.reset(world);
+            return 0;
+        }
+        self.generation = g;
+        // ~ a cell changed with only one of the last two generations is
+        // not periodic
+        for &(x, y, _) in changes.iter() {
+            let i = y * self.width + x;
+            if self.changed_at[i] != g - 1 {
+                self.irregular_at[i] = g;
+            }
+            self.changed_at[i] = g;
+            self.mark_dirty(x, y);
+        }
+        for &(x, y) in &self.last_changes {
+            if self.changed_at[y * self.width + x] == g - 1 {
+                self.irregular_at[y * self.width + x] = g;
+            }
+        }
+        self.last_changes.clear();
+        self.last_changes.extend(changes.iter().map(|&(x, y, _)| (x, y)));
+        if (g - self.first) % self.policy.every != 0 {
+            return 0;
+        }
+        self.update_objects(world);
+        let reaped = self.reap(world, changes);
+        self.reaped += reaped;
+        reaped
+    }
+
+    // ~ determines the objects anew next to the cells changed since the
+    // last look; the others are the same as then
+    fn update_objects(&mut self, world: &World) {
+        let dirty_cells = ::std::mem::replace(&mut self.dirty_cells, Vec::new());
+        let mut seeds = Vec::new();
+        for &(x, y) in &dirty_cells {
+            self.dirty[y * self.width + x] = false;
+            seeds.push((x, y));
+            for &(dx, dy) in world.neighbourhood() {
+                if let Some((nx, ny)) = world.shift_target(x, y, dx, dy) {
+                    seeds.push((nx, ny));
+                }
+            }
+        }
+        // ~ the objects next to a change may have grown, shrunk, merged
+        // or split
+        for i in 0..seeds.len() {
+            let (x, y) = seeds[i];
+            let o = self.owner[y * self.width + x];
+            if o != NO_OBJECT {
+                for (cx, cy) in self.objects[o].take().unwrap() {
+                    self.owner[cy * self.width + cx] = NO_OBJECT;
+                    seeds.push((cx, cy));
+                }
+            }
+        }
+        for (x, y) in seeds {
+            if world.is_alive(x, y) && self.owner[y * self.width + x] == NO_OBJECT {
+                let o = self.objects.iter().position(|o| o.is_none()).unwrap_or(self.objects.len());
+                let cells = self.fill(world, x, y, o);
+                if o == self.objects.len() {
+                    self.objects.push(Some(cells));
+                } else {
+                    self.objects[o] = Some(cells);
+                }
+            }
+        }
+    }
+
+    // ~ collects the live cells connected to the given one into the
+    // given object
+    fn fill(&mut self, world: &World, x: usize, y: usize, o: usize) -> Vec<(usize, usize)> {
+        let mut cells = vec![(x, y)];
+        self.owner[y * self.width + x] = o;
+        let mut i = 0;
+        while i < cells.len() {
+            let (x, y) = cells[i];
+            for &(dx, dy) in world.neighbourhood() {
+                if let Some((nx, ny)) = world.shift_target(x, y, dx, dy) {
+                    let j = ny * self.width + nx;
+                    if world.is_alive(nx, ny) && self.owner[j] == NO_OBJECT {
+                        self.owner[j] = o;
+                        cells.push((nx, ny));
+                    }
+                }
+            }
+            i += 1;
+        }
+        cells
+    }
+
+    // ~ removes the objects far enough from any activity
+    fn reap(&mut self, world: &mut World, changes: &mut Vec<Change>) -> usize {
+        let g = self.generation;
+        let age = self.policy.age;
+        let active: Vec<bool> = self.irregular_at.iter().map(|&t| t + age > g).collect();
+        if !active.iter().any(|&a| a) {
+            return 0;
+        }
+        // ~ the cells within `distance` of the activity, and at least
+        // within the reach of the neighbourhood (the cells affecting an
+        // object's evolution)
+        let reach = world.neighbourhood()
+                         .iter()
+                         .map(|&(dx, dy)| cmp::max(dx.abs(), dy.abs()) as usize)
+                         .max()
+                         .unwrap_or(0);
+        let wrap = world.boundary() != BoundaryMode::Bounded;
+        let near = dilate(&active, self.width, self.height, cmp::max(self.policy.distance, reach), wrap);
+        let mut reaped = 0;
+        for o in 0..self.objects.len() {
+            let far = match self.objects[o] {
+                Some(ref cells) => cells.iter().all(|&(x, y)| !near[y * self.width + x]),
+                None => false,
+            };
+            if far {
+                for (x, y) in self.objects[o].take().unwrap() {
+                    world.set_alive(x, y, false);
+                    self.owner[y * self.width + x] = NO_OBJECT;
+                    changes.push((x, y, false));
+                    reaped += 1;
+                }
+            }
+        }
+        reaped
+    }
+}
+
+// ~ the cells within `r` cells in either direction of a set one
+fn dilate(cells: &[bool], width: usize, height: usize, r: usize, wrap: bool) -> Vec<bool> {
+    let rows = dilate_lines(cells, width, height, 1, width, r, wrap);
+    dilate_lines(&rows, height, width, width, 1, r, wrap)
+}
+
+// ~ dilates `n` lines of `len` cells each, the cells of a line being
+// `step` apart and the lines `stride` apart, by a sliding count of the
+// set cells within `r` of each cell
+fn dilate_lines(cells: &[bool], len: usize, n: usize, step: usize, stride: usize, r: usize, wrap: bool)
+                -> Vec<bool> {
+    let mut out = vec![false; cells.len()];
+    for line in 0..n {
+        let at = |i: usize| cells[line * stride + i * step];
+        if wrap && 2 * r + 1 >= len {
+            let any = (0..len).any(&at);
+            for i in 0..len {
+                out[line * stride + i * step] = any;
+            }
+            continue;
+        }
+        // ~ the count of the window around the cell before the first
+        let mut count = 0;
+        for k in 0..r + 1 {
+            if wrap {
+                count += at(len - 1 - k) as usize;
+            }
+            if k < r && k < len {
+                count += at(k) as usize;
+            }
+        }
+        for i in 0..len {
+            // ~ the window moves on by one: in comes `i + r`, out goes
+            // `i - r - 1`
+            let incoming = i + r;
+            if incoming < len {
+                count += at(incoming) as usize;
+            } else if wrap {
+                count += at(incoming - len) as usize;
+            }
+            if i > r {
+                count -= at(i - r - 1) as usize;
+            } else if wrap {
+                count -= at(i + len - r - 1) as usize;
+            }
+            out[line * stride + i * step] = count > 0;
+        }
+    }
+    out
+}
+
+#[test]
+fn test_policy() {
+    assert_eq!(Ok(Policy::default()), Policy::parse(""));
+    assert_eq!(Ok(Policy {
+                   every: 50,
+                   age: 20,
+                   distance: 3,
+               }),
+               Policy::parse("distance=3,every=50,age=20"));
+    assert_eq!(Ok(Policy { age: 7, ..Policy::default() }), Policy::parse("age=7"));
+    for s in &["every=0", "age", "age=x", "speed=3", "age=-1"] {
+        assert!(Policy::parse(s).is_err(), "{}", s);
+    }
+}
+
+#[test]
+fn test_dilate() {
+    let mut cells = vec![false; 10 * 3];
+    cells[10 + 1] = true;
+    let show = |v: &[bool]| {
+        v.chunks(10)
+         .map(|row| row.iter().map(|&c| if c { 'X' } else { '.' }).collect::<String>())
+         .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["XXX.......", "XXX.......", "XXX......."], show(&dilate(&cells, 10, 3, 1, false)));
+    // ~ across the edges of a torus
+    assert_eq!(vec!["XXXX.....X", "XXXX.....X", "XXXX.....X"], show(&dilate(&cells, 10, 3, 2, true)));
+    assert_eq!(vec!["XXXX......", "XXXX......", "XXXX......"], show(&dilate(&cells, 10, 3, 2, false)));
+    assert_eq!(cells, dilate(&cells, 10, 3, 0, true));
+}
+
+#[test]
+fn test_reap_debris() {
+    use parser;
+    use simulation;
+
+    // ~ a block far from an R-pentomino, which keeps evolving for over
+    // a thousand generations
+    let mut world = World::empty(120, 120);
+    for &(x, y) in &[(5, 5), (6, 5), (5, 6), (6, 6)] {
+        world.set_alive(x, y, true);
+    }
+    let r = parser::parse_cells(".OO\nOO.\n.O.\n").unwrap();
+    for y in 0..r.height() {
+        for x in 0..r.width() {
+            if r.is_alive(x, y) {
+                world.set_alive(x + 80, y + 80, true);
+            }
+        }
+    }
+    world.restart();
+    let policy = Policy {
+        every: 20,
+        age: 30,
+        distance: 20,
+    };
+    let mut reaper = Reaper::new(policy);
+    let mut changes = Vec::new();
+    reaper.observe(&mut world, &mut changes);
+    let mut reaped_at = None;
+    for _ in 0..60 {
+        simulation::step(&mut world, None, &mut changes);
+        let before = changes.len();
+        if reaper.observe(&mut world, &mut changes) > 0 {
+            assert!(reaped_at.is_none());
+            reaped_at = Some(world.generation());
+            // ~ reported as changes
+            assert_eq!(vec![(5, 5, false), (6, 5, false), (5, 6, false), (6, 6, false)],
+                       {
+                           let mut c = changes[before..].to_vec();
+                           c.sort_by_key(|&(x, y, _)| (y, x));
+                           c
+                       });
+        }
+    }
+    // ~ once periodic for the given age, at the next look
+    assert_eq!(Some(40), reaped_at);
+    assert_eq!((4, 0), (reaper.reaped(), world.count_in_rect(0, 0, 20, 20)));
+    // ~ the activity is untouched
+    let mut unreaped = World::empty(120, 120);
+    for y in 0..r.height() {
+        for x in 0..r.width() {
+            if r.is_alive(x, y) {
+                unreaped.set_alive(x + 80, y + 80, true);
+            }
+        }
+    }
+    unreaped.advance_generations(60);
+    assert_eq!(unreaped.checksum(), world.checksum());
+}
+
+#[test]
+fn test_reap_nothing_periodic() {
+    use simulation;
+
+    // ~ a block, a blinker, and a beehive: periodic as a whole
+    let mut world = World::empty(60, 60);
+    for &(x, y) in &[(5, 5), (6, 5), (5, 6), (6, 6), (40, 40), (41, 40), (42, 40), (20, 50), (21, 50), (19, 51),
+                     (22, 51), (20, 52), (21, 52)] {
+        world.set_alive(x, y, true);
+    }
+    world.restart();
+    let checksum = world.checksum();
+    let mut reaper = Reaper::new(Policy {
+        every: 10,
+        age: 20,
+        distance: 5,
+    });
+    let mut changes = Vec::new();
+    reaper.observe(&mut world, &mut changes);
+    for _ in 0..100 {
+        simulation::step(&mut world, None, &mut changes);
+        assert_eq!(0, reaper.observe(&mut world, &mut changes));
+    }
+    assert_eq!(checksum, world.checksum());
+
+    // ~ but a glider flying by keeps the others alive only near it
+    world.set_alive(31, 30, true);
+    world.set_alive(32, 31, true);
+    for x in 30..33 {
+        world.set_alive(x, 32, true);
+    }
+    let mut reaped = 0;
+    for _ in 0..30 {
+        simulation::step(&mut world, None, &mut changes);
+        reaped += reaper.observe(&mut world, &mut changes);
+    }
+    assert!(reaped > 0);
+}
@@ -0,0 +1,127 @@
+//! A reference pattern shown dimly behind the world (see `--reference`
+//! and `o`), e.g. to recreate a known pattern by hand cell by cell.
+//!
+//! The reference is read-only and takes no part in advancing the world.
+//! Its cells line up with the world's by their coordinates, just as a
+//! file of the same pattern loaded with `--file` would; cells beyond its
+//! edges count as dead.  The user interface draws the cells alive in the
+//! reference but dead in the world dimly while the overlay is shown,
+//! and the status line tells how many cells differ (see `World::diff`.)
+
+use world::World;
+
+/// A pattern to match the world against; see the module's description.
+pub struct Reference {
+    world: World,
+    shown: bool,
+    // ~ the revision of the world the difference was last counted for
+    seen: Option<usize>,
+    differing: usize,
+}
+
+impl Reference {
+    /// Creates a reference of the given pattern, shown at first.
+    pub fn new(world: World) -> Reference {
+        Reference {
+            world: world,
+            shown: true,
+            seen: None,
+            differing: 0,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Whether the overlay is shown.
+    pub fn shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Shows the overlay if it was hidden and vice versa; tells whether
+    /// it is shown now.
+    pub fn toggle(&mut self) -> bool {
+        self.shown ^= true;
+        self.shown
+    }
+
+    /// Moves the reference's cells along with those of a world which grew
+    /// by the given number of cells to the left and top, e.g. as it
+    /// expands automatically.
+    pub fn translate(&mut self, dx: usize, dy: usize) {
+        if dx > 0 || dy > 0 {
+            self.world.grow(dx, dy, 0, 0);
+            self.seen = None;
+        }
+    }
+
+    /// Counts the cells differing between the given world and the
+    /// reference unless the world did not change since they were last
+    /// counted (see `World::revision`), e.g. for a redraw while paused.
+    pub fn update(&mut self, world: &World) {
+        if self.seen != Some(world.revision()) {
+            self.differing = world.diff(&self.world).len();
+            self.seen = Some(world.revision());
+        }
+    }
+
+    /// The number of cells alive in one of the world and the reference
+    /// only, as of the last `update`.
+    pub fn differing(&self) -> usize {
+        self.differing
+    }
+
+    /// Whether the given cell of the world is to be drawn as the
+    /// reference's: dead in the world, alive in the reference, and the
+    /// overlay shown.
+    pub fn behind(&self, x: usize, y: usize, alive: bool) -> bool {
+        self.shown && !alive && self.world.get(x, y).unwrap_or(false)
+    }
+}
+
+#[test]
+fn test_differing() {
+    let mut glider = World::empty(5, 4);
+    for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        glider.set_alive(x, y, true);
+    }
+    let mut reference = Reference::new(glider.clone());
+    // ~ an empty world of another size lacks all five cells
+    let mut edit = World::empty(8, 6);
+    reference.update(&edit);
+    assert_eq!(5, reference.differing());
+    // ~ recreated cell by cell, with one cell too many along the way
+    for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (5, 5)] {
+        edit.set_alive(x, y, true);
+    }
+    reference.update(&edit);
+    assert_eq!(2, reference.differing());
+    assert_eq!(vec![(2, 2, false), (5, 5, true)], edit.diff(reference.world()));
+    edit.set_alive(2, 2, true);
+    edit.set_alive(5, 5, false);
+    reference.update(&edit);
+    assert_eq!(0, reference.differing());
+    // ~ advancing the world leaves the reference as it is
+    edit.advance_generations(1);
+    reference.update(&edit);
+    assert_eq!(glider.live_cells(), reference.world().live_cells());
+    assert!(reference.differing() > 0);
+
+    // ~ only the missing cells are drawn as the reference's, and only
+    // while shown
+    assert!(reference.behind(1, 0, false));
+    assert!(!reference.behind(1, 0, true));
+    assert!(!reference.behind(0, 0, false));
+    assert!(!reference.behind(7, 5, false));
+    assert!(!reference.toggle());
+    assert!(!reference.behind(1, 0, false));
+
+    // ~ moved along with a world grown to the left and top
+    let mut grown = glider.clone();
+    grown.grow(3, 1, 0, 0);
+    reference.translate(3, 1);
+    reference.update(&grown);
+    assert_eq!(0, reference.differing());
+    assert!(reference.toggle() && reference.behind(4, 1, false));
+}
@@ -0,0 +1,442 @@
+//! Remote control of the UI by another process over TCP.
+//!
+//! A client sends one JSON object per line naming a command by its
+//! `cmd` key, e.g. `{"cmd":"step","n":10}`:
+//!
+//! - `pause` and `resume` the animation
+//! - `step` advances `n` (default 1) generations, pausing first
+//! - `set-speed` sets the delay between two generations to the one
+//!   `+` and `-` can reach nearest to `delay_ms`
+//! - `reseed` generates a random world from `seed` (a fresh one if
+//!   not given) with the probability `density` of a cell being alive
+//! - `load-pattern` stamps the bundled pattern `name` (see
+//!   `patterns::STAMPS`) at the center of an emptied world
+//! - `status` changes nothing
+//!
+//! Each command is answered by one line: the status of the game once
+//! the command was carried out (see `Status`), or `{"error":"..."}`
+//! for a malformed one, the connection staying open either way.  The
+//! UI carries out the commands by dispatching the corresponding keys,
+//! like it replays macros.  One client is served at a time; others are
+//! turned away.  Neither accepting nor reading ever blocks: `Remote`
+//! is polled by the UI's event loop.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+
+use manifest::Json;
+use patterns;
+use world::Rule;
+
+/// The host bound to if `--listen` gives just a port.
+pub const DEFAULT_HOST: &'static str = "127.0.0.1";
+
+/// The most generations a single `step` command advances.
+pub const MAX_STEPS: usize = 10000;
+
+// ~ the longest command accepted; the connection is read in chunks
+// until a line is complete
+const MAX_LINE_LENGTH: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Pause,
+    Resume,
+    Step(usize),
+    /// the requested delay in milliseconds (at least one)
+    SetSpeed(u64),
+    Reseed {
+        seed: Option<u64>,
+        density: Option<f64>,
+    },
+    /// the index of the pattern in `patterns::STAMPS`
+    LoadPattern(usize),
+    Status,
+}
+
+/// Parses a command sent by a client.
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let doc = try!(Json::parse(line));
+    let cmd = match doc.get("cmd") {
+        Some(&Json::Str(ref cmd)) => cmd,
+        _ => return Err("Expected an object with a \"cmd\"".to_owned()),
+    };
+    match &cmd[..] {
+        "pause" => Ok(Command::Pause),
+        "resume" => Ok(Command::Resume),
+        "status" => Ok(Command::Status),
+        "step" => {
+            match doc.get("n") {
+                None => Ok(Command::Step(1)),
+                Some(&Json::Int(n)) if n >= 0 && n as usize <= MAX_STEPS => Ok(Command::Step(n as usize)),
+                Some(n) => Err(format!("Invalid number of steps (0 to {}): {}", MAX_STEPS, n)),
+            }
+        }
+        "set-speed" => {
+            match doc.get("delay_ms") {
+                Some(&Json::Int(ms)) if ms > 0 => Ok(Command::SetSpeed(ms as u64)),
+                Some(ms) => Err(format!("Invalid delay: {}", ms)),
+                None => Err("Missing \"delay_ms\"".to_owned()),
+            }
+        }
+        "reseed" => {
+            // ~ seeds are written as strings (see `Status`), but plain
+            // numbers will do too
+            let seed = match doc.get("seed") {
+                None | Some(&Json::Null) => None,
+                Some(&Json::Int(s)) if s >= 0 => Some(s as u64),
+                Some(&Json::Str(ref s)) if s.parse::<u64>().is_ok() => s.parse().ok(),
+                Some(s) => return Err(format!("Invalid seed: {}", s)),
+            };
+            let density = match doc.get("density") {
+                None | Some(&Json::Null) => None,
+                Some(&Json::Int(d)) if d == 0 || d == 1 => Some(d as f64),
+                Some(&Json::Float(d)) if d >= 0.0 && d <= 1.0 => Some(d),
+                Some(d) => return Err(format!("Invalid density (0 to 1): {}", d)),
+            };
+            Ok(Command::Reseed {
+                seed: seed,
+                density: density,
+            })
+        }
+        "load-pattern" => {
+            let name = match doc.get("name") {
+                Some(&Json::Str(ref name)) => name,
+                _ => return Err("Missing \"name\"".to_owned()),
+            };
+            match patterns::STAMPS.iter().position(|&(n, _)| n.eq_ignore_ascii_case(name)) {
+                Some(i) => Ok(Command::LoadPattern(i)),
+                None => Err(format!("Unknown pattern: {}", name)),
+            }
+        }
+        _ => Err(format!("Unknown command: {}", cmd)),
+    }
+}
+
+/// The state of the game reported after every command.
+#[derive(Clone, Copy, Debug)]
+pub struct Status {
+    pub generation: usize,
+    pub alive: usize,
+    pub paused: bool,
+    pub rule: Rule,
+    /// the seed the displayed world was generated from, if any
+    pub seed: Option<u64>,
+    /// the delay between two generations; `None` if unthrottled
+    pub delay_ms: Option<i64>,
+}
+
+impl Status {
+    /// The status as sent to the client, e.g.
+    /// `{"generation":3,"alive":12,"paused":true,"rule":"B3/S23",
+    /// "seed":"42","delay_ms":100}`.  The seed is a string for it
+    /// might exceed the integers JSON readers handle exactly.
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![("generation".to_owned(), Json::Int(self.generation as i64)),
+                          ("alive".to_owned(), Json::Int(self.alive as i64)),
+                          ("paused".to_owned(), Json::Bool(self.paused)),
+                          ("rule".to_owned(), Json::Str(self.rule.to_string())),
+                          ("seed".to_owned(), Json::opt(self.seed, |s| Json::Str(s.to_string()))),
+                          ("delay_ms".to_owned(), Json::opt(self.delay_ms, Json::Int))])
+    }
+}
+
+fn error_json(msg: &str) -> Json {
+    Json::Object(vec![("error".to_owned(), Json::str(msg))])
+}
+
+/// Turns `PORT` into an address on `DEFAULT_HOST`; other addresses are
+/// taken as they are.
+pub fn address(s: &str) -> String {
+    match s.parse::<u16>() {
+        Ok(port) => format!("{}:{}", DEFAULT_HOST, port),
+        Err(_) => s.to_owned(),
+    }
+}
+
+/// A connection to a client; non-blocking unless in memory.
+pub trait Stream: Read + Write {}
+
+impl<S: Read + Write> Stream for S {}
+
+struct Client {
+    stream: Box<dyn Stream>,
+    // ~ received but not yet taken as commands
+    input: Vec<u8>,
+    // ~ whether the client closed its end
+    eof: bool,
+}
+
+impl Client {
+    // ~ reads whatever has arrived; `false` if the connection failed
+    fn receive(&mut self) -> bool {
+        let mut buf = [0; 1024];
+        while !self.eof {
+            match self.stream.read(&mut buf) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.input.extend_from_slice(&buf[..n]),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+
+    // ~ the next complete line, if any
+    fn next_line(&mut self) -> Option<Result<String, String>> {
+        match self.input.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                let line: Vec<u8> = self.input.drain(..i + 1).collect();
+                Some(String::from_utf8(line).map_err(|_| "Invalid UTF-8".to_owned()))
+            }
+            None if self.input.len() > MAX_LINE_LENGTH => {
+                self.input.clear();
+                Some(Err(format!("Command too long (more than {} bytes)", MAX_LINE_LENGTH)))
+            }
+            None => None,
+        }
+    }
+
+    fn send(&mut self, reply: &Json) -> bool {
+        writeln!(self.stream, "{}", reply).and_then(|_| self.stream.flush()).is_ok()
+    }
+}
+
+pub struct Remote {
+    listener: Option<TcpListener>,
+    client: Option<Client>,
+    // ~ whether the client's last command is yet to be answered
+    awaiting: bool,
+}
+
+impl Remote {
+    /// Listens for clients at the given address (see `address`.)
+    pub fn listen(addr: &str) -> Result<Remote, String> {
+        let listener = try!(TcpListener::bind(addr)
+                                .and_then(|l| l.set_nonblocking(true).map(|_| l))
+                                .map_err(|e| format!("{}: {}", addr, e)));
+        Ok(Remote {
+            listener: Some(listener),
+            client: None,
+            awaiting: false,
+        })
+    }
+
+    /// Serves the given (already connected) client only, e.g. a stream
+    /// in memory.
+    pub fn with_client<S: Stream + 'static>(stream: S) -> Remote {
+        Remote {
+            listener: None,
+            client: Some(Client {
+                stream: Box::new(stream),
+                input: Vec::new(),
+                eof: false,
+            }),
+            awaiting: false,
+        }
+    }
+
+    /// The address listened at, if listening.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.as_ref().and_then(|l| l.local_addr().ok())
+    }
+
+    /// Answers the client's last command with the given status, and
+    /// returns its next command if one has arrived.  Malformed commands
+    /// are answered right away.
+    pub fn poll(&mut self, status: Status) -> Option<Command> {
+        self.accept();
+        let mut client = match self.client.take() {
+            Some(client) => client,
+            None => return None,
+        };
+        let mut ok = true;
+        if self.awaiting {
+            self.awaiting = false;
+            ok = client.send(&status.to_json());
+        }
+        ok = ok && client.receive();
+        let mut cmd = None;
+        while ok && cmd.is_none() {
+            match client.next_line() {
+                None => break,
+                Some(Ok(ref line)) if line.trim().is_empty() => {}
+                Some(line) => {
+                    match line.and_then(|line| parse_command(&line)) {
+                        Ok(c) => cmd = Some(c),
+                        Err(e) => ok = client.send(&error_json(&e)),
+                    }
+                }
+            }
+        }
+        // ~ a client gone is dropped once its commands are taken
+        if ok && (cmd.is_some() || !client.eof) {
+            self.client = Some(client);
+        }
+        self.awaiting = cmd.is_some();
+        cmd
+    }
+
+    // ~ accepts a waiting client unless one is served already
+    fn accept(&mut self) {
+        let listener = match self.listener {
+            Some(ref l) => l,
+            None => return,
+        };
+        while let Ok((stream, _)) = listener.accept() {
+            if self.client.is_some() {
+                let mut stream = stream;
+                let _ = writeln!(stream, "{}", error_json("Another client is connected"));
+                continue;
+            }
+            if stream.set_nonblocking(true).is_ok() {
+                self.client = Some(Client {
+                    stream: Box::new(stream),
+                    input: Vec::new(),
+                    eof: false,
+                });
+            }
+        }
+    }
+}
+
+/// A client in memory; all it sent has arrived already.
+#[cfg(test)]
+pub struct MemoryClient {
+    input: io::Cursor<Vec<u8>>,
+    /// what the client received
+    pub output: ::std::rc::Rc<::std::cell::RefCell<Vec<u8>>>,
+}
+
+#[cfg(test)]
+impl MemoryClient {
+    pub fn new(input: &str) -> MemoryClient {
+        MemoryClient {
+            input: io::Cursor::new(input.as_bytes().to_vec()),
+            output: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Read for MemoryClient {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+#[cfg(test)]
+impl Write for MemoryClient {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_command() {
+    assert_eq!(Ok(Command::Pause), parse_command(r#"{"cmd":"pause"}"#));
+    assert_eq!(Ok(Command::Resume), parse_command(r#" {"cmd": "resume", "x": 1} "#));
+    assert_eq!(Ok(Command::Status), parse_command(r#"{"cmd":"status"}"#));
+    assert_eq!(Ok(Command::Step(1)), parse_command(r#"{"cmd":"step"}"#));
+    assert_eq!(Ok(Command::Step(25)), parse_command(r#"{"cmd":"step","n":25}"#));
+    assert_eq!(Ok(Command::SetSpeed(40)), parse_command(r#"{"cmd":"set-speed","delay_ms":40}"#));
+    assert_eq!(Ok(Command::Reseed {
+                   seed: Some(7),
+                   density: Some(0.5),
+               }),
+               parse_command(r#"{"cmd":"reseed","seed":"7","density":0.5}"#));
+    assert_eq!(Ok(Command::Reseed {
+                   seed: Some(7),
+                   density: None,
+               }),
+               parse_command(r#"{"cmd":"reseed","seed":7}"#));
+    assert_eq!(Ok(Command::Reseed {
+                   seed: None,
+                   density: Some(1.0),
+               }),
+               parse_command(r#"{"cmd":"reseed","density":1}"#));
+    assert_eq!(Ok(Command::LoadPattern(2)), parse_command(r#"{"cmd":"load-pattern","name":"r-pentomino"}"#));
+
+    assert_eq!(Err("Unknown command: jump".to_owned()), parse_command(r#"{"cmd":"jump"}"#));
+    assert_eq!(Err("Expected an object with a \"cmd\"".to_owned()), parse_command(r#"["pause"]"#));
+    assert_eq!(Err("Invalid number of steps (0 to 10000): -1".to_owned()),
+               parse_command(r#"{"cmd":"step","n":-1}"#));
+    assert_eq!(Err("Unknown pattern: Boat".to_owned()), parse_command(r#"{"cmd":"load-pattern","name":"Boat"}"#));
+    for line in &[r#"{"cmd":"step","n":10001}"#,
+                  r#"{"cmd":"set-speed","delay_ms":0}"#,
+                  r#"{"cmd":"set-speed"}"#,
+                  r#"{"cmd":"reseed","seed":-3}"#,
+                  r#"{"cmd":"reseed","density":1.5}"#,
+                  r#"{"cmd":"load-pattern"}"#,
+                  "pause"] {
+        assert!(parse_command(line).is_err(), "{}", line);
+    }
+}
+
+#[cfg(test)]
+fn status() -> Status {
+    Status {
+        generation: 4,
+        alive: 9,
+        paused: true,
+        rule: ::world::CONWAY,
+        seed: Some(u64::max_value()),
+        delay_ms: None,
+    }
+}
+
+#[test]
+fn test_poll() {
+    let client = MemoryClient::new("{\"cmd\":\"pause\"}\n\nnonsense\n{\"cmd\":\"status\"}\n{\"cmd\":\"resume\"}");
+    let output = client.output.clone();
+    let mut r = Remote::with_client(client);
+    assert_eq!(Some(Command::Pause), r.poll(status()));
+    assert!(output.borrow().is_empty());
+    // ~ the pause answered, the malformed line too
+    assert_eq!(Some(Command::Status), r.poll(status()));
+    assert_eq!(None, r.poll(status()));
+    assert_eq!("{\"generation\":4,\"alive\":9,\"paused\":true,\"rule\":\"B3/S23\",\
+                \"seed\":\"18446744073709551615\",\"delay_ms\":null}\n\
+                {\"error\":\"Invalid JSON at offset 0: expected null\"}\n\
+                {\"generation\":4,\"alive\":9,\"paused\":true,\"rule\":\"B3/S23\",\
+                \"seed\":\"18446744073709551615\",\"delay_ms\":null}\n",
+               String::from_utf8(output.borrow().clone()).unwrap());
+    // ~ the unterminated last line is dropped with the client
+    assert!(r.client.is_none());
+}
+
+#[test]
+fn test_single_client() {
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    let mut r = Remote::listen(&address("0")).unwrap();
+    let addr = r.local_addr().unwrap();
+    assert!(addr.ip().is_loopback());
+    let mut first = TcpStream::connect(addr).unwrap();
+    let second = TcpStream::connect(addr).unwrap();
+    first.write_all(b"{\"cmd\":\"step\",\"n\":2}\n").unwrap();
+    // ~ nothing blocks while waiting for the command to arrive
+    let mut cmd = None;
+    for _ in 0..100 {
+        cmd = r.poll(status());
+        if cmd.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(Some(Command::Step(2)), cmd);
+    let mut refused = String::new();
+    io::BufReader::new(second).read_to_string(&mut refused).unwrap();
+    assert_eq!("{\"error\":\"Another client is connected\"}\n", refused);
+    // ~ the first client is answered
+    assert_eq!(None, r.poll(status()));
+    let mut reply = [0; 16];
+    first.read_exact(&mut reply).unwrap();
+    assert_eq!(b"{\"generation\":4,", &reply);
+}
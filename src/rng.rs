@@ -0,0 +1,149 @@
+//! The random number generator of seeded worlds.
+//!
+//! A world generated from a seed (see `World::random_seeded`) must be
+//! the same on any platform and with any version of the `rand` crate,
+//! e.g. for bookmarks and fingerprints recorded earlier to stay valid.
+//! It is hence generated by the generator specified here rather than
+//! by `rand`, which is left to the unseeded worlds:
+//!
+//! - the numbers are those of xoshiro256** by Blackman and Vigna
+//!   (<https://prng.di.unimi.it/xoshiro256starstar.c>);
+//! - a generator is seeded from a `u64` by taking the first four
+//!   outputs of splitmix64 (<https://prng.di.unimi.it/splitmix64.c>)
+//!   started at it as its state;
+//! - a chance `p` is met if the upper 32 bits of the next number are
+//!   below `p * 2^32` (rounded), i.e. exactly for `p` a multiple of
+//!   `2^-32`, without any floating point arithmetic per number;
+//! - a number below `n` (at most `2^32`) is the upper 32 bits of the
+//!   next number multiplied by `n`, shifted right by 32 bits.
+//!
+//! Any change to the above changes the seeded worlds and must come with
+//! a new `GENERATOR`, which the run manifests record.
+
+/// The name and version of the scheme specified above.
+pub const GENERATOR: &'static str = "xoshiro256**+splitmix64/1";
+
+/// Returns the output of splitmix64 following the state `x`, i.e. its
+/// first output if started at `x`.
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// A probability in units of `2^-32`; see `chance`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chance(u64);
+
+/// Converts the given probability for `Rng::meets`; probabilities
+/// outside of 0 to 1 are taken as the nearer of the two.
+pub fn chance(p: f64) -> Chance {
+    let p = if p > 0.0 { p.min(1.0) } else { 0.0 };
+    Chance((p * 4294967296.0).round() as u64)
+}
+
+/// The xoshiro256** generator.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    s: [u64; 4],
+}
+
+impl Rng {
+    /// Creates a generator starting with the given state, which must
+    /// not be all zeros.
+    pub fn from_state(s: [u64; 4]) -> Rng {
+        assert!(s != [0; 4], "xoshiro256** must not start with a zero state");
+        Rng { s: s }
+    }
+
+    /// Creates a generator seeded by splitmix64 started at the given
+    /// seed.
+    pub fn seeded(seed: u64) -> Rng {
+        let mut x = seed;
+        let mut s = [0; 4];
+        for w in s.iter_mut() {
+            *w = splitmix64(x);
+            x = x.wrapping_add(0x9e3779b97f4a7c15);
+        }
+        // ~ splitmix64 is a bijection of its state advancing by an odd
+        // constant; four outputs are never all zeros
+        Rng::from_state(s)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+        result
+    }
+
+    /// Whether the next number meets the given chance.
+    pub fn meets(&mut self, c: Chance) -> bool {
+        self.next_u64() >> 32 < c.0
+    }
+
+    /// A number below `n`; `n` must be positive and at most `2^32`.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0 && n as u64 <= 1 << 32);
+        ((self.next_u64() >> 32) * n as u64 >> 32) as usize
+    }
+
+    /// Shuffles the given slice (Fisher-Yates, from its end.)
+    pub fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let j = self.below(i + 1);
+            v.swap(i, j);
+        }
+    }
+}
+
+#[test]
+fn test_splitmix64_reference() {
+    // ~ the outputs of splitmix64.c started at 1234567
+    let mut x: u64 = 1234567;
+    let mut outputs = Vec::new();
+    for _ in 0..5 {
+        outputs.push(splitmix64(x));
+        x = x.wrapping_add(0x9e3779b97f4a7c15);
+    }
+    assert_eq!(vec![6457827717110365317, 3203168211198807973, 9817491932198370423, 4593380528125082431,
+                    16408922859458223821],
+               outputs);
+}
+
+#[test]
+fn test_xoshiro256starstar_reference() {
+    // ~ the outputs of xoshiro256starstar.c starting with the state
+    // 1, 2, 3, 4
+    let mut r = Rng::from_state([1, 2, 3, 4]);
+    let outputs: Vec<u64> = (0..10).map(|_| r.next_u64()).collect();
+    assert_eq!(vec![11520, 0, 1509978240, 1215971899390074240, 1216172134540287360, 607988272756665600,
+                    16172922978634559625, 8476171486693032832, 10595114339597558777, 2904607092377533576],
+               outputs);
+}
+
+#[test]
+fn test_chances() {
+    let mut r = Rng::seeded(0);
+    assert_eq!(Chance(0), chance(-1.0));
+    assert_eq!(Chance(1 << 32), chance(2.0));
+    assert!((0..1000).all(|_| !r.meets(chance(0.0)) && r.meets(chance(1.0))));
+    let n = (0..100000).filter(|_| r.meets(chance(0.3))).count();
+    assert!(n > 29000 && n < 31000, "{}", n);
+    let mut seen = [0; 7];
+    for _ in 0..7000 {
+        seen[r.below(7)] += 1;
+    }
+    assert!(seen.iter().all(|&n| n > 850 && n < 1150), "{:?}", seen);
+    let mut v: Vec<usize> = (0..50).collect();
+    r.shuffle(&mut v);
+    assert!(v != (0..50).collect::<Vec<_>>());
+    v.sort();
+    assert_eq!((0..50).collect::<Vec<_>>(), v);
+}
@@ -0,0 +1,143 @@
+//! A rule and a pattern given in a single argument (see `--run`), e.g.
+//! `B3/S23:glider@40,20`:
+//!
+//! ```text
+//! SPEC    = RULE ":" PATTERN [ "@" X "," Y ]
+//! RULE    = the B/S notation, e.g. B36/S23 (see `Rule::parse`)
+//! PATTERN = the name of a bundled pattern (see `patterns::STAMPS`;
+//!           case insensitive), or the rows of a pattern in the
+//!           plaintext format (`.` and `O`) separated by `$`,
+//!           e.g. .O.$..O$OOO
+//! X, Y    = the position of the pattern's top left cell, wrapping
+//!           around the edges like `World::blit`; without it, the
+//!           pattern is centered
+//! ```
+
+use parser;
+use patterns;
+use stamp::Stamp;
+use world::{Rule, World};
+
+/// The parts of a parsed spec.
+#[derive(Debug)]
+pub struct RunSpec {
+    pub rule: Rule,
+    /// trimmed to its live cells
+    pub pattern: World,
+    pub offset: Option<(isize, isize)>,
+}
+
+impl RunSpec {
+    pub fn parse(s: &str) -> Result<RunSpec, String> {
+        let colon = match s.find(':') {
+            Some(colon) => colon,
+            None => return Err(format!("Invalid run spec (expected RULE:PATTERN[@X,Y]): {}", s)),
+        };
+        let (rule, rest) = (&s[..colon], &s[colon + 1..]);
+        let rule = try!(Rule::parse(rule).ok_or_else(|| format!("Invalid rule: {}", rule)));
+        let (pattern, offset) = match rest.rfind('@') {
+            None => (rest, None),
+            Some(at) => (&rest[..at], Some(try!(parse_offset(&rest[at + 1..])))),
+        };
+        Ok(RunSpec {
+            rule: rule,
+            pattern: try!(parse_pattern(pattern)),
+            offset: offset,
+        })
+    }
+
+    /// An empty world of the given size with the pattern placed onto
+    /// it; patterns larger than the world are refused.
+    pub fn world(&self, width: usize, height: usize) -> Result<World, String> {
+        let (w, h) = (self.pattern.width(), self.pattern.height());
+        if w > width || h > height {
+            return Err(format!("The pattern takes {}x{} cells, the world has {}x{}", w, h, width, height));
+        }
+        let (x, y) = self.offset.unwrap_or((((width - w) / 2) as isize, ((height - h) / 2) as isize));
+        let mut world = World::empty(width, height);
+        world.set_rule(self.rule);
+        world.blit(x, y, &self.pattern);
+        Ok(world)
+    }
+}
+
+fn parse_offset(s: &str) -> Result<(isize, isize), String> {
+    let mut parts = s.splitn(2, ',');
+    match (parts.next().and_then(|x| x.trim().parse().ok()),
+           parts.next().and_then(|y| y.trim().parse().ok())) {
+        (Some(x), Some(y)) => Ok((x, y)),
+        _ => Err(format!("Invalid offset (expected X,Y): {}", s)),
+    }
+}
+
+fn parse_pattern(s: &str) -> Result<World, String> {
+    if let Some(i) = patterns::STAMPS.iter().position(|&(n, _)| n.eq_ignore_ascii_case(s)) {
+        return Ok(Stamp::bundled(i).pattern().clone());
+    }
+    if s.is_empty() || !s.chars().all(|c| c == '.' || c == 'O' || c == '$') {
+        let names: Vec<_> = patterns::STAMPS.iter().map(|&(n, _)| n).collect();
+        return Err(format!("Unknown pattern: {} (bundled: {}; or rows of . and O separated by $)",
+                           s,
+                           names.join(", ")));
+    }
+    let mut pattern = try!(parser::parse_cells(&s.replace('$', "\n"))
+                               .map_err(|e| format!("Invalid pattern {}: {}", s, e)));
+    if pattern.is_empty() {
+        return Err(format!("Invalid pattern {}: no live cells", s));
+    }
+    pattern.trim(0);
+    Ok(pattern)
+}
+
+#[test]
+fn test_parse() {
+    let spec = RunSpec::parse("B3/S23:glider@40,20").unwrap();
+    assert_eq!(::world::CONWAY, spec.rule);
+    assert_eq!(Some((40, 20)), spec.offset);
+    assert_eq!(".O\n..O\nOOO\n", parser::render_cells(&spec.pattern, &[]));
+
+    let spec = RunSpec::parse("b36/s23:Gosper glider gun").unwrap();
+    assert_eq!("B36/S23", spec.rule.to_string());
+    assert_eq!(None, spec.offset);
+    assert_eq!((36, 9), (spec.pattern.width(), spec.pattern.height()));
+
+    // ~ inline, trimmed to the live cells
+    let spec = RunSpec::parse("B3/S23:....$.OO.$.OO.@-1, 2").unwrap();
+    assert_eq!(Some((-1, 2)), spec.offset);
+    assert_eq!("OO\nOO\n", parser::render_cells(&spec.pattern, &[]));
+}
+
+#[test]
+fn test_parse_malformed() {
+    let err = |s: &str| RunSpec::parse(s).unwrap_err();
+    assert_eq!("Invalid run spec (expected RULE:PATTERN[@X,Y]): glider", err("glider"));
+    assert_eq!("Invalid rule: 3/23", err("3/23:glider"));
+    assert_eq!("Invalid rule: ", err(":glider"));
+    assert!(err("B3/S23:boat").starts_with("Unknown pattern: boat (bundled: Glider, "));
+    assert!(err("B3/S23:").starts_with("Unknown pattern:  (bundled: "));
+    assert!(err("B3/S23:.o.").starts_with("Unknown pattern: .o. "));
+    assert_eq!("Invalid pattern ...$...: no live cells", err("B3/S23:...$..."));
+    assert_eq!("Invalid offset (expected X,Y): 40", err("B3/S23:glider@40"));
+    assert_eq!("Invalid offset (expected X,Y): x,1", err("B3/S23:glider@x,1"));
+    assert_eq!("Invalid offset (expected X,Y): ", err("B3/S23:glider@"));
+}
+
+#[test]
+fn test_world() {
+    let spec = RunSpec::parse("B3/S23:glider@1,0").unwrap();
+    let world = spec.world(6, 4).unwrap();
+    assert_eq!(::world::CONWAY, world.rule());
+    assert_eq!("..O\n...O\n.OOO\n.\n", parser::render_cells(&world, &[]));
+
+    // ~ centered without an offset; wrapping around the edges with one
+    let spec = RunSpec::parse("B36/S23:OO$OO").unwrap();
+    let world = spec.world(6, 4).unwrap();
+    assert_eq!("B36/S23", world.rule().to_string());
+    assert_eq!(".\n..OO\n..OO\n.\n", parser::render_cells(&world, &[]));
+    let spec = RunSpec::parse("B3/S23:OO$OO@5,3").unwrap();
+    assert_eq!("O....O\n.\n.\nO....O\n", parser::render_cells(&spec.world(6, 4).unwrap(), &[]));
+
+    let spec = RunSpec::parse("B3/S23:pulsar").unwrap();
+    assert_eq!("The pattern takes 13x13 cells, the world has 20x10",
+               spec.world(20, 10).unwrap_err());
+}
@@ -0,0 +1,199 @@
+//! Switching the rule of a world at given generations (see
+//! `--rule-schedule`), e.g. for runs whose character changes over time.
+//!
+//! A `RuleSchedule` lists the generations at which its rules take
+//! effect, starting at generation 0; a world lives by the rule of the
+//! last of them not after its generation.  The schedule is consulted
+//! before each generation is advanced (see `apply`): the rule of the
+//! generation a world is at decides its next one.  Since the rule
+//! follows from the generation alone, a world restored at a generation
+//! (e.g. from a slot or a binary file) picks up the schedule at the
+//! right position.
+//!
+//! A schedule is written like `0:B3/S23,500:B36/S23`; `Display` gives
+//! it back in that form.
+
+use std::fmt;
+
+use period::{self, Measurement};
+use world::{Rule, World};
+
+/// The rules of a world by generation; see the module's description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleSchedule {
+    // ~ ascending by generation, the first at generation 0
+    entries: Vec<(usize, Rule)>,
+}
+
+impl RuleSchedule {
+    /// Parses a schedule like `0:B3/S23,500:B36/S23`: rules preceded by
+    /// the generations they take effect at, strictly ascending from 0.
+    pub fn parse(s: &str) -> Result<RuleSchedule, String> {
+        let mut entries: Vec<(usize, Rule)> = Vec::new();
+        for part in s.split(',') {
+            let mut fields = part.trim().splitn(2, ':');
+            let (generation, rule) = match (fields.next(), fields.next()) {
+                (Some(g), Some(r)) => (g.trim(), r.trim()),
+                _ => return Err(format!("Invalid schedule entry (expected GEN:RULE): {}", part)),
+            };
+            let generation = try!(generation.parse::<usize>()
+                                            .map_err(|_| format!("Invalid generation: {}", generation)));
+            let rule = try!(Rule::parse(rule).ok_or_else(|| format!("Invalid rule: {}", rule)));
+            match entries.last() {
+                None if generation != 0 => {
+                    return Err(format!("The schedule starts at generation {} (expected 0)", generation))
+                }
+                Some(&(last, _)) if generation == last => {
+                    return Err(format!("Generation {} is scheduled twice", generation))
+                }
+                Some(&(last, _)) if generation < last => {
+                    return Err(format!("Generation {} is scheduled after generation {}", generation, last))
+                }
+                _ => {}
+            }
+            entries.push((generation, rule));
+        }
+        Ok(RuleSchedule { entries: entries })
+    }
+
+    /// The rule the world at the given generation lives by.
+    pub fn rule_at(&self, generation: usize) -> &Rule {
+        let i = match self.entries.binary_search_by_key(&generation, |e| e.0) {
+            Ok(i) => i,
+            // ~ the first entry is at generation 0
+            Err(i) => i - 1,
+        };
+        &self.entries[i].1
+    }
+
+    /// The next switch after the given generation, if any: the
+    /// generation and the rule taking effect at it.
+    pub fn next_switch(&self, generation: usize) -> Option<(usize, Rule)> {
+        let i = match self.entries.binary_search_by_key(&generation, |e| e.0) {
+            Ok(i) => i + 1,
+            Err(i) => i,
+        };
+        self.entries.get(i).cloned()
+    }
+
+    /// Sets the rule of the given world's generation; returns it if it
+    /// differs from the one the world lived by.
+    pub fn apply(&self, world: &mut World) -> Option<Rule> {
+        let rule = *self.rule_at(world.generation());
+        if rule == world.rule() {
+            return None;
+        }
+        world.set_rule(rule);
+        Some(rule)
+    }
+
+    /// The number of generations a world at the given generation can
+    /// be advanced by before the next switch (at most `n`).
+    pub fn steady_for(&self, generation: usize, n: usize) -> usize {
+        match self.next_switch(generation) {
+            Some((g, _)) if g - generation < n => g - generation,
+            _ => n,
+        }
+    }
+
+    /// Measures the period of the given world (see `period::measure`)
+    /// under the rule of its generation; a recurrence counts only before
+    /// the next switch, which would invalidate it.
+    pub fn measure_period(&self, world: &World, max_period: usize) -> Option<Measurement> {
+        let mut w = world.clone();
+        self.apply(&mut w);
+        period::measure(&w, self.steady_for(w.generation(), max_period))
+    }
+}
+
+impl fmt::Display for RuleSchedule {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &(generation, rule)) in self.entries.iter().enumerate() {
+            try!(write!(fmt, "{}{}:{}", if i > 0 { "," } else { "" }, generation, rule));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse() {
+    let s = RuleSchedule::parse("0:B3/S23, 500:b36/s23,1200:B3678/S34678").unwrap();
+    assert_eq!("0:B3/S23,500:B36/S23,1200:B3678/S34678", s.to_string());
+    assert_eq!(Ok(s.clone()), RuleSchedule::parse(&s.to_string()));
+    assert_eq!("0:B2/S", RuleSchedule::parse("0:B2/S").unwrap().to_string());
+
+    let err = |s: &str| RuleSchedule::parse(s).unwrap_err();
+    assert_eq!("Generation 500 is scheduled after generation 1200", err("0:B3/S23,1200:B2/S,500:B36/S23"));
+    assert_eq!("Generation 500 is scheduled twice", err("0:B3/S23,500:B2/S,500:B36/S23"));
+    assert_eq!("The schedule starts at generation 10 (expected 0)", err("10:B3/S23"));
+    assert_eq!("Invalid rule: B9/S23", err("0:B3/S23,500:B9/S23"));
+    assert_eq!("Invalid generation: -5", err("0:B3/S23,-5:B36/S23"));
+    assert_eq!("Invalid schedule entry (expected GEN:RULE): B3/S23", err("B3/S23"));
+    assert!(RuleSchedule::parse("").is_err());
+}
+
+#[test]
+fn test_rule_at() {
+    let s = RuleSchedule::parse("0:B3/S23,500:B36/S23,1200:B3678/S34678").unwrap();
+    let rule = |g| s.rule_at(g).to_string();
+    assert_eq!(("B3/S23", "B3/S23"), (&rule(0)[..], &rule(499)[..]));
+    assert_eq!(("B36/S23", "B36/S23"), (&rule(500)[..], &rule(1199)[..]));
+    assert_eq!(("B3678/S34678", "B3678/S34678"), (&rule(1200)[..], &rule(usize::max_value())[..]));
+    assert_eq!(Some(500), s.next_switch(0).map(|n| n.0));
+    assert_eq!(Some(1200), s.next_switch(500).map(|n| n.0));
+    assert_eq!(None, s.next_switch(1200));
+    assert_eq!((100, 1, 7), (s.steady_for(400, 1000), s.steady_for(499, 1000), s.steady_for(600, 7)));
+    assert_eq!(1000, s.steady_for(1500, 1000));
+}
+
+#[test]
+fn test_schedule_matches_manual_switches() {
+    // ~ the schedule applied before each generation advances the board
+    // like switching the rules by hand at the same generations
+    let s = RuleSchedule::parse("0:B3/S23,30:B36/S23,70:B3678/S34678").unwrap();
+    let board = World::random_seeded(42, 48, 32);
+    let mut scheduled = board.clone();
+    let mut switches = Vec::new();
+    for _ in 0..100 {
+        if let Some(rule) = s.apply(&mut scheduled) {
+            switches.push((scheduled.generation(), rule.to_string()));
+        }
+        scheduled.advance_generations(1);
+    }
+    assert_eq!(vec![(30, "B36/S23".to_owned()), (70, "B3678/S34678".to_owned())], switches);
+
+    let mut manual = board.clone();
+    for &(until, rule) in &[(30, "B3/S23"), (70, "B36/S23"), (100, "B3678/S34678")] {
+        manual.set_rule(Rule::parse(rule).unwrap());
+        manual.advance_generations(until - manual.generation());
+    }
+    assert_eq!(100, scheduled.generation());
+    assert_eq!(manual.live_cells(), scheduled.live_cells());
+    // ~ the rules matter: Conway's all the way ends up elsewhere
+    let mut conway = board.clone();
+    conway.advance_generations(100);
+    assert!(conway.live_cells() != scheduled.live_cells());
+}
+
+#[test]
+fn test_resume_from_binary() {
+    // ~ a world saved at a generation picks up the schedule where it
+    // left off once loaded
+    let s = RuleSchedule::parse("0:B3/S23,30:B36/S23,70:B3678/S34678").unwrap();
+    let mut world = World::random_seeded(7, 40, 30);
+    let run = |w: &mut World, until: usize| {
+        while w.generation() < until {
+            s.apply(w);
+            w.advance_generations(1);
+        }
+    };
+    run(&mut world, 50);
+    let mut buf = Vec::new();
+    world.save_binary(&mut buf).unwrap();
+    let mut loaded = World::load_binary(&buf[..]).unwrap();
+    assert_eq!(50, loaded.generation());
+    run(&mut world, 100);
+    run(&mut loaded, 100);
+    assert_eq!(world.rule(), loaded.rule());
+    assert_eq!(world.live_cells(), loaded.live_cells());
+}
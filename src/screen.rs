@@ -0,0 +1,248 @@
+//! The mapping between the cells of a world and the positions on the
+//! screen they are drawn at.
+//!
+//! The world's visible part (its viewport) is drawn starting at the top
+//! left corner of the screen, or within a one character frame around
+//! it.  Each cell takes `cell_width` columns, e.g. two for double-width
+//! glyphs.  The last line of the screen is taken by the status line if
+//! shown.  The screen may be a part of the terminal's, e.g. to show a
+//! small world framed in the middle of it.
+//!
+//! The size of the terminal is taken once per frame (see
+//! `FrameGeometry`): a terminal resized while a frame is drawn, e.g.
+//! while its window is dragged, must not have the rest of the frame
+//! printed beyond its new edges.
+
+use std::cmp;
+
+/// Describes how the cells of a world are laid out on the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScreenMap {
+    /// the size of the screen in columns and lines
+    pub screen_width: usize,
+    pub screen_height: usize,
+    /// the terminal's column and line of the screen's top left corner
+    pub origin: (usize, usize),
+    /// the number of columns a cell takes; 1 or 2
+    pub cell_width: usize,
+    /// whether the world is framed by a one character border
+    pub frame: bool,
+    /// whether the last line of the screen shows the status line
+    pub status: bool,
+    /// the world's cell shown in the top left corner of the world's
+    /// part of the screen
+    pub viewport: (usize, usize),
+}
+
+impl ScreenMap {
+    /// Maps the cells of a world one to one to the screen's characters,
+    /// unframed and starting with the world's top left cell.
+    pub fn new(screen_width: usize, screen_height: usize, status: bool) -> ScreenMap {
+        ScreenMap {
+            screen_width: screen_width,
+            screen_height: screen_height,
+            origin: (0, 0),
+            cell_width: 1,
+            frame: false,
+            status: status,
+            viewport: (0, 0),
+        }
+    }
+
+    // ~ the offset of the first cell's position on the screen
+    fn border(&self) -> usize {
+        if self.frame {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// The number of cells of the world visible in either direction.
+    pub fn visible(&self) -> (usize, usize) {
+        let lines = self.screen_height.saturating_sub(if self.status { 1 } else { 0 });
+        let inner = |len: usize| len.saturating_sub(2 * self.border());
+        (inner(self.screen_width) / cmp::max(1, self.cell_width), inner(lines))
+    }
+
+    /// Determines the screen position (column and line) of the
+    /// leftmost column of the given cell of the world; `None` if the
+    /// cell is not visible.
+    pub fn world_to_screen(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (vx, vy) = self.viewport;
+        let (w, h) = self.visible();
+        if x < vx || y < vy || x - vx >= w || y - vy >= h {
+            return None;
+        }
+        Some((self.origin.0 + self.border() + (x - vx) * self.cell_width,
+              self.origin.1 + self.border() + (y - vy)))
+    }
+
+    /// Determines the cell of the world shown at the given screen
+    /// position (any column of a wide cell); `None` for the positions
+    /// of the status line, the frame, the padding next to the last
+    /// cell, or beyond the screen.
+    pub fn screen_to_world(&self, col: usize, line: usize) -> Option<(usize, usize)> {
+        let (b0, b1) = (self.origin.0 + self.border(), self.origin.1 + self.border());
+        if col < b0 || line < b1 {
+            return None;
+        }
+        let (x, y) = ((col - b0) / cmp::max(1, self.cell_width), line - b1);
+        let (w, h) = self.visible();
+        if x >= w || y >= h {
+            return None;
+        }
+        Some((self.viewport.0 + x, self.viewport.1 + y))
+    }
+}
+
+/// The size of the terminal a frame is drawn for, taken when the frame
+/// begins; everything printed in the frame is laid out for it and
+/// clipped to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameGeometry {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FrameGeometry {
+    pub fn new(width: usize, height: usize) -> FrameGeometry {
+        FrameGeometry {
+            width: width,
+            height: height,
+        }
+    }
+
+    /// The number of lines available to the world; all but the last
+    /// one if it shows the status line.
+    pub fn world_lines(&self, status: bool) -> usize {
+        if status {
+            self.height.saturating_sub(1)
+        } else {
+            self.height
+        }
+    }
+
+    /// The part of this geometry still on a terminal of the given size,
+    /// e.g. one shrunk since the frame began.
+    pub fn within(&self, width: usize, height: usize) -> FrameGeometry {
+        FrameGeometry::new(cmp::min(self.width, width), cmp::min(self.height, height))
+    }
+
+    /// The number of the `len` characters printed from the given
+    /// position on which fall within this geometry; those beyond its
+    /// right edge are dropped rather than wrapped.
+    pub fn clip(&self, x: usize, y: usize, len: usize) -> usize {
+        if y >= self.height {
+            0
+        } else {
+            cmp::min(len, self.width.saturating_sub(x))
+        }
+    }
+}
+
+#[test]
+fn test_identity() {
+    let m = ScreenMap::new(10, 5, true);
+    assert_eq!((10, 4), m.visible());
+    assert_eq!(Some((3, 2)), m.world_to_screen(3, 2));
+    assert_eq!(Some((9, 3)), m.screen_to_world(9, 3));
+    // ~ the status line
+    assert_eq!(None, m.world_to_screen(3, 4));
+    assert_eq!(None, m.screen_to_world(3, 4));
+    assert_eq!(Some((3, 4)), ScreenMap { status: false, ..m }.screen_to_world(3, 4));
+}
+
+#[test]
+fn test_origin() {
+    // ~ a framed 3x2 world in the middle of a 20x10 terminal
+    let m = ScreenMap {
+        origin: (7, 3),
+        frame: true,
+        ..ScreenMap::new(5, 4, false)
+    };
+    assert_eq!((3, 2), m.visible());
+    assert_eq!(Some((8, 4)), m.world_to_screen(0, 0));
+    assert_eq!(Some((10, 5)), m.world_to_screen(2, 1));
+    assert_eq!(Some((2, 1)), m.screen_to_world(10, 5));
+    // ~ the frame and the terminal around it
+    for &(col, line) in &[(7, 4), (8, 3), (11, 5), (8, 6), (0, 0), (19, 9)] {
+        assert_eq!(None, m.screen_to_world(col, line));
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    for &cell_width in [1, 2].iter() {
+        for &frame in [false, true].iter() {
+            for &status in [false, true].iter() {
+                for &viewport in [(0, 0), (3, 2)].iter() {
+                    let m = ScreenMap {
+                        screen_width: 13,
+                        screen_height: 7,
+                        origin: (0, 0),
+                        cell_width: cell_width,
+                        frame: frame,
+                        status: status,
+                        viewport: viewport,
+                    };
+                    let b = if frame { 1 } else { 0 };
+                    let lines = if status { 6 } else { 7 };
+                    let (w, h) = m.visible();
+                    assert_eq!(((13 - 2 * b) / cell_width, lines - 2 * b), (w, h), "{:?}", m);
+                    // ~ every screen position either shows a cell or is
+                    // part of the frame, the status line or the padding
+                    let mut shown = 0;
+                    for line in 0..7 {
+                        for col in 0..13 {
+                            let inside = col >= b && line >= b && line < lines - b &&
+                                         (col - b) / cell_width < w;
+                            match m.screen_to_world(col, line) {
+                                Some((x, y)) => {
+                                    assert!(inside, "{:?} {},{}", m, col, line);
+                                    // ~ the leftmost column of the cell
+                                    assert_eq!(Some((col - (col - b) % cell_width, line)),
+                                               m.world_to_screen(x, y));
+                                    shown += 1;
+                                }
+                                None => assert!(!inside, "{:?} {},{}", m, col, line),
+                            }
+                        }
+                    }
+                    assert_eq!(w * h * cell_width, shown);
+                    // ~ every visible cell maps back to itself; others
+                    // are not shown
+                    for y in 0..12 {
+                        for x in 0..16 {
+                            let visible = x >= viewport.0 && y >= viewport.1 &&
+                                          x - viewport.0 < w &&
+                                          y - viewport.1 < h;
+                            match m.world_to_screen(x, y) {
+                                Some((col, line)) => {
+                                    assert!(visible);
+                                    for c in col..col + cell_width {
+                                        assert_eq!(Some((x, y)), m.screen_to_world(c, line));
+                                    }
+                                }
+                                None => assert!(!visible, "{:?} {},{}", m, x, y),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_frame_geometry() {
+    let g = FrameGeometry::new(40, 12);
+    assert_eq!((11, 12), (g.world_lines(true), g.world_lines(false)));
+    assert_eq!(0, FrameGeometry::new(40, 0).world_lines(true));
+    assert_eq!((10, 40, 0, 0), (g.clip(0, 11, 10), g.clip(0, 0, 50), g.clip(40, 0, 1), g.clip(0, 12, 1)));
+    assert_eq!(3, g.clip(37, 5, 10));
+    // ~ the terminal shrunk meanwhile
+    let shrunk = g.within(30, 20);
+    assert_eq!(FrameGeometry::new(30, 12), shrunk);
+    assert_eq!(0, shrunk.clip(35, 0, 3));
+}
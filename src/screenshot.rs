@@ -0,0 +1,247 @@
+//! Capturing the screen as shown, e.g. to attach it to a bug report
+//! (see `F12` and `--screenshot-on-exit`).
+//!
+//! Terminals (and rustbox) offer no way to read back what was printed;
+//! the user interface hence mirrors every print in a `ScreenBuffer`, a
+//! shadow of the terminal's buffers: prints go to the back buffer,
+//! which `present` copies to the front one, just like the terminal
+//! does.  The front buffer can be rendered at any time as plain text
+//! or as text with ANSI escape sequences reproducing the colors and
+//! attributes (24-bit colors as printed, even if the terminal
+//! approximated them.)
+
+use rustbox::{self, Color, Style};
+
+use color::{self, Rgb};
+use io_util;
+
+/// A cell of the screen.
+#[derive(Clone, Copy)]
+pub struct ScreenCell {
+    pub ch: char,
+    pub style: Style,
+    /// the colors; the basic ones closest to the 24-bit colors if
+    /// printed in them
+    pub fg: Color,
+    pub bg: Color,
+    /// the colors if printed in 24-bit colors
+    pub fg_rgb: Option<Rgb>,
+    pub bg_rgb: Option<Rgb>,
+}
+
+const BLANK: ScreenCell = ScreenCell {
+    ch: ' ',
+    style: rustbox::RB_NORMAL,
+    fg: Color::Default,
+    bg: Color::Default,
+    fg_rgb: None,
+    bg_rgb: None,
+};
+
+/// The shadow of a terminal's buffers; see the module's description.
+pub struct ScreenBuffer {
+    width: usize,
+    height: usize,
+    back: Vec<ScreenCell>,
+    front: Vec<ScreenCell>,
+}
+
+impl ScreenBuffer {
+    pub fn new(width: usize, height: usize) -> ScreenBuffer {
+        ScreenBuffer {
+            width: width,
+            height: height,
+            back: vec![BLANK; width * height],
+            front: vec![BLANK; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Changes the size of the screen, clearing it; the user interface
+    /// redraws everything after a resize anyway.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = ScreenBuffer::new(width, height);
+    }
+
+    pub fn print(&mut self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) {
+        for (i, c) in s.chars().enumerate() {
+            self.print_char(x + i, y, sty, fg, bg, c);
+        }
+    }
+
+    pub fn print_char(&mut self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, c: char) {
+        self.put(x,
+                 y,
+                 ScreenCell {
+                     ch: c,
+                     style: sty,
+                     fg: fg,
+                     bg: bg,
+                     fg_rgb: None,
+                     bg_rgb: None,
+                 });
+    }
+
+    pub fn print_char_rgb(&mut self, x: usize, y: usize, sty: Style, fg: Rgb, bg: Rgb, c: char) {
+        self.put(x,
+                 y,
+                 ScreenCell {
+                     ch: c,
+                     style: sty,
+                     fg: color::nearest_basic(fg),
+                     bg: color::nearest_basic(bg),
+                     fg_rgb: Some(fg),
+                     bg_rgb: Some(bg),
+                 });
+    }
+
+    pub fn clear(&mut self) {
+        for c in &mut self.back {
+            *c = BLANK;
+        }
+    }
+
+    pub fn present(&mut self) {
+        self.front.clone_from(&self.back);
+    }
+
+    /// Returns the presented cell at the given position.
+    pub fn cell(&self, x: usize, y: usize) -> ScreenCell {
+        self.front[y * self.width + x]
+    }
+
+    /// Renders the presented screen as plain text, a line per row;
+    /// trailing blanks are left out.
+    pub fn to_text(&self) -> String {
+        let mut s = String::new();
+        for y in 0..self.height {
+            let line: String = (0..self.width).map(|x| self.cell(x, y).ch).collect();
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Renders the presented screen as text with ANSI escape sequences
+    /// (SGR) for the colors and attributes, a line per row; each line
+    /// ends with the attributes reset.
+    pub fn to_ansi(&self) -> String {
+        let mut s = String::new();
+        for y in 0..self.height {
+            let mut last = None;
+            for x in 0..self.width {
+                let c = self.cell(x, y);
+                let attrs = sgr(c);
+                if last.as_ref() != Some(&attrs) {
+                    s.push_str(&attrs);
+                    last = Some(attrs);
+                }
+                s.push(c.ch);
+            }
+            s.push_str("\x1b[0m\n");
+        }
+        s
+    }
+
+    /// Writes the presented screen to the given file: with ANSI escape
+    /// sequences if its name ends with `.ans` (or `.ansi`), as plain
+    /// text otherwise.
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        let text = if filename.ends_with(".ans") || filename.ends_with(".ansi") {
+            self.to_ansi()
+        } else {
+            self.to_text()
+        };
+        io_util::atomic_write(filename, |out| out.write_all(text.as_bytes()))
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    fn put(&mut self, x: usize, y: usize, c: ScreenCell) {
+        // ~ like the terminal, ignore out of screen prints
+        if x < self.width && y < self.height {
+            self.back[y * self.width + x] = c;
+        }
+    }
+}
+
+// ~ the escape sequence setting the attributes of the given cell from
+// scratch
+fn sgr(c: ScreenCell) -> String {
+    let mut s = "\x1b[0".to_owned();
+    for &(flag, code) in &[(rustbox::RB_BOLD, 1), (rustbox::RB_UNDERLINE, 4), (rustbox::RB_REVERSE, 7)] {
+        if c.style.contains(flag) {
+            s.push_str(&format!(";{}", code));
+        }
+    }
+    for &(basic, rgb, base) in &[(c.fg, c.fg_rgb, 30), (c.bg, c.bg_rgb, 40)] {
+        match (basic, rgb) {
+            (_, Some(Rgb(r, g, b))) => s.push_str(&format!(";{};2;{};{};{}", base + 8, r, g, b)),
+            (Color::Default, None) => {}
+            (basic, None) => s.push_str(&format!(";{}", base + basic as usize - 1)),
+        }
+    }
+    s.push('m');
+    s
+}
+
+#[test]
+fn test_to_text() {
+    let mut screen = ScreenBuffer::new(6, 3);
+    screen.print(1, 0, rustbox::RB_BOLD, Color::Red, Color::Default, "gen 7");
+    screen.print_char(0, 2, rustbox::RB_NORMAL, Color::Default, Color::Default, '#');
+    // ~ out of the screen: cut off
+    screen.print(4, 1, rustbox::RB_NORMAL, Color::Default, Color::Default, "abc");
+    screen.print(0, 3, rustbox::RB_NORMAL, Color::Default, Color::Default, "x");
+    // ~ nothing shown until presented
+    assert_eq!("\n\n\n", screen.to_text());
+    screen.present();
+    assert_eq!(" gen 7\n    ab\n#\n", screen.to_text());
+    screen.clear();
+    assert_eq!(" gen 7\n    ab\n#\n", screen.to_text());
+    screen.present();
+    assert_eq!("\n\n\n", screen.to_text());
+    screen.resize(2, 1);
+    assert_eq!((2, 1, "\n".to_owned()), (screen.width(), screen.height(), screen.to_text()));
+}
+
+#[test]
+fn test_to_ansi() {
+    let mut screen = ScreenBuffer::new(4, 2);
+    screen.print(0, 0, rustbox::RB_BOLD, Color::Red, Color::Default, "ab");
+    screen.print_char(2, 0, rustbox::RB_UNDERLINE | rustbox::RB_REVERSE, Color::White, Color::Black, 'c');
+    screen.print_char_rgb(1, 1, rustbox::RB_NORMAL, Rgb(255, 255, 255), Rgb(68, 1, 84), 'O');
+    screen.present();
+    assert_eq!(concat!("\x1b[0;1;31mab\x1b[0;4;7;37;40mc\x1b[0m \x1b[0m\n",
+                       "\x1b[0m \x1b[0;38;2;255;255;255;48;2;68;1;84mO\x1b[0m  \x1b[0m\n"),
+               screen.to_ansi());
+    // ~ the basic colors closest to the 24-bit ones
+    assert!(Color::White == screen.cell(1, 1).fg);
+}
+
+#[test]
+fn test_save() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::process;
+
+    let mut screen = ScreenBuffer::new(3, 1);
+    screen.print(0, 0, rustbox::RB_NORMAL, Color::Green, Color::Default, "OO");
+    screen.present();
+    let saved = [("txt", "OO\n"), ("ans", "\x1b[0;32mOO\x1b[0m \x1b[0m\n")];
+    for &(ext, expected) in &saved {
+        let path = env::temp_dir().join(format!("cgol-test-screen-{}.{}", process::id(), ext));
+        screen.save(path.to_str().unwrap()).unwrap();
+        let mut text = String::new();
+        File::open(&path).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(expected, text);
+        fs::remove_file(&path).unwrap();
+    }
+}
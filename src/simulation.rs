@@ -0,0 +1,269 @@
+//! Driving a world generation by generation independently of any
+//! front-end.
+//!
+//! `Simulation` owns a world along with the settings applying to its
+//! evolution and hands out the cells changed by every generation for
+//! the host to render, e.g. a GUI or a web page embedding the engine.
+//! The terminal UI and the headless mode, which keep their world
+//! elsewhere, advance it through `step` likewise.
+
+use schedule::RuleSchedule;
+use world::{AutoExpand, Growth, PhaseTimer, Rule, StepSummary, World};
+
+/// A cell changed by a generation as `(x, y, alive)`; see
+/// `World::advance_generation`.
+pub type Change = (usize, usize, bool);
+
+/// Advances the given world by one generation, growing it afterwards
+/// as configured by `auto_expand`, and collects the changed cells into
+/// `changes` (clearing it first.)  The changes are given in the
+/// coordinates of the world after it grew, i.e. shifted by the growth
+/// to the left and top.
+pub fn step(world: &mut World,
+            auto_expand: Option<&AutoExpand>,
+            changes: &mut Vec<Change>)
+            -> (StepSummary, Growth) {
+    step_timed(world, auto_expand, changes, &mut ())
+}
+
+/// Advances the given world like `step`, timing the phases of the
+/// generation with `timer` (see `World::advance_generation_timed`.)
+pub fn step_timed<T: PhaseTimer>(world: &mut World,
+                                 auto_expand: Option<&AutoExpand>,
+                                 changes: &mut Vec<Change>,
+                                 timer: &mut T)
+                                 -> (StepSummary, Growth) {
+    changes.clear();
+    let summary = world.advance_generation_timed(|x, y, alive| changes.push((x, y, alive)), timer);
+    let growth = match auto_expand {
+        Some(cfg) => world.auto_expand(cfg),
+        None => Growth::default(),
+    };
+    if growth.left > 0 || growth.top > 0 {
+        for c in changes.iter_mut() {
+            c.0 += growth.left;
+            c.1 += growth.top;
+        }
+    }
+    (summary, growth)
+}
+
+/// Advances the given world like `step` but without collecting the
+/// changed cells for as long as `more` (asked after every generation)
+/// tells so, e.g. to skip ahead without drawing the generations in
+/// between; the rules switch as scheduled, if so.  `more` is given the
+/// number of columns and rows the world just grew by to the left and
+/// top, e.g. to move along what refers to its cells.  Returns these
+/// numbers for all the generations advanced.
+pub fn skip_while<F>(world: &mut World,
+                     auto_expand: Option<&AutoExpand>,
+                     schedule: Option<&RuleSchedule>,
+                     mut more: F)
+                     -> (usize, usize)
+    where F: FnMut(&World, (usize, usize)) -> bool
+{
+    let mut shifted = (0, 0);
+    loop {
+        if let Some(s) = schedule {
+            s.apply(world);
+        }
+        world.advance_generations(1);
+        let grown = match auto_expand {
+            Some(cfg) => {
+                let growth = world.auto_expand(cfg);
+                (growth.left, growth.top)
+            }
+            None => (0, 0),
+        };
+        shifted = (shifted.0 + grown.0, shifted.1 + grown.1);
+        if !more(world, grown) {
+            return shifted;
+        }
+    }
+}
+
+pub struct Simulation {
+    world: World,
+    auto_expand: Option<AutoExpand>,
+    schedule: Option<RuleSchedule>,
+    // ~ the rule the last generation switched to, if it did
+    switched: Option<Rule>,
+    // ~ the changes of the last generation; kept to reuse the buffer
+    changes: Vec<Change>,
+    summary: StepSummary,
+    growth: Growth,
+}
+
+impl Simulation {
+    pub fn new(world: World) -> Simulation {
+        Simulation {
+            world: world,
+            auto_expand: None,
+            schedule: None,
+            switched: None,
+            changes: Vec::new(),
+            summary: StepSummary::default(),
+            growth: Growth::default(),
+        }
+    }
+
+    /// Grows the (bounded) world as its live cells approach the edges
+    /// with every following generation; `None` keeps its size.
+    pub fn set_auto_expand(&mut self, auto_expand: Option<AutoExpand>) {
+        self.auto_expand = auto_expand;
+    }
+
+    /// Switches the rule of the world at the generations scheduled
+    /// from the next generation on; `None` leaves the rule be.
+    pub fn set_schedule(&mut self, schedule: Option<RuleSchedule>) {
+        self.schedule = schedule;
+    }
+
+    pub fn schedule(&self) -> Option<&RuleSchedule> {
+        self.schedule.as_ref()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The world for the host to modify, e.g. to toggle cells edited
+    /// by the user.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn into_world(self) -> World {
+        self.world
+    }
+
+    /// Advances the world by one generation and returns the changed
+    /// cells in row-major order (see `step`.)  Once the world grew (see
+    /// `growth`) the host is to draw it anew entirely, the cells having
+    /// moved if it grew to the left or top.
+    pub fn tick(&mut self) -> &[Change] {
+        let world = &mut self.world;
+        self.switched = self.schedule.as_ref().and_then(|s| s.apply(world));
+        let (summary, growth) = step(&mut self.world, self.auto_expand.as_ref(), &mut self.changes);
+        self.summary = summary;
+        self.growth = growth;
+        &self.changes
+    }
+
+    /// The number of births and deaths of the last generation.
+    pub fn summary(&self) -> StepSummary {
+        self.summary
+    }
+
+    /// How much the world grew with the last generation.
+    pub fn growth(&self) -> Growth {
+        self.growth
+    }
+
+    /// The rule the schedule switched to for the last generation, if
+    /// it did.
+    pub fn switched(&self) -> Option<Rule> {
+        self.switched
+    }
+}
+
+#[cfg(test)]
+fn blinker() -> World {
+    let mut w = World::empty(5, 5);
+    for x in 1..4 {
+        w.set_alive(x, 2, true);
+    }
+    w
+}
+
+#[test]
+fn test_ticks() {
+    let mut sim = Simulation::new(blinker());
+    assert_eq!(&[(2, 1, true), (1, 2, false), (3, 2, false), (2, 3, true)], sim.tick());
+    assert_eq!(StepSummary {
+                   births: 2,
+                   deaths: 2,
+                   evaluated: 25,
+               }, sim.summary());
+    assert_eq!(&[(2, 1, false), (1, 2, true), (3, 2, true), (2, 3, false)], sim.tick());
+    assert!(sim.growth().is_empty());
+    // ~ the host's edits take part in the next generation
+    sim.world_mut().set_alive(2, 2, false);
+    assert_eq!(&[(1, 2, false), (3, 2, false)], sim.tick());
+    assert!(sim.tick().is_empty());
+    let w = sim.into_world();
+    assert_eq!((4, 0), (w.generation(), w.alive()));
+}
+
+#[test]
+fn test_ticks_growing() {
+    use world::BoundaryMode;
+
+    // ~ the changes are reported where the cells are after the growth
+    let mut world = blinker();
+    world.set_boundary(BoundaryMode::Bounded);
+    let mut sim = Simulation::new(world);
+    sim.set_auto_expand(Some(AutoExpand {
+        margin: 2,
+        increment: 3,
+        max_size: None,
+    }));
+    assert_eq!(&[(2, 4, true), (1, 5, false), (3, 5, false), (2, 6, true)], sim.tick());
+    // ~ the vertical blinker is near the top and bottom edges only
+    assert_eq!((0, 3, 0, 3), {
+        let g = sim.growth();
+        (g.left, g.top, g.right, g.bottom)
+    });
+    assert!(sim.world().is_alive(2, 4) && !sim.world().is_alive(1, 5));
+}
+
+#[test]
+fn test_skip_while() {
+    use world::BoundaryMode;
+
+    let mut w = blinker();
+    assert_eq!((0, 0), skip_while(&mut w, None, None, |w, _| w.generation() < 7));
+    assert_eq!(7, w.generation());
+    let mut vertical = blinker();
+    vertical.advance_generation(|_, _, _| {});
+    assert_eq!(vertical.live_cells(), w.live_cells());
+    // ~ at least one generation
+    skip_while(&mut w, None, None, |_, _| false);
+    assert_eq!((8, blinker().live_cells()), (w.generation(), w.live_cells()));
+    // ~ the rules switch as scheduled: without survivals from
+    // generation 10 on, the blinker leaves two cells born
+    let s = RuleSchedule::parse("0:B3/S23,10:B3/S").unwrap();
+    skip_while(&mut w, None, Some(&s), |w, _| w.generation() < 11);
+    assert_eq!((11, 2), (w.generation(), w.alive()));
+    // ~ the growth of every generation adds up to the one returned
+    let mut w = blinker();
+    w.set_boundary(BoundaryMode::Bounded);
+    let cfg = AutoExpand {
+        margin: 2,
+        increment: 3,
+        max_size: None,
+    };
+    let mut grown = Vec::new();
+    let shifted = skip_while(&mut w, Some(&cfg), None, |w, g| {
+        grown.push(g);
+        w.generation() < 4
+    });
+    assert_eq!(4, grown.len());
+    assert!(shifted.0 > 0 && shifted.1 > 0);
+    assert_eq!(shifted, grown.iter().fold((0, 0), |a, g| (a.0 + g.0, a.1 + g.1)));
+    assert_eq!((4, 5), w.live_cells()[0]);
+}
+
+#[test]
+fn test_schedule() {
+    let mut sim = Simulation::new(blinker());
+    sim.set_schedule(Some(RuleSchedule::parse("0:B3/S23,2:B3/S").unwrap()));
+    sim.tick();
+    sim.tick();
+    assert_eq!((None, 3), (sim.switched(), sim.world().alive()));
+    sim.tick();
+    assert_eq!(Some("B3/S".to_owned()), sim.switched().map(|r| r.to_string()));
+    assert_eq!((3, 2), (sim.world().generation(), sim.world().alive()));
+    sim.tick();
+    assert_eq!((None, 0), (sim.switched(), sim.world().alive()));
+}
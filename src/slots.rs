@@ -0,0 +1,260 @@
+//! A handful of numbered slots keeping snapshots of worlds, e.g. to
+//! hop between interesting configurations during a session.
+//!
+//! The slots can be persisted in a binary file: the magic `CGOLSLT2`
+//! followed by one record per slot; either a `0` byte for an empty
+//! slot, or a `1` byte followed by
+//!
+//! - the world's boundary mode; `'W'`, `'B'`, or `'T'` followed by the
+//!   twist's offsets (two `i32`s),
+//! - the number of offsets of its neighbourhood (`u32`) and the
+//!   offsets (two `i32`s each),
+//! - the world in the binary format (see `World::save_binary`.)
+//!
+//! All numbers are little endian.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+
+use io_util::{self, invalid_data, read_u32, write_u32};
+use world::{BoundaryMode, World};
+
+/// The number of slots; they are numbered from 1.
+pub const COUNT: usize = 5;
+
+const MAGIC: &'static [u8; 8] = b"CGOLSLT2";
+
+pub struct Slots {
+    slots: Vec<Option<World>>,
+    // ~ whether a slot was saved since the slots were created or read
+    modified: bool,
+}
+
+impl Slots {
+    /// Creates `COUNT` empty slots.
+    pub fn new() -> Slots {
+        Slots {
+            slots: vec![None; COUNT],
+            modified: false,
+        }
+    }
+
+    /// Stores a copy of the given world in slot `n`, replacing the
+    /// slot's previous world.
+    pub fn save(&mut self, n: usize, world: &World) {
+        assert!(n >= 1 && n <= COUNT, "no such slot: {}", n);
+        self.slots[n - 1] = Some(world.clone());
+        self.modified = true;
+    }
+
+    /// Retrieves the world stored in slot `n`; `None` if the slot is
+    /// empty.
+    pub fn get(&self, n: usize) -> Option<&World> {
+        assert!(n >= 1 && n <= COUNT, "no such slot: {}", n);
+        self.slots[n - 1].as_ref()
+    }
+
+    /// Determines whether any slot was saved since these slots were
+    /// created or read.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Reads the slots from the given file; a missing file results in
+    /// empty slots.
+    pub fn load_from_file(filename: &str) -> Result<Slots, String> {
+        match File::open(filename) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Slots::new()),
+            Err(e) => Err(format!("{}: {}", filename, e)),
+            Ok(f) => Slots::read_from(BufReader::new(f)).map_err(|e| format!("{}: {}", filename, e)),
+        }
+    }
+
+    /// Writes the slots to the given file.  The slots are written to a
+    /// temporary file first which is then renamed over the given one
+    /// (see `io_util::atomic_write`.)
+    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
+        io_util::atomic_write(filename, |mut out| self.write_to(&mut out))
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn read_from<R: Read>(mut input: R) -> io::Result<Slots> {
+        let mut magic = [0u8; 8];
+        try!(input.read_exact(&mut magic));
+        if &magic != MAGIC {
+            return Err(invalid_data("not a slots file"));
+        }
+        let mut slots = Slots::new();
+        for slot in slots.slots.iter_mut() {
+            let mut tag = [0u8; 1];
+            try!(input.read_exact(&mut tag));
+            *slot = match tag[0] {
+                0 => None,
+                1 => Some(try!(read_world(&mut input))),
+                _ => return Err(invalid_data("invalid slot")),
+            };
+        }
+        Ok(slots)
+    }
+
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        try!(out.write_all(MAGIC));
+        for slot in &self.slots {
+            match *slot {
+                None => try!(out.write_all(&[0])),
+                Some(ref w) => {
+                    try!(out.write_all(&[1]));
+                    try!(write_world(out, w));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_world<W: Write>(out: &mut W, world: &World) -> io::Result<()> {
+    match world.boundary() {
+        BoundaryMode::Wrap => try!(out.write_all(b"W")),
+        BoundaryMode::Bounded => try!(out.write_all(b"B")),
+        BoundaryMode::Twisted { dx, dy } => {
+            try!(out.write_all(b"T"));
+            try!(write_u32(out, dx as i32 as u32));
+            try!(write_u32(out, dy as i32 as u32));
+        }
+    }
+    try!(write_u32(out, world.neighbourhood().len() as u32));
+    for &(dx, dy) in world.neighbourhood() {
+        try!(write_u32(out, dx as i32 as u32));
+        try!(write_u32(out, dy as i32 as u32));
+    }
+    world.save_binary(out)
+}
+
+fn read_world<R: Read>(input: &mut R) -> io::Result<World> {
+    let mut tag = [0u8; 1];
+    try!(input.read_exact(&mut tag));
+    let boundary = match tag[0] {
+        b'W' => BoundaryMode::Wrap,
+        b'B' => BoundaryMode::Bounded,
+        b'T' => {
+            let dx = try!(read_u32(input)) as i32 as isize;
+            let dy = try!(read_u32(input)) as i32 as isize;
+            BoundaryMode::Twisted { dx: dx, dy: dy }
+        }
+        _ => return Err(invalid_data("invalid boundary mode")),
+    };
+    let n = try!(read_u32(input));
+    let mut neighbourhood = Vec::new();
+    for _ in 0..n {
+        let dx = try!(read_u32(input)) as i32 as isize;
+        let dy = try!(read_u32(input)) as i32 as isize;
+        neighbourhood.push((dx, dy));
+    }
+    let mut world = try!(World::load_binary(input));
+    world.set_boundary(boundary);
+    world.set_neighbourhood(neighbourhood);
+    Ok(world)
+}
+
+#[cfg(test)]
+fn same_world(a: &World, b: &World) -> bool {
+    a.generation_hash() == b.generation_hash() && a.generation() == b.generation() &&
+    a.boundary() == b.boundary() && a.neighbourhood() == b.neighbourhood()
+}
+
+#[test]
+fn test_save_and_get() {
+    let mut slots = Slots::new();
+    assert!(!slots.is_modified());
+    assert!((1..COUNT + 1).all(|n| slots.get(n).is_none()));
+
+    let mut w = World::random_seeded(1, 20, 10);
+    slots.save(3, &w);
+    assert!(slots.is_modified());
+    // ~ the slot keeps a snapshot; advancing the world leaves it alone
+    let saved = w.clone();
+    w.advance_generation(|_, _, _| {});
+    assert!(same_world(&saved, slots.get(3).unwrap()));
+    assert!(slots.get(2).is_none());
+
+    // ~ saving again overwrites the slot
+    slots.save(3, &w);
+    assert_eq!(1, slots.get(3).unwrap().generation());
+}
+
+#[test]
+#[should_panic]
+fn test_no_such_slot() {
+    Slots::new().get(COUNT + 1);
+}
+
+#[test]
+fn test_round_trip() {
+    let mut slots = Slots::new();
+    let mut a = World::random_seeded(2, 13, 7);
+    for _ in 0..5 {
+        a.advance_generation(|_, _, _| {});
+    }
+    let mut b = World::random_seeded(3, 30, 30);
+    b.set_boundary(BoundaryMode::Twisted { dx: -3, dy: 1 });
+    b.set_neighbourhood(vec![(-2, 1), (2, -1), (1, 2)]);
+    b.set_generation(1240);
+    slots.save(1, &a);
+    slots.save(5, &b);
+
+    let mut buf = Vec::new();
+    slots.write_to(&mut buf).unwrap();
+    let read = Slots::read_from(&buf[..]).unwrap();
+    assert!(!read.is_modified());
+    assert!(same_world(&a, read.get(1).unwrap()));
+    assert!(same_world(&b, read.get(5).unwrap()));
+    assert!((2..5).all(|n| read.get(n).is_none()));
+}
+
+#[test]
+fn test_corrupted() {
+    let mut slots = Slots::new();
+    let world = World::random_seeded(4, 20, 20);
+    slots.save(2, &world);
+    let mut buf = Vec::new();
+    slots.write_to(&mut buf).unwrap();
+
+    // ~ truncated
+    assert!(Slots::read_from(&buf[..buf.len() - 1]).is_err());
+    // ~ not a slots file
+    assert!(Slots::read_from(&b"!a comment\n.O\n"[..]).is_err());
+    // ~ a huge world: the width and height following the 8 byte magic
+    // of the binary format
+    let mut binary = Vec::new();
+    world.save_binary(&mut binary).unwrap();
+    let at = buf.windows(binary.len()).position(|w| w == &binary[..]).unwrap() + 8;
+    let mut huge = buf.clone();
+    for b in &mut huge[at..at + 8] {
+        *b = 0xff;
+    }
+    let e = Slots::read_from(&huge[..]).err().unwrap();
+    assert_eq!("invalid world dimensions", e.to_string());
+}
+
+#[test]
+fn test_files() {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    let dir = env::temp_dir();
+    let file = dir.join(format!("cgol-test-slots-{}.bin", process::id())).to_str().unwrap().to_owned();
+    let _ = fs::remove_file(&file);
+    assert!(Slots::load_from_file(&file).unwrap().get(1).is_none());
+
+    let mut slots = Slots::new();
+    let w = World::random_seeded(5, 10, 10);
+    slots.save(4, &w);
+    slots.save_to_file(&file).unwrap();
+    assert!(same_world(&w, Slots::load_from_file(&file).unwrap().get(4).unwrap()));
+
+    File::create(&file).unwrap().write_all(b"CGOLSLT2\x07").unwrap();
+    let e = Slots::load_from_file(&file).err().unwrap();
+    assert!(e.starts_with(&file[..]));
+    let _ = fs::remove_file(&file);
+}
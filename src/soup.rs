@@ -0,0 +1,222 @@
+//! Statistics over random worlds ("soups") run until they settle.
+//!
+//! A soup settles once it repeats one of its last generations (see
+//! `patterns::Recurrence`); soups still evolving after a number of
+//! generations, e.g. because gliders keep circling a torus, are given
+//! up on and counted apart.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use patterns::Recurrence;
+use world::World;
+
+/// The number of generations after which a soup is given up on unless
+/// specified otherwise.
+pub const DEFAULT_MAX_GENERATIONS: usize = 10000;
+
+/// What is counted of every settled soup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// the number of live cells once settled
+    Population,
+    /// the number of generations it took to settle
+    Generations,
+}
+
+impl Value {
+    pub fn parse(s: &str) -> Option<Value> {
+        match s {
+            "population" => Some(Value::Population),
+            "generations" => Some(Value::Generations),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            Value::Population => "population",
+            Value::Generations => "generations",
+        })
+    }
+}
+
+/// Advances the given world until it repeats one of its last
+/// generations, but at most by `max_generations`.  Returns the number
+/// of generations advanced if it settled.
+pub fn settle(world: &mut World, max_generations: usize) -> Option<usize> {
+    let mut recurrence = Recurrence::new();
+    for n in 0..(max_generations + 1) {
+        if recurrence.observe(world) {
+            return Some(n);
+        }
+        if n < max_generations {
+            world.advance_generation(|_, _, _| {});
+        }
+    }
+    None
+}
+
+/// Settles the given world like `settle` and takes the cells it ended
+/// up with as its generation 0 (see `--presettle`), whether it settled
+/// or not.  Returns the number of generations it took to reach the
+/// settled form if it settled; the repeat telling so is noticed only a
+/// period later, but the world then shows the same phase.
+pub fn presettle(world: &mut World, max_generations: usize) -> Option<usize> {
+    let mut recurrence = Recurrence::new();
+    let mut settled = None;
+    for n in 0..(max_generations + 1) {
+        if let Some(period) = recurrence.repeats(world) {
+            settled = Some(n - period);
+            break;
+        }
+        if n < max_generations {
+            world.advance_generation(|_, _, _| {});
+        }
+    }
+    world.restart();
+    settled
+}
+
+/// Describes the outcome of `presettle` for the user.
+pub fn describe_presettle(settled: Option<usize>, max_generations: usize) -> String {
+    match settled {
+        Some(n) => format!("presettled in {} generations", n),
+        None => format!("not settled within {} generations; starting from there", max_generations),
+    }
+}
+
+/// The number of soups per value of the counted property.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Histogram {
+    bins: BTreeMap<usize, usize>,
+    /// the number of soups which did not settle
+    pub unsettled: usize,
+}
+
+impl Histogram {
+    pub fn new() -> Histogram {
+        Histogram::default()
+    }
+
+    /// Counts a soup settled with the given value.
+    pub fn add(&mut self, value: usize) {
+        *self.bins.entry(value).or_insert(0) += 1;
+    }
+
+    /// The values counted along with their counts, in ascending order.
+    pub fn bins(&self) -> Vec<(usize, usize)> {
+        self.bins.iter().map(|(&v, &n)| (v, n)).collect()
+    }
+
+    /// The number of soups counted, settled or not.
+    pub fn total(&self) -> usize {
+        self.bins.values().sum::<usize>() + self.unsettled
+    }
+}
+
+/// Writes a `value count` line per value counted in ascending order,
+/// followed by an `unsettled count` line if any soup did not settle.
+impl fmt::Display for Histogram {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (v, n) in &self.bins {
+            try!(writeln!(fmt, "{} {}", v, n));
+        }
+        if self.unsettled > 0 {
+            try!(writeln!(fmt, "unsettled {}", self.unsettled));
+        }
+        Ok(())
+    }
+}
+
+/// Runs `count` soups generated by `soup` from the seeds following
+/// `first_seed` (itself included) and counts the given value of each.
+pub fn histogram<F>(soup: F, first_seed: u64, count: usize, max_generations: usize, value: Value) -> Histogram
+    where F: Fn(u64) -> World
+{
+    let mut h = Histogram::new();
+    for i in 0..count {
+        let mut world = soup(first_seed.wrapping_add(i as u64));
+        match settle(&mut world, max_generations) {
+            None => h.unsettled += 1,
+            Some(n) if value == Value::Generations => h.add(n),
+            Some(_) => h.add(world.alive()),
+        }
+    }
+    h
+}
+
+#[test]
+fn test_settle() {
+    use parser;
+
+    // ~ a block repeats right away, a blinker after two generations
+    let mut block = parser::parse_cells("....\n.OO.\n.OO.\n....").unwrap();
+    assert_eq!(Some(1), settle(&mut block, 10));
+    let mut blinker = parser::parse_cells(".....\n.....\n.OOO.\n.....\n.....").unwrap();
+    assert_eq!(Some(2), settle(&mut blinker, 10));
+    assert_eq!(2, blinker.generation());
+    // ~ a glider on a torus never does
+    let mut glider = parser::parse_cells(".O...\n..O..\nOOO..\n.....\n.....").unwrap();
+    assert_eq!(None, settle(&mut glider, 40));
+    assert_eq!(40, glider.generation());
+}
+
+#[test]
+fn test_presettle() {
+    use parser;
+
+    // ~ a T-tetromino evolves into a traffic light (four blinkers) by
+    // generation 9, which generation 11 repeats
+    let mut t = World::empty(15, 15);
+    for &(x, y) in &[(6, 6), (7, 6), (8, 6), (7, 7)] {
+        t.set_alive(x, y, true);
+    }
+    let mut nine = t.clone();
+    assert_eq!(Some(9), presettle(&mut t, 100));
+    assert_eq!((0, 12), (t.generation(), t.alive()));
+    // ~ in the phase of generation 9
+    nine.advance_generations(9);
+    assert_eq!(nine.live_cells(), t.live_cells());
+    // ~ a traffic light is settled already, and so is a block
+    let light = t.live_cells();
+    assert_eq!(Some(0), presettle(&mut t, 100));
+    assert_eq!(light, t.live_cells());
+    let mut block = parser::parse_cells("....\n.OO.\n.OO.\n....").unwrap();
+    assert_eq!(Some(0), presettle(&mut block, 100));
+    assert_eq!("presettled in 9 generations", describe_presettle(Some(9), 100));
+
+    // ~ a glider on a torus runs up to the limit
+    let mut glider = parser::parse_cells(".O...\n..O..\nOOO..\n.....\n.....").unwrap();
+    assert_eq!(None, presettle(&mut glider, 40));
+    assert_eq!((0, 5), (glider.generation(), glider.alive()));
+    assert_eq!("not settled within 40 generations; starting from there",
+               describe_presettle(None, 40));
+}
+
+#[test]
+fn test_histogram() {
+    let soup = |seed| World::random_seeded(seed, 16, 16);
+    for &value in &[Value::Population, Value::Generations] {
+        let h = histogram(&soup, 3, 12, 500, value);
+        assert_eq!(12, h.total(), "{}", value);
+        assert_eq!(12, h.bins().iter().map(|&(_, n)| n).sum::<usize>() + h.unsettled);
+        // ~ reproducible from the seeds
+        assert_eq!(h, histogram(&soup, 3, 12, 500, value));
+    }
+    // ~ the lines of the output sum up to the count as well
+    let h = histogram(&soup, 100, 8, 500, Value::Population);
+    let lines = h.to_string();
+    let sum: usize = lines.lines().map(|l| l.split(' ').nth(1).unwrap().parse::<usize>().unwrap()).sum();
+    assert_eq!(8, sum);
+
+    let mut h = Histogram::new();
+    h.add(5);
+    h.add(0);
+    h.add(5);
+    h.unsettled = 1;
+    assert_eq!("0 1\n5 2\nunsettled 1\n", h.to_string());
+    assert_eq!(4, h.total());
+}
@@ -0,0 +1,109 @@
+//! Patterns to be stamped onto the world.
+//!
+//! A stamp is one of the bundled patterns (see `patterns::STAMPS`),
+//! possibly rotated and mirrored, which the UI previews at a cursor
+//! before placing it with `World::blit`.
+
+use parser;
+use patterns;
+use world::World;
+
+pub struct Stamp {
+    /// the index of the pattern in `patterns::STAMPS`
+    index: usize,
+    // ~ trimmed to the live cells
+    pattern: World,
+}
+
+impl Stamp {
+    /// Creates the stamp of the bundled pattern of the given index
+    /// (modulo their number.)
+    pub fn bundled(index: usize) -> Stamp {
+        let index = index % patterns::STAMPS.len();
+        // ~ bundled and hence known to parse
+        let mut pattern = parser::parse_cells(patterns::STAMPS[index].1).unwrap();
+        pattern.trim(0);
+        Stamp {
+            index: index,
+            pattern: pattern,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn name(&self) -> &'static str {
+        patterns::STAMPS[self.index].0
+    }
+
+    pub fn pattern(&self) -> &World {
+        &self.pattern
+    }
+
+    pub fn width(&self) -> usize {
+        self.pattern.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.pattern.height()
+    }
+
+    /// Turns the pattern by a quarter clockwise.
+    pub fn rotate(&mut self) {
+        let height = self.pattern.height();
+        let turned = self.pattern.live_cells().into_iter().map(|(x, y)| (height - 1 - y, x));
+        self.pattern = World::from_coords(height, self.pattern.width(), turned).unwrap();
+    }
+
+    /// Mirrors the pattern left to right.
+    pub fn mirror(&mut self) {
+        let width = self.pattern.width();
+        let mirrored = self.pattern.live_cells().into_iter().map(|(x, y)| (width - 1 - x, y));
+        self.pattern = World::from_coords(width, self.pattern.height(), mirrored).unwrap();
+    }
+
+    /// The cells of the given world covered by the live cells of the
+    /// pattern placed at `(x, y)`, wrapping around a torus like
+    /// `World::blit`; those beyond the edges of a bounded world are left
+    /// out.
+    pub fn cells_at(&self, x: usize, y: usize, world: &World) -> Vec<(usize, usize)> {
+        self.pattern
+            .live_cells()
+            .into_iter()
+            .filter_map(|(px, py)| world.shift_target(x, y, px as isize, py as isize))
+            .collect()
+    }
+}
+
+#[test]
+fn test_transform() {
+    // ~ the glider: .O. / ..O / OOO
+    let mut s = Stamp::bundled(0);
+    assert_eq!("Glider", s.name());
+    assert_eq!(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)], s.pattern().live_cells());
+    // ~ O.. / O.O / OO.
+    s.rotate();
+    assert_eq!(vec![(0, 0), (0, 1), (2, 1), (0, 2), (1, 2)], s.pattern().live_cells());
+    // ~ ..O / O.O / .OO
+    s.mirror();
+    assert_eq!(vec![(2, 0), (0, 1), (2, 1), (1, 2), (2, 2)], s.pattern().live_cells());
+
+    // ~ four turns are none; non-square patterns swap their dimensions
+    let mut s = Stamp::bundled(1);
+    let cells = s.pattern().live_cells();
+    s.rotate();
+    assert_eq!((4, 5), (s.width(), s.height()));
+    s.rotate();
+    s.rotate();
+    s.rotate();
+    assert_eq!(cells, s.pattern().live_cells());
+    assert_eq!(0, Stamp::bundled(patterns::STAMPS.len()).index());
+
+    // ~ clipped at the edges of a bounded world, wrapped on a torus
+    let s = Stamp::bundled(0);
+    let mut bounded = World::empty(5, 5);
+    bounded.set_boundary(::world::BoundaryMode::Bounded);
+    assert_eq!(vec![(4, 2), (3, 4), (4, 4)], s.cells_at(3, 2, &bounded));
+    assert_eq!(vec![(4, 2), (0, 3), (3, 4), (4, 4), (0, 4)], s.cells_at(3, 2, &World::empty(5, 5)));
+}
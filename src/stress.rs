@@ -0,0 +1,211 @@
+//! Finding out how large a world gets how fast (see `--stress`).
+//!
+//! A small pattern growing without bounds (the R-pentomino, sending
+//! off gliders) is run on a bounded world growing along with it (see
+//! `World::auto_expand`) until the world reaches a given number of
+//! cells, or until a time limit.  The world never grows beyond the
+//! number of cells: a growth which would exceed it ends the run
+//! instead, much like the maximum size of an auto-expanding world
+//! (which applies as well.)
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use parser;
+use patterns;
+use world::{AutoExpand, BoundaryMode, World};
+
+/// The number of cells at which the world stops growing unless
+/// specified otherwise.
+pub const DEFAULT_MAX_CELLS: usize = 1 << 24;
+
+/// The time (in seconds) after which the run ends unless specified
+/// otherwise.
+pub const DEFAULT_TIME_LIMIT_SECS: u64 = 60;
+
+/// The interval (in seconds) of the timing lines.
+pub const REPORT_SECS: u64 = 1;
+
+/// The pattern the run starts from.
+pub const PATTERN: &'static str = "R-pentomino";
+
+/// When to end a run.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub max_cells: usize,
+    pub time_limit: Duration,
+    /// the interval of the timing lines
+    pub report_every: Duration,
+}
+
+/// Why a run ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// the world needed to grow beyond `Limits::max_cells`
+    MaxCells,
+    /// the world needed to grow beyond `AutoExpand::max_size`
+    MaxSize,
+    TimeLimit,
+    /// all cells died; the world has no reason to grow anymore
+    Extinct,
+}
+
+/// The final state of a run.
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome {
+    pub stop: Stop,
+    pub generation: usize,
+    pub width: usize,
+    pub height: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let why = match self.stop {
+            Stop::MaxCells => "reached the maximum number of cells",
+            Stop::MaxSize => "reached the maximum size",
+            Stop::TimeLimit => "reached the time limit",
+            Stop::Extinct => "died out",
+        };
+        write!(fmt,
+               "{} at generation {}: {}x{} ({} cells) after {:.2}s",
+               why,
+               self.generation,
+               self.width,
+               self.height,
+               self.width * self.height,
+               secs(self.elapsed))
+    }
+}
+
+/// The world a run starts from: `PATTERN` in the middle of a bounded
+/// world just large enough for it to grow in any direction.
+pub fn initial_world(cfg: &AutoExpand) -> World {
+    // ~ bundled and hence known to parse
+    let cells = patterns::STAMPS.iter().find(|&&(name, _)| name == PATTERN).unwrap().1;
+    let mut pattern = parser::parse_cells(cells).unwrap();
+    pattern.trim(0);
+    let room = cfg.margin + 1;
+    let mut world = World::empty(pattern.width() + 2 * room, pattern.height() + 2 * room);
+    world.set_boundary(BoundaryMode::Bounded);
+    world.blit(room as isize, room as isize, &pattern);
+    world
+}
+
+/// Advances the given world growing as configured by `cfg` and within
+/// the given limits; writes a timing line to `log` at the interval of
+/// `limits.report_every`.
+pub fn run<W: Write>(world: &mut World, cfg: &AutoExpand, limits: &Limits, mut log: W) -> io::Result<Outcome> {
+    let started = Instant::now();
+    let mut reported = (started, world.generation());
+    let stop;
+    loop {
+        if world.is_empty() {
+            stop = Stop::Extinct;
+            break;
+        }
+        if started.elapsed() >= limits.time_limit {
+            stop = Stop::TimeLimit;
+            break;
+        }
+        // ~ the world grows by at most one increment on either side at
+        // once; unless that fits, it may not grow at all
+        let (w, h) = (world.width(), world.height());
+        let fits = (w + 2 * cfg.increment).saturating_mul(h + 2 * cfg.increment) <= limits.max_cells;
+        let (max_width, max_height) = cfg.max_size.unwrap_or((usize::max_value(), usize::max_value()));
+        let max_size = if fits {
+            (max_width, max_height)
+        } else {
+            (cmp::min(w, max_width), cmp::min(h, max_height))
+        };
+        world.advance_generations(1);
+        if world.auto_expand(&AutoExpand { max_size: Some(max_size), ..*cfg }).capped {
+            stop = if fits { Stop::MaxSize } else { Stop::MaxCells };
+            break;
+        }
+        let now = Instant::now();
+        if now.duration_since(reported.0) >= limits.report_every {
+            let elapsed = now.duration_since(reported.0);
+            let rate = (world.generation() - reported.1) as f64 / secs(elapsed).max(1e-9);
+            try!(writeln!(log,
+                          "generation={} size={}x{} cells={} alive={} elapsed={:.2}s rate={:.0}gen/s",
+                          world.generation(),
+                          world.width(),
+                          world.height(),
+                          world.width() * world.height(),
+                          world.alive(),
+                          secs(started.elapsed()),
+                          rate));
+            reported = (now, world.generation());
+        }
+    }
+    Ok(Outcome {
+        stop: stop,
+        generation: world.generation(),
+        width: world.width(),
+        height: world.height(),
+        elapsed: started.elapsed(),
+    })
+}
+
+fn secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9
+}
+
+#[cfg(test)]
+const TEST_EXPAND: AutoExpand = AutoExpand {
+    margin: 2,
+    increment: 16,
+    max_size: None,
+};
+
+#[test]
+fn test_run_max_cells() {
+    let limits = Limits {
+        max_cells: 100 * 100,
+        time_limit: Duration::from_secs(600),
+        report_every: Duration::from_secs(0),
+    };
+    let mut world = initial_world(&TEST_EXPAND);
+    assert_eq!((9, 9), (world.width(), world.height()));
+    let mut log = Vec::new();
+    let outcome = run(&mut world, &TEST_EXPAND, &limits, &mut log).unwrap();
+    assert_eq!(Stop::MaxCells, outcome.stop);
+    assert!(outcome.width * outcome.height <= limits.max_cells);
+    // ~ could not have grown by another increment on both sides
+    assert!((outcome.width + 32) * (outcome.height + 32) > limits.max_cells);
+    assert_eq!(outcome.generation, world.generation());
+
+    // ~ a line per generation but the last
+    let log = String::from_utf8(log).unwrap();
+    assert_eq!(outcome.generation - 1, log.lines().count());
+    assert!(log.starts_with("generation=1 size=9x9 cells=81 alive=6 elapsed="));
+    let summary = format!("reached the maximum number of cells at generation {}: ", outcome.generation);
+    assert!(outcome.to_string().starts_with(&summary));
+}
+
+#[test]
+fn test_run_limits() {
+    let mut limits = Limits {
+        max_cells: DEFAULT_MAX_CELLS,
+        time_limit: Duration::from_secs(0),
+        report_every: Duration::from_secs(REPORT_SECS),
+    };
+    let mut world = initial_world(&TEST_EXPAND);
+    let outcome = run(&mut world, &TEST_EXPAND, &limits, io::sink()).unwrap();
+    assert_eq!((Stop::TimeLimit, 0), (outcome.stop, outcome.generation));
+
+    // ~ the maximum size of the auto-expanding world applies as well
+    limits.time_limit = Duration::from_secs(600);
+    let cfg = AutoExpand { max_size: Some((60, 50)), ..TEST_EXPAND };
+    let outcome = run(&mut world, &cfg, &limits, io::sink()).unwrap();
+    assert_eq!(Stop::MaxSize, outcome.stop);
+    assert!(outcome.width <= 60 && outcome.height <= 50);
+
+    let mut world = World::empty(5, 5);
+    let outcome = run(&mut world, &TEST_EXPAND, &limits, io::sink()).unwrap();
+    assert_eq!((Stop::Extinct, 0), (outcome.stop, outcome.generation));
+}
@@ -0,0 +1,294 @@
+//! Seeding a world with spaceships heading for its center (see
+//! `--gliders` and `--spaceships`), e.g. for demos.
+//!
+//! The ships are placed at random near the edges, each turned to travel
+//! roughly toward the center: a glider along the diagonal, a
+//! lightweight spaceship along the axis closer to the direction of the
+//! center.  They keep `MARGIN` cells away from the edges, lest they
+//! interact across them right away, and `CLEARANCE` cells away from
+//! each other and any other live cells.  A ship not finding room within
+//! `MAX_TRIES` positions is left out; so is any ship if the world is too
+//! small for it.
+//!
+//! The positions are drawn from the generator of the seeded worlds
+//! (see `rng`), such that a seed seeds the same ships anywhere.
+
+use std::cmp;
+
+use rng::Rng;
+use stamp::Stamp;
+use world::World;
+
+/// The number of dead cells kept between a ship and the edges.
+pub const MARGIN: usize = 2;
+
+/// The number of dead cells kept between a ship and other live cells.
+pub const CLEARANCE: usize = 2;
+
+/// The number of positions tried for a ship before leaving it out.
+pub const MAX_TRIES: usize = 100;
+
+/// The kinds of ships.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ship {
+    Glider,
+    /// the lightweight spaceship
+    Lwss,
+}
+
+impl Ship {
+    // ~ the index of the ship among the bundled patterns and the
+    // direction it travels in as bundled
+    fn bundled(self) -> (usize, (isize, isize)) {
+        match self {
+            Ship::Glider => (0, (1, 1)),
+            Ship::Lwss => (1, (-1, 0)),
+        }
+    }
+}
+
+/// What to seed a world with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fleet {
+    Gliders,
+    /// gliders and lightweight spaceships
+    Spaceships,
+}
+
+impl Fleet {
+    /// Places `n` ships of this fleet; see `place_gliders` and
+    /// `place_spaceships`.
+    pub fn place(self, world: &mut World, rng: &mut Rng, n: usize) -> usize {
+        match self {
+            Fleet::Gliders => place_gliders(world, rng, n),
+            Fleet::Spaceships => place_spaceships(world, rng, n),
+        }
+    }
+
+    /// An empty world of the given size with `n` ships of this fleet
+    /// placed as drawn from the given seed.
+    pub fn world(self, n: usize, seed: u64, width: usize, height: usize) -> World {
+        let mut world = World::empty(width, height);
+        self.place(&mut world, &mut Rng::seeded(seed), n);
+        world
+    }
+}
+
+/// A ship placed onto a world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub ship: Ship,
+    /// the bounding box of the ship's live cells
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// the direction the ship travels in; a cell along either axis per
+    /// period
+    pub heading: (isize, isize),
+}
+
+/// Places `n` gliders heading for the center; determines the number
+/// actually placed.
+pub fn place_gliders(world: &mut World, rng: &mut Rng, n: usize) -> usize {
+    place_ships(world, rng, n, &[Ship::Glider]).len()
+}
+
+/// Places `n` ships heading for the center, gliders and lightweight
+/// spaceships at random; determines the number actually placed.
+pub fn place_spaceships(world: &mut World, rng: &mut Rng, n: usize) -> usize {
+    place_ships(world, rng, n, &[Ship::Glider, Ship::Lwss]).len()
+}
+
+/// Places `n` ships of the given kinds (picked at random) heading for
+/// the center of the world; determines where the ships placed went.
+pub fn place_ships(world: &mut World, rng: &mut Rng, n: usize, kinds: &[Ship]) -> Vec<Placement> {
+    let (width, height) = (world.width(), world.height());
+    let center = (width / 2, height / 2);
+    let band = cmp::max(1, cmp::min(width, height) / 8);
+    let mut placed: Vec<Placement> = Vec::with_capacity(n);
+    for i in 0..n {
+        let ship = kinds[rng.below(kinds.len())];
+        let (name, size) = {
+            let s = Stamp::bundled(ship.bundled().0);
+            // ~ any turn of the ship fits a square of its larger
+            // dimension
+            (s.name(), cmp::max(s.width(), s.height()))
+        };
+        if width < size + 2 * MARGIN || height < size + 2 * MARGIN {
+            info!("ship {} of {}: the world of {}x{} is too small for a {}", i + 1, n, width, height, name);
+            continue;
+        }
+        let mut found = None;
+        for _ in 0..MAX_TRIES {
+            let (x, y) = near_edge(rng, width, height, size, band);
+            let heading = heading_for(ship, rng, (x + size / 2, y + size / 2), center);
+            let stamp = oriented(ship, heading);
+            let p = Placement {
+                ship: ship,
+                x: x,
+                y: y,
+                width: stamp.width(),
+                height: stamp.height(),
+                heading: heading,
+            };
+            if is_clear(world, &placed, &p) {
+                found = Some((p, stamp));
+                break;
+            }
+        }
+        match found {
+            Some((p, stamp)) => {
+                world.blit(p.x as isize, p.y as isize, stamp.pattern());
+                placed.push(p);
+            }
+            None => info!("ship {} of {}: no room for a {} after {} tries", i + 1, n, name, MAX_TRIES),
+        }
+    }
+    if placed.len() < n {
+        warn!("placed {} of {} ships; the world of {}x{} has no room for the others",
+              placed.len(),
+              n,
+              width,
+              height);
+    }
+    placed
+}
+
+// ~ a random top left cell of a square of the given size within `band`
+// cells of one of the edges, keeping MARGIN cells away from all of them
+fn near_edge(rng: &mut Rng, width: usize, height: usize, size: usize, band: usize) -> (usize, usize) {
+    let (max_x, max_y) = (width - MARGIN - size, height - MARGIN - size);
+    let along = |rng: &mut Rng, max: usize| MARGIN + rng.below(max - MARGIN + 1);
+    let depth = |rng: &mut Rng, max: usize| rng.below(cmp::min(band, max - MARGIN + 1));
+    match rng.below(4) {
+        0 => (along(rng, max_x), MARGIN + depth(rng, max_y)),
+        1 => (along(rng, max_x), max_y - depth(rng, max_y)),
+        2 => (MARGIN + depth(rng, max_x), along(rng, max_y)),
+        _ => (max_x - depth(rng, max_x), along(rng, max_y)),
+    }
+}
+
+// ~ the heading of the given ship closest to the direction from `at` to
+// `center`; a tie is broken at random
+fn heading_for(ship: Ship, rng: &mut Rng, at: (usize, usize), center: (usize, usize)) -> (isize, isize) {
+    let (dx, dy) = (center.0 as isize - at.0 as isize, center.1 as isize - at.1 as isize);
+    let sign = |rng: &mut Rng, d: isize| {
+        if d != 0 {
+            d.signum()
+        } else if rng.below(2) == 0 {
+            -1
+        } else {
+            1
+        }
+    };
+    match ship {
+        Ship::Glider => (sign(rng, dx), sign(rng, dy)),
+        Ship::Lwss if dx.abs() >= dy.abs() => (sign(rng, dx), 0),
+        Ship::Lwss => (0, sign(rng, dy)),
+    }
+}
+
+// ~ the ship turned to travel in the given direction, which must be one
+// of its four
+fn oriented(ship: Ship, heading: (isize, isize)) -> Stamp {
+    let (index, mut h) = ship.bundled();
+    let mut stamp = Stamp::bundled(index);
+    // ~ a quarter turn clockwise turns a heading (x, y) into (-y, x)
+    for _ in 0..4 {
+        if h == heading {
+            return stamp;
+        }
+        stamp.rotate();
+        h = (-h.1, h.0);
+    }
+    panic!("{:?} cannot head for {:?}", ship, heading);
+}
+
+// ~ whether the given ship keeps CLEARANCE cells away from the ships
+// placed and any other live cells: a dry run of the bounding boxes
+// first, then the cells
+fn is_clear(world: &World, placed: &[Placement], p: &Placement) -> bool {
+    let (x0, y0) = (p.x.saturating_sub(CLEARANCE), p.y.saturating_sub(CLEARANCE));
+    let (x1, y1) = (p.x + p.width + CLEARANCE, p.y + p.height + CLEARANCE);
+    let overlaps = |q: &Placement| q.x < x1 && x0 < q.x + q.width && q.y < y1 && y0 < q.y + q.height;
+    !placed.iter().any(overlaps) && world.count_in_rect(x0, y0, x1 - x0, y1 - y0) == 0
+}
+
+#[test]
+fn test_place_ships() {
+    for &(kinds, n) in &[(&[Ship::Glider][..], 12), (&[Ship::Glider, Ship::Lwss][..], 10)] {
+        let mut world = World::empty(80, 25);
+        let placed = place_ships(&mut world, &mut Rng::seeded(7), n, kinds);
+        assert_eq!(n, placed.len());
+        // ~ no cells overlap: each ship brought all of its cells alive
+        let cells = |p: &Placement| if p.ship == Ship::Glider { 5 } else { 9 };
+        assert_eq!(placed.iter().map(cells).sum::<usize>(), world.alive());
+        for (i, p) in placed.iter().enumerate() {
+            assert!(kinds.contains(&p.ship));
+            assert_eq!(cells(p), world.count_in_rect(p.x, p.y, p.width, p.height));
+            // ~ clear of the edges and of each other
+            assert!(p.x >= MARGIN && p.y >= MARGIN && p.x + p.width + MARGIN <= 80 &&
+                    p.y + p.height + MARGIN <= 25,
+                    "{:?}",
+                    p);
+            for q in &placed[i + 1..] {
+                assert!(p.x + p.width + CLEARANCE <= q.x || q.x + q.width + CLEARANCE <= p.x ||
+                        p.y + p.height + CLEARANCE <= q.y || q.y + q.height + CLEARANCE <= p.y,
+                        "{:?} {:?}",
+                        p,
+                        q);
+            }
+            // ~ heading within 90 degrees of the center (twice the
+            // distance from the ship's center to the world's)
+            let (dx, dy) = (80 - 2 * p.x as isize - p.width as isize,
+                            25 - 2 * p.y as isize - p.height as isize);
+            assert!(dx * p.heading.0 + dy * p.heading.1 > 0, "{:?}", p);
+        }
+    }
+    // ~ the same seed places the same ships
+    let (mut a, mut b) = (World::empty(80, 25), World::empty(80, 25));
+    assert_eq!(10, place_spaceships(&mut a, &mut Rng::seeded(3), 10));
+    assert_eq!(10, Fleet::Spaceships.place(&mut b, &mut Rng::seeded(3), 10));
+    assert_eq!(a.live_cells(), b.live_cells());
+}
+
+#[test]
+fn test_place_ships_crowded() {
+    // ~ too small for any glider within the margin
+    let mut world = World::empty(6, 20);
+    assert_eq!(0, place_gliders(&mut world, &mut Rng::seeded(1), 3));
+    assert!(world.is_empty());
+    // ~ room for some only; live cells are kept clear of
+    let mut world = World::empty(16, 16);
+    world.set_alive(8, 8, true);
+    let placed = place_gliders(&mut world, &mut Rng::seeded(1), 20);
+    assert!(placed > 0 && placed < 20, "{}", placed);
+    assert_eq!(5 * placed + 1, world.alive());
+    assert_eq!(1, world.count_in_rect(6, 6, 5, 5));
+}
+
+#[test]
+fn test_headings() {
+    // ~ each ship travels as headed: a glider a cell diagonally in four
+    // generations, a lightweight spaceship two cells
+    let headings = [(Ship::Glider, 1, vec![(1, 1), (-1, 1), (-1, -1), (1, -1)]),
+                    (Ship::Lwss, 2, vec![(-1, 0), (0, -1), (1, 0), (0, 1)])];
+    for &(ship, speed, ref hs) in &headings {
+        for &h in hs {
+            let mut world = World::empty(20, 20);
+            world.blit(8, 8, oriented(ship, h).pattern());
+            let expected: Vec<_> = world.live_cells()
+                                        .into_iter()
+                                        .map(|(x, y)| ((x as isize + speed * h.0) as usize,
+                                                       (y as isize + speed * h.1) as usize))
+                                        .collect();
+            world.advance_generations(4);
+            let mut cells = world.live_cells();
+            let mut expected = expected;
+            cells.sort();
+            expected.sort();
+            assert_eq!(expected, cells, "{:?} {:?}", ship, h);
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! The terminal the UI draws to and receives its events from.
+
+use color::{self, Rgb};
+use std::time::Instant;
+
+use rustbox::{Color, EventResult, RustBox, Style};
+use time::Duration;
+
+pub trait Terminal {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn print(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str);
+    fn print_char(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, c: char);
+    /// Prints a character in 24-bit colors; terminals unable to do so
+    /// approximate them with the basic colors.
+    fn print_char_rgb(&self, x: usize, y: usize, sty: Style, fg: Rgb, bg: Rgb, c: char) {
+        self.print_char(x, y, sty, color::nearest_basic(fg), color::nearest_basic(bg), c)
+    }
+    fn clear(&self);
+    fn present(&self);
+    fn set_cursor(&self, x: isize, y: isize);
+    /// Waits for the next event.
+    fn poll_event(&self) -> EventResult;
+    /// Waits for the next event at most for the given duration.
+    fn peek_event(&self, timeout: Duration) -> EventResult;
+    /// The current time as far as waiting for events is concerned,
+    /// e.g. to check for them at an interval.
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+impl Terminal for RustBox {
+    fn width(&self) -> usize {
+        RustBox::width(self)
+    }
+    fn height(&self) -> usize {
+        RustBox::height(self)
+    }
+    fn print(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) {
+        RustBox::print(self, x, y, sty, fg, bg, s)
+    }
+    fn print_char(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, c: char) {
+        RustBox::print_char(self, x, y, sty, fg, bg, c)
+    }
+    // ~ `print_char_rgb` falls back to the basic colors: rustbox (and
+    // termbox underneath it) passes colors as 16-bit attributes
+    // holding one of its eight colors; there is no way to emit raw
+    // 24-bit escape sequences through it
+    fn clear(&self) {
+        RustBox::clear(self)
+    }
+    fn present(&self) {
+        RustBox::present(self)
+    }
+    fn set_cursor(&self, x: isize, y: isize) {
+        RustBox::set_cursor(self, x, y)
+    }
+    fn poll_event(&self) -> EventResult {
+        RustBox::poll_event(self, false)
+    }
+    fn peek_event(&self, timeout: Duration) -> EventResult {
+        RustBox::peek_event(self, timeout, false)
+    }
+}
+
+/// An in-memory terminal replaying a scripted sequence of events.
+#[cfg(test)]
+pub mod fake {
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::time::{Duration as StdDuration, Instant};
+
+    use color::{self, Rgb};
+    use rustbox::{self, Color, Event, EventResult, Style};
+    use time::Duration;
+
+    use super::Terminal;
+
+    #[derive(Clone, Copy)]
+    pub struct ScreenCell {
+        pub ch: char,
+        pub style: Style,
+        pub fg: Color,
+        pub bg: Color,
+        /// the background color if printed in 24-bit colors
+        pub bg_rgb: Option<Rgb>,
+    }
+
+    const BLANK: ScreenCell = ScreenCell {
+        ch: ' ',
+        style: rustbox::RB_NORMAL,
+        fg: Color::Default,
+        bg: Color::Default,
+        bg_rgb: None,
+    };
+
+    pub struct FakeTerminal {
+        width: Cell<usize>,
+        height: Cell<usize>,
+        // ~ the cells being drawn to
+        back: RefCell<Vec<ScreenCell>>,
+        // ~ the cells presented to the user
+        front: RefCell<Vec<ScreenCell>>,
+        // ~ the events along with the time (since `start`) they arrive at
+        events: RefCell<VecDeque<(StdDuration, Event)>>,
+        start: Instant,
+        // ~ the time waited for events (or `advance`d) so far, which
+        // passes on the terminal's clock without being slept
+        skipped: Cell<StdDuration>,
+        // ~ the positions printed to (or cleared) since last taken
+        printed: RefCell<Vec<(usize, usize)>>,
+        // ~ the calls printing so far
+        print_calls: Cell<usize>,
+        // ~ the characters printed beyond the edges of the screen so far
+        out_of_bounds: Cell<usize>,
+    }
+
+    impl FakeTerminal {
+        /// Creates a terminal of the given size delivering the given
+        /// events.  Timeouts of `peek_event` are to be scripted as
+        /// `Event::NoEvent`; running out of events is a bug in the
+        /// test.
+        pub fn new(width: usize, height: usize, events: Vec<Event>) -> FakeTerminal {
+            FakeTerminal::with_timed_events(width,
+                                            height,
+                                            events.into_iter()
+                                                  .map(|e| (StdDuration::from_millis(0), e))
+                                                  .collect())
+        }
+
+        /// Creates a terminal delivering the given characters as key
+        /// events.
+        pub fn with_keys(width: usize, height: usize, keys: &str) -> FakeTerminal {
+            let events = keys.chars()
+                             .map(|c| Event::KeyEvent(Some(rustbox::Key::Char(c))))
+                             .collect();
+            FakeTerminal::new(width, height, events)
+        }
+
+        /// Creates a terminal delivering each of the given events not
+        /// before the given time passed since its creation on its clock
+        /// (see `now`.)  Until then, `peek_event` times out.  The clock
+        /// runs in real time, but waiting for an event advances it
+        /// instead of sleeping, as does `advance`, such that tests
+        /// depend on the time waited rather than the time taken.
+        pub fn with_timed_events(width: usize,
+                                 height: usize,
+                                 events: Vec<(StdDuration, Event)>)
+                                 -> FakeTerminal {
+            FakeTerminal {
+                width: Cell::new(width),
+                height: Cell::new(height),
+                back: RefCell::new(vec![BLANK; width * height]),
+                front: RefCell::new(vec![BLANK; width * height]),
+                events: RefCell::new(events.into_iter().collect()),
+                start: Instant::now(),
+                skipped: Cell::new(StdDuration::from_millis(0)),
+                printed: RefCell::new(Vec::new()),
+                print_calls: Cell::new(0),
+                out_of_bounds: Cell::new(0),
+            }
+        }
+
+        /// Advances the terminal's clock by the given time, e.g. for
+        /// work taking that long.
+        pub fn advance(&self, d: StdDuration) {
+            self.skipped.set(self.skipped.get() + d);
+        }
+
+        /// Returns the presented cell at the given position.
+        pub fn cell(&self, x: usize, y: usize) -> ScreenCell {
+            self.front.borrow()[y * self.width.get() + x]
+        }
+
+        /// Returns the positions printed to (or cleared) since the last
+        /// call, in the order of the prints; repeated prints to a
+        /// position are listed repeatedly.
+        pub fn take_printed(&self) -> Vec<(usize, usize)> {
+            self.printed.borrow_mut().drain(..).collect()
+        }
+
+        /// Returns the number of calls printing a string or a
+        /// character so far.
+        pub fn print_calls(&self) -> usize {
+            self.print_calls.get()
+        }
+
+        /// Returns the number of characters printed beyond the edges of
+        /// the screen so far; termbox ignores them, but a terminal might
+        /// not.
+        pub fn out_of_bounds(&self) -> usize {
+            self.out_of_bounds.get()
+        }
+
+        /// Returns the presented characters of the given line.
+        pub fn line(&self, y: usize) -> String {
+            (0..self.width.get()).map(|x| self.cell(x, y).ch).collect()
+        }
+
+        /// Renders the presented screen as text: the characters, the
+        /// foreground colors (upper case if bold), and the background
+        /// colors, each as a grid framed by `|`.  Colors are given by
+        /// their initial (`k` for black), `.` standing for the default
+        /// color (`#` if bold) and `~` for a 24-bit color.
+        pub fn snapshot(&self) -> String {
+            fn color(c: Color) -> char {
+                b".krgybmcw"[c as usize] as char
+            }
+            let fg = |c: ScreenCell| {
+                match (color(c.fg), c.style.contains(rustbox::RB_BOLD)) {
+                    ('.', true) => '#',
+                    (f, true) => f.to_ascii_uppercase(),
+                    (f, false) => f,
+                }
+            };
+            let bg = |c: ScreenCell| if c.bg_rgb.is_some() { '~' } else { color(c.bg) };
+            format!("text:\n{}fg:\n{}bg:\n{}", self.grid(|c| c.ch), self.grid(fg), self.grid(bg))
+        }
+
+        // ~ renders the presented cells mapped by `f` line by line
+        fn grid<F: Fn(ScreenCell) -> char>(&self, f: F) -> String {
+            let mut s = String::new();
+            for y in 0..self.height.get() {
+                s.push('|');
+                s.extend((0..self.width.get()).map(|x| f(self.cell(x, y))));
+                s.push_str("|\n");
+            }
+            s
+        }
+
+        /// Changes the size of the screen, clearing it; by itself, i.e.
+        /// without a resize event, e.g. to shrink it in the middle of a
+        /// frame like a window being dragged.
+        pub fn resize(&self, width: usize, height: usize) {
+            self.width.set(width);
+            self.height.set(height);
+            *self.back.borrow_mut() = vec![BLANK; width * height];
+            *self.front.borrow_mut() = vec![BLANK; width * height];
+        }
+
+        fn put(&self, x: usize, y: usize, c: ScreenCell) {
+            // ~ like termbox, silently ignore out of screen prints
+            if x < self.width.get() && y < self.height.get() {
+                self.back.borrow_mut()[y * self.width.get() + x] = c;
+                self.printed.borrow_mut().push((x, y));
+            } else {
+                self.out_of_bounds.set(self.out_of_bounds.get() + 1);
+            }
+        }
+
+        // ~ waits at most `timeout` for the next event to arrive
+        fn next_event(&self, timeout: Option<StdDuration>) -> Event {
+            let due = match self.events.borrow().front() {
+                None => panic!("FakeTerminal: out of events"),
+                Some(&(due, _)) => due,
+            };
+            let now = self.start.elapsed() + self.skipped.get();
+            if due > now {
+                let wait = due - now;
+                match timeout {
+                    Some(t) if t < wait => {
+                        self.advance(t);
+                        return Event::NoEvent;
+                    }
+                    _ => self.advance(wait),
+                }
+            }
+            let e = self.events.borrow_mut().pop_front().unwrap().1;
+            if let Event::ResizeEvent(w, h) = e {
+                self.resize(w as usize, h as usize);
+            }
+            e
+        }
+    }
+
+    impl Terminal for FakeTerminal {
+        fn width(&self) -> usize {
+            self.width.get()
+        }
+        fn height(&self) -> usize {
+            self.height.get()
+        }
+        fn print(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) {
+            self.print_calls.set(self.print_calls.get() + 1);
+            for (i, c) in s.chars().enumerate() {
+                self.put(x + i,
+                         y,
+                         ScreenCell {
+                             ch: c,
+                             style: sty,
+                             fg: fg,
+                             bg: bg,
+                             bg_rgb: None,
+                         });
+            }
+        }
+        fn print_char(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, c: char) {
+            self.print_calls.set(self.print_calls.get() + 1);
+            self.put(x,
+                     y,
+                     ScreenCell {
+                         ch: c,
+                         style: sty,
+                         fg: fg,
+                         bg: bg,
+                         bg_rgb: None,
+                     });
+        }
+        fn print_char_rgb(&self, x: usize, y: usize, sty: Style, fg: Rgb, bg: Rgb, c: char) {
+            self.print_calls.set(self.print_calls.get() + 1);
+            self.put(x,
+                     y,
+                     ScreenCell {
+                         ch: c,
+                         style: sty,
+                         fg: color::nearest_basic(fg),
+                         bg: Color::Default,
+                         bg_rgb: Some(bg),
+                     });
+        }
+        fn clear(&self) {
+            for c in self.back.borrow_mut().iter_mut() {
+                *c = BLANK;
+            }
+            let (width, height) = (self.width.get(), self.height.get());
+            self.printed.borrow_mut().extend((0..height).flat_map(|y| (0..width).map(move |x| (x, y))));
+        }
+        fn present(&self) {
+            self.front.borrow_mut().clone_from(&self.back.borrow());
+        }
+        fn set_cursor(&self, _: isize, _: isize) {}
+        fn poll_event(&self) -> EventResult {
+            loop {
+                match self.next_event(None) {
+                    Event::NoEvent => {}
+                    e => return Ok(e),
+                }
+            }
+        }
+        fn peek_event(&self, timeout: Duration) -> EventResult {
+            let nanos = timeout.num_nanoseconds().unwrap_or(0);
+            Ok(self.next_event(Some(StdDuration::new(nanos as u64 / 1_000_000_000,
+                                                     (nanos % 1_000_000_000) as u32))))
+        }
+        fn now(&self) -> Instant {
+            self.start + self.start.elapsed() + self.skipped.get()
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Reading text files written by other programs, e.g. patterns from
+//! decades-old archives: their line endings may be CRLF or a lone CR,
+//! their comments in Latin-1 rather than UTF-8, and a UTF-8 byte order
+//! mark may precede them.
+//!
+//! `TextInput` normalizes such a file's bytes before any parsing: the
+//! byte order mark is stripped, every CRLF and lone CR becomes a LF,
+//! and bytes not valid in UTF-8 are decoded as U+FFFD replacement
+//! characters instead of failing the whole file.  Comments thus read
+//! fine in any encoding; the parts of a file to be ASCII (e.g. the
+//! cells of a pattern) are checked to be with `non_ascii`, such that a
+//! stray byte is reported with its row and column.
+
+use std::char;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// The UTF-8 encoded byte order mark.
+const BOM: &'static [u8] = b"\xef\xbb\xbf";
+
+/// The text of a file normalized for parsing; see the module's
+/// description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+}
+
+impl TextInput {
+    pub fn from_bytes(bytes: &[u8]) -> TextInput {
+        let bytes = if bytes.starts_with(BOM) { &bytes[BOM.len()..] } else { bytes };
+        let mut normalized = Vec::with_capacity(bytes.len());
+        let mut bytes = bytes.iter().cloned().peekable();
+        while let Some(b) = bytes.next() {
+            if b == b'\r' {
+                // ~ CRLF and a lone CR alike
+                if bytes.peek() == Some(&b'\n') {
+                    bytes.next();
+                }
+                normalized.push(b'\n');
+            } else {
+                normalized.push(b);
+            }
+        }
+        TextInput { text: String::from_utf8_lossy(&normalized).into_owned() }
+    }
+
+    /// Reads all of the given input.
+    pub fn read_from<R: Read>(mut input: R) -> io::Result<TextInput> {
+        let mut bytes = Vec::new();
+        try!(input.read_to_end(&mut bytes));
+        Ok(TextInput::from_bytes(&bytes))
+    }
+
+    pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<TextInput> {
+        let f = try!(File::open(path));
+        let len = try!(f.metadata()).len() as usize;
+        let mut bytes = Vec::with_capacity(len);
+        try!((&f).read_to_end(&mut bytes));
+        Ok(TextInput::from_bytes(&bytes))
+    }
+
+    /// The normalized text; lines end with a LF only.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Finds the first character of the given line which is not ASCII;
+/// determines its (1-based) column along with a description, e.g. to
+/// report a pattern's cells in another encoding than expected.
+pub fn non_ascii(line: &str) -> Option<(usize, String)> {
+    line.chars().enumerate().find(|&(_, c)| !c.is_ascii()).map(|(i, c)| {
+        let reason = if c == char::REPLACEMENT_CHARACTER {
+            "Byte not valid in UTF-8 (expected ASCII)".to_owned()
+        } else {
+            format!("Non-ASCII character: {} (expected ASCII)", c)
+        };
+        (i + 1, reason)
+    })
+}
+
+#[test]
+fn test_line_endings() {
+    let text = |bytes: &[u8]| TextInput::from_bytes(bytes).as_str().to_owned();
+    assert_eq!("a\nb\n", text(b"a\r\nb\r\n"));
+    assert_eq!("a\nb\n", text(b"a\rb\r"));
+    // ~ mixed; a CR right before a CRLF is a line of its own
+    assert_eq!("a\nb\nc\n\nd", text(b"a\nb\r\nc\r\r\nd"));
+    assert_eq!("", text(b""));
+    assert_eq!("\n", text(b"\r"));
+}
+
+#[test]
+fn test_bom() {
+    let text = |bytes: &[u8]| TextInput::from_bytes(bytes).as_str().to_owned();
+    assert_eq!("!x\n", text(b"\xef\xbb\xbf!x\r"));
+    // ~ only at the start
+    assert_eq!("a\u{feff}", text(b"a\xef\xbb\xbf"));
+    // ~ a partial one is just invalid
+    assert_eq!("\u{fffd}a", text(b"\xef\xbba"));
+}
+
+#[test]
+fn test_encodings() {
+    // ~ Latin-1 (`é` is 0xe9) decodes lossily, UTF-8 as it is
+    let input = TextInput::from_bytes(b"!caf\xe9 au lait\n!caf\xc3\xa9\n.O\n");
+    assert_eq!("!caf\u{fffd} au lait\n!café\n.O\n", input.as_str());
+    let lines: Vec<_> = input.as_str().lines().collect();
+    assert_eq!(None, non_ascii(lines[2]));
+    assert_eq!(Some((5, "Byte not valid in UTF-8 (expected ASCII)".to_owned())), non_ascii(lines[0]));
+    assert_eq!(Some((5, "Non-ASCII character: é (expected ASCII)".to_owned())), non_ascii(lines[1]));
+    // ~ NUL is ASCII; it is up to the parsers to refuse it
+    assert_eq!("O\u{0}O", TextInput::from_bytes(b"O\x00O").as_str());
+    assert_eq!(None, non_ascii("O\u{0}O"));
+}
+
+#[test]
+fn test_read() {
+    let input = TextInput::read_from(&b"\xef\xbb\xbfx\r\ny"[..]).unwrap();
+    assert_eq!("x\ny", input.as_str());
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/text/crlf.cells");
+    assert_eq!("!CRLF line endings\n.O\n..O\nOOO\n", TextInput::read_file(path).unwrap().as_str());
+    let e = TextInput::read_file("/nonexistent/cgol-test.cells").unwrap_err();
+    assert_eq!(io::ErrorKind::NotFound, e.kind());
+}
@@ -0,0 +1,217 @@
+//! The colors of the world's cells on the screen.
+//!
+//! A theme can be read from a text file mapping the cells' states to
+//! colors, one per line: a key, the foreground color and optionally
+//! the background color, e.g. `alive green black`.  The keys are
+//! `alive` and `dead` for the cells in general, and `alive:N` and
+//! `dead:N` for cells with `N` (0 to 8) live neighbours while coloring
+//! cells by their number of neighbours.  Empty lines and lines
+//! starting with `#` are ignored.
+
+use std::fs::File;
+use std::io::Read;
+
+use rustbox::Color;
+
+use text::TextInput;
+
+/// The names of the colors understood by `parse_color`; in the order
+/// of the terminal's palette, following `default`.
+pub const COLOR_NAMES: [&'static str; 9] = ["default", "black", "red", "green", "yellow", "blue",
+                                            "magenta", "cyan", "white"];
+
+const COLORS: [Color; 9] = [Color::Default,
+                            Color::Black,
+                            Color::Red,
+                            Color::Green,
+                            Color::Yellow,
+                            Color::Blue,
+                            Color::Magenta,
+                            Color::Cyan,
+                            Color::White];
+
+/// The colors configured by the user for the world's cells.  Colors
+/// of features (e.g. the neighbour count overlay) take precedence
+/// over these, which in turn take precedence over the terminal's
+/// default colors.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// the foreground color of live cells
+    pub fg: Color,
+    /// the background color of all cells
+    pub bg: Color,
+    /// the foreground color of dead cells
+    pub dead_fg: Color,
+    /// the background color of live cells instead of `bg`
+    pub alive_bg: Option<Color>,
+    /// the colors of dead (first) and live cells by their number of
+    /// live neighbours replacing those of the features coloring cells
+    /// so; without a background the cell's usual one applies
+    pub counts: [[Option<(Color, Option<Color>)>; 9]; 2],
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            fg: Color::Default,
+            bg: Color::Default,
+            dead_fg: Color::Default,
+            alive_bg: None,
+            counts: [[None; 9]; 2],
+        }
+    }
+}
+
+impl Theme {
+    /// Resolves the foreground and background colors of a (live or
+    /// dead) cell given the colors a feature requests for it, if any.
+    pub fn cell(&self, alive: bool, fg: Option<Color>, bg: Option<Color>) -> (Color, Color) {
+        let (default_fg, default_bg) = if alive {
+            (self.fg, self.alive_bg.unwrap_or(self.bg))
+        } else {
+            (self.dead_fg, self.bg)
+        };
+        (fg.unwrap_or(default_fg), bg.unwrap_or(default_bg))
+    }
+
+    /// The colors configured for a (live or dead) cell with `n` live
+    /// neighbours, if any.
+    pub fn count_cell(&self, alive: bool, n: usize) -> Option<(Color, Color)> {
+        match self.counts[alive as usize].get(n) {
+            Some(&Some((fg, bg))) => Some(self.cell(alive, Some(fg), bg)),
+            _ => None,
+        }
+    }
+
+    /// Reads a theme from the given file (see the module's
+    /// description.)
+    pub fn load_from_file(filename: &str) -> Result<Theme, String> {
+        File::open(filename)
+            .map_err(|e| e.to_string())
+            .and_then(Theme::read_from)
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn read_from<R: Read>(input: R) -> Result<Theme, String> {
+        let input = try!(TextInput::read_from(input).map_err(|e| e.to_string()));
+        let mut theme = Theme::default();
+        for (i, line) in input.as_str().lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: String| format!("line {}: {}", i + 1, reason);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (fg, bg) = match words.len() {
+                2 => (try!(parse_color(words[1]).map_err(&invalid)), None),
+                3 => {
+                    (try!(parse_color(words[1]).map_err(&invalid)),
+                     Some(try!(parse_color(words[2]).map_err(&invalid))))
+                }
+                _ => return Err(invalid("expected a key followed by one or two colors".to_owned())),
+            };
+            let mut key = words[0].splitn(2, ':');
+            let alive = match key.next() {
+                Some("alive") => true,
+                Some("dead") => false,
+                _ => return Err(invalid(format!("Unknown key: {}", words[0]))),
+            };
+            match key.next() {
+                None if alive => {
+                    theme.fg = fg;
+                    theme.alive_bg = bg;
+                }
+                None => {
+                    theme.dead_fg = fg;
+                    theme.bg = bg.unwrap_or(theme.bg);
+                }
+                Some(n) => {
+                    match n.parse::<usize>() {
+                        Ok(n) if n <= 8 => theme.counts[alive as usize][n] = Some((fg, bg)),
+                        _ => return Err(invalid(format!("Invalid number of neighbours: {}", n))),
+                    }
+                }
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// Parses a color given by its name (see `COLOR_NAMES`) or by its
+/// index `0` to `7` in the terminal's palette.
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let s = s.trim();
+    if let Some(i) = COLOR_NAMES.iter().position(|&n| n.eq_ignore_ascii_case(s)) {
+        return Ok(COLORS[i]);
+    }
+    match s.parse::<usize>() {
+        Ok(i) if i < 8 => Ok(COLORS[i + 1]),
+        _ => {
+            Err(format!("Unknown color: {} (expected one of {} or a palette index 0-7)",
+                        s,
+                        COLOR_NAMES.join(", ")))
+        }
+    }
+}
+
+#[test]
+fn test_parse_color() {
+    assert!(Color::Blue == parse_color("blue").unwrap());
+    assert!(Color::Default == parse_color("Default").unwrap());
+    assert!(Color::Black == parse_color("0").unwrap());
+    assert!(Color::White == parse_color("7").unwrap());
+    assert_eq!("Unknown color: 8 (expected one of default, black, red, green, yellow, blue, \
+                magenta, cyan, white or a palette index 0-7)",
+               parse_color("8").err().unwrap());
+    assert!(parse_color("orange").is_err());
+}
+
+#[test]
+fn test_resolution_order() {
+    let t = Theme {
+        fg: Color::Green,
+        bg: Color::Black,
+        ..Theme::default()
+    };
+    assert!((Color::Green, Color::Black) == t.cell(true, None, None));
+    // ~ the user's fg applies to live cells only
+    assert!((Color::Default, Color::Black) == t.cell(false, None, None));
+    assert!((Color::Red, Color::Black) == t.cell(true, Some(Color::Red), None));
+    assert!((Color::Default, Color::Cyan) == t.cell(false, None, Some(Color::Cyan)));
+    let t = Theme::default();
+    assert!((Color::Default, Color::Default) == t.cell(true, None, None));
+}
+
+#[test]
+fn test_read_theme() {
+    let input = "# night\nalive yellow\ndead blue black\n\ndead:3  white green\nalive:8 red\n";
+    let t = Theme::read_from(input.as_bytes()).unwrap();
+    assert!((Color::Yellow, Color::Black) == t.cell(true, None, None));
+    assert!((Color::Blue, Color::Black) == t.cell(false, None, None));
+    assert!(Some((Color::White, Color::Green)) == t.count_cell(false, 3));
+    // ~ the background of the cell's state unless given
+    assert!(Some((Color::Red, Color::Black)) == t.count_cell(true, 8));
+    assert!(None == t.count_cell(false, 2) && None == t.count_cell(true, 3));
+    let t = Theme::read_from("alive green white\n".as_bytes()).unwrap();
+    assert!((Color::Green, Color::White) == t.cell(true, None, None));
+    assert!((Color::Default, Color::Default) == t.cell(false, None, None));
+
+    for &(input, e) in [("alive\n", "line 1: expected a key followed by one or two colors"),
+                        ("\nfoo red\n", "line 2: Unknown key: foo"),
+                        ("dead:9 red\n", "line 1: Invalid number of neighbours: 9"),
+                        ("alive orange\n", "line 1: Unknown color: orange (expected one of default, black, \
+                                             red, green, yellow, blue, magenta, cyan, white or a palette \
+                                             index 0-7)")]
+                           .iter() {
+        assert_eq!(e, Theme::read_from(input.as_bytes()).err().unwrap());
+    }
+}
+
+#[test]
+fn test_read_fixture() {
+    // ~ CR line endings and a Latin-1 comment
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/text/theme.txt");
+    let t = Theme::load_from_file(path).unwrap();
+    assert!((Color::Yellow, Color::Black) == t.cell(true, None, None));
+    assert!((Color::Blue, Color::Black) == t.cell(false, None, None));
+}
@@ -1,11 +1,55 @@
+use std::cell::{RefCell, RefMut};
+use std::cmp;
+use std::collections::VecDeque;
 use std::fmt::{self, Write};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::iter;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration as StdDuration, Instant};
 
-use rustbox::{self, RustBox, InitOptions, Event, Color};
+use rustbox::{self, RustBox, InitOptions, Event, Color, Style};
 use rustbox::keyboard::Key;
-use time::Duration;
-use rand::thread_rng;
-use world::World;
+use time::{self, Duration};
+use rand::{thread_rng, Rng};
+use autoframe::AutoFrame;
+use bindings::{self, Binding};
+use bookmarks::{self, Bookmarks};
+use clipboard::{self, Copied};
+use compare::{Ghost, Presence};
+use color::{self, ColorTier, Rgb};
+use counter::{self, Rect, StreamCounter};
+use damage::{self, DamageList};
+use follow::{Follow, Lost};
+use hud;
+use metrics;
+use minimap::{self, Corner, Layout};
+use parser;
+use perf::PerfCounters;
+use patterns::{self, DemoCue, DemoPhase, Pack, Sequencer};
+use period::{self, Measurement};
+use progress::{self, ProgressMeter};
+use prompt::{Outcome, Prompt};
+use reaper::{self, Reaper};
+use reference::Reference;
+use remote::{self, Command, Remote};
+use schedule::RuleSchedule;
+use screen::{FrameGeometry, ScreenMap};
+use screenshot::ScreenBuffer;
+use simulation;
+use macros::{self, Macros};
+use slots::{self, Slots};
+use soup;
+use stamp::Stamp;
+use synthesis::Fleet;
+use terminal::Terminal;
+use theme::Theme;
+use workspace::{Labels, Workspace};
+use world::{self, AutoExpand, BoundaryMode, RandomKind, Rule, UpdateMode, World};
 
+#[derive(Debug)]
 enum Error {
     RustboxInit(rustbox::InitError),
     RustboxEvent(rustbox::EventError),
@@ -31,46 +75,754 @@ impl fmt::Display for Error {
     }
 }
 
-struct UI {
-    terminal: RustBox,
-    width: usize,
-    height: usize,
+struct UI<T: Terminal> {
+    terminal: T,
+    // ~ the size of the terminal the frame being drawn (or the last one
+    // drawn) is laid out for; see `begin_frame`
+    frame: FrameGeometry,
 
     line_buf: String,
 
     alive_char: char,
     dead_char: char,
+    color_tier: ColorTier,
+    theme: Theme,
+
+    // ~ whether to color dead cells by their number of live neighbours
+    // while the animation is paused
+    show_neighbour_counts: bool,
+    // ~ whether to show the neighbour count of every cell as a digit
+    // while the animation is paused
+    show_count_digits: bool,
+    // ~ whether to color live cells by their number of live neighbours
+    density_color: bool,
+    // ~ the generation the neighbour counts were determined at and
+    // the counts; shared by the overlays and the density coloring
+    counts_cache: Option<(usize, Vec<u8>)>,
+    // ~ whether to show the status line; if not, the world occupies
+    // the whole screen
+    show_status: bool,
+    // ~ the period of the displayed generation if measured; `Some(None)`
+    // if none was found
+    period: Option<Option<Measurement>>,
+    // ~ the number of history frames left while playing backwards
+    rewinding: Option<usize>,
+    // ~ whether the animation's delay adapts to the number of cells
+    // changed by the last step (see `slowmo_delay`)
+    slowmo: bool,
+    slowmo_bounds: (i64, i64),
+    // ~ the number of cells changed by the last step
+    last_changes: usize,
+    // ~ the delay between two generations of the animation; `None`
+    // when unthrottled
+    delay: Option<Duration>,
+    auto_expand: Option<AutoExpand>,
+    // ~ counts the objects passing the region configured by the user
+    counter: Option<StreamCounter>,
+    slots: Slots,
+    // ~ the file the slots are written back to on exit; none if it
+    // could not be read, not to overwrite what is in it
+    slots_file: Option<String>,
+    macros: Macros,
+    // ~ the file the macros are written back to on exit, likewise
+    macros_file: Option<String>,
+    // ~ the register being recorded into along with the keys recorded
+    // so far
+    recording: Option<(char, Vec<Key>)>,
+    // ~ the keys of the macros being replayed, dispatched ahead of the
+    // terminal's events; `None` marks the end of a macro
+    pending: VecDeque<Option<Key>>,
+    // ~ the registers of the macros being replayed, innermost last
+    playing: Vec<char>,
+    // ~ the pause before each replayed key
+    macro_delay: StdDuration,
+    // ~ the rule of any world shown; changed by the rule editor
+    rule: Rule,
+    // ~ the rule editor if open; `true` while editing the survival
+    // (rather than the birth) counts
+    rule_editor: Option<bool>,
+    // ~ the file the displayed world was loaded from, if any
+    file: Option<PathBuf>,
+    // ~ the patterns cycled through instead of the file's directory
+    pack: Option<Pack>,
+    show_minimap: bool,
+    minimap_corner: Corner,
+    // ~ the mini-map's layout, the generation it was sampled at, and
+    // the sampled densities
+    minimap_cache: Option<(Layout, usize, Vec<f64>)>,
+    // ~ a note on the last action (e.g. "saved slot 3"); shown until
+    // the world changes
+    message: Option<String>,
+    bookmarks: Bookmarks,
+    // ~ the labels of the displayed world's cells
+    labels: Labels,
+    // ~ the labels of the world restarted from by the rule editor
+    initial_labels: Labels,
+    // ~ whether to list the bookmarks (to jump to one by its number)
+    show_bookmarks: bool,
+    // ~ the name of the demo's phase being shown
+    demo: Option<&'static str>,
+    // ~ the most live cells of the displayed world since it was loaded
+    // or generated
+    peak: usize,
+    // ~ the file the statistics of the displayed generation are
+    // appended to on demand
+    statlog: Option<File>,
+    // ~ the population of the generations since the displayed world
+    // was loaded or generated, starting with `populations_from`
+    populations: Vec<usize>,
+    populations_from: usize,
+    // ~ whether to show the chart of the populations
+    show_plot: bool,
+    // ~ what the large generation display shows, the corner configured
+    // for it (`None` choosing the emptiest one), the corner it is shown
+    // in, and the area of the screen last covered by it
+    hud: hud::Mode,
+    hud_corner: Option<Corner>,
+    hud_shown_in: Corner,
+    hud_rect: Option<(usize, usize, usize, usize)>,
+    // ~ the parts of the screen to draw anew with the next frame
+    damage: DamageList,
+    // ~ the cells changed by the last generation
+    changes: Vec<simulation::Change>,
+    // ~ the pattern previewed for stamping, if any, and the cell of the
+    // world its top left cell is over
+    stamp: Option<Stamp>,
+    stamp_at: (usize, usize),
+    // ~ the name and the cells of the pattern stamped last, to nudge
+    // with the arrow keys until any other key is pressed
+    placed: Option<(&'static str, Vec<(usize, usize)>)>,
+    // ~ the cell of the world shown in the top left corner of the
+    // screen; other than the world's own only if it is larger than the
+    // screen
+    viewport: (usize, usize),
+    // ~ moves the viewport along with the live cells, if on
+    autoframe: Option<AutoFrame>,
+    // ~ the object the viewport follows, if any
+    follow: Option<Follow>,
+    // ~ the world run under the rule compared with, if any
+    ghost: Option<Ghost>,
+    // ~ the generations at which the rule switches, if given
+    schedule: Option<RuleSchedule>,
+    // ~ the pattern to match the world against, if any
+    reference: Option<Reference>,
+    // ~ the mirror of the terminal's buffers to capture the screen from
+    // (see `screenshot`); kept up by the printing wrappers
+    screen: RefCell<ScreenBuffer>,
+    // ~ the viewports marked to return to by their numbers (see
+    // `view_number`)
+    view_marks: [Option<(usize, usize)>; VIEW_MARKS],
+    // ~ the seed the displayed world was generated from, if any
+    seed: Option<u64>,
+    // ~ the seed and the density (unless the default) of the world the
+    // next `r` generates, as requested remotely; an empty world without
+    // a seed to stamp a pattern onto (see `remote_keys`)
+    generate: Option<(Option<u64>, Option<f64>)>,
+    // ~ the remote control, if listening
+    remote: Option<Remote>,
+    // ~ the file name (or the generation) being typed on the status
+    // line, if any, and what to do with it
+    prompt: Option<(Dialog, Prompt)>,
+    // ~ the progress of the unthrottled animation while it runs
+    fast_forward: Option<FastForward>,
+    // ~ whether the keys changing the world are refused
+    read_only: bool,
+    // ~ the number of characters of `UNLOCK_CHORD` typed so far, if
+    // any
+    unlock: Option<usize>,
+    perf: PerfCounters,
+    show_perf: bool,
+    // ~ the interval of the counters' log lines and when the last one
+    // was written
+    perf_log: Option<(StdDuration, Instant)>,
+    // ~ the remover of stable debris, if any; its removals are part of
+    // the changes of a generation
+    reaper: Option<Reaper>,
+    // ~ the world set aside while a region of it runs on its own, if
+    // any
+    sandbox: Option<Sandbox>,
+}
+
+// ~ the purposes of the file name prompt
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Dialog {
+    // ~ the file to be saved to along with the name of the existing
+    // file confirmed to be overwritten, if any
+    SaveAs { overwrite: Option<String> },
+    Load,
+    // ~ the generation to fast-forward to
+    Goto,
+    // ~ the region to run on its own
+    Sandbox,
+}
+
+// ~ the state of the session set aside while a region of the world runs
+// on its own in a sandbox; restored when leaving it
+struct Sandbox {
+    parent: World,
+    // ~ the region of the parent taken into the sandbox
+    region: Rect,
+    history: VecDeque<World>,
+    initial: World,
+    viewport: (usize, usize),
+    auto_expand: Option<AutoExpand>,
+    reaper: Option<Reaper>,
+    counter: Option<StreamCounter>,
+    labels: Labels,
+    initial_labels: Labels,
+    peak: usize,
+    overlays: Overlays,
+}
+
+// ~ the overlays drawn over the world; hidden in the sandbox, where they
+// would show the world set aside
+#[derive(Clone, Copy)]
+struct Overlays {
+    neighbour_counts: bool,
+    count_digits: bool,
+    density_color: bool,
+    minimap: bool,
+    bookmarks: bool,
+    plot: bool,
+    perf: bool,
+    hud: hud::Mode,
+}
+
+// ~ the progress of the unthrottled animation, possibly up to a given
+// generation
+struct FastForward {
+    target: Option<usize>,
+    meter: ProgressMeter,
+    started: Instant,
+    // ~ the generation it started from
+    from: usize,
+    // ~ when and at which generation the meter was last sampled
+    sampled: (Instant, usize),
+    // ~ the progress as shown in the status line; refreshed along with
+    // the meter only
+    text: String,
+}
+
+impl FastForward {
+    fn new(generation: usize, target: Option<usize>) -> FastForward {
+        let now = Instant::now();
+        let mut ff = FastForward {
+            target: target,
+            meter: ProgressMeter::new(progress::DEFAULT_WINDOW_SECS),
+            started: now,
+            from: generation,
+            sampled: (now, generation),
+            text: String::new(),
+        };
+        ff.describe(generation);
+        ff
+    }
+
+    // ~ samples the meter at the given generation unless it was
+    // sampled less than PROGRESS_UPDATE_MS ago
+    fn sample(&mut self, generation: usize) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.sampled.0);
+        if elapsed >= StdDuration::from_millis(PROGRESS_UPDATE_MS) {
+            self.meter.add_sample(generation.saturating_sub(self.sampled.1), elapsed);
+            self.sampled = (now, generation);
+            self.describe(generation);
+        }
+    }
+
+    fn describe(&mut self, generation: usize) {
+        let done = generation.saturating_sub(self.from);
+        let rate = self.meter.rate().map_or(String::new(), |r| format!(", {:.0} gen/s", r));
+        self.text = match self.target {
+            Some(target) => {
+                let left = target.saturating_sub(generation);
+                let eta = self.meter
+                              .eta(left)
+                              .map_or(String::new(), |d| format!(", ETA {}", progress::format_duration(d)));
+                format!("To gen {}: {} done, {} left{}{} (Esc cancels)", target, done, left, rate, eta)
+            }
+            None => format!("Turbo: {} done{} (Esc stops)", done, rate),
+        };
+    }
 }
 
-impl UI {
-    fn init(alive: char, dead: char) -> Result<UI, Error> {
-        let t = try!(RustBox::init(InitOptions { buffer_stderr: true, ..Default::default() }));
+impl<T: Terminal> UI<T> {
+    fn init(t: T, opts: &Options) -> UI<T> {
         let (width, height) = (t.width(), t.height());
-        Ok(UI {
+        // ~ an unreadable slots file is not worth aborting for
+        let mut warnings: Vec<_> = opts.message.iter().cloned().collect();
+        let (slots, slots_file) = match opts.slots_file {
+            None => (Slots::new(), None),
+            Some(ref f) => match Slots::load_from_file(f) {
+                Ok(slots) => (slots, Some(f.clone())),
+                Err(e) => {
+                    warnings.push(format!("{} (slots not loaded, nor saved)", e));
+                    (Slots::new(), None)
+                }
+            },
+        };
+        let statlog = match opts.statlog_file {
+            None => None,
+            Some(ref f) => match OpenOptions::new().append(true).create(true).open(f) {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    warnings.push(format!("{}: {} (no stats log)", f, e));
+                    None
+                }
+            },
+        };
+        let (macros, macros_file) = match opts.macros_file {
+            None => (Macros::new(), None),
+            Some(ref f) => match Macros::load_from_file(f) {
+                Ok(macros) => (macros, Some(f.clone())),
+                Err(e) => {
+                    warnings.push(format!("{} (macros not loaded, nor saved)", e));
+                    (Macros::new(), None)
+                }
+            },
+        };
+        let pack = match opts.pack {
+            None => None,
+            Some(ref dir) => match Pack::open(dir, opts.max_line_length, opts.allow_empty) {
+                Ok(pack) => Some(pack),
+                Err(e) => {
+                    warnings.push(format!("{} (pack not loaded)", e));
+                    None
+                }
+            },
+        };
+        let mut ui = UI {
             terminal: t,
-            width: width,
-            height: height,
+            frame: FrameGeometry::new(width, height),
             line_buf: String::with_capacity(width),
-            alive_char: alive,
-            dead_char: dead,
+            alive_char: opts.alive_char,
+            dead_char: opts.dead_char,
+            color_tier: opts.color_tier,
+            theme: opts.theme,
+            show_neighbour_counts: false,
+            show_count_digits: false,
+            density_color: opts.density_color,
+            counts_cache: None,
+            show_status: opts.show_status,
+            period: None,
+            rewinding: None,
+            slowmo: false,
+            slowmo_bounds: opts.slowmo_bounds,
+            last_changes: 0,
+            delay: Some(Duration::milliseconds(DEFAULT_DELAY_MS)),
+            auto_expand: opts.auto_expand,
+            counter: opts.count_region.map(|r| StreamCounter::new(r, counter::DEFAULT_DEBOUNCE)),
+            slots: slots,
+            slots_file: slots_file,
+            macros: macros,
+            macros_file: macros_file,
+            recording: None,
+            pending: VecDeque::new(),
+            playing: Vec::new(),
+            macro_delay: StdDuration::from_millis(opts.macro_delay_ms),
+            rule: opts.rule,
+            rule_editor: None,
+            file: opts.file.as_ref().map(PathBuf::from),
+            pack: pack,
+            show_minimap: false,
+            minimap_corner: opts.minimap_corner,
+            minimap_cache: None,
+            message: None,
+            bookmarks: Bookmarks::new(0, bookmarks::DEFAULT_CAP),
+            labels: Labels::new(),
+            initial_labels: Labels::new(),
+            show_bookmarks: false,
+            demo: None,
+            peak: 0,
+            statlog: statlog,
+            populations: Vec::new(),
+            populations_from: 0,
+            show_plot: false,
+            hud: hud::Mode::Off,
+            hud_corner: opts.hud_corner,
+            hud_shown_in: opts.hud_corner.unwrap_or(hud::CORNERS[0]),
+            hud_rect: None,
+            damage: DamageList::new(damage::DEFAULT_CAP),
+            changes: Vec::new(),
+            stamp: None,
+            stamp_at: (0, 0),
+            placed: None,
+            viewport: (0, 0),
+            autoframe: if opts.autoframe { Some(AutoFrame::new()) } else { None },
+            follow: None,
+            ghost: opts.compare.map(Ghost::new),
+            schedule: opts.schedule.clone(),
+            reference: opts.reference.clone().map(Reference::new),
+            screen: RefCell::new(ScreenBuffer::new(width, height)),
+            view_marks: [None; VIEW_MARKS],
+            seed: None,
+            generate: None,
+            remote: None,
+            prompt: None,
+            fast_forward: None,
+            read_only: opts.read_only,
+            unlock: None,
+            perf: {
+                let mut perf = PerfCounters::new();
+                perf.set_timed(opts.perf_log_secs.is_some());
+                perf
+            },
+            show_perf: false,
+            perf_log: opts.perf_log_secs.map(|secs| (StdDuration::from_secs(secs), Instant::now())),
+            reaper: opts.reap.map(Reaper::new),
+            sandbox: None,
+        };
+        for m in warnings {
+            ui.set_warning(m);
+        }
+        ui
+    }
+
+    // ~ shows the given error or warning on the status line; logs it
+    // too while the status line is hidden, not to lose it (see `n`)
+    fn set_warning(&mut self, m: String) {
+        if !self.show_status {
+            warn!("{}", m);
+        }
+        self.message = Some(m);
+    }
+
+    // ~ starts bookmarking the run of the given initial world; reads
+    // the bookmarks of a previous run of it if configured
+    fn init_bookmarks(&mut self, initial: &World, opts: &Options) {
+        let fingerprint = bookmarks::fingerprint(initial);
+        self.bookmarks = match opts.bookmarks_file {
+            None => Bookmarks::new(fingerprint, bookmarks::DEFAULT_CAP),
+            Some(ref f) => match Bookmarks::load_from_file(f, fingerprint, bookmarks::DEFAULT_CAP) {
+                Ok(b) => b,
+                Err(e) => {
+                    self.set_warning(format!("{} (bookmarks not loaded)", e));
+                    Bookmarks::new(fingerprint, bookmarks::DEFAULT_CAP)
+                }
+            },
+        };
+    }
+
+    // ~ starts bookmarking the run of a world loaded or generated
+    // anew.  the file holds the bookmarks of a single run; those of the
+    // run left are saved to it first, such that it has none of another
+    // run to offer
+    fn restart_bookmarks(&mut self, initial: &World, opts: &Options) {
+        let fingerprint = bookmarks::fingerprint(initial);
+        if let Some(ref f) = opts.bookmarks_file {
+            if self.bookmarks.is_modified() {
+                if let Err(e) = self.bookmarks.save_to_file(f) {
+                    self.set_warning(format!("{} (bookmarks not saved)", e));
+                    return;
+                }
+                if fingerprint != self.bookmarks.fingerprint() {
+                    self.bookmarks = Bookmarks::new(fingerprint, bookmarks::DEFAULT_CAP);
+                    return;
+                }
+            }
+        }
+        self.init_bookmarks(initial, opts);
+    }
+
+    // ~ loads the pattern following (or preceding) the current file
+    // in its directory and makes it the current file; `None` if there
+    // is none or it fails to load, the status line telling why
+    fn load_next_file(&mut self, forward: bool, opts: &Options) -> Option<Workspace> {
+        if self.pack.is_some() {
+            return self.load_next_pattern(forward, opts);
+        }
+        let current = match self.file {
+            None => {
+                self.set_warning("no file loaded".to_owned());
+                return None;
+            }
+            Some(ref f) => f.clone(),
+        };
+        let dir = match current.parent() {
+            Some(d) if d != Path::new("") => d.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let current = match current.file_name() {
+            Some(name) => dir.join(name),
+            None => current.clone(),
+        };
+        let files = match patterns::list_patterns(&dir) {
+            Ok(files) => files,
+            Err(e) => {
+                self.set_warning(format!("{}: {}", dir.display(), e));
+                return None;
+            }
+        };
+        let i = match cycle_file(&files, &current, forward) {
+            Some(i) => i,
+            None => {
+                self.set_warning(format!("{}: no patterns", dir.display()));
+                return None;
+            }
+        };
+        let name = files[i].to_string_lossy().into_owned();
+        // ~ even if it fails to load, to go on from there
+        self.file = Some(files[i].clone());
+        match self.load_file(&name, opts) {
+            Ok(ws) => {
+                self.message = Some(format!("{} ({}/{})", name, i + 1, files.len()));
+                Some(ws)
+            }
+            Err(e) => {
+                self.set_warning(format!("{}: {}", name, e));
+                None
+            }
+        }
+    }
+
+    // ~ the binding of the given key if refused in the read-only mode
+    fn refused(&self, key: Key) -> Option<&'static Binding> {
+        if !self.read_only {
+            return None;
+        }
+        let applies = |context| {
+            match context {
+                bindings::Context::Always => true,
+                bindings::Context::BookmarksShown => self.show_bookmarks,
+                bindings::Context::Statlog => self.statlog.is_some(),
+            }
+        };
+        bindings::lookup(key, applies).and_then(|b| if b.mutating { Some(b) } else { None })
+    }
+
+    // ~ loads the pattern of the given file with its labels and makes
+    // it the current file
+    fn load_file(&mut self, name: &str, opts: &Options) -> Result<Workspace, parser::ParseError> {
+        let mut ws = try!(parser::load_workspace(name, opts.max_line_length, opts.allow_empty));
+        opts.apply(&mut ws.world);
+        self.file = Some(PathBuf::from(name));
+        Ok(ws)
+    }
+
+    // ~ opens the file name prompt pre-filled with the given text; the
+    // game is to be paused
+    fn open_prompt(&mut self, world: &World, dialog: Dialog, text: &str) {
+        let (label, extensions): (_, &'static [&'static str]) = match dialog {
+            Dialog::SaveAs { .. } => ("Save as", &parser::EXPORT_EXTENSIONS),
+            Dialog::Load => ("Load", &patterns::EXTENSIONS),
+            Dialog::Goto => ("Go to generation", &[]),
+            Dialog::Sandbox => ("Sandbox X,Y,W,H", &[]),
+        };
+        self.prompt = Some((dialog, Prompt::new(label, text, extensions)));
+        self.message = None;
+        self.update_status(world);
+        self.place_cursor();
+        self.flush();
+    }
+
+    // ~ the line showing the prompt, scrolled to keep the cursor on
+    // the screen, and the column of the cursor
+    fn prompt_line(&self) -> Option<(String, usize)> {
+        self.prompt.as_ref().map(|&(_, ref prompt)| {
+            let mut line = format!("{}: {}", prompt.label(), prompt.text());
+            let cursor = prompt.label().chars().count() + 2 + prompt.cursor();
+            let skip = (cursor + 1).saturating_sub(self.frame.width);
+            if let Some(ref m) = self.message {
+                line = format!("{} / {}", line, m);
+            }
+            let mut line: String = line.chars().skip(skip).collect();
+            fit_line(&mut line, self.frame.width, true);
+            (line, cursor - skip)
         })
     }
 
-    fn width(&self) -> usize {
-        self.width
+    // ~ shows the cursor in the prompt, or else parks it in the bottom
+    // right corner
+    fn place_cursor(&self) {
+        match self.prompt_line() {
+            Some((_, x)) => self.set_cursor(x, self.frame.height - 1),
+            None => self.set_cursor(self.frame.width - 1, self.frame.height - 1),
+        }
     }
-    fn height(&self) -> usize {
-        self.height
+
+    // ~ loads the pattern following (or preceding) the current file in
+    // the pack; the first (or last) one if the current file is not
+    // part of the pack
+    fn load_next_pattern(&mut self, forward: bool, opts: &Options) -> Option<Workspace> {
+        let pack = self.pack.as_mut().unwrap();
+        if pack.is_empty() {
+            self.set_warning("no patterns in pack".to_owned());
+            return None;
+        }
+        let n = pack.len();
+        let i = match (self.file.as_ref().and_then(|f| pack.position(f)), forward) {
+            (Some(i), true) => (i + 1) % n,
+            (Some(i), false) => (i + n - 1) % n,
+            (None, true) => 0,
+            (None, false) => n - 1,
+        };
+        self.file = Some(pack.path(i).to_path_buf());
+        match pack.load(i) {
+            Ok(mut w) => {
+                opts.apply(&mut w);
+                self.message = Some(format!("{} ({}/{})", pack.name(i), i + 1, n));
+                Some(Workspace::new(w))
+            }
+            Err(e) => {
+                let m = format!("{}: {}", pack.name(i), e);
+                self.set_warning(m);
+                None
+            }
+        }
     }
 
-    fn expand_to_screen(&mut self, world: &mut World) {
-        let (w, h) = (self.terminal.width(), self.terminal.height());
-        if w == self.width && h == self.height {
+    // ~ records the population of the displayed generation; the
+    // generations following it are forgotten, e.g. when playing
+    // backwards, and so are all for an unrelated world
+    fn record_population(&mut self, world: &World) {
+        let g = world.generation();
+        if g < self.populations_from || g > self.populations_from + self.populations.len() {
+            self.populations.clear();
+            self.populations_from = g;
+        }
+        self.populations.truncate(g - self.populations_from);
+        self.populations.push(world.alive());
+    }
+
+    // ~ charts the recorded populations in the top left corner of the
+    // screen
+    fn print_plot(&self) {
+        let height = cmp::min(PLOT_HEIGHT, self.world_lines().saturating_sub(2));
+        if height == 0 {
             return;
         }
-        world.expand_to(w, h);
-        self.width = w;
-        self.height = h;
+        let chart = metrics::plot_from(&self.populations, self.populations_from, self.frame.width, height);
+        let lines: Vec<&str> = chart.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        for (y, line) in lines.iter().enumerate() {
+            let line = format!("{:1$}", line, width);
+            let line: String = line.chars().take(self.frame.width).collect();
+            self.put_str(0, y, rustbox::RB_NORMAL, Color::White, Color::Blue, &line);
+        }
+    }
+
+    // ~ appends the statistics of the displayed generation to the stats
+    // log as a line of `key=value` pairs
+    fn log_stats(&mut self, world: &World) -> io::Result<()> {
+        let f = match self.statlog {
+            Some(ref mut f) => f,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, "no stats log (see --statlog)")),
+        };
+        let mut line = format!("generation={} alive={} density={:.6} peak={}",
+                               world.generation(),
+                               world.alive(),
+                               metrics::density(world),
+                               cmp::max(self.peak, world.alive()));
+        match self.period {
+            None => {}
+            Some(None) => {
+                let _ = write!(line, " period=>{}", period::DEFAULT_MAX_PERIOD);
+            }
+            Some(Some(m)) => {
+                let _ = write!(line, " period={}", m);
+            }
+        }
+        line.push('\n');
+        try!(io::Write::write_all(f, line.as_bytes()));
+        // ~ a line at a time, as the user asked for it; it's hence worth
+        // forcing it to the disk right away
+        try!(f.sync_data());
+        self.perf.bytes_written += line.len() as u64;
+        Ok(())
+    }
+
+    // ~ the next key of the macros being replayed, if any
+    fn next_replayed_key(&mut self) -> Option<Key> {
+        loop {
+            match self.pending.pop_front() {
+                Some(Some(key)) => return Some(key),
+                Some(None) => {
+                    self.playing.pop();
+                }
+                None => return None,
+            }
+        }
+    }
+
+    // ~ queues the keys of the macro in the given register ahead of
+    // those still pending; a macro invoking itself (directly or through
+    // others) would never end and stops all replaying instead.  returns
+    // a message for the status line
+    fn replay_macro(&mut self, register: char) -> Option<String> {
+        if self.playing.contains(&register) {
+            self.pending.clear();
+            self.playing.clear();
+            return Some(format!("@{} invokes itself; stopped", register));
+        }
+        let keys = match self.macros.get(register) {
+            Some(keys) => keys.to_vec(),
+            None => return Some(format!("@{} is empty", register)),
+        };
+        self.pending.push_front(None);
+        for &key in keys.iter().rev() {
+            self.pending.push_front(Some(key));
+        }
+        self.playing.push(register);
+        None
+    }
+
+    // ~ moves what refers to the world's cells along with them, the
+    // world having grown by the given number of cells to the left and
+    // top (the object followed aside, see `Follow::translate`)
+    fn translate(&mut self, dx: usize, dy: usize) {
+        self.labels.translate(dx, dy);
+        if let Some(ref mut r) = self.reference {
+            r.translate(dx, dy);
+        }
+        for mark in self.view_marks.iter_mut() {
+            *mark = mark.map(|(x, y)| (x + dx, y + dy));
+        }
+        if let Some(ref mut c) = self.counter {
+            c.translate(dx, dy);
+        }
+    }
+
+    // ~ restarts counting the passing objects in the region as given,
+    // e.g. for a new world; the counter set aside by the sandbox stays
+    fn reset_counter(&mut self, opts: &Options) {
+        if self.counter.is_some() {
+            self.counter = opts.count_region.map(|r| StreamCounter::new(r, counter::DEFAULT_DEBOUNCE));
+        }
+    }
+
+    // ~ takes the terminal's size for the frame about to be drawn; tells
+    // whether it differs from the last frame's
+    fn begin_frame(&mut self) -> bool {
+        let frame = FrameGeometry::new(self.terminal.width(), self.terminal.height());
+        mem::replace(&mut self.frame, frame) != frame
+    }
+
+    // ~ fits the world to the terminal's current size, e.g. after it was
+    // resized
+    fn expand_to_screen(&mut self, world: &mut World) {
+        self.begin_frame();
+        // ~ the sandbox keeps the size of its region; the world set aside
+        // is fit when left
+        if self.sandbox.is_none() {
+            self.fit_to_screen(world);
+        }
+    }
+
+    // ~ expands the world to the size of the screen; auto-expanding
+    // worlds never shrink.  labels falling outside are dropped
+    fn fit_to_screen(&mut self, world: &mut World) {
+        if self.auto_expand.is_some() {
+            let (w, h) = (cmp::max(world.width(), self.frame.width),
+                          cmp::max(world.height(), self.frame.height));
+            world.expand_to(w, h);
+        } else {
+            world.expand_to(self.frame.width, self.frame.height);
+        }
+        let dropped = self.labels.crop(world.width(), world.height());
+        if !dropped.is_empty() {
+            let names: Vec<_> = dropped.iter().map(|&(_, _, ref l)| &l[..]).collect();
+            self.set_warning(format!("dropped labels outside the world: {}", names.join(", ")));
+        }
     }
 
     fn get_drawing_char(&self, alive: bool) -> char {
@@ -89,157 +841,4808 @@ impl UI {
         }
     }
 
+    // ~ prints the visible part of the world's row shown on the given
+    // line of the screen in the colors of the theme
+    fn print_row(&mut self, world: &World, line: usize) {
+        let (vx, vy) = self.viewport;
+        let h = vy + line;
+        self.render_line(world, h);
+        for (col, c) in self.line_buf.chars().skip(vx).take(self.frame.width).enumerate() {
+            let w = vx + col;
+            let alive = world.is_alive(w, h);
+            let (c, fg, bg) = match self.ghost_look(w, h, alive).or_else(|| self.reference_look(w, h, alive)) {
+                Some(look) => look,
+                None => {
+                    let (fg, bg) = self.theme.cell(alive, None, self.label_color(w, h));
+                    (c, fg, bg)
+                }
+            };
+            self.put_char(col, line, rustbox::RB_NORMAL, fg, bg, c);
+        }
+    }
+
+    // ~ keeps the ghost (see `compare`) in step with the world outside of
+    // the sandbox; tells whether it started over
+    fn follow_ghost(&mut self, world: &World) -> bool {
+        match self.ghost {
+            Some(ref mut g) if self.sandbox.is_none() => g.follow(world),
+            _ => false,
+        }
+    }
+
+    // ~ how a cell alive in the world or the ghost only is drawn: alive,
+    // in the color of the rule it lives by; `None` for the others,
+    // drawn as usual
+    fn ghost_look(&self, x: usize, y: usize, alive: bool) -> Option<(char, Color, Color)> {
+        let fg = match self.ghost {
+            Some(ref g) if self.sandbox.is_none() => {
+                match g.presence(x, y, alive) {
+                    Presence::First => FIRST_RULE_COLOR,
+                    Presence::Second => SECOND_RULE_COLOR,
+                    Presence::Both | Presence::Neither => return None,
+                }
+            }
+            _ => return None,
+        };
+        let (_, bg) = self.theme.cell(true, None, self.label_color(x, y));
+        Some((self.get_drawing_char(true), fg, bg))
+    }
+
+    // ~ how a cell alive in the reference but dead in the world is
+    // drawn: alive, dimly; `None` for the others.  Not in the sandbox,
+    // whose cells do not line up with the reference's
+    fn reference_look(&self, x: usize, y: usize, alive: bool) -> Option<(char, Color, Color)> {
+        match self.reference {
+            Some(ref r) if self.sandbox.is_none() && r.behind(x, y, alive) => {
+                let (_, bg) = self.theme.cell(false, None, self.label_color(x, y));
+                Some((self.get_drawing_char(true), REFERENCE_COLOR, bg))
+            }
+            _ => None,
+        }
+    }
+
     fn print_world(&mut self, world: &World) {
-        for h in 0..(world.height() - 1) {
-            self.render_line(world, h);
-            self.print_line(0, h, &self.line_buf);
+        if self.sandbox.is_some() {
+            self.print_sandbox(world);
+            self.update_status(world);
+            return;
+        }
+        self.follow_ghost(world);
+        for line in 0..cmp::min(world.height() - self.viewport.1 - 1, self.frame.height) {
+            self.print_row(world, line);
+        }
+        if self.density_color {
+            self.print_density_colors(world);
         }
         self.update_status(world);
+        self.print_hud(world);
+    }
+
+    // ~ draws the world of the sandbox framed in the middle of the
+    // screen
+    fn print_sandbox(&mut self, world: &World) {
+        let map = self.screen_map();
+        let ((x0, y0), (width, height)) = (map.origin, (map.screen_width, map.screen_height));
+        let edge = format!("+{}+", "-".repeat(width - 2));
+        self.put_str(x0, y0, rustbox::RB_BOLD, Color::Yellow, Color::Default, &edge);
+        self.put_str(x0, y0 + height - 1, rustbox::RB_BOLD, Color::Yellow, Color::Default, &edge);
+        for y in y0 + 1..y0 + height - 1 {
+            self.put_char(x0, y, rustbox::RB_BOLD, Color::Yellow, Color::Default, '|');
+            self.put_char(x0 + width - 1, y, rustbox::RB_BOLD, Color::Yellow, Color::Default, '|');
+        }
+        for y in 0..world.height() {
+            for x in 0..world.width() {
+                self.print_cell(x, y, world.is_alive(x, y));
+            }
+        }
+    }
+
+    // ~ hides the overlays, telling which were shown
+    fn take_overlays(&mut self) -> Overlays {
+        let shown = Overlays {
+            neighbour_counts: self.show_neighbour_counts,
+            count_digits: self.show_count_digits,
+            density_color: self.density_color,
+            minimap: self.show_minimap,
+            bookmarks: self.show_bookmarks,
+            plot: self.show_plot,
+            perf: self.show_perf,
+            hud: self.hud,
+        };
+        self.show_neighbour_counts = false;
+        self.show_count_digits = false;
+        self.density_color = false;
+        self.show_minimap = false;
+        self.show_bookmarks = false;
+        self.show_plot = false;
+        self.show_perf = false;
+        self.hud = hud::Mode::Off;
+        self.hud_rect = None;
+        shown
+    }
+
+    fn restore_overlays(&mut self, shown: Overlays) {
+        self.show_neighbour_counts = shown.neighbour_counts;
+        self.show_count_digits = shown.count_digits;
+        self.density_color = shown.density_color;
+        self.show_minimap = shown.minimap;
+        self.show_bookmarks = shown.bookmarks;
+        self.show_plot = shown.plot;
+        self.show_perf = shown.perf;
+        self.hud = shown.hud;
+    }
+
+    // ~ sets the world aside, along with its history and whatever
+    // refers to its cells, to run the given copy of a region of it on
+    // its own
+    fn enter_sandbox(&mut self,
+                     region: Rect,
+                     sandbox: World,
+                     world: &mut World,
+                     history: &mut VecDeque<World>,
+                     initial: &mut World) {
+        let overlays = self.take_overlays();
+        self.sandbox = Some(Sandbox {
+            parent: mem::replace(world, sandbox),
+            region: region,
+            history: mem::replace(history, VecDeque::with_capacity(HISTORY_LEN)),
+            initial: mem::replace(initial, world.clone()),
+            viewport: mem::replace(&mut self.viewport, (0, 0)),
+            auto_expand: self.auto_expand.take(),
+            reaper: self.reaper.take(),
+            counter: self.counter.take(),
+            labels: mem::replace(&mut self.labels, Labels::new()),
+            initial_labels: mem::replace(&mut self.initial_labels, Labels::new()),
+            peak: mem::replace(&mut self.peak, world.alive()),
+            overlays: overlays,
+        });
+        self.rewinding = None;
+        self.period = None;
+        self.message = Some("Enter pastes back, Esc discards".to_owned());
+    }
+
+    // ~ restores the world set aside by `enter_sandbox`, pasting the
+    // sandbox's region back into it if asked to
+    fn leave_sandbox(&mut self,
+                     paste: bool,
+                     world: &mut World,
+                     history: &mut VecDeque<World>,
+                     initial: &mut World) {
+        let sb = self.sandbox.take().unwrap();
+        let region = mem::replace(world, sb.parent);
+        *history = sb.history;
+        *initial = sb.initial;
+        self.viewport = sb.viewport;
+        self.auto_expand = sb.auto_expand;
+        self.reaper = sb.reaper;
+        self.counter = sb.counter;
+        self.labels = sb.labels;
+        self.initial_labels = sb.initial_labels;
+        self.peak = sb.peak;
+        self.restore_overlays(sb.overlays);
+        self.rewinding = None;
+        self.period = None;
+        self.message = Some(if paste {
+            // ~ taken from there, hence fitting
+            let changed = world.replace_region(sb.region.x, sb.region.y, &region).unwrap();
+            self.peak = cmp::max(self.peak, world.alive());
+            format!("pasted back the sandbox after {} gens ({} cells changed)",
+                    region.generation(),
+                    changed)
+        } else {
+            format!("discarded the sandbox after {} gens", region.generation())
+        });
+        // ~ the screen might have been resized meanwhile
+        self.fit_to_screen(world);
+    }
+
+    // ~ the region of the world typed into the sandbox's prompt along
+    // with a copy of it; the region must lie within the world and fit
+    // the screen along with its frame
+    fn extract_sandbox(&self, world: &World, s: &str) -> Result<(Rect, World), String> {
+        let r = try!(Rect::parse(s).ok_or_else(|| format!("not a region (X,Y,W,H): {}", s)));
+        // ~ not `r.width + 2`, which might overflow
+        if r.width > self.frame.width.saturating_sub(2) || r.height > self.world_lines().saturating_sub(2) {
+            return Err(format!("a {}x{} region does not fit the screen", r.width, r.height));
+        }
+        match world.extract(r.x, r.y, r.width, r.height, BoundaryMode::Bounded) {
+            Ok(w) => Ok((r, w)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // ~ draws the generation in large digits over the world in the
+    // configured corner, or the one covering the fewest live cells; the
+    // cells of the area no longer covered are drawn anew
+    fn print_hud(&mut self, world: &World) {
+        if self.hud == hud::Mode::Off {
+            return;
+        }
+        let population = if self.hud == hud::Mode::Population {
+            Some(world.alive())
+        } else {
+            None
+        };
+        let lines = hud::render(world.generation(), population);
+        let (width, height) = (lines[0].chars().count(), lines.len());
+        let corner = match self.hud_corner {
+            Some(c) => c,
+            None => {
+                hud::emptiest_corner(world,
+                                     self.hud_shown_in,
+                                     width,
+                                     height,
+                                     self.frame.width,
+                                     self.world_lines(),
+                                     self.viewport)
+            }
+        };
+        let rect = hud::place(corner, width, height, self.frame.width, self.world_lines())
+                       .map(|(x, y)| (x, y, width, height));
+        if let Some((x0, y0, w, h)) = self.hud_rect {
+            if self.hud_rect != rect {
+                let (vx, vy) = self.viewport;
+                for y in vy + y0..cmp::min(vy + y0 + h, world.height()) {
+                    for x in vx + x0..cmp::min(vx + x0 + w, world.width()) {
+                        self.print_cell(x, y, world.is_alive(x, y));
+                    }
+                }
+            }
+        }
+        self.hud_shown_in = corner;
+        self.hud_rect = rect;
+        if let Some((x, y, _, _)) = rect {
+            for (i, line) in lines.iter().enumerate() {
+                self.put_str(x, y + i, rustbox::RB_BOLD, Color::White, Color::Blue, line);
+            }
+        }
     }
 
     fn update_status(&mut self, world: &World) {
-        let line_is_clean = if world.height() >= self.height() {
-            let h = self.height() - 1;
-            self.render_line(world, h);
-            self.print_line(0, h, &self.line_buf);
+        // ~ the prompt takes the status line, shown or not
+        if let Some((line, _)) = self.prompt_line() {
+            self.print_line(0, self.frame.height - 1, &line);
+            return;
+        }
+        let line_is_clean = if world.height() - self.viewport.1 >= self.frame.height {
+            let h = self.frame.height - 1;
+            self.print_row(world, h);
             true
         } else {
             false
         };
-        self.print_status(line_is_clean,
-                          format_args!("Gen: {} / Alive: {}", world.generation(), world.alive()));
+        if self.show_status {
+            let mut extra = String::new();
+            match self.period {
+                None => {}
+                Some(None) => {
+                    let _ = write!(extra, " / Period: > {}", period::DEFAULT_MAX_PERIOD);
+                }
+                Some(Some(m)) => {
+                    let _ = write!(extra, " / {}", m);
+                }
+            }
+            if let Some(n) = self.rewinding {
+                let _ = write!(extra, " / Rewinding: {} left", n);
+            }
+            match self.rule_editor {
+                Some(false) => {
+                    let _ = write!(extra, " / Rule: {} [births]", world.rule());
+                }
+                Some(true) => {
+                    let _ = write!(extra, " / Rule: {} [survival]", world.rule());
+                }
+                None if world.rule() != world::CONWAY => {
+                    let _ = write!(extra, " / Rule: {}", world.rule());
+                }
+                None => {}
+            }
+            let schedule = self.schedule.as_ref().filter(|_| self.sandbox.is_none());
+            if let Some((g, rule)) = schedule.and_then(|s| s.next_switch(world.generation())) {
+                let _ = write!(extra, " / Next: {} in {}", rule, g - world.generation());
+            }
+            if let Some(ref stamp) = self.stamp {
+                let (x, y) = self.stamp_at;
+                let _ = write!(extra, " / Stamp: {} at {},{}", stamp.name(), x, y);
+                let clipped = stamp.pattern().alive() - stamp.cells_at(x, y, world).len();
+                if clipped > 0 {
+                    let _ = write!(extra, " ({} clipped)", clipped);
+                }
+            }
+            if let Some(ref sb) = self.sandbox {
+                let r = sb.region;
+                let _ = write!(extra, " / Sandbox: {}x{} at {},{}", r.width, r.height, r.x, r.y);
+            }
+            if world.update_mode() != UpdateMode::Synchronous {
+                let _ = write!(extra, " / Update: {}", world.update_mode());
+            }
+            if self.slowmo {
+                extra.push_str(" / AUTO");
+            }
+            if let Some(ref c) = self.counter {
+                let _ = write!(extra, " / {}", c);
+            }
+            if let Some(name) = self.demo {
+                let _ = write!(extra, " / Demo: {}", name);
+            }
+            if let Some((register, _)) = self.recording {
+                let _ = write!(extra, " / Recording: {}", register);
+            }
+            if self.read_only {
+                extra.push_str(" / READ-ONLY");
+            }
+            if let Some(ref r) = self.reaper {
+                let _ = write!(extra, " / Reaped: {}", r.reaped());
+            }
+            if let Some(ref m) = self.message {
+                let _ = write!(extra, " / {}", m);
+            }
+            if world.is_empty() {
+                extra.push_str(" / EXTINCT");
+            }
+            if world.width() > self.frame.width || world.height() > self.frame.height {
+                // ~ only a part of the world is visible
+                let _ = write!(extra, " / World: {}x{}", world.width(), world.height());
+                if self.viewport != (0, 0) {
+                    let _ = write!(extra, " at {},{}", self.viewport.0, self.viewport.1);
+                }
+            }
+            if self.autoframe.is_some() {
+                extra.push_str(" / Autoframe");
+            }
+            if self.follow.is_some() {
+                extra.push_str(" / Following");
+            }
+            if let Some(ref g) = self.ghost {
+                let _ = write!(extra, " / vs {}: {} differ", g.rule(), g.differing());
+            }
+            match self.reference {
+                Some(ref mut r) if r.shown() && self.sandbox.is_none() => {
+                    r.update(world);
+                    let _ = write!(extra, " / Reference: {} differ", r.differing());
+                }
+                _ => {}
+            }
+            if world.boundary() != BoundaryMode::Wrap {
+                let _ = write!(extra, " / Edges: {}", world.boundary());
+            }
+            match (self.delay, &self.fast_forward) {
+                (Some(d), _) => {
+                    // ~ the delay in effect, which the slow motion
+                    // adapts up to the one set
+                    let d = if self.slowmo {
+                        slowmo_delay(self.last_changes, world.width() * world.height(), self.slowmo_bounds)
+                    } else {
+                        d
+                    };
+                    let _ = write!(extra, " / Delay: {}ms", d.num_milliseconds());
+                }
+                // ~ the progress stands in for the missing delay
+                (None, &Some(ref ff)) => {
+                    let _ = write!(extra, " / {}", ff.text);
+                }
+                (None, &None) => extra.push_str(" / Delay: none"),
+            }
+            self.print_status(line_is_clean,
+                              format_args!("Gen: {} / Alive: {}{}",
+                                           world.generation(),
+                                           world.alive(),
+                                           extra));
+        }
+    }
+
+    // ~ the number of screen lines available to the world
+    fn world_lines(&self) -> usize {
+        self.frame.world_lines(self.show_status)
     }
 
     fn print_status(&mut self, clear: bool, args: fmt::Arguments) {
         self.line_buf.clear();
         let _ = self.line_buf.write_fmt(args);
-
-        if !clear {
-            for _ in 0..(self.line_buf.len() - self.width()) {
-                self.line_buf.push(' ');
-            }
-        }
-        self.print_line(0, self.height - 1, &self.line_buf);
+        // ~ a clean line shows the world's last row beyond the text
+        fit_line(&mut self.line_buf, self.frame.width, !clear);
+        self.print_line(0, self.frame.height - 1, &self.line_buf);
     }
 
     fn print_line(&self, x: usize, y: usize, line: &str) {
-        self.terminal.print(x,
-                            y,
-                            rustbox::RB_NORMAL,
-                            Color::Default,
-                            Color::Default,
-                            line);
+        self.put_str(x, y, rustbox::RB_NORMAL, Color::Default, Color::Default, line);
     }
 
-    fn print_char(&self, x: usize, y: usize, c: char) {
-        self.terminal.print_char(x, y, rustbox::RB_NORMAL, Color::Default, Color::Default, c);
+    // ~ colors the dead cells of the world by their number of live
+    // neighbours; the cells about to be born show their count
+    fn print_neighbour_counts(&mut self, world: &World) {
+        self.update_counts(world);
+        let counts = &self.counts_cache.as_ref().unwrap().1;
+        let (vx, vy) = self.viewport;
+        for h in vy..cmp::min(world.height(), vy + self.world_lines()) {
+            for w in vx..cmp::min(world.width(), vx + self.frame.width) {
+                if !world.is_alive(w, h) {
+                    let n = counts[h * world.width() + w];
+                    let (c, style) = if world.rule().next(false, n as usize) {
+                        (char::from_digit(n as u32, 10).unwrap(), rustbox::RB_BOLD)
+                    } else {
+                        (self.dead_char, rustbox::RB_NORMAL)
+                    };
+                    // ~ the theme's colors for the count take precedence
+                    // over the gradient too
+                    match (self.theme.count_cell(false, n as usize), self.color_tier) {
+                        (Some((fg, bg)), _) => self.put_char(w - vx, h - vy, style, fg, bg, c),
+                        (None, ColorTier::Basic) => {
+                            let (fg, bg) = self.theme.cell(false, None, neighbour_count_color(n));
+                            self.put_char(w - vx, h - vy, style, fg, bg, c)
+                        }
+                        (None, ColorTier::Truecolor) => {
+                            let bg = color::gradient(n as f64 / 8.0, color::FIRE);
+                            self.put_char_rgb(w - vx, h - vy, style, Rgb(255, 255, 255), bg, c)
+                        }
+                    }
+                }
+            }
+        }
     }
 
-    fn clear(&self) {
-        self.terminal.clear();
+    // ~ colors the visible live cells by their number of live
+    // neighbours; lone cells blue, crowded ones red
+    fn print_density_colors(&mut self, world: &World) {
+        let (width, height) = (self.frame.width, self.world_lines());
+        self.print_density_colors_in(world, (0, 0, width, height));
     }
 
-    fn flush(&self) {
-        self.terminal.present();
+    // ~ colors the live cells like `print_density_colors` within the
+    // given rectangle of the screen only
+    fn print_density_colors_in(&mut self, world: &World, (x, y, width, height): damage::Rect) {
+        self.update_counts(world);
+        let counts = &self.counts_cache.as_ref().unwrap().1;
+        let (vx, vy) = self.viewport;
+        for line in y..cmp::min(y + height, cmp::min(world.height() - vy, self.world_lines())) {
+            for col in x..cmp::min(x + width, cmp::min(world.width() - vx, self.frame.width)) {
+                let (w, h) = (vx + col, vy + line);
+                if world.is_alive(w, h) {
+                    let n = counts[h * world.width() + w];
+                    let (fg, bg) = self.theme
+                                       .count_cell(true, n as usize)
+                                       .unwrap_or_else(|| self.theme.cell(true, Some(density_color(n)), None));
+                    self.put_char(col, line, rustbox::RB_NORMAL, fg, bg, self.alive_char);
+                }
+            }
+        }
     }
 
-    fn set_cursor(&self, w: usize, h: usize) {
-        self.terminal.set_cursor(w as isize, h as isize);
+    // ~ determines the neighbour counts of the world unless already
+    // known for its generation
+    fn update_counts(&mut self, world: &World) {
+        let fresh = match self.counts_cache {
+            Some((g, ref counts)) => {
+                g == world.generation() && !world.is_dirty() && counts.len() == world.width() * world.height()
+            }
+            None => false,
+        };
+        if !fresh {
+            self.counts_cache = Some((world.generation(), world.neighbour_counts()));
+        }
     }
 
-    fn redraw_scene(&mut self, world: &World, clear: bool) {
-        if clear {
-            self.clear();
+    // ~ replaces the visible cells by their number of live neighbours;
+    // digits of live cells are bold, dead cells without neighbours show
+    // the dead char
+    fn print_count_digits(&self, world: &World) {
+        let (vx, vy) = self.viewport;
+        for h in vy..cmp::min(world.height(), vy + self.world_lines()) {
+            for w in vx..cmp::min(world.width(), vx + self.frame.width) {
+                let n = world.neighbour_count(w, h);
+                let (c, style) = match (world.is_alive(w, h), n) {
+                    (true, n) => (digit(n), rustbox::RB_BOLD),
+                    (false, 0) => (self.dead_char, rustbox::RB_NORMAL),
+                    (false, n) => (digit(n), rustbox::RB_NORMAL),
+                };
+                self.put_char(w - vx, h - vy, style, Color::Default, Color::Default, c);
+            }
         }
-        self.print_world(world);
-        self.set_cursor(self.width - 1, self.height - 1);
-        self.flush();
     }
-}
 
-pub fn run(world: Option<World>, alive: char, dead: char) -> Result<(), String> {
-    run_(world, alive, dead).map_err(|e| format!("error: {}", e))
-}
-
-fn run_(world: Option<World>, alive: char, dead: char) -> Result<(), Error> {
-    let mut ui = try!(UI::init(alive, dead));
-    // ~ if no world was explicitely specified, generated one
-    let mut world = match world {
-        Some(w) => w,
-        None => World::random(&mut thread_rng(), ui.width(), ui.height()),
-    };
-    // ~ expand the give world to the size of the ui and draw the world
-    {
-        world.expand_to(ui.width(), ui.height());
-        ui.redraw_scene(&world, false);
+    // ~ draws the mini-map over the world with the visible part of the
+    // world outlined; the map is sampled anew every few generations,
+    // and whenever the world was replaced or its layout changed.  given
+    // the damage of a frame, the map is drawn only if sampled anew or
+    // damaged
+    fn print_minimap(&mut self, world: &World, damage: Option<&DamageList>) {
+        let layout = match Layout::new(world.width(),
+                                       world.height(),
+                                       self.frame.width,
+                                       self.world_lines(),
+                                       self.minimap_corner) {
+            Some(l) => l,
+            None => return,
+        };
+        let gen = world.generation();
+        let stale = match self.minimap_cache {
+            Some((l, g, _)) => {
+                l != layout || world.is_dirty() || gen < g || gen >= g + minimap::REFRESH_EVERY
+            }
+            None => true,
+        };
+        if stale {
+            self.minimap_cache = Some((layout, gen, minimap::downsample(world, &layout)));
+        } else if let Some(d) = damage {
+            if !d.intersects((layout.x, layout.y, layout.width, layout.height)) {
+                return;
+            }
+        }
+        let densities = &self.minimap_cache.as_ref().unwrap().2;
+        let rect = layout.viewport_rect(self.viewport.0,
+                                        self.viewport.1,
+                                        self.frame.width,
+                                        self.world_lines());
+        for my in 0..layout.height {
+            for mx in 0..layout.width {
+                let (c, style, fg) = match minimap::outline_char(mx, my, rect) {
+                    Some(c) => (c, rustbox::RB_BOLD, Color::Yellow),
+                    None => {
+                        let d = densities[my * layout.width + mx];
+                        (minimap::density_char(d), rustbox::RB_NORMAL, Color::White)
+                    }
+                };
+                self.put_char(layout.x + mx, layout.y + my, style, fg, Color::Blue, c);
+            }
+        }
     }
 
-    let mut maxdelay = Duration::milliseconds(100);
-    let mut nextdelay = maxdelay;
-    let mut animate = false;
-
-    // ~ start the event loop
-    loop {
-        let e = try!(if animate {
-            ui.terminal.peek_event(nextdelay, false)
+    // ~ lists the bookmarks in the top left corner of the screen; given
+    // the damage of a frame, only if damaged
+    fn print_bookmarks(&self, damage: Option<&DamageList>) {
+        let mut lines = Vec::with_capacity(self.bookmarks.len() + 1);
+        if self.bookmarks.is_empty() {
+            lines.push("No bookmarks (b: add)".to_owned());
         } else {
-            ui.terminal.poll_event(false)
-        });
+            lines.push("Bookmarks (1-9: jump, B: close)".to_owned());
+            for (i, b) in self.bookmarks.iter().enumerate() {
+                lines.push(format!("{}: gen {} / {} alive", i + 1, b.generation(), b.alive()));
+            }
+        }
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        if let Some(d) = damage {
+            if !d.intersects((0, 0, width, lines.len())) {
+                return;
+            }
+        }
+        for (y, line) in lines.iter().take(self.world_lines()).enumerate() {
+            let line = format!("{:1$}", line, width);
+            let line: String = line.chars().take(self.frame.width).collect();
+            self.put_str(0, y, rustbox::RB_NORMAL, Color::White, Color::Blue, &line);
+        }
+    }
+
+    // ~ shows the performance counters in the bottom left corner of the
+    // world's part of the screen
+    fn print_perf(&self) {
+        let p = &self.perf;
+        let mut text = String::from("Performance (P: close)\n");
+        let _ = writeln!(text, "{} gens / {} cells evaluated", p.generations, p.cells_evaluated);
+        let _ = writeln!(text, "{} changes / peak {}", p.changes, p.peak_changes);
+        let _ = writeln!(text, "{} full / {} partial redraws", p.full_redraws, p.incremental_updates);
+        let _ = writeln!(text, "{} prints / {} bytes written", p.prints(), p.bytes_written);
+        let _ = write!(text, "step {}ms / render {}ms", p.step_ns / 1_000_000, p.render_ns / 1_000_000);
+        let lines: Vec<&str> = text.lines().collect();
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        let y = self.world_lines().saturating_sub(lines.len());
+        for (i, line) in lines.iter().take(self.world_lines()).enumerate() {
+            let line = format!("{:1$}", line, width);
+            let line: String = line.chars().take(self.frame.width).collect();
+            self.put_str(0, y + i, rustbox::RB_NORMAL, Color::White, Color::Blue, &line);
+        }
+    }
+
+    // ~ shows the given title and line of text framed in the middle of
+    // the world's part of the screen
+    fn print_title_card(&self, title: &str, text: &str) {
+        let inner = cmp::max(title.chars().count(), text.chars().count());
+        let lines = [format!("+{}+", "-".repeat(inner + 2)),
+                     format!("| {:^1$} |", title, inner),
+                     format!("| {:1$} |", text, inner),
+                     format!("+{}+", "-".repeat(inner + 2))];
+        let x = self.frame.width.saturating_sub(inner + 4) / 2;
+        let y = self.world_lines().saturating_sub(lines.len()) / 2;
+        for (i, line) in lines.iter().take(self.world_lines()).enumerate() {
+            let line: String = line.chars().take(self.frame.width).collect();
+            self.put_str(x, y + i, rustbox::RB_BOLD, Color::White, Color::Blue, &line);
+        }
+    }
+
+    // ~ the background marking labeled cells
+    fn label_color(&self, x: usize, y: usize) -> Option<Color> {
+        self.labels.get(x, y).map(|_| LABEL_COLOR)
+    }
+
+    // ~ prints a cell of the world in the colors of the theme
+    fn print_cell(&self, x: usize, y: usize, alive: bool) {
+        if let Some((col, line)) = self.screen_map().world_to_screen(x, y) {
+            let look = self.ghost_look(x, y, alive).or_else(|| self.reference_look(x, y, alive));
+            let (c, fg, bg) = look.unwrap_or_else(|| {
+                let (fg, bg) = self.theme.cell(alive, None, self.label_color(x, y));
+                (self.get_drawing_char(alive), fg, bg)
+            });
+            self.put_char(col, line, rustbox::RB_NORMAL, fg, bg, c);
+        }
+    }
+
+    // ~ the placement of the world's cells on the screen; the world of
+    // the sandbox is framed in the middle of it
+    fn screen_map(&self) -> ScreenMap {
+        let map = ScreenMap::new(self.frame.width, self.frame.height, self.show_status);
+        match self.sandbox {
+            None => ScreenMap { viewport: self.viewport, ..map },
+            Some(ref sb) => {
+                let (width, height) = (sb.region.width + 2, sb.region.height + 2);
+                ScreenMap {
+                    origin: (self.frame.width.saturating_sub(width) / 2,
+                             self.world_lines().saturating_sub(height) / 2),
+                    frame: true,
+                    ..ScreenMap::new(width, height, false)
+                }
+            }
+        }
+    }
+
+    // ~ keeps the viewport within the world, e.g. after it was replaced
+    // by a smaller one
+    fn clamp_viewport(&mut self, world: &World) {
+        let max = (world.width().saturating_sub(self.frame.width),
+                   world.height().saturating_sub(self.world_lines()));
+        self.viewport = (cmp::min(self.viewport.0, max.0), cmp::min(self.viewport.1, max.1));
+    }
+
+    // ~ moves the viewport a step toward centering the object followed,
+    // if any
+    fn move_along(&mut self, world: &World) {
+        if let Some(ref f) = self.follow {
+            let viewport = f.next_viewport(world, self.viewport, (self.frame.width, self.world_lines()));
+            if viewport != self.viewport {
+                self.viewport = viewport;
+                self.damage.mark_full();
+            }
+        }
+    }
+
+    // ~ moves the viewport to the given cell of the world, as far as the
+    // world reaches, and draws it anew
+    fn move_viewport(&mut self, world: &World, (x, y): (usize, usize)) {
+        if self.follow.take().is_some() {
+            self.message = Some("stopped following: the view was moved".to_owned());
+        }
+        self.viewport = (x, y);
+        self.clamp_viewport(world);
+        self.redraw_scene(world, true);
+    }
+
+    // ~ the part of the frame's geometry still on the terminal, which
+    // might have shrunk since the frame began
+    fn printable(&self) -> FrameGeometry {
+        self.frame.within(self.terminal.width(), self.terminal.height())
+    }
+
+    // ~ the terminal's printing, counted (see `perf`) and clipped to the
+    // frame's geometry
+    fn put_str(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) {
+        let len = s.chars().count();
+        let n = self.printable().clip(x, y, len);
+        if n == 0 {
+            return;
+        }
+        let s = if n < len {
+            &s[..s.char_indices().nth(n).unwrap().0]
+        } else {
+            s
+        };
+        self.perf.count_print();
+        self.terminal.print(x, y, sty, fg, bg, s);
+        self.shadow().print(x, y, sty, fg, bg, s);
+    }
+
+    fn put_char(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, c: char) {
+        if self.printable().clip(x, y, 1) == 0 {
+            return;
+        }
+        self.perf.count_print();
+        self.terminal.print_char(x, y, sty, fg, bg, c);
+        self.shadow().print_char(x, y, sty, fg, bg, c);
+    }
+
+    fn put_char_rgb(&self, x: usize, y: usize, sty: Style, fg: Rgb, bg: Rgb, c: char) {
+        if self.printable().clip(x, y, 1) == 0 {
+            return;
+        }
+        self.perf.count_print();
+        self.terminal.print_char_rgb(x, y, sty, fg, bg, c);
+        self.shadow().print_char_rgb(x, y, sty, fg, bg, c);
+    }
+
+    fn clear(&self) {
+        self.terminal.clear();
+        self.shadow().clear();
+    }
+
+    // ~ presents the frame; if the terminal changed its size while the
+    // frame was drawn, the next one is drawn anew as a whole
+    fn flush(&mut self) {
+        self.terminal.present();
+        self.shadow().present();
+        if (self.terminal.width(), self.terminal.height()) != (self.frame.width, self.frame.height) {
+            self.damage.mark_full();
+        }
+    }
+
+    // ~ the mirror of the terminal's buffers, reallocated (blank) if the
+    // terminal changed its size since
+    fn shadow<'a>(&'a self) -> RefMut<'a, ScreenBuffer> {
+        let mut screen = self.screen.borrow_mut();
+        let (width, height) = (self.terminal.width(), self.terminal.height());
+        if (screen.width(), screen.height()) != (width, height) {
+            screen.resize(width, height);
+        }
+        screen
+    }
+
+    // ~ writes the screen as shown to a plain text and an ANSI file named
+    // after the current time in the temporary directory; tells where,
+    // or why it failed
+    fn screenshot(&self) -> Result<String, String> {
+        let stamp = time::now().strftime("%Y%m%d-%H%M%S").map(|t| t.to_string()).unwrap_or_default();
+        let (text, ansi) = {
+            let screen = self.shadow();
+            (screen.to_text(), screen.to_ansi())
+        };
+        let written = clipboard::write_temp(&text, &format!("cgol-screen-{}.txt", stamp))
+                          .and_then(|txt| {
+                              clipboard::write_temp(&ansi, &format!("cgol-screen-{}.ans", stamp))
+                                  .map(|ans| (txt, ans))
+                          });
+        match written {
+            Ok((txt, ans)) => Ok(format!("screen written to {} and {}", txt.display(), ans.display())),
+            Err(e) => Err(format!("screenshot failed: {}", e)),
+        }
+    }
+
+    // ~ writes the bookmarks, macros and slots modified in the session
+    // back to their files, if any
+    fn save_state(&self, opts: &Options) -> Result<(), String> {
+        if let Some(ref f) = opts.bookmarks_file {
+            if self.bookmarks.is_modified() {
+                try!(self.bookmarks.save_to_file(f));
+            }
+        }
+        if let Some(ref f) = self.macros_file {
+            if self.macros.is_modified() {
+                try!(self.macros.save_to_file(f));
+            }
+        }
+        if let Some(ref f) = self.slots_file {
+            if self.slots.is_modified() {
+                try!(self.slots.save_to_file(f));
+            }
+        }
+        Ok(())
+    }
+
+    // ~ writes the screen as shown to the file given by
+    // `--screenshot-on-exit`, if any
+    fn screenshot_on_exit(&self, opts: &Options) -> Result<(), String> {
+        match opts.screenshot_on_exit {
+            Some(ref f) => self.shadow().save(f),
+            None => Ok(()),
+        }
+    }
+
+    fn set_cursor(&self, w: usize, h: usize) {
+        let g = self.printable();
+        self.terminal.set_cursor(cmp::min(w, g.width.saturating_sub(1)) as isize,
+                                 cmp::min(h, g.height.saturating_sub(1)) as isize);
+    }
+
+    // ~ draws the preview of the stamp over the world without touching
+    // it; moving the preview damages the cells beneath (see
+    // `change_stamp`)
+    fn print_stamp(&self, world: &World) {
+        if let Some(ref stamp) = self.stamp {
+            let map = self.screen_map();
+            for (x, y) in stamp.cells_at(self.stamp_at.0, self.stamp_at.1, world) {
+                if let Some((col, line)) = map.world_to_screen(x, y) {
+                    self.put_char(col, line, rustbox::RB_NORMAL, STAMP_COLOR, Color::Default, self.alive_char);
+                }
+            }
+        }
+    }
+
+    // ~ marks the cells covered by the stamp's preview as damaged
+    fn damage_stamp(&mut self, world: &World) {
+        if let Some(ref stamp) = self.stamp {
+            let map = self.screen_map();
+            for (x, y) in stamp.cells_at(self.stamp_at.0, self.stamp_at.1, world) {
+                if let Some((col, line)) = map.world_to_screen(x, y) {
+                    self.damage.add_cell(col, line);
+                }
+            }
+        }
+    }
+
+    // ~ applies `f` to the stamp's preview, e.g. moving or turning it,
+    // and draws anew just the cells it covered before and after
+    fn change_stamp<F: FnOnce(&mut UI<T>)>(&mut self, world: &World, f: F) {
+        self.damage_stamp(world);
+        f(self);
+        self.damage_stamp(world);
+        self.render_damage(world);
+    }
+
+    // ~ marks the cells of the screen showing a different state in the
+    // given world than in the one shown before as damaged; the whole
+    // screen if their sizes differ
+    fn damage_changed(&mut self, before: &World, world: &World) {
+        if (before.width(), before.height()) != (world.width(), world.height()) {
+            self.damage.mark_full();
+            return;
+        }
+        let map = self.screen_map();
+        for line in 0..self.world_lines() {
+            for col in 0..self.frame.width {
+                match map.screen_to_world(col, line) {
+                    Some((w, h)) if w < world.width() && h < world.height() => {
+                        if before.is_alive(w, h) != world.is_alive(w, h) {
+                            self.damage.add_cell(col, line);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // ~ the frame's render pass: draws the damaged parts of the screen,
+    // i.e. the damaged cells (or the cells of the damaged rectangles
+    // unless just cells were damaged) and the overlays they intersect,
+    // and the overlays whose contents change with every generation
+    // (the status line, the generation display, and the chart); the
+    // whole screen if it is damaged as a whole
+    fn render_damage(&mut self, world: &World) {
+        let damage = mem::replace(&mut self.damage, DamageList::new(damage::DEFAULT_CAP));
+        // ~ damage is told in positions of the last frame's layout
+        if self.begin_frame() || damage.is_full() {
+            self.redraw_scene(world, true);
+            return;
+        }
+        let started = self.perf.start();
+        self.perf.incremental_updates += 1;
+        let map = self.screen_map();
+        let print_at = |ui: &UI<T>, col, line| {
+            match map.screen_to_world(col, line) {
+                Some((w, h)) if w < world.width() && h < world.height() => {
+                    ui.print_cell(w, h, world.is_alive(w, h))
+                }
+                _ => {}
+            }
+        };
+        match damage.cells() {
+            Some(cells) => {
+                // ~ row by row, each cell once
+                let mut cells = cells.to_vec();
+                cells.sort_by_key(|&(col, line)| (line, col));
+                cells.dedup();
+                for (col, line) in cells {
+                    print_at(self, col, line);
+                }
+            }
+            None => {
+                for &(x, y, width, height) in damage.rects() {
+                    for line in y..y + height {
+                        for col in x..x + width {
+                            print_at(self, col, line);
+                        }
+                    }
+                }
+            }
+        }
+        if self.density_color {
+            // ~ the changes affect the counts of the surrounding cells
+            for &(x, y, width, height) in damage.rects() {
+                let (x0, y0) = (x.saturating_sub(1), y.saturating_sub(1));
+                self.print_density_colors_in(world, (x0, y0, x + width + 1 - x0, y + height + 1 - y0));
+            }
+        }
+        self.print_stamp(world);
+        self.update_status(world);
+        // ~ the changed cells might have been drawn over the overlays
+        self.print_hud(world);
+        if self.show_minimap {
+            self.print_minimap(world, Some(&damage));
+        }
+        if self.show_bookmarks {
+            self.print_bookmarks(Some(&damage));
+        }
+        self.record_population(world);
+        if self.show_plot {
+            self.print_plot();
+        }
+        if self.show_perf {
+            self.print_perf();
+        }
+        self.flush();
+        self.perf.add_render_time(started);
+    }
+
+    fn redraw_scene(&mut self, world: &World, clear: bool) {
+        let started = self.perf.start();
+        self.perf.full_redraws += 1;
+        self.begin_frame();
+        self.clamp_viewport(world);
+        // ~ anything damaged is drawn along with the rest
+        self.damage = DamageList::new(damage::DEFAULT_CAP);
+        if clear {
+            self.clear();
+            self.minimap_cache = None;
+            self.counts_cache = None;
+        }
+        self.print_world(world);
+        self.print_stamp(world);
+        if self.show_minimap {
+            self.print_minimap(world, None);
+        }
+        if self.show_bookmarks {
+            self.print_bookmarks(None);
+        }
+        self.record_population(world);
+        if self.show_plot {
+            self.print_plot();
+        }
+        if self.show_perf {
+            self.print_perf();
+        }
+        self.place_cursor();
+        self.flush();
+        self.perf.add_render_time(started);
+    }
+}
+
+pub struct Options {
+    /// the seed to generate the initial random world from
+    pub seed: Option<u64>,
+    /// the distribution of the live cells of generated worlds
+    pub random_kind: RandomKind,
+    /// the boundary mode of generated worlds
+    pub boundary: BoundaryMode,
+    pub alive_char: char,
+    pub dead_char: char,
+    /// the colors available for the neighbour count overlay
+    pub color_tier: ColorTier,
+    /// the colors of the world's cells
+    pub theme: Theme,
+    /// whether to show the status line initially
+    pub show_status: bool,
+    /// whether to color live cells by their number of live neighbours
+    /// initially
+    pub density_color: bool,
+    /// grows the (bounded) world as its live cells approach the edges
+    pub auto_expand: Option<AutoExpand>,
+    /// the neighbourhood of generated worlds
+    pub neighbourhood: Vec<(isize, isize)>,
+    /// the rule of any world shown (until changed with the rule
+    /// editor)
+    pub rule: Rule,
+    /// the order in which the cells of any world shown are updated
+    pub update_mode: UpdateMode,
+    /// the region to count passing objects (e.g. gliders) in
+    pub count_region: Option<Rect>,
+    /// the file to read the save slots from at startup and to write
+    /// them to on exit
+    pub slots_file: Option<String>,
+    /// the corner of the screen to show the mini-map in
+    pub minimap_corner: Corner,
+    /// the file the initial world was loaded from; its directory is
+    /// the one cycled through with `[` and `]`
+    pub file: Option<String>,
+    /// the directory of a pattern pack to cycle through with `[` and
+    /// `]` instead
+    pub pack: Option<String>,
+    /// the longest line accepted in files in the plaintext format
+    pub max_line_length: usize,
+    /// whether to accept plaintext files without live cells
+    pub allow_empty: bool,
+    /// the file to read the bookmarks of a previous run of the same
+    /// world from and to write them to on exit
+    pub bookmarks_file: Option<String>,
+    /// the shortest and the longest delay (in milliseconds) of the
+    /// adaptive slow motion
+    pub slowmo_bounds: (i64, i64),
+    /// the file to append the statistics of the displayed generation
+    /// to on demand
+    pub statlog_file: Option<String>,
+    /// the corner of the screen to show the large generation display
+    /// in; `None` for the one covering the fewest live cells
+    pub hud_corner: Option<Corner>,
+    /// the file to read the recorded macros from at startup and to
+    /// write them to on exit
+    pub macros_file: Option<String>,
+    /// the pause (in milliseconds) before each key of a replayed macro
+    pub macro_delay_ms: u64,
+    /// a note to show in the status line at startup, e.g. a warning
+    /// about the configuration
+    pub message: Option<String>,
+    /// the address to listen for a remote control at (see `remote`)
+    pub listen: Option<String>,
+    /// whether to refuse the keys changing the world (see `bindings`)
+    pub read_only: bool,
+    /// the interval (in seconds) to log the performance counters at
+    pub perf_log_secs: Option<u64>,
+    /// the policy of removing stable debris (see `reaper`), if any
+    pub reap: Option<reaper::Policy>,
+    /// the ships to seed generated worlds with instead of random cells
+    /// (see `synthesis`), if any
+    pub fleet: Option<(Fleet, usize)>,
+    /// whether to keep the live cells in view (see `autoframe`)
+    pub autoframe: bool,
+    /// the file to write the screen as shown at the end to (see
+    /// `screenshot`), if any
+    pub screenshot_on_exit: Option<String>,
+    /// the rule to run the world under alongside for comparison (see
+    /// `compare`), if any
+    pub compare: Option<Rule>,
+    /// the generations at which to switch the rule (see `schedule`),
+    /// if any
+    pub schedule: Option<RuleSchedule>,
+    /// settle the initial world (see `soup::presettle`) for at most
+    /// that many generations before showing it
+    pub presettle: Option<usize>,
+    /// the pattern to show behind the world for recreating it (see
+    /// `reference`), if any
+    pub reference: Option<World>,
+}
+
+impl Options {
+    // ~ configures a generated world
+    fn apply(&self, world: &mut World) {
+        world.set_boundary(self.boundary);
+        world.set_neighbourhood(self.neighbourhood.clone());
+        world.set_rule(self.rule);
+        world.set_update_mode(self.update_mode);
+    }
+
+    // ~ generates a world from the given seed: random cells, or the
+    // ships of the fleet if configured
+    fn generate(&self, seed: u64, width: usize, height: usize) -> World {
+        match self.fleet {
+            Some((fleet, n)) => fleet.world(n, seed, width, height),
+            None => World::random_seeded_with(seed, width, height, self.random_kind),
+        }
+    }
+}
+
+/// The shortest delay between two generations the animation can be
+/// sped up to with `+`; the unthrottled mode is entered explicitly.
+/// Sub-millisecond delays are never reached: polling for input without
+/// a timeout would starve the input.
+const MIN_DELAY_MS: i64 = 1;
+
+/// The longest delay between two generations the animation can be
+/// slowed down to with `-`.
+const MAX_DELAY_MS: i64 = 60000;
+
+/// The delay between two generations the animation starts with.
+const DEFAULT_DELAY_MS: i64 = 100;
+
+/// The keys to type to allow changes in the read-only mode; long
+/// enough not to be typed by accident.
+const UNLOCK_CHORD: &'static str = "!unlock";
+
+/// The background color marking labeled cells.
+const LABEL_COLOR: Color = Color::Yellow;
+
+/// The color of the live cells of the stamp's preview.
+const STAMP_COLOR: Color = Color::Cyan;
+
+/// The colors of the cells alive under one of the rules compared only
+/// (see `compare`): the world's rule, and the other one.
+const FIRST_RULE_COLOR: Color = Color::Green;
+const SECOND_RULE_COLOR: Color = Color::Magenta;
+
+/// The color of the cells alive in the reference pattern only (see
+/// `reference`); dim on the usual dark background.
+const REFERENCE_COLOR: Color = Color::Blue;
+
+/// The interval (in milliseconds) at which the unthrottled animation
+/// checks for user input.
+const TURBO_INPUT_CHECK_MS: u64 = 20;
+
+/// The interval (in milliseconds) at which the progress of the
+/// unthrottled animation is measured and its display refreshed.
+const PROGRESS_UPDATE_MS: u64 = 250;
+
+/// The glyphs drawn over the world by the UI's overlays (besides the
+/// characters of the cells themselves), along with the overlays
+/// drawing them.  A cell drawn in one of them is easily mistaken for
+/// part of the overlay.
+const OVERLAY_GLYPHS: [(&'static str, &'static str); 5] = [("the mini-map", ".:*#+-|"),
+                                                           ("the neighbour counts", "0123456789"),
+                                                           ("the population chart", "▁▂▃▄▅▆▇█|+-0123456789"),
+                                                           ("the generation display", "█0123456789"),
+                                                           ("the title cards", "+-|")];
+
+/// Describes the ways the given characters for live and dead cells
+/// collide with each other or with the glyphs drawn by the UI's
+/// overlays; empty if they do not.  The overlays never infer a cell's
+/// state from the character on the screen, so collisions only confuse
+/// the eye.
+pub fn glyph_collisions(alive_char: char, dead_char: char) -> Vec<String> {
+    let mut collisions = Vec::new();
+    if alive_char == dead_char {
+        collisions.push(format!("alive and dead char are both '{}'", alive_char));
+    }
+    for &(name, c) in [("alive", alive_char), ("dead", dead_char)].iter() {
+        let overlays: Vec<&str> = OVERLAY_GLYPHS.iter()
+                                                .filter(|&&(_, glyphs)| glyphs.contains(c))
+                                                .map(|&(overlay, _)| overlay)
+                                                .collect();
+        if !overlays.is_empty() {
+            collisions.push(format!("{} char '{}' is also drawn by {}", name, c, overlays.join(", ")));
+        }
+    }
+    collisions
+}
+
+/// The default bounds (in milliseconds) of the delay of the adaptive
+/// slow motion.
+pub const DEFAULT_SLOWMO_BOUNDS: (i64, i64) = (20, 1000);
+
+/// The share of a world's cells changed by a step which slows the
+/// adaptive slow motion down to its longest delay.
+const SLOWMO_FULL_ACTIVITY: f64 = 0.1;
+
+/// The largest number of lines of the population chart.
+const PLOT_HEIGHT: usize = 8;
+
+/// The number of past generations kept for playing backwards.
+const HISTORY_LEN: usize = 256;
+
+/// The number of viewports which can be marked to return to; each is
+/// selected by a single digit.
+const VIEW_MARKS: usize = 9;
+
+/// The longest the event loop waits for input while listening for a
+/// remote control before polling it again.
+const REMOTE_POLL_MS: i64 = 50;
+
+pub fn run(ws: Option<Workspace>, opts: &Options) -> Result<(), String> {
+    // ~ before taking over the screen to report failures plainly
+    let remote = match opts.listen {
+        Some(ref addr) => Some(try!(Remote::listen(addr).map_err(|e| format!("error: {}", e)))),
+        None => None,
+    };
+    let t = try!(RustBox::init(InitOptions { buffer_stderr: true, ..Default::default() })
+                     .map_err(|e| format!("error: {}", Error::from(e))));
+    let mut ui = UI::init(t, opts);
+    ui.remote = remote;
+    let world = ws.map(|ws| {
+        ui.labels = ws.labels;
+        ws.world
+    });
+    try!(run_(&mut ui, world, opts, advance_one_step).map_err(|e| format!("error: {}", e)));
+    // ~ a failed screenshot is reported only after the session's state
+    // is saved, not to lose it
+    let screenshot = ui.screenshot_on_exit(opts);
+    try!(ui.save_state(opts));
+    screenshot
+}
+
+/// Runs the demo: cycles through the bundled patterns (see
+/// `patterns::DEMO`) until a key is pressed.
+pub fn run_demo(opts: &Options) -> Result<(), String> {
+    let t = try!(RustBox::init(InitOptions { buffer_stderr: true, ..Default::default() })
+                     .map_err(|e| format!("error: {}", Error::from(e))));
+    let mut ui = UI::init(t, opts);
+    try!(patterns::check_fits(patterns::DEMO, ui.frame.width, ui.world_lines())
+             .map_err(|e| format!("error: {}", e)));
+    let start = Instant::now();
+    let clock = || {
+        let d = start.elapsed();
+        d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000
+    };
+    try!(run_demo_(&mut ui, opts, patterns::DEMO, clock).map_err(|e| format!("error: {}", e)));
+    ui.screenshot_on_exit(opts)
+}
+
+// ~ runs the given phases of the demo over and over until a key is
+// pressed; `clock` tells the time in milliseconds
+fn run_demo_<T, C>(ui: &mut UI<T>, opts: &Options, phases: &[DemoPhase], clock: C) -> Result<(), Error>
+    where T: Terminal,
+          C: Fn() -> u64
+{
+    let mut seq = Sequencer::new(phases);
+    let mut world = World::empty(ui.frame.width, ui.frame.height);
+    let mut wait = Duration::nanoseconds(0);
+    // ~ whether the current phase's title card is shown
+    let mut card = false;
+    loop {
+        match try!(ui.terminal.peek_event(wait)) {
+            Event::KeyEvent(_) => break,
+            Event::ResizeEvent(..) => {
+                // ~ a world exceeding the screen (see `DemoWorld::Gun`)
+                // is kept as is rather than wrapped onto it
+                let (w, h) = (ui.terminal.width(), ui.terminal.height());
+                world.expand_to(cmp::max(world.width(), w), cmp::max(world.height(), h));
+                ui.redraw_scene(&world, true);
+                if card {
+                    ui.print_title_card(seq.phase().name, seq.phase().description);
+                    ui.flush();
+                }
+            }
+            _ => {}
+        }
+        match seq.next(clock(), &world) {
+            DemoCue::Start(i) => {
+                // ~ a fresh start; no counts or notes of the previous
+                // phase apply to it
+                let phase = &phases[i];
+                world = phase.world(&mut thread_rng(), ui.frame.width, ui.frame.height);
+                opts.apply(&mut world);
+                ui.labels = Labels::new();
+                ui.reset_counter(opts);
+                ui.period = None;
+                ui.message = None;
+                ui.demo = Some(phase.name);
+                ui.peak = world.alive();
+                ui.delay = Some(Duration::milliseconds(phase.delay_ms as i64));
+                // ~ the mini-map tells where the parts off the screen
+                // are at
+                ui.viewport = (0, 0);
+                ui.minimap_cache = None;
+                ui.show_minimap = world.width() > ui.frame.width || world.height() > ui.frame.height;
+                ui.redraw_scene(&world, true);
+                ui.print_title_card(phase.name, phase.description);
+                ui.flush();
+                card = true;
+                wait = Duration::nanoseconds(0);
+            }
+            DemoCue::Title(ms) => wait = Duration::milliseconds(ms as i64),
+            DemoCue::Step => {
+                if card {
+                    card = false;
+                    ui.redraw_scene(&world, true);
+                }
+                advance_one_step(ui, &mut world);
+                wait = Duration::milliseconds(seq.phase().delay_ms as i64);
+            }
+        }
+    }
+    Ok(())
+}
+
+// ~ runs the event loop until the user quits; returns the final world.
+// `step` advances the world by one generation and updates the screen.
+fn run_<T, S>(ui: &mut UI<T>, world: Option<World>, opts: &Options, step: S) -> Result<World, Error>
+    where T: Terminal,
+          S: Fn(&mut UI<T>, &mut World)
+{
+    // ~ if no world was explicitely specified, generated one
+    let mut world = match (world, opts.seed) {
+        (Some(w), _) => w,
+        (None, seed) => {
+            // ~ a seed of its own to tell the remote control
+            let seed = seed.unwrap_or_else(|| thread_rng().gen());
+            let mut w = opts.generate(seed, ui.frame.width, ui.frame.height);
+            opts.apply(&mut w);
+            ui.seed = Some(seed);
+            w
+        }
+    };
+    ui.init_bookmarks(&world, opts);
+    // ~ expand the give world to the size of the ui and draw the world;
+    // settled as it is shown, i.e. on the screen's torus
+    {
+        ui.fit_to_screen(&mut world);
+        if let Some(max) = opts.presettle {
+            let note = soup::describe_presettle(soup::presettle(&mut world, max), max);
+            ui.message = Some(match ui.message.take() {
+                Some(m) => format!("{}; {}", m, note),
+                None => note,
+            });
+        }
+        ui.peak = world.alive();
+        ui.redraw_scene(&world, false);
+    }
+
+    let mut maxdelay = Duration::milliseconds(DEFAULT_DELAY_MS);
+    let mut nextdelay = maxdelay;
+    let mut animate = false;
+    // ~ whether to animate as fast as possible; input is then checked
+    // only every TURBO_INPUT_CHECK_MS
+    let mut turbo = false;
+    let mut last_input_check = ui.terminal.now();
+    // ~ the most recent generations preceding the displayed one
+    let mut history = VecDeque::with_capacity(HISTORY_LEN);
+    // ~ whether the animation plays backwards through the history
+    let mut reverse = false;
+    // ~ the first key of a slot chord ('m' to save, '\'' to load) or a
+    // view chord ('v' to mark, 'V' to return) waiting for the number
+    let mut chord = None;
+    // ~ the world as loaded or generated; restarted from by the rule
+    // editor
+    let mut initial = world.clone();
+    ui.initial_labels = ui.labels.clone();
+
+    // ~ replaces the displayed world, e.g. by a loaded one, and its
+    // labels; none unless given
+    macro_rules! replace_world {
+        ($w:expr) => {
+            replace_world!($w, Labels::new())
+        };
+        ($w:expr, $labels:expr) => {{
+            world = $w;
+            ui.labels = $labels;
+            // ~ settings of the session rather than of the world
+            world.set_rule(ui.rule);
+            world.set_update_mode(opts.update_mode);
+            ui.fit_to_screen(&mut world);
+            initial = world.clone();
+            ui.initial_labels = ui.labels.clone();
+            history.clear();
+            reverse = false;
+            ui.reset_counter(opts);
+            ui.period = None;
+            ui.rewinding = None;
+            ui.peak = world.alive();
+            ui.seed = None;
+            ui.follow = None;
+            ui.view_marks = [None; VIEW_MARKS];
+        }}
+    }
+
+    // ~ adapts the world to the size of the screen and redraws it; the
+    // history's generations no longer fit a world fit anew
+    macro_rules! refit_screen {
+        () => {{
+            let size = (world.width(), world.height());
+            ui.expand_to_screen(&mut world);
+            if (world.width(), world.height()) != size {
+                history.clear();
+            }
+            ui.redraw_scene(&world, true);
+        }}
+    }
+
+    macro_rules! step_forward {
+        () => {{
+            if history.len() == HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(world.clone());
+            step(ui, &mut world);
+        }}
+    }
+
+    // ~ start the event loop
+    loop {
+        if let Some((every, ref mut logged)) = ui.perf_log {
+            if logged.elapsed() >= every {
+                info!("perf: {}", ui.perf);
+                *logged = Instant::now();
+            }
+        }
+        // ~ whatever stopped the unthrottled animation ended its progress
+        if ui.fast_forward.is_some() && !(animate && turbo) {
+            ui.fast_forward = None;
+            ui.update_status(&world);
+            ui.flush();
+        }
+        // ~ a remote command is taken once the keys of the previous one
+        // were dispatched, which is when that one is answered
+        while ui.remote.is_some() && ui.pending.is_empty() {
+            let status = remote::Status {
+                generation: world.generation(),
+                alive: world.alive(),
+                paused: !animate,
+                rule: ui.rule,
+                seed: ui.seed,
+                delay_ms: ui.delay.map(|d| d.num_milliseconds()),
+            };
+            let cmd = match ui.remote.as_mut().unwrap().poll(status) {
+                Some(cmd) => cmd,
+                None => break,
+            };
+            match cmd {
+                Command::Reseed { seed, density } => {
+                    ui.generate = Some((Some(seed.unwrap_or_else(|| thread_rng().gen())), density));
+                }
+                // ~ stamped onto an empty world
+                Command::LoadPattern(_) => ui.generate = Some((None, None)),
+                _ => {}
+            }
+            let keys = remote_keys(&cmd, animate, turbo, maxdelay);
+            if !keys.is_empty() {
+                // ~ close the rule editor, the stamp preview and the
+                // prompt, and cancel any chord, lest they take the keys;
+                // with nothing open, `Esc` would leave the sandbox or stop
+                // skipping ahead instead
+                let open = ui.rule_editor.iter().count() + ui.stamp.iter().count() + ui.prompt.iter().count() +
+                           chord.iter().count();
+                ui.pending.extend(iter::repeat(Key::Esc).take(open).chain(keys).map(Some));
+            }
+        }
+        // ~ the keys of macros being replayed (or of remote commands)
+        // come first
+        let replayed = ui.next_replayed_key();
+        if replayed.is_some() && !ui.playing.is_empty() && ui.macro_delay > StdDuration::from_millis(0) {
+            thread::sleep(ui.macro_delay);
+        }
+        // ~ while listening for a remote control, the animation's delay
+        // is waited for in parts to poll it in between
+        let poll = Duration::milliseconds(REMOTE_POLL_MS);
+        let cut_short = ui.remote.is_some() && replayed.is_none() && animate && !turbo && nextdelay > poll;
+        let e = try!(if let Some(key) = replayed {
+            Ok(Event::KeyEvent(Some(key)))
+        } else if animate && turbo {
+            if ui.terminal.now() - last_input_check >= StdDuration::from_millis(TURBO_INPUT_CHECK_MS) {
+                last_input_check = ui.terminal.now();
+                ui.terminal.peek_event(Duration::nanoseconds(0))
+            } else {
+                Ok(Event::NoEvent)
+            }
+        } else if cut_short || (!animate && ui.remote.is_some()) {
+            ui.terminal.peek_event(poll)
+        } else if animate {
+            ui.terminal.peek_event(nextdelay)
+        } else {
+            ui.terminal.poll_event()
+        });
         match e {
-            Event::NoEvent => {
-                // ~ advance generation
-                advance_one_step(&mut ui, &mut world);
+            Event::NoEvent if cut_short => nextdelay = nextdelay - poll,
+            Event::NoEvent if !animate => {}
+            Event::NoEvent if reverse => {
+                // ~ go back one generation; pause once the history is
+                // exhausted
+                match history.pop_back() {
+                    Some(w) => {
+                        let before = mem::replace(&mut world, w);
+                        ui.damage_changed(&before, &world);
+                    }
+                    None => {
+                        reverse = false;
+                        animate = false;
+                    }
+                }
+                ui.period = None;
+                ui.rewinding = if reverse { Some(history.len()) } else { None };
+                ui.render_damage(&world);
                 nextdelay = maxdelay;
             }
+            Event::NoEvent if ui.fast_forward.as_ref().map_or(false, |ff| ff.target.is_some()) => {
+                // ~ skip ahead to the target generation without drawing
+                // the ones in between, checking for input as often as
+                // the unthrottled animation
+                let target = ui.fast_forward.as_ref().unwrap().target.unwrap();
+                let deadline = ui.terminal.now() + StdDuration::from_millis(TURBO_INPUT_CHECK_MS);
+                let (from, started) = (world.generation(), ui.perf.start());
+                let mut lost = None;
+                let shifted = {
+                    let (peak, counter, follow) = (&mut ui.peak, &mut ui.counter, &mut ui.follow);
+                    let terminal = &ui.terminal;
+                    let auto_expand = ui.auto_expand.as_ref();
+                    let schedule = if ui.sandbox.is_none() { ui.schedule.as_ref() } else { None };
+                    simulation::skip_while(&mut world, auto_expand, schedule, |w, (dx, dy)| {
+                        *peak = cmp::max(*peak, w.alive());
+                        if let Some(ref mut c) = *counter {
+                            c.observe(w);
+                        }
+                        // ~ the object moved along with the cells if the
+                        // world grew to the left or top
+                        if let Some(ref mut f) = *follow {
+                            f.translate(dx, dy);
+                        }
+                        if let Some(Err(e)) = follow.as_mut().map(|f| f.track(w)) {
+                            *follow = None;
+                            lost = Some(e);
+                        }
+                        w.generation() < target && !w.is_empty() && terminal.now() < deadline
+                    })
+                };
+                ui.perf.add_skipped(world.generation() - from, world.width() * world.height());
+                ui.perf.add_step_time(started);
+                ui.translate(shifted.0, shifted.1);
+                // ~ the generations skipped are not kept
+                history.clear();
+                ui.period = None;
+                ui.message = None;
+                if let Some(lost) = lost {
+                    ui.set_warning(lost_message(lost));
+                }
+                ui.move_along(&world);
+                if world.generation() >= target || world.is_empty() {
+                    let ff = ui.fast_forward.take().unwrap();
+                    animate = false;
+                    turbo = false;
+                    ui.delay = Some(maxdelay);
+                    ui.message = Some(format!("reached gen {} in {}",
+                                              world.generation(),
+                                              progress::format_duration(ff.started.elapsed())));
+                    ui.redraw_scene(&world, true);
+                } else {
+                    ui.fast_forward.as_mut().unwrap().sample(world.generation());
+                    ui.update_status(&world);
+                    ui.flush();
+                }
+            }
+            Event::NoEvent => {
+                // ~ advance generation; there is nothing to animate
+                // once all cells are dead
+                step_forward!();
+                if let Some(ref mut ff) = ui.fast_forward {
+                    ff.sample(world.generation());
+                }
+                nextdelay = if ui.slowmo {
+                    slowmo_delay(ui.last_changes, world.width() * world.height(), ui.slowmo_bounds)
+                } else {
+                    maxdelay
+                };
+                if world.is_empty() {
+                    animate = false;
+                    turbo = false;
+                    ui.delay = Some(maxdelay);
+                }
+            }
             Event::KeyEvent(Some(key)) => {
+                // ~ record the keys typed (rather than replayed) except
+                // for the one stopping the recording
+                if replayed.is_none() && key != Key::Char('Q') {
+                    if let Some((_, ref mut keys)) = ui.recording {
+                        keys.push(key);
+                    }
+                }
+                // ~ any key but a slot's number (or a register) cancels
+                // a chord
+                let chord_key = chord.take();
+                if arrow_offset(key).is_none() {
+                    ui.placed = None;
+                }
                 match key {
-                    Key::Char('q') => {
-                        // ~ quit
-                        break;
+                    key if ui.prompt.is_some() => {
+                        let outcome = ui.prompt.as_mut().unwrap().1.key(key);
+                        match outcome {
+                            Outcome::Editing => ui.message = None,
+                            Outcome::Cancelled => {
+                                ui.prompt = None;
+                                ui.message = None;
+                            }
+                            Outcome::Submitted => {
+                                let (dialog, name) = {
+                                    let &(ref dialog, ref prompt) = ui.prompt.as_ref().unwrap();
+                                    (dialog.clone(), prompt.text())
+                                };
+                                // ~ a failure leaves the prompt open with
+                                // the name to correct
+                                match dialog {
+                                    Dialog::Goto => {
+                                        match name.trim().parse() {
+                                            Ok(target) if target > world.generation() => {
+                                                // ~ skipping ahead up to
+                                                // the generation
+                                                ui.prompt = None;
+                                                animate = true;
+                                                turbo = true;
+                                                ui.delay = None;
+                                                last_input_check = Instant::now();
+                                                nextdelay = Duration::nanoseconds(0);
+                                                ui.fast_forward = Some(FastForward::new(world.generation(),
+                                                                                        Some(target)));
+                                            }
+                                            Ok(target) if history.iter().any(|w| w.generation() == target) => {
+                                                // ~ back through the history
+                                                while world.generation() != target {
+                                                    world = history.pop_back().unwrap();
+                                                }
+                                                ui.prompt = None;
+                                                reverse = false;
+                                                ui.rewinding = None;
+                                                ui.period = None;
+                                                ui.message = Some(format!("back at gen {}", target));
+                                                ui.redraw_scene(&world, true);
+                                            }
+                                            Ok(target) if target == world.generation() => {
+                                                ui.message = Some(format!("already at gen {}", target))
+                                            }
+                                            Ok(target) => {
+                                                ui.set_warning(format!("gen {} is no longer in the history",
+                                                                       target))
+                                            }
+                                            Err(_) => ui.set_warning(format!("not a generation: {}", name)),
+                                        }
+                                    }
+                                    Dialog::Sandbox => {
+                                        match ui.extract_sandbox(&world, &name) {
+                                            Ok((region, w)) => {
+                                                ui.prompt = None;
+                                                ui.enter_sandbox(region,
+                                                                 w,
+                                                                 &mut world,
+                                                                 &mut history,
+                                                                 &mut initial);
+                                                reverse = false;
+                                            }
+                                            Err(e) => ui.set_warning(e),
+                                        }
+                                    }
+                                    _ if name.is_empty() => ui.set_warning("no file name".to_owned()),
+                                    Dialog::SaveAs { ref overwrite }
+                                        if overwrite.as_ref() != Some(&name) && Path::new(&name).exists() => {
+                                        ui.message = Some(format!("{} exists; Enter to overwrite", name));
+                                        if let Some((ref mut dialog, _)) = ui.prompt {
+                                            *dialog = Dialog::SaveAs { overwrite: Some(name) };
+                                        }
+                                    }
+                                    Dialog::SaveAs { .. } => {
+                                        let ws = Workspace {
+                                            world: world.clone(),
+                                            labels: ui.labels.clone(),
+                                        };
+                                        let comment = format!("generation {}", world.generation());
+                                        match parser::export(&name, &ws, &[&comment]) {
+                                            Ok(()) => {
+                                                ui.prompt = None;
+                                                ui.message = Some(format!("saved gen {} to {}",
+                                                                          world.generation(),
+                                                                          name));
+                                            }
+                                            Err(e) => ui.set_warning(e),
+                                        }
+                                    }
+                                    Dialog::Load => {
+                                        match ui.load_file(&name, opts) {
+                                            Ok(ws) => {
+                                                ui.prompt = None;
+                                                ui.restart_bookmarks(&ws.world, opts);
+                                                replace_world!(ws.world, ws.labels);
+                                                ui.message = Some(format!("loaded {}", name));
+                                            }
+                                            Err(e) => ui.set_warning(format!("{}: {}", name, e)),
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if ui.prompt.is_none() {
+                            // ~ the world might have changed (or its
+                            // last row been covered by the prompt)
+                            ui.redraw_scene(&world, true);
+                        } else {
+                            ui.update_status(&world);
+                            ui.place_cursor();
+                            ui.flush();
+                        }
                     }
-                    Key::Char('r') => {
-                        // ~ regenerate (random) world
+                    key if ui.unlock.is_some() => {
+                        // ~ a key not continuing the chord cancels it and
+                        // is dropped
+                        let n = ui.unlock.take().unwrap();
+                        match key {
+                            Key::Char(c) if UNLOCK_CHORD[n..].starts_with(c) => {
+                                if n + 1 == UNLOCK_CHORD.len() {
+                                    ui.read_only = false;
+                                    ui.message = Some("changes allowed".to_owned());
+                                } else {
+                                    ui.unlock = Some(n + 1);
+                                }
+                            }
+                            _ => ui.message = None,
+                        }
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('!') if ui.read_only => {
+                        ui.unlock = Some(1);
+                        ui.message = Some(format!("type {} to allow changes", UNLOCK_CHORD));
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    key if chord_key.is_none() && ui.refused(key).is_some() => {
+                        let action = ui.refused(key).unwrap().action;
+                        ui.message = Some(format!("read-only mode: {} blocked", action));
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    key if ui.sandbox.is_some() && !sandbox_key(key) => {
+                        ui.message = Some("not in the sandbox: Enter pastes back, Esc discards".to_owned());
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Enter | Key::Esc if ui.sandbox.is_some() => {
+                        // ~ paste the sandbox's region back into the
+                        // world or discard it
+                        ui.leave_sandbox(key == Key::Enter, &mut world, &mut history, &mut initial);
                         animate = false;
-                        nextdelay = Duration::nanoseconds(0);
-                        world = World::random(&mut thread_rng(), ui.width(), ui.height());
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        reverse = false;
                         ui.redraw_scene(&world, true);
                     }
-                    Key::Char('s') => {
-                        // ~ advance generation
-                        advance_one_step(&mut ui, &mut world);
+                    Key::Char(c) if ui.rule_editor.is_some() && c.to_digit(10).map_or(false, |n| n <= 8) => {
+                        // ~ toggle the count in the rule and watch the
+                        // initial world evolve under the new rule
+                        let n = c.to_digit(10).unwrap();
+                        if ui.rule_editor == Some(true) {
+                            ui.rule.survival ^= 1 << n;
+                        } else {
+                            ui.rule.birth ^= 1 << n;
+                        }
+                        replace_world!(initial.clone(), ui.initial_labels.clone());
+                        ui.message = None;
+                        ui.redraw_scene(&world, true);
                     }
-                    Key::Char('-') => {
-                        maxdelay = maxdelay * 2;
-                        nextdelay = maxdelay;
+                    Key::Tab if ui.rule_editor.is_some() => {
+                        // ~ switch between the birth and survival counts
+                        ui.rule_editor = ui.rule_editor.map(|survival| !survival);
+                        ui.update_status(&world);
+                        ui.flush();
                     }
-                    Key::Char('+') => {
-                        if maxdelay.num_milliseconds() > 0 {
-                            maxdelay = maxdelay / 2;
-                            nextdelay = maxdelay;
+                    Key::Enter | Key::Esc | Key::Char('e') if ui.rule_editor.is_some() => {
+                        ui.rule_editor = None;
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Up | Key::Down | Key::Left | Key::Right if ui.stamp.is_some() => {
+                        let (dx, dy) = arrow_offset(key).unwrap();
+                        let (x, y) = ui.stamp_at;
+                        let at = (nudge(x, dx, world.width()), nudge(y, dy, world.height()));
+                        ui.change_stamp(&world, |ui| ui.stamp_at = at);
+                    }
+                    Key::Up | Key::Down | Key::Left | Key::Right if ui.placed.is_some() => {
+                        // ~ move the cells just stamped by a cell; they
+                        // cross the edges like moving cells do
+                        let (dx, dy) = arrow_offset(key).unwrap();
+                        let (name, cells) = ui.placed.take().unwrap();
+                        let before = world.clone();
+                        let (moved, dropped) = world.shift_cells(&cells, dx, dy);
+                        ui.message = Some(match dropped {
+                            0 => format!("nudged {}", name),
+                            n => format!("nudged {}; {} cells dropped", name, n),
+                        });
+                        if !moved.is_empty() {
+                            ui.placed = Some((name, moved));
                         }
+                        ui.damage_changed(&before, &world);
+                        ui.render_damage(&world);
+                    }
+                    Key::Up | Key::Down | Key::Left | Key::Right => {
+                        // ~ pan a world larger than the screen by a
+                        // quarter of the screen
+                        let (vx, vy) = ui.viewport;
+                        let (dx, dy) = (cmp::max(1, ui.frame.width / 4), cmp::max(1, ui.world_lines() / 4));
+                        let at = match key {
+                            Key::Up => (vx, vy.saturating_sub(dy)),
+                            Key::Down => (vx, vy + dy),
+                            Key::Left => (vx.saturating_sub(dx), vy),
+                            _ => (vx + dx, vy),
+                        };
+                        ui.move_viewport(&world, at);
+                    }
+                    Key::Tab if ui.stamp.is_some() => {
+                        // ~ the next of the bundled patterns
+                        ui.change_stamp(&world, |ui| {
+                            let next = ui.stamp.as_ref().unwrap().index() + 1;
+                            ui.stamp = Some(Stamp::bundled(next));
+                        });
+                    }
+                    Key::Char('r') if ui.stamp.is_some() => {
+                        ui.change_stamp(&world, |ui| ui.stamp.as_mut().unwrap().rotate());
+                    }
+                    Key::Char('f') if ui.stamp.is_some() => {
+                        ui.change_stamp(&world, |ui| ui.stamp.as_mut().unwrap().mirror());
+                    }
+                    Key::Enter if ui.stamp.is_some() => {
+                        let stamp = ui.stamp.take().unwrap();
+                        // ~ only those the stamp brings alive; the live
+                        // cells it covers are not part of it
+                        let cells: Vec<_> = stamp.cells_at(ui.stamp_at.0, ui.stamp_at.1, &world)
+                                                 .into_iter()
+                                                 .filter(|&(x, y)| !world.is_alive(x, y))
+                                                 .collect();
+                        let (x, y) = (ui.stamp_at.0 as isize, ui.stamp_at.1 as isize);
+                        let report = world.blit(x, y, stamp.pattern());
+                        ui.placed = Some((stamp.name(), cells));
+                        ui.message = Some(if report.clipped > 0 {
+                            format!("stamped {}; {} cells clipped", stamp.name(), report.clipped)
+                        } else {
+                            format!("stamped {}", stamp.name())
+                        });
+                        // ~ the overlays' caches are stale
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Esc if ui.fast_forward.is_some() => {
+                        // ~ stop fast-forwarding where it got to
+                        animate = false;
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        ui.fast_forward = None;
+                        ui.message = Some(format!("stopped at gen {}", world.generation()));
+                        // ~ skipping ahead did not draw the generations
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Esc if ui.stamp.is_some() => {
+                        // ~ the world was never touched; the cells
+                        // beneath the preview just need to be drawn
+                        ui.change_stamp(&world, |ui| ui.stamp = None);
+                    }
+                    Key::Char('S') => {
+                        // ~ preview a pattern for stamping at the center
+                        // of the visible part of the world; pauses the
+                        // game
+                        animate = false;
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        let stamp = Stamp::bundled(0);
+                        let (vx, vy) = ui.viewport;
+                        let visible = (cmp::min(world.width() - vx, ui.frame.width),
+                                       cmp::min(world.height() - vy, ui.frame.height));
+                        let at = (vx + visible.0.saturating_sub(stamp.width()) / 2,
+                                  vy + visible.1.saturating_sub(stamp.height()) / 2);
+                        ui.change_stamp(&world, |ui| {
+                            ui.stamp = Some(stamp);
+                            ui.stamp_at = at;
+                        });
+                    }
+                    Key::Char('X') => {
+                        // ~ prompt for a region of the world to run on
+                        // its own; half the visible part of the world
+                        // around its center to start with.  pauses the
+                        // game
+                        animate = false;
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        let (vx, vy) = ui.viewport;
+                        let visible = (cmp::min(world.width() - vx, ui.frame.width),
+                                       cmp::min(world.height() - vy, ui.world_lines()));
+                        let (w, h) = (cmp::max(1, visible.0 / 2), cmp::max(1, visible.1 / 2));
+                        let region = format!("{},{},{},{}",
+                                             vx + (visible.0 - w) / 2,
+                                             vy + (visible.1 - h) / 2,
+                                             w,
+                                             h);
+                        ui.open_prompt(&world, Dialog::Sandbox, &region);
+                    }
+                    Key::Char('e') => {
+                        // ~ open the rule editor on the status line, unless
+                        // the schedule decides the rule
+                        if ui.schedule.is_some() {
+                            ui.message = Some("the rule follows the schedule".to_owned());
+                        } else {
+                            ui.rule_editor = Some(false);
+                        }
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char(c) if (chord_key == Some('m') || chord_key == Some('\'')) &&
+                                    slot_number(c).is_some() => {
+                        let n = slot_number(c).unwrap();
+                        if chord_key == Some('m') {
+                            ui.slots.save(n, &world);
+                            ui.message = Some(format!("saved slot {}", n));
+                        } else if let Some(w) = ui.slots.get(n).cloned() {
+                            replace_world!(w);
+                            ui.message = Some(format!("loaded slot {} (gen {})", n, world.generation()));
+                        } else {
+                            ui.message = Some(format!("slot {} is empty", n));
+                        }
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char(c) if (chord_key == Some('v') || chord_key == Some('V')) &&
+                                    view_number(c).is_some() => {
+                        let n = view_number(c).unwrap();
+                        if chord_key == Some('v') {
+                            ui.view_marks[n - 1] = Some(ui.viewport);
+                            let (x, y) = ui.viewport;
+                            ui.message = Some(format!("marked view {} at {},{}", n, x, y));
+                            ui.update_status(&world);
+                            ui.flush();
+                        } else if let Some(at) = ui.view_marks[n - 1] {
+                            ui.message = Some(format!("back to view {}", n));
+                            ui.move_viewport(&world, at);
+                        } else {
+                            ui.message = Some(format!("view {} is not marked", n));
+                            ui.update_status(&world);
+                            ui.flush();
+                        }
+                    }
+                    Key::Char('0') if chord_key == Some('V') => {
+                        // ~ home: the world's top left corner
+                        ui.message = Some("back to the top left corner".to_owned());
+                        ui.move_viewport(&world, (0, 0));
+                    }
+                    Key::Char(c) if chord_key == Some('Q') && macros::is_register(c) => {
+                        ui.recording = Some((c, Vec::new()));
+                        ui.message = None;
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char(c) if chord_key == Some('@') && macros::is_register(c) => {
+                        match ui.replay_macro(c) {
+                            Some(m) => ui.set_warning(m),
+                            None => ui.message = None,
+                        }
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char(c) if ui.show_bookmarks && bookmark_number(c, ui.bookmarks.len()).is_some() => {
+                        // ~ jump back to a bookmark; the history is
+                        // recomputed from there
+                        let i = bookmark_number(c, ui.bookmarks.len()).unwrap() - 1;
+                        let (generation, w) = {
+                            let b = ui.bookmarks.get(i).unwrap();
+                            (b.generation(), b.world())
+                        };
+                        match w {
+                            Ok(mut w) => {
+                                opts.apply(&mut w);
+                                replace_world!(w);
+                                ui.show_bookmarks = false;
+                                ui.message = Some(format!("jumped to gen {}", generation));
+                            }
+                            Err(e) => ui.set_warning(format!("bookmark {}: {}", i + 1, e)),
+                        }
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('b') => {
+                        // ~ bookmark the displayed generation
+                        ui.message = Some(match ui.bookmarks.add(&world) {
+                            None => format!("bookmarked gen {}", world.generation()),
+                            Some(b) => {
+                                format!("bookmarked gen {}; dropped gen {}",
+                                        world.generation(),
+                                        b.generation())
+                            }
+                        });
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('B') => {
+                        // ~ toggle the list of bookmarks
+                        ui.show_bookmarks ^= true;
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('Q') => {
+                        // ~ stop recording, or start it once the
+                        // register is known
+                        match ui.recording.take() {
+                            Some((register, keys)) => {
+                                ui.message = Some(format!("recorded @{} ({} keys)", register, keys.len()));
+                                ui.macros.set(register, keys);
+                                ui.update_status(&world);
+                                ui.flush();
+                            }
+                            None => chord = Some('Q'),
+                        }
+                    }
+                    Key::Char(c @ 'm') | Key::Char(c @ '\'') | Key::Char(c @ '@') | Key::Char(c @ 'v') |
+                    Key::Char(c @ 'V') => {
+                        chord = Some(c);
+                    }
+                    Key::Char(c @ ']') | Key::Char(c @ '[') => {
+                        // ~ load the next/previous pattern of the
+                        // current file's directory
+                        if let Some(ws) = ui.load_next_file(c == ']', opts) {
+                            ui.restart_bookmarks(&ws.world, opts);
+                            replace_world!(ws.world, ws.labels);
+                        }
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('q') => {
+                        // ~ quit
+                        break;
+                    }
+                    Key::Char('r') => {
+                        // ~ regenerate (random) world; as requested by
+                        // the remote control if it did
+                        animate = false;
+                        nextdelay = Duration::nanoseconds(0);
+                        let (seed, density) = ui.generate
+                                                .take()
+                                                .unwrap_or_else(|| (Some(thread_rng().gen()), None));
+                        world = match (seed, density) {
+                            (None, _) => World::empty(ui.frame.width, ui.frame.height),
+                            (Some(seed), Some(d)) => {
+                                World::random_seeded_with_density(seed, ui.frame.width, ui.frame.height, d)
+                            }
+                            (Some(seed), None) => opts.generate(seed, ui.frame.width, ui.frame.height),
+                        };
+                        ui.seed = seed;
+                        ui.labels = Labels::new();
+                        ui.follow = None;
+                        ui.view_marks = [None; VIEW_MARKS];
+                        opts.apply(&mut world);
+                        world.set_rule(ui.rule);
+                        initial = world.clone();
+                        ui.initial_labels = Labels::new();
+                        history.clear();
+                        reverse = false;
+                        ui.reset_counter(opts);
+                        ui.period = None;
+                        ui.rewinding = None;
+                        ui.message = None;
+                        ui.peak = world.alive();
+                        ui.restart_bookmarks(&world, opts);
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('s') => {
+                        // ~ advance generation
+                        step_forward!();
+                    }
+                    Key::Char('R') => {
+                        // ~ toggle playing backwards through the history
+                        reverse ^= true;
+                        animate |= reverse;
+                        nextdelay = Duration::nanoseconds(0);
+                        ui.rewinding = if reverse { Some(history.len()) } else { None };
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('w') => {
+                        // ~ switch between a torus and a bounded grid;
+                        // takes effect with the next generation
+                        if ui.auto_expand.is_some() {
+                            ui.message = Some("auto-expanding worlds stay bounded".to_owned());
+                        } else {
+                            let b = toggled_boundary(world.boundary(), opts.boundary);
+                            world.set_boundary(b);
+                            ui.message = Some(format!("edges: {}", b));
+                        }
+                        // ~ the cached neighbour counts are stale
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('y') => {
+                        // ~ copy the live cells as RLE, e.g. to paste
+                        // them into another Life program
+                        let rle = parser::render_rle(&world);
+                        let name = format!("cgol-gen{}.rle", world.generation());
+                        match clipboard::copy(&rle, &name) {
+                            Ok(Copied::Clipboard(tool)) => {
+                                let m = format!("copied gen {} as RLE ({})", world.generation(), tool);
+                                ui.message = Some(m);
+                            }
+                            Ok(Copied::File(path)) => {
+                                ui.message = Some(format!("no clipboard; RLE written to {}", path.display()));
+                            }
+                            Err(e) => ui.set_warning(format!("copy failed: {}", e)),
+                        }
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('a') => {
+                        // ~ toggle the adaptive slow motion
+                        ui.slowmo ^= true;
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('-') => {
+                        turbo = false;
+                        maxdelay = adjust_delay(maxdelay, false);
+                        nextdelay = maxdelay;
+                        ui.delay = Some(maxdelay);
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('+') => {
+                        maxdelay = adjust_delay(maxdelay, true);
+                        nextdelay = maxdelay;
+                        if !turbo {
+                            ui.delay = Some(maxdelay);
+                            ui.update_status(&world);
+                            ui.flush();
+                        }
+                    }
+                    Key::Char('0') => {
+                        // ~ toggle the unthrottled animation
+                        turbo ^= true;
+                        animate |= turbo;
+                        ui.delay = if turbo { None } else { Some(maxdelay) };
+                        ui.fast_forward = if turbo {
+                            Some(FastForward::new(world.generation(), None))
+                        } else {
+                            None
+                        };
+                        ui.update_status(&world);
+                        ui.flush();
+                        last_input_check = Instant::now();
+                        nextdelay = Duration::nanoseconds(0);
                     }
                     Key::Ctrl('l') => {
                         // ~ redraw screen
-                        ui.expand_to_screen(&mut world);
-                        ui.redraw_scene(&world, true);
+                        refit_screen!();
                     }
                     Key::Char(' ') => {
                         animate ^= true;
+                        turbo &= animate;
+                        ui.delay = if turbo { None } else { Some(maxdelay) };
                         nextdelay = Duration::nanoseconds(0);
+                        if animate && (ui.show_neighbour_counts || ui.show_count_digits) {
+                            ui.redraw_scene(&world, true);
+                        } else {
+                            ui.update_status(&world);
+                            ui.flush();
+                        }
+                    }
+                    Key::Char('n') => {
+                        // ~ toggle the status line; the world's
+                        // dimensions stay untouched, only its last line
+                        // gets covered or uncovered
+                        ui.show_status ^= true;
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('L') if ui.statlog.is_none() => {
+                        // ~ prompt for a pattern file to load; the
+                        // directory of the current one to start with
+                        animate = false;
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        let dir = ui.file
+                                    .as_ref()
+                                    .and_then(|f| f.parent())
+                                    .filter(|d| *d != Path::new(""))
+                                    .map_or(String::new(), |d| format!("{}/", d.display()));
+                        ui.open_prompt(&world, Dialog::Load, &dir);
+                    }
+                    Key::Char('g') => {
+                        // ~ prompt for a generation to fast-forward to
+                        animate = false;
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        ui.open_prompt(&world, Dialog::Goto, "");
+                    }
+                    Key::Char('W') => {
+                        // ~ prompt for a file to save the displayed
+                        // generation to
+                        animate = false;
+                        turbo = false;
+                        ui.delay = Some(maxdelay);
+                        let name = suggested_filename(ui.file.as_ref().map(|f| f.as_path()),
+                                                      world.generation());
+                        ui.open_prompt(&world, Dialog::SaveAs { overwrite: None }, &name);
+                    }
+                    Key::Char('L') => {
+                        // ~ append the displayed generation's statistics
+                        // to the stats log
+                        match ui.log_stats(&world) {
+                            Ok(()) => ui.message = Some(format!("logged gen {}", world.generation())),
+                            Err(e) => ui.set_warning(format!("stats log: {}", e)),
+                        }
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('H') => {
+                        // ~ cycle the large generation display through
+                        // showing the generation, additionally the
+                        // population, and nothing
+                        ui.hud = ui.hud.next();
+                        ui.hud_rect = None;
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('G') => {
+                        // ~ toggle the chart of the population; hiding
+                        // it uncovers the world beneath
+                        ui.show_plot ^= true;
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('p') => {
+                        // ~ measure the period of the current generation;
+                        // shown until the world changes
+                        ui.period = Some(match ui.schedule {
+                            Some(ref s) => s.measure_period(&world, period::DEFAULT_MAX_PERIOD),
+                            None => period::measure(&world, period::DEFAULT_MAX_PERIOD),
+                        });
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('#') => {
+                        // ~ toggle the neighbour count digits
+                        ui.show_count_digits ^= true;
+                        if !ui.show_count_digits {
+                            ui.redraw_scene(&world, true);
+                        }
+                    }
+                    Key::Char('M') => {
+                        // ~ toggle the mini-map; hiding it uncovers the
+                        // world beneath
+                        ui.show_minimap ^= true;
+                        if ui.show_minimap {
+                            ui.print_minimap(&world, None);
+                            ui.flush();
+                        } else {
+                            ui.redraw_scene(&world, true);
+                        }
+                    }
+                    Key::Char('F') => {
+                        // ~ toggle keeping the live cells in view; the
+                        // viewport moves from the next generation on
+                        ui.autoframe = match ui.autoframe {
+                            None => Some(AutoFrame::new()),
+                            Some(_) => None,
+                        };
+                        ui.follow = None;
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('T') => {
+                        // ~ toggle following the object nearest to the
+                        // center of the view; the viewport moves from
+                        // the next generation on
+                        ui.message = Some(match ui.follow.take() {
+                            Some(_) => "stopped following".to_owned(),
+                            None => {
+                                let (vx, vy) = ui.viewport;
+                                let center = (vx + cmp::min(world.width() - vx, ui.frame.width) / 2,
+                                              vy + cmp::min(world.height() - vy, ui.world_lines()) / 2);
+                                ui.follow = Follow::engage(&world, center);
+                                match ui.follow {
+                                    Some(ref f) => {
+                                        ui.autoframe = None;
+                                        let (x, y) = f.center();
+                                        format!("following {} cells at {},{}", f.cells().len(), x, y)
+                                    }
+                                    None => "nothing to follow".to_owned(),
+                                }
+                            }
+                        });
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::F(12) => {
+                        // ~ capture the screen before telling where it
+                        // went
+                        match ui.screenshot() {
+                            Ok(m) => ui.message = Some(m),
+                            Err(e) => ui.set_warning(e),
+                        }
+                        ui.update_status(&world);
+                        ui.flush();
+                    }
+                    Key::Char('d') => {
+                        // ~ toggle coloring live cells by density
+                        ui.density_color ^= true;
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('P') => {
+                        // ~ toggle the performance counters; measuring
+                        // the time while shown
+                        ui.show_perf ^= true;
+                        ui.perf.set_timed(ui.show_perf || ui.perf_log.is_some());
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('o') => {
+                        // ~ toggle the reference overlay along with the
+                        // count of the cells differing
+                        match ui.reference {
+                            Some(ref mut r) => {
+                                r.toggle();
+                            }
+                            None => ui.message = Some("no reference pattern (see --reference)".to_owned()),
+                        }
+                        ui.redraw_scene(&world, true);
+                    }
+                    Key::Char('c') => {
+                        // ~ toggle the neighbour count overlay
+                        ui.show_neighbour_counts ^= true;
+                        if !ui.show_neighbour_counts {
+                            ui.redraw_scene(&world, true);
+                        }
                     }
                     _ => {}
                 }
+                // ~ the neighbour counts are shown only while paused to
+                // avoid flicker
+                if ui.show_neighbour_counts && !animate {
+                    ui.print_neighbour_counts(&world);
+                    ui.flush();
+                }
+                if ui.show_count_digits && !animate {
+                    ui.print_count_digits(&world);
+                    ui.flush();
+                }
+                if !animate && (ui.show_neighbour_counts || ui.show_count_digits) {
+                    ui.hud_rect = None;
+                    ui.print_hud(&world);
+                    ui.flush();
+                }
+                if ui.show_minimap && !animate && (ui.show_neighbour_counts || ui.show_count_digits) {
+                    ui.print_minimap(&world, None);
+                    ui.flush();
+                }
+                if ui.show_bookmarks && !animate && (ui.show_neighbour_counts || ui.show_count_digits) {
+                    ui.print_bookmarks(None);
+                    ui.flush();
+                }
+            }
+            Event::ResizeEvent(..) => {
+                refit_screen!();
             }
             _ => {}
         }
     }
-    Ok(())
+    Ok(world)
+}
+
+// ~ the index of the file following (or preceding) `current` among the
+// sorted `files`, wrapping around at the ends; `current` itself need
+// not be among them (any longer), e.g. if it was deleted
+fn cycle_file(files: &[PathBuf], current: &Path, forward: bool) -> Option<usize> {
+    let n = files.len();
+    if n == 0 {
+        return None;
+    }
+    Some(match (files.binary_search_by(|f| f.as_path().cmp(current)), forward) {
+        (Ok(i), true) => (i + 1) % n,
+        (Err(i), true) => i % n,
+        (Ok(i), false) | (Err(i), false) => (i + n - 1) % n,
+    })
+}
+
+// ~ the number of the view mark selected by the given digit, if any
+fn view_number(c: char) -> Option<usize> {
+    match c.to_digit(10) {
+        Some(n) if n >= 1 && n as usize <= VIEW_MARKS => Some(n as usize),
+        _ => None,
+    }
+}
+
+// ~ the slot selected by the given key, if any
+fn slot_number(c: char) -> Option<usize> {
+    match c.to_digit(10) {
+        Some(n) if n >= 1 && n as usize <= slots::COUNT => Some(n as usize),
+        _ => None,
+    }
+}
+
+// ~ the boundary mode switched to from `current`: a bounded grid from
+// a (twisted) torus, and back to the `initial` torus (or the classic
+// one if the world started out bounded)
+fn toggled_boundary(current: BoundaryMode, initial: BoundaryMode) -> BoundaryMode {
+    match (current, initial) {
+        (BoundaryMode::Bounded, BoundaryMode::Bounded) => BoundaryMode::Wrap,
+        (BoundaryMode::Bounded, b) => b,
+        _ => BoundaryMode::Bounded,
+    }
+}
+
+// ~ halves (`faster`) or doubles the given delay between two
+// generations keeping it within `MIN_DELAY_MS` and `MAX_DELAY_MS`
+fn adjust_delay(delay: Duration, faster: bool) -> Duration {
+    let d = if faster { delay / 2 } else { delay * 2 };
+    cmp::min(cmp::max(d, Duration::milliseconds(MIN_DELAY_MS)),
+             Duration::milliseconds(MAX_DELAY_MS))
+}
+
+// ~ the delay following a step which changed `changes` of `cells`
+// cells; the more changes, the longer the delay within the given
+// bounds (in milliseconds)
+fn slowmo_delay(changes: usize, cells: usize, bounds: (i64, i64)) -> Duration {
+    let (min, max) = bounds;
+    let activity = changes as f64 / cmp::max(cells, 1) as f64;
+    let f = (activity / SLOWMO_FULL_ACTIVITY).min(1.0);
+    Duration::milliseconds(min + ((max - min) as f64 * f).round() as i64)
+}
+
+// ~ whether the given key is taken in the sandbox; the others act on
+// what is set aside (or on the overlays hidden)
+fn sandbox_key(key: Key) -> bool {
+    match key {
+        Key::Char(' ') | Key::Char('s') | Key::Char('R') | Key::Char('+') | Key::Char('-') | Key::Char('w') |
+        Key::Char('n') | Key::Char('p') | Key::Char('y') | Key::Ctrl('l') | Key::F(12) | Key::Enter |
+        Key::Esc => true,
+        _ => false,
+    }
+}
+
+// ~ the (1-based) number of the bookmark selected by the given key
+// among `count` bookmarks, if any
+fn bookmark_number(c: char, count: usize) -> Option<usize> {
+    match c.to_digit(10) {
+        Some(n) if n >= 1 && n as usize <= count => Some(n as usize),
+        _ => None,
+    }
+}
+
+fn digit(n: usize) -> char {
+    (b'0' + n as u8) as char
+}
+
+// ~ the color of live cells with `n` live neighbours
+fn density_color(n: u8) -> Color {
+    match n {
+        0 | 1 => Color::Blue,
+        2 => Color::Cyan,
+        3 => Color::Green,
+        4 => Color::Yellow,
+        5 => Color::Magenta,
+        _ => Color::Red,
+    }
+}
+
+// ~ the background color of dead cells with `n` live neighbours; none
+// of its own without any
+fn neighbour_count_color(n: u8) -> Option<Color> {
+    match n {
+        0 => None,
+        1 => Some(Color::Blue),
+        2 => Some(Color::Cyan),
+        3 => Some(Color::Green),
+        4 => Some(Color::Yellow),
+        5 => Some(Color::Magenta),
+        _ => Some(Color::Red),
+    }
+}
+
+fn advance_one_step<T: Terminal>(ui: &mut UI<T>, world: &mut World) {
+    ui.period = None;
+    ui.message = None;
+    if ui.follow_ghost(world) {
+        ui.damage.mark_full();
+    }
+    // ~ the sandbox's generations count from zero; it keeps the rule
+    // of the world it was taken from
+    let schedule = if ui.sandbox.is_none() { ui.schedule.as_ref() } else { None };
+    if let Some(rule) = schedule.and_then(|s| s.apply(world)) {
+        info!("generation {}: switched to rule {}", world.generation(), rule);
+        ui.message = Some(format!("gen {}: switched to {}", world.generation(), rule));
+    }
+    let started = ui.perf.start();
+    let (summary, growth) = simulation::step(world, ui.auto_expand.as_ref(), &mut ui.changes);
+    if let Some(ref mut r) = ui.reaper {
+        r.observe(world, &mut ui.changes);
+    }
+    ui.perf.add_step(&summary);
+    ui.perf.add_step_time(started);
+    ui.last_changes = summary.births + summary.deaths;
+    ui.peak = cmp::max(ui.peak, world.alive());
+    if let Some(ref mut c) = ui.counter {
+        c.observe(world);
+    }
+    if let Some(ref mut g) = ui.ghost {
+        if ui.sandbox.is_none() {
+            g.step(world, &ui.changes);
+        }
+    }
+    if growth.is_empty() {
+        let map = ui.screen_map();
+        let ghost_changes = match ui.ghost {
+            Some(ref g) if ui.sandbox.is_none() => g.changes(),
+            _ => &[],
+        };
+        for &(w, h, _) in ui.changes.iter().chain(ghost_changes) {
+            if let Some((col, line)) = map.world_to_screen(w, h) {
+                ui.damage.add_cell(col, line);
+            }
+        }
+    } else {
+        // ~ the cells moved if the world grew to the left or top; the
+        // viewport follows them
+        ui.translate(growth.left, growth.top);
+        if let Some(ref mut f) = ui.follow {
+            f.translate(growth.left, growth.top);
+        }
+        ui.viewport = (ui.viewport.0 + growth.left, ui.viewport.1 + growth.top);
+        ui.damage.mark_full();
+    }
+    if ui.sandbox.is_none() {
+        let screen = (ui.frame.width, ui.world_lines());
+        if let Some(ref mut f) = ui.autoframe {
+            let viewport = f.next(world, ui.viewport, screen);
+            if viewport != ui.viewport {
+                ui.viewport = viewport;
+                ui.damage.mark_full();
+            }
+        }
+        if let Some(Err(e)) = ui.follow.as_mut().map(|f| f.track(world)) {
+            ui.follow = None;
+            ui.set_warning(lost_message(e));
+        }
+        ui.move_along(world);
+    }
+    ui.render_damage(world);
+}
+
+// ~ tells why the object followed was lost
+fn lost_message(lost: Lost) -> String {
+    match lost {
+        Lost::Vanished => "stopped following: the object vanished".to_owned(),
+        Lost::Merged => "stopped following: the object merged with another".to_owned(),
+    }
+}
+
+// ~ fits the given line to `width` characters: cuts it short with a
+// trailing `…` if longer, and pads it with spaces if shorter and `pad`
+fn fit_line(line: &mut String, width: usize, pad: bool) {
+    let n = line.chars().count();
+    if n > width {
+        let cut = line.char_indices().nth(width.saturating_sub(1)).map_or(line.len(), |(i, _)| i);
+        line.truncate(cut);
+        if width > 0 {
+            line.push('…');
+        }
+    } else if pad {
+        line.extend(iter::repeat(' ').take(width - n));
+    }
+}
+
+// ~ the name suggested for saving the generation of the given number
+// of the world loaded from the given file, if any: the file's name
+// with the generation appended, in the same format if it can be
+// written
+fn suggested_filename(file: Option<&Path>, generation: usize) -> String {
+    let (stem, ext) = match file {
+        None => ("cgol".to_owned(), "cells"),
+        Some(f) => {
+            let stem = f.with_extension("").to_string_lossy().into_owned();
+            let ext = f.extension().and_then(|e| e.to_str()).unwrap_or("");
+            (stem, parser::EXPORT_EXTENSIONS.iter().cloned().find(|&e| e == ext).unwrap_or("cells"))
+        }
+    };
+    format!("{}-gen{}.{}", stem, generation, ext)
+}
+
+// ~ the keys carrying out the given remote command while the game is
+// animated (`turbo` if unthrottled) or not at the delay `maxdelay`
+fn remote_keys(cmd: &Command, animate: bool, turbo: bool, maxdelay: Duration) -> Vec<Key> {
+    let pause = if animate { vec![Key::Char(' ')] } else { Vec::new() };
+    match *cmd {
+        Command::Pause => pause,
+        Command::Resume if animate => Vec::new(),
+        Command::Resume => vec![Key::Char(' ')],
+        Command::Step(n) => pause.into_iter().chain(iter::repeat(Key::Char('s')).take(n)).collect(),
+        Command::SetSpeed(ms) => {
+            // ~ leave the unthrottled mode first
+            let mut keys = if turbo { vec![Key::Char('0')] } else { Vec::new() };
+            keys.extend(delay_keys(maxdelay, Duration::milliseconds(ms as i64)));
+            keys
+        }
+        Command::Reseed { .. } => vec![Key::Char('r')],
+        Command::LoadPattern(i) => {
+            // ~ an empty world (see `UI::generate`) to stamp the i-th
+            // pattern onto
+            let mut keys = vec![Key::Char('r'), Key::Char('S')];
+            keys.extend(iter::repeat(Key::Tab).take(i));
+            keys.push(Key::Enter);
+            keys
+        }
+        Command::Status => Vec::new(),
+    }
+}
+
+// ~ the presses of `+` or `-` changing the delay `from` to the one
+// closest to `target` which they can reach; the fewest if several are
+fn delay_keys(from: Duration, target: Duration) -> Vec<Key> {
+    let off = |d: Duration| (d - target).num_microseconds().map_or(i64::max_value(), |us| us.abs());
+    let mut best = (off(from), 0, Key::Char('+'));
+    for &(faster, key) in &[(true, '+'), (false, '-')] {
+        let (mut d, mut n) = (from, 0);
+        loop {
+            let next = adjust_delay(d, faster);
+            if next == d {
+                break;
+            }
+            d = next;
+            n += 1;
+            if off(d) < best.0 {
+                best = (off(d), n, Key::Char(key));
+            }
+        }
+    }
+    vec![best.2; best.1]
+}
+
+// ~ moves the coordinate `v` by `d` within `0..len`
+fn nudge(v: usize, d: isize, len: usize) -> usize {
+    cmp::min(cmp::max(v as isize + d, 0) as usize, len.saturating_sub(1))
+}
+
+// ~ the offset of a cell in the direction of the given arrow key, if it
+// is one
+fn arrow_offset(key: Key) -> Option<(isize, isize)> {
+    match key {
+        Key::Up => Some((0, -1)),
+        Key::Down => Some((0, 1)),
+        Key::Left => Some((-1, 0)),
+        Key::Right => Some((1, 0)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod golden;
+
+#[cfg(test)]
+fn test_options() -> Options {
+    Options {
+        seed: None,
+        random_kind: RandomKind::Uniform,
+        boundary: BoundaryMode::Wrap,
+        alive_char: 'O',
+        dead_char: '.',
+        color_tier: ColorTier::Basic,
+        theme: Theme::default(),
+        show_status: true,
+        density_color: false,
+        auto_expand: None,
+        neighbourhood: ::world::NEIGHBOURS.to_vec(),
+        rule: world::CONWAY,
+        update_mode: UpdateMode::Synchronous,
+        count_region: None,
+        slots_file: None,
+        minimap_corner: Corner::TopRight,
+        file: None,
+        pack: None,
+        max_line_length: parser::DEFAULT_MAX_LINE_LENGTH,
+        allow_empty: false,
+        bookmarks_file: None,
+        slowmo_bounds: DEFAULT_SLOWMO_BOUNDS,
+        statlog_file: None,
+        hud_corner: None,
+        macros_file: None,
+        macro_delay_ms: 0,
+        message: None,
+        listen: None,
+        read_only: false,
+        perf_log_secs: None,
+        reap: None,
+        fleet: None,
+        autoframe: false,
+        screenshot_on_exit: None,
+        compare: None,
+        schedule: None,
+        presettle: None,
+        reference: None,
+    }
+}
+
+#[test]
+fn test_toggle_status_world_fills_screen() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let world = World::random_seeded(5, 20, 6);
+    let hash = world.generation_hash();
+
+    let mut ui = UI::init(FakeTerminal::with_keys(20, 6, "nnq"), &opts);
+    let world = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).starts_with("Gen: 0 / Alive: "));
+    assert_eq!(hash, world.generation_hash());
+
+    // ~ on/off/on; the status line is hidden and the world's last
+    // line fully visible
+    let mut ui = UI::init(FakeTerminal::with_keys(20, 6, "nnnq"), &opts);
+    let world = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!(hash, world.generation_hash());
+    for h in 0..6 {
+        ui.render_line(&world, h);
+        assert_eq!(ui.line_buf, ui.terminal.line(h));
+    }
+}
+
+#[test]
+fn test_toggle_status_world_fits() {
+    use terminal::fake::FakeTerminal;
+
+    // ~ a world of just the screen's size needs no expansion; cells in
+    // its last line live in the row the status line covers
+    let opts = test_options();
+    let mut world = World::empty(20, 6);
+    for &(x, y) in &[(0, 0), (19, 0), (3, 5), (4, 5), (19, 5)] {
+        world.set_alive(x, y, true);
+    }
+    let cells = world.live_cells();
+    for (n, keys) in ["q", "nq", "nnq", "nnnq"].iter().enumerate() {
+        let mut ui = UI::init(FakeTerminal::with_keys(20, 6, keys), &opts);
+        let world = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+        assert_eq!((20, 6), (world.width(), world.height()), "after {} toggles", n);
+        assert_eq!(cells, world.live_cells(), "after {} toggles", n);
+        let shown = n % 2 == 0;
+        assert_eq!(shown, ui.terminal.line(5).starts_with("Gen: 0 / Alive: 5"), "after {} toggles", n);
+        for h in 0..(if shown { 5 } else { 6 }) {
+            ui.render_line(&world, h);
+            assert_eq!(ui.line_buf, ui.terminal.line(h));
+        }
+    }
+}
+
+#[test]
+fn test_density_color() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.density_color = true;
+    let mut world = World::empty(7, 7);
+    for x in 1..4 {
+        world.set_alive(x, 2, true);
+    }
+    world.set_alive(5, 5, true);
+    let mut ui = UI::init(FakeTerminal::with_keys(7, 8, "sq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    // ~ the blinker turned vertical; its ends have one neighbour, its
+    // center two
+    assert!(Color::Blue == ui.terminal.cell(2, 1).fg);
+    assert!(Color::Cyan == ui.terminal.cell(2, 2).fg);
+    assert!(Color::Blue == ui.terminal.cell(2, 3).fg);
+    assert!(Color::Default == ui.terminal.cell(5, 5).fg);
+    assert!(Color::Default == ui.terminal.cell(1, 2).fg);
+    // ~ the counts were cached for the displayed generation
+    assert_eq!(Some(1), ui.counts_cache.as_ref().map(|c| c.0));
+
+    // ~ toggled off
+    let mut ui = UI::init(FakeTerminal::with_keys(7, 8, "dq"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert!(Color::Default == ui.terminal.cell(2, 2).fg);
+    assert_eq!('O', ui.terminal.cell(2, 2).ch);
+}
+
+#[test]
+fn test_neighbour_counts_truecolor() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.color_tier = ColorTier::Truecolor;
+    let mut world = World::empty(7, 7);
+    for x in 1..4 {
+        world.set_alive(x, 2, true);
+    }
+    let mut ui = UI::init(FakeTerminal::with_keys(7, 8, "cq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    // ~ dead cells with one, two, and three live neighbours
+    let bgs: Vec<_> = [(0, 2), (1, 1), (2, 1)]
+                          .iter()
+                          .map(|&(x, y)| ui.terminal.cell(x, y).bg_rgb.unwrap())
+                          .collect();
+    assert!(bgs[0] != bgs[1] && bgs[1] != bgs[2] && bgs[0] != bgs[2]);
+    assert_eq!('3', ui.terminal.cell(2, 1).ch);
+    // ~ live cells are not part of the overlay
+    assert_eq!(None, ui.terminal.cell(1, 2).bg_rgb);
+
+    // ~ unless the theme colors the count
+    opts.theme.counts[0][3] = Some((Color::Cyan, Some(Color::Magenta)));
+    let mut ui = UI::init(FakeTerminal::with_keys(7, 8, "cq"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    let cell = ui.terminal.cell(2, 1);
+    assert!(cell.bg_rgb.is_none() && Color::Cyan == cell.fg && Color::Magenta == cell.bg);
+    assert!(ui.terminal.cell(0, 2).bg_rgb.is_some());
+}
+
+#[cfg(test)]
+fn assert_quits_in_time(keys: &[(u64, char)]) {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    // ~ a step as slow as that of a huge world
+    fn slow_step(ui: &mut UI<FakeTerminal>, world: &mut World) {
+        ui.terminal.advance(StdDuration::from_millis(STEP_MS));
+        advance_one_step(ui, world);
+    }
+    const STEP_MS: u64 = 5;
+
+    let events = keys.iter()
+                     .map(|&(ms, c)| (StdDuration::from_millis(ms), Event::KeyEvent(Some(Key::Char(c)))))
+                     .collect();
+    let quit_at = keys[keys.len() - 1].0;
+    let opts = test_options();
+    let mut ui = UI::init(FakeTerminal::with_timed_events(20, 10, events), &opts);
+    let world = run_(&mut ui, Some(World::random_seeded(8, 20, 10)), &opts, slow_step).unwrap();
+    // ~ the terminal's clock runs at least as fast as the steps: once the
+    // key is due, the steps of at most one more input check follow
+    let most = (quit_at + TURBO_INPUT_CHECK_MS) / STEP_MS + 1;
+    assert!(world.generation() > 0 && world.generation() as u64 <= most,
+            "quit after {} steps instead of at most {}",
+            world.generation(),
+            most);
+}
+
+#[test]
+fn test_quit_responsive_at_min_delay() {
+    let mut keys: Vec<_> = "+++++++++++++++".chars().map(|c| (0, c)).collect();
+    keys.push((0, ' '));
+    keys.push((100, 'q'));
+    assert_quits_in_time(&keys);
+}
+
+#[test]
+fn test_quit_responsive_unthrottled() {
+    assert_quits_in_time(&[(0, '0'), (100, 'q')]);
+}
+
+#[test]
+fn test_measure_period_status() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let mut world = World::empty(60, 8);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        world.set_alive(x, y, true);
+    }
+    // ~ measured, then advanced; the measurement disappears
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 8, "pq"), &opts);
+    let world = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(7).starts_with("Gen: 0 / Alive: 5 / spaceship p4 (1,1) c/4 diagonal / "));
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 8, "psq"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(7).starts_with("Gen: 1 / Alive: 5 / Delay: 100ms."));
+}
+
+#[test]
+fn test_reverse_play() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let world = World::random_seeded(11, 50, 6);
+    let hash = world.generation_hash();
+    let key = |c| Event::KeyEvent(Some(Key::Char(c)));
+
+    // ~ three steps forward, one back
+    let events = vec![key('s'), key('s'), key('s'), key('R'), Event::NoEvent, key('q')];
+    let mut ui = UI::init(FakeTerminal::new(50, 6, events), &opts);
+    let world = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!(2, world.generation());
+    assert!(ui.terminal.line(5).starts_with("Gen: 2 / Alive: "));
+    assert!(ui.terminal.line(5).contains(" / Rewinding: 2 left"));
+
+    // ~ rewinding pauses once the history is exhausted; 'q' is then
+    // received by a blocking poll
+    let events = vec![key('s'), key('s'), key('R'), Event::NoEvent, Event::NoEvent,
+                      Event::NoEvent, Event::NoEvent, key('q')];
+    let mut ui = UI::init(FakeTerminal::new(50, 6, events), &opts);
+    let world = run_(&mut ui, Some(World::random_seeded(11, 50, 6)), &opts, advance_one_step)
+                    .unwrap();
+    assert_eq!(0, world.generation());
+    assert_eq!(hash, world.generation_hash());
+    assert!(!ui.terminal.line(5).contains("Rewinding"));
+}
+
+#[test]
+fn test_pause_on_extinction() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let mut world = World::empty(30, 6);
+    world.set_alive(3, 3, true);
+    // ~ after the lone cell died, the animation pauses; the remaining
+    // timeouts are skipped by the blocking poll
+    let events = vec![Event::KeyEvent(Some(Key::Char(' '))),
+                      Event::NoEvent,
+                      Event::NoEvent,
+                      Event::KeyEvent(Some(Key::Char('q')))];
+    let mut ui = UI::init(FakeTerminal::new(30, 6, events), &opts);
+    let world = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!(1, world.generation());
+    assert!(ui.terminal.line(5).starts_with("Gen: 1 / Alive: 0 / EXTINCT"));
+}
+
+#[test]
+fn test_count_digits() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    // ~ a blinker; the world's last line lies beneath the status line
+    let blinker = || {
+        let mut w = World::empty(5, 6);
+        for x in 1..4 {
+            w.set_alive(x, 2, true);
+        }
+        w
+    };
+    let lines = |t: &FakeTerminal| (0..5).map(|y| t.line(y)).collect::<Vec<_>>();
+
+    let mut ui = UI::init(FakeTerminal::with_keys(5, 6, "#q"), &opts);
+    run_(&mut ui, Some(blinker()), &opts, advance_one_step).unwrap();
+    assert_eq!(vec![".....", "12321", "11211", "12321", "....."], lines(&ui.terminal));
+
+    // ~ stepping re-renders the counts of the next phase
+    let mut ui = UI::init(FakeTerminal::with_keys(5, 6, "#sq"), &opts);
+    run_(&mut ui, Some(blinker()), &opts, advance_one_step).unwrap();
+    assert_eq!(vec![".111.", ".212.", ".323.", ".212.", ".111."], lines(&ui.terminal));
+
+    // ~ toggling off restores the normal view
+    let mut ui = UI::init(FakeTerminal::with_keys(5, 6, "q"), &opts);
+    run_(&mut ui, Some(blinker()), &opts, advance_one_step).unwrap();
+    let normal = lines(&ui.terminal);
+    assert_eq!(".OOO.", normal[2]);
+    let mut ui = UI::init(FakeTerminal::with_keys(5, 6, "##q"), &opts);
+    run_(&mut ui, Some(blinker()), &opts, advance_one_step).unwrap();
+    assert_eq!(normal, lines(&ui.terminal));
+}
+
+#[test]
+fn test_auto_expand_beyond_screen() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    // ~ a south bound glider in a world taller than the screen
+    let mut world = World::empty(40, 10);
+    world.set_boundary(BoundaryMode::Bounded);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        world.set_alive(x + 10, y + 4, true);
+    }
+    let mut events = vec![Event::KeyEvent(Some(Key::Char(' ')))];
+    events.extend((0..8).map(|_| Event::NoEvent));
+    events.push(Event::KeyEvent(Some(Key::Char('q'))));
+    let mut ui = UI::init(FakeTerminal::new(40, 6, events), &opts);
+    let world = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!(5, world.alive());
+    assert_eq!((0, 0), world.origin());
+    assert_eq!((40, 18), (world.width(), world.height()));
+    assert!(ui.terminal.line(5).starts_with("Gen: 8 / Alive: 5 / World: 40x18"));
+}
+
+#[test]
+fn test_slots() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    let world = World::random_seeded(12, 60, 6);
+    let hash = world.generation_hash();
+
+    // ~ saved, advanced, and loaded again
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 6, "m3ss'3q"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!((0, hash), (w.generation(), w.generation_hash()));
+    assert!(ui.terminal.line(5).contains(" / loaded slot 3 (gen 0)"));
+    assert!(ui.slots.is_modified());
+
+    // ~ the message disappears with the next generation; an empty
+    // slot leaves the world alone
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 6, "ss'2q"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!(2, w.generation());
+    assert!(ui.terminal.line(5).contains(" / slot 2 is empty"));
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 6, "m1sq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(!ui.terminal.line(5).contains("slot"));
+
+    // ~ a chord is cancelled by any other key
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 6, "m6'x'1q"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).contains(" / slot 1 is empty"));
+
+    // ~ a corrupted slots file is reported but does not prevent the
+    // start; it is left as it is
+    let file = env::temp_dir().join(format!("cgol-test-ui-slots-{}.bin", process::id()));
+    let file = file.to_str().unwrap().to_owned();
+    File::create(&file).unwrap().write_all(b"CGOLSLT2").unwrap();
+    opts.slots_file = Some(file.clone());
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "q"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).contains("(slots not loaded, nor saved)"));
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "m1q"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    ui.save_state(&opts).unwrap();
+    assert_eq!(b"CGOLSLT2".to_vec(), fs::read(&file).unwrap());
+    // ~ unlike a readable one
+    fs::remove_file(&file).unwrap();
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "m1q"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    ui.save_state(&opts).unwrap();
+    assert!(Slots::load_from_file(&file).unwrap().get(1).is_some());
+    let _ = fs::remove_file(&file);
 }
 
-fn advance_one_step(ui: &mut UI, world: &mut World) {
-    world.advance_generation(|w, h, alive| {
-        ui.print_char(w, h, ui.get_drawing_char(alive));
+#[test]
+fn test_minimap() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
     });
-    ui.update_status(&world);
-    ui.flush();
+    let world = |w, h| {
+        let mut world = World::empty(w, h);
+        world.set_boundary(BoundaryMode::Bounded);
+        for x in (0..w).filter(|x| x % 3 == 0) {
+            world.set_alive(x, 6, true);
+        }
+        world
+    };
+    let map = |t: &FakeTerminal, y: usize, from: usize, to: usize| -> String {
+        t.line(y).chars().skip(from).take(to - from).collect()
+    };
+
+    // ~ 240x44 at scale 12; the visible 60x11 cells are the top left
+    // 5x1 characters
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 12, "Mq"), &opts);
+    run_(&mut ui, Some(world(240, 44)), &opts, advance_one_step).unwrap();
+    assert_eq!("+---+...............", map(&ui.terminal, 0, 40, 60));
+    assert_eq!("                    ", map(&ui.terminal, 3, 40, 60));
+
+    // ~ 100x30 at scale 5; 12x3 characters outlined, the cells of
+    // the sixth row showing in the second row of the map
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 12, "Mq"), &opts);
+    run_(&mut ui, Some(world(100, 30)), &opts, advance_one_step).unwrap();
+    assert_eq!("+----------+        ", map(&ui.terminal, 0, 40, 60));
+    assert_eq!("|..........|........", map(&ui.terminal, 1, 40, 60));
+    assert_eq!("+----------+        ", map(&ui.terminal, 2, 40, 60));
+
+    // ~ a step draws the changed cells; the map stays on top
+    let events = vec![Event::KeyEvent(Some(Key::Char('M'))),
+                      Event::KeyEvent(Some(Key::Char('s'))),
+                      Event::KeyEvent(Some(Key::Char('q')))];
+    let mut ui = UI::init(FakeTerminal::new(60, 12, events), &opts);
+    let w = run_(&mut ui, Some(world(100, 30)), &opts, advance_one_step).unwrap();
+    assert_eq!(1, w.generation());
+    assert_eq!("+----------+", map(&ui.terminal, 0, 40, 52));
+
+    // ~ hiding the map leaves no traces
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 12, "MMq"), &opts);
+    let w = run_(&mut ui, Some(world(100, 30)), &opts, advance_one_step).unwrap();
+    for h in 0..11 {
+        ui.render_line(&w, h);
+        assert_eq!(&ui.line_buf[..60], &ui.terminal.line(h)[..]);
+    }
+}
+
+#[test]
+fn test_cycle_file() {
+    let files: Vec<PathBuf> = ["p/a.cells", "p/b.cells", "p/c.cgolb"].iter().map(PathBuf::from).collect();
+    assert_eq!(Some(2), cycle_file(&files, Path::new("p/b.cells"), true));
+    assert_eq!(Some(0), cycle_file(&files, Path::new("p/c.cgolb"), true));
+    assert_eq!(Some(2), cycle_file(&files, Path::new("p/a.cells"), false));
+    // ~ the current file was deleted
+    assert_eq!(Some(1), cycle_file(&files, Path::new("p/ab.cells"), true));
+    assert_eq!(Some(0), cycle_file(&files, Path::new("p/ab.cells"), false));
+    assert_eq!(Some(0), cycle_file(&files, Path::new("p/d.cells"), true));
+    assert_eq!(None, cycle_file(&[], Path::new("p/a.cells"), true));
+}
+
+#[test]
+fn test_browse_files() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let dir = env::temp_dir().join(format!("cgol-test-browse-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    for &(name, cells) in [("a.cells", "O"), ("b.cells", "OO"), ("c.cells", "OOO"),
+                           ("notes.txt", "OOOO"), ("x.cells", "!broken\nx")]
+                              .iter() {
+        File::create(dir.join(name)).unwrap().write_all(cells.as_bytes()).unwrap();
+    }
+    let mut opts = test_options();
+    opts.file = Some(dir.join("b.cells").to_str().unwrap().to_owned());
+
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "]q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(5, 5)), &opts, advance_one_step).unwrap();
+    assert_eq!(3, w.alive());
+    assert!(ui.terminal.line(5).contains("c.cells (3/4)"));
+
+    // ~ a broken file is reported and skipped by the next key
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "]]q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(5, 5)), &opts, advance_one_step).unwrap();
+    assert_eq!(3, w.alive());
+    assert!(ui.terminal.line(5).contains("x.cells: 2:1: Invalid character: x"));
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "]]]q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(5, 5)), &opts, advance_one_step).unwrap();
+    assert_eq!(1, w.alive());
+
+    // ~ the current file vanished
+    fs::remove_file(dir.join("b.cells")).unwrap();
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "[q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(5, 5)), &opts, advance_one_step).unwrap();
+    assert_eq!(1, w.alive());
+    assert!(ui.terminal.line(5).contains("a.cells (1/3)"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_browse_pack() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let dir = env::temp_dir().join(format!("cgol-test-browse-pack-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    for &(name, cells) in [("a.cells", "O"), ("b.cells", "OO"), ("index.txt", "b.cells Two\na.cells\n")]
+                              .iter() {
+        File::create(dir.join(name)).unwrap().write_all(cells.as_bytes()).unwrap();
+    }
+    let mut opts = test_options();
+    opts.pack = Some(dir.to_str().unwrap().to_owned());
+
+    // ~ in the order of the index, starting at its first pattern
+    let mut ui = UI::init(FakeTerminal::with_keys(80, 6, "]q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(5, 5)), &opts, advance_one_step).unwrap();
+    assert_eq!(2, w.alive());
+    assert!(ui.terminal.line(5).contains("Two (1/2)"));
+    let mut ui = UI::init(FakeTerminal::with_keys(80, 6, "[q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(5, 5)), &opts, advance_one_step).unwrap();
+    assert_eq!(1, w.alive());
+    assert!(ui.terminal.line(5).contains("a (2/2)"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_bookmarks() {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    let world = World::random_seeded(8, 60, 11);
+
+    // ~ bookmark generation 2, step on, and jump back
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 11, "ssbsssB1q"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    let mut expected = world.clone();
+    for _ in 0..2 {
+        expected.advance_generation(|_, _, _| {});
+    }
+    assert_eq!(2, w.generation());
+    assert_eq!(expected.generation_hash(), w.generation_hash());
+    assert!(ui.terminal.line(10).contains("jumped to gen 2"));
+    // ~ stepping forward from there recomputes normally
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 11, "ssbsssB1sq"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    expected.advance_generation(|_, _, _| {});
+    assert_eq!(expected.generation_hash(), w.generation_hash());
+    assert_eq!(3, w.generation());
+
+    // ~ the list of bookmarks
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 11, "bsbBq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(1).starts_with("1: gen 0 / "));
+    assert!(ui.terminal.line(2).starts_with("2: gen 1 / "));
+
+    // ~ bookmarks survive the run; but are not offered for another world
+    let file = env::temp_dir().join(format!("cgol-test-bookmarks-{}.bin", process::id()));
+    let file = file.to_str().unwrap().to_owned();
+    let _ = fs::remove_file(&file);
+    opts.bookmarks_file = Some(file.clone());
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 11, "sbq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    ui.bookmarks.save_to_file(&file).unwrap();
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 11, "B1q"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!(1, w.generation());
+    let mut ui = UI::init(FakeTerminal::with_keys(160, 11, "q"), &opts);
+    run_(&mut ui, Some(World::random_seeded(9, 60, 11)), &opts, advance_one_step).unwrap();
+    assert_eq!(0, ui.bookmarks.len());
+    assert!(ui.terminal.line(10).contains("bookmarks of a different world or rule (bookmarks not loaded)"));
+
+    // ~ a world generated anew starts a run of its own; the bookmarks
+    // of the one left are saved first
+    let _ = fs::remove_file(&file);
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 11, "sbrbq"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!((1, bookmarks::fingerprint(&w)), (ui.bookmarks.len(), ui.bookmarks.fingerprint()));
+    let fingerprint = bookmarks::fingerprint(&world);
+    let saved = Bookmarks::load_from_file(&file, fingerprint, bookmarks::DEFAULT_CAP).unwrap();
+    assert_eq!(vec![1], saved.iter().map(|b| b.generation()).collect::<Vec<_>>());
+    let _ = fs::remove_file(&file);
+}
+
+#[test]
+fn test_theme() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.theme = Theme {
+        fg: Color::Green,
+        bg: Color::Black,
+        ..Theme::default()
+    };
+    let mut world = World::empty(10, 4);
+    for x in 1..4 {
+        world.set_alive(x, 1, true);
+    }
+    // ~ a full redraw and an incremental update
+    for keys in &["q", "sq"] {
+        let mut ui = UI::init(FakeTerminal::with_keys(40, 5, keys), &opts);
+        run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+        for y in 0..4 {
+            for x in 0..40 {
+                let c = ui.terminal.cell(x, y);
+                assert!(Color::Black == c.bg, "{}: ({}, {})", keys, x, y);
+                let fg = if c.ch == 'O' { Color::Green } else { Color::Default };
+                assert!(fg == c.fg, "{}: ({}, {})", keys, x, y);
+            }
+        }
+        assert_eq!(3, (0..4).map(|y| ui.terminal.line(y).matches('O').count()).sum::<usize>());
+        // ~ not the status line; the world's last row shows beyond it
+        let status = ui.terminal.line(4);
+        assert!(status.starts_with("Gen: "));
+        let len = status.find(" / Delay: 100ms").unwrap() + " / Delay: 100ms".len();
+        assert!((0..len).all(|x| Color::Default == ui.terminal.cell(x, 4).bg));
+        assert!((len..40).all(|x| Color::Black == ui.terminal.cell(x, 4).bg));
+    }
+}
+
+#[test]
+fn test_adjust_delay() {
+    let ms = Duration::milliseconds;
+    assert_eq!(ms(50), adjust_delay(ms(100), true));
+    assert_eq!(ms(200), adjust_delay(ms(100), false));
+    // ~ never below the minimum, however often sped up
+    let mut d = ms(DEFAULT_DELAY_MS);
+    for _ in 0..20 {
+        d = adjust_delay(d, true);
+    }
+    assert_eq!(ms(MIN_DELAY_MS), d);
+    // ~ a sub-millisecond delay is raised to the minimum by either key
+    assert_eq!(ms(MIN_DELAY_MS), adjust_delay(Duration::microseconds(300), true));
+    assert_eq!(ms(MIN_DELAY_MS), adjust_delay(Duration::microseconds(300), false));
+    // ~ slowing down again doubles from the minimum
+    assert_eq!(ms(2), adjust_delay(d, false));
+    for _ in 0..40 {
+        d = adjust_delay(d, false);
+    }
+    assert_eq!(ms(MAX_DELAY_MS), d);
+}
+
+#[test]
+fn test_delay_status() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    for &(keys, delay) in [("q", "100ms"), ("-q", "200ms"), ("++++++++++q", "1ms"),
+                           ("++++++++++-q", "2ms"), ("0q", "none"), ("00q", "100ms"), ("0 q", "100ms")]
+                              .iter() {
+        let mut world = World::empty(60, 5);
+        for &(x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)].iter() {
+            world.set_alive(x, y, true);
+        }
+        let mut ui = UI::init(FakeTerminal::with_keys(60, 5, keys), &opts);
+        run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+        let status = ui.terminal.line(4);
+        if delay == "none" {
+            // ~ fast-forwarding shows its progress instead
+            assert!(status.contains(" / Turbo: ") && !status.contains("Delay"), "{}: {}", keys, status);
+        } else {
+            assert!(status.contains(&format!(" / Delay: {}.", delay)), "{}: {}", keys, status);
+        }
+    }
+}
+
+#[test]
+fn test_slowmo_delay() {
+    let bounds = (20, 1000);
+    assert_eq!(Duration::milliseconds(20), slowmo_delay(0, 1000, bounds));
+    assert_eq!(Duration::milliseconds(510), slowmo_delay(50, 1000, bounds));
+    assert_eq!(Duration::milliseconds(1000), slowmo_delay(100, 1000, bounds));
+    // ~ clamped
+    assert_eq!(Duration::milliseconds(1000), slowmo_delay(900, 1000, bounds));
+    assert_eq!(Duration::milliseconds(20), slowmo_delay(0, 0, bounds));
+}
+
+#[test]
+fn test_slowmo_status() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let mut ui = UI::init(FakeTerminal::with_keys(100, 6, "asq"), &opts);
+    run_(&mut ui, Some(World::random_seeded(3, 100, 6)), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).contains(" / AUTO"));
+    assert!(ui.last_changes > 0);
+    // ~ the delay adapted to the changes rather than the one set
+    let delay = slowmo_delay(ui.last_changes, 100 * 6, DEFAULT_SLOWMO_BOUNDS).num_milliseconds();
+    assert!(delay != DEFAULT_DELAY_MS);
+    assert!(ui.terminal.line(5).contains(&format!(" / Delay: {}ms", delay)), "{}", ui.terminal.line(5));
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 6, "aaq"), &opts);
+    run_(&mut ui, Some(World::random_seeded(3, 40, 6)), &opts, advance_one_step).unwrap();
+    assert!(!ui.terminal.line(5).contains("AUTO"));
+}
+
+#[test]
+fn test_update_mode_status() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 5, "q"), &opts);
+    run_(&mut ui, Some(World::empty(60, 5)), &opts, advance_one_step).unwrap();
+    assert!(!ui.terminal.line(4).contains("Update"));
+
+    // ~ applies to generated worlds as well as to replaced ones
+    opts.update_mode = UpdateMode::AsyncRowMajor;
+    opts.seed = Some(1);
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 5, "m1'1q"), &opts);
+    let world = run_(&mut ui, None, &opts, advance_one_step).unwrap();
+    assert_eq!(UpdateMode::AsyncRowMajor, world.update_mode());
+    assert!(ui.terminal.line(4).contains(" / Update: async-row-major / "));
+}
+
+#[test]
+fn test_toggled_boundary() {
+    let twist = BoundaryMode::Twisted { dx: 1, dy: 0 };
+    assert_eq!(BoundaryMode::Bounded, toggled_boundary(BoundaryMode::Wrap, BoundaryMode::Wrap));
+    assert_eq!(BoundaryMode::Wrap, toggled_boundary(BoundaryMode::Bounded, BoundaryMode::Wrap));
+    assert_eq!(BoundaryMode::Bounded, toggled_boundary(twist, twist));
+    assert_eq!(twist, toggled_boundary(BoundaryMode::Bounded, twist));
+    assert_eq!(BoundaryMode::Wrap, toggled_boundary(BoundaryMode::Bounded, BoundaryMode::Bounded));
+}
+
+#[test]
+fn test_toggle_boundary() {
+    use terminal::fake::FakeTerminal;
+
+    // ~ a glider crossing the bottom edge; on a torus it re-enters at
+    // the top, on a bounded grid it crashes into a block
+    let glider = || {
+        let mut w = World::empty(20, 8);
+        for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+            w.set_alive(x + 5, y + 3, true);
+        }
+        w
+    };
+    let opts = test_options();
+    let keys = "c".to_owned() + &"s".repeat(12) + "q";
+    let mut ui = UI::init(FakeTerminal::with_keys(20, 8, &keys), &opts);
+    let torus = run_(&mut ui, Some(glider()), &opts, advance_one_step).unwrap();
+    assert_eq!(5, torus.alive());
+
+    let keys = "wc".to_owned() + &"s".repeat(12) + "q";
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 8, &keys), &opts);
+    let world = run_(&mut ui, Some(glider()), &opts, advance_one_step).unwrap();
+    assert_eq!(BoundaryMode::Bounded, world.boundary());
+    assert!(world.generation_hash() != torus.generation_hash());
+    assert!(ui.terminal.line(7).contains(" / Edges: bounded"));
+    // ~ the neighbour counts shown are those of the bounded grid
+    let counts = world.neighbour_counts();
+    for y in 0..7 {
+        for x in 0..20 {
+            let c = ui.terminal.cell(x, y);
+            if !world.is_alive(x, y) && counts[y * 20 + x] == 3 {
+                assert_eq!('3', c.ch, "({}, {})", x, y);
+            }
+        }
+    }
+
+    // ~ and back
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 8, "wwq"), &opts);
+    let world = run_(&mut ui, Some(glider()), &opts, advance_one_step).unwrap();
+    assert_eq!(BoundaryMode::Wrap, world.boundary());
+    assert!(ui.terminal.line(7).contains(" / edges: wrap"));
+}
+
+#[test]
+fn test_labels() {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let dir = env::temp_dir().join(format!("cgol-test-labels-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    fs::write(dir.join("gun.cells"), "!label 0,0 ejector\n!label 30,2 lane 3\nOO\nOO\n").unwrap();
+    let mut opts = test_options();
+    opts.file = Some(dir.join("gun.cells").to_str().unwrap().to_owned());
+
+    // ~ loaded along with the pattern and marked on screen
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 6, "]q"), &opts);
+    run_(&mut ui, Some(World::empty(40, 6)), &opts, advance_one_step).unwrap();
+    assert_eq!(2, ui.labels.len());
+    for y in 0..5 {
+        for x in 0..40 {
+            let labeled = (x, y) == (1, 1) || (x, y) == (31, 3);
+            assert!((Color::Yellow == ui.terminal.cell(x, y).bg) == labeled, "({}, {})", x, y);
+        }
+    }
+    // ~ dropped once outside a smaller screen
+    let mut ui = UI::init(FakeTerminal::with_keys(20, 6, "]q"), &opts);
+    let w = run_(&mut ui, Some(World::empty(20, 6)), &opts, advance_one_step).unwrap();
+    assert_eq!(20, w.width());
+    assert_eq!(vec![(1, 1, "ejector")], ui.labels.iter().collect::<Vec<_>>());
+    assert_eq!(Some("dropped labels outside the world: lane 3"), ui.message.as_ref().map(|m| &m[..]));
+    // ~ kept by the rule editor restarting the world, yet not carried
+    // over to a world from a slot
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 6, "]e3eq"), &opts);
+    run_(&mut ui, Some(World::empty(40, 6)), &opts, advance_one_step).unwrap();
+    assert_eq!(2, ui.labels.len());
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 6, "m1]'1q"), &opts);
+    run_(&mut ui, Some(World::empty(40, 6)), &opts, advance_one_step).unwrap();
+    assert_eq!(0, ui.labels.len());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_demo() {
+    use std::cell::Cell;
+    use rustbox::Event;
+    use patterns::{DemoStop, DemoWorld};
+    use terminal::fake::FakeTerminal;
+
+    let phases = [DemoPhase {
+                      name: "Blinker",
+                      description: "period 2",
+                      world: DemoWorld::Cells("OOO\n"),
+                      stop: DemoStop::Generations(3),
+                      delay_ms: 10,
+                  },
+                  DemoPhase {
+                      name: "Block",
+                      description: "still",
+                      world: DemoWorld::Cells("OO\nOO\n"),
+                      stop: DemoStop::Generations(5),
+                      delay_ms: 10,
+                  }];
+    // ~ a clock advancing by 250ms whenever asked for the time
+    let now = Cell::new(0);
+    let clock = || {
+        now.set(now.get() + 250);
+        now.get()
+    };
+    let run = |events: usize| {
+        let mut events = vec![Event::NoEvent; events];
+        events.push(Event::KeyEvent(Some(Key::Char('x'))));
+        let mut ui = UI::init(FakeTerminal::new(40, 10, events), &test_options());
+        now.set(0);
+        run_demo_(&mut ui, &test_options(), &phases, &clock).unwrap();
+        ui
+    };
+
+    // ~ the title card first
+    let ui = run(4);
+    assert_eq!("+----------+", ui.terminal.line(2).trim_matches('.'));
+    assert_eq!("| Blinker  |", ui.terminal.line(3).trim_matches('.'));
+    assert_eq!("| period 2 |", ui.terminal.line(4).trim_matches('.'));
+    assert!(ui.terminal.line(9).starts_with("Gen: 0 / Alive: 3 / Demo: Blinker"),
+            "{}", ui.terminal.line(9));
+    // ~ then the pattern evolving for its generations
+    let ui = run(11);
+    assert!(!ui.terminal.line(4).contains('|'));
+    assert!(ui.terminal.line(9).starts_with("Gen: 3 / Alive: 3 / Demo: Blinker"),
+            "{}", ui.terminal.line(9));
+    // ~ the next phase starts afresh
+    let ui = run(12);
+    assert_eq!("| Block |", ui.terminal.line(3).trim_matches('.'));
+    assert!(ui.terminal.line(9).starts_with("Gen: 0 / Alive: 4 / Demo: Block"),
+            "{}", ui.terminal.line(9));
+    assert!(!ui.show_minimap);
+
+    // ~ a world exceeding the screen comes with the mini-map
+    let phases = [DemoPhase {
+                      name: "Glider",
+                      description: "off it goes",
+                      world: DemoWorld::Gun(".O\n..O\nOOO\n"),
+                      stop: DemoStop::Generations(200),
+                      delay_ms: 10,
+                  }];
+    let events = vec![Event::NoEvent, Event::KeyEvent(Some(Key::Char('x')))];
+    let mut ui = UI::init(FakeTerminal::new(40, 10, events), &test_options());
+    run_demo_(&mut ui, &test_options(), &phases, &clock).unwrap();
+    assert!(ui.show_minimap);
+}
+
+#[test]
+fn test_statlog() {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let file = env::temp_dir().join(format!("cgol-test-statlog-{}.txt", process::id()));
+    let _ = fs::remove_file(&file);
+    let mut opts = test_options();
+    opts.statlog_file = Some(file.to_str().unwrap().to_owned());
+    // ~ a blinker next to a dying cell (on a 60x6 screen); two lines
+    // appended per run
+    let mut world = World::empty(10, 6);
+    for &(x, y) in [(1, 2), (2, 2), (3, 2), (7, 4)].iter() {
+        world.set_alive(x, y, true);
+    }
+    for _ in 0..2 {
+        let mut ui = UI::init(FakeTerminal::with_keys(60, 6, "LspLq"), &opts);
+        run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+        assert!(ui.terminal.line(5).contains("logged gen 1"), "{}", ui.terminal.line(5));
+    }
+    let line0 = "generation=0 alive=4 density=0.011111 peak=4";
+    let line1 = "generation=1 alive=3 density=0.008333 peak=4 period=oscillator p2";
+    assert_eq!(format!("{}\n{}\n{}\n{}\n", line0, line1, line0, line1),
+               fs::read_to_string(&file).unwrap());
+    let _ = fs::remove_file(&file);
+
+    // ~ errors are noted in the status line; without a stats log L
+    // loads a file instead (see `test_file_prompt`)
+    opts.statlog_file = Some("/nonexistent/statlog.txt".to_owned());
+    let ui = UI::init(FakeTerminal::with_keys(60, 6, "q"), &opts);
+    assert!(ui.message.unwrap().ends_with("(no stats log)"));
+}
+
+#[test]
+fn test_plot() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    // ~ a blinker next to a dying cell
+    let mut world = World::empty(40, 12);
+    for &(x, y) in [(21, 6), (22, 6), (23, 6), (30, 2)].iter() {
+        world.set_alive(x, y, true);
+    }
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "sssGq"), &test_options());
+    run_(&mut ui, Some(world.clone()), &test_options(), advance_one_step).unwrap();
+    assert_eq!(vec![4, 3, 3, 3], ui.populations);
+    // ~ a box as wide as the chart over the world
+    let start = |y: usize| ui.terminal.line(y).chars().take(8).collect::<String>();
+    assert_eq!("4 |█   .", start(0));
+    assert_eq!("3 |█▁▁▁.", start(7));
+    assert_eq!("  +----.", start(8));
+    assert_eq!("   0  3.", start(9));
+    assert_eq!("........", start(10));
+    // ~ hidden again; playing backwards forgets the later generations
+    let key = |c| Event::KeyEvent(Some(Key::Char(c)));
+    let events = vec![key('s'), key('s'), key('s'), key('G'), key('G'), key('R'), Event::NoEvent, key('q')];
+    let mut ui = UI::init(FakeTerminal::new(40, 12, events), &test_options());
+    let w = run_(&mut ui, Some(world), &test_options(), advance_one_step).unwrap();
+    assert_eq!(2, w.generation());
+    assert!(ui.terminal.line(0).starts_with("......"));
+    assert_eq!(vec![4, 3, 3], ui.populations);
+}
+
+#[test]
+fn test_hud() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    // ~ a blinker in the top right corner
+    let mut world = World::empty(40, 12);
+    for &(x, y) in [(36, 1), (37, 1), (38, 1)].iter() {
+        world.set_alive(x, y, true);
+    }
+    let start = |ui: &UI<FakeTerminal>, y: usize| ui.terminal.line(y).chars().take(10).collect::<String>();
+    let end = |ui: &UI<FakeTerminal>, y: usize| {
+        let line: Vec<char> = ui.terminal.line(y).chars().collect();
+        line[line.len() - 10..].iter().cloned().collect::<String>()
+    };
+    // ~ shown in the emptiest corner, first the generation only, then
+    // along with the population
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "HsHq"), &test_options());
+    run_(&mut ui, Some(world.clone()), &test_options(), advance_one_step).unwrap();
+    assert_eq!("  █      .", start(&ui, 0));
+    assert_eq!(" ███     .", start(&ui, 4));
+    assert_eq!(" 3 alive .", start(&ui, 5));
+    assert_eq!(".......O..", end(&ui, 0));
+    // ~ hidden again
+    let key = |c| Event::KeyEvent(Some(Key::Char(c)));
+    let events = vec![key('H'), key('s'), key('H'), key('H'), key('q')];
+    let mut ui = UI::init(FakeTerminal::new(40, 12, events), &test_options());
+    run_(&mut ui, Some(world.clone()), &test_options(), advance_one_step).unwrap();
+    assert_eq!("..........", start(&ui, 0));
+    // ~ in the configured corner, no matter what is beneath
+    let mut opts = test_options();
+    opts.hud_corner = Some(Corner::TopRight);
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "Hq"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!("..... ███ ", end(&ui, 0));
+    assert_eq!("..... █ █ ", end(&ui, 1));
+    assert_eq!("..........", start(&ui, 0));
+}
+
+#[test]
+fn test_glyph_collisions() {
+    assert!(glyph_collisions('O', ' ').is_empty());
+    assert_eq!(vec!["alive char '|' is also drawn by the mini-map, the population chart, the title cards"],
+               glyph_collisions('|', ' '));
+    assert_eq!(vec!["alive and dead char are both '█'",
+                    "alive char '█' is also drawn by the population chart, the generation display",
+                    "dead char '█' is also drawn by the population chart, the generation display"],
+               glyph_collisions('█', '█'));
+    assert_eq!(vec!["dead char '3' is also drawn by the neighbour counts, the population chart, \
+                     the generation display"],
+               glyph_collisions('O', '3'));
+}
+
+#[test]
+fn test_overlays_with_colliding_glyphs() {
+    use terminal::fake::FakeTerminal;
+
+    // ~ live cells drawn like the outline of the mini-map and the axes
+    // of the chart; all over the screen, such that every overlay
+    // covers some of them
+    let mut opts = test_options();
+    opts.alive_char = '|';
+    opts.message = Some(glyph_collisions('|', '.').join("; "));
+    let mut world = World::empty(40, 12);
+    for y in 0..11 {
+        for x in 0..40 {
+            if (x * 7 + y * 3) % 5 == 0 {
+                world.set_alive(x, y, true);
+            }
+        }
+    }
+    let expected: Vec<String> = (0..11)
+        .map(|y| (0..40).map(|x| if world.is_alive(x, y) { '|' } else { '.' }).collect())
+        .collect();
+    // ~ the mini-map, the chart, and the generation display (in either
+    // mode) shown and hidden again without advancing the world
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "MGHMGHHq"), &opts);
+    assert!(ui.message.as_ref().unwrap().starts_with("alive char '|' is also drawn by the mini-map"));
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    for (y, line) in expected.iter().enumerate() {
+        assert_eq!(line, &ui.terminal.line(y), "line {}", y);
+    }
+}
+
+#[test]
+fn test_macros() {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let world = World::random_seeded(12, 60, 6);
+
+    // ~ recorded while being executed, then replayed; the same as
+    // typing the keys twice
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 6, "Qam1ss'1ssQ@aq"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    let mut by_hand = UI::init(FakeTerminal::with_keys(60, 6, "m1ss'1ssm1ss'1ssq"), &opts);
+    let expected = run_(&mut by_hand, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!((expected.generation(), expected.generation_hash()),
+               (w.generation(), w.generation_hash()));
+    assert_eq!(Some(&macros::decode("m1ss'1ss").unwrap()[..]), ui.macros.get('a'));
+    assert!(ui.macros.is_modified());
+
+    // ~ the status line tells about the recording
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "Qbsq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).contains(" / Recording: b"));
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "QbsQq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).contains(" / recorded @b (1 keys)"));
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "@cq"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(5).contains(" / @c is empty"));
+
+    // ~ a macro invoking itself, directly or not, is stopped
+    for &(a, b) in [("ss@a", ""), ("s@b", "s@a")].iter() {
+        let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "@aq"), &opts);
+        ui.macros.set('a', macros::decode(a).unwrap());
+        ui.macros.set('b', macros::decode(b).unwrap());
+        let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+        assert!(ui.terminal.line(5).contains(" / @a invokes itself; stopped"));
+        assert!(w.generation() <= 2);
+    }
+
+    // ~ a macros file which cannot be read is left as it is
+    let file = env::temp_dir().join(format!("cgol-test-ui-macros-{}.txt", process::id()));
+    fs::write(&file, "a=ss\nnot a macro\n").unwrap();
+    let mut opts = opts;
+    opts.macros_file = Some(file.to_str().unwrap().to_owned());
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "q"), &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.message.as_ref().unwrap().ends_with("(macros not loaded, nor saved)"));
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "QasQq"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    ui.save_state(&opts).unwrap();
+    assert_eq!("a=ss\nnot a macro\n", fs::read_to_string(&file).unwrap());
+    let _ = fs::remove_file(&file);
+}
+
+// ~ with the feature the RLE would end up in the real clipboard
+#[cfg(not(feature = "clipboard"))]
+#[test]
+fn test_copy_rle() {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "sssyq"), &opts);
+    let w = run_(&mut ui, Some(World::random_seeded(12, 120, 6)), &opts, advance_one_step).unwrap();
+    let path = env::temp_dir().join("cgol-gen3.rle");
+    assert!(ui.terminal.line(5).contains(&format!(" / no clipboard; RLE written to {}", path.display())));
+    let mut s = String::new();
+    File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+    assert_eq!(parser::render_rle(&w), s);
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_rule_editor() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let world = World::random_seeded(12, 120, 6);
+    let steps = |mut w: World, rule: &str, n: usize| {
+        w.set_rule(Rule::parse(rule).unwrap());
+        for _ in 0..n {
+            w.advance_generation(|_, _, _| {});
+        }
+        w
+    };
+
+    // ~ each change restarts from the initial world under the edited
+    // rule
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "sse6ssq"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    let expected = steps(world.clone(), "B36/S23", 2);
+    assert_eq!((2, expected.generation_hash()), (w.generation(), w.generation_hash()));
+    assert_eq!("B36/S23", w.rule().to_string());
+    assert!(ui.terminal.line(5).contains(" / Rule: B36/S23 [births] / "));
+
+    // ~ the survival counts after a tab; closed again, the rule is
+    // still shown as it is not the classic one
+    let key = |k| Event::KeyEvent(Some(k));
+    let events = vec![key(Key::Char('e')),
+                      key(Key::Tab),
+                      key(Key::Char('2')),
+                      key(Key::Char('s')),
+                      key(Key::Enter),
+                      key(Key::Char('2')),
+                      key(Key::Char('q'))];
+    let mut ui = UI::init(FakeTerminal::new(120, 6, events), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!(steps(world.clone(), "B3/S3", 1).generation_hash(), w.generation_hash());
+    assert!(ui.terminal.line(5).contains(" / Rule: B3/S3 / "));
+    assert!(!ui.terminal.line(5).contains("[survival]"));
+
+    // ~ a regenerated world keeps the edited rule
+    let mut ui = UI::init(FakeTerminal::with_keys(120, 6, "e1erq"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!("B13/S23", w.rule().to_string());
+}
+
+#[test]
+fn test_stamp() {
+    use terminal::fake::FakeTerminal;
+
+    let keys = |keys: &[Key]| {
+        let mut events: Vec<Event> = keys.iter().map(|&k| Event::KeyEvent(Some(k))).collect();
+        events.push(Event::KeyEvent(Some(Key::Char('q'))));
+        FakeTerminal::new(60, 12, events)
+    };
+    let mut world = World::empty(60, 12);
+    for &(x, y) in &[(2, 8), (3, 8), (2, 9), (3, 9)] {
+        world.set_alive(x, y, true);
+    }
+    let block = world.live_cells();
+    let is_preview = |t: &FakeTerminal, x: usize, y: usize| t.cell(x, y).fg == STAMP_COLOR;
+    // ~ the glider turned clockwise (O.. / O.O / OO.) at 30,5
+    let turned = [(30, 5), (30, 6), (32, 6), (30, 7), (31, 7)];
+
+    // ~ previewed at the center, moved and turned; the world stays
+    let opts = test_options();
+    let mut ui = UI::init(keys(&[Key::Char('S'), Key::Right, Key::Right, Key::Down, Key::Char('r')]),
+                          &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!(block, w.live_cells());
+    for &(x, y) in &turned {
+        assert!(is_preview(&ui.terminal, x, y) && ui.terminal.cell(x, y).ch == 'O', "({}, {})", x, y);
+    }
+    // ~ where the glider was shown first
+    assert!(!is_preview(&ui.terminal, 29, 4) && ui.terminal.cell(29, 4).ch == '.');
+    assert!(ui.terminal.line(11).contains(" / Stamp: Glider at 30,5"));
+
+    // ~ moving the preview draws just its footprints before and after
+    // (and the status line)
+    ui.terminal.take_printed();
+    ui.change_stamp(&w, |ui| ui.stamp_at = (31, 5));
+    let printed = ui.terminal.take_printed();
+    let moved: Vec<_> = turned.iter().map(|&(x, y)| (x + 1, y)).collect();
+    assert!(printed.iter().all(|&(x, y)| y == 11 || (x >= 30 && x <= 33 && y >= 5 && y <= 7)));
+    assert!(moved.iter().all(|c| printed.contains(c)) && !is_preview(&ui.terminal, 30, 7));
+
+    // ~ committed
+    let mut ui = UI::init(keys(&[Key::Char('S'), Key::Right, Key::Right, Key::Down, Key::Char('r'), Key::Enter]),
+                          &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    let mut expected = block.clone();
+    expected.extend(turned.iter().cloned());
+    expected.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(expected, w.live_cells());
+    assert!(turned.iter().all(|&(x, y)| !is_preview(&ui.terminal, x, y)));
+    assert!(ui.terminal.line(11).contains(" / stamped Glider"));
+
+    // ~ clipped at the right edge of a bounded world
+    let mut right = vec![Key::Char('S')];
+    right.extend([Key::Right; 30].iter().cloned());
+    let mut bounded = world.clone();
+    bounded.set_boundary(BoundaryMode::Bounded);
+    let mut ui = UI::init(keys(&right), &opts);
+    run_(&mut ui, Some(bounded.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(11).contains(" / Stamp: Glider at 58,4 (2 clipped)"));
+    right.push(Key::Enter);
+    let mut ui = UI::init(keys(&right), &opts);
+    let w = run_(&mut ui, Some(bounded), &opts, advance_one_step).unwrap();
+    assert_eq!(block.len() + 3, w.alive());
+    assert!(ui.terminal.line(11).contains(" / stamped Glider; 2 cells clipped"));
+
+    // ~ wrapped around the torus instead
+    let mut ui = UI::init(keys(&right), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!(block.len() + 5, w.alive());
+    assert!(w.is_alive(0, 5) && w.is_alive(0, 6) && !is_preview(&ui.terminal, 0, 5));
+    assert!(ui.terminal.line(11).contains(" / stamped Glider") && !ui.terminal.line(11).contains("clipped"));
+
+    // ~ cancelled after picking the next pattern
+    let mut ui = UI::init(keys(&[Key::Char('S'), Key::Right, Key::Tab, Key::Esc]), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!(block, w.live_cells());
+    assert!((0..11).all(|y| (0..60).all(|x| !is_preview(&ui.terminal, x, y))));
+    assert!(!ui.terminal.line(11).contains("Stamp"));
+}
+
+#[test]
+fn test_sandbox() {
+    use std::cell::RefCell;
+    use terminal::fake::FakeTerminal;
+
+    // ~ a 5x5 region around a blinker, stepped in the sandbox
+    let keys = |more: &[Key]| {
+        let mut keys = vec![Key::Char('X'), Key::Ctrl('u')];
+        keys.extend("36,2,5,5".chars().map(Key::Char));
+        keys.extend([Key::Enter, Key::Char('s')].iter().chain(more));
+        keys.push(Key::Char('q'));
+        let events = keys.into_iter().map(|k| Event::KeyEvent(Some(k))).collect();
+        FakeTerminal::new(80, 10, events)
+    };
+    let mut world = World::empty(80, 10);
+    for &(x, y) in &[(38, 4), (39, 4), (40, 4)] {
+        world.set_alive(x, y, true);
+    }
+    let opts = test_options();
+    // ~ the screen as drawn by the steps
+    let screens = RefCell::new(Vec::new());
+    let step = |ui: &mut UI<FakeTerminal>, world: &mut World| {
+        advance_one_step(ui, world);
+        screens.borrow_mut().push((0..10).map(|y| ui.terminal.line(y)).collect::<Vec<_>>());
+    };
+
+    // ~ pasted back in the opposite phase; the keys acting on the world
+    // set aside are refused meanwhile
+    let mut ui = UI::init(keys(&[Key::Char('b'), Key::Enter]), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, &step).unwrap();
+    assert_eq!(vec![(39, 3), (39, 4), (39, 5)], w.live_cells());
+    assert_eq!(0, w.generation());
+    assert!(ui.bookmarks.is_empty() && ui.sandbox.is_none());
+    assert!(ui.terminal.line(9).contains(" / pasted back the sandbox after 1 gens (4 cells changed)"),
+            "{}",
+            ui.terminal.line(9));
+    assert_eq!(format!("{:.<39}{:.<41}", "", "O"), ui.terminal.line(3));
+    // ~ framed in the middle of the screen
+    let screen = screens.borrow()[0].clone();
+    let framed: Vec<_> = screen[1..8].iter().map(|l| &l[36..43]).collect();
+    assert_eq!(vec!["+-----+", "|.....|", "|...O.|", "|...O.|", "|...O.|", "|.....|", "+-----+"], framed);
+    assert!(screen[..9].iter().all(|l| l[..36].trim().is_empty() && l[43..].trim().is_empty()));
+    assert!(screen[9].starts_with("Gen: 1 / Alive: 3 / Sandbox: 5x5 at 36,2 / Edges: bounded /"),
+            "{}",
+            screen[9]);
+
+    // ~ discarded
+    screens.borrow_mut().clear();
+    let mut ui = UI::init(keys(&[Key::Char('s'), Key::Char('s'), Key::Esc]), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, &step).unwrap();
+    assert_eq!(world.live_cells(), w.live_cells());
+    assert_eq!(0, w.generation());
+    assert_eq!(3, screens.borrow().len());
+    assert!(ui.terminal.line(9).contains(" / discarded the sandbox after 3 gens"));
+    assert_eq!(format!("{:.<38}{:.<42}", "", "OOO"), ui.terminal.line(4));
+
+    // ~ regions beyond the world or the screen are refused
+    let ui = UI::init(FakeTerminal::new(80, 10, vec![]), &opts);
+    let err = |region: &str| ui.extract_sandbox(&world, region).unwrap_err();
+    assert_eq!("cell 80,2 is outside of the 80x10 world", err("75,0,6,3"));
+    assert_eq!("a 5x8 region does not fit the screen", err("0,0,5,8"));
+    assert_eq!("not a region (X,Y,W,H): 0,0,3", err("0,0,3"));
+    let huge = usize::max_value();
+    assert_eq!(format!("not a region (X,Y,W,H): {},0,2,2", huge), err(&format!("{},0,2,2", huge)));
+    assert_eq!(format!("a {}x2 region does not fit the screen", huge), err(&format!("0,0,{},2", huge)));
+    let (region, sandbox) = ui.extract_sandbox(&world, "38,4,3,1").unwrap();
+    assert_eq!((38, 4, 3, 1), (region.x, region.y, region.width, region.height));
+    assert_eq!((BoundaryMode::Bounded, 3), (sandbox.boundary(), sandbox.alive()));
+}
+
+#[test]
+fn test_file_prompt() {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let dir = env::temp_dir().join(format!("cgol-test-file-prompt-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+    let run = |keys: Vec<Key>, world: &World| {
+        let mut events: Vec<Event> = keys.into_iter().map(|k| Event::KeyEvent(Some(k))).collect();
+        events.push(Event::KeyEvent(Some(Key::Char('q'))));
+        let opts = test_options();
+        let mut ui = UI::init(FakeTerminal::new(120, 8, events), &opts);
+        let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+        (ui, w)
+    };
+    // ~ opens the prompt, replaces the suggested name and submits it
+    let typed = |open: char, name: &str, more: &[Key]| {
+        let mut keys = vec![Key::Char(open), Key::Ctrl('u')];
+        keys.extend(name.chars().map(Key::Char));
+        keys.push(Key::Enter);
+        keys.extend(more.iter().cloned());
+        keys
+    };
+    let mut world = World::empty(120, 7);
+    for &(x, y) in &[(1, 2), (2, 2), (3, 2)] {
+        world.set_alive(x, y, true);
+    }
+    assert_eq!("cgol-gen0.cells", suggested_filename(None, 0));
+    assert_eq!("a/glider-gen7.rle", suggested_filename(Some(Path::new("a/glider.rle")), 7));
+    assert_eq!("a/b-gen7.cells", suggested_filename(Some(Path::new("a/b.txt")), 7));
+
+    // ~ an existing file is overwritten only on pressing Enter again
+    let old = path("old.cells");
+    fs::write(&old, "O\n").unwrap();
+    let (ui, _) = run(typed('W', &old, &[Key::Esc]), &world);
+    assert_eq!("O\n", fs::read_to_string(&old).unwrap());
+    assert!(ui.prompt.is_none());
+    let (ui, _) = run(typed('W', &old, &[Key::Enter]), &world);
+    assert!(ui.terminal.line(7).contains(&format!(" / saved gen 0 to {}", old)), "{}", ui.terminal.line(7));
+    assert_eq!(3, parser::load_world(&old, parser::DEFAULT_MAX_LINE_LENGTH, false).unwrap().alive());
+
+    // ~ a failure keeps the name typed to correct it (cancelled here
+    // to quit); the format is told by the extension
+    let bad = path("missing/new.coords");
+    let good = path("new.coords");
+    let mut keys = typed('W', &bad, &[]);
+    let (ui, _) = run(typed('W', &bad, &[Key::Esc]), &world);
+    assert!(ui.prompt.is_none() && !Path::new(&bad).exists());
+    for _ in 0.."missing/new.coords".len() {
+        keys.push(Key::Backspace);
+    }
+    keys.extend("new.coords".chars().map(Key::Char));
+    keys.push(Key::Enter);
+    let (ui, _) = run(keys, &world);
+    assert!(ui.prompt.is_none() && ui.terminal.line(7).contains(&format!("saved gen 0 to {}", good)));
+    assert!(fs::read_to_string(&good).unwrap().contains("1,2\n2,2\n3,2\n"));
+
+    // ~ the prompt shows the last message and scrolls to the cursor
+    let mut ui = UI::init(FakeTerminal::new(30, 8, Vec::new()), &test_options());
+    ui.prompt = Some((Dialog::Load, Prompt::new("Load", "a.cells", &[])));
+    ui.message = Some("denied".to_owned());
+    ui.update_status(&world);
+    ui.flush();
+    assert_eq!(format!("{:30}", "Load: a.cells / denied"), ui.terminal.line(7));
+    ui.prompt = Some((Dialog::Load, Prompt::new("Load", "patterns/very/long/name.cells", &[])));
+    ui.message = None;
+    assert_eq!(Some(("patterns/very/long/name.cells ".to_owned(), 29)), ui.prompt_line());
+
+    // ~ loaded after an error, the name completed from the directory
+    let mut keys = typed('L', &path("nothing.cells"), &[Key::Ctrl('u')]);
+    keys.extend(path("ol").chars().map(Key::Char));
+    keys.extend(vec![Key::Tab, Key::Enter]);
+    let (ui, w) = run(keys, &World::empty(120, 7));
+    assert!(ui.terminal.line(7).contains(&format!(" / loaded {}", old)), "{}", ui.terminal.line(7));
+    assert_eq!((3, Some(PathBuf::from(&old))), (w.alive(), ui.file.clone()));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_remote() {
+    use remote::MemoryClient;
+    use terminal::fake::FakeTerminal;
+
+    let commands = ["{\"cmd\":\"status\"}",
+                    "{\"cmd\":\"step\",\"n\":3}",
+                    "{\"cmd\":\"resume\"}",
+                    "{\"cmd\":\"set-speed\",\"delay_ms\":30}",
+                    "{\"cmd\":\"jump\"}",
+                    "{\"cmd\":\"pause\"}",
+                    "{\"cmd\":\"reseed\",\"seed\":\"7\",\"density\":0.5}",
+                    "{\"cmd\":\"load-pattern\",\"name\":\"glider\"}"];
+    let client = MemoryClient::new(&(commands.join("\n") + "\n"));
+    let output = client.output.clone();
+    let mut world = World::empty(40, 10);
+    for x in 1..4 {
+        world.set_alive(x, 2, true);
+    }
+    let opts = test_options();
+    let mut ui = UI::init(FakeTerminal::new(40, 11, vec![Event::KeyEvent(Some(Key::Char('q')))]), &opts);
+    ui.remote = Some(Remote::with_client(client));
+    let w = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+
+    // ~ all answered in turn, the game going on from each command's
+    // effect
+    let seeded = World::random_seeded_with_density(7, ui.frame.width, ui.frame.height, 0.5).alive();
+    let status = |gen: usize, alive: usize, paused: bool, seed: &str, delay_ms: usize| {
+        format!("{{\"generation\":{},\"alive\":{},\"paused\":{},\"rule\":\"B3/S23\",\
+                 \"seed\":{},\"delay_ms\":{}}}",
+                gen,
+                alive,
+                paused,
+                seed,
+                delay_ms)
+    };
+    let replies = String::from_utf8(output.borrow().clone()).unwrap();
+    assert_eq!(vec![status(0, 3, true, "null", 100),
+                    status(3, 3, true, "null", 100),
+                    status(3, 3, false, "null", 100),
+                    // ~ the nearest of 100 / 2^n ms
+                    status(3, 3, false, "null", 25),
+                    "{\"error\":\"Unknown command: jump\"}".to_owned(),
+                    status(3, 3, true, "null", 25),
+                    status(0, seeded, true, "\"7\"", 25),
+                    // ~ a pattern loaded rather than generated
+                    status(0, 5, true, "null", 25)],
+               replies.lines().collect::<Vec<_>>());
+    // ~ the glider stamped at the center
+    assert_eq!(vec![(19, 4), (20, 5), (18, 6), (19, 6), (20, 6)], w.live_cells());
+    assert!(ui.terminal.line(10).contains("stamped Glider"));
+
+    // ~ with nothing open, a command leaves the sandbox be
+    let client = MemoryClient::new("{\"cmd\":\"step\",\"n\":1}\n");
+    let events = vec![Event::KeyEvent(Some(Key::Esc)), Event::KeyEvent(Some(Key::Char('q')))];
+    let mut ui = UI::init(FakeTerminal::new(40, 11, events), &opts);
+    ui.remote = Some(Remote::with_client(client));
+    let mut keys = vec![Key::Char('X'), Key::Ctrl('u')];
+    keys.extend("16,2,5,5".chars().map(Key::Char));
+    keys.push(Key::Enter);
+    ui.pending.extend(keys.into_iter().map(Some));
+    let w = run_(&mut ui, Some(World::random_seeded(3, 40, 10)), &opts, advance_one_step).unwrap();
+    assert_eq!(0, w.generation());
+    assert_eq!(Some("discarded the sandbox after 1 gens"), ui.message.as_ref().map(|m| &m[..]));
+}
+
+#[test]
+fn test_fit_line() {
+    use terminal::fake::FakeTerminal;
+
+    let fit = |s: &str, width, pad| {
+        let mut line = s.to_owned();
+        fit_line(&mut line, width, pad);
+        line
+    };
+    assert_eq!("Gen: 1 / Alive: 3   ", fit("Gen: 1 / Alive: 3", 20, true));
+    assert_eq!("Gen: 1 / Alive: 3", fit("Gen: 1 / Alive: 3", 20, false));
+    assert_eq!("Gen: 1 / A…", fit("Gen: 1 / Alive: 3", 11, true));
+    // ~ characters rather than bytes
+    assert_eq!("größer…", fit("größer als die Welt", 7, false));
+    assert_eq!("grü  ", fit("grü", 5, true));
+    assert_eq!("…", fit("ab", 1, true));
+    assert_eq!("", fit("ab", 0, true));
+    assert_eq!("ab", fit("ab", 2, false));
+
+    // ~ a narrow terminal cuts the status line short
+    let opts = test_options();
+    let mut ui = UI::init(FakeTerminal::with_keys(24, 4, "q"), &opts);
+    ui.message = Some("ünreadable.cells: no such file".to_owned());
+    run_(&mut ui, Some(World::empty(24, 4)), &opts, advance_one_step).unwrap();
+    assert_eq!("Gen: 0 / Alive: 0 / ünr…", ui.terminal.line(3));
+}
+
+#[test]
+fn test_delay_keys() {
+    let ms = Duration::milliseconds;
+    assert_eq!(vec![Key::Char('+'); 2], delay_keys(ms(100), ms(30)));
+    assert_eq!(vec![Key::Char('-'); 3], delay_keys(ms(100), ms(700)));
+    assert!(delay_keys(ms(100), ms(110)).is_empty());
+    // ~ beyond the reachable delays
+    assert_eq!(vec![Key::Char('+'); 7], delay_keys(ms(100), ms(1)));
+    assert_eq!(vec![Key::Char('-'); 10], delay_keys(ms(100), ms(100000)));
+}
+
+#[test]
+fn test_damage_confined() {
+    use terminal::fake::FakeTerminal;
+
+    // ~ a still block and a lone cell dying with the next generation
+    // as the only change
+    let world = |x, y| {
+        let mut world = World::empty(40, 12);
+        for &(x, y) in &[(2, 8), (3, 8), (2, 9), (3, 9), (x, y)] {
+            world.set_alive(x, y, true);
+        }
+        world
+    };
+    // ~ the prints of the step alone
+    let step = |ui: &mut UI<FakeTerminal>, world: &mut World| {
+        ui.terminal.take_printed();
+        advance_one_step(ui, world);
+    };
+    // ~ the positions printed apart from the allowed ones and the
+    // status line
+    fn outside<F: Fn(usize, usize) -> bool>(printed: &[(usize, usize)], allowed: F) -> Vec<(usize, usize)> {
+        printed.iter().cloned().filter(|&(x, y)| y != 11 && !allowed(x, y)).collect()
+    }
+    // ~ the mini-map of the 40x12 world covers 20x6 characters in the
+    // top right corner
+    let in_map = |x: usize, y: usize| x >= 20 && y < 6;
+
+    // ~ the cell apart from the map: just the cell (and the status
+    // line) are printed
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "Msq"), &test_options());
+    run_(&mut ui, Some(world(5, 3)), &test_options(), step).unwrap();
+    let printed = ui.terminal.take_printed();
+    assert!(printed.contains(&(5, 3)));
+    assert_eq!(Vec::<(usize, usize)>::new(), outside(&printed, |x, y| (x, y) == (5, 3)));
+    assert_eq!('.', ui.terminal.cell(5, 3).ch);
+
+    // ~ the cell beneath the map: the map is drawn again on top
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "Msq"), &test_options());
+    run_(&mut ui, Some(world(25, 3)), &test_options(), step).unwrap();
+    let printed = ui.terminal.take_printed();
+    assert!(printed.contains(&(20, 0)) && printed.contains(&(39, 5)));
+    assert_eq!(Vec::<(usize, usize)>::new(), outside(&printed, in_map));
+    assert!(Color::Blue == ui.terminal.cell(25, 3).bg);
+
+    // ~ coloring by density redraws the neighbours of the cell as well
+    let mut opts = test_options();
+    opts.density_color = true;
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "sq"), &opts);
+    run_(&mut ui, Some(world(5, 3)), &opts, step).unwrap();
+    let printed = ui.terminal.take_printed();
+    let near = |x: usize, y: usize| x >= 4 && x <= 6 && y >= 2 && y <= 4;
+    assert_eq!(Vec::<(usize, usize)>::new(), outside(&printed, near));
+}
+
+#[test]
+fn test_goto_generation() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    // ~ quits an hour after the keys, i.e. once skipping ahead finished:
+    // the terminal's clock jumps to the key only while it is waited for
+    let run = |world: World, keys: &[Key]| {
+        let mut events: Vec<_> = keys.iter()
+                                     .map(|&k| (StdDuration::from_millis(0), Event::KeyEvent(Some(k))))
+                                     .collect();
+        events.push((StdDuration::from_secs(3600), Event::KeyEvent(Some(Key::Char('q')))));
+        let (width, height) = (world.width(), world.height());
+        let mut ui = UI::init(FakeTerminal::with_timed_events(width, height, events), &opts);
+        let w = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+        (ui, w)
+    };
+    let goto = |typed: &str, more: &[Key]| {
+        let mut keys = vec![Key::Char('g')];
+        keys.extend(typed.chars().map(Key::Char));
+        keys.push(Key::Enter);
+        keys.extend(more.iter().cloned());
+        keys
+    };
+    // ~ skips up to the generation, drawing just that one
+    let (ui, w) = run(World::random_seeded(7, 80, 6), &goto("50", &[]));
+    assert_eq!(50, w.generation());
+    assert!(ui.terminal.line(5).contains(" / reached gen 50 in "), "{}", ui.terminal.line(5));
+    assert!(ui.fast_forward.is_none() && ui.delay.is_some());
+    let cells = patterns::STAMPS.iter().find(|&&(name, _)| name == "Pulsar").unwrap().1;
+    let mut pattern = parser::parse_cells(cells).unwrap();
+    pattern.trim(0);
+    let mut pulsar = World::empty(20, 17);
+    pulsar.blit(3, 2, &pattern);
+    let mut phases = vec![pulsar.clone()];
+    for _ in 0..2 {
+        let mut next = phases.last().unwrap().clone();
+        next.advance_generation(|_, _, _| {});
+        phases.push(next);
+    }
+    let (ui, w) = run(pulsar.clone(), &goto("1000", &[]));
+    assert_eq!((1000, phases[1].live_cells()), (w.generation(), w.live_cells()));
+    for y in 0..w.height() - 1 {
+        let row: String = (0..w.width()).map(|x| if w.is_alive(x, y) { 'O' } else { '.' }).collect();
+        assert_eq!(row, ui.terminal.line(y));
+    }
+    // ~ or until cancelled
+    let (ui, w) = run(World::random_seeded(7, 80, 6), &goto("1000000000", &[Key::Esc]));
+    assert!(w.generation() < 1000000000);
+    assert!(ui.terminal.line(5).contains(&format!(" / stopped at gen {}", w.generation())),
+            "{}",
+            ui.terminal.line(5));
+    assert!(ui.fast_forward.is_none() && ui.delay.is_some());
+    // ~ back only as far as the history goes; the prompt stays to
+    // correct the input
+    let mut keys = vec![Key::Char('s'); 3];
+    keys.extend(goto("1", &[]));
+    let (_, w) = run(pulsar.clone(), &keys);
+    assert_eq!((1, phases[1].live_cells()), (w.generation(), w.live_cells()));
+    keys.extend(goto("50", &[]));
+    keys.extend(goto("2", &[Key::Backspace, Key::Char('9'), Key::Char('9'), Key::Enter]));
+    let (_, w) = run(pulsar, &keys);
+    assert_eq!((99, phases[0].live_cells()), (w.generation(), w.live_cells()));
+    for typed in &["0", "x"] {
+        let (_, w) = run(World::random_seeded(7, 80, 6),
+                         &goto(typed, &[Key::Backspace, Key::Char('5'), Key::Enter]));
+        assert_eq!(5, w.generation());
+    }
+}
+
+#[test]
+fn test_autoframe() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    let mut world = World::empty(100, 30);
+    for &(x, y) in &[(80, 22), (81, 22), (80, 23), (81, 23)] {
+        world.set_alive(x, y, true);
+    }
+    let run = |opts: &Options, keys: &str| {
+        let keys = format!("{}{}q", keys, iter::repeat('s').take(20).collect::<String>());
+        let mut ui = UI::init(FakeTerminal::with_keys(60, 10, &keys), opts);
+        run_(&mut ui, Some(world.clone()), opts, advance_one_step).unwrap();
+        ui
+    };
+    // ~ centering the block on the 60x9 cells shown, as far as the world
+    // reaches
+    let ui = run(&opts, "F");
+    assert_eq!((40, 19), ui.viewport);
+    assert!(ui.terminal.line(9).contains(" / Autoframe"), "{}", ui.terminal.line(9));
+    assert_eq!((0, 0), run(&opts, "").viewport);
+    // ~ on from the start, and toggled off
+    opts.autoframe = true;
+    assert_eq!((40, 19), run(&opts, "").viewport);
+    let ui = run(&opts, "F");
+    assert_eq!((0, 0), ui.viewport);
+    assert!(!ui.terminal.line(9).contains("Autoframe"));
+}
+
+#[test]
+fn test_view_marks() {
+    use terminal::fake::FakeTerminal;
+
+    // ~ only auto-expanding worlds get larger than the screen
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    let run = |keys: &[Key]| {
+        let events = keys.iter().chain(&[Key::Char('q')]).map(|&k| Event::KeyEvent(Some(k))).collect();
+        let mut ui = UI::init(FakeTerminal::new(60, 10, events), &opts);
+        let w = run_(&mut ui, Some(World::random_seeded(5, 100, 30)), &opts, advance_one_step).unwrap();
+        (ui, w)
+    };
+    let shown = |ui: &UI<FakeTerminal>, w: &World, line: usize| {
+        let (vx, vy) = ui.viewport;
+        (vx..vx + 60).map(|x| if w.is_alive(x, vy + line) { 'O' } else { '.' }).collect::<String>()
+    };
+    // ~ panned by a quarter of the screen, as far as the world reaches
+    let (ui, w) = run(&[Key::Right, Key::Right, Key::Down]);
+    assert_eq!((30, 2), ui.viewport);
+    for line in 0..9 {
+        assert_eq!(shown(&ui, &w, line), ui.terminal.line(line));
+    }
+    assert!(ui.terminal.line(9).contains(" / World: 100x30 at 30,2 "), "{}", ui.terminal.line(9));
+    let mut keys = vec![Key::Down; 20];
+    keys.extend(vec![Key::Right; 20]);
+    let (ui, _) = run(&keys);
+    assert_eq!((40, 21), ui.viewport);
+    // ~ marked, left, and returned to
+    let (ui, _) = run(&[Key::Right, Key::Char('v'), Key::Char('3'), Key::Char('V'), Key::Char('0')]);
+    assert_eq!(((0, 0), Some((15, 0))), (ui.viewport, ui.view_marks[2]));
+    assert_eq!(Some("back to the top left corner"), ui.message.as_ref().map(|m| &m[..]));
+    let (ui, _) = run(&[Key::Right, Key::Char('v'), Key::Char('3'), Key::Left, Key::Char('V'),
+                        Key::Char('3')]);
+    assert_eq!(((15, 0), Some("back to view 3")), (ui.viewport, ui.message.as_ref().map(|m| &m[..])));
+    let (ui, _) = run(&[Key::Right, Key::Char('V'), Key::Char('4')]);
+    assert_eq!(((15, 0), Some("view 4 is not marked")), (ui.viewport, ui.message.as_ref().map(|m| &m[..])));
+    // ~ forgotten with the world
+    let (ui, _) = run(&[Key::Right, Key::Char('v'), Key::Char('3'), Key::Char('r')]);
+    assert_eq!(None, ui.view_marks[2]);
+    // ~ moved along with the cells as the world grows to the left and
+    // top, like the viewport
+    let mut up_left = World::empty(100, 30);
+    // ~ a glider heading left and up
+    for &(x, y) in &[(20, 5), (21, 5), (22, 5), (20, 6), (21, 7)] {
+        up_left.set_alive(x, y, true);
+    }
+    let mut keys = vec![Key::Right, Key::Char('v'), Key::Char('3')];
+    keys.extend(vec![Key::Char('s'); 30]);
+    keys.push(Key::Char('q'));
+    let events = keys.iter().map(|&k| Event::KeyEvent(Some(k))).collect();
+    let mut ui = UI::init(FakeTerminal::new(60, 10, events), &opts);
+    let w = run_(&mut ui, Some(up_left), &opts, advance_one_step).unwrap();
+    let grown = (w.width() - 100, w.height() - 30);
+    assert!(grown.1 > 0, "{:?}", grown);
+    assert_eq!((Some((15 + grown.0, grown.1)), (15 + grown.0, grown.1)), (ui.view_marks[2], ui.viewport));
+    // ~ a world of the screen's size pans just to uncover its last row
+    // from beneath the status line
+    let opts = test_options();
+    let events = [Key::Right, Key::Down, Key::Char('q')].iter().map(|&k| Event::KeyEvent(Some(k))).collect();
+    let mut ui = UI::init(FakeTerminal::new(40, 10, events), &opts);
+    run_(&mut ui, Some(World::random_seeded(5, 100, 30)), &opts, advance_one_step).unwrap();
+    assert_eq!((0, 1), ui.viewport);
+}
+
+#[test]
+fn test_read_only() {
+    use bindings::{BINDINGS, Context};
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.read_only = true;
+    let initial = World::random_seeded(9, 100, 11);
+    let run = |keys: &[Key]| {
+        // ~ leaving any prompt or chord opened before quitting
+        let quit = [Key::Esc, Key::Char('q')];
+        let events = keys.iter().chain(&quit).map(|&k| Event::KeyEvent(Some(k))).collect();
+        let mut ui = UI::init(FakeTerminal::new(100, 11, events), &opts);
+        let w = run_(&mut ui, Some(initial.clone()), &opts, advance_one_step).unwrap();
+        (ui, w)
+    };
+    let state = |w: &World| (bookmarks::fingerprint(w), w.generation(), w.rule(), w.boundary());
+    // ~ the keys changing the world leave it alone and name what they
+    // would have done
+    for b in BINDINGS.iter().filter(|b| b.mutating) {
+        let mut keys = match b.context {
+            Context::BookmarksShown => vec![Key::Char('B'), b.key],
+            _ => vec![b.key],
+        };
+        // ~ the keys completing the action if it were not refused
+        keys.extend(&[Key::Char('2'), Key::Enter]);
+        let (ui, w) = run(&keys);
+        assert_eq!(state(&initial), state(&w), "{:?}", b.key);
+        assert!(ui.stamp.is_none() && ui.rule_editor.is_none() && ui.read_only);
+        let blocked = format!("read-only mode: {} blocked", b.action);
+        assert!(ui.terminal.line(10).contains(&blocked), "{:?}: {}", b.key, ui.terminal.line(10));
+        assert!(ui.terminal.line(10).contains(" / READ-ONLY / "));
+    }
+    // ~ the others work as ever
+    let allowed = BINDINGS.iter().filter(|b| !b.mutating && b.context == Context::Always);
+    for b in allowed.filter(|b| b.key != Key::Char('q')) {
+        let (ui, _) = run(&[b.key]);
+        assert!(ui.message.as_ref().map_or(true, |m| !m.contains("read-only")), "{:?}", b.key);
+    }
+    let (_, w) = run(&[Key::Char('s'), Key::Char('s')]);
+    assert_eq!(2, w.generation());
+    // ~ no key handled yet missing from the table changes anything but
+    // the generation shown
+    let mut keys: Vec<Key> = (b' '..b'~' + 1).map(|c| Key::Char(c as char)).collect();
+    keys.extend((1..13).map(Key::F));
+    keys.extend((b'a'..b'z' + 1).map(|c| Key::Ctrl(c as char)));
+    let playing: Vec<Key> = " sRg0@".chars().map(Key::Char).collect();
+    for &key in keys.iter().filter(|k| !playing.contains(k)) {
+        if bindings::lookup(key, |_| true).map_or(false, |b| b.mutating) {
+            continue;
+        }
+        let (ui, w) = run(&[key, Key::Char('2'), Key::Enter]);
+        assert_eq!(state(&initial), state(&w), "{:?}", key);
+        let saved = (ui.bookmarks.is_modified(), ui.macros.is_modified(), ui.slots.is_modified());
+        assert_eq!((false, false, false), saved, "{:?}", key);
+    }
+    // ~ changes are allowed again only after the whole chord; a key
+    // off the chord is dropped
+    let keys: Vec<Key> = "!unlo!unlocxw".chars().map(Key::Char).collect();
+    let (ui, w) = run(&keys);
+    assert_eq!((true, BoundaryMode::Wrap), (ui.read_only, w.boundary()));
+    assert_eq!(Some("read-only mode: switch the edges blocked"), ui.message.as_ref().map(|m| &m[..]));
+    let keys: Vec<Key> = "!unlockw".chars().map(Key::Char).collect();
+    let (ui, w) = run(&keys);
+    assert!(!ui.read_only);
+    assert!(w.boundary() != BoundaryMode::Wrap);
+}
+
+#[test]
+fn test_perf_counters() {
+    use terminal::fake::FakeTerminal;
+
+    let mut world = World::empty(40, 12);
+    for x in 4..7 {
+        world.set_alive(x, 5, true);
+    }
+    let mut ui = UI::init(FakeTerminal::with_keys(40, 12, "sssq"), &test_options());
+    run_(&mut ui, Some(world), &test_options(), advance_one_step).unwrap();
+    let p = &ui.perf;
+    // ~ a blinker turns two cells on and two off with every generation
+    assert_eq!((3, 3 * 40 * 12, 12, 4), (p.generations, p.cells_evaluated, p.changes, p.peak_changes));
+    assert_eq!((1, 3), (p.full_redraws, p.incremental_updates));
+    assert_eq!(ui.terminal.print_calls() as u64, p.prints());
+    assert!(!p.to_string().contains("step_ns"));
+}
+
+#[test]
+fn test_incremental_frames() {
+    use rustbox::Event;
+    use terminal::fake::FakeTerminal;
+
+    let key = |c| Event::KeyEvent(Some(Key::Char(c)));
+    let run = |events: Vec<Event>| {
+        let mut ui = UI::init(FakeTerminal::new(80, 24, events), &test_options());
+        run_(&mut ui, Some(World::random_seeded(7, 80, 24)), &test_options(), advance_one_step).unwrap();
+        ui
+    };
+
+    // ~ the first frame is drawn in full, the generations following it
+    // by their changes, also while playing backwards
+    let first = run(vec![key('q')]).perf.prints();
+    let ui = run(vec![key('s'), key('s'), key('R'), Event::NoEvent, Event::NoEvent, key('q')]);
+    assert_eq!((1, 4), (ui.perf.full_redraws, ui.perf.incremental_updates));
+    assert_eq!(ui.terminal.print_calls() as u64, ui.perf.prints());
+    // ~ each frame prints the cells changed (the ones going forward
+    // undone going backwards) and the status line along with the
+    // world's row behind it; far less than a full frame
+    let frames = ui.perf.prints() - first;
+    let changes = ui.perf.changes;
+    assert!(frames <= 2 * changes + 5 * 81, "{} prints for {} changes", frames, changes);
+    assert!(frames < 2 * first, "{} prints for 4 frames, {} for a full one", frames, first);
+
+    // ~ a generated world is drawn in full again
+    let ui = run(vec![key('s'), key('r'), key('s'), key('q')]);
+    assert_eq!((2, 2), (ui.perf.full_redraws, ui.perf.incremental_updates));
+}
+
+#[test]
+fn test_screenshot() {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::process;
+    use terminal::fake::FakeTerminal;
+
+    let read = |path: &str| {
+        let mut text = String::new();
+        File::open(path).unwrap().read_to_string(&mut text).unwrap();
+        fs::remove_file(path).unwrap();
+        text
+    };
+    let mut opts = test_options();
+    let path = env::temp_dir().join(format!("cgol-test-screenshot-on-exit-{}.ans", process::id()));
+    opts.screenshot_on_exit = Some(path.to_str().unwrap().to_owned());
+    let events = vec![Event::KeyEvent(Some(Key::F(12))), Event::KeyEvent(Some(Key::Char('q')))];
+    let mut ui = UI::init(FakeTerminal::new(30, 6, events), &opts);
+    run_(&mut ui, Some(World::random_seeded(3, 30, 5)), &opts, advance_one_step).unwrap();
+    ui.screenshot_on_exit(&opts).unwrap();
+
+    // ~ the screen before the message telling where it went
+    let message = ui.message.clone().unwrap();
+    assert!(message.starts_with("screen written to "), "{}", message);
+    let paths: Vec<_> = message["screen written to ".len()..].split(" and ").collect();
+    assert!(paths[0].ends_with(".txt") && paths[1].ends_with(".ans"), "{}", message);
+    let text = read(paths[0]);
+    let ansi = read(paths[1]);
+    assert_eq!(6, text.lines().count());
+    assert_eq!(ui.terminal.line(0).trim_end(), text.lines().next().unwrap());
+    assert!(!text.contains("screen w"));
+    assert!(ansi.contains("\x1b[0m\n") && ansi.contains(ui.terminal.line(0).trim_end()));
+    // ~ on exit: with the message (cut off)
+    let on_exit = read(path.to_str().unwrap());
+    assert!(on_exit.contains("/ screen w"));
+    assert!(on_exit.starts_with("\x1b["));
+}
+
+#[test]
+fn test_compare() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.compare = Rule::parse("B36/S23");
+    let world = World::random_seeded(11, 60, 9);
+    let keys = format!("{}q", iter::repeat('s').take(12).collect::<String>());
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 10, &keys), &opts);
+    let w = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    let ghost = ui.ghost.as_ref().unwrap();
+    assert_eq!(w.generation(), ghost.world().generation());
+    let differing = ghost.differing();
+    assert!(differing > 0);
+    assert!(ui.terminal.line(9).contains(&format!(" / vs B36/S23: {} differ", differing)),
+            "{}",
+            ui.terminal.line(9));
+    // ~ the cells alive under one of the rules only in its color
+    for y in 0..9 {
+        for x in 0..60 {
+            let expected = match (w.is_alive(x, y), ghost.world().is_alive(x, y)) {
+                (true, false) => Color::Green,
+                (false, true) => Color::Magenta,
+                _ => ui.theme.cell(w.is_alive(x, y), None, None).0,
+            };
+            let cell = ui.terminal.cell(x, y);
+            assert!(cell.fg == expected, "{},{}", x, y);
+            assert_eq!(w.is_alive(x, y) || ghost.world().is_alive(x, y), cell.ch == ui.alive_char);
+        }
+    }
+}
+
+#[test]
+fn test_rule_schedule() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.schedule = Some(RuleSchedule::parse("0:B3/S23,5:B36/S23").unwrap());
+    let world = World::random_seeded(11, 60, 9);
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 10, "sssq"), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert_eq!((3, world::CONWAY), (w.generation(), w.rule()));
+    assert!(ui.terminal.line(9).contains(" / Next: B36/S23 in 2"), "{}", ui.terminal.line(9));
+
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 10, "ssssssq"), &opts);
+    let w = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!((6, "B36/S23".to_owned()), (w.generation(), w.rule().to_string()));
+    assert_eq!(Some("gen 5: switched to B36/S23".to_owned()), ui.message);
+    assert!(!ui.terminal.line(9).contains("Next:"), "{}", ui.terminal.line(9));
+
+    // ~ a sandbox keeps the rule of the world it was taken from rather
+    // than following the schedule from its own generation zero
+    use std::cell::RefCell;
+    let mut keys = vec![Key::Char('s'); 6];
+    keys.extend(&[Key::Char('X'), Key::Ctrl('u')]);
+    keys.extend("10,2,5,5".chars().map(Key::Char));
+    keys.extend(&[Key::Enter, Key::Char('s'), Key::Char('s'), Key::Esc, Key::Char('q')]);
+    let events = keys.into_iter().map(|k| Event::KeyEvent(Some(k))).collect();
+    let mut ui = UI::init(FakeTerminal::new(60, 10, events), &opts);
+    let rules = RefCell::new(Vec::new());
+    let step = |ui: &mut UI<FakeTerminal>, world: &mut World| {
+        advance_one_step(ui, world);
+        rules.borrow_mut().push((world.generation(), world.rule().to_string(), ui.sandbox.is_some()));
+    };
+    run_(&mut ui, Some(World::random_seeded(11, 60, 9)), &opts, &step).unwrap();
+    let rules = rules.into_inner();
+    assert_eq!(8, rules.len());
+    assert_eq!((6, "B36/S23".to_owned(), false), rules[5]);
+    assert_eq!(vec![(1, "B36/S23".to_owned(), true), (2, "B36/S23".to_owned(), true)], rules[6..].to_vec());
+}
+
+#[test]
+fn test_nudge_stamped() {
+    use terminal::fake::FakeTerminal;
+
+    let keys = |keys: &[Key]| {
+        let mut events: Vec<Event> = keys.iter().map(|&k| Event::KeyEvent(Some(k))).collect();
+        events.push(Event::KeyEvent(Some(Key::Char('q'))));
+        FakeTerminal::new(60, 12, events)
+    };
+    let mut world = World::empty(60, 12);
+    world.set_alive(10, 2, true);
+    let opts = test_options();
+    // ~ the glider stamped at 28,4 (.O. / ..O / OOO), moved two cells
+    // to the right and one up
+    let stamped = [(29, 4), (30, 5), (28, 6), (29, 6), (30, 6)];
+    let nudged: Vec<_> = stamped.iter().map(|&(x, y)| (x + 2, y - 1)).collect();
+    let mut ui = UI::init(keys(&[Key::Char('S'), Key::Enter, Key::Right, Key::Up, Key::Right]), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    let mut expected = nudged.clone();
+    expected.push((10, 2));
+    expected.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!((expected.clone(), 6), (w.live_cells(), w.alive()));
+    assert!(ui.terminal.line(11).contains(" / nudged Glider"), "{}", ui.terminal.line(11));
+    assert!(expected.iter().all(|&(x, y)| ui.terminal.cell(x, y).ch == 'O'));
+    assert!(stamped.iter().all(|&(x, y)| nudged.contains(&(x, y)) || ui.terminal.cell(x, y).ch == '.'));
+
+    // ~ any other key lets go of the pattern: the arrows pan again
+    let mut ui = UI::init(keys(&[Key::Char('S'), Key::Enter, Key::Char('+'), Key::Right]), &opts);
+    let w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.placed.is_none());
+    let mut expected = stamped.to_vec();
+    expected.push((10, 2));
+    expected.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(expected, w.live_cells());
+
+    // ~ cells pushed beyond the edges of a bounded world are dropped
+    world.set_boundary(BoundaryMode::Bounded);
+    let mut moves = vec![Key::Char('S'), Key::Enter];
+    moves.extend(iter::repeat(Key::Down).take(6));
+    let mut ui = UI::init(keys(&moves), &opts);
+    let w = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert_eq!((vec![(10, 2), (29, 10), (30, 11)], 3), (w.live_cells(), w.alive()));
+    assert!(ui.terminal.line(11).contains(" / nudged Glider; 3 cells dropped"), "{}", ui.terminal.line(11));
+
+    // ~ a live cell the glider was stamped over stays where it was
+    let mut world = World::empty(60, 12);
+    world.set_alive(29, 4, true);
+    let mut ui = UI::init(keys(&[Key::Char('S'), Key::Enter, Key::Right]), &opts);
+    let w = run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    let mut expected: Vec<_> = stamped[1..].iter().map(|&(x, y)| (x + 1, y)).collect();
+    expected.push((29, 4));
+    expected.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!((expected, 5), (w.live_cells(), w.alive()));
+}
+
+#[test]
+fn test_count_region_auto_expand() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    let region = Rect {
+        x: 40,
+        y: 15,
+        width: 5,
+        height: 5,
+    };
+    opts.count_region = Some(region);
+    let mut up_left = World::empty(60, 21);
+    // ~ a glider heading left and up, away from the region
+    for &(x, y) in &[(20, 5), (21, 5), (22, 5), (20, 6), (21, 7)] {
+        up_left.set_alive(x, y, true);
+    }
+    // ~ the region moves along with the cells as the world grows to the
+    // left and top
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 21, &format!("{}q", "s".repeat(30))), &opts);
+    let w = run_(&mut ui, Some(up_left.clone()), &opts, advance_one_step).unwrap();
+    let grown = (w.width() - 60, w.height() - 21);
+    assert!(grown.1 > 0, "{:?}", grown);
+    let counter = ui.counter.as_ref().unwrap();
+    assert_eq!((40 + grown.0, 15 + grown.1, 0), (counter.region().x, counter.region().y, counter.count()));
+    // ~ and is back where given for a new world
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 21, &format!("{}rq", "s".repeat(30))), &opts);
+    run_(&mut ui, Some(up_left), &opts, advance_one_step).unwrap();
+    assert_eq!(region, ui.counter.as_ref().unwrap().region());
+}
+
+#[test]
+fn test_reference_auto_expand() {
+    use terminal::fake::FakeTerminal;
+
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    // ~ a block next to the top left corner; the world grows to the
+    // left and top, and the reference along with it
+    let mut block = World::empty(100, 10);
+    for &(x, y) in &[(1, 1), (2, 1), (1, 2), (2, 2)] {
+        block.set_alive(x, y, true);
+    }
+    opts.reference = Some(block.clone());
+    let mut ui = UI::init(FakeTerminal::with_keys(100, 11, "sq"), &opts);
+    let w = run_(&mut ui, Some(block), &opts, advance_one_step).unwrap();
+    assert_eq!(vec![(9, 9), (10, 9), (9, 10), (10, 10)], w.live_cells());
+    assert!(ui.terminal.line(10).contains(" / Reference: 0 differ"), "{}", ui.terminal.line(10));
+    assert_eq!(w.live_cells(), ui.reference.as_ref().unwrap().world().live_cells());
+}
+
+#[test]
+fn test_follow() {
+    use terminal::fake::FakeTerminal;
+
+    // ~ only auto-expanding worlds get larger than the screen
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    let mut world = World::empty(240, 120);
+    // ~ a glider heading right and down
+    for &(x, y) in &[(61, 30), (62, 31), (60, 32), (61, 32), (62, 32)] {
+        world.set_alive(x, y, true);
+    }
+    // ~ engaged and caught up with on the 60x20 cells shown
+    let keys = format!("T{}q", iter::repeat('s').take(40).collect::<String>());
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 21, &keys), &opts);
+    let mut w = run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.terminal.line(20).contains(" / Following"), "{}", ui.terminal.line(20));
+    for _ in 0..200 {
+        advance_one_step(&mut ui, &mut w);
+        let (x, y) = ui.follow.as_ref().unwrap().center();
+        let (vx, vy) = ui.viewport;
+        // ~ within the central third
+        assert!(x >= vx + 20 && x < vx + 40 && y >= vy + 6 && y < vy + 14, "{:?} at {:?}", (x, y), (vx, vy));
+    }
+    assert_eq!((240, 120), (w.width(), w.height()));
+    // ~ some 60 cells away from where it started
+    assert_eq!((121, 91), ui.follow.as_ref().unwrap().center());
+
+    // ~ the glider deleted
+    for (x, y) in ui.follow.as_ref().unwrap().cells().to_vec() {
+        w.set_alive(x, y, false);
+    }
+    advance_one_step(&mut ui, &mut w);
+    assert!(ui.follow.is_none());
+    assert_eq!(Some("stopped following: the object vanished"), ui.message.as_ref().map(|m| &m[..]));
+
+    // ~ on a torus (as large as the screen, as it does not expand)
+    // across its edges; neither lost nor confused with another object
+    let mut opts = test_options();
+    opts.auto_expand = None;
+    let mut torus = World::empty(60, 21);
+    for &(x, y) in &[(41, 10), (42, 11), (40, 12), (41, 12), (42, 12)] {
+        torus.set_alive(x, y, true);
+    }
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 21, "Tq"), &opts);
+    let mut w = run_(&mut ui, Some(torus), &opts, advance_one_step).unwrap();
+    assert_eq!((60, 21, BoundaryMode::Wrap), (w.width(), w.height(), w.boundary()));
+    let mut crossed = (false, false);
+    for _ in 0..200 {
+        let before = ui.follow.as_ref().unwrap().center();
+        advance_one_step(&mut ui, &mut w);
+        let f = ui.follow.as_ref().unwrap();
+        let (x, y) = f.center();
+        crossed = (crossed.0 || x < before.0, crossed.1 || y < before.1);
+        assert_eq!((5, 5), (f.cells().len(), w.alive()));
+        assert!(f.cells().iter().all(|&(x, y)| w.is_alive(x, y)));
+    }
+    assert_eq!((true, true), crossed);
+    assert_eq!(((41 + 50) % 60, (11 + 50) % 21), ui.follow.as_ref().unwrap().center());
+
+    // ~ skipping ahead in an auto-expanding world growing to the left
+    // and top; the object moves along with the cells
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    let mut up_left = World::empty(60, 20);
+    // ~ a glider heading left and up
+    for &(x, y) in &[(10, 5), (11, 5), (12, 5), (10, 6), (11, 7)] {
+        up_left.set_alive(x, y, true);
+    }
+    let mut events: Vec<_> = "Tg40\n".chars()
+                                     .map(|c| if c == '\n' { Key::Enter } else { Key::Char(c) })
+                                     .map(|k| (StdDuration::from_millis(0), Event::KeyEvent(Some(k))))
+                                     .collect();
+    // ~ once skipping ahead finished (see `test_goto_generation`)
+    events.push((StdDuration::from_secs(3600), Event::KeyEvent(Some(Key::Char('q')))));
+    let mut ui = UI::init(FakeTerminal::with_timed_events(60, 21, events), &opts);
+    let w = run_(&mut ui, Some(up_left), &opts, advance_one_step).unwrap();
+    assert_eq!(40, w.generation());
+    assert!(w.width() > 60 && w.height() > 21);
+    let mut cells = ui.follow.as_ref().expect("still following").cells().to_vec();
+    cells.sort();
+    let mut live = w.live_cells();
+    live.sort();
+    assert_eq!(live, cells);
+
+    // ~ panning breaks off, as does toggling it off
+    let mut ui = UI::init(FakeTerminal::new(60, 21, vec![Event::KeyEvent(Some(Key::Char('T'))),
+                                                         Event::KeyEvent(Some(Key::Left)),
+                                                         Event::KeyEvent(Some(Key::Char('q')))]),
+                          &opts);
+    run_(&mut ui, Some(world.clone()), &opts, advance_one_step).unwrap();
+    assert!(ui.follow.is_none());
+    assert!(ui.terminal.line(20).contains("stopped following: the view was moved"));
+    let mut ui = UI::init(FakeTerminal::with_keys(60, 21, "TTq"), &opts);
+    run_(&mut ui, Some(world), &opts, advance_one_step).unwrap();
+    assert!(ui.follow.is_none() && !ui.terminal.line(20).contains(" / Following"));
+}
+
+#[test]
+fn test_shrink_mid_frame() {
+    use terminal::fake::FakeTerminal;
+
+    let opts = test_options();
+    let mut world = World::empty(40, 12);
+    for &(x, y) in &[(3, 1), (4, 2), (2, 3), (3, 3), (4, 3)] {
+        world.set_alive(x, y, true);
+    }
+    // ~ a blinker next to the right edge
+    for x in 34..37 {
+        world.set_alive(x, 5, true);
+    }
+    let mut ui = UI::init(FakeTerminal::new(40, 12, vec![]), &opts);
+    ui.redraw_scene(&world, true);
+    // ~ a frame whose world is drawn for 40 columns; the terminal shrinks
+    // before its status line is
+    ui.begin_frame();
+    for line in 0..ui.world_lines() {
+        ui.print_row(&world, line);
+    }
+    ui.terminal.resize(30, 12);
+    ui.update_status(&world);
+    ui.flush();
+    assert_eq!(0, ui.terminal.out_of_bounds());
+    assert!(ui.terminal.line(11).starts_with("Gen: 0 / Alive: 8"), "{}", ui.terminal.line(11));
+    assert!(ui.damage.is_full());
+
+    // ~ the next frame is laid out for 30 columns and drawn as a whole
+    advance_one_step(&mut ui, &mut world);
+    assert_eq!(0, ui.terminal.out_of_bounds());
+    assert_eq!(FrameGeometry::new(30, 12), ui.frame);
+    for y in 0..11 {
+        let row: String = (0..30).map(|x| if world.is_alive(x, y) { 'O' } else { '.' }).collect();
+        assert_eq!(row, ui.terminal.line(y), "line {}", y);
+    }
+    assert!(ui.terminal.line(11).starts_with("Gen: 1 / Alive: 8"), "{}", ui.terminal.line(11));
+    let shown: Vec<String> = (0..12).map(|y| ui.terminal.line(y).trim_end().to_owned()).collect();
+    assert_eq!(shown.join("\n") + "\n", ui.shadow().to_text());
+    assert!(!ui.damage.is_full());
 }
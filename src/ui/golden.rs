@@ -0,0 +1,226 @@
+//! Golden-file tests of the UI: scripted key strokes are fed to the
+//! UI running on a fake terminal and the screen presented after each
+//! step of a script is compared to the one recorded in
+//! `tests/golden/<scenario>.txt`.
+//!
+//! Along the way, the UI's mirror of the screen (see `screenshot`) is
+//! checked to match the fake terminal's cell by cell after each step.
+//!
+//! Run the tests with `CGOL_BLESS=1` to (re-)record the golden files
+//! after an intended change of the UI's appearance.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+
+use rustbox::Event;
+use rustbox::keyboard::Key;
+
+use terminal::Terminal;
+use terminal::fake::FakeTerminal;
+use world::{AutoExpand, BoundaryMode, World};
+use super::{advance_one_step, run_, test_options, Options, UI};
+
+const GOLDEN_DIR: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+/// The environment variable requesting to record the golden files
+/// instead of comparing against them.
+const BLESS_VAR: &'static str = "CGOL_BLESS";
+
+fn keys(s: &str) -> Vec<Event> {
+    s.chars().map(|c| Event::KeyEvent(Some(Key::Char(c)))).collect()
+}
+
+// ~ the given number of animation timeouts
+fn ticks(n: usize) -> Vec<Event> {
+    (0..n).map(|_| Event::NoEvent).collect()
+}
+
+// ~ runs the given script, a sequence of named steps, against a fresh
+// UI per step replaying all the events up to and including the step;
+// the screens presented after the steps are compared with (or, when
+// blessing, recorded as) the scenario's golden file
+fn check_scenario<F>(scenario: &str,
+                     (width, height): (usize, usize),
+                     opts: &Options,
+                     world: F,
+                     script: &[(&str, Vec<Event>)])
+    where F: Fn() -> World
+{
+    let mut actual = String::new();
+    let mut events = Vec::new();
+    for &(name, ref step) in script {
+        events.extend(step.iter().cloned());
+        let mut all = events.clone();
+        all.push(Event::KeyEvent(Some(Key::Char('q'))));
+        let mut ui = UI::init(FakeTerminal::new(width, height, all), opts);
+        run_(&mut ui, Some(world()), opts, advance_one_step).unwrap();
+        check_shadow(&ui, name);
+        actual.push_str(&format!("== {} ==\n{}", name, ui.terminal.snapshot()));
+    }
+
+    let path = format!("{}/{}.txt", GOLDEN_DIR, scenario);
+    if env::var_os(BLESS_VAR).is_some() {
+        fs::create_dir_all(GOLDEN_DIR).unwrap();
+        File::create(&path).unwrap().write_all(actual.as_bytes()).unwrap();
+        return;
+    }
+    let mut expected = String::new();
+    if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut expected)) {
+        panic!("{}: {} (run with {}=1 to record it)", path, e, BLESS_VAR);
+    }
+    if expected != actual {
+        panic!("{}: screens differ (run with {}=1 to accept them)\n{}",
+               path,
+               BLESS_VAR,
+               diff(&expected, &actual));
+    }
+}
+
+// ~ checks the UI's mirror of the screen to hold what the terminal
+// presents: the characters, the attributes, and the colors (24-bit ones
+// as printed)
+fn check_shadow(ui: &UI<FakeTerminal>, step: &str) {
+    let screen = ui.shadow();
+    let t = &ui.terminal;
+    assert_eq!((t.width(), t.height()), (screen.width(), screen.height()), "{}", step);
+    for y in 0..t.height() {
+        for x in 0..t.width() {
+            let (expected, actual) = (t.cell(x, y), screen.cell(x, y));
+            let same_colors = match actual.bg_rgb {
+                Some(bg) => expected.bg_rgb == Some(bg) && expected.fg == actual.fg,
+                None => expected.bg_rgb.is_none() && expected.fg == actual.fg && expected.bg == actual.bg,
+            };
+            assert!(expected.ch == actual.ch && expected.style == actual.style && same_colors,
+                    "{}: the mirror differs from the screen at {},{}\n{}",
+                    step,
+                    x,
+                    y,
+                    screen.to_text());
+        }
+    }
+}
+
+// ~ lists the differing lines of the expected and the actual screens
+// along with the step they belong to
+fn diff(expected: &str, actual: &str) -> String {
+    let (e, a): (Vec<_>, Vec<_>) = (expected.lines().collect(), actual.lines().collect());
+    let mut out = String::new();
+    let mut step = "";
+    for i in 0..::std::cmp::max(e.len(), a.len()) {
+        let (el, al) = (e.get(i).cloned().unwrap_or(""), a.get(i).cloned().unwrap_or(""));
+        if al.starts_with("== ") {
+            step = al;
+        }
+        if el != al {
+            out.push_str(&format!("{} line {}:\n- {}\n+ {}\n", step, i + 1, el, al));
+        }
+    }
+    out
+}
+
+fn glider(width: usize, height: usize) -> World {
+    let mut w = World::empty(width, height);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        w.set_alive(x + 2, y + 1, true);
+    }
+    w
+}
+
+#[test]
+fn golden_status_bar() {
+    let opts = test_options();
+    check_scenario("status_bar",
+                   (40, 8),
+                   &opts,
+                   || World::random_seeded(21, 40, 8),
+                   &[("initial", vec![]),
+                     ("hidden", keys("n")),
+                     ("shown again", keys("n")),
+                     ("period", keys("p"))]);
+}
+
+#[test]
+fn golden_incremental_step() {
+    let opts = test_options();
+    check_scenario("incremental_step",
+                   (30, 8),
+                   &opts,
+                   || glider(30, 8),
+                   &[("initial", vec![]),
+                     ("step 1", keys("s")),
+                     ("step 3", keys("ss")),
+                     ("animated 4", {
+                         let mut e = keys(" ");
+                         e.extend(ticks(4));
+                         e.extend(keys(" "));
+                         e
+                     })]);
+}
+
+#[test]
+fn golden_resize() {
+    let opts = test_options();
+    check_scenario("resize",
+                   (30, 6),
+                   &opts,
+                   || glider(30, 6),
+                   &[("initial", vec![]),
+                     ("grown", vec![Event::ResizeEvent(40, 10)]),
+                     ("step 2", keys("ss"))]);
+}
+
+#[test]
+fn golden_neighbour_overlays() {
+    let opts = test_options();
+    let blinker = || {
+        let mut w = World::empty(20, 7);
+        for x in 3..6 {
+            w.set_alive(x, 3, true);
+        }
+        w
+    };
+    check_scenario("neighbour_overlays",
+                   (20, 8),
+                   &opts,
+                   blinker,
+                   &[("colors", keys("c")),
+                     ("colors step", keys("s")),
+                     ("digits", keys("c#")),
+                     ("off", keys("#"))]);
+}
+
+#[test]
+fn golden_minimap() {
+    let mut opts = test_options();
+    opts.auto_expand = Some(AutoExpand {
+        margin: 2,
+        increment: 8,
+        max_size: None,
+    });
+    let world = || {
+        let mut w = World::random_seeded(22, 100, 30);
+        w.set_boundary(BoundaryMode::Bounded);
+        w
+    };
+    check_scenario("minimap",
+                   (60, 12),
+                   &opts,
+                   world,
+                   &[("shown", keys("M")),
+                     ("step 2", keys("ss")),
+                     ("hidden", keys("M"))]);
+}
+
+#[test]
+fn golden_slots() {
+    let opts = test_options();
+    check_scenario("slots",
+                   (50, 8),
+                   &opts,
+                   || glider(50, 8),
+                   &[("saved", keys("m2")),
+                     ("step 2", keys("ss")),
+                     ("loaded", keys("'2")),
+                     ("empty", keys("'4"))]);
+}
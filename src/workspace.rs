@@ -0,0 +1,239 @@
+//! A world along with the annotations of its pattern's author: short
+//! labels (e.g. "ejector", "lane 3") attached to cells.
+//!
+//! The labels are kept apart from the `World` to keep stepping lean.
+//! They are anchored to the coordinates of their cells and follow the
+//! changes of the world's layout:
+//!
+//! - shifting the world moves them along with the cells (re-entering
+//!   at the opposite edge, or dropped beyond the edges of a bounded
+//!   world),
+//! - growing the world translates them such that they stay with their
+//!   cells,
+//! - resizing the world keeps them in place; those falling outside a
+//!   shrunk world are dropped and reported.
+//!
+//! Advancing a generation or editing cells leaves them untouched.
+
+use std::collections::btree_map::{self, BTreeMap};
+
+use world::{AutoExpand, Growth, World};
+
+/// The longest label (in characters) accepted.
+pub const MAX_LABEL_LEN: usize = 40;
+
+/// Checks whether the given text is acceptable as a label: neither
+/// empty nor too long, and on a single line.
+pub fn check_label(label: &str) -> Result<(), String> {
+    if label.trim().is_empty() {
+        return Err("Empty label".to_owned());
+    }
+    if label.chars().count() > MAX_LABEL_LEN {
+        return Err(format!("Label too long (at most {} characters)", MAX_LABEL_LEN));
+    }
+    if label.chars().any(|c| c.is_control()) {
+        return Err("Label with control characters".to_owned());
+    }
+    Ok(())
+}
+
+/// The labels of a world's cells.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Labels {
+    // ~ keyed by (y, x) to iterate the labels in row-major order
+    labels: BTreeMap<(usize, usize), String>,
+}
+
+impl Labels {
+    pub fn new() -> Labels {
+        Labels::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// The label of the cell at (x, y), if any.
+    pub fn get(&self, x: usize, y: usize) -> Option<&str> {
+        self.labels.get(&(y, x)).map(|s| &s[..])
+    }
+
+    /// Labels the cell at (x, y), replacing and returning its previous
+    /// label; see `check_label` for the labels accepted.
+    pub fn set(&mut self, x: usize, y: usize, label: &str) -> Result<Option<String>, String> {
+        try!(check_label(label));
+        Ok(self.labels.insert((y, x), label.trim().to_owned()))
+    }
+
+    /// Removes the label of the cell at (x, y).
+    pub fn remove(&mut self, x: usize, y: usize) -> Option<String> {
+        self.labels.remove(&(y, x))
+    }
+
+    /// Iterates the labels in row-major order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { inner: self.labels.iter() }
+    }
+
+    /// Moves all labels by the given (non-negative) offset, e.g. along
+    /// with the cells of a world grown to the left or top.
+    pub fn translate(&mut self, dx: usize, dy: usize) {
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        let labels = ::std::mem::replace(&mut self.labels, BTreeMap::new());
+        self.labels = labels.into_iter().map(|((y, x), l)| ((y + dy, x + dx), l)).collect();
+    }
+
+    /// Moves the labels like `World::shift` moves the cells of the
+    /// given world; returns the labels dropped beyond the edges of a
+    /// bounded world.
+    pub fn shift(&mut self, world: &World, dx: isize, dy: isize) -> Vec<(usize, usize, String)> {
+        let labels = ::std::mem::replace(&mut self.labels, BTreeMap::new());
+        let mut dropped = Vec::new();
+        for ((y, x), l) in labels {
+            match world.shift_target(x, y, dx, dy) {
+                Some((nx, ny)) => {
+                    self.labels.insert((ny, nx), l);
+                }
+                None => dropped.push((x, y, l)),
+            }
+        }
+        dropped
+    }
+
+    /// Drops (and returns) the labels outside a world of the given
+    /// dimensions.
+    pub fn crop(&mut self, width: usize, height: usize) -> Vec<(usize, usize, String)> {
+        let outside: Vec<_> = self.labels
+                                  .keys()
+                                  .filter(|&&(y, x)| x >= width || y >= height)
+                                  .cloned()
+                                  .collect();
+        outside.into_iter()
+               .map(|(y, x)| {
+                   let l = self.labels.remove(&(y, x)).unwrap();
+                   (x, y, l)
+               })
+               .collect()
+    }
+}
+
+/// Iterates the labels of `Labels` as `(x, y, label)`.
+pub struct Iter<'a> {
+    inner: btree_map::Iter<'a, (usize, usize), String>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (usize, usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(&(y, x), l)| (x, y, &l[..]))
+    }
+}
+
+/// A world and the labels of its cells, changed in concert.
+#[derive(Clone)]
+pub struct Workspace {
+    pub world: World,
+    pub labels: Labels,
+}
+
+impl Workspace {
+    /// A world without labels.
+    pub fn new(world: World) -> Workspace {
+        Workspace {
+            world: world,
+            labels: Labels::new(),
+        }
+    }
+
+    /// Shifts the world (see `World::shift`) and its labels; returns
+    /// the labels dropped.
+    pub fn shift(&mut self, dx: isize, dy: isize) -> Vec<(usize, usize, String)> {
+        let dropped = self.labels.shift(&self.world, dx, dy);
+        self.world.shift(dx, dy);
+        dropped
+    }
+
+    /// Grows the world (see `World::grow`); the labels stay with their
+    /// cells.
+    pub fn grow(&mut self, left: usize, top: usize, right: usize, bottom: usize) {
+        self.world.grow(left, top, right, bottom);
+        self.labels.translate(left, top);
+    }
+
+    /// Grows the world as needed (see `World::auto_expand`); the labels
+    /// stay with their cells.
+    pub fn auto_expand(&mut self, cfg: &AutoExpand) -> Growth {
+        let g = self.world.auto_expand(cfg);
+        self.labels.translate(g.left, g.top);
+        g
+    }
+
+    /// Resizes the world (see `World::expand_to`); returns the labels
+    /// dropped as they fall outside.
+    pub fn expand_to(&mut self, width: usize, height: usize) -> Vec<(usize, usize, String)> {
+        self.world.expand_to(width, height);
+        self.labels.crop(width, height)
+    }
+}
+
+#[test]
+fn test_labels() {
+    let mut labels = Labels::new();
+    assert_eq!(None, labels.set(3, 1, " ejector ").unwrap());
+    assert_eq!(Some("ejector".to_owned()), labels.set(3, 1, "lane 3").unwrap());
+    labels.set(0, 2, "reflector").unwrap();
+    labels.set(5, 0, "gun").unwrap();
+    assert_eq!(Some("lane 3"), labels.get(3, 1));
+    assert_eq!(None, labels.get(1, 3));
+    // ~ row-major
+    assert_eq!(vec![(5, 0, "gun"), (3, 1, "lane 3"), (0, 2, "reflector")],
+               labels.iter().collect::<Vec<_>>());
+
+    assert!(labels.set(0, 0, "  ").is_err());
+    assert!(labels.set(0, 0, "two\nlines").is_err());
+    assert!(labels.set(0, 0, &"x".repeat(MAX_LABEL_LEN + 1)).is_err());
+    assert!(labels.set(0, 0, &"x".repeat(MAX_LABEL_LEN)).is_ok());
+    assert_eq!(Some("x".repeat(MAX_LABEL_LEN)), labels.remove(0, 0));
+    assert_eq!(3, labels.len());
+}
+
+#[test]
+fn test_transforms() {
+    use world::BoundaryMode;
+
+    let mut ws = Workspace::new(World::empty(10, 6));
+    for &(x, y) in [(1, 1), (8, 4)].iter() {
+        ws.world.set_alive(x, y, true);
+    }
+    ws.labels.set(1, 1, "a").unwrap();
+    ws.labels.set(8, 4, "b").unwrap();
+
+    // ~ shifting moves the labels along with their cells, re-entering
+    // at the opposite edges of a torus
+    assert!(ws.shift(3, 2).is_empty());
+    assert!(ws.world.is_alive(4, 3) && ws.world.is_alive(1, 0));
+    assert_eq!(vec![(1, 0, "b"), (4, 3, "a")], ws.labels.iter().collect::<Vec<_>>());
+
+    // ~ growing keeps them with their cells
+    ws.grow(2, 1, 0, 0);
+    assert!(ws.world.is_alive(6, 4) && ws.world.is_alive(3, 1));
+    assert_eq!(vec![(3, 1, "b"), (6, 4, "a")], ws.labels.iter().collect::<Vec<_>>());
+
+    // ~ beyond the edges of a bounded world they are dropped
+    ws.world.set_boundary(BoundaryMode::Bounded);
+    assert_eq!(vec![(3, 1, "b".to_owned())], ws.shift(0, -2));
+    assert_eq!(vec![(6, 2, "a")], ws.labels.iter().collect::<Vec<_>>());
+
+    // ~ resizing keeps them in place unless outside
+    assert!(ws.expand_to(20, 20).is_empty());
+    assert_eq!(vec![(6, 2, "a")], ws.labels.iter().collect::<Vec<_>>());
+    assert_eq!(vec![(6, 2, "a".to_owned())], ws.expand_to(5, 5));
+    assert!(ws.labels.is_empty());
+}
@@ -1,12 +1,315 @@
+use std::cmp;
+use std::error;
 use std::fmt::{self, Write};
+use std::io::{self, Read};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 use rand::Rng;
 
+use io_util::{invalid_data, read_u32, read_u64, write_u32, write_u64};
+use rng::{self, chance, splitmix64};
+
+/// The probability of a cell being alive in a randomly generated world.
+const RANDOM_DENSITY: f64 = 0.3;
+
+/// The probability of a cell within a blob of a clustered random
+/// world being alive; see `RandomKind::Clustered`.
+const CLUSTER_DENSITY: f64 = 0.6;
+
+/// The width of the blobs of a clustered random world unless given.
+pub const DEFAULT_CLUSTER_SIZE: usize = 8;
+
+/// The number of cells worth generating on a separate thread when
+/// creating a seeded random world.
+const RANDOM_CELLS_PER_THREAD: usize = 1 << 20;
+
+/// The maximum number of threads used to generate a seeded random
+/// world.
+const RANDOM_MAX_THREADS: usize = 8;
+
+/// The largest number of cells of a world loaded from a file.
+pub const MAX_CELLS: usize = 1 << 30;
+
+/// The magic and version starting a world in the binary format.
+const BINARY_MAGIC: &'static [u8; 8] = b"CGOLB\x00\x00\x01";
+
+/// Describes how the edges of a world connect to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// The edges wrap around; the world is a torus.
+    Wrap,
+    /// The edges wrap around with an offset ("twisted torus"): cells
+    /// crossing the top or bottom edge are shifted by `dx` columns,
+    /// cells crossing the left or right edge by `dy` rows.
+    Twisted { dx: isize, dy: isize },
+    /// The edges do not connect; cells beyond them are considered dead.
+    Bounded,
+}
+
+impl fmt::Display for BoundaryMode {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BoundaryMode::Wrap => fmt.write_str("wrap"),
+            BoundaryMode::Twisted { dx, dy } => write!(fmt, "twist {},{}", dx, dy),
+            BoundaryMode::Bounded => fmt.write_str("bounded"),
+        }
+    }
+}
+
+/// Describes the order in which the cells of a world are updated when
+/// advancing a generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateMode {
+    /// All cells are updated at once from the previous generation; the
+    /// classic game.
+    Synchronous,
+    /// The cells are updated one at a time, each seeing the updates
+    /// made before it, in a random order determined by `seed` and the
+    /// generation.
+    AsyncRandomOrder { seed: u64 },
+    /// Like `AsyncRandomOrder`, but only the first `percent` percent of
+    /// the cells in that order are updated each generation; the others
+    /// keep their state.  The lower the percentage, the more the
+    /// outcome depends on chance.
+    AsyncRandomSubset { seed: u64, percent: u8 },
+    /// The cells are updated one at a time row by row, each seeing the
+    /// updates made before it.
+    AsyncRowMajor,
+}
+
+impl fmt::Display for UpdateMode {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UpdateMode::Synchronous => fmt.write_str("sync"),
+            UpdateMode::AsyncRandomOrder { .. } => fmt.write_str("async-random"),
+            UpdateMode::AsyncRandomSubset { percent, .. } => write!(fmt, "async-random:{}", percent),
+            UpdateMode::AsyncRowMajor => fmt.write_str("async-row-major"),
+        }
+    }
+}
+
+/// Determines the fate of a cell by its number of live neighbours: a
+/// dead cell is born with any of the `birth` counts, a live cell
+/// survives with any of the `survival` counts; both are sets of counts
+/// 0 to 8 as bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: u16,
+    pub survival: u16,
+}
+
+/// The rule of the classic game, `B3/S23`.
+pub const CONWAY: Rule = Rule {
+    birth: 1 << 3,
+    survival: 1 << 2 | 1 << 3,
+};
+
+impl Rule {
+    /// Parses a rule in the B/S notation, e.g. `B36/S23`; the letters
+    /// are case insensitive and either set of counts may be empty.
+    pub fn parse(s: &str) -> Option<Rule> {
+        let counts = |part: &str, prefix: char| -> Option<u16> {
+            let mut chars = part.chars();
+            if chars.next().map(|c| c.to_ascii_uppercase()) != Some(prefix) {
+                return None;
+            }
+            let mut bits = 0;
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => bits |= 1 << n,
+                    _ => return None,
+                }
+            }
+            Some(bits)
+        };
+        let mut parts = s.split('/');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(b), Some(s), None) => {
+                match (counts(b, 'B'), counts(s, 'S')) {
+                    (Some(b), Some(s)) => Some(Rule { birth: b, survival: s }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The state of a cell in the next generation given its current
+    /// state and number of live neighbours.
+    #[inline]
+    pub fn next(&self, alive: bool, neighbours: usize) -> bool {
+        let counts = if alive { self.survival } else { self.birth };
+        neighbours <= 8 && counts & (1 << neighbours) != 0
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |bits: u16| (0..9).filter(|n| bits & (1 << n) != 0).map(|n| n.to_string()).collect::<String>();
+        write!(fmt, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+/// Describes how the live cells of a random world are distributed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RandomKind {
+    /// Each cell is alive independently of the others.
+    Uniform,
+    /// The live cells are clumped in blobs: squares of `cluster_size`
+    /// cells wide densely filled with live cells, scattered across the
+    /// world (wrapping around its edges.)  The world's density is
+    /// about the same as of a uniform one.  A size whose area
+    /// overflows leaves the world empty.
+    Clustered { cluster_size: usize },
+}
+
+impl fmt::Display for RandomKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RandomKind::Uniform => fmt.write_str("uniform"),
+            RandomKind::Clustered { cluster_size } => write!(fmt, "clustered:{}", cluster_size),
+        }
+    }
+}
+
+/// Configures the growth of a bounded world whose live cells come
+/// close to its edges; see `World::auto_expand`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoExpand {
+    /// the distance to an edge at which the world grows
+    pub margin: usize,
+    /// the number of cells to grow by at once
+    pub increment: usize,
+    /// the maximum dimensions of the world (width, height)
+    pub max_size: Option<(usize, usize)>,
+}
+
+/// Describes the growth of a world by `World::auto_expand`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Growth {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    /// whether the world failed to grow as required due to its
+    /// maximum size
+    pub capped: bool,
+}
+
+impl Growth {
+    pub fn is_empty(&self) -> bool {
+        self.left == 0 && self.top == 0 && self.right == 0 && self.bottom == 0
+    }
+}
+
+/// The offsets of the neighbours of a cell in the classic (Moore)
+/// neighbourhood.
+pub const NEIGHBOURS: [(isize, isize); 8] = [(-1, -1), (0, -1), (1, -1),
+                                         (-1, 0),           (1, 0),
+                                         (-1, 1),  (0, 1),  (1, 1)];
+
+/// The error of accessing a cell beyond the edges of a world; see
+/// `World::try_set_alive`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub x: usize,
+    pub y: usize,
+    /// the dimensions of the world
+    pub width: usize,
+    pub height: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "cell {},{} is outside of the {}x{} world", self.x, self.y, self.width, self.height)
+    }
+}
+
+impl error::Error for OutOfBounds {}
+
+/// Tells what `World::blit` did with the live cells of a pattern.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlitReport {
+    /// the cells placed into the world, including those already alive
+    pub placed: usize,
+    /// the cells dropped beyond the edges of a bounded world
+    pub clipped: usize,
+    /// the cells placed which were already alive
+    pub overwrote: usize,
+}
+
+/// Describes the changes made by advancing a world by one generation.
+/// The numbers agree with the changes reported to the callback of
+/// `World::advance_generation`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepSummary {
+    /// the number of cells which came alive
+    pub births: usize,
+    /// the number of cells which died
+    pub deaths: usize,
+    /// the number of cells whose next state was determined
+    pub evaluated: usize,
+}
+
+/// The phases of advancing a world by a generation (see
+/// `World::advance_generation_timed`), in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// counting the live neighbours and determining the changes
+    Count,
+    /// applying the changes to the cells; none of its own in the
+    /// asynchronous update modes, which apply them while counting
+    Apply,
+    /// reporting the changed cells to the callback
+    Report,
+}
+
+/// Told when the phases of advancing a generation end, e.g. to time
+/// them (see `perf::PhaseProfile`.)
+pub trait PhaseTimer {
+    /// The first phase begins.
+    fn begin(&mut self);
+    /// The given phase ends; the next one begins.
+    fn end(&mut self, phase: Phase);
+}
+
+/// No timing at all.
+impl PhaseTimer for () {
+    #[inline(always)]
+    fn begin(&mut self) {}
+    #[inline(always)]
+    fn end(&mut self, _: Phase) {}
+}
+
+// ~ the revisions handed out so far (see `World::revision`)
+static REVISIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn next_revision() -> usize {
+    REVISIONS.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+#[derive(Clone)]
 pub struct World {
     width: usize,
     height: usize,
+    boundary: BoundaryMode,
+    // ~ the logical coordinates of the cell at (0, 0); changed only by
+    // growing the world to the left or top
+    origin: (isize, isize),
+    // ~ the offsets of the cells considered neighbours of a cell
+    neighbourhood: Vec<(isize, isize)>,
+    rule: Rule,
+    update_mode: UpdateMode,
 
     generation: usize, // current generation of cells
+    // ~ whether cells were edited (rather than stepped) since the last
+    // generation advanced; see `is_dirty`
+    dirty: bool,
+    // ~ tells the cells apart from those of any other world of the
+    // process; see `revision`
+    revision: usize,
     alive: usize, // current number of live cells
     cells: Vec<u8>,   // cells addressable by: `x + y*width`; 1 if alive, 0 if dead
 }
@@ -27,19 +330,147 @@ impl World {
     }
 
     pub fn random<R: Rng>(r: &mut R, width: usize, height: usize) -> World {
+        World::random_with(r, width, height, RandomKind::Uniform)
+    }
+
+    /// Generates a random world with live cells distributed as given;
+    /// the same as `random_seeded_with` for a seed drawn from `r`.
+    pub fn random_with<R: Rng>(r: &mut R, width: usize, height: usize, kind: RandomKind) -> World {
+        World::random_seeded_with(r.gen(), width, height, kind)
+    }
+
+    // ~ generates a world from a single random number stream
+    fn random_from(r: &mut rng::Rng, width: usize, height: usize, kind: RandomKind) -> World {
         let mut v = Vec::with_capacity(width * height);
-        for _ in 0..(width * height) {
-            v.push(r.gen::<f64>() < 0.3);
+        match kind {
+            RandomKind::Uniform => {
+                let alive = chance(RANDOM_DENSITY);
+                for _ in 0..(width * height) {
+                    v.push(r.meets(alive));
+                }
+            }
+            RandomKind::Clustered { cluster_size } => {
+                v.resize(width * height, false);
+                let size = cmp::max(1, cluster_size);
+                // ~ as many blobs as to reach about the density of a
+                // uniform world, neglecting their overlaps; none in an
+                // empty world, nor of a size whose area overflows
+                let blobs = match size.checked_mul(size) {
+                    Some(area) if !v.is_empty() => {
+                        let area = area as f64 * CLUSTER_DENSITY;
+                        ((width * height) as f64 * RANDOM_DENSITY / area).round() as usize
+                    }
+                    _ => 0,
+                };
+                let alive = chance(CLUSTER_DENSITY);
+                for _ in 0..blobs {
+                    let (x, y) = (r.below(width), r.below(height));
+                    for dy in 0..size {
+                        for dx in 0..size {
+                            if r.meets(alive) {
+                                v[(y + dy) % height * width + (x + dx) % width] = true;
+                            }
+                        }
+                    }
+                }
+            }
         }
         World::from_cells(width, height, v)
     }
 
+    /// Generates a random world determined solely by the given seed,
+    /// the same on any platform and with any version of the `rand`
+    /// crate (see `rng`.)  Large worlds are generated in parallel; the
+    /// result is the same as of `random_seeded_with_threads` for any
+    /// number of threads.
+    pub fn random_seeded(seed: u64, width: usize, height: usize) -> World {
+        let threads = cmp::max(1, (width * height) / RANDOM_CELLS_PER_THREAD);
+        World::random_seeded_with_threads(seed, width, height, cmp::min(threads, RANDOM_MAX_THREADS))
+    }
+
+    /// Generates a random world like `random_with` determined solely
+    /// by the given seed; uniform ones like `random_seeded`.
+    pub fn random_seeded_with(seed: u64, width: usize, height: usize, kind: RandomKind) -> World {
+        match kind {
+            RandomKind::Uniform => World::random_seeded(seed, width, height),
+            // ~ generated by a single stream; clusters span rows
+            _ => World::random_from(&mut row_rng(seed, 0), width, height, kind),
+        }
+    }
+
+    /// Generates a uniform random world determined solely by the given
+    /// seed with each cell alive with the probability `density`; at
+    /// the default density the same as `random_seeded`.
+    pub fn random_seeded_with_density(seed: u64, width: usize, height: usize, density: f64) -> World {
+        World::from_cells(width, height, random_rows(seed, width, 0, height, density))
+    }
+
+    /// Generates a random world determined solely by the given seed
+    /// utilizing up to `threads` threads.
+    ///
+    /// Each row of the world is generated from its own random number
+    /// stream seeded by `row_rng(seed, row)`; the rows are distributed
+    /// to the threads in continuous bands.  Hence, the generated world
+    /// does not depend on the number of threads used.
+    pub fn random_seeded_with_threads(seed: u64,
+                                      width: usize,
+                                      height: usize,
+                                      threads: usize)
+                                      -> World {
+        let threads = cmp::max(1, cmp::min(threads, height));
+        if threads == 1 {
+            return World::from_cells(width, height, random_rows(seed, width, 0, height, RANDOM_DENSITY));
+        }
+        let band = (height + threads - 1) / threads;
+        let workers: Vec<_> = (0..threads)
+                                  .map(|i| {
+                                      let from = cmp::min(height, i * band);
+                                      let to = cmp::min(height, from + band);
+                                      thread::spawn(move || random_rows(seed, width, from, to, RANDOM_DENSITY))
+                                  })
+                                  .collect();
+        let mut cells = Vec::with_capacity(width * height);
+        for worker in workers {
+            cells.extend(worker.join().unwrap());
+        }
+        World::from_cells(width, height, cells)
+    }
+
+    /// Creates a world of the given size with the cells at the given
+    /// coordinates alive, e.g. those of a pattern read cell by cell;
+    /// cells listed repeatedly count once.  Fails on the first cell
+    /// beyond the edges.
+    pub fn from_coords<I>(width: usize, height: usize, coords: I) -> Result<World, OutOfBounds>
+        where I: IntoIterator<Item = (usize, usize)>
+    {
+        let mut cells = vec![false; width * height];
+        for (x, y) in coords {
+            if x >= width || y >= height {
+                return Err(OutOfBounds {
+                    x: x,
+                    y: y,
+                    width: width,
+                    height: height,
+                });
+            }
+            cells[y * width + x] = true;
+        }
+        Ok(World::from_cells(width, height, cells))
+    }
+
     fn from_cells(width: usize, height: usize, cells: Vec<bool>) -> World {
         assert_eq!(width * height, cells.len());
         World {
             width: width,
             height: height,
+            boundary: BoundaryMode::Wrap,
+            origin: (0, 0),
+            neighbourhood: NEIGHBOURS.to_vec(),
+            rule: CONWAY,
+            update_mode: UpdateMode::Synchronous,
             generation: 0,
+            dirty: false,
+            revision: next_revision(),
             alive: cells.iter().filter(|&x| *x).count(),
             cells: cells.into_iter().map(|x| if x { 1 } else { 0 }).collect(),
         }
@@ -50,6 +481,12 @@ impl World {
         self.alive
     }
 
+    /// Determines whether no cell of this world is alive.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.alive == 0
+    }
+
     #[inline]
     pub fn width(&self) -> usize {
         self.width
@@ -60,11 +497,204 @@ impl World {
         self.height
     }
 
+    /// The number of generations this world has advanced.  The
+    /// counter follows a single policy:
+    ///
+    /// - advancing a generation increments it,
+    /// - changes of the layout (`expand_to`, `grow`, `shift`) leave it
+    ///   untouched,
+    /// - replacing the content (a new or loaded world, `restart`)
+    ///   starts it at zero,
+    /// - editing cells (`set_alive`) leaves it untouched but marks the
+    ///   world as dirty (see `is_dirty`).
     #[inline]
     pub fn generation(&self) -> usize {
         self.generation
     }
 
+    /// Determines whether cells were edited since the last generation
+    /// advanced, i.e. whether the current cells are not (only) the
+    /// outcome of stepping the generation counted.  Observers relying
+    /// on the evolution being continuous (e.g. caches keyed by the
+    /// generation) must not trust their assumptions then.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// A number changing with every change of this world's cells or
+    /// dimensions, by editing or advancing it alike: worlds of the same
+    /// revision (a world and its clones) have the same cells, e.g. to
+    /// tell whether something derived from a world is still current
+    /// without looking at all of its cells.  Unique within the process.
+    #[inline]
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Sets the number of generations this world is considered to
+    /// have advanced; meant only for restoring a saved session.
+    pub fn set_generation(&mut self, generation: usize) {
+        self.generation = generation;
+    }
+
+    /// Takes the current cells as the new content of this world: the
+    /// generation counter starts over at zero and the world is no
+    /// longer dirty, e.g. once a pattern has been read cell by cell.
+    pub fn restart(&mut self) {
+        self.generation = 0;
+        self.dirty = false;
+    }
+
+    #[inline]
+    pub fn boundary(&self) -> BoundaryMode {
+        self.boundary
+    }
+
+    #[inline]
+    pub fn neighbourhood(&self) -> &[(isize, isize)] {
+        &self.neighbourhood
+    }
+
+    /// Changes the set of offsets of the cells considered neighbours of
+    /// a cell, e.g. to emulate a hexagonal grid; the classic
+    /// neighbourhood is `NEIGHBOURS`.  Takes effect with the next
+    /// generation advanced.
+    ///
+    /// A neighbourhood may have more than eight offsets, whereas rules
+    /// only name counts up to eight: a cell with more than eight live
+    /// neighbours is neither born nor survives, in every update mode
+    /// (see `Rule::next`.)
+    pub fn set_neighbourhood(&mut self, offsets: Vec<(isize, isize)>) {
+        self.neighbourhood = offsets;
+    }
+
+    #[inline]
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Changes the rule the cells live by; takes effect with the next
+    /// generation advanced.
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    #[inline]
+    pub fn update_mode(&self) -> UpdateMode {
+        self.update_mode
+    }
+
+    /// Changes the order in which the cells are updated; takes effect
+    /// with the next generation advanced.
+    pub fn set_update_mode(&mut self, mode: UpdateMode) {
+        self.update_mode = mode;
+    }
+
+    /// Returns the logical coordinates of the cell at (0, 0).  These
+    /// are (0, 0) unless the world has grown to the left or top; the
+    /// logical coordinates of a cell stay the same while it grows.
+    #[inline]
+    pub fn origin(&self) -> (isize, isize) {
+        self.origin
+    }
+
+    /// Sets the logical coordinates of the cell at (0, 0), e.g. those a
+    /// world was loaded with.
+    pub fn set_origin(&mut self, origin: (isize, isize)) {
+        self.origin = origin;
+    }
+
+    /// Changes the way the edges of this world connect; takes effect
+    /// with the next generation advanced.
+    pub fn set_boundary(&mut self, boundary: BoundaryMode) {
+        self.boundary = boundary;
+    }
+
+    /// Computes a 64-bit (FNV-1a) hash over the world's dimensions
+    /// and cells.  This is considerably cheaper than keeping and
+    /// comparing copies of whole worlds, e.g. when looking for cycles.
+    ///
+    /// Note: equal hashes do not guarantee equal worlds; a match must
+    /// be confirmed by comparing the cells if certainty is required.
+    pub fn generation_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        for &b in [self.width as u64, self.height as u64].iter() {
+            for i in 0..8 {
+                hash = (hash ^ ((b >> (i * 8)) & 0xff)).wrapping_mul(FNV_PRIME);
+            }
+        }
+        for &c in self.cells.iter() {
+            hash = (hash ^ c as u64).wrapping_mul(FNV_PRIME);
+        }
+        hash
+    }
+
+    /// Computes a checksum over the live cells: the XOR of a hash of
+    /// each live cell's logical coordinates (see `origin`.)  Unlike
+    /// `generation_hash` it does not depend on the world's dimensions,
+    /// hence it survives the growth of auto-expanding worlds; being
+    /// cheap, it is meant to be logged periodically to detect a run
+    /// drifting from the expected results.
+    pub fn checksum(&self) -> u64 {
+        let mut sum = 0;
+        for h in 0..self.height {
+            let y = (self.origin.1 + h as isize) as u32 as u64;
+            let row = &self.cells[h * self.width..(h + 1) * self.width];
+            for (w, _) in row.iter().enumerate().filter(|&(_, &c)| c != 0) {
+                let x = (self.origin.0 + w as isize) as u32 as u64;
+                sum ^= splitmix64(x << 32 | y);
+            }
+        }
+        sum
+    }
+
+    /// Writes this world in the compact binary format (`.cgolb`): the
+    /// magic `CGOLB` followed by the format's version (`0x00 0x00
+    /// 0x01`), the world's width and height (little endian `u32`s),
+    /// its generation (`u64`), and its cells packed row by row, eight
+    /// cells per byte starting with the least significant bit.  The
+    /// boundary mode and the neighbourhood are not part of the format.
+    pub fn save_binary<W: io::Write>(&self, mut out: W) -> io::Result<()> {
+        try!(out.write_all(BINARY_MAGIC));
+        try!(write_u32(&mut out, self.width as u32));
+        try!(write_u32(&mut out, self.height as u32));
+        try!(write_u64(&mut out, self.generation as u64));
+        let mut bits = vec![0u8; (self.cells.len() + 7) / 8];
+        for (i, &c) in self.cells.iter().enumerate() {
+            bits[i / 8] |= c << (i % 8);
+        }
+        out.write_all(&bits)
+    }
+
+    /// Reads a world written by `save_binary`; worlds of more than
+    /// `MAX_CELLS` cells are rejected.
+    pub fn load_binary<R: Read>(mut input: R) -> io::Result<World> {
+        let mut magic = [0u8; 8];
+        try!(input.read_exact(&mut magic));
+        if &magic[..5] != &BINARY_MAGIC[..5] {
+            return Err(invalid_data("not a binary world"));
+        }
+        if &magic != BINARY_MAGIC {
+            return Err(invalid_data("unsupported version of the binary world format"));
+        }
+        let width = try!(read_u32(&mut input)) as usize;
+        let height = try!(read_u32(&mut input)) as usize;
+        let generation = try!(read_u64(&mut input)) as usize;
+        if width == 0 || height == 0 || width.saturating_mul(height) > MAX_CELLS {
+            return Err(invalid_data("invalid world dimensions"));
+        }
+        let mut bits = vec![0u8; (width * height + 7) / 8];
+        try!(input.read_exact(&mut bits));
+        let cells = (0..width * height).map(|i| bits[i / 8] & (1 << (i % 8)) != 0).collect();
+        let mut world = World::from_cells(width, height, cells);
+        world.generation = generation;
+        Ok(world)
+    }
+
     pub fn expand_to(&mut self, new_width: usize, new_height: usize) {
         if new_width == self.width && new_height == self.height {
             return;
@@ -83,10 +713,231 @@ impl World {
         self.width = new_width;
         self.height = new_height;
         self.cells = ncells;
+        self.revision = next_revision();
         self.alive = alive;
     }
 
+    /// Adds the given number of dead columns and rows at the edges of
+    /// this world.  The origin moves such that the logical coordinates
+    /// of the existing cells stay the same.
+    pub fn grow(&mut self, left: usize, top: usize, right: usize, bottom: usize) {
+        let (nwidth, nheight) = (self.width + left + right, self.height + top + bottom);
+        let mut ncells = vec![0; nwidth * nheight];
+        for h in 0..self.height {
+            let offs = (h + top) * nwidth + left;
+            ncells[offs..offs + self.width]
+                .copy_from_slice(&self.cells[h * self.width..(h + 1) * self.width]);
+        }
+        self.width = nwidth;
+        self.height = nheight;
+        self.cells = ncells;
+        self.revision = next_revision();
+        self.origin = (self.origin.0 - left as isize, self.origin.1 - top as isize);
+    }
+
+    /// Determines the smallest rectangle containing all live cells as
+    /// `(x, y, width, height)`; `None` if there are none.  The edges
+    /// are not taken to wrap around.
+    pub fn live_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for h in 0..self.height {
+            let row = &self.cells[h * self.width..(h + 1) * self.width];
+            let (first, last) = match (row.iter().position(|&c| c != 0),
+                                       row.iter().rposition(|&c| c != 0)) {
+                (Some(first), Some(last)) => (first, last),
+                _ => continue,
+            };
+            bounds = Some(match bounds {
+                None => (first, h, last, h),
+                Some((x0, y0, x1, _)) => (cmp::min(x0, first), y0, cmp::max(x1, last), h),
+            });
+        }
+        bounds.map(|(x0, y0, x1, y1)| (x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+    }
+
+    /// Shrinks (or grows) this world to the bounding box of its live
+    /// cells (see `live_bounds`) surrounded by `margin` dead cells; the
+    /// logical coordinates of the cells stay the same.  An empty world
+    /// shrinks to `2 * margin` cells in either direction, but at least
+    /// to a single cell.
+    pub fn trim(&mut self, margin: usize) {
+        let (x, y, width, height) = match self.live_bounds() {
+            Some(b) => b,
+            None => {
+                let n = cmp::max(1, 2 * margin);
+                self.width = n;
+                self.height = n;
+                self.cells = vec![0; n * n];
+                self.revision = next_revision();
+                return;
+            }
+        };
+        let (nwidth, nheight) = (width + 2 * margin, height + 2 * margin);
+        let mut ncells = vec![0; nwidth * nheight];
+        for h in 0..height {
+            let offs = (h + margin) * nwidth + margin;
+            let row = (y + h) * self.width + x;
+            ncells[offs..offs + width].copy_from_slice(&self.cells[row..row + width]);
+        }
+        self.width = nwidth;
+        self.height = nheight;
+        self.cells = ncells;
+        self.revision = next_revision();
+        self.origin = (self.origin.0 + x as isize - margin as isize,
+                       self.origin.1 + y as isize - margin as isize);
+    }
+
+    /// Grows this world by `cfg.increment` cells in the direction of
+    /// each edge its live cells came within `cfg.margin` of.  Only the
+    /// strips along the edges are examined, hence this is cheap enough
+    /// to be called after every generation.
+    ///
+    /// The growth is limited by `cfg.max_size`; if a required growth
+    /// is not possible, the world stays as it is in that direction and
+    /// the result is marked as `capped`.
+    pub fn auto_expand(&mut self, cfg: &AutoExpand) -> Growth {
+        let (left, top, right, bottom) = self.live_near_edges(cfg.margin);
+        let (max_width, max_height) = cfg.max_size.unwrap_or((usize::max_value(), usize::max_value()));
+        // ~ hands out the remaining room to the requested directions
+        let claim = |wanted: bool, room: &mut usize, capped: &mut bool| {
+            if !wanted {
+                return 0;
+            }
+            let n = cmp::min(cfg.increment, *room);
+            *room -= n;
+            *capped |= n == 0;
+            n
+        };
+        let mut g = Growth::default();
+        let mut room = max_width.saturating_sub(self.width);
+        g.left = claim(left, &mut room, &mut g.capped);
+        g.right = claim(right, &mut room, &mut g.capped);
+        let mut room = max_height.saturating_sub(self.height);
+        g.top = claim(top, &mut room, &mut g.capped);
+        g.bottom = claim(bottom, &mut room, &mut g.capped);
+        if !g.is_empty() {
+            self.grow(g.left, g.top, g.right, g.bottom);
+        }
+        g
+    }
+
+    // ~ determines whether there are live cells within `margin` cells
+    // of the left, top, right, and bottom edge respectively
+    fn live_near_edges(&self, margin: usize) -> (bool, bool, bool, bool) {
+        let (mw, mh) = (cmp::min(margin, self.width), cmp::min(margin, self.height));
+        let any = |xs: ::std::ops::Range<usize>, ys: ::std::ops::Range<usize>| {
+            ys.clone().any(|y| xs.clone().any(|x| self.is_alive(x, y)))
+        };
+        (any(0..mw, 0..self.height),
+         any(0..self.width, 0..mh),
+         any(self.width - mw..self.width, 0..self.height),
+         any(0..self.width, self.height - mh..self.height))
+    }
+
+    /// Edits the state of the specified cell; marks the world as dirty
+    /// (see `is_dirty`.)  The cell is expected to change its state.
+    /// Panics if it is beyond the edges of this world; callers not
+    /// knowing its coordinates to be valid are to use `try_set_alive`.
     pub fn set_alive(&mut self, w: usize, h: usize, alive: bool) {
+        self.check_bounds(w, h);
+        self.set_cell(w, h, alive);
+        self.dirty = true;
+        self.revision = next_revision();
+    }
+
+    /// Brings the cells of this world alive which are live in the given
+    /// pattern placed with its top left cell at `(x, y)`; the other
+    /// cells stay as they are.  The pattern wraps around the edges of a
+    /// (twisted) torus like cells moving across them, its position too
+    /// being taken modulo the world's dimensions; cells falling beyond
+    /// the edges of a bounded world are dropped.
+    pub fn blit(&mut self, x: isize, y: isize, pattern: &World) -> BlitReport {
+        let mut report = BlitReport::default();
+        for (px, py) in pattern.live_cells() {
+            let target = if self.width == 0 || self.height == 0 {
+                None
+            } else {
+                self.neighbour(0, 0, x + px as isize, y + py as isize)
+            };
+            match target {
+                None => report.clipped += 1,
+                Some((w, h)) => {
+                    report.placed += 1;
+                    if self.is_alive(w, h) {
+                        report.overwrote += 1;
+                    } else {
+                        self.set_alive(w, h, true);
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// A new world holding a copy of the region of the given size with
+    /// its top left cell at (x, y), e.g. to see what a part of this
+    /// world does on its own.  The copy has this world's rule,
+    /// neighbourhood and update mode, the given boundary mode, and
+    /// starts at generation zero.  The region must lie within this
+    /// world; the error names its bottom right cell otherwise.
+    pub fn extract(&self,
+                   x: usize,
+                   y: usize,
+                   width: usize,
+                   height: usize,
+                   boundary: BoundaryMode)
+                   -> Result<World, OutOfBounds> {
+        try!(self.check_region(x, y, width, height));
+        let mut cells = Vec::with_capacity(width * height);
+        for h in y..y + height {
+            let row = h * self.width;
+            cells.extend(self.cells[row + x..row + x + width].iter().map(|&c| c != 0));
+        }
+        let mut region = World::from_cells(width, height, cells);
+        region.boundary = boundary;
+        region.origin = (self.origin.0 + x as isize, self.origin.1 + y as isize);
+        region.neighbourhood = self.neighbourhood.clone();
+        region.rule = self.rule;
+        region.update_mode = self.update_mode;
+        Ok(region)
+    }
+
+    /// Copies all cells of `region` (unlike `blit`, the dead ones too)
+    /// into this world with its top left cell at (x, y), e.g. to paste
+    /// back a region taken by `extract` after evolving it; determines
+    /// the number of cells changed.  The region must lie within this
+    /// world; nothing is changed otherwise.
+    pub fn replace_region(&mut self, x: usize, y: usize, region: &World) -> Result<usize, OutOfBounds> {
+        try!(self.check_region(x, y, region.width, region.height));
+        let mut changed = 0;
+        for h in 0..region.height {
+            for w in 0..region.width {
+                let alive = region.is_alive(w, h);
+                if self.is_alive(x + w, y + h) != alive {
+                    self.set_alive(x + w, y + h, alive);
+                    changed += 1;
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    // ~ regions reaching beyond `usize` are out of bounds as well
+    fn check_region(&self, x: usize, y: usize, width: usize, height: usize) -> Result<(), OutOfBounds> {
+        match (x.checked_add(width), y.checked_add(height)) {
+            (Some(right), Some(bottom)) if right <= self.width && bottom <= self.height => return Ok(()),
+            _ => {}
+        }
+        Err(OutOfBounds {
+            x: x.saturating_add(width).saturating_sub(1),
+            y: y.saturating_add(height).saturating_sub(1),
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    // ~ changes the state of a cell without marking the world as dirty
+    fn set_cell(&mut self, w: usize, h: usize, alive: bool) {
         debug_assert!(w < self.width);
         debug_assert!(h < self.height);
 
@@ -98,128 +949,643 @@ impl World {
         }
     }
 
-    pub fn is_alive(&self, w: usize, h: usize) -> bool {
-        self.is_alive_num(w, h) != 0
+    /// Edits the state of the specified cell like `set_alive`, but
+    /// checks its coordinates in all builds; a cell already in the
+    /// given state stays untouched.
+    pub fn try_set_alive(&mut self, w: usize, h: usize, alive: bool) -> Result<(), OutOfBounds> {
+        match self.get(w, h) {
+            None => {
+                Err(OutOfBounds {
+                    x: w,
+                    y: h,
+                    width: self.width,
+                    height: self.height,
+                })
+            }
+            Some(a) if a == alive => Ok(()),
+            Some(_) => {
+                self.set_alive(w, h, alive);
+                Ok(())
+            }
+        }
     }
 
-    // ~ returns 1 if the specified cell is alive, otherwise 0.
-    fn is_alive_num(&self, w: usize, h: usize) -> usize {
-        self.cell(self.cell_offset(w, h)) as usize
+    /// Determines whether the specified cell is alive; panics if it is
+    /// beyond the edges of this world (see `get`.)
+    pub fn is_alive(&self, w: usize, h: usize) -> bool {
+        self.check_bounds(w, h);
+        self.is_alive_num(w, h) != 0
     }
 
+    // ~ panics unless the specified cell exists; the unchecked access
+    // is left to the private methods
     #[inline]
-    fn cell_offset(&self, w: usize, h: usize) -> usize {
-        debug_assert!(w < self.width);
-        debug_assert!(h < self.height);
-        h * self.width + w
+    fn check_bounds(&self, w: usize, h: usize) {
+        if w >= self.width || h >= self.height {
+            panic!("{}",
+                   OutOfBounds {
+                       x: w,
+                       y: h,
+                       width: self.width,
+                       height: self.height,
+                   });
+        }
     }
 
-    #[inline]
-    fn cell(&self, offset: usize) -> u8 {
-        debug_assert!(offset < self.cells.len());
-        unsafe { *self.cells.get_unchecked(offset) }
+    /// Determines whether the specified cell is alive like `is_alive`;
+    /// `None` if it is beyond the edges of this world.
+    pub fn get(&self, w: usize, h: usize) -> Option<bool> {
+        if w < self.width && h < self.height {
+            Some(self.is_alive(w, h))
+        } else {
+            None
+        }
     }
 
-    //
-    // Rules (https://en.wikipedia.org/wiki/Conway's_Game_of_Life):
-    //
-    // 1. Any live cell with fewer than two live neighbours dies, as if
-    // caused by under-population.
-    // 2. Any live cell with two or three live neighbours lives on to the
-    // next generation.
-    // 3. Any live cell with more than three live neighbours dies, as if
-    // by over-population.
-    // 4. Any dead cell with exactly three live neighbours becomes a live
-    // cell, as if by reproduction.
-    //
-    pub fn advance_generation<F: FnMut(usize, usize, bool)>(&mut self, mut cb: F) {
-
-        // ~ computes the the number of alive neighbours for (w, h)
-        // assuming the cell is somewhere at the border of the world.
-        fn border_neighbour_count(world: &World, w: usize, h: usize) -> (bool, usize) {
-            let mut cnt =
-                  world.is_alive_num(wrapped(w, -1, world.width), wrapped(h, -1, world.height))
-                + world.is_alive_num(w,                           wrapped(h, -1, world.height))
-                + world.is_alive_num(wrapped(w, 1, world.width),  wrapped(h, -1, world.height));
-
-            cnt += world.is_alive_num(wrapped(w, -1, world.width), h)
-                + world.is_alive_num(wrapped(w, 1, world.width),  h);
-
-            cnt += world.is_alive_num(wrapped(w, -1, world.width), wrapped(h, 1, world.height))
-                + world.is_alive_num(w,                           wrapped(h, 1, world.height))
-                + world.is_alive_num(wrapped(w, 1, world.width),  wrapped(h, 1, world.height));
+    /// Determines the number of live neighbours of the specified cell
+    /// with respect to the world's boundary mode.
+    pub fn neighbour_count(&self, w: usize, h: usize) -> usize {
+        self.neighbourhood.iter()
+                          .filter_map(|&(dx, dy)| self.neighbour(w, h, dx, dy))
+                          .map(|(nw, nh)| self.is_alive_num(nw, nh))
+                          .sum()
+    }
 
-            (world.is_alive(w, h), cnt)
+    /// Counts the live cells within the rectangle of the given size
+    /// whose top left corner is at (x, y); the parts of the rectangle
+    /// beyond the world's edges are ignored.
+    pub fn count_in_rect(&self, x: usize, y: usize, width: usize, height: usize) -> usize {
+        let x1 = x.checked_add(width).map_or(self.width, |x1| cmp::min(x1, self.width));
+        let y1 = y.checked_add(height).map_or(self.height, |y1| cmp::min(y1, self.height));
+        let mut n = 0;
+        for h in cmp::min(y, y1)..y1 {
+            let row = h * self.width;
+            n += self.cells[row + cmp::min(x, x1)..row + x1].iter().filter(|&&c| c != 0).count();
         }
+        n
+    }
 
-        // ~ computes the number of alive neighbours for (w, h)
-        // assuming the cell is not at the border of the world.
-        fn inner_neighbour_count(world: &World, w: usize, h: usize) -> (bool, usize) {
-            let mut cnt =
-                  world.is_alive_num(w - 1, h - 1)
-                + world.is_alive_num(w,     h - 1)
-                + world.is_alive_num(w + 1, h - 1);
-
-            cnt += world.is_alive_num(w - 1, h)
-                + world.is_alive_num(w + 1, h);
-
-            let center_alive = world.is_alive(w, h);
-
-            cnt += world.is_alive_num(w - 1, h + 1)
-                + world.is_alive_num(w,     h + 1)
-                + world.is_alive_num(w + 1, h + 1);
+    /// Renders this world as text, one line per row with the given
+    /// characters for live and dead cells.
+    pub fn as_ascii(&self, alive: char, dead: char) -> String {
+        let mut s = String::with_capacity((self.width + 1) * self.height);
+        for h in 0..self.height {
+            s.extend((0..self.width).map(|w| if self.is_alive(w, h) { alive } else { dead }));
+            s.push('\n');
+        }
+        s
+    }
 
-            (center_alive, cnt)
+    /// Determines the coordinates of the live cells in row-major order.
+    pub fn live_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::with_capacity(self.alive);
+        for (i, &c) in self.cells.iter().enumerate() {
+            if c != 0 {
+                cells.push((i % self.width, i / self.width));
+            }
         }
+        cells
+    }
 
-        let mut changes = Vec::new();
-        macro_rules! eval_counts {
-            ($w:expr, $h:expr, $count:expr) => {
-                match $count {
-                    (true, n) if n < 2 || n > 3 => changes.push(($w, $h, false)),
-                    (false, 3) => changes.push(($w, $h, true)),
-                    _ => {}
+    /// Iterates over the coordinates of the live cells within the given
+    /// rectangle in row-major order; the rectangle is clipped to the
+    /// world (see `count_in_rect`) and no cell outside it is visited.
+    pub fn live_cells_in<'a>(&'a self,
+                             x: usize,
+                             y: usize,
+                             width: usize,
+                             height: usize)
+                             -> impl Iterator<Item = (usize, usize)> + 'a {
+        let (x1, y1) = (cmp::min(x.saturating_add(width), self.width),
+                        cmp::min(y.saturating_add(height), self.height));
+        let x0 = cmp::min(x, x1);
+        (cmp::min(y, y1)..y1).flat_map(move |h| {
+            let row = &self.cells[h * self.width + x0..h * self.width + x1];
+            row.iter().enumerate().filter(|&(_, &c)| c != 0).map(move |(i, _)| (x0 + i, h))
+        })
+    }
+
+    /// Determines the cells alive in only one of this world and
+    /// `other` as `(x, y, alive_here)` in row-major order.  The worlds
+    /// are aligned at their top left corners; cells beyond the smaller
+    /// one count as dead.
+    pub fn diff(&self, other: &World) -> Vec<(usize, usize, bool)> {
+        let alive = |w: &World, x: usize, y: usize| x < w.width && y < w.height && w.is_alive(x, y);
+        let mut cells = Vec::new();
+        for y in 0..cmp::max(self.height, other.height) {
+            for x in 0..cmp::max(self.width, other.width) {
+                let here = alive(self, x, y);
+                if here != alive(other, x, y) {
+                    cells.push((x, y, here));
                 }
             }
         }
+        cells
+    }
+
+    /// Determines whether the region of this world with its top left
+    /// corner at (x, y) equals the whole of `other`, its dead cells
+    /// included; e.g. to check whether an eater has been restored.
+    /// Regions reaching beyond the world's edges never match.
+    pub fn matches_at(&self, other: &World, x: usize, y: usize) -> bool {
+        let fits = |at: usize, len: usize, max: usize| at.checked_add(len).map_or(false, |end| end <= max);
+        if !fits(x, other.width, self.width) || !fits(y, other.height, self.height) {
+            return false;
+        }
+        (0..other.height).all(|h| {
+            let row = (y + h) * self.width + x;
+            let orow = h * other.width;
+            self.cells[row..row + other.width] == other.cells[orow..orow + other.width]
+        })
+    }
 
-        {
-            // upper row
+    /// Determines the number of live neighbours of all cells; the
+    /// result is addressable by `x + y*width`.
+    pub fn neighbour_counts(&self) -> Vec<u8> {
+        let mut counts = Vec::with_capacity(self.cells.len());
+        for h in 0..self.height {
             for w in 0..self.width {
-                eval_counts!(w, 0, border_neighbour_count(self, w, 0))
+                counts.push(self.neighbour_count(w, h) as u8);
             }
-            // lower row
-            let h = self.height - 1;
+        }
+        counts
+    }
+
+    /// Counts the cells by their number of live neighbours, e.g. to
+    /// tell how many cells the rule lets be born or survive next.
+    /// Cells with more than eight live neighbours (possible with a
+    /// larger neighbourhood) are left out.
+    pub fn neighbour_histogram(&self) -> [usize; 9] {
+        let mut histogram = [0; 9];
+        for h in 0..self.height {
             for w in 0..self.width {
-                eval_counts!(w, h, border_neighbour_count(self, w, h))
-            }
-            // left column
-            for h in 1..(self.height - 1) {
-                eval_counts!(0, h, border_neighbour_count(self, 0, h))
-            }
-            // right column
-            let w = self.width - 1;
-            for h in 1..(self.height - 1) {
-                eval_counts!(w, h, border_neighbour_count(self, w, h))
+                if let Some(n) = histogram.get_mut(self.neighbour_count(w, h)) {
+                    *n += 1;
+                }
             }
         }
-        // inner cells
-        for h in 1..(self.height - 1) {
-            for w in 1..(self.width -1) {
-                eval_counts!(w, h, inner_neighbour_count(self, w, h));
-            }
+        histogram
+    }
+
+    // ~ returns 1 if the specified cell is alive, otherwise 0.
+    fn is_alive_num(&self, w: usize, h: usize) -> usize {
+        self.cell(self.cell_offset(w, h)) as usize
+    }
+
+    #[inline]
+    fn cell_offset(&self, w: usize, h: usize) -> usize {
+        debug_assert!(w < self.width);
+        debug_assert!(h < self.height);
+        h * self.width + w
+    }
+
+    // ~ determines the coordinates of the cell at the offset (dx, dy)
+    // from (w, h) with respect to the world's boundary mode; `None` if
+    // the offset leads beyond the edges of a bounded world.
+    fn neighbour(&self, w: usize, h: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        match self.boundary {
+            BoundaryMode::Wrap => Some((wrapped(w, dx, self.width), wrapped(h, dy, self.height))),
+            BoundaryMode::Twisted { dx: tx, dy: ty } => {
+                // ~ the number of times (and the direction) the edges
+                // are crossed
+                let cross_x = crossings(w, dx, self.width);
+                let cross_y = crossings(h, dy, self.height);
+                Some((wrapped(w, dx + cross_y * tx, self.width),
+                      wrapped(h, dy + cross_x * ty, self.height)))
+            }
+            BoundaryMode::Bounded => {
+                if crossings(w, dx, self.width) == 0 && crossings(h, dy, self.height) == 0 {
+                    Some(((w as isize + dx) as usize, (h as isize + dy) as usize))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Determines where `shift` moves the cell at (x, y); `None` if it
+    /// is dropped beyond the edges of a bounded world.
+    pub fn shift_target(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        self.neighbour(x, y, dx, dy)
+    }
+
+    /// Moves all live cells of this world by the given offset.  Cells
+    /// moved beyond the edges of a bounded world are dropped; otherwise
+    /// they re-enter the world as they would when moving across the
+    /// edges generation by generation.  The generation counter is left
+    /// untouched.
+    pub fn shift(&mut self, dx: isize, dy: isize) {
+        let mut alive = 0;
+        let mut ncells = vec![0; self.cells.len()];
+        for h in 0..self.height {
+            for w in 0..self.width {
+                if self.is_alive(w, h) {
+                    if let Some((nw, nh)) = self.neighbour(w, h, dx, dy) {
+                        ncells[nh * self.width + nw] = 1;
+                        alive += 1;
+                    }
+                }
+            }
+        }
+        self.cells = ncells;
+        self.revision = next_revision();
+        self.alive = alive;
+    }
+
+    /// Moves the given cells of this world by the given offset like
+    /// `shift` moves all of them, e.g. to nudge a pattern just placed;
+    /// the other cells stay as they are, those the moved cells land on
+    /// being alive after all.  The given cells are to be distinct; dead
+    /// ones among them are ignored.  Determines the cells the move
+    /// brought alive, leaving out those merged with the live cells they
+    /// landed on, and the number of cells dropped beyond the edges of a
+    /// bounded world.
+    pub fn shift_cells(&mut self,
+                       cells: &[(usize, usize)],
+                       dx: isize,
+                       dy: isize)
+                       -> (Vec<(usize, usize)>, usize) {
+        let alive: Vec<_> = cells.iter().cloned().filter(|&(x, y)| self.is_alive(x, y)).collect();
+        for &(x, y) in &alive {
+            self.set_alive(x, y, false);
+        }
+        let moved: Vec<_> = alive.iter().filter_map(|&(x, y)| self.neighbour(x, y, dx, dy)).collect();
+        let dropped = alive.len() - moved.len();
+        let landed = moved.into_iter().filter(|&(x, y)| !self.is_alive(x, y)).collect::<Vec<_>>();
+        for &(x, y) in &landed {
+            self.set_alive(x, y, true);
+        }
+        (landed, dropped)
+    }
+
+    #[inline]
+    fn cell(&self, offset: usize) -> u8 {
+        debug_assert!(offset < self.cells.len());
+        unsafe { *self.cells.get_unchecked(offset) }
+    }
+
+    //
+    // Rules (https://en.wikipedia.org/wiki/Conway's_Game_of_Life):
+    //
+    // 1. Any live cell with fewer than two live neighbours dies, as if
+    // caused by under-population.
+    // 2. Any live cell with two or three live neighbours lives on to the
+    // next generation.
+    // 3. Any live cell with more than three live neighbours dies, as if
+    // by over-population.
+    // 4. Any dead cell with exactly three live neighbours becomes a live
+    // cell, as if by reproduction.
+    //
+    /// Advances this world by one generation and reports every cell
+    /// which changed to `cb` as `(x, y, alive)`.  The callback's
+    /// contract, relied upon by renderers and recorders:
+    ///
+    /// - it is invoked only after the whole generation (including the
+    ///   generation counter) has been committed,
+    /// - each changed cell is reported exactly once, unchanged cells
+    ///   are not reported at all,
+    /// - the cells are reported in row-major order, i.e. ordered by
+    ///   `(y, x)`,
+    /// - `alive` is the cell's state in the new generation.
+    ///
+    /// Any optimization of this method has to keep these guarantees.
+    /// They hold for every update mode (see `set_update_mode`.)
+    pub fn advance_generation<F: FnMut(usize, usize, bool)>(&mut self, cb: F) -> StepSummary {
+        self.advance_generation_timed(cb, &mut ())
+    }
+
+    /// Advances this world like `advance_generation`, telling `timer`
+    /// when each of its phases begins and ends (see `Phase`), e.g. to
+    /// profile them.  `advance_generation` passes a timer doing nothing,
+    /// which compiles away.
+    pub fn advance_generation_timed<F, T>(&mut self, cb: F, timer: &mut T) -> StepSummary
+        where F: FnMut(usize, usize, bool),
+              T: PhaseTimer
+    {
+        timer.begin();
+        let n = self.cells.len();
+        let (summary, evaluated) = match self.update_mode {
+            UpdateMode::Synchronous => (self.advance_generation_sync(cb, timer), n),
+            UpdateMode::AsyncRandomOrder { seed } => {
+                let mut order: Vec<usize> = (0..n).collect();
+                row_rng(seed, self.generation).shuffle(&mut order);
+                (self.advance_generation_async(order.into_iter(), cb, timer), n)
+            }
+            UpdateMode::AsyncRandomSubset { seed, percent } => {
+                // ~ the same order as `AsyncRandomOrder`, cut short
+                let mut order: Vec<usize> = (0..n).collect();
+                row_rng(seed, self.generation).shuffle(&mut order);
+                order.truncate(n * cmp::min(100, percent as usize) / 100);
+                let evaluated = order.len();
+                (self.advance_generation_async(order.into_iter(), cb, timer), evaluated)
+            }
+            UpdateMode::AsyncRowMajor => (self.advance_generation_async(0..n, cb, timer), n),
+        };
+        StepSummary { evaluated: evaluated, ..summary }
+    }
+
+    /// Advances this world by the given number of generations as fast
+    /// as possible, i.e. without reporting the changed cells, e.g. to
+    /// skip ahead to a generation without looking at the ones in
+    /// between.
+    pub fn advance_generations(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.advance_generation(|_, _, _| {});
+        }
+    }
+
+    // ~ advances by updating the cells at the given offsets one at a
+    // time in place.  the changes are determined by comparing the
+    // cells before and after the sweep rather than collected during it
+    // as a cell's neighbours might be updated after it.  the sweep both
+    // counts and applies; it is timed as counting along with the
+    // comparison, with no applying phase
+    fn advance_generation_async<I, F, T>(&mut self, order: I, mut cb: F, timer: &mut T) -> StepSummary
+        where I: Iterator<Item = usize>,
+              F: FnMut(usize, usize, bool),
+              T: PhaseTimer
+    {
+        let before = self.cells.clone();
+        for offset in order {
+            let (w, h) = (offset % self.width, offset / self.width);
+            let n = self.neighbour_count(w, h);
+            let alive = self.cell(offset) != 0;
+            self.cells[offset] = if self.rule.next(alive, n) { 1 } else { 0 };
+        }
+        // ~ the whole generation is committed, the number of live cells
+        // included, before the first callback
+        let mut summary = StepSummary::default();
+        let mut changed = Vec::new();
+        for (offset, (&old, &new)) in before.iter().zip(self.cells.iter()).enumerate() {
+            if old != new {
+                if new != 0 {
+                    summary.births += 1;
+                } else {
+                    summary.deaths += 1;
+                }
+                changed.push(offset);
+            }
+        }
+        self.alive = self.alive + summary.births - summary.deaths;
+        self.generation += 1;
+        self.dirty = false;
+        self.revision = next_revision();
+        timer.end(Phase::Count);
+        for offset in changed {
+            cb(offset % self.width, offset / self.width, self.cells[offset] != 0);
+        }
+        timer.end(Phase::Report);
+        summary
+    }
+
+    // ~ advances by evaluating all cells against the previous
+    // generation, then applying the changes
+    fn advance_generation_sync<F, T>(&mut self, mut cb: F, timer: &mut T) -> StepSummary
+        where F: FnMut(usize, usize, bool),
+              T: PhaseTimer
+    {
+        // ~ computes the the number of alive neighbours for (w, h)
+        // assuming the cell is somewhere at the border of the world.
+        fn border_neighbour_count(world: &World, w: usize, h: usize) -> (bool, usize) {
+            let mut cnt = 0;
+            for &(dx, dy) in NEIGHBOURS.iter() {
+                if let Some((nw, nh)) = world.neighbour(w, h, dx, dy) {
+                    cnt += world.is_alive_num(nw, nh);
+                }
+            }
+            (world.is_alive(w, h), cnt)
+        }
+
+        // ~ sums up the cell (w, h) and its left and right neighbour
+        // for every column `w` of `left..right` of row `h` into `out`;
+        // `left` must not be the first and `right` not beyond the last
+        // column
+        fn row_sums(world: &World, h: usize, left: usize, right: usize, out: &mut Vec<u8>) {
+            let row = &world.cells[h * world.width + left - 1..h * world.width + right + 1];
+            out.clear();
+            out.extend(row.windows(3).map(|t| t[0] + t[1] + t[2]));
+        }
+
+        // ~ whether a cell changes by its state and number of live
+        // neighbours, indexed by `state * 9 + count`
+        let mut flips = [false; 18];
+        for n in 0..9 {
+            flips[n] = self.rule.next(false, n);
+            flips[9 + n] = !self.rule.next(true, n);
+        }
+        let mut changes = Vec::new();
+        // ~ more than eight live neighbours (see `set_neighbourhood`)
+        // neither give birth nor let survive: such a cell changes iff
+        // it is alive, like `Rule::next` has it
+        macro_rules! eval_counts {
+            ($w:expr, $h:expr, $count:expr) => {{
+                let (alive, n): (bool, usize) = $count;
+                let flip = if n <= 8 { flips[alive as usize * 9 + n] } else { alive };
+                if flip {
+                    changes.push(($w, $h, !alive));
+                }
+            }};
+        }
+
+        // ~ the number of live neighbours of an inner cell is the sum of
+        // the triple sums (see `row_sums`) of its column in the rows
+        // above, at, and below it minus the cell itself; the sums of a
+        // row are thus computed once rather than for each of the three
+        // rows using them.  sweeps the inner cells `left..right` of row
+        // `h` given the sums of the three rows
+        macro_rules! eval_inner_row {
+            ($h:expr, $left:expr, $right:expr, $above:expr, $at:expr, $below:expr) => {{
+                let (h, left) = ($h, $left);
+                let row = &self.cells[h * self.width + left..h * self.width + $right];
+                for (i, &c) in row.iter().enumerate() {
+                    let n = ($above[i] + $at[i] + $below[i] - c) as usize;
+                    if flips[c as usize * 9 + n] {
+                        changes.push((left + i, h, c == 0));
+                    }
+                }
+            }};
+        }
+
+        let (mut above, mut at, mut below) = (Vec::new(), Vec::new(), Vec::new());
+        if self.neighbourhood[..] != NEIGHBOURS[..] {
+            // ~ a custom neighbourhood; the shortcuts for the inner
+            // cells below do not apply
+            for h in 0..self.height {
+                for w in 0..self.width {
+                    eval_counts!(w, h, (self.is_alive(w, h), self.neighbour_count(w, h)));
+                }
+            }
+        } else {
+            // ~ row by row such that the changes are collected in
+            // row-major order
+            let inner = self.width > 2 && self.height > 2;
+            if inner {
+                row_sums(self, 0, 1, self.width - 1, &mut above);
+                row_sums(self, 1, 1, self.width - 1, &mut at);
+            }
+            for h in 0..self.height {
+                if h == 0 || h == self.height - 1 {
+                    // upper or lower row
+                    for w in 0..self.width {
+                        eval_counts!(w, h, border_neighbour_count(self, w, h))
+                    }
+                    continue;
+                }
+                // left column
+                eval_counts!(0, h, border_neighbour_count(self, 0, h));
+                // inner cells
+                if inner {
+                    row_sums(self, h + 1, 1, self.width - 1, &mut below);
+                    eval_inner_row!(h, 1, self.width - 1, above, at, below);
+                    // ~ rotate the sums for the next row
+                    mem::swap(&mut above, &mut at);
+                    mem::swap(&mut at, &mut below);
+                }
+                // right column
+                if self.width > 1 {
+                    let w = self.width - 1;
+                    eval_counts!(w, h, border_neighbour_count(self, w, h))
+                }
+            }
         }
+        timer.end(Phase::Count);
         // apply changes
         for &(w, h, change) in changes.iter() {
-            self.set_alive(w, h, change);
+            self.set_cell(w, h, change);
         }
         // track the number of generations advanced
         self.generation += 1;
+        self.dirty = false;
+        self.revision = next_revision();
+        timer.end(Phase::Apply);
         // notify callback
+        let mut summary = StepSummary::default();
         for &(w, h, change) in changes.iter() {
+            if change {
+                summary.births += 1;
+            } else {
+                summary.deaths += 1;
+            }
             cb(w, h, change);
         }
+        timer.end(Phase::Report);
+        summary
+    }
+}
+
+/// Derives the random number stream generating the row `row` of a
+/// world seeded with `seed` (see `rng`.)  The stream is seeded with the
+/// seed mixed with the row index through splitmix64.
+fn row_rng(seed: u64, row: usize) -> rng::Rng {
+    rng::Rng::seeded(seed ^ splitmix64(row as u64))
+}
+
+// ~ generates the rows `from..to` of a world seeded with `seed`, each
+// cell being alive with the probability `density`
+fn random_rows(seed: u64, width: usize, from: usize, to: usize, density: f64) -> Vec<bool> {
+    let alive = chance(density);
+    let mut v = Vec::with_capacity(width * (to - from));
+    for row in from..to {
+        let mut r = row_rng(seed, row);
+        for _ in 0..width {
+            v.push(r.meets(alive));
+        }
+    }
+    v
+}
+
+#[test]
+fn test_random_seeded_independent_of_threads() {
+    let w = World::random_seeded_with_threads(42, 97, 61, 1);
+    for &threads in &[2, 3, 8, 100] {
+        let v = World::random_seeded_with_threads(42, 97, 61, threads);
+        assert_eq!(w.cells, v.cells);
+        assert_eq!(w.alive, v.alive);
     }
+    assert!(w.cells != World::random_seeded_with_threads(43, 97, 61, 1).cells);
+}
+
+#[test]
+fn test_random_clustered() {
+    // ~ the share of the live cells whose right neighbour is alive,
+    // too; about the density for independent cells
+    fn neighbours_alive(w: &World) -> f64 {
+        let mut n = 0;
+        for y in 0..w.height() {
+            for x in 0..w.width() {
+                if w.is_alive(x, y) && w.is_alive((x + 1) % w.width(), y) {
+                    n += 1;
+                }
+            }
+        }
+        n as f64 / w.alive() as f64
+    }
+    let uniform = World::random_seeded_with(5, 200, 200, RandomKind::Uniform);
+    let clustered = World::random_seeded_with(5, 200, 200, RandomKind::Clustered { cluster_size: 6 });
+    for w in &[&uniform, &clustered] {
+        let density = w.alive() as f64 / (200 * 200) as f64;
+        assert!(density > 0.2 && density < 0.4, "{}", density);
+    }
+    assert!((neighbours_alive(&uniform) - RANDOM_DENSITY).abs() < 0.03);
+    assert!(neighbours_alive(&clustered) > 0.5, "{}", neighbours_alive(&clustered));
+    // ~ reproducible
+    assert_eq!(clustered.cells,
+               World::random_seeded_with(5, 200, 200, RandomKind::Clustered { cluster_size: 6 }).cells);
+    let huge = RandomKind::Clustered { cluster_size: usize::max_value() };
+    assert!(World::random_from(&mut row_rng(1, 0), 20, 20, huge).is_empty());
+    assert!(World::random_from(&mut row_rng(1, 0), 0, 0, RandomKind::Clustered { cluster_size: 3 })
+                .is_empty());
+}
+
+#[test]
+fn test_random_seeded_row_streams() {
+    let w = World::random_seeded_with_threads(7, 10, 5, 3);
+    for h in 0..5 {
+        let mut r = row_rng(7, h);
+        for x in 0..10 {
+            assert_eq!(r.meets(chance(RANDOM_DENSITY)), w.is_alive(x, h));
+        }
+    }
+}
+
+#[test]
+fn test_random_seeded_pinned() {
+    // ~ seeded worlds must never change (see `rng`); the populations
+    // are confirmed by an independent implementation of the scheme
+    let pinned = [(42, 80, 24, RANDOM_DENSITY, 0x3526af685ef18ae4, 575),
+                  (7, 97, 61, 0.5, 0xccb89e2e5e5cc802, 3027),
+                  (1, 1000, 3, 0.1, 0x20ede0f858a56cfc, 289),
+                  (0, 5, 5, 0.9, 0x91fb97f76888e1f4, 21)];
+    for &(seed, width, height, density, hash, alive) in &pinned {
+        let w = World::random_seeded_with_density(seed, width, height, density);
+        assert_eq!((hash, alive), (w.generation_hash(), w.alive()), "seed {}", seed);
+    }
+    let w = World::random_seeded_with(5, 64, 32, RandomKind::Clustered { cluster_size: 6 });
+    assert_eq!((0xa86b22370badd03c, 543), (w.generation_hash(), w.alive()));
+}
+
+#[test]
+fn test_random_seeded_density() {
+    assert_eq!(World::random_seeded(3, 30, 20).cells,
+               World::random_seeded_with_density(3, 30, 20, RANDOM_DENSITY).cells);
+    assert!(World::random_seeded_with_density(3, 30, 20, 0.0).is_empty());
+    assert_eq!(600, World::random_seeded_with_density(3, 30, 20, 1.0).alive());
+    let dense = World::random_seeded_with_density(3, 100, 100, 0.8).alive();
+    assert!(dense > 7500 && dense < 8500, "{}", dense);
+}
+
+#[test]
+fn test_generation_hash() {
+    let mut a = World::random_seeded(1, 20, 10);
+    let b = World::random_seeded(1, 20, 10);
+    assert_eq!(a.generation_hash(), b.generation_hash());
+
+    let alive = a.is_alive(3, 4);
+    a.set_alive(3, 4, !alive);
+    assert!(a.generation_hash() != b.generation_hash());
+
+    // ~ same cells, but different dimensions
+    assert!(World::empty(4, 2).generation_hash() != World::empty(2, 4).generation_hash());
 }
 
 fn wrapped(w: usize, offs: isize, wrap: usize) -> usize {
@@ -232,6 +1598,18 @@ fn wrapped(w: usize, offs: isize, wrap: usize) -> usize {
     }
 }
 
+// ~ returns the number of times `w + offs` crosses the edges of
+// `0..wrap`; negative if crossing the start, positive if crossing the
+// end, and 0 if `w + offs` falls into the range
+fn crossings(w: usize, offs: isize, wrap: usize) -> isize {
+    let (n, wrap) = (w as isize + offs, wrap as isize);
+    if n < 0 {
+        (n + 1) / wrap - 1
+    } else {
+        n / wrap
+    }
+}
+
 #[test]
 fn test_wrapped() {
     assert_eq!(wrapped(1, 1, 5), 2);
@@ -244,6 +1622,1046 @@ fn test_wrapped() {
     assert_eq!(wrapped(2, -11, 5), 1);
 }
 
+#[test]
+fn test_crossings() {
+    assert_eq!(crossings(1, 1, 5), 0);
+    assert_eq!(crossings(4, 1, 5), 1);
+    assert_eq!(crossings(0, -1, 5), -1);
+    assert_eq!(crossings(0, -5, 5), -1);
+    assert_eq!(crossings(0, -6, 5), -2);
+    assert_eq!(crossings(3, 12, 5), 3);
+}
+
+#[test]
+fn test_neighbour_counts() {
+    let mut w = World::empty(5, 5);
+    for x in 1..4 {
+        w.set_alive(x, 2, true);
+    }
+    assert_eq!(vec![0, 0, 0, 0, 0,
+                    1, 2, 3, 2, 1,
+                    1, 1, 2, 1, 1,
+                    1, 2, 3, 2, 1,
+                    0, 0, 0, 0, 0],
+               w.neighbour_counts());
+    assert_eq!(2, w.neighbour_count(2, 2));
+}
+
+#[test]
+fn test_neighbour_histogram() {
+    // ~ a blinker: ten cells without neighbours in the rows out of its
+    // reach, and its center with two
+    let mut w = World::empty(5, 5);
+    for x in 1..4 {
+        w.set_alive(x, 2, true);
+    }
+    assert_eq!([10, 8, 5, 2, 0, 0, 0, 0, 0], w.neighbour_histogram());
+    // ~ a block filling a bounded world but for the edges
+    let mut w = World::empty(4, 4);
+    w.set_boundary(BoundaryMode::Bounded);
+    for &(x, y) in &[(1, 1), (2, 1), (1, 2), (2, 2)] {
+        w.set_alive(x, y, true);
+    }
+    assert_eq!([0, 4, 8, 4, 0, 0, 0, 0, 0], w.neighbour_histogram());
+    // ~ every cell counted once
+    let w = World::random_seeded(11, 30, 20);
+    assert_eq!(600, w.neighbour_histogram().iter().sum::<usize>());
+    let counts = w.neighbour_counts();
+    assert_eq!(counts.iter().filter(|&&n| n == 3).count(), w.neighbour_histogram()[3]);
+}
+
+#[test]
+fn test_twisted_glider() {
+    // ~ places a south-east bound glider with its top left corner at (x, y)
+    fn glider(x: usize, y: usize) -> World {
+        let mut w = World::empty(10, 10);
+        for &(gx, gy) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+            w.set_alive(x + gx, y + gy, true);
+        }
+        w
+    }
+    // ~ on a plain torus the glider returns to its origin after 40
+    // generations; crossing the bottom edge once lands it shifted by
+    // the twist
+    let mut w = glider(1, 1);
+    w.set_boundary(BoundaryMode::Twisted { dx: 3, dy: 0 });
+    for _ in 0..40 {
+        w.advance_generation(|_, _, _| {});
+    }
+    assert_eq!(glider(4, 1).cells, w.cells);
+
+    let mut w = glider(1, 1);
+    for _ in 0..40 {
+        w.advance_generation(|_, _, _| {});
+    }
+    assert_eq!(glider(1, 1).cells, w.cells);
+}
+
+#[test]
+fn test_is_empty() {
+    let mut w = World::empty(5, 5);
+    assert!(w.is_empty());
+    w.set_alive(2, 2, true);
+    assert!(!w.is_empty());
+    // ~ a lone cell dies of loneliness
+    w.advance_generation(|_, _, _| {});
+    assert!(w.is_empty());
+}
+
+#[test]
+fn test_auto_expand_glider() {
+    let cfg = AutoExpand {
+        margin: 2,
+        increment: 16,
+        max_size: None,
+    };
+    // ~ a south-east bound glider in a corner
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let mut w = World::empty(10, 10);
+    w.set_boundary(BoundaryMode::Bounded);
+    for &(x, y) in glider.iter() {
+        w.set_alive(x + 2, y + 2, true);
+    }
+    for _ in 0..500 {
+        w.advance_generation(|_, _, _| {});
+        assert!(!w.auto_expand(&cfg).capped);
+    }
+    // ~ the glider survived and travelled 125 cells; the world grew
+    // only to the right and bottom
+    assert_eq!(5, w.alive());
+    assert_eq!((0, 0), w.origin());
+    for &(x, y) in glider.iter() {
+        assert!(w.is_alive(x + 127, y + 127));
+    }
+    assert!(w.width() >= 132 && w.width() <= 132 + 16);
+    assert_eq!(w.width(), w.height());
+}
+
+#[test]
+fn test_auto_expand_capped() {
+    let cfg = AutoExpand {
+        margin: 2,
+        increment: 4,
+        max_size: Some((14, 12)),
+    };
+    // ~ a north-west bound glider
+    let mut w = World::empty(10, 10);
+    w.set_boundary(BoundaryMode::Bounded);
+    for &(x, y) in [(5, 5), (6, 5), (7, 5), (5, 6), (6, 7)].iter() {
+        w.set_alive(x, y, true);
+    }
+    let mut capped = false;
+    for _ in 0..100 {
+        w.advance_generation(|_, _, _| {});
+        capped |= w.auto_expand(&cfg).capped;
+    }
+    assert!(capped);
+    assert_eq!((14, 12), (w.width(), w.height()));
+    assert_eq!((-4, -2), w.origin());
+}
+
+#[test]
+fn test_knight_neighbourhood() {
+    let knight = vec![(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+    let mut w = World::empty(6, 6);
+    w.set_neighbourhood(knight);
+    w.set_alive(2, 2, true);
+    w.set_alive(3, 3, true);
+    // ~ (4, 1) and (1, 4) are a knight's move away from both cells
+    assert_eq!(vec![0, 1, 0, 1, 0, 0,
+                    1, 0, 1, 0, 2, 0,
+                    0, 1, 0, 0, 0, 1,
+                    1, 0, 0, 0, 1, 0,
+                    0, 2, 0, 1, 0, 1,
+                    0, 0, 1, 0, 1, 0],
+               w.neighbour_counts());
+
+    // ~ both cells die of loneliness; no cell has three live neighbours
+    w.advance_generation(|_, _, _| {});
+    assert!(w.is_empty());
+}
+
+#[test]
+fn test_counts_above_eight() {
+    // ~ every count up to eight lets a cell be born and survive
+    let mut w = World::empty(9, 9);
+    w.set_boundary(BoundaryMode::Bounded);
+    w.set_neighbourhood((-2..3)
+                            .flat_map(|dy| (-2..3).map(move |dx| (dx, dy)))
+                            .filter(|&d| d != (0, 0))
+                            .collect());
+    w.set_rule(Rule::parse("B012345678/S012345678").unwrap());
+    for y in 0..9 {
+        for x in 0..9 {
+            if (x, y) != (4, 4) {
+                w.set_alive(x, y, true);
+            }
+        }
+    }
+    assert_eq!((8, 23, 24), (w.neighbour_count(0, 0), w.neighbour_count(4, 3), w.neighbour_count(4, 4)));
+    w.advance_generation(|_, _, _| {});
+    // ~ the corners survive with eight; the cells with more die, and
+    // the center is not born of 24
+    assert!(w.is_alive(0, 0) && w.is_alive(8, 8));
+    assert!(!w.is_alive(4, 3) && !w.is_alive(4, 4));
+    assert_eq!(4, w.alive());
+}
+
+#[test]
+fn test_large_neighbourhood_update_modes() {
+    // ~ the 24 cells of the 5x5 square around a cell
+    let square: Vec<(isize, isize)> = (-2..3)
+                                          .flat_map(|dy| (-2..3).map(move |dx| (dx, dy)))
+                                          .filter(|&d| d != (0, 0))
+                                          .collect();
+    // ~ no count lets a cell survive or be born: a full world dies out
+    // at once whether swept synchronously or row by row, although its
+    // cells have 24 live neighbours
+    for &mode in [UpdateMode::Synchronous, UpdateMode::AsyncRowMajor].iter() {
+        let mut w = World::empty(12, 9);
+        w.set_neighbourhood(square.clone());
+        w.set_rule(Rule::parse("B/S").unwrap());
+        w.set_update_mode(mode);
+        for y in 0..9 {
+            for x in 0..12 {
+                w.set_alive(x, y, true);
+            }
+        }
+        let mut died = 0;
+        w.advance_generation(|_, _, alive| died += !alive as usize);
+        assert_eq!((108, 0), (died, w.alive()), "{:?}", mode);
+    }
+    // ~ synchronously, every cell becomes what `Rule::next` makes of its
+    // count, be it above eight or not
+    let mut w = World::random_seeded(5, 20, 15);
+    w.set_neighbourhood(square);
+    w.set_rule(Rule::parse("B3678/S2345678").unwrap());
+    for _ in 0..3 {
+        let before = w.clone();
+        w.advance_generation(|_, _, _| {});
+        for y in 0..w.height() {
+            for x in 0..w.width() {
+                let n = before.neighbour_count(x, y);
+                assert_eq!(before.rule().next(before.is_alive(x, y), n),
+                           w.is_alive(x, y),
+                           "{},{}: {}",
+                           x,
+                           y,
+                           n);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_count_in_rect() {
+    let mut w = World::empty(6, 4);
+    for &(x, y) in [(0, 0), (2, 1), (3, 1), (5, 3)].iter() {
+        w.set_alive(x, y, true);
+    }
+    assert_eq!(4, w.count_in_rect(0, 0, 6, 4));
+    assert_eq!(2, w.count_in_rect(2, 1, 2, 1));
+    assert_eq!(1, w.count_in_rect(3, 0, 2, 3));
+    assert_eq!(1, w.count_in_rect(4, 2, 10, 10));
+    assert_eq!(0, w.count_in_rect(7, 5, 2, 2));
+    assert_eq!(0, w.count_in_rect(1, 1, 0, 3));
+    let huge = usize::max_value();
+    assert_eq!(2, w.count_in_rect(3, 1, huge, huge));
+    assert_eq!(0, w.count_in_rect(huge, huge, huge, huge));
+}
+
+#[test]
+fn test_checked_access() {
+    let mut w = World::empty(4, 3);
+    assert_eq!(Ok(()), w.try_set_alive(3, 2, true));
+    assert_eq!((Some(true), Some(false)), (w.get(3, 2), w.get(0, 0)));
+    assert_eq!((None, None, None), (w.get(4, 0), w.get(0, 3), w.get(usize::max_value(), 1)));
+    // ~ repeated edits keep the population right
+    assert_eq!(Ok(()), w.try_set_alive(3, 2, true));
+    assert_eq!(Ok(()), w.try_set_alive(0, 0, false));
+    assert_eq!(1, w.alive());
+    let e = w.try_set_alive(4, 1, true).unwrap_err();
+    assert_eq!(OutOfBounds {
+                   x: 4,
+                   y: 1,
+                   width: 4,
+                   height: 3,
+               },
+               e);
+    assert_eq!("cell 4,1 is outside of the 4x3 world", e.to_string());
+    assert!(w.try_set_alive(1, usize::max_value(), false).is_err());
+    assert_eq!((1, vec![(3, 2)]), (w.alive(), w.live_cells()));
+}
+
+#[test]
+#[should_panic(expected = "cell 0,3 is outside of the 4x3 world")]
+fn test_set_alive_out_of_bounds() {
+    // ~ within the cells' storage, yet not a cell of the world
+    World::empty(4, 3).set_alive(0, 3, true);
+}
+
+#[test]
+#[should_panic(expected = "cell 4,0 is outside of the 4x3 world")]
+fn test_is_alive_out_of_bounds() {
+    World::empty(4, 3).is_alive(4, 0);
+}
+
+#[test]
+fn test_from_coords() {
+    let w = World::from_coords(4, 3, vec![(2, 2), (3, 0), (0, 1), (3, 0)]).unwrap();
+    assert_eq!((4, 3), (w.width(), w.height()));
+    assert_eq!((3, vec![(3, 0), (0, 1), (2, 2)]), (w.alive(), w.live_cells()));
+    assert!(!w.is_dirty());
+    assert_eq!(0, World::from_coords(4, 3, None).unwrap().alive());
+    // ~ the same cells as edited one by one
+    let mut edited = World::empty(4, 3);
+    for &(x, y) in &[(2, 2), (3, 0), (0, 1)] {
+        edited.set_alive(x, y, true);
+    }
+    assert_eq!(edited.live_cells(), w.live_cells());
+
+    let e = World::from_coords(4, 3, vec![(1, 1), (4, 0), (0, 9)]).unwrap_err();
+    assert_eq!(OutOfBounds {
+                   x: 4,
+                   y: 0,
+                   width: 4,
+                   height: 3,
+               },
+               e);
+    assert!(World::from_coords(4, 3, Some((0, 3))).is_err());
+    assert!(World::from_coords(0, 0, Some((0, 0))).is_err());
+}
+
+#[test]
+fn test_blit() {
+    let mut w = World::empty(5, 4);
+    w.set_alive(3, 2, true);
+    let mut p = World::empty(3, 2);
+    for &(x, y) in &[(0, 0), (1, 1), (2, 1)] {
+        p.set_alive(x, y, true);
+    }
+    w.set_boundary(BoundaryMode::Bounded);
+    // ~ an overlap keeps the live cell; the rightmost cell is dropped
+    let report = |placed, clipped, overwrote| {
+        BlitReport {
+            placed: placed,
+            clipped: clipped,
+            overwrote: overwrote,
+        }
+    };
+    assert_eq!(report(3, 0, 1), w.blit(1, 1, &p));
+    assert_eq!(vec![(1, 1), (2, 2), (3, 2)], w.live_cells());
+    assert_eq!(report(2, 1, 0), w.blit(3, 0, &p));
+    assert_eq!((5, true), (w.alive(), w.is_dirty()));
+    assert_eq!(report(0, 3, 0), w.blit(-3, 0, &p));
+
+    // ~ a glider across the corner of a torus shows in all four
+    // corners, wherever the placement is given modulo the dimensions
+    let mut glider = ::parser::parse_cells(".O.\n..O\nOOO").unwrap();
+    glider.trim(0);
+    let corners = vec![(0, 0), (4, 0), (5, 0), (5, 4), (0, 5)];
+    for &(x, y) in &[(4, 4), (10, -2), (-8, 16)] {
+        let mut torus = World::empty(6, 6);
+        assert_eq!(report(5, 0, 0), torus.blit(x, y, &glider), "{},{}", x, y);
+        assert_eq!(corners, torus.live_cells(), "{},{}", x, y);
+        // ~ placed once more, it overwrites itself
+        assert_eq!(report(5, 0, 5), torus.blit(x, y, &glider));
+        assert_eq!(5, torus.alive());
+    }
+    // ~ on a bounded world just the cell within stays
+    let mut bounded = World::empty(6, 6);
+    bounded.set_boundary(BoundaryMode::Bounded);
+    assert_eq!(report(1, 4, 0), bounded.blit(4, 4, &glider));
+    assert_eq!(vec![(5, 4)], bounded.live_cells());
+    assert_eq!(1, bounded.alive());
+    // ~ nowhere to place anything
+    assert_eq!(report(0, 5, 0), World::empty(0, 0).blit(0, 0, &glider));
+}
+
+#[test]
+fn test_extract_replace_region() {
+    let mut w = World::from_cells(5, 4, "O...O.OO.......O...O".chars().map(|c| c == 'O').collect());
+    w.set_rule(Rule::parse("B36/S23").unwrap());
+    w.set_origin((-2, 3));
+    // ~ the corners, taken and pasted back unchanged
+    for &(x, y) in &[(0, 0), (3, 0), (0, 2), (3, 2)] {
+        let region = w.extract(x, y, 2, 2, BoundaryMode::Bounded).unwrap();
+        assert_eq!((2, 2, 0), (region.width(), region.height(), region.generation()));
+        assert_eq!((w.rule(), BoundaryMode::Bounded), (region.rule(), region.boundary()));
+        assert_eq!((x as isize - 2, y as isize + 3), region.origin());
+        assert_eq!(w.count_in_rect(x, y, 2, 2), region.alive());
+        let mut copy = w.clone();
+        assert_eq!(Ok(0), copy.replace_region(x, y, &region));
+        assert_eq!(w.as_ascii('O', '.'), copy.as_ascii('O', '.'));
+        assert!(!copy.is_dirty());
+    }
+    let region = w.extract(0, 0, 5, 4, BoundaryMode::Wrap).unwrap();
+    assert_eq!(w.as_ascii('O', '.'), region.as_ascii('O', '.'));
+    assert_eq!("..\n", w.extract(2, 2, 2, 1, BoundaryMode::Wrap).unwrap().as_ascii('O', '.'));
+
+    // ~ dead cells of the region replace live ones; the count stays
+    // exact
+    let mut region = w.extract(3, 2, 2, 2, BoundaryMode::Bounded).unwrap();
+    region.set_alive(4 - 3, 3 - 2, false);
+    region.set_alive(0, 0, true);
+    assert_eq!(Ok(2), w.replace_region(3, 2, &region));
+    assert_eq!("O...O\n.OO..\n...O.\nO....\n", w.as_ascii('O', '.'));
+    assert_eq!((6, true), (w.alive(), w.is_dirty()));
+
+    // ~ regions beyond the edges; nothing changes
+    let oob = |x, y| {
+        OutOfBounds {
+            x: x,
+            y: y,
+            width: 5,
+            height: 4,
+        }
+    };
+    assert_eq!(Some(oob(5, 1)), w.extract(4, 0, 2, 2, BoundaryMode::Wrap).err());
+    assert_eq!(Some(oob(1, 4)), w.extract(0, 3, 2, 2, BoundaryMode::Wrap).err());
+    assert_eq!(Err(oob(4, 4)), w.replace_region(3, 3, &region));
+    assert_eq!(Err(oob(5, 3)), w.replace_region(4, 2, &World::empty(2, 2)));
+    // ~ as well as regions reaching beyond `usize`
+    let huge = usize::max_value();
+    assert_eq!(Some(oob(huge - 1, 1)), w.extract(huge, 0, 2, 2, BoundaryMode::Bounded).err());
+    assert_eq!(Err(oob(1, huge - 1)), w.replace_region(0, huge - 1, &World::empty(2, 2)));
+    assert_eq!(6, w.alive());
+}
+
+#[test]
+fn test_trim() {
+    let mut glider = World::empty(3, 3);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        glider.set_alive(x, y, true);
+    }
+    // ~ a glider in the middle of a large world
+    let mut w = World::empty(40, 30);
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        w.set_alive(x + 20, y + 14, true);
+    }
+    assert_eq!(Some((20, 14, 3, 3)), w.live_bounds());
+    let checksum = w.checksum();
+    let mut trimmed = w.clone();
+    trimmed.trim(0);
+    assert_eq!((3, 3, 5), (trimmed.width(), trimmed.height(), trimmed.alive()));
+    assert!(trimmed.matches_at(&glider, 0, 0));
+    assert_eq!((20, 14), trimmed.origin());
+    assert_eq!(checksum, trimmed.checksum());
+    // ~ with a margin
+    w.trim(2);
+    assert_eq!((7, 7), (w.width(), w.height()));
+    assert!(w.matches_at(&glider, 2, 2));
+    assert_eq!(Some((2, 2, 3, 3)), w.live_bounds());
+    assert_eq!((18, 12), w.origin());
+    assert_eq!(checksum, w.checksum());
+    // ~ a margin beyond the former edges
+    let mut w = World::empty(4, 4);
+    w.set_alive(0, 3, true);
+    w.trim(1);
+    assert_eq!((3, 3, (-1, 2)), (w.width(), w.height(), w.origin()));
+    assert!(w.is_alive(1, 1));
+
+    let mut empty = World::empty(30, 20);
+    assert_eq!(None, empty.live_bounds());
+    empty.trim(0);
+    assert_eq!((1, 1, 0), (empty.width(), empty.height(), empty.alive()));
+    empty.trim(3);
+    assert_eq!((6, 6), (empty.width(), empty.height()));
+}
+
+#[test]
+fn test_matches_at() {
+    let mut block = World::empty(2, 2);
+    for &(x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+        block.set_alive(x, y, true);
+    }
+    let mut w = World::empty(8, 6);
+    for &(x, y) in [(4, 2), (5, 2), (4, 3), (5, 3), (0, 5)].iter() {
+        w.set_alive(x, y, true);
+    }
+    assert!(w.matches_at(&block, 4, 2));
+    assert!(!w.matches_at(&block, 3, 2));
+    assert!(!w.matches_at(&block, 5, 3));
+    // ~ dead cells of the pattern must be dead in the world, too
+    let mut padded = World::empty(4, 4);
+    for &(x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)].iter() {
+        padded.set_alive(x, y, true);
+    }
+    assert!(w.matches_at(&padded, 3, 1));
+    w.set_alive(6, 4, true);
+    assert!(!w.matches_at(&padded, 3, 1));
+    assert!(w.matches_at(&block, 4, 2));
+    // ~ beyond the edges
+    assert!(!w.matches_at(&block, 7, 0));
+    assert!(!w.matches_at(&World::empty(1, 1), 0, 6));
+    assert!(w.matches_at(&World::empty(1, 1), 7, 5));
+    let huge = usize::max_value();
+    assert!(!w.matches_at(&block, huge, 0) && !w.matches_at(&block, 0, huge));
+}
+
+#[test]
+fn test_shift() {
+    fn world(cells: &[(usize, usize)]) -> World {
+        let mut w = World::empty(5, 4);
+        for &(x, y) in cells {
+            w.set_alive(x, y, true);
+        }
+        w
+    }
+    let cells = [(0, 0), (1, 0), (4, 1), (2, 3)];
+
+    let mut w = world(&cells);
+    w.shift(2, 1);
+    assert_eq!(world(&[(2, 1), (3, 1), (1, 2), (4, 0)]).cells, w.cells);
+    assert_eq!(4, w.alive());
+    w.shift(-2, -1);
+    assert_eq!(world(&cells).cells, w.cells);
+    w.shift(-11, 9);
+    assert_eq!(world(&[(4, 1), (0, 1), (3, 2), (1, 0)]).cells, w.cells);
+
+    let mut w = world(&cells);
+    w.set_boundary(BoundaryMode::Bounded);
+    w.shift(2, 1);
+    assert_eq!(world(&[(2, 1), (3, 1)]).cells, w.cells);
+    assert_eq!(2, w.alive());
+
+    let mut w = world(&cells);
+    w.set_boundary(BoundaryMode::Bounded);
+    w.shift(-1, -1);
+    assert_eq!(world(&[(3, 0), (1, 2)]).cells, w.cells);
+    assert_eq!(2, w.alive());
+    w.shift(0, -7);
+    assert_eq!(0, w.alive());
+}
+
+#[test]
+fn test_shift_cells() {
+    let mut w = World::empty(6, 5);
+    let block = [(4, 3), (5, 3), (4, 4), (5, 4)];
+    for &(x, y) in block.iter().chain(&[(0, 0), (1, 0)]) {
+        w.set_alive(x, y, true);
+    }
+    // ~ across the edges of the torus; the other cells stay
+    let moved = w.shift_cells(&block, 1, 1);
+    assert_eq!(vec![(0, 0), (1, 0), (5, 0), (0, 4), (5, 4)], w.live_cells());
+    // ~ merged with the cell landed on, which is no longer one of those
+    // moved
+    assert_eq!((vec![(5, 4), (0, 4), (5, 0)], 0), moved);
+    assert_eq!(5, w.alive());
+
+    let mut w = World::empty(6, 5);
+    w.set_boundary(BoundaryMode::Bounded);
+    for &(x, y) in &block {
+        w.set_alive(x, y, true);
+    }
+    let (moved, dropped) = w.shift_cells(&[(4, 3), (5, 3), (4, 4), (5, 4), (0, 0)], 1, 0);
+    assert_eq!((vec![(5, 3), (5, 4)], 2), (moved.clone(), dropped));
+    assert_eq!((2, moved), (w.alive(), w.live_cells()));
+}
+
+#[test]
+fn test_bounded_neighbour_counts() {
+    let mut w = World::empty(3, 3);
+    w.set_boundary(BoundaryMode::Bounded);
+    for x in 0..3 {
+        w.set_alive(x, 0, true);
+    }
+    assert_eq!(vec![1, 2, 1,
+                    2, 3, 2,
+                    0, 0, 0],
+               w.neighbour_counts());
+    // ~ the blinker's ends fall off the edges
+    w.advance_generation(|_, _, _| {});
+    assert_eq!(2, w.alive());
+    assert!(w.is_alive(1, 0) && w.is_alive(1, 1));
+}
+
+// ~ advances the world by one generation and checks the changes
+// reported to the callback against the contract of
+// `advance_generation`
+#[cfg(test)]
+fn assert_step_contract(w: &mut World) {
+    let before = w.clone();
+    let mut reported = Vec::new();
+    let summary = w.advance_generation(|x, y, alive| reported.push((x, y, alive)));
+    assert_eq!(before.generation() + 1, w.generation());
+
+    // ~ strictly ordered by (y, x), hence no cell reported twice
+    assert!(reported.windows(2).all(|p| (p[0].1, p[0].0) < (p[1].1, p[1].0)),
+            "unordered or duplicate reports: {:?}",
+            reported);
+    // ~ exactly the changed cells in their new state
+    let mut changed = Vec::new();
+    for y in 0..w.height() {
+        for x in 0..w.width() {
+            if before.is_alive(x, y) != w.is_alive(x, y) {
+                changed.push((x, y, w.is_alive(x, y)));
+            }
+        }
+    }
+    assert_eq!(changed, reported);
+    assert_eq!(reported.iter().filter(|c| c.2).count(), summary.births);
+    assert_eq!(reported.iter().filter(|c| !c.2).count(), summary.deaths);
+}
+
+#[test]
+fn test_advance_generation_contract() {
+    let mut worlds = vec![World::random_seeded(1, 30, 20),
+                          World::random_seeded(2, 1, 12),
+                          World::random_seeded(3, 12, 1),
+                          World::random_seeded(4, 2, 2)];
+    let mut w = World::random_seeded(5, 25, 25);
+    w.set_boundary(BoundaryMode::Bounded);
+    worlds.push(w);
+    let mut w = World::random_seeded(6, 25, 25);
+    w.set_boundary(BoundaryMode::Twisted { dx: 3, dy: -1 });
+    worlds.push(w);
+    let mut w = World::random_seeded(7, 25, 25);
+    w.set_neighbourhood(vec![(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1),
+                             (-1, 2)]);
+    worlds.push(w);
+    for mut w in worlds {
+        for _ in 0..10 {
+            assert_step_contract(&mut w);
+        }
+    }
+}
+
+#[test]
+fn test_advance_generation_single_row() {
+    // ~ the upper and the lower row coincide; every changed cell is
+    // still reported once
+    let mut w = World::empty(6, 1);
+    w.set_boundary(BoundaryMode::Bounded);
+    w.set_alive(2, 0, true);
+    let mut reported = Vec::new();
+    w.advance_generation(|x, y, alive| reported.push((x, y, alive)));
+    assert_eq!(vec![(2, 0, false)], reported);
+}
+
+#[test]
+fn test_advance_generations() {
+    use parser;
+
+    // ~ a pulsar (period 3) far ahead is one of its first three phases
+    let mut w = parser::parse_cells("...............
+...............
+...OOO...OOO...
+...............
+.O....O.O....O.
+.O....O.O....O.
+.O....O.O....O.
+...OOO...OOO...
+...............
+...OOO...OOO...
+.O....O.O....O.
+.O....O.O....O.
+.O....O.O....O.
+...............
+...OOO...OOO...
+...............
+...............")
+                     .unwrap();
+    let mut phases = vec![w.live_cells()];
+    for _ in 0..2 {
+        w.advance_generation(|_, _, _| {});
+        phases.push(w.live_cells());
+    }
+    assert!(phases[0] != phases[1] && phases[1] != phases[2] && phases[0] != phases[2]);
+    w.advance_generations(998);
+    assert_eq!((1000, &phases[1]), (w.generation(), &w.live_cells()));
+    w.advance_generations(0);
+    assert_eq!(1000, w.generation());
+    w.advance_generations(1001);
+    assert_eq!((2001, &phases[0]), (w.generation(), &w.live_cells()));
+}
+
+#[test]
+fn test_advance_generation_reference() {
+    // ~ the rules applied cell by cell to a copy of the previous
+    // generation, without any shortcuts
+    fn reference_step(w: &World) -> World {
+        let mut next = w.clone();
+        for y in 0..w.height() {
+            for x in 0..w.width() {
+                let alive = match (w.is_alive(x, y), w.neighbour_count(x, y)) {
+                    (true, 2) | (true, 3) | (false, 3) => true,
+                    _ => false,
+                };
+                if alive != w.is_alive(x, y) {
+                    next.set_alive(x, y, alive);
+                }
+            }
+        }
+        next
+    }
+    for &(width, height) in &[(1, 1), (1, 5), (5, 1), (2, 2), (3, 3), (4, 7), (17, 3), (64, 64), (131, 45)] {
+        for seed in 0..4 {
+            for &boundary in &[BoundaryMode::Wrap, BoundaryMode::Bounded] {
+                let mut w = World::random_seeded(seed, width, height);
+                w.set_boundary(boundary);
+                for g in 0..6 {
+                    let expected = reference_step(&w);
+                    w.advance_generation(|_, _, _| {});
+                    assert!(expected.cells == w.cells, "{}x{} seed {} gen {}", width, height, seed, g);
+                    assert_eq!(expected.alive(), w.alive());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rule() {
+    assert_eq!("B3/S23", CONWAY.to_string());
+    assert_eq!(Some(CONWAY), Rule::parse("b3/s23"));
+    let highlife = Rule::parse("B36/S23").unwrap();
+    assert_eq!("B36/S23", highlife.to_string());
+    assert_eq!("B2/S", Rule::parse("B2/S").unwrap().to_string());
+    for s in &["B3", "B3/S23/X", "S23/B3", "B9/S23", "B3/S2a", ""] {
+        assert_eq!(None, Rule::parse(s), "{}", s);
+    }
+    assert!(highlife.next(false, 6) && !highlife.next(true, 6) && highlife.next(true, 2));
+
+    // ~ every path of advancing a generation follows the rule; the
+    // cells compared with those of the neighbour counts beforehand
+    for rule in &["B36/S23", "B2/S", "B0/S012345678", "B1357/S1357"] {
+        let rule = Rule::parse(rule).unwrap();
+        for &(width, height) in &[(9, 7), (40, 17)] {
+            let mut w = World::random_seeded(5, width, height);
+            w.set_rule(rule);
+            for g in 0..4 {
+                let counts = w.neighbour_counts();
+                let expected: Vec<u8> = w.cells
+                                         .iter()
+                                         .zip(counts.iter())
+                                         .map(|(&c, &n)| rule.next(c != 0, n as usize) as u8)
+                                         .collect();
+                w.advance_generation(|_, _, _| {});
+                assert!(expected == w.cells, "{} {}x{} gen {}", rule, width, height, g);
+            }
+        }
+    }
+    // ~ as do the asynchronous updates
+    let mut w = World::empty(5, 5);
+    w.set_rule(Rule::parse("B1/S").unwrap());
+    w.set_update_mode(UpdateMode::AsyncRowMajor);
+    w.set_alive(2, 2, true);
+    w.advance_generation(|_, _, _| {});
+    assert!(!w.is_alive(2, 2) && w.is_alive(1, 1));
+}
+
+#[test]
+fn test_diff() {
+    let mut a = World::empty(4, 3);
+    let mut b = World::empty(3, 5);
+    for &(x, y) in [(0, 0), (3, 1), (2, 2)].iter() {
+        a.set_alive(x, y, true);
+    }
+    for &(x, y) in [(0, 0), (1, 2), (2, 4)].iter() {
+        b.set_alive(x, y, true);
+    }
+    assert_eq!(vec![(3, 1, true), (1, 2, false), (2, 2, true), (2, 4, false)], a.diff(&b));
+    assert_eq!(vec![(3, 1, false), (1, 2, true), (2, 2, false), (2, 4, true)], b.diff(&a));
+    assert!(a.diff(&a.clone()).is_empty());
+}
+
+#[test]
+fn test_live_cells() {
+    let mut w = World::empty(4, 3);
+    assert!(w.live_cells().is_empty());
+    for &(x, y) in [(2, 2), (3, 0), (0, 1), (1, 1)].iter() {
+        w.set_alive(x, y, true);
+    }
+    assert_eq!(vec![(3, 0), (0, 1), (1, 1), (2, 2)], w.live_cells());
+}
+
+#[test]
+fn test_live_cells_in() {
+    let w = World::random_seeded(11, 30, 20);
+    let all = w.live_cells();
+    for &(x, y, width, height) in [(0, 0, 30, 20), (5, 3, 10, 7), (25, 15, 10, 10), (3, 4, 0, 5),
+                                   (40, 2, 5, 5), (2, 2, usize::max_value(), usize::max_value())]
+                                      .iter() {
+        let expected: Vec<_> = all.iter()
+                                  .cloned()
+                                  .filter(|&(cx, cy)| {
+                                      cx >= x && cy >= y && cx - x < width && cy - y < height
+                                  })
+                                  .collect();
+        let cells: Vec<_> = w.live_cells_in(x, y, width, height).collect();
+        assert_eq!(expected, cells, "{},{} {}x{}", x, y, width, height);
+        assert_eq!(w.count_in_rect(x, y, cmp::min(width, 100), cmp::min(height, 100)), cells.len());
+    }
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let mut w = World::random_seeded(9, 123, 45);
+    for _ in 0..17 {
+        w.advance_generation(|_, _, _| {});
+    }
+    let mut buf = Vec::new();
+    w.save_binary(&mut buf).unwrap();
+    // ~ header plus one bit per cell
+    assert_eq!(24 + (123 * 45 + 7) / 8, buf.len());
+    let v = World::load_binary(&buf[..]).unwrap();
+    assert_eq!((123, 45, 17), (v.width(), v.height(), v.generation()));
+    assert_eq!(w.alive(), v.alive());
+    assert_eq!(w.cells, v.cells);
+}
+
+#[test]
+fn test_binary_malformed() {
+    let mut buf = Vec::new();
+    World::random_seeded(10, 20, 20).save_binary(&mut buf).unwrap();
+
+    let e = World::load_binary(&buf[..buf.len() - 1]).err().unwrap();
+    assert_eq!(io::ErrorKind::UnexpectedEof, e.kind());
+    assert!(World::load_binary(&b"!glider\n.O\n..O\nOOO\n"[..]).is_err());
+    let mut v = buf.clone();
+    v[7] = 2;
+    assert!(World::load_binary(&v[..]).is_err());
+    // ~ oversized; rejected before allocating the cells
+    let mut v = buf.clone();
+    for b in &mut v[8..16] {
+        *b = 0xff;
+    }
+    assert_eq!(io::ErrorKind::InvalidData, World::load_binary(&v[..]).err().unwrap().kind());
+}
+
+#[test]
+fn test_update_mode_synchronous() {
+    // ~ the blinker oscillates as always
+    let mut w = World::empty(5, 5);
+    for x in 1..4 {
+        w.set_alive(x, 2, true);
+    }
+    let horizontal = w.cells.clone();
+    w.set_update_mode(UpdateMode::Synchronous);
+    w.advance_generation(|_, _, _| {});
+    assert!((1..4).all(|y| w.is_alive(2, y)));
+    assert_eq!(3, w.alive());
+    w.advance_generation(|_, _, _| {});
+    assert_eq!(horizontal, w.cells);
+}
+
+#[test]
+fn test_update_mode_async_row_major() {
+    let mut w = World::empty(5, 5);
+    for x in 1..4 {
+        w.set_alive(x, 2, true);
+    }
+    w.set_update_mode(UpdateMode::AsyncRowMajor);
+    // ~ swept row by row: (2,1) is born seeing the blinker's three
+    // cells; (3,1) then sees (2,1) besides (2,2) and (3,2) and is born,
+    // too, whereas (1,1) was visited too early to see (2,1).  in the
+    // blinker's row (1,2) keeps two neighbours, (2,2) now has four and
+    // dies, and (3,2) keeps two after (2,2) died; the row below sees
+    // at most two live cells
+    let mut reported = Vec::new();
+    let summary = w.advance_generation(|x, y, alive| reported.push((x, y, alive)));
+    assert_eq!(vec![(2, 1, true), (3, 1, true), (2, 2, false)], reported);
+    assert_eq!((2, 1), (summary.births, summary.deaths));
+    assert_eq!(4, w.alive());
+    assert_eq!((1, false), (w.generation(), w.is_dirty()));
+}
+
+#[test]
+fn test_update_mode_async_changes() {
+    // ~ the changes reported agree with a comparison of the cells
+    // before and after the step
+    for &mode in [UpdateMode::AsyncRowMajor,
+                  UpdateMode::AsyncRandomOrder { seed: 1 },
+                  UpdateMode::AsyncRandomOrder { seed: 2 },
+                  UpdateMode::AsyncRandomSubset { seed: 1, percent: 30 }]
+                     .iter() {
+        for seed in 0..5 {
+            let mut w = World::random_seeded(seed, 23, 17);
+            w.set_update_mode(mode);
+            for _ in 0..3 {
+                let before = w.clone();
+                let mut reported = Vec::new();
+                let summary = w.advance_generation(|x, y, alive| reported.push((x, y, alive)));
+                let mut expected = Vec::new();
+                for y in 0..w.height() {
+                    for x in 0..w.width() {
+                        if before.is_alive(x, y) != w.is_alive(x, y) {
+                            expected.push((x, y, w.is_alive(x, y)));
+                        }
+                    }
+                }
+                assert_eq!(expected, reported, "{:?} seed {}", mode, seed);
+                assert_eq!(summary.births + summary.deaths, reported.len());
+                assert_eq!(w.cells.iter().filter(|&&c| c != 0).count(), w.alive());
+            }
+        }
+    }
+    // ~ the random order depends on its seed, yet is reproducible
+    let step = |seed| {
+        let mut w = World::random_seeded(7, 23, 17);
+        w.set_update_mode(UpdateMode::AsyncRandomOrder { seed: seed });
+        steps(w, 2).cells
+    };
+    assert_eq!(step(1), step(1));
+    assert!(step(1) != step(2));
+}
+
+#[test]
+fn test_update_mode_async_random_subset() {
+    let run = |mode| {
+        let mut w = World::random_seeded(7, 23, 17);
+        w.set_update_mode(mode);
+        steps(w, 4).cells
+    };
+    // ~ all cells updated: just a random order
+    assert_eq!(run(UpdateMode::AsyncRandomOrder { seed: 3 }),
+               run(UpdateMode::AsyncRandomSubset { seed: 3, percent: 100 }));
+    // ~ no cell updated: nothing changes
+    let mut w = World::random_seeded(7, 23, 17);
+    let cells = w.cells.clone();
+    w.set_update_mode(UpdateMode::AsyncRandomSubset { seed: 3, percent: 0 });
+    w.advance_generation(|_, _, _| panic!("no change expected"));
+    assert_eq!((cells, 1), (w.cells.clone(), w.generation()));
+    // ~ a blinker updated in part no longer oscillates like in the
+    // synchronous game
+    let mut blinker = World::empty(7, 7);
+    for x in 2..5 {
+        blinker.set_alive(x, 3, true);
+    }
+    let sync = steps(blinker.clone(), 6).cells;
+    assert_eq!(blinker.cells, sync);
+    blinker.set_update_mode(UpdateMode::AsyncRandomSubset { seed: 3, percent: 50 });
+    assert!(steps(blinker, 6).cells != sync);
+    assert_eq!("async-random:50",
+               UpdateMode::AsyncRandomSubset { seed: 3, percent: 50 }.to_string());
+}
+
+#[test]
+fn test_revision() {
+    let mut w = World::empty(6, 5);
+    // ~ another world, although of the same cells
+    assert!(World::empty(6, 5).revision() != w.revision());
+    let r = w.revision();
+    assert_eq!(r, w.clone().revision());
+    // ~ settings leave the cells as they are
+    w.set_rule(Rule::parse("B36/S23").unwrap());
+    w.set_generation(3);
+    w.expand_to(6, 5);
+    assert_eq!(r, w.revision());
+    let mut seen = vec![r];
+    w.set_alive(1, 1, true);
+    seen.push(w.revision());
+    w.advance_generation(|_, _, _| {});
+    seen.push(w.revision());
+    w.set_update_mode(UpdateMode::AsyncRowMajor);
+    w.advance_generation(|_, _, _| {});
+    seen.push(w.revision());
+    w.expand_to(8, 8);
+    seen.push(w.revision());
+    w.shift(1, 0);
+    seen.push(w.revision());
+    w.trim(1);
+    seen.push(w.revision());
+    let mut unique = seen.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(seen.len(), unique.len());
+}
+
+#[test]
+fn test_checksum() {
+    // ~ deterministic for a seeded run
+    let run = |seed| {
+        let mut w = World::random_seeded(seed, 40, 30);
+        (0..50).map(|_| {
+                   w.advance_generation(|_, _, _| {});
+                   w.checksum()
+               })
+               .collect::<Vec<_>>()
+    };
+    let sums = run(17);
+    assert_eq!(sums, run(17));
+    assert!(sums != run(18));
+    assert!(sums.windows(2).all(|p| p[0] != p[1]));
+
+    // ~ a single cell flipped is noticed
+    let mut w = World::random_seeded(17, 40, 30);
+    let sum = w.checksum();
+    w.set_alive(3, 4, !w.is_alive(3, 4));
+    assert!(w.checksum() != sum);
+    assert_eq!(0, World::empty(5, 5).checksum());
+
+    // ~ growing keeps the logical coordinates, hence the checksum
+    let sum = w.checksum();
+    w.grow(3, 2, 1, 4);
+    assert_eq!(sum, w.checksum());
+}
+
+// ~ advances the given world by `n` generations
+#[cfg(test)]
+fn steps(mut w: World, n: usize) -> World {
+    for _ in 0..n {
+        w.advance_generation(|_, _, _| {});
+    }
+    w
+}
+
+#[test]
+fn test_generation_step() {
+    let w = World::random_seeded(13, 20, 10);
+    assert_eq!((0, false), (w.generation(), w.is_dirty()));
+    let w = steps(w, 5);
+    assert_eq!((5, false), (w.generation(), w.is_dirty()));
+    // ~ an empty world advances, too
+    let w = steps(World::empty(3, 3), 2);
+    assert_eq!(2, w.generation());
+}
+
+#[test]
+fn test_generation_layout() {
+    let mut w = steps(World::random_seeded(14, 20, 10), 3);
+    w.expand_to(30, 12);
+    assert_eq!((3, false), (w.generation(), w.is_dirty()));
+    w.expand_to(10, 5);
+    assert_eq!((3, false), (w.generation(), w.is_dirty()));
+    w.grow(1, 2, 3, 4);
+    assert_eq!((3, false), (w.generation(), w.is_dirty()));
+    w.shift(-2, 7);
+    assert_eq!((3, false), (w.generation(), w.is_dirty()));
+}
+
+#[test]
+fn test_generation_edit() {
+    let mut w = steps(World::random_seeded(15, 20, 10), 4);
+    let alive = w.is_alive(3, 3);
+    w.set_alive(3, 3, !alive);
+    assert_eq!((4, true), (w.generation(), w.is_dirty()));
+    // ~ layout changes keep the mark; the next step clears it
+    w.shift(1, 1);
+    assert!(w.is_dirty());
+    assert!(w.clone().is_dirty());
+    let w = steps(w, 1);
+    assert_eq!((5, false), (w.generation(), w.is_dirty()));
+}
+
+#[test]
+fn test_generation_replace() {
+    let mut w = steps(World::random_seeded(16, 20, 10), 6);
+    w.set_alive(0, 0, !w.is_alive(0, 0));
+    w.restart();
+    assert_eq!((0, false), (w.generation(), w.is_dirty()));
+
+    // ~ restoring a session keeps its counter
+    let mut w = steps(w, 2);
+    w.set_generation(1240);
+    assert_eq!((1240, false), (w.generation(), w.is_dirty()));
+    let mut buf = Vec::new();
+    w.save_binary(&mut buf).unwrap();
+    let v = World::load_binary(&buf[..]).unwrap();
+    assert_eq!((1240, false), (v.generation(), v.is_dirty()));
+}
+
 #[cfg(test)]
 mod benches {
     use super::World;
@@ -261,4 +2679,40 @@ mod benches {
             black_box(state);
         }));
     }
+
+    // ~ benchmarks advancing a seeded world of the given size
+    macro_rules! bench_advance {
+        ($name:ident, $width:expr, $height:expr) => {
+            #[bench]
+            fn $name(b: &mut Bencher) {
+                let mut w = World::random_seeded(42, $width, $height);
+                b.iter(|| w.advance_generation(|_, _, state| {
+                    black_box(state);
+                }));
+            }
+        }
+    }
+
+    bench_advance!(advance_generation_500x500, 500, 500);
+    bench_advance!(advance_generation_2000x2000, 2000, 2000);
+    bench_advance!(advance_generation_16384x64, 16384, 64);
+
+    // ~ the cost of counting (see `perf`) on top of advancing
+    #[bench]
+    fn advance_generation_500x500_counted(b: &mut Bencher) {
+        let mut w = World::random_seeded(42, 500, 500);
+        let mut perf = ::perf::PerfCounters::new();
+        b.iter(|| {
+            let summary = w.advance_generation(|_, _, state| {
+                black_box(state);
+            });
+            perf.add_step(&summary);
+        });
+        black_box(perf.generations);
+    }
+
+    #[bench]
+    fn random_seeded_world_4000x4000(b: &mut Bencher) {
+        b.iter(|| black_box(World::random_seeded(42, 4000, 4000)));
+    }
 }